@@ -0,0 +1,405 @@
+//! Common interface for protocol adapters
+//!
+//! Lets the hub drive HTTP, Modbus, OPC UA, WebRTC and WebSocket adapters uniformly,
+//! e.g. for health checks or a generic operation endpoint. Operations are expressed
+//! with [`AdapterOp`] in each protocol's native terms and answered with an
+//! [`AdapterResult`]. OPC UA addressing and values are only available with the `opcua`
+//! feature.
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use uaip_core::error::{Result, UaipError};
+
+#[cfg(feature = "opcua")]
+use crate::opcua::{DataValue, NodeId, OpcValue};
+
+/// Adapter shared between tasks
+pub type SharedAdapter = Arc<Mutex<dyn ProtocolAdapter>>;
+
+/// Modbus data table
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModbusTable {
+    Coil,
+    DiscreteInput,
+    HoldingRegister,
+    InputRegister,
+}
+
+/// What an operation addresses, in the adapter's native terms
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "protocol", rename_all = "snake_case")]
+pub enum Target {
+    /// HTTP resource path relative to the base URL
+    Http { path: String },
+    /// Range of Modbus coils or registers
+    Modbus {
+        table: ModbusTable,
+        address: u16,
+        #[serde(default = "default_count")]
+        count: u16,
+    },
+    /// OPC UA node
+    #[cfg(feature = "opcua")]
+    #[serde(rename = "opcua")]
+    OpcUa { node_id: NodeId },
+    /// WebRTC data channel
+    DataChannel { label: String },
+    /// The WebSocket connection itself
+    WebSocket,
+}
+
+fn default_count() -> u16 {
+    1
+}
+
+impl Target {
+    /// Protocol the target belongs to
+    pub fn protocol(&self) -> &'static str {
+        match self {
+            Self::Http { .. } => "http",
+            Self::Modbus { .. } => "modbus",
+            #[cfg(feature = "opcua")]
+            Self::OpcUa { .. } => "opcua",
+            Self::DataChannel { .. } => "webrtc",
+            Self::WebSocket => "websocket",
+        }
+    }
+}
+
+/// Value written to or read from a target
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum AdapterValue {
+    /// Arbitrary JSON document
+    Json(serde_json::Value),
+    /// Modbus holding or input registers
+    Registers(Vec<u16>),
+    /// Modbus coils or discrete inputs
+    Bits(Vec<bool>),
+    /// OPC UA variant
+    #[cfg(feature = "opcua")]
+    Opc(OpcValue),
+}
+
+impl AdapterValue {
+    /// Plain JSON form of the value, for adapters that send JSON
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            Self::Json(value) => value.clone(),
+            Self::Registers(values) => serde_json::json!(values),
+            Self::Bits(values) => serde_json::json!(values),
+            #[cfg(feature = "opcua")]
+            Self::Opc(value) => serde_json::to_value(value).unwrap_or_default(),
+        }
+    }
+}
+
+/// Protocol-independent adapter operation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum AdapterOp {
+    /// Read the target
+    Read { target: Target },
+    /// Write a value to the target
+    Write { target: Target, value: AdapterValue },
+    /// List the children of an OPC UA node
+    #[cfg(feature = "opcua")]
+    Browse { node_id: NodeId },
+    /// Call an OPC UA method
+    #[cfg(feature = "opcua")]
+    Call {
+        object_id: NodeId,
+        method_id: NodeId,
+        #[serde(default)]
+        args: Vec<OpcValue>,
+    },
+}
+
+impl AdapterOp {
+    /// Operation name, e.g. `read`
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Read { .. } => "read",
+            Self::Write { .. } => "write",
+            #[cfg(feature = "opcua")]
+            Self::Browse { .. } => "browse",
+            #[cfg(feature = "opcua")]
+            Self::Call { .. } => "call",
+        }
+    }
+}
+
+/// Outcome of an adapter operation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum AdapterResult {
+    /// Value read from the target
+    Value(AdapterValue),
+    /// OPC UA value with timestamps and status
+    #[cfg(feature = "opcua")]
+    DataValue(DataValue),
+    /// Child nodes found by a browse
+    #[cfg(feature = "opcua")]
+    Nodes(Vec<NodeId>),
+    /// Output arguments of a method call
+    #[cfg(feature = "opcua")]
+    Outputs(Vec<OpcValue>),
+    /// The write was accepted
+    Written,
+}
+
+/// Behaviour shared by all protocol adapters
+#[async_trait]
+pub trait ProtocolAdapter: Send + Sync {
+    /// Short adapter type name, e.g. `modbus`
+    fn adapter_type(&self) -> &'static str;
+
+    /// Check the connection to the device or server
+    async fn health_check(&mut self) -> Result<()>;
+
+    /// Run an operation
+    async fn execute(&mut self, op: AdapterOp) -> Result<AdapterResult>;
+}
+
+// The helpers below go unused in builds without the adapters that call them
+
+/// Error for an operation an adapter cannot perform
+#[allow(dead_code)]
+pub(crate) fn unsupported(adapter_type: &str, op: &AdapterOp) -> UaipError {
+    UaipError::CapabilityNotSupported(format!(
+        "{} adapter does not support {}",
+        adapter_type,
+        op.kind()
+    ))
+}
+
+/// Error for a target that belongs to another protocol
+#[allow(dead_code)]
+pub(crate) fn foreign_target(adapter_type: &str, target: &Target) -> UaipError {
+    UaipError::InvalidParameter(format!(
+        "{} adapter cannot address a {} target",
+        adapter_type,
+        target.protocol()
+    ))
+}
+
+/// Error for a value the target cannot hold
+#[allow(dead_code)]
+pub(crate) fn invalid_value(adapter_type: &str, value: &AdapterValue) -> UaipError {
+    UaipError::InvalidParameter(format!(
+        "Invalid value for {} adapter: {}",
+        adapter_type,
+        value.to_json()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    // Which tests are built depends on the enabled adapters
+    #![allow(unused_imports)]
+
+    use super::*;
+    #[cfg(feature = "http")]
+    use crate::http::{HttpAdapter, HttpConfig};
+    #[cfg(feature = "modbus")]
+    use crate::modbus::{ModbusAdapter, ModbusConfig};
+    #[cfg(feature = "opcua")]
+    use crate::opcua::{OpcUaAdapter, OpcUaConfig};
+    #[cfg(feature = "webrtc")]
+    use crate::webrtc::{WebRtcAdapter, WebRtcConfig};
+
+    #[cfg(all(
+        feature = "http",
+        feature = "modbus",
+        feature = "opcua",
+        feature = "webrtc"
+    ))]
+    #[tokio::test]
+    async fn test_uniform_health_check() {
+        // An address with nothing listening
+        let closed = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+
+        let mut adapters: Vec<Box<dyn ProtocolAdapter>> = vec![
+            Box::new(
+                HttpAdapter::new(HttpConfig {
+                    base_url: format!("http://{}", closed),
+                    max_retries: 0,
+                    ..HttpConfig::default()
+                })
+                .unwrap(),
+            ),
+            Box::new(
+                ModbusAdapter::new(ModbusConfig {
+                    server_address: closed.to_string(),
+                    ..ModbusConfig::default()
+                })
+                .unwrap(),
+            ),
+            Box::new(OpcUaAdapter::new(OpcUaConfig::default()).unwrap()),
+            Box::new(WebRtcAdapter::new(WebRtcConfig::default()).unwrap()),
+        ];
+
+        let mut results = Vec::new();
+        for adapter in adapters.iter_mut() {
+            results.push((adapter.adapter_type(), adapter.health_check().await.is_ok()));
+        }
+
+        assert_eq!(
+            results,
+            vec![
+                ("http", false),
+                ("modbus", false),
+                ("opcua", true),
+                ("webrtc", true),
+            ]
+        );
+    }
+
+    #[cfg(all(feature = "opcua", feature = "webrtc"))]
+    #[tokio::test]
+    async fn test_execute_through_trait() {
+        let mut adapter: Box<dyn ProtocolAdapter> =
+            Box::new(OpcUaAdapter::new(OpcUaConfig::default()).unwrap());
+        let temperature = NodeId::new(2, "Temperature");
+
+        let result = adapter
+            .execute(AdapterOp::Read {
+                target: Target::OpcUa {
+                    node_id: temperature.clone(),
+                },
+            })
+            .await
+            .unwrap();
+        assert!(matches!(
+            result,
+            AdapterResult::DataValue(DataValue {
+                value: OpcValue::Double(_),
+                ..
+            })
+        ));
+
+        let op: AdapterOp = serde_json::from_value(serde_json::json!({
+            "op": "write",
+            "target": {
+                "protocol": "opcua",
+                "node_id": { "namespace": 2, "identifier": "Temperature" },
+            },
+            "value": { "type": "json", "value": "hot" },
+        }))
+        .unwrap();
+        assert!(adapter.execute(op).await.is_err());
+
+        let result = adapter
+            .execute(AdapterOp::Browse {
+                node_id: temperature,
+            })
+            .await
+            .unwrap();
+        assert!(matches!(result, AdapterResult::Nodes(nodes) if nodes.len() == 2));
+
+        let err = adapter
+            .execute(AdapterOp::Read {
+                target: Target::DataChannel {
+                    label: "telemetry".to_string(),
+                },
+            })
+            .await
+            .unwrap_err();
+        assert!(matches!(err, UaipError::InvalidParameter(_)));
+
+        let mut webrtc: Box<dyn ProtocolAdapter> =
+            Box::new(WebRtcAdapter::new(WebRtcConfig::default()).unwrap());
+        let err = webrtc
+            .execute(AdapterOp::Read {
+                target: Target::DataChannel {
+                    label: "telemetry".to_string(),
+                },
+            })
+            .await
+            .unwrap_err();
+        assert!(matches!(err, UaipError::CapabilityNotSupported(_)));
+    }
+
+    /// Answer one Read Holding Registers request with `address + i` in each register
+    #[cfg(feature = "modbus")]
+    async fn serve_holding_registers(listener: tokio::net::TcpListener) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut request = [0u8; 12];
+        stream.read_exact(&mut request).await.unwrap();
+        assert_eq!(request[7], 0x03);
+
+        let address = u16::from_be_bytes([request[8], request[9]]);
+        let count = u16::from_be_bytes([request[10], request[11]]);
+        let mut response = request[..7].to_vec();
+        response[4..6].copy_from_slice(&(3 + 2 * count).to_be_bytes());
+        response.extend_from_slice(&[0x03, (2 * count) as u8]);
+        for i in 0..count {
+            response.extend_from_slice(&(address + i).to_be_bytes());
+        }
+        stream.write_all(&response).await.unwrap();
+    }
+
+    #[cfg(feature = "modbus")]
+    #[tokio::test]
+    async fn test_modbus_read_round_trip() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let server_address = listener.local_addr().unwrap().to_string();
+        let server = tokio::spawn(serve_holding_registers(listener));
+
+        // The op arrives as JSON, e.g. from a REST request
+        let op: AdapterOp = serde_json::from_value(serde_json::json!({
+            "op": "read",
+            "target": {
+                "protocol": "modbus",
+                "table": "holding_register",
+                "address": 40,
+                "count": 3,
+            },
+        }))
+        .unwrap();
+        assert_eq!(
+            op,
+            AdapterOp::Read {
+                target: Target::Modbus {
+                    table: ModbusTable::HoldingRegister,
+                    address: 40,
+                    count: 3,
+                },
+            }
+        );
+
+        let mut adapter: Box<dyn ProtocolAdapter> = Box::new(
+            ModbusAdapter::new(ModbusConfig {
+                server_address,
+                max_retries: 0,
+                ..ModbusConfig::default()
+            })
+            .unwrap(),
+        );
+        let result = adapter.execute(op).await.unwrap();
+        server.await.unwrap();
+
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "type": "value",
+                "value": { "type": "registers", "value": [40, 41, 42] },
+            })
+        );
+        let decoded: AdapterResult = serde_json::from_value(json).unwrap();
+        assert!(matches!(
+            decoded,
+            AdapterResult::Value(AdapterValue::Registers(values)) if values == vec![40, 41, 42]
+        ));
+    }
+}
@@ -3,15 +3,21 @@
 //! Provides HTTP client functionality for connecting devices that communicate via REST APIs.
 //! Supports common HTTP methods (GET, POST, PUT, DELETE) with request/response handling.
 
+use async_trait::async_trait;
 use reqwest::{Client, Method, RequestBuilder, Response};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::net::SocketAddr;
 use std::time::Duration;
 use tracing::{debug, error, info};
 
 use uaip_core::{
     error::{Result, UaipError},
-    message::UaipMessage,
+    message::{RetryPolicy, UaipMessage},
+};
+
+use crate::adapter::{
+    foreign_target, unsupported, AdapterOp, AdapterResult, AdapterValue, ProtocolAdapter, Target,
 };
 
 /// HTTP adapter configuration
@@ -43,6 +49,14 @@ pub struct HttpConfig {
     /// Connection pool max idle per host
     #[serde(default = "default_pool_size")]
     pub pool_max_idle_per_host: usize,
+
+    /// Follow HTTP redirects; when disabled a redirect is returned as the response
+    #[serde(default = "default_true")]
+    pub follow_redirects: bool,
+
+    /// Connect to these addresses instead of resolving the base URL's host
+    #[serde(default)]
+    pub pinned_addresses: Vec<SocketAddr>,
 }
 
 fn default_true() -> bool {
@@ -64,10 +78,22 @@ impl Default for HttpConfig {
             auth: None,
             verify_tls: true,
             pool_max_idle_per_host: 10,
+            follow_redirects: true,
+            pinned_addresses: Vec::new(),
         }
     }
 }
 
+impl HttpConfig {
+    /// Retry policy for requests: `max_retries` retries, `retry_delay_ms` apart
+    pub fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy::fixed(
+            self.max_retries.saturating_add(1),
+            Duration::from_millis(self.retry_delay_ms),
+        )
+    }
+}
+
 /// HTTP authentication configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "lowercase")]
@@ -112,6 +138,18 @@ impl HttpAdapter {
 
         client_builder = client_builder.default_headers(headers);
 
+        if !config.follow_redirects {
+            client_builder = client_builder.redirect(reqwest::redirect::Policy::none());
+        }
+        if !config.pinned_addresses.is_empty() {
+            let url = reqwest::Url::parse(&config.base_url)
+                .map_err(|e| UaipError::InvalidConfiguration(format!("Invalid base URL: {}", e)))?;
+            let host = url.host_str().ok_or_else(|| {
+                UaipError::InvalidConfiguration(format!("Base URL has no host: {}", url))
+            })?;
+            client_builder = client_builder.resolve_to_addrs(host, &config.pinned_addresses);
+        }
+
         let client = client_builder.build().map_err(|e| {
             UaipError::ConnectionError(format!("Failed to create HTTP client: {}", e))
         })?;
@@ -150,12 +188,16 @@ impl HttpAdapter {
 
     /// Execute a request with retries
     async fn execute_with_retry(&self, request: RequestBuilder) -> Result<Response> {
+        let retry = self.config.retry_policy();
         let mut last_error = None;
 
-        for attempt in 0..=self.config.max_retries {
-            if attempt > 0 {
-                debug!("Retry attempt {} after delay", attempt);
-                tokio::time::sleep(Duration::from_millis(self.config.retry_delay_ms)).await;
+        for attempt in 1..=retry.max_attempts {
+            if attempt > 1 {
+                let Some(delay) = retry.next_delay(attempt - 1) else {
+                    break;
+                };
+                debug!("Retry attempt {} after {:?}", attempt - 1, delay);
+                tokio::time::sleep(delay).await;
             }
 
             // Clone the request for retry
@@ -286,9 +328,43 @@ impl HttpAdapter {
     }
 }
 
+#[async_trait]
+impl ProtocolAdapter for HttpAdapter {
+    fn adapter_type(&self) -> &'static str {
+        "http"
+    }
+
+    async fn health_check(&mut self) -> Result<()> {
+        HttpAdapter::health_check(self).await
+    }
+
+    /// Reads GET the target path; writes POST the value as JSON and return the response
+    async fn execute(&mut self, op: AdapterOp) -> Result<AdapterResult> {
+        let value = match op {
+            AdapterOp::Read {
+                target: Target::Http { path },
+            } => self.get_json(&path).await?,
+            AdapterOp::Write {
+                target: Target::Http { path },
+                value,
+            } => self.post_json_response(&path, &value.to_json()).await?,
+            AdapterOp::Read { target } | AdapterOp::Write { target, .. } => {
+                return Err(foreign_target(self.adapter_type(), &target))
+            }
+            // Only reachable with the OPC UA operations of the `opcua` feature
+            #[allow(unreachable_patterns)]
+            op => return Err(unsupported(self.adapter_type(), &op)),
+        };
+        Ok(AdapterResult::Value(AdapterValue::Json(value)))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
     #[test]
     fn test_http_config_default() {
@@ -317,12 +393,19 @@ mod tests {
             }),
             verify_tls: false,
             pool_max_idle_per_host: 20,
+            follow_redirects: true,
+            pinned_addresses: Vec::new(),
         };
 
         assert_eq!(config.base_url, "https://api.example.com");
         assert_eq!(config.timeout_seconds, 60);
         assert_eq!(config.max_retries, 5);
         assert!(!config.verify_tls);
+
+        let retry = config.retry_policy();
+        assert_eq!(retry.max_attempts, 6);
+        assert_eq!(retry.next_delay(1), Some(Duration::from_millis(2000)));
+        assert_eq!(retry.next_delay(6), None);
     }
 
     #[test]
@@ -380,6 +463,8 @@ mod tests {
             }),
             verify_tls: true,
             pool_max_idle_per_host: 5,
+            follow_redirects: true,
+            pinned_addresses: Vec::new(),
         };
 
         let adapter = HttpAdapter::new(config).unwrap();
@@ -387,4 +472,56 @@ mod tests {
         assert_eq!(adapter.get_config().timeout_seconds, 10);
         assert_eq!(adapter.get_config().max_retries, 2);
     }
+
+    /// Serve `response` to every connection, counting the connections
+    async fn serve(response: String) -> (SocketAddr, Arc<AtomicUsize>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let connections = Arc::new(AtomicUsize::new(0));
+        let counter = connections.clone();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                counter.fetch_add(1, Ordering::SeqCst);
+                let mut buf = [0u8; 1024];
+                let _ = socket.read(&mut buf).await;
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+        (address, connections)
+    }
+
+    #[tokio::test]
+    async fn test_pinned_addresses_and_redirect_policy() {
+        let (target, target_hits) =
+            serve("HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n".to_string()).await;
+        let (redirect, _) = serve(format!(
+            "HTTP/1.1 302 Found\r\nLocation: http://{}/health\r\nContent-Length: 0\r\n\r\n",
+            target
+        ))
+        .await;
+        let config = |follow_redirects| HttpConfig {
+            // The name never resolves; only the pinned address can be reached
+            base_url: format!("http://device.invalid:{}", redirect.port()),
+            timeout_seconds: 5,
+            max_retries: 0,
+            follow_redirects,
+            pinned_addresses: vec![redirect],
+            ..HttpConfig::default()
+        };
+
+        let err = HttpAdapter::new(config(false))
+            .unwrap()
+            .health_check()
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("302"), "{}", err);
+        assert_eq!(target_hits.load(Ordering::SeqCst), 0);
+
+        HttpAdapter::new(config(true))
+            .unwrap()
+            .health_check()
+            .await
+            .unwrap();
+        assert_eq!(target_hits.load(Ordering::SeqCst), 1);
+    }
 }
@@ -1,10 +1,35 @@
 //! UAIP Adapters - Protocol Adapters
 //!
 //! This crate provides adapters for various IoT protocols (MQTT, HTTP, WebSocket, Modbus, OPC UA, WebRTC).
+//!
+//! Each adapter is behind a cargo feature of the same name; all are enabled by default.
 
+pub mod adapter;
+#[cfg(feature = "http")]
 pub mod http;
+#[cfg(feature = "modbus")]
 pub mod modbus;
+#[cfg(feature = "mqtt")]
 pub mod mqtt;
+#[cfg(feature = "opcua")]
 pub mod opcua;
+#[cfg(feature = "webrtc")]
 pub mod webrtc;
+#[cfg(feature = "websocket")]
 pub mod websocket;
+
+/// Adapter types compiled into this build
+pub const ENABLED_ADAPTERS: &[&str] = &[
+    #[cfg(feature = "http")]
+    "http",
+    #[cfg(feature = "modbus")]
+    "modbus",
+    #[cfg(feature = "mqtt")]
+    "mqtt",
+    #[cfg(feature = "opcua")]
+    "opcua",
+    #[cfg(feature = "webrtc")]
+    "webrtc",
+    #[cfg(feature = "websocket")]
+    "websocket",
+];
@@ -3,14 +3,26 @@
 //! Provides Modbus TCP/RTU client functionality for industrial IoT devices.
 //! Supports reading and writing coils, discrete inputs, holding registers, and input registers.
 
+use async_trait::async_trait;
+use lazy_static::lazy_static;
+use prometheus::{register_int_counter_vec, IntCounterVec};
 use serde::{Deserialize, Serialize};
+use std::future::Future;
 use std::net::SocketAddr;
 use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt};
 use tokio::net::TcpStream;
+use tokio::sync::Mutex;
 use tokio::time::timeout;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
 use uaip_core::error::{Result, UaipError};
+use uaip_core::message::RetryPolicy;
+
+use crate::adapter::{
+    foreign_target, invalid_value, unsupported, AdapterOp, AdapterResult, AdapterValue,
+    ModbusTable, ProtocolAdapter, Target,
+};
 
 /// Modbus function codes
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -25,6 +37,102 @@ pub enum FunctionCode {
     WriteMultipleRegisters = 0x10,
 }
 
+lazy_static! {
+    /// Modbus connections re-established after a dropped link
+    pub static ref MODBUS_RECONNECTS: IntCounterVec = register_int_counter_vec!(
+        "uaip_modbus_reconnects_total",
+        "Modbus connections re-established after a dropped link",
+        &["server"]
+    )
+    .unwrap();
+}
+
+/// MBAP header size, including the unit ID
+const MBAP_HEADER_LEN: usize = 7;
+
+/// Longest delay between retries
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// Largest PDU a Modbus TCP frame can carry
+const MAX_PDU_LEN: usize = 253;
+
+/// Most coils or discrete inputs a single read request may cover
+pub const MAX_READ_BITS: u16 = 2000;
+
+/// Most holding or input registers a single read request may cover
+pub const MAX_READ_REGISTERS: u16 = 125;
+
+/// Read `count` items from `address` in requests of at most `max_count` items,
+/// concatenating the results in address order
+async fn read_chunked<T, F, Fut>(
+    address: u16,
+    count: u16,
+    max_count: u16,
+    mut read: F,
+) -> Result<Vec<T>>
+where
+    F: FnMut(u16, u16) -> Fut,
+    Fut: Future<Output = Result<Vec<T>>>,
+{
+    let end = u32::from(address) + u32::from(count);
+    if count == 0 || end > 0x10000 {
+        return Err(UaipError::InvalidParameter(format!(
+            "Cannot read {} items from address {}",
+            count, address
+        )));
+    }
+
+    let mut values = Vec::with_capacity(count as usize);
+    let mut next = u32::from(address);
+    while next < end {
+        let chunk = (end - next).min(u32::from(max_count)) as u16;
+        values.extend(read(next as u16, chunk).await?);
+        next += u32::from(chunk);
+    }
+    Ok(values)
+}
+
+/// Read one Modbus TCP frame: the MBAP header, then the number of bytes it declares
+async fn read_frame<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Vec<u8>> {
+    let read_error =
+        |e: std::io::Error| UaipError::ConnectionError(format!("Failed to read response: {}", e));
+
+    let mut frame = vec![0u8; MBAP_HEADER_LEN];
+    reader.read_exact(&mut frame).await.map_err(read_error)?;
+
+    // The length field counts the unit ID and the PDU
+    let length = u16::from_be_bytes([frame[4], frame[5]]) as usize;
+    if !(2..=MAX_PDU_LEN + 1).contains(&length) {
+        return Err(UaipError::InvalidMessage(format!(
+            "Invalid MBAP length: {}",
+            length
+        )));
+    }
+
+    frame.resize(MBAP_HEADER_LEN + length - 1, 0);
+    reader
+        .read_exact(&mut frame[MBAP_HEADER_LEN..])
+        .await
+        .map_err(read_error)?;
+    Ok(frame)
+}
+
+/// Description of a Modbus exception code
+pub fn exception_name(code: u8) -> &'static str {
+    match code {
+        0x01 => "Illegal function",
+        0x02 => "Illegal data address",
+        0x03 => "Illegal data value",
+        0x04 => "Server device failure",
+        0x05 => "Acknowledge",
+        0x06 => "Server device busy",
+        0x08 => "Memory parity error",
+        0x0A => "Gateway path unavailable",
+        0x0B => "Gateway target device failed to respond",
+        _ => "Unknown exception",
+    }
+}
+
 impl FunctionCode {
     pub fn from_u8(value: u8) -> Option<Self> {
         match value {
@@ -41,6 +149,42 @@ impl FunctionCode {
     }
 }
 
+/// Order of coils within each byte of a packed coil/discrete input field
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BitOrder {
+    /// First coil in the least significant bit, as the Modbus specification defines
+    #[default]
+    LsbFirst,
+    /// First coil in the most significant bit, as some devices pack them
+    MsbFirst,
+}
+
+impl BitOrder {
+    fn mask(self, index: usize) -> u8 {
+        match self {
+            Self::LsbFirst => 1 << (index % 8),
+            Self::MsbFirst => 0x80 >> (index % 8),
+        }
+    }
+
+    /// Pack coil values into bytes, padding the last byte with zeros
+    pub fn pack(self, bits: &[bool]) -> Vec<u8> {
+        let mut bytes = vec![0u8; bits.len().div_ceil(8)];
+        for (i, _) in bits.iter().enumerate().filter(|(_, on)| **on) {
+            bytes[i / 8] |= self.mask(i);
+        }
+        bytes
+    }
+
+    /// Unpack the first `count` coil values from bytes
+    pub fn unpack(self, bytes: &[u8], count: usize) -> Vec<bool> {
+        (0..count)
+            .map(|i| bytes[i / 8] & self.mask(i) != 0)
+            .collect()
+    }
+}
+
 /// Modbus adapter configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ModbusConfig {
@@ -62,8 +206,16 @@ pub struct ModbusConfig {
     /// Maximum retries for failed operations
     pub max_retries: u32,
 
-    /// Retry delay in milliseconds
+    /// Delay before the first retry in milliseconds; doubles for each further retry
     pub retry_delay_ms: u64,
+
+    /// Time budget for one operation, including retries and reconnects, in milliseconds
+    #[serde(default = "default_operation_deadline_ms")]
+    pub operation_deadline_ms: u64,
+
+    /// Order of coils within each packed byte
+    #[serde(default)]
+    pub bit_order: BitOrder,
 }
 
 impl Default for ModbusConfig {
@@ -76,14 +228,44 @@ impl Default for ModbusConfig {
             write_timeout: 5,
             max_retries: 3,
             retry_delay_ms: 1000,
+            operation_deadline_ms: default_operation_deadline_ms(),
+            bit_order: BitOrder::default(),
         }
     }
 }
 
+fn default_operation_deadline_ms() -> u64 {
+    30_000
+}
+
+impl ModbusConfig {
+    /// Retry policy for requests: `max_retries` retries with exponential backoff from
+    /// `retry_delay_ms`
+    pub fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy::exponential(
+            self.max_retries.saturating_add(1),
+            Duration::from_millis(self.retry_delay_ms),
+            MAX_RETRY_DELAY,
+        )
+    }
+}
+
+/// Persistent connection to the server
+#[derive(Default)]
+struct Connection {
+    stream: Option<TcpStream>,
+    /// The previous connection dropped and has not been re-established yet
+    lost: bool,
+}
+
 /// Modbus TCP adapter for industrial device communication
+///
+/// Requests share one persistent connection. When it drops, the next request
+/// re-establishes it, backing off between attempts within `operation_deadline_ms`.
 pub struct ModbusAdapter {
     config: ModbusConfig,
     transaction_id: std::sync::atomic::AtomicU16,
+    connection: Mutex<Connection>,
 }
 
 impl ModbusAdapter {
@@ -97,6 +279,7 @@ impl ModbusAdapter {
         Ok(Self {
             config,
             transaction_id: std::sync::atomic::AtomicU16::new(1),
+            connection: Mutex::new(Connection::default()),
         })
     }
 
@@ -140,10 +323,11 @@ impl ModbusAdapter {
 
     /// Read coils (function code 0x01)
     pub async fn read_coils(&self, address: u16, count: u16) -> Result<Vec<bool>> {
-        if count == 0 || count > 2000 {
-            return Err(UaipError::InvalidParameter(
-                "Count must be between 1 and 2000".to_string(),
-            ));
+        if count == 0 || count > MAX_READ_BITS {
+            return Err(UaipError::InvalidParameter(format!(
+                "Count must be between 1 and {}",
+                MAX_READ_BITS
+            )));
         }
 
         let transaction_id = self.next_transaction_id();
@@ -157,10 +341,11 @@ impl ModbusAdapter {
 
     /// Read discrete inputs (function code 0x02)
     pub async fn read_discrete_inputs(&self, address: u16, count: u16) -> Result<Vec<bool>> {
-        if count == 0 || count > 2000 {
-            return Err(UaipError::InvalidParameter(
-                "Count must be between 1 and 2000".to_string(),
-            ));
+        if count == 0 || count > MAX_READ_BITS {
+            return Err(UaipError::InvalidParameter(format!(
+                "Count must be between 1 and {}",
+                MAX_READ_BITS
+            )));
         }
 
         let transaction_id = self.next_transaction_id();
@@ -174,10 +359,11 @@ impl ModbusAdapter {
 
     /// Read holding registers (function code 0x03)
     pub async fn read_holding_registers(&self, address: u16, count: u16) -> Result<Vec<u16>> {
-        if count == 0 || count > 125 {
-            return Err(UaipError::InvalidParameter(
-                "Count must be between 1 and 125".to_string(),
-            ));
+        if count == 0 || count > MAX_READ_REGISTERS {
+            return Err(UaipError::InvalidParameter(format!(
+                "Count must be between 1 and {}",
+                MAX_READ_REGISTERS
+            )));
         }
 
         let transaction_id = self.next_transaction_id();
@@ -191,10 +377,11 @@ impl ModbusAdapter {
 
     /// Read input registers (function code 0x04)
     pub async fn read_input_registers(&self, address: u16, count: u16) -> Result<Vec<u16>> {
-        if count == 0 || count > 125 {
-            return Err(UaipError::InvalidParameter(
-                "Count must be between 1 and 125".to_string(),
-            ));
+        if count == 0 || count > MAX_READ_REGISTERS {
+            return Err(UaipError::InvalidParameter(format!(
+                "Count must be between 1 and {}",
+                MAX_READ_REGISTERS
+            )));
         }
 
         let transaction_id = self.next_transaction_id();
@@ -206,6 +393,56 @@ impl ModbusAdapter {
         self.parse_registers_response(&response, count)
     }
 
+    /// Read any number of coils, split into requests of at most 2000
+    ///
+    /// Each chunk is a separate request, so the read is not atomic: values may change
+    /// between chunks. The first failed chunk fails the whole read.
+    pub async fn read_coils_chunked(&self, address: u16, count: u16) -> Result<Vec<bool>> {
+        read_chunked(address, count, MAX_READ_BITS, |address, count| {
+            self.read_coils(address, count)
+        })
+        .await
+    }
+
+    /// Read any number of discrete inputs, split into requests of at most 2000
+    ///
+    /// Not atomic across chunks; see [`Self::read_coils_chunked`].
+    pub async fn read_discrete_inputs_chunked(
+        &self,
+        address: u16,
+        count: u16,
+    ) -> Result<Vec<bool>> {
+        read_chunked(address, count, MAX_READ_BITS, |address, count| {
+            self.read_discrete_inputs(address, count)
+        })
+        .await
+    }
+
+    /// Read any number of holding registers, split into requests of at most 125
+    ///
+    /// Not atomic across chunks; see [`Self::read_coils_chunked`]. Values spanning
+    /// several registers may be torn if they straddle a chunk boundary.
+    pub async fn read_holding_registers_chunked(
+        &self,
+        address: u16,
+        count: u16,
+    ) -> Result<Vec<u16>> {
+        read_chunked(address, count, MAX_READ_REGISTERS, |address, count| {
+            self.read_holding_registers(address, count)
+        })
+        .await
+    }
+
+    /// Read any number of input registers, split into requests of at most 125
+    ///
+    /// Not atomic across chunks; see [`Self::read_holding_registers_chunked`].
+    pub async fn read_input_registers_chunked(&self, address: u16, count: u16) -> Result<Vec<u16>> {
+        read_chunked(address, count, MAX_READ_REGISTERS, |address, count| {
+            self.read_input_registers(address, count)
+        })
+        .await
+    }
+
     /// Write single coil (function code 0x05)
     pub async fn write_single_coil(&self, address: u16, value: bool) -> Result<()> {
         let transaction_id = self.next_transaction_id();
@@ -219,6 +456,31 @@ impl ModbusAdapter {
         Ok(())
     }
 
+    /// Write multiple coils (function code 0x0F)
+    pub async fn write_multiple_coils(&self, address: u16, values: &[bool]) -> Result<()> {
+        if values.is_empty() || values.len() > 1968 {
+            return Err(UaipError::InvalidParameter(
+                "Values count must be between 1 and 1968".to_string(),
+            ));
+        }
+
+        let transaction_id = self.next_transaction_id();
+        let packed = self.config.bit_order.pack(values);
+        let mut pdu = vec![FunctionCode::WriteMultipleCoils as u8];
+        pdu.extend_from_slice(&address.to_be_bytes());
+        pdu.extend_from_slice(&(values.len() as u16).to_be_bytes());
+        pdu.push(packed.len() as u8); // Byte count
+        pdu.extend_from_slice(&packed);
+
+        self.send_request(transaction_id, pdu).await?;
+        debug!(
+            "Wrote {} coils starting at address {}",
+            values.len(),
+            address
+        );
+        Ok(())
+    }
+
     /// Write single register (function code 0x06)
     pub async fn write_single_register(&self, address: u16, value: u16) -> Result<()> {
         let transaction_id = self.next_transaction_id();
@@ -258,20 +520,35 @@ impl ModbusAdapter {
         Ok(())
     }
 
-    /// Send request with retry logic
+    /// Send request with retry logic, within the operation deadline
     async fn send_request(&self, transaction_id: u16, pdu: Vec<u8>) -> Result<Vec<u8>> {
+        timeout(
+            Duration::from_millis(self.config.operation_deadline_ms),
+            self.send_with_retries(transaction_id, &pdu),
+        )
+        .await
+        .map_err(|_| UaipError::Timeout("Modbus operation deadline exceeded".to_string()))?
+    }
+
+    async fn send_with_retries(&self, transaction_id: u16, pdu: &[u8]) -> Result<Vec<u8>> {
+        let retry = self.config.retry_policy();
         let mut last_error = None;
 
-        for attempt in 0..=self.config.max_retries {
-            if attempt > 0 {
-                debug!("Retry attempt {}", attempt);
-                tokio::time::sleep(Duration::from_millis(self.config.retry_delay_ms)).await;
+        for attempt in 1..=retry.max_attempts {
+            if attempt > 1 {
+                let Some(delay) = retry.next_delay(attempt - 1) else {
+                    break;
+                };
+                debug!("Retry attempt {}", attempt - 1);
+                tokio::time::sleep(delay).await;
             }
 
-            match self.execute_request(transaction_id, &pdu).await {
+            match self.execute_request(transaction_id, pdu).await {
                 Ok(response) => return Ok(response),
+                // The server answered; retrying would get the same exception
+                Err(e @ UaipError::ProtocolError { .. }) => return Err(e),
                 Err(e) => {
-                    error!("Modbus request failed (attempt {}): {}", attempt + 1, e);
+                    error!("Modbus request failed (attempt {}): {}", attempt, e);
                     last_error = Some(e);
                 }
             }
@@ -282,10 +559,57 @@ impl ModbusAdapter {
         }))
     }
 
-    /// Execute a single request
+    /// Execute a single request on the persistent connection
+    ///
+    /// A request that fails on a reused connection, which the server may have closed
+    /// while idle, is retried once on a fresh connection. A connection is discarded
+    /// whenever a request on it fails for any reason but a Modbus exception.
     async fn execute_request(&self, transaction_id: u16, pdu: &[u8]) -> Result<Vec<u8>> {
+        let mut connection = self.connection.lock().await;
+
+        // Held outside the slot so a cancelled request cannot leave a half-read stream
+        if let Some(mut stream) = connection.stream.take() {
+            match self.exchange(&mut stream, transaction_id, pdu).await {
+                Err(e) if is_broken(&e) => {
+                    warn!(
+                        "Modbus connection to {} lost, reconnecting: {}",
+                        self.config.server_address, e
+                    );
+                    connection.lost = true;
+                }
+                result => {
+                    connection.stream = Some(stream);
+                    return result;
+                }
+            }
+        }
+
         let mut stream = self.connect().await?;
+        if std::mem::take(&mut connection.lost) {
+            MODBUS_RECONNECTS
+                .with_label_values(&[&self.config.server_address])
+                .inc();
+            info!(
+                "Reconnected to Modbus server: {}",
+                self.config.server_address
+            );
+        }
+
+        let result = self.exchange(&mut stream, transaction_id, pdu).await;
+        match &result {
+            Err(e) if is_broken(e) => connection.lost = true,
+            _ => connection.stream = Some(stream),
+        }
+        result
+    }
 
+    /// Send a request and read its response on `stream`
+    async fn exchange(
+        &self,
+        stream: &mut TcpStream,
+        transaction_id: u16,
+        pdu: &[u8],
+    ) -> Result<Vec<u8>> {
         // Build complete request (MBAP header + PDU)
         let length = (pdu.len() + 1) as u16; // +1 for unit ID
         let header = self.build_mbap_header(transaction_id, length);
@@ -302,23 +626,13 @@ impl ModbusAdapter {
         .map_err(|_| UaipError::Timeout("Write timeout".to_string()))?
         .map_err(|e| UaipError::ConnectionError(format!("Failed to send request: {}", e)))?;
 
-        // Read response
-        use tokio::io::AsyncReadExt;
-        let mut response = vec![0u8; 260]; // Max Modbus TCP frame size
-        let n = timeout(
+        // Read response; the frame may arrive across several TCP reads
+        let response = timeout(
             Duration::from_secs(self.config.read_timeout),
-            stream.read(&mut response),
+            read_frame(stream),
         )
         .await
-        .map_err(|_| UaipError::Timeout("Read timeout".to_string()))?
-        .map_err(|e| UaipError::ConnectionError(format!("Failed to read response: {}", e)))?;
-
-        response.truncate(n);
-
-        // Validate response
-        if response.len() < 8 {
-            return Err(UaipError::InvalidMessage("Response too short".to_string()));
-        }
+        .map_err(|_| UaipError::Timeout("Read timeout".to_string()))??;
 
         // Check transaction ID
         let resp_transaction_id = u16::from_be_bytes([response[0], response[1]]);
@@ -329,6 +643,23 @@ impl ModbusAdapter {
             )));
         }
 
+        // Exception responses set the high bit of the function code
+        let function = response[7];
+        if function & 0x80 != 0 {
+            let code = *response
+                .get(8)
+                .ok_or_else(|| UaipError::InvalidMessage("Response too short".to_string()))?;
+            return Err(UaipError::ProtocolError {
+                protocol: "modbus".to_string(),
+                code: code as u32,
+                message: format!(
+                    "{} (function 0x{:02X})",
+                    exception_name(code),
+                    function & 0x7F
+                ),
+            });
+        }
+
         // Extract PDU (skip MBAP header)
         Ok(response[7..].to_vec())
     }
@@ -340,20 +671,14 @@ impl ModbusAdapter {
         }
 
         let byte_count = pdu[1] as usize;
-        if pdu.len() < 2 + byte_count {
+        if pdu.len() < 2 + byte_count || byte_count < (count as usize).div_ceil(8) {
             return Err(UaipError::InvalidMessage("Incomplete response".to_string()));
         }
 
-        let mut coils = Vec::new();
-        for i in 0..count {
-            let byte_index = (i / 8) as usize;
-            let bit_index = i % 8;
-            let byte = pdu[2 + byte_index];
-            let value = (byte & (1 << bit_index)) != 0;
-            coils.push(value);
-        }
-
-        Ok(coils)
+        Ok(self
+            .config
+            .bit_order
+            .unpack(&pdu[2..2 + byte_count], count as usize))
     }
 
     /// Parse registers response
@@ -394,6 +719,90 @@ impl ModbusAdapter {
     }
 }
 
+/// Whether a failed request leaves the connection unusable
+///
+/// Anything but a Modbus exception may have left the stream closed or out of sync.
+fn is_broken(error: &UaipError) -> bool {
+    !matches!(error, UaipError::ProtocolError { .. })
+}
+
+#[async_trait]
+impl ProtocolAdapter for ModbusAdapter {
+    fn adapter_type(&self) -> &'static str {
+        "modbus"
+    }
+
+    async fn health_check(&mut self) -> Result<()> {
+        ModbusAdapter::health_check(self).await
+    }
+
+    async fn execute(&mut self, op: AdapterOp) -> Result<AdapterResult> {
+        match op {
+            AdapterOp::Read {
+                target:
+                    Target::Modbus {
+                        table,
+                        address,
+                        count,
+                    },
+            } => {
+                // Ranges beyond a single request's limit are read in chunks
+                let value = match table {
+                    ModbusTable::Coil => {
+                        AdapterValue::Bits(self.read_coils_chunked(address, count).await?)
+                    }
+                    ModbusTable::DiscreteInput => {
+                        AdapterValue::Bits(self.read_discrete_inputs_chunked(address, count).await?)
+                    }
+                    ModbusTable::HoldingRegister => AdapterValue::Registers(
+                        self.read_holding_registers_chunked(address, count).await?,
+                    ),
+                    ModbusTable::InputRegister => AdapterValue::Registers(
+                        self.read_input_registers_chunked(address, count).await?,
+                    ),
+                };
+                Ok(AdapterResult::Value(value))
+            }
+            AdapterOp::Write {
+                target: Target::Modbus { table, address, .. },
+                value,
+            } => {
+                match (table, &value) {
+                    (ModbusTable::HoldingRegister, AdapterValue::Registers(values)) => {
+                        match values.as_slice() {
+                            [] => return Err(invalid_value(self.adapter_type(), &value)),
+                            [single] => self.write_single_register(address, *single).await?,
+                            _ => self.write_multiple_registers(address, values).await?,
+                        }
+                    }
+                    (ModbusTable::Coil, AdapterValue::Bits(bits)) => match bits.as_slice() {
+                        [] => return Err(invalid_value(self.adapter_type(), &value)),
+                        [on] => self.write_single_coil(address, *on).await?,
+                        _ => self.write_multiple_coils(address, bits).await?,
+                    },
+                    (ModbusTable::HoldingRegister | ModbusTable::Coil, _) => {
+                        return Err(invalid_value(self.adapter_type(), &value))
+                    }
+                    // Discrete inputs and input registers are read-only
+                    _ => {
+                        return Err(UaipError::InvalidParameter(format!(
+                            "Modbus {:?} table is read-only",
+                            table
+                        )))
+                    }
+                }
+                Ok(AdapterResult::Written)
+            }
+            AdapterOp::Read { target } | AdapterOp::Write { target, .. } => {
+                Err(foreign_target(self.adapter_type(), &target))
+            }
+            // Only reachable with the OPC UA operations of the `opcua` feature
+            #[allow(unreachable_patterns)]
+            op => Err(unsupported(self.adapter_type(), &op)),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -405,6 +814,7 @@ mod tests {
         assert_eq!(config.unit_id, 1);
         assert_eq!(config.connection_timeout, 10);
         assert_eq!(config.max_retries, 3);
+        assert_eq!(config.operation_deadline_ms, 30_000);
     }
 
     #[test]
@@ -474,4 +884,218 @@ mod tests {
         assert!(coils[3]); // bit 3
         assert!(!coils[4]); // bit 4
     }
+
+    #[test]
+    fn test_coil_bit_orders() {
+        // Same wire bytes, opposite coil order within each byte
+        let bytes = [0b0000_1101, 0b1000_0010];
+        let on = |coils: Vec<bool>| -> Vec<usize> {
+            coils
+                .iter()
+                .enumerate()
+                .filter(|(_, on)| **on)
+                .map(|(i, _)| i)
+                .collect()
+        };
+        assert_eq!(on(BitOrder::LsbFirst.unpack(&bytes, 16)), [0, 2, 3, 9, 15]);
+        assert_eq!(on(BitOrder::MsbFirst.unpack(&bytes, 16)), [4, 5, 7, 8, 14]);
+
+        for order in [BitOrder::LsbFirst, BitOrder::MsbFirst] {
+            let coils = order.unpack(&bytes, 16);
+            assert_eq!(order.pack(&coils), bytes);
+            // Partial bytes are padded with zeros
+            let first_ten = &coils[..10];
+            assert_eq!(order.unpack(&order.pack(first_ten), 10), first_ten);
+        }
+
+        let config = ModbusConfig {
+            bit_order: BitOrder::MsbFirst,
+            ..Default::default()
+        };
+        let adapter = ModbusAdapter::new(config).unwrap();
+        let coils = adapter
+            .parse_coils_response(&[0x01, 0x01, 0x80], 2)
+            .unwrap();
+        assert_eq!(coils, [true, false]);
+        // Byte count too small for the requested coils
+        assert!(adapter
+            .parse_coils_response(&[0x01, 0x01, 0x80], 9)
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_response_split_across_reads() {
+        use tokio::io::AsyncWriteExt;
+        use tokio::net::TcpListener;
+
+        // Split inside the MBAP header, at its end, and inside the PDU
+        for split in [5, 7, 9] {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let config = ModbusConfig {
+                server_address: listener.local_addr().unwrap().to_string(),
+                ..Default::default()
+            };
+
+            let server = tokio::spawn(async move {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut request = [0u8; 12];
+                socket.read_exact(&mut request).await.unwrap();
+
+                // Echo the transaction ID; PDU holds registers 0x1234 and 0x5678
+                let mut response = request[..2].to_vec();
+                response.extend_from_slice(&[0x00, 0x00, 0x00, 0x07, 0x01]);
+                response.extend_from_slice(&[0x03, 0x04, 0x12, 0x34, 0x56, 0x78]);
+                socket.write_all(&response[..split]).await.unwrap();
+                socket.flush().await.unwrap();
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                socket.write_all(&response[split..]).await.unwrap();
+            });
+
+            let adapter = ModbusAdapter::new(config).unwrap();
+            let registers = adapter.read_holding_registers(0, 2).await.unwrap();
+            assert_eq!(registers, [0x1234, 0x5678]);
+            server.await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dropped_connection_reconnects() {
+        use tokio::io::AsyncWriteExt;
+        use tokio::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let server_address = listener.local_addr().unwrap().to_string();
+        let config = ModbusConfig {
+            server_address: server_address.clone(),
+            retry_delay_ms: 10,
+            ..Default::default()
+        };
+
+        // Answer one request on a connection with a single register value
+        async fn answer(listener: &TcpListener, value: u16) -> TcpStream {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = [0u8; 12];
+            socket.read_exact(&mut request).await.unwrap();
+
+            let mut response = request[..2].to_vec();
+            response.extend_from_slice(&[0x00, 0x00, 0x00, 0x05, 0x01, 0x03, 0x02]);
+            response.extend_from_slice(&value.to_be_bytes());
+            socket.write_all(&response).await.unwrap();
+            socket
+        }
+
+        // The server closes the first connection after answering once
+        let server = tokio::spawn(async move {
+            drop(answer(&listener, 0x0001).await);
+            answer(&listener, 0x0002).await
+        });
+
+        let reconnects = || {
+            MODBUS_RECONNECTS
+                .with_label_values(&[&server_address])
+                .get()
+        };
+        let adapter = ModbusAdapter::new(config).unwrap();
+        assert_eq!(
+            adapter.read_holding_registers(0, 1).await.unwrap(),
+            [0x0001]
+        );
+        assert_eq!(reconnects(), 0);
+
+        // The server dropped the link; the next call reconnects and succeeds
+        assert_eq!(
+            adapter.read_holding_registers(0, 1).await.unwrap(),
+            [0x0002]
+        );
+        assert_eq!(reconnects(), 1);
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_operation_deadline_bounds_retries() {
+        // Nothing listens on the port, so every attempt fails to connect
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let server_address = listener.local_addr().unwrap().to_string();
+        drop(listener);
+
+        let adapter = ModbusAdapter::new(ModbusConfig {
+            server_address,
+            max_retries: 100,
+            retry_delay_ms: 20,
+            operation_deadline_ms: 100,
+            ..Default::default()
+        })
+        .unwrap();
+        let started = std::time::Instant::now();
+        let err = adapter.read_holding_registers(0, 1).await.unwrap_err();
+        assert!(matches!(err, UaipError::Timeout(_)), "{}", err);
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    #[tokio::test]
+    async fn test_chunked_read_splits_large_ranges() {
+        use tokio::io::AsyncWriteExt;
+        use tokio::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let config = ModbusConfig {
+            server_address: listener.local_addr().unwrap().to_string(),
+            ..Default::default()
+        };
+
+        // Each register holds its own address; the requested ranges are returned
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut ranges = Vec::new();
+            for _ in 0..3 {
+                let mut request = [0u8; 12];
+                socket.read_exact(&mut request).await.unwrap();
+                let address = u16::from_be_bytes([request[8], request[9]]);
+                let count = u16::from_be_bytes([request[10], request[11]]);
+                ranges.push((address, count));
+
+                let mut response = request[..2].to_vec();
+                response.extend_from_slice(&[0x00, 0x00]);
+                response.extend_from_slice(&(3 + 2 * count).to_be_bytes());
+                response.extend_from_slice(&[0x01, 0x03, (2 * count) as u8]);
+                for register in address..address + count {
+                    response.extend_from_slice(&register.to_be_bytes());
+                }
+                socket.write_all(&response).await.unwrap();
+            }
+            ranges
+        });
+
+        let adapter = ModbusAdapter::new(config).unwrap();
+        let registers = adapter
+            .read_holding_registers_chunked(10, 300)
+            .await
+            .unwrap();
+        assert_eq!(registers.len(), 300);
+        assert!(registers.iter().copied().eq(10..310));
+        assert_eq!(server.await.unwrap(), [(10, 125), (135, 125), (260, 50)]);
+
+        // Ranges running past the end of the address space are rejected up front
+        assert!(matches!(
+            adapter.read_holding_registers_chunked(65500, 300).await,
+            Err(UaipError::InvalidParameter(_))
+        ));
+        assert!(adapter.read_coils_chunked(0, 0).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_invalid_frame_length_rejected() {
+        let mut frame: &[u8] = &[0x00, 0x01, 0x00, 0x00, 0x01, 0x00, 0x01];
+        assert!(matches!(
+            read_frame(&mut frame).await,
+            Err(UaipError::InvalidMessage(_))
+        ));
+
+        // Connection closed before the declared PDU arrived
+        let mut truncated: &[u8] = &[0x00, 0x01, 0x00, 0x00, 0x00, 0x07, 0x01, 0x03];
+        assert!(matches!(
+            read_frame(&mut truncated).await,
+            Err(UaipError::ConnectionError(_))
+        ));
+    }
 }
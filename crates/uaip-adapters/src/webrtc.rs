@@ -3,6 +3,7 @@
 //! Provides WebRTC functionality for real-time peer-to-peer communication.
 //! Supports data channels, audio/video streaming, and signaling.
 
+use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -11,6 +12,10 @@ use tracing::{debug, info};
 
 use uaip_core::error::{Result, UaipError};
 
+use crate::adapter::{
+    foreign_target, unsupported, AdapterOp, AdapterResult, ProtocolAdapter, Target,
+};
+
 /// WebRTC ICE server configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IceServer {
@@ -160,6 +165,48 @@ fn default_true() -> bool {
     true
 }
 
+/// How a data channel recovers lost messages
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataChannelReliability {
+    /// Retransmit until delivered
+    Reliable,
+    /// Give up after this many retransmissions
+    MaxRetransmits(u16),
+    /// Give up once a message is this many milliseconds old
+    MaxPacketLifeTime(u16),
+}
+
+impl DataChannelConfig {
+    /// Check the configuration against the data channel rules of the WebRTC spec
+    ///
+    /// A channel is limited either by retransmits or by packet lifetime, never both,
+    /// and negotiated channels need an explicit ID.
+    pub fn validate(&self) -> Result<()> {
+        if self.max_retransmits.is_some() && self.max_packet_life_time.is_some() {
+            return Err(UaipError::InvalidParameter(format!(
+                "Data channel '{}' cannot set both max_retransmits and max_packet_life_time",
+                self.label
+            )));
+        }
+        if self.negotiated && self.id.is_none() {
+            return Err(UaipError::InvalidParameter(format!(
+                "Negotiated data channel '{}' requires an id",
+                self.label
+            )));
+        }
+        Ok(())
+    }
+
+    /// Reliability mode selected by the configuration
+    pub fn reliability(&self) -> DataChannelReliability {
+        match (self.max_retransmits, self.max_packet_life_time) {
+            (Some(retransmits), _) => DataChannelReliability::MaxRetransmits(retransmits),
+            (None, Some(life_time)) => DataChannelReliability::MaxPacketLifeTime(life_time),
+            (None, None) => DataChannelReliability::Reliable,
+        }
+    }
+}
+
 impl Default for DataChannelConfig {
     fn default() -> Self {
         Self {
@@ -223,15 +270,15 @@ pub type DataChannelHandler = Arc<dyn Fn(String, Vec<u8>) -> Result<()> + Send +
 
 /// WebRTC data channel
 pub struct DataChannel {
-    label: String,
+    config: DataChannelConfig,
     state: RwLock<ConnectionState>,
     message_handler: RwLock<Option<DataChannelHandler>>,
 }
 
 impl DataChannel {
-    fn new(label: String) -> Self {
+    fn new(config: DataChannelConfig) -> Self {
         Self {
-            label,
+            config,
             state: RwLock::new(ConnectionState::New),
             message_handler: RwLock::new(None),
         }
@@ -239,7 +286,22 @@ impl DataChannel {
 
     /// Get channel label
     pub fn label(&self) -> &str {
-        &self.label
+        &self.config.label
+    }
+
+    /// Whether messages are delivered in order
+    pub fn ordered(&self) -> bool {
+        self.config.ordered
+    }
+
+    /// Reliability mode the channel was created with
+    pub fn reliability(&self) -> DataChannelReliability {
+        self.config.reliability()
+    }
+
+    /// Configuration the channel was created with
+    pub fn config(&self) -> &DataChannelConfig {
+        &self.config
     }
 
     /// Send data
@@ -252,9 +314,11 @@ impl DataChannel {
         }
 
         debug!(
-            "Sending {} bytes on data channel: {}",
+            "Sending {} bytes on data channel: {} (ordered: {}, {:?})",
             data.len(),
-            self.label
+            self.label(),
+            self.ordered(),
+            self.reliability()
         );
         Ok(())
     }
@@ -300,6 +364,10 @@ pub struct WebRtcAdapter {
 impl WebRtcAdapter {
     /// Create a new WebRTC adapter
     pub fn new(config: WebRtcConfig) -> Result<Self> {
+        for channel in &config.data_channels {
+            channel.validate()?;
+        }
+
         info!(
             "WebRTC adapter created with {} ICE servers",
             config.ice_servers.len()
@@ -405,13 +473,20 @@ impl WebRtcAdapter {
 
     /// Create a data channel
     pub async fn create_data_channel(&self, config: DataChannelConfig) -> Result<Arc<DataChannel>> {
-        info!("Creating data channel: {}", config.label);
+        config.validate()?;
+        info!(
+            "Creating data channel: {} (ordered: {}, {:?})",
+            config.label,
+            config.ordered,
+            config.reliability()
+        );
 
-        let channel = Arc::new(DataChannel::new(config.label.clone()));
+        let label = config.label.clone();
+        let channel = Arc::new(DataChannel::new(config));
         self.data_channels
             .write()
             .await
-            .insert(config.label.clone(), channel.clone());
+            .insert(label, channel.clone());
 
         // Simulate connection
         *channel.state.write().await = ConnectionState::Connected;
@@ -478,6 +553,36 @@ impl WebRtcAdapter {
     }
 }
 
+#[async_trait]
+impl ProtocolAdapter for WebRtcAdapter {
+    fn adapter_type(&self) -> &'static str {
+        "webrtc"
+    }
+
+    async fn health_check(&mut self) -> Result<()> {
+        WebRtcAdapter::health_check(self).await
+    }
+
+    /// Writes send the value as JSON on the data channel named by the target;
+    /// incoming data is delivered through message handlers, so reads are unsupported
+    async fn execute(&mut self, op: AdapterOp) -> Result<AdapterResult> {
+        match op {
+            AdapterOp::Write {
+                target: Target::DataChannel { label },
+                value,
+            } => {
+                let channel = self.get_data_channel(&label).await.ok_or_else(|| {
+                    UaipError::NotFound(format!("Data channel not found: {}", label))
+                })?;
+                channel.send_json(&value.to_json()).await?;
+                Ok(AdapterResult::Written)
+            }
+            AdapterOp::Write { target, .. } => Err(foreign_target(self.adapter_type(), &target)),
+            op => Err(unsupported(self.adapter_type(), &op)),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -576,6 +681,68 @@ mod tests {
         assert_eq!(channel.state().await, ConnectionState::Connected);
     }
 
+    #[tokio::test]
+    async fn test_unreliable_data_channel_keeps_its_parameters() {
+        let adapter = WebRtcAdapter::new(WebRtcConfig::default()).unwrap();
+
+        let channel = adapter
+            .create_data_channel(DataChannelConfig {
+                label: "telemetry".to_string(),
+                ordered: false,
+                max_retransmits: Some(0),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert!(!channel.ordered());
+        assert_eq!(
+            channel.reliability(),
+            DataChannelReliability::MaxRetransmits(0)
+        );
+        assert!(channel.send(vec![1, 2, 3]).await.is_ok());
+
+        let channel = adapter
+            .create_data_channel(DataChannelConfig {
+                label: "video-hints".to_string(),
+                max_packet_life_time: Some(500),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert!(channel.ordered());
+        assert_eq!(
+            channel.reliability(),
+            DataChannelReliability::MaxPacketLifeTime(500)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_conflicting_reliability_limits_are_rejected() {
+        let invalid = DataChannelConfig {
+            label: "bad".to_string(),
+            max_retransmits: Some(3),
+            max_packet_life_time: Some(1000),
+            ..Default::default()
+        };
+
+        let adapter = WebRtcAdapter::new(WebRtcConfig::default()).unwrap();
+        let result = adapter.create_data_channel(invalid.clone()).await;
+        assert!(matches!(result, Err(UaipError::InvalidParameter(_))));
+        assert!(adapter.get_data_channel("bad").await.is_none());
+
+        let config = WebRtcConfig {
+            data_channels: vec![invalid],
+            ..Default::default()
+        };
+        assert!(WebRtcAdapter::new(config).is_err());
+
+        let negotiated = DataChannelConfig {
+            negotiated: true,
+            ..Default::default()
+        };
+        assert!(negotiated.validate().is_err());
+    }
+
     #[tokio::test]
     async fn test_get_data_channel() {
         let config = WebRtcConfig::default();
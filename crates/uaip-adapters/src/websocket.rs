@@ -3,10 +3,12 @@
 //! Provides WebSocket client functionality for real-time bidirectional communication.
 //! Supports text and binary messages with automatic reconnection and ping/pong.
 
+use async_trait::async_trait;
 use futures_util::{SinkExt, StreamExt};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use std::time::Duration;
+use tokio::sync::mpsc::error::TrySendError;
 use tokio::sync::{mpsc, RwLock};
 use tokio::time::{interval, sleep};
 use tokio_tungstenite::{connect_async, tungstenite::Message, MaybeTlsStream, WebSocketStream};
@@ -14,7 +16,11 @@ use tracing::{debug, error, info, warn};
 
 use uaip_core::{
     error::{Result, UaipError},
-    message::UaipMessage,
+    message::{RetryPolicy, UaipMessage},
+};
+
+use crate::adapter::{
+    foreign_target, unsupported, AdapterOp, AdapterResult, ProtocolAdapter, Target,
 };
 
 /// WebSocket adapter configuration
@@ -35,14 +41,29 @@ pub struct WebSocketConfig {
     /// Pong timeout in seconds
     pub pong_timeout_seconds: u64,
 
-    /// Message buffer size
+    /// Capacity of the outgoing message queue
     pub message_buffer_size: usize,
 
+    /// What sends do when the outgoing queue is full
+    #[serde(default)]
+    pub send_policy: SendPolicy,
+
     /// Enable TLS certificate verification
     #[serde(default = "default_true")]
     pub verify_tls: bool,
 }
 
+/// Behaviour of sends when the outgoing queue is full
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SendPolicy {
+    /// Wait until the connection task drains the queue
+    #[default]
+    Wait,
+    /// Fail the send immediately
+    Reject,
+}
+
 fn default_true() -> bool {
     true
 }
@@ -56,11 +77,23 @@ impl Default for WebSocketConfig {
             ping_interval_seconds: 30,
             pong_timeout_seconds: 10,
             message_buffer_size: 100,
+            send_policy: SendPolicy::Wait,
             verify_tls: true,
         }
     }
 }
 
+impl WebSocketConfig {
+    /// Retry policy for reconnecting; unlimited attempts when `max_reconnect_attempts` is 0
+    pub fn reconnect_policy(&self) -> RetryPolicy {
+        let max_attempts = match self.max_reconnect_attempts {
+            0 => u32::MAX,
+            n => n,
+        };
+        RetryPolicy::fixed(max_attempts, Duration::from_millis(self.reconnect_delay_ms))
+    }
+}
+
 /// WebSocket connection state
 #[derive(Debug, Clone, PartialEq)]
 pub enum ConnectionState {
@@ -85,6 +118,83 @@ pub enum WsMessage {
     Uaip(Box<UaipMessage>),
 }
 
+/// Kind of a framed WebSocket message
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FrameType {
+    /// Body is a serialized `UaipMessage`
+    Uaip,
+}
+
+/// Envelope carrying a `UaipMessage` in a WebSocket text frame
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UaipFrame {
+    /// Frame type discriminator
+    #[serde(rename = "type")]
+    pub frame_type: FrameType,
+
+    /// ID of the carried message
+    pub message_id: String,
+
+    /// Correlation ID of the carried message
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<String>,
+
+    /// Byte length of `body`
+    pub length: usize,
+
+    /// Serialized message
+    pub body: String,
+}
+
+impl UaipFrame {
+    /// Frame a UAIP message
+    pub fn encode(message: &UaipMessage) -> Result<String> {
+        let body = serde_json::to_string(message).map_err(|e| {
+            UaipError::InvalidMessage(format!("Failed to serialize message: {}", e))
+        })?;
+        let frame = Self {
+            frame_type: FrameType::Uaip,
+            message_id: message.header.message_id.clone(),
+            correlation_id: message.header.correlation_id.clone(),
+            length: body.len(),
+            body,
+        };
+        Ok(serde_json::to_string(&frame)?)
+    }
+
+    /// Decode a framed UAIP message, rejecting frames whose envelope does not match the body
+    pub fn decode(text: &str) -> Result<UaipMessage> {
+        let frame: Self = serde_json::from_str(text)
+            .map_err(|e| UaipError::InvalidMessage(format!("Malformed UAIP frame: {}", e)))?;
+        if frame.body.len() != frame.length {
+            return Err(UaipError::InvalidMessage(format!(
+                "UAIP frame length mismatch: declared {}, got {}",
+                frame.length,
+                frame.body.len()
+            )));
+        }
+
+        let message: UaipMessage = serde_json::from_str(&frame.body)
+            .map_err(|e| UaipError::InvalidMessage(format!("Malformed UAIP message: {}", e)))?;
+        if message.header.message_id != frame.message_id
+            || message.header.correlation_id != frame.correlation_id
+        {
+            return Err(UaipError::InvalidMessage(
+                "UAIP frame envelope does not match message header".to_string(),
+            ));
+        }
+        Ok(message)
+    }
+
+    /// Whether a text frame claims to be a UAIP frame
+    fn is_uaip_frame(text: &str) -> bool {
+        serde_json::from_str::<serde_json::Value>(text)
+            .is_ok_and(|v| v.get("type").and_then(|t| t.as_str()) == Some("uaip"))
+    }
+}
+
 /// Message handler callback type
 pub type MessageHandler = Arc<dyn Fn(WsMessage) -> Result<()> + Send + Sync>;
 
@@ -165,7 +275,9 @@ impl WebSocketAdapter {
                     match msg {
                         Some(Ok(Message::Text(text))) => {
                             debug!("Received text message: {} bytes", text.len());
-                            Self::handle_message(WsMessage::Text(text), &message_handler).await;
+                            if let Some(msg) = Self::decode_text(text) {
+                                Self::handle_message(msg, &message_handler).await;
+                            }
                         }
                         Some(Ok(Message::Binary(data))) => {
                             debug!("Received binary message: {} bytes", data.len());
@@ -220,6 +332,20 @@ impl WebSocketAdapter {
         info!("WebSocket handler task exited");
     }
 
+    /// Turn an inbound text frame into a message, dropping malformed UAIP frames
+    fn decode_text(text: String) -> Option<WsMessage> {
+        if !UaipFrame::is_uaip_frame(&text) {
+            return Some(WsMessage::Text(text));
+        }
+        match UaipFrame::decode(&text) {
+            Ok(message) => Some(WsMessage::Uaip(Box::new(message))),
+            Err(e) => {
+                warn!("Dropping UAIP frame: {}", e);
+                None
+            }
+        }
+    }
+
     /// Handle incoming message
     async fn handle_message(msg: WsMessage, handler: &Arc<RwLock<Option<MessageHandler>>>) {
         let handler_lock = handler.read().await;
@@ -232,36 +358,50 @@ impl WebSocketAdapter {
         }
     }
 
-    /// Send text message
-    pub async fn send_text(&self, text: String) -> Result<()> {
-        if let Some(tx) = &self.message_tx {
-            tx.send(Message::Text(text))
+    /// Queue an outgoing message, applying the configured `SendPolicy` when the queue is full
+    async fn send(&self, msg: Message) -> Result<()> {
+        let tx = self
+            .message_tx
+            .as_ref()
+            .ok_or_else(|| UaipError::InvalidState("Not connected".to_string()))?;
+
+        match self.config.send_policy {
+            SendPolicy::Wait => tx
+                .send(msg)
                 .await
-                .map_err(|e| UaipError::ConnectionError(format!("Failed to send text: {}", e)))?;
-            Ok(())
-        } else {
-            Err(UaipError::InvalidState("Not connected".to_string()))
+                .map_err(|e| UaipError::ConnectionError(format!("Failed to send message: {}", e))),
+            SendPolicy::Reject => tx.try_send(msg).map_err(|e| match e {
+                TrySendError::Full(_) => UaipError::ResourceUnavailable(format!(
+                    "WebSocket send queue full ({} messages)",
+                    tx.max_capacity()
+                )),
+                TrySendError::Closed(_) => {
+                    UaipError::ConnectionError("WebSocket connection closed".to_string())
+                }
+            }),
         }
     }
 
+    /// Number of messages waiting to be written to the socket
+    pub fn queue_depth(&self) -> usize {
+        self.message_tx
+            .as_ref()
+            .map_or(0, |tx| tx.max_capacity() - tx.capacity())
+    }
+
+    /// Send text message
+    pub async fn send_text(&self, text: String) -> Result<()> {
+        self.send(Message::Text(text)).await
+    }
+
     /// Send binary message
     pub async fn send_binary(&self, data: Vec<u8>) -> Result<()> {
-        if let Some(tx) = &self.message_tx {
-            tx.send(Message::Binary(data))
-                .await
-                .map_err(|e| UaipError::ConnectionError(format!("Failed to send binary: {}", e)))?;
-            Ok(())
-        } else {
-            Err(UaipError::InvalidState("Not connected".to_string()))
-        }
+        self.send(Message::Binary(data)).await
     }
 
-    /// Send UAIP message
-    pub async fn send_uaip_message(&self, message: &UaipMessage) -> Result<()> {
-        let json = serde_json::to_string(message).map_err(|e| {
-            UaipError::InvalidMessage(format!("Failed to serialize message: {}", e))
-        })?;
-        self.send_text(json).await
+    /// Send UAIP message in a `UaipFrame`
+    pub async fn send_uaip(&self, message: &UaipMessage) -> Result<()> {
+        self.send_text(UaipFrame::encode(message)?).await
     }
 
     /// Disconnect from server
@@ -293,33 +433,66 @@ impl WebSocketAdapter {
 
     /// Connect with automatic reconnection
     pub async fn connect_with_retry(&mut self) -> Result<()> {
+        let retry = self.config.reconnect_policy();
         let mut attempts = 0;
-        let max_attempts = self.config.max_reconnect_attempts;
 
         loop {
             match self.connect().await {
                 Ok(_) => return Ok(()),
                 Err(e) => {
                     attempts += 1;
-                    if max_attempts > 0 && attempts >= max_attempts {
+                    let Some(delay) = retry.next_delay(attempts) else {
                         return Err(UaipError::MaxRetriesExceeded(format!(
                             "Failed to connect after {} attempts",
                             attempts
                         )));
-                    }
+                    };
 
                     error!(
-                        "Connection attempt {} failed: {}, retrying in {}ms",
-                        attempts, e, self.config.reconnect_delay_ms
+                        "Connection attempt {} failed: {}, retrying in {:?}",
+                        attempts, e, delay
                     );
 
-                    sleep(Duration::from_millis(self.config.reconnect_delay_ms)).await;
+                    sleep(delay).await;
                 }
             }
         }
     }
 }
 
+#[async_trait]
+impl ProtocolAdapter for WebSocketAdapter {
+    fn adapter_type(&self) -> &'static str {
+        "websocket"
+    }
+
+    async fn health_check(&mut self) -> Result<()> {
+        if self.is_connected().await {
+            Ok(())
+        } else {
+            Err(UaipError::ConnectionError(format!(
+                "WebSocket state: {:?}",
+                self.get_state().await
+            )))
+        }
+    }
+
+    /// Writes send the value as a JSON text frame; reads are unsupported
+    async fn execute(&mut self, op: AdapterOp) -> Result<AdapterResult> {
+        match op {
+            AdapterOp::Write {
+                target: Target::WebSocket,
+                value,
+            } => {
+                self.send_text(value.to_json().to_string()).await?;
+                Ok(AdapterResult::Written)
+            }
+            AdapterOp::Write { target, .. } => Err(foreign_target(self.adapter_type(), &target)),
+            op => Err(unsupported(self.adapter_type(), &op)),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -333,6 +506,7 @@ mod tests {
         assert_eq!(config.ping_interval_seconds, 30);
         assert!(config.verify_tls);
         assert_eq!(config.message_buffer_size, 100);
+        assert_eq!(config.send_policy, SendPolicy::Wait);
     }
 
     #[test]
@@ -344,6 +518,7 @@ mod tests {
             ping_interval_seconds: 60,
             pong_timeout_seconds: 15,
             message_buffer_size: 200,
+            send_policy: SendPolicy::Reject,
             verify_tls: false,
         };
 
@@ -351,6 +526,10 @@ mod tests {
         assert_eq!(config.reconnect_delay_ms, 10000);
         assert_eq!(config.max_reconnect_attempts, 5);
         assert!(!config.verify_tls);
+
+        assert_eq!(config.reconnect_policy().next_delay(5), None);
+        let unlimited = WebSocketConfig::default().reconnect_policy();
+        assert_eq!(unlimited.next_delay(1000), Some(Duration::from_secs(5)));
     }
 
     #[test]
@@ -377,6 +556,7 @@ mod tests {
             ping_interval_seconds: 15,
             pong_timeout_seconds: 5,
             message_buffer_size: 50,
+            send_policy: SendPolicy::Wait,
             verify_tls: true,
         };
 
@@ -394,6 +574,102 @@ mod tests {
         assert!(result.is_err());
     }
 
+    fn uaip_message() -> UaipMessage {
+        use uaip_core::message::{Action, EntityType};
+
+        UaipMessage::new(
+            "device_001".to_string(),
+            EntityType::Device,
+            "hub".to_string(),
+            EntityType::System,
+        )
+        .with_correlation_id("req-1".to_string())
+        .with_action(Action::Execute)
+    }
+
+    #[test]
+    fn test_uaip_frame_round_trip() {
+        let message = uaip_message();
+        let text = UaipFrame::encode(&message).unwrap();
+
+        assert_eq!(UaipFrame::decode(&text).unwrap(), message);
+        match WebSocketAdapter::decode_text(text) {
+            Some(WsMessage::Uaip(decoded)) => assert_eq!(*decoded, message),
+            other => panic!("expected UAIP message, got {:?}", other),
+        }
+        assert!(matches!(
+            WebSocketAdapter::decode_text("hello".to_string()),
+            Some(WsMessage::Text(_))
+        ));
+    }
+
+    #[test]
+    fn test_uaip_frame_rejects_malformed() {
+        let message = uaip_message();
+        let mut frame: UaipFrame =
+            serde_json::from_str(&UaipFrame::encode(&message).unwrap()).unwrap();
+
+        let mut wrong_length = frame.clone();
+        wrong_length.length += 1;
+        let wrong_length = serde_json::to_string(&wrong_length).unwrap();
+        assert!(UaipFrame::decode(&wrong_length).is_err());
+        assert!(WebSocketAdapter::decode_text(wrong_length).is_none());
+
+        frame.correlation_id = Some("other".to_string());
+        assert!(UaipFrame::decode(&serde_json::to_string(&frame).unwrap()).is_err());
+
+        assert!(UaipFrame::decode(r#"{"type":"uaip","body":"{}"}"#).is_err());
+        assert!(
+            UaipFrame::decode(r#"{"type":"other","message_id":"m","length":2,"body":"{}"}"#)
+                .is_err()
+        );
+    }
+
+    /// Adapter whose outgoing queue is never drained
+    fn stalled_adapter(send_policy: SendPolicy) -> (WebSocketAdapter, mpsc::Receiver<Message>) {
+        let mut adapter = WebSocketAdapter::new(WebSocketConfig {
+            message_buffer_size: 2,
+            send_policy,
+            ..Default::default()
+        });
+        let (tx, rx) = mpsc::channel(adapter.config.message_buffer_size);
+        adapter.message_tx = Some(tx);
+        (adapter, rx)
+    }
+
+    #[tokio::test]
+    async fn test_full_queue_applies_backpressure() {
+        let (adapter, mut rx) = stalled_adapter(SendPolicy::Wait);
+        adapter.send_text("1".to_string()).await.unwrap();
+        adapter.send_text("2".to_string()).await.unwrap();
+        assert_eq!(adapter.queue_depth(), 2);
+
+        let blocked = tokio::time::timeout(
+            Duration::from_millis(50),
+            adapter.send_text("3".to_string()),
+        )
+        .await;
+        assert!(blocked.is_err());
+
+        rx.recv().await.unwrap();
+        adapter.send_text("3".to_string()).await.unwrap();
+        assert_eq!(adapter.queue_depth(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_full_queue_rejects_when_configured() {
+        let (adapter, mut rx) = stalled_adapter(SendPolicy::Reject);
+        adapter.send_text("1".to_string()).await.unwrap();
+        adapter.send_binary(vec![2]).await.unwrap();
+
+        let result = adapter.send_text("3".to_string()).await;
+        assert!(matches!(result, Err(UaipError::ResourceUnavailable(_))));
+
+        rx.recv().await.unwrap();
+        assert_eq!(adapter.queue_depth(), 1);
+        adapter.send_text("3".to_string()).await.unwrap();
+    }
+
     #[tokio::test]
     async fn test_message_handler_set() {
         let config = WebSocketConfig::default();
@@ -27,6 +27,9 @@ pub struct Claims {
     /// Session ID
     #[serde(skip_serializing_if = "Option::is_none")]
     pub session_id: Option<String>,
+    /// Tenant the subject belongs to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant_id: Option<String>,
 }
 
 /// JWT token generator and validator
@@ -57,6 +60,18 @@ impl JwtManager {
         client_id: &str,
         scopes: Vec<String>,
         session_id: Option<String>,
+    ) -> Result<String> {
+        self.generate_tenant_token(agent_id, client_id, scopes, session_id, None)
+    }
+
+    /// Generate a new JWT token scoped to a tenant
+    pub fn generate_tenant_token(
+        &self,
+        agent_id: &str,
+        client_id: &str,
+        scopes: Vec<String>,
+        session_id: Option<String>,
+        tenant_id: Option<String>,
     ) -> Result<String> {
         let now = Utc::now();
         let exp = now + Duration::seconds(self.expiry_seconds);
@@ -70,6 +85,7 @@ impl JwtManager {
             scopes,
             client_id: client_id.to_string(),
             session_id,
+            tenant_id,
         };
 
         encode(&Header::default(), &claims, &self.encoding_key).map_err(|e| {
@@ -93,11 +109,12 @@ impl JwtManager {
     pub fn refresh_token(&self, old_token: &str) -> Result<String> {
         let claims = self.validate_token(old_token)?;
 
-        self.generate_token(
+        self.generate_tenant_token(
             &claims.sub,
             &claims.client_id,
             claims.scopes,
             claims.session_id,
+            claims.tenant_id,
         )
     }
 
@@ -320,6 +337,33 @@ mod tests {
         assert!(new_claims.exp > old_claims.exp);
     }
 
+    #[test]
+    fn test_tenant_claim_survives_refresh() {
+        let manager = create_test_manager();
+
+        let token = manager
+            .generate_tenant_token(
+                "agent_001",
+                "client_001",
+                vec![],
+                None,
+                Some("acme".to_string()),
+            )
+            .expect("Should generate token");
+        let refreshed = manager.refresh_token(&token).expect("Should refresh token");
+
+        let claims = manager
+            .validate_token(&refreshed)
+            .expect("Should validate token");
+        assert_eq!(claims.tenant_id.as_deref(), Some("acme"));
+
+        // Tokens issued without a tenant carry no claim
+        let token = manager
+            .generate_token("agent_001", "client_001", vec![], None)
+            .expect("Should generate token");
+        assert!(manager.validate_token(&token).unwrap().tenant_id.is_none());
+    }
+
     #[test]
     fn test_has_scope() {
         let manager = create_test_manager();
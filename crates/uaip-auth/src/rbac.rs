@@ -8,9 +8,12 @@ use std::collections::{HashMap, HashSet};
 use uaip_core::error::{Result, UaipError};
 
 /// Permission represents a specific action on a resource
+///
+/// Resources may have several colon-separated segments, as in `command:light:toggle`,
+/// the permission to send `toggle` commands to lights.
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Permission {
-    /// Resource type (e.g., "device", "telemetry", "command")
+    /// Resource type (e.g., "device", "telemetry", "command:light")
     pub resource: String,
     /// Action (e.g., "read", "write", "execute", "delete")
     pub action: String,
@@ -25,20 +28,29 @@ impl Permission {
         }
     }
 
-    /// Parse from colon-separated string (e.g., "device:read")
+    /// Permission to send `action` commands to devices of `device_type`
+    pub fn command(device_type: &str, action: &str) -> Self {
+        Self::new(format!("command:{}", device_type), action)
+    }
+
+    /// Permission to send `action` commands to devices in group `group_id`
+    pub fn group_command(group_id: &str, action: &str) -> Self {
+        Self::new(format!("group:{}:command", group_id), action)
+    }
+
+    /// Parse from colon-separated string (e.g., "device:read"); the last segment is the action
     pub fn parse(perm_str: &str) -> Result<Self> {
-        let parts: Vec<&str> = perm_str.split(':').collect();
-        if parts.len() != 2 {
-            return Err(UaipError::InvalidParameter(format!(
+        match perm_str.rsplit_once(':') {
+            Some((resource, action))
+                if !action.is_empty() && resource.split(':').all(|s| !s.is_empty()) =>
+            {
+                Ok(Self::new(resource, action))
+            }
+            _ => Err(UaipError::InvalidParameter(format!(
                 "Invalid permission format: {}",
                 perm_str
-            )));
+            ))),
         }
-
-        Ok(Self {
-            resource: parts[0].to_string(),
-            action: parts[1].to_string(),
-        })
     }
 
     /// Convert to string representation
@@ -47,8 +59,20 @@ impl Permission {
     }
 
     /// Check if this permission matches a wildcard permission
+    ///
+    /// A `*` pattern resource matches any resource; otherwise `*` matches one segment.
     pub fn matches(&self, pattern: &Permission) -> bool {
-        let resource_match = pattern.resource == "*" || pattern.resource == self.resource;
+        let resource_match = pattern.resource == "*" || {
+            let mut expected = pattern.resource.split(':');
+            let mut actual = self.resource.split(':');
+            loop {
+                match (expected.next(), actual.next()) {
+                    (None, None) => break true,
+                    (Some(p), Some(a)) if p == "*" || p == a => continue,
+                    _ => break false,
+                }
+            }
+        };
         let action_match = pattern.action == "*" || pattern.action == self.action;
         resource_match && action_match
     }
@@ -266,3 +290,42 @@ impl Default for RbacManager {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_multi_segment_permission() {
+        let permission = Permission::parse("command:light:toggle").unwrap();
+        assert_eq!(permission, Permission::command("light", "toggle"));
+        assert_eq!(permission.to_string_repr(), "command:light:toggle");
+        assert_eq!(
+            Permission::parse("device:read").unwrap(),
+            Permission::new("device", "read")
+        );
+
+        for invalid in ["device", "device:", ":read", "command::toggle"] {
+            assert!(Permission::parse(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_role_may_toggle_light_but_not_unlock_door() {
+        let role = Role::new("lighting", "Controls lights")
+            .add_permission(Permission::parse("command:light:toggle").unwrap());
+
+        assert!(role.has_permission(&Permission::command("light", "toggle")));
+        assert!(!role.has_permission(&Permission::command("light", "dim")));
+        assert!(!role.has_permission(&Permission::command("door", "unlock")));
+
+        let operator = Role::new("operator", "Commands any device")
+            .add_permission(Permission::parse("command:*:*").unwrap());
+        assert!(operator.has_permission(&Permission::command("door", "unlock")));
+        assert!(!operator.has_permission(&Permission::group_command("lobby", "unlock")));
+
+        let lobby = Role::new("lobby", "Commands the lobby group")
+            .add_permission(Permission::group_command("lobby", "*"));
+        assert!(lobby.has_permission(&Permission::group_command("lobby", "unlock")));
+        assert!(!lobby.has_permission(&Permission::group_command("vault", "unlock")));
+    }
+}
@@ -177,6 +177,10 @@ pub struct AiSession {
     /// Session context/state data
     pub context: HashMap<String, serde_json::Value>,
 
+    /// Context keys in insertion order (oldest first), used for truncation
+    #[serde(default)]
+    pub context_order: Vec<String>,
+
     /// Session statistics
     pub stats: SessionStats,
 }
@@ -193,10 +197,54 @@ impl AiSession {
             started_at: now,
             last_activity_at: now,
             context: HashMap::new(),
+            context_order: Vec::new(),
             stats: SessionStats::default(),
         }
     }
 
+    /// Set a context entry, moving the key to the newest position
+    pub fn set_context(&mut self, key: String, value: serde_json::Value) {
+        self.context_order.retain(|k| k != &key);
+        self.context_order.push(key.clone());
+        self.context.insert(key, value);
+        self.last_activity_at = chrono::Utc::now();
+    }
+
+    /// Remove a context entry
+    pub fn remove_context(&mut self, key: &str) -> Option<serde_json::Value> {
+        self.context_order.retain(|k| k != key);
+        self.context.remove(key)
+    }
+
+    /// Serialized size of the session context in bytes
+    pub fn context_size_bytes(&self) -> usize {
+        serde_json::to_vec(&self.context)
+            .map(|bytes| bytes.len())
+            .unwrap_or(0)
+    }
+
+    /// Drop the oldest context entries until the context fits in `max_bytes`
+    ///
+    /// Returns the keys that were removed.
+    pub fn truncate_context(&mut self, max_bytes: usize) -> Vec<String> {
+        // Keys set directly on the map (not through set_context) are treated as oldest
+        let untracked: Vec<String> = self
+            .context
+            .keys()
+            .filter(|k| !self.context_order.contains(k))
+            .cloned()
+            .collect();
+        self.context_order.splice(0..0, untracked);
+
+        let mut removed = Vec::new();
+        while self.context_size_bytes() > max_bytes && !self.context_order.is_empty() {
+            let key = self.context_order.remove(0);
+            self.context.remove(&key);
+            removed.push(key);
+        }
+        removed
+    }
+
     /// Add device to session
     pub fn add_device(&mut self, device_id: DeviceId) {
         if !self.devices.contains(&device_id) {
@@ -485,6 +533,24 @@ mod tests {
         assert!(session.devices.is_empty());
     }
 
+    #[test]
+    fn test_ai_session_context_truncation() {
+        let mut session = AiSession::new(Uuid::new_v4());
+
+        session.set_context("first".to_string(), serde_json::json!("a".repeat(40)));
+        session.set_context("second".to_string(), serde_json::json!("b".repeat(40)));
+        session.set_context("third".to_string(), serde_json::json!("c".repeat(40)));
+
+        // Re-setting a key makes it the newest entry
+        session.set_context("first".to_string(), serde_json::json!("a".repeat(40)));
+
+        let removed = session.truncate_context(100);
+        assert_eq!(removed, vec!["second".to_string(), "third".to_string()]);
+        assert!(session.context.contains_key("first"));
+        assert!(session.context_size_bytes() <= 100);
+        assert_eq!(session.context_order, vec!["first".to_string()]);
+    }
+
     #[test]
     fn test_session_stats() {
         let mut stats = SessionStats::default();
@@ -0,0 +1,190 @@
+//! Payload encoding keyed on `Metadata.content_type`
+//!
+//! Payloads default to JSON. Devices on constrained links can opt into MessagePack or
+//! CBOR by setting the content type; the hub decodes whatever the sender declared.
+
+use serde::{de::DeserializeOwned, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::{Result, UaipError};
+use crate::message::{Payload, UaipMessage};
+
+/// Supported payload encodings
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ContentType {
+    #[default]
+    Json,
+    MessagePack,
+    Cbor,
+}
+
+impl ContentType {
+    /// Canonical MIME type
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Json => "application/json",
+            Self::MessagePack => "application/msgpack",
+            Self::Cbor => "application/cbor",
+        }
+    }
+
+    /// Resolve an optional content type, defaulting to JSON when unset
+    pub fn resolve(content_type: Option<&str>) -> Result<Self> {
+        content_type.map_or(Ok(Self::Json), str::parse)
+    }
+
+    /// Encode a value
+    pub fn encode<T: Serialize>(&self, value: &T) -> Result<Vec<u8>> {
+        match self {
+            Self::Json => Ok(serde_json::to_vec(value)?),
+            // Named fields, so optional fields skipped on encode still decode
+            Self::MessagePack => rmp_serde::to_vec_named(value).map_err(|e| self.error(e)),
+            Self::Cbor => {
+                let mut bytes = Vec::new();
+                ciborium::into_writer(value, &mut bytes).map_err(|e| self.error(e))?;
+                Ok(bytes)
+            }
+        }
+    }
+
+    /// Decode a value
+    pub fn decode<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T> {
+        match self {
+            Self::Json => Ok(serde_json::from_slice(bytes)?),
+            Self::MessagePack => rmp_serde::from_slice(bytes).map_err(|e| self.error(e)),
+            Self::Cbor => ciborium::from_reader(bytes).map_err(|e| self.error(e)),
+        }
+    }
+
+    fn error(&self, e: impl fmt::Display) -> UaipError {
+        UaipError::InvalidMessage(format!("Invalid {} payload: {}", self.as_str(), e))
+    }
+}
+
+impl FromStr for ContentType {
+    type Err = UaipError;
+
+    /// Parse a MIME type, ignoring parameters such as `charset`
+    fn from_str(s: &str) -> Result<Self> {
+        let essence = s.split(';').next().unwrap_or_default().trim();
+        match essence.to_ascii_lowercase().as_str() {
+            "application/json" => Ok(Self::Json),
+            "application/msgpack" | "application/x-msgpack" | "application/vnd.msgpack" => {
+                Ok(Self::MessagePack)
+            }
+            "application/cbor" => Ok(Self::Cbor),
+            _ => Err(UaipError::InvalidMessage(format!(
+                "Unsupported content type: {}",
+                s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for ContentType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl UaipMessage {
+    /// Content type of the payload, JSON when unset
+    pub fn content_type(&self) -> Result<ContentType> {
+        ContentType::resolve(self.metadata.content_type.as_deref())
+    }
+
+    /// Encode the payload using the message's content type
+    pub fn encode_payload(&self) -> Result<Vec<u8>> {
+        self.content_type()?.encode(&self.payload)
+    }
+
+    /// Replace the payload with one decoded using the message's content type
+    pub fn decode_payload(&mut self, bytes: &[u8]) -> Result<()> {
+        self.payload = self.content_type()?.decode::<Payload>(bytes)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::{Action, CompressionType, Data, DataEncoding, DataFormat, EntityType};
+    use std::collections::HashMap;
+
+    fn message(content_type: Option<&str>) -> UaipMessage {
+        let mut message = UaipMessage::new(
+            "sensor-1".to_string(),
+            EntityType::Device,
+            "hub".to_string(),
+            EntityType::System,
+        );
+        message.metadata.content_type = content_type.map(String::from);
+        message.payload.action = Action::Write;
+        message.payload.capability = Some("temperature".to_string());
+        message.payload.data = Some(Data {
+            format: DataFormat::Json,
+            encoding: DataEncoding::Utf8,
+            compression: CompressionType::None,
+            content: serde_json::json!({"celsius": 21.5, "samples": [21, 22]}),
+        });
+        message.payload.parameters = Some(HashMap::from([(
+            "unit".to_string(),
+            serde_json::json!("C"),
+        )]));
+        message
+    }
+
+    #[test]
+    fn test_payload_round_trips_through_binary_encodings() {
+        for content_type in ["application/msgpack", "application/cbor"] {
+            let sent = message(Some(content_type));
+            let bytes = sent.encode_payload().unwrap();
+            assert_ne!(bytes, serde_json::to_vec(&sent.payload).unwrap());
+
+            let mut received = message(Some(content_type));
+            received.payload = Payload {
+                action: Action::Read,
+                device_type: None,
+                capability: None,
+                data: None,
+                parameters: None,
+            };
+            received.decode_payload(&bytes).unwrap();
+            assert_eq!(received.payload, sent.payload);
+        }
+    }
+
+    #[test]
+    fn test_content_type_defaults_to_json() {
+        let message = message(None);
+        assert_eq!(message.content_type().unwrap(), ContentType::Json);
+        assert_eq!(
+            message.encode_payload().unwrap(),
+            serde_json::to_vec(&message.payload).unwrap()
+        );
+        assert_eq!(
+            "Application/JSON; charset=utf-8"
+                .parse::<ContentType>()
+                .unwrap(),
+            ContentType::Json
+        );
+    }
+
+    #[test]
+    fn test_unknown_content_type_is_rejected() {
+        let mut message = message(Some("application/xml"));
+        assert!(matches!(
+            message.encode_payload(),
+            Err(UaipError::InvalidMessage(_))
+        ));
+        assert!(message.decode_payload(b"<payload/>").is_err());
+
+        let mut cbor = message.clone();
+        cbor.metadata.content_type = Some("application/cbor".to_string());
+        assert!(matches!(
+            cbor.decode_payload(b"not cbor"),
+            Err(UaipError::InvalidMessage(_))
+        ));
+    }
+}
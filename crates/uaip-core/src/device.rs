@@ -6,9 +6,14 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use crate::error::{Result, UaipError};
+
 /// Device ID type alias
 pub type DeviceId = String;
 
+/// Name of the capability through which cameras advertise their video stream
+pub const VIDEO_STREAM_CAPABILITY: &str = "video.stream";
+
 /// Device information structure
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct DeviceInfo {
@@ -67,6 +72,63 @@ pub struct Capability {
     /// Description
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+    /// Stream source, for a `video.stream` capability
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stream: Option<VideoStreamSource>,
+}
+
+/// Stream a camera serves
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct VideoStreamSource {
+    /// URL the device serves the stream from
+    pub url: String,
+    /// Supported streaming protocols, e.g. `WEBRTC` or `HLS`
+    pub protocols: Vec<String>,
+    /// Supported resolutions as `WIDTHxHEIGHT`, e.g. `1920x1080`
+    #[serde(default)]
+    pub resolutions: Vec<String>,
+}
+
+impl VideoStreamSource {
+    /// Parse a `WIDTHxHEIGHT` resolution
+    pub fn parse_resolution(resolution: &str) -> Option<(u32, u32)> {
+        let (width, height) = resolution.split_once('x')?;
+        match (width.parse().ok()?, height.parse().ok()?) {
+            (0, _) | (_, 0) => None,
+            dimensions => Some(dimensions),
+        }
+    }
+
+    /// Whether the source advertises a protocol, compared case-insensitively
+    pub fn supports_protocol(&self, protocol: &str) -> bool {
+        self.protocols
+            .iter()
+            .any(|p| p.eq_ignore_ascii_case(protocol))
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.url.is_empty() {
+            return Err(UaipError::InvalidParameter(
+                "stream source url cannot be empty".to_string(),
+            ));
+        }
+        if self.protocols.is_empty() {
+            return Err(UaipError::InvalidParameter(
+                "stream source must list at least one protocol".to_string(),
+            ));
+        }
+        if let Some(bad) = self
+            .resolutions
+            .iter()
+            .find(|r| Self::parse_resolution(r).is_none())
+        {
+            return Err(UaipError::InvalidParameter(format!(
+                "stream resolution '{}' is not WIDTHxHEIGHT",
+                bad
+            )));
+        }
+        Ok(())
+    }
 }
 
 /// Capability types
@@ -250,9 +312,16 @@ impl Capability {
             supported_actions: Vec::new(),
             parameters: None,
             description: None,
+            stream: None,
         }
     }
 
+    /// Set the stream source of a `video.stream` capability
+    pub fn with_stream(mut self, stream: VideoStreamSource) -> Self {
+        self.stream = Some(stream);
+        self
+    }
+
     /// Add a supported action
     pub fn add_action(mut self, action: String) -> Self {
         self.supported_actions.push(action);
@@ -269,6 +338,140 @@ impl Capability {
         }
         self
     }
+
+    /// Check that the capability and its parameter specs are well formed
+    pub fn validate(&self) -> Result<()> {
+        if self.name.is_empty() {
+            return Err(UaipError::InvalidParameter(
+                "capability name cannot be empty".to_string(),
+            ));
+        }
+        for (name, spec) in self.parameters.iter().flatten() {
+            spec.validate(name).map_err(|e| {
+                UaipError::InvalidParameter(format!("capability '{}': {}", self.name, e))
+            })?;
+        }
+        match &self.stream {
+            Some(stream) => stream.validate().map_err(|e| {
+                UaipError::InvalidParameter(format!("capability '{}': {}", self.name, e))
+            }),
+            None if self.name == VIDEO_STREAM_CAPABILITY => Err(UaipError::InvalidParameter(
+                format!("capability '{}' must describe its stream", self.name),
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Check command parameters against this capability's parameter specs
+    ///
+    /// Capabilities without parameter specs accept any parameters.
+    pub fn validate_parameters(
+        &self,
+        parameters: &serde_json::Map<String, serde_json::Value>,
+    ) -> Result<()> {
+        let Some(specs) = &self.parameters else {
+            return Ok(());
+        };
+
+        if let Some(unknown) = parameters.keys().find(|name| !specs.contains_key(*name)) {
+            return Err(UaipError::InvalidParameter(format!(
+                "'{}' is not a parameter of capability '{}'",
+                unknown, self.name
+            )));
+        }
+        for (name, spec) in specs {
+            match parameters.get(name) {
+                Some(value) => spec.check(name, value)?,
+                None if spec.required => {
+                    return Err(UaipError::InvalidParameter(format!(
+                        "missing required parameter '{}'",
+                        name
+                    )))
+                }
+                None => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ParameterSpec {
+    /// Check that the spec is consistent: ranges only on numeric types, `min <= max`,
+    /// allowed values only on strings, and a default that satisfies the spec
+    pub fn validate(&self, name: &str) -> Result<()> {
+        let numeric = matches!(
+            self.param_type,
+            ParameterType::Integer | ParameterType::Float
+        );
+        if !numeric && (self.min.is_some() || self.max.is_some()) {
+            return Err(UaipError::InvalidParameter(format!(
+                "parameter '{}': min/max require a numeric type",
+                name
+            )));
+        }
+        if let (Some(min), Some(max)) = (self.min, self.max) {
+            if min > max {
+                return Err(UaipError::InvalidParameter(format!(
+                    "parameter '{}': min {} is greater than max {}",
+                    name, min, max
+                )));
+            }
+        }
+        if let Some(allowed) = &self.allowed_values {
+            if self.param_type != ParameterType::String || allowed.is_empty() {
+                return Err(UaipError::InvalidParameter(format!(
+                    "parameter '{}': allowed values require a string type and at least one value",
+                    name
+                )));
+            }
+        }
+        if let Some(default) = &self.default {
+            self.check(name, default)?;
+        }
+        Ok(())
+    }
+
+    /// Check a value against the spec's type, range, and allowed values
+    pub fn check(&self, name: &str, value: &serde_json::Value) -> Result<()> {
+        let type_matches = match self.param_type {
+            ParameterType::String => value.is_string(),
+            ParameterType::Integer => value.is_i64() || value.is_u64(),
+            ParameterType::Float => value.is_number(),
+            ParameterType::Boolean => value.is_boolean(),
+            ParameterType::Object => value.is_object(),
+            ParameterType::Array => value.is_array(),
+        };
+        if !type_matches {
+            return Err(UaipError::InvalidParameter(format!(
+                "parameter '{}' must be of type {:?}, got {}",
+                name, self.param_type, value
+            )));
+        }
+
+        if let Some(number) = value.as_f64() {
+            let below = self.min.is_some_and(|min| number < min);
+            let above = self.max.is_some_and(|max| number > max);
+            if below || above {
+                return Err(UaipError::InvalidParameter(format!(
+                    "parameter '{}' value {} is outside range [{}, {}]",
+                    name,
+                    number,
+                    self.min.map_or("-inf".to_string(), |m| m.to_string()),
+                    self.max.map_or("inf".to_string(), |m| m.to_string()),
+                )));
+            }
+        }
+
+        if let (Some(allowed), Some(text)) = (&self.allowed_values, value.as_str()) {
+            if !allowed.iter().any(|a| a == text) {
+                return Err(UaipError::InvalidParameter(format!(
+                    "parameter '{}' must be one of {:?}, got '{}'",
+                    name, allowed, text
+                )));
+            }
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -313,6 +516,125 @@ mod tests {
         assert!(capability.parameters.is_some());
     }
 
+    fn dimmable_light() -> Capability {
+        Capability::new("dimmer".to_string(), CapabilityType::Actuator, true)
+            .add_action("set".to_string())
+            .add_parameter(
+                "brightness".to_string(),
+                ParameterSpec {
+                    param_type: ParameterType::Integer,
+                    required: true,
+                    default: None,
+                    min: Some(0.0),
+                    max: Some(100.0),
+                    allowed_values: None,
+                    unit: Some("%".to_string()),
+                    description: None,
+                },
+            )
+            .add_parameter(
+                "color_mode".to_string(),
+                ParameterSpec {
+                    param_type: ParameterType::String,
+                    required: false,
+                    default: Some(serde_json::json!("white")),
+                    min: None,
+                    max: None,
+                    allowed_values: Some(vec!["white".to_string(), "rgb".to_string()]),
+                    unit: None,
+                    description: None,
+                },
+            )
+    }
+
+    fn params(value: serde_json::Value) -> serde_json::Map<String, serde_json::Value> {
+        value.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn test_capability_parameter_range() {
+        let capability = dimmable_light();
+        capability.validate().unwrap();
+
+        let ok = params(serde_json::json!({ "brightness": 75, "color_mode": "rgb" }));
+        assert!(capability.validate_parameters(&ok).is_ok());
+
+        for invalid in [
+            serde_json::json!({ "brightness": 150 }),
+            serde_json::json!({ "brightness": -1 }),
+            serde_json::json!({ "brightness": 50.5 }),
+            serde_json::json!({ "brightness": 50, "color_mode": "hsv" }),
+            serde_json::json!({ "brightness": 50, "speed": 2 }),
+            serde_json::json!({ "color_mode": "white" }),
+        ] {
+            assert!(
+                capability
+                    .validate_parameters(&params(invalid.clone()))
+                    .is_err(),
+                "{} should be rejected",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn test_capability_rejects_inconsistent_spec() {
+        let mut capability = dimmable_light();
+        let brightness = capability
+            .parameters
+            .as_mut()
+            .unwrap()
+            .get_mut("brightness")
+            .unwrap();
+        brightness.min = Some(100.0);
+        brightness.max = Some(0.0);
+        assert!(capability.validate().is_err());
+
+        let mut capability = dimmable_light();
+        let color_mode = capability
+            .parameters
+            .as_mut()
+            .unwrap()
+            .get_mut("color_mode")
+            .unwrap();
+        color_mode.default = Some(serde_json::json!("blue"));
+        assert!(capability.validate().is_err());
+    }
+
+    #[test]
+    fn test_video_stream_capability_describes_its_source() {
+        let camera: Capability = serde_json::from_value(serde_json::json!({
+            "name": "video.stream",
+            "capability_type": "video_stream",
+            "is_primary": true,
+            "supported_actions": [],
+            "stream": {
+                "url": "rtsp://10.0.0.7/main",
+                "protocols": ["WEBRTC", "hls"],
+                "resolutions": ["1920x1080", "1280x720"]
+            }
+        }))
+        .unwrap();
+        assert!(camera.validate().is_ok());
+        let stream = camera.stream.as_ref().unwrap();
+        assert!(stream.supports_protocol("HLS"));
+        assert!(!stream.supports_protocol("RTMP"));
+        assert_eq!(
+            VideoStreamSource::parse_resolution(&stream.resolutions[0]),
+            Some((1920, 1080))
+        );
+
+        let mut bad = camera.clone();
+        bad.stream.as_mut().unwrap().resolutions = vec!["1080p".to_string()];
+        assert!(bad.validate().is_err());
+        let bare = Capability::new(
+            VIDEO_STREAM_CAPABILITY.to_string(),
+            CapabilityType::VideoStream,
+            true,
+        );
+        assert!(bare.validate().is_err());
+    }
+
     #[test]
     fn test_device_has_capability() {
         let device = DeviceInfo::new(
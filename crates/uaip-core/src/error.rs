@@ -26,6 +26,10 @@ pub enum UaipError {
     #[error("Invalid message format: {0}")]
     InvalidMessage(String),
 
+    /// No protocol version both sides support
+    #[error("Unsupported protocol version: {0}")]
+    UnsupportedVersion(String),
+
     /// Device not found
     #[error("Device not found: {0}")]
     DeviceNotFound(String),
@@ -42,6 +46,14 @@ pub enum UaipError {
     #[error("Connection error: {0}")]
     ConnectionError(String),
 
+    /// Error reported by a device or server in its own protocol, e.g. a Modbus exception
+    #[error("{protocol} error {code}: {message}")]
+    ProtocolError {
+        protocol: String,
+        code: u32,
+        message: String,
+    },
+
     /// Timeout errors
     #[error("Operation timed out: {0}")]
     Timeout(String),
@@ -86,6 +98,10 @@ pub enum UaipError {
     #[error("Not found: {0}")]
     NotFound(String),
 
+    /// A backend the operation needs, such as the database, is not configured
+    #[error("Not configured: {0}")]
+    NotConfigured(String),
+
     /// Invalid state
     #[error("Invalid state: {0}")]
     InvalidState(String),
@@ -137,6 +153,7 @@ pub enum ErrorCode {
     MissingRequiredField,
     InvalidMessageFormat,
     CorrelationIdMismatch,
+    ProtocolError,
 
     // Device Management (3xxx)
     DeviceNotFound,
@@ -211,6 +228,7 @@ impl From<UaipError> for ErrorResponse {
             UaipError::AuthenticationFailed(msg) => (ErrorCode::AuthenticationFailed, msg.clone()),
             UaipError::AuthorizationFailed(msg) => (ErrorCode::AuthorizationFailed, msg.clone()),
             UaipError::InvalidMessage(msg) => (ErrorCode::InvalidMessage, msg.clone()),
+            UaipError::UnsupportedVersion(msg) => (ErrorCode::UnsupportedVersion, msg.clone()),
             UaipError::DeviceNotFound(msg) => (ErrorCode::DeviceNotFound, msg.clone()),
             UaipError::DeviceAlreadyRegistered(msg) => {
                 (ErrorCode::DeviceAlreadyRegistered, msg.clone())
@@ -219,6 +237,7 @@ impl From<UaipError> for ErrorResponse {
                 (ErrorCode::CapabilityNotSupported, msg.clone())
             }
             UaipError::ConnectionError(msg) => (ErrorCode::ConnectionFailed, msg.clone()),
+            UaipError::ProtocolError { .. } => (ErrorCode::ProtocolError, error.to_string()),
             UaipError::Timeout(msg) => (ErrorCode::ConnectionTimeout, msg.clone()),
             UaipError::RateLimitExceeded => (
                 ErrorCode::RateLimitExceeded,
@@ -233,6 +252,7 @@ impl From<UaipError> for ErrorResponse {
             UaipError::NotPermitted(msg) => (ErrorCode::InsufficientPermissions, msg.clone()),
             UaipError::ResourceUnavailable(msg) => (ErrorCode::ResourceUnavailable, msg.clone()),
             UaipError::NotFound(msg) => (ErrorCode::ResourceNotFound, msg.clone()),
+            UaipError::NotConfigured(msg) => (ErrorCode::ServiceUnavailable, msg.clone()),
             UaipError::InvalidState(msg) => (ErrorCode::InvalidDeviceState, msg.clone()),
             UaipError::MaxRetriesExceeded(msg) => (ErrorCode::QueueError, msg.clone()),
             UaipError::InternalError(msg) => (ErrorCode::InternalError, msg.clone()),
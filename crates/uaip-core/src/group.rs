@@ -0,0 +1,383 @@
+//! Device Groups
+//!
+//! Groups collect devices by room, zone, or site so that commands and automation
+//! conditions can target many devices at once. Groups may contain other groups of the
+//! same tenant.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use crate::error::{Result, UaipError};
+
+/// A named set of devices and nested groups
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DeviceGroup {
+    /// Unique group identifier
+    pub id: String,
+    /// Display name
+    pub name: String,
+    /// Group description
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Devices that are direct members
+    #[serde(default)]
+    pub device_ids: Vec<String>,
+    /// Groups nested inside this group
+    #[serde(default)]
+    pub subgroup_ids: Vec<String>,
+    /// Tenant that owns the group; `None` for groups outside any tenant
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant_id: Option<String>,
+}
+
+impl DeviceGroup {
+    /// Create an empty group
+    pub fn new(id: impl Into<String>, name: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            name: name.into(),
+            description: None,
+            device_ids: Vec::new(),
+            subgroup_ids: Vec::new(),
+            tenant_id: None,
+        }
+    }
+
+    /// Assign the group to a tenant
+    pub fn with_tenant(mut self, tenant_id: impl Into<String>) -> Self {
+        self.tenant_id = Some(tenant_id.into());
+        self
+    }
+
+    /// Add direct device members
+    pub fn with_devices<I, S>(mut self, device_ids: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.device_ids
+            .extend(device_ids.into_iter().map(Into::into));
+        self
+    }
+
+    /// Add nested groups
+    pub fn with_subgroups<I, S>(mut self, subgroup_ids: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.subgroup_ids
+            .extend(subgroup_ids.into_iter().map(Into::into));
+        self
+    }
+}
+
+/// Collection of device groups with nesting validation
+#[derive(Debug, Clone, Default)]
+pub struct DeviceGroups {
+    groups: HashMap<String, DeviceGroup>,
+}
+
+impl DeviceGroups {
+    /// Create an empty collection
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build a collection from stored groups, validating every group
+    pub fn from_groups(groups: Vec<DeviceGroup>) -> Result<Self> {
+        let mut collection = Self {
+            groups: groups.into_iter().map(|g| (g.id.clone(), g)).collect(),
+        };
+
+        let ids: Vec<String> = collection.groups.keys().cloned().collect();
+        for id in ids {
+            let group = collection.groups.remove(&id).expect("group exists");
+            collection.insert(group)?;
+        }
+
+        Ok(collection)
+    }
+
+    /// Insert or replace a group
+    ///
+    /// Fails if a nested group does not exist in the group's tenant or the nesting would
+    /// form a cycle.
+    pub fn insert(&mut self, group: DeviceGroup) -> Result<()> {
+        if group.id.is_empty() {
+            return Err(UaipError::InvalidParameter(
+                "group id cannot be empty".to_string(),
+            ));
+        }
+
+        for subgroup_id in &group.subgroup_ids {
+            if subgroup_id == &group.id {
+                return Err(UaipError::InvalidConfiguration(format!(
+                    "Group membership cycle: {} -> {}",
+                    group.id, group.id
+                )));
+            }
+            let same_tenant = self
+                .groups
+                .get(subgroup_id)
+                .is_some_and(|subgroup| subgroup.tenant_id == group.tenant_id);
+            if !same_tenant {
+                return Err(UaipError::NotFound(format!(
+                    "Group not found: {}",
+                    subgroup_id
+                )));
+            }
+        }
+
+        for subgroup_id in &group.subgroup_ids {
+            let mut path = vec![group.id.clone()];
+            if self.find_path(subgroup_id, &group.id, &mut path) {
+                path.push(group.id.clone());
+                return Err(UaipError::InvalidConfiguration(format!(
+                    "Group membership cycle: {}",
+                    path.join(" -> ")
+                )));
+            }
+        }
+
+        self.groups.insert(group.id.clone(), group);
+        Ok(())
+    }
+
+    /// Remove a group that no other group nests
+    pub fn remove(&mut self, group_id: &str) -> Result<DeviceGroup> {
+        if !self.groups.contains_key(group_id) {
+            return Err(UaipError::NotFound(format!(
+                "Group not found: {}",
+                group_id
+            )));
+        }
+
+        let mut parents: Vec<&str> = self
+            .groups
+            .values()
+            .filter(|g| g.subgroup_ids.iter().any(|id| id == group_id))
+            .map(|g| g.id.as_str())
+            .collect();
+        if !parents.is_empty() {
+            parents.sort_unstable();
+            return Err(UaipError::InvalidState(format!(
+                "Group {} is nested in: {}",
+                group_id,
+                parents.join(", ")
+            )));
+        }
+
+        Ok(self.groups.remove(group_id).expect("group exists"))
+    }
+
+    /// Get a group by ID
+    pub fn get(&self, group_id: &str) -> Option<&DeviceGroup> {
+        self.groups.get(group_id)
+    }
+
+    /// The groups owned by a tenant
+    pub fn for_tenant(&self, tenant_id: &str) -> DeviceGroups {
+        Self {
+            groups: self
+                .groups
+                .iter()
+                .filter(|(_, group)| group.tenant_id.as_deref() == Some(tenant_id))
+                .map(|(id, group)| (id.clone(), group.clone()))
+                .collect(),
+        }
+    }
+
+    /// All groups, ordered by ID
+    pub fn list(&self) -> Vec<&DeviceGroup> {
+        let mut groups: Vec<&DeviceGroup> = self.groups.values().collect();
+        groups.sort_by(|a, b| a.id.cmp(&b.id));
+        groups
+    }
+
+    /// Devices in a group and all of its nested groups, sorted and deduplicated
+    pub fn members(&self, group_id: &str) -> Result<Vec<String>> {
+        if !self.groups.contains_key(group_id) {
+            return Err(UaipError::NotFound(format!(
+                "Group not found: {}",
+                group_id
+            )));
+        }
+
+        let mut members = BTreeSet::new();
+        let mut visited = HashSet::new();
+        let mut pending = vec![group_id];
+
+        while let Some(id) = pending.pop() {
+            if !visited.insert(id) {
+                continue;
+            }
+            if let Some(group) = self.groups.get(id) {
+                members.extend(group.device_ids.iter().cloned());
+                pending.extend(group.subgroup_ids.iter().map(String::as_str));
+            }
+        }
+
+        Ok(members.into_iter().collect())
+    }
+
+    /// Resolved membership of every group
+    pub fn resolve_all(&self) -> HashMap<String, Vec<String>> {
+        self.groups
+            .keys()
+            .filter_map(|id| Some((id.clone(), self.members(id).ok()?)))
+            .collect()
+    }
+
+    /// Number of groups
+    pub fn len(&self) -> usize {
+        self.groups.len()
+    }
+
+    /// Whether there are no groups
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    /// Depth-first search for `target` through nested groups, recording the path
+    fn find_path(&self, from: &str, target: &str, path: &mut Vec<String>) -> bool {
+        if path.iter().skip(1).any(|id| id == from) {
+            return false;
+        }
+        path.push(from.to_string());
+
+        if let Some(group) = self.groups.get(from) {
+            for next in &group.subgroup_ids {
+                if next == target || self.find_path(next, target, path) {
+                    return true;
+                }
+            }
+        }
+
+        path.pop();
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn building() -> DeviceGroups {
+        let mut groups = DeviceGroups::new();
+        groups
+            .insert(DeviceGroup::new("kitchen", "Kitchen").with_devices(["light-1", "sensor-1"]))
+            .unwrap();
+        groups
+            .insert(DeviceGroup::new("hall", "Hall").with_devices(["light-2"]))
+            .unwrap();
+        groups
+            .insert(
+                DeviceGroup::new("floor-1", "First floor")
+                    .with_devices(["light-1"])
+                    .with_subgroups(["kitchen", "hall"]),
+            )
+            .unwrap();
+        groups
+            .insert(DeviceGroup::new("site", "Site").with_subgroups(["floor-1"]))
+            .unwrap();
+        groups
+    }
+
+    #[test]
+    fn test_nested_membership() {
+        let groups = building();
+
+        assert_eq!(
+            groups.members("kitchen").unwrap(),
+            vec!["light-1", "sensor-1"]
+        );
+        assert_eq!(
+            groups.members("site").unwrap(),
+            vec!["light-1", "light-2", "sensor-1"]
+        );
+        assert!(matches!(
+            groups.members("garage"),
+            Err(UaipError::NotFound(_))
+        ));
+        assert_eq!(groups.resolve_all().len(), 4);
+    }
+
+    #[test]
+    fn test_rejects_membership_cycle() {
+        let mut groups = building();
+
+        let err = groups
+            .insert(DeviceGroup::new("kitchen", "Kitchen").with_subgroups(["site"]))
+            .unwrap_err();
+        assert!(matches!(err, UaipError::InvalidConfiguration(_)));
+        assert!(err
+            .to_string()
+            .contains("kitchen -> site -> floor-1 -> kitchen"));
+
+        let err = groups
+            .insert(DeviceGroup::new("hall", "Hall").with_subgroups(["hall"]))
+            .unwrap_err();
+        assert!(matches!(err, UaipError::InvalidConfiguration(_)));
+
+        // The rejected updates leave the original groups intact
+        assert!(groups.get("kitchen").unwrap().subgroup_ids.is_empty());
+        assert!(groups.get("hall").unwrap().subgroup_ids.is_empty());
+    }
+
+    #[test]
+    fn test_remove_nested_group() {
+        let mut groups = building();
+
+        assert!(matches!(
+            groups.remove("kitchen"),
+            Err(UaipError::InvalidState(_))
+        ));
+        assert!(groups.remove("site").is_ok());
+        assert!(groups.remove("floor-1").is_ok());
+        assert!(groups.remove("kitchen").is_ok());
+        assert_eq!(groups.len(), 1);
+    }
+
+    #[test]
+    fn test_groups_scoped_to_tenant() {
+        let mut groups = DeviceGroups::new();
+        groups
+            .insert(
+                DeviceGroup::new("lobby", "Lobby")
+                    .with_tenant("acme")
+                    .with_devices(["door-1"]),
+            )
+            .unwrap();
+        groups
+            .insert(DeviceGroup::new("yard", "Yard").with_tenant("globex"))
+            .unwrap();
+
+        let acme = groups.for_tenant("acme");
+        assert_eq!(acme.len(), 1);
+        assert_eq!(acme.members("lobby").unwrap(), vec!["door-1"]);
+        assert!(groups.for_tenant("globex").get("lobby").is_none());
+
+        // Another tenant's group cannot be nested
+        let err = groups
+            .insert(
+                DeviceGroup::new("site", "Site")
+                    .with_tenant("globex")
+                    .with_subgroups(["lobby"]),
+            )
+            .unwrap_err();
+        assert!(matches!(err, UaipError::NotFound(_)));
+    }
+
+    #[test]
+    fn test_from_groups_validates() {
+        let cyclic = vec![
+            DeviceGroup::new("a", "A").with_subgroups(["b"]),
+            DeviceGroup::new("b", "B").with_subgroups(["a"]),
+        ];
+        assert!(DeviceGroups::from_groups(cyclic).is_err());
+
+        let missing = vec![DeviceGroup::new("a", "A").with_subgroups(["b"])];
+        assert!(DeviceGroups::from_groups(missing).is_err());
+    }
+}
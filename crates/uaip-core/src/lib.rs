@@ -3,15 +3,21 @@
 //! This crate provides the fundamental types and message formats for the UAIP protocol.
 
 pub mod ai_agent;
+pub mod codec;
 pub mod device;
 pub mod error;
+pub mod group;
 pub mod message;
 pub mod network;
 pub mod protocol;
+pub mod version;
 
 pub use ai_agent::*;
+pub use codec::*;
 pub use device::*;
 pub use error::*;
+pub use group::*;
 pub use message::*;
 pub use network::*;
 pub use protocol::*;
+pub use version::*;
@@ -5,8 +5,15 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::str::FromStr;
+use std::time::Duration;
 use uuid::Uuid;
 
+use crate::error::UaipError;
+
+/// Default message time-to-live in milliseconds
+pub const DEFAULT_TTL_MS: u64 = 5000;
+
 /// Root message structure for UAIP
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct UaipMessage {
@@ -50,7 +57,7 @@ pub struct Entity {
 }
 
 /// Entity type enumeration
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 #[serde(rename_all = "snake_case")]
 pub enum EntityType {
     Device,
@@ -70,6 +77,34 @@ pub enum Priority {
     Critical,
 }
 
+impl Priority {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Low => "low",
+            Self::Normal => "normal",
+            Self::High => "high",
+            Self::Critical => "critical",
+        }
+    }
+}
+
+impl FromStr for Priority {
+    type Err = UaipError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "low" => Ok(Self::Low),
+            "normal" => Ok(Self::Normal),
+            "high" => Ok(Self::High),
+            "critical" => Ok(Self::Critical),
+            other => Err(UaipError::InvalidParameter(format!(
+                "Unknown priority: {}",
+                other
+            ))),
+        }
+    }
+}
+
 /// Routing information for multi-hop scenarios
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Routing {
@@ -110,6 +145,9 @@ pub struct Encryption {
     pub enabled: bool,
     pub algorithm: String,
     pub key_id: String,
+    /// Base64-encoded nonce the payload was encrypted with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,
 }
 
 /// Digital signature information
@@ -209,6 +247,9 @@ pub struct Metadata {
     pub retry_policy: Option<RetryPolicy>,
     /// Quality of Service level
     pub qos: QosLevel,
+    /// Deliver in submission order relative to the recipient's other ordered messages
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub ordered: bool,
     /// Content type
     #[serde(skip_serializing_if = "Option::is_none")]
     pub content_type: Option<String>,
@@ -217,20 +258,97 @@ pub struct Metadata {
     pub user_data: Option<HashMap<String, serde_json::Value>>,
 }
 
-/// Retry policy configuration
+/// Retry policy shared by message delivery, adapters and HTTP clients
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct RetryPolicy {
-    pub enabled: bool,
-    pub max_retries: u32,
+    /// Total attempts, including the first
+    pub max_attempts: u32,
+    /// How the delay between attempts grows
     pub backoff: BackoffStrategy,
+    /// Fraction of each delay that is randomized (0.0 to 1.0)
+    #[serde(default)]
+    pub jitter: f64,
 }
 
 /// Backoff strategies for retries
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-#[serde(rename_all = "lowercase")]
+#[serde(tag = "type", rename_all = "lowercase")]
 pub enum BackoffStrategy {
-    Linear,
-    Exponential,
+    /// The same delay before every retry
+    Fixed { delay_ms: u64 },
+    /// A delay that doubles before every retry, up to `max_ms`
+    Exponential { base_ms: u64, max_ms: u64 },
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::exponential(3, Duration::from_secs(1), Duration::from_secs(60)).with_jitter(0.2)
+    }
+}
+
+impl RetryPolicy {
+    /// Retry with the same delay between attempts
+    pub fn fixed(max_attempts: u32, delay: Duration) -> Self {
+        Self {
+            max_attempts: max_attempts.max(1),
+            backoff: BackoffStrategy::Fixed {
+                delay_ms: duration_ms(delay),
+            },
+            jitter: 0.0,
+        }
+    }
+
+    /// Retry with a doubling delay, capped at `max`
+    pub fn exponential(max_attempts: u32, base: Duration, max: Duration) -> Self {
+        Self {
+            max_attempts: max_attempts.max(1),
+            backoff: BackoffStrategy::Exponential {
+                base_ms: duration_ms(base),
+                max_ms: duration_ms(max.max(base)),
+            },
+            jitter: 0.0,
+        }
+    }
+
+    /// Set the total number of attempts
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Set the randomized fraction of each delay
+    pub fn with_jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Delay after the given attempt (1-based), without jitter
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let step = attempt.saturating_sub(1).min(63);
+        let delay_ms = match self.backoff {
+            BackoffStrategy::Fixed { delay_ms } => delay_ms,
+            BackoffStrategy::Exponential { base_ms, max_ms } => base_ms
+                .checked_mul(1 << step)
+                .map_or(max_ms, |delay| delay.min(max_ms)),
+        };
+        Duration::from_millis(delay_ms)
+    }
+
+    /// Delay before retrying after the given attempt (1-based), with up to `jitter` of it
+    /// removed at random
+    ///
+    /// Returns `None` once the attempt was the last one allowed.
+    pub fn next_delay(&self, attempt: u32) -> Option<Duration> {
+        if attempt >= self.max_attempts {
+            return None;
+        }
+        let jitter = self.jitter.clamp(0.0, 1.0) * rand::random::<f64>();
+        Some(self.backoff(attempt).mul_f64(1.0 - jitter))
+    }
+}
+
+fn duration_ms(duration: Duration) -> u64 {
+    u64::try_from(duration.as_millis()).unwrap_or(u64::MAX)
 }
 
 /// Quality of Service levels
@@ -256,7 +374,7 @@ impl UaipMessage {
                 message_id: format!("msg_{}", Uuid::new_v4().simple()),
                 correlation_id: None,
                 timestamp: Utc::now(),
-                ttl: 5000, // 5 seconds default
+                ttl: DEFAULT_TTL_MS,
                 priority: Priority::Normal,
                 sender: Entity {
                     id: sender_id,
@@ -288,6 +406,7 @@ impl UaipMessage {
                 ack_timeout: None,
                 retry_policy: None,
                 qos: QosLevel::AtMostOnce,
+                ordered: false,
                 content_type: Some("application/json".to_string()),
                 user_data: None,
             },
@@ -312,6 +431,12 @@ impl UaipMessage {
         self
     }
 
+    /// Require delivery in submission order to the recipient
+    pub fn with_ordered_delivery(mut self, ordered: bool) -> Self {
+        self.metadata.ordered = ordered;
+        self
+    }
+
     /// Set payload action
     pub fn with_action(mut self, action: Action) -> Self {
         self.payload.action = action;
@@ -403,4 +528,65 @@ mod tests {
         assert_eq!(msg.payload.action, Action::Execute);
         assert_eq!(msg.header.correlation_id, Some("corr_123".to_string()));
     }
+
+    #[test]
+    fn test_priority_from_str() {
+        assert_eq!("critical".parse::<Priority>().unwrap(), Priority::Critical);
+        assert_eq!("Low".parse::<Priority>().unwrap(), Priority::Low);
+        assert!("urgent".parse::<Priority>().is_err());
+        assert!(Priority::Critical > Priority::Low);
+
+        for priority in [
+            Priority::Low,
+            Priority::Normal,
+            Priority::High,
+            Priority::Critical,
+        ] {
+            assert_eq!(priority.as_str().parse::<Priority>().unwrap(), priority);
+        }
+    }
+
+    #[test]
+    fn test_fixed_retry_delays() {
+        let policy = RetryPolicy::fixed(4, Duration::from_millis(500));
+
+        let delays: Vec<_> = (1..=4).map(|n| policy.next_delay(n)).collect();
+        let delay = Some(Duration::from_millis(500));
+        assert_eq!(delays, vec![delay, delay, delay, None]);
+        assert_eq!(RetryPolicy::fixed(0, Duration::ZERO).next_delay(1), None);
+    }
+
+    #[test]
+    fn test_exponential_retry_delays_are_capped() {
+        let policy = RetryPolicy::exponential(6, Duration::from_secs(10), Duration::from_secs(60));
+
+        let delays: Vec<_> = (1..=6)
+            .map(|n| policy.next_delay(n).map(|d| d.as_secs()))
+            .collect();
+        assert_eq!(
+            delays,
+            vec![Some(10), Some(20), Some(40), Some(60), Some(60), None]
+        );
+        assert_eq!(policy.backoff(200), Duration::from_secs(60));
+
+        let json = serde_json::to_value(&policy).unwrap();
+        assert_eq!(json["backoff"]["type"], "exponential");
+        assert_eq!(json["backoff"]["max_ms"], 60_000);
+        assert_eq!(serde_json::from_value::<RetryPolicy>(json).unwrap(), policy);
+    }
+
+    #[test]
+    fn test_retry_jitter_stays_within_bounds() {
+        let policy = RetryPolicy::exponential(6, Duration::from_secs(10), Duration::from_secs(60))
+            .with_jitter(0.5);
+
+        for attempt in 1..=5 {
+            let full = policy.backoff(attempt);
+            for _ in 0..50 {
+                let delay = policy.next_delay(attempt).unwrap();
+                assert!(delay <= full && delay >= full / 2);
+            }
+        }
+        assert_eq!(RetryPolicy::default().with_jitter(3.0).jitter, 1.0);
+    }
 }
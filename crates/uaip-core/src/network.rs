@@ -10,7 +10,10 @@ use std::time::Duration;
 use crate::error::{Result, UaipError};
 
 /// Network configuration for services and adapters
+///
+/// Sections missing from a deserialized configuration keep their defaults.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct NetworkConfig {
     /// Service bindings
     pub services: HashMap<String, ServiceConfig>,
@@ -207,10 +210,16 @@ pub struct AdapterEndpoint {
     pub retry_delay_ms: u64,
 
     /// Custom parameters
+    #[serde(default)]
     pub parameters: HashMap<String, String>,
 }
 
 impl AdapterEndpoint {
+    /// Get connection timeout duration
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms)
+    }
+
     /// Create a Modbus TCP endpoint
     pub fn modbus_tcp(host: &str, port: u16, unit_id: u8) -> Self {
         let mut parameters = HashMap::new();
@@ -368,6 +377,54 @@ impl Default for ConnectionPoolConfig {
     }
 }
 
+impl ConnectionPoolConfig {
+    /// Check the pool settings are usable
+    pub fn validate(&self) -> Result<()> {
+        if self.max_connections_per_endpoint == 0 {
+            return Err(UaipError::InvalidConfiguration(
+                "max_connections_per_endpoint must be at least 1".to_string(),
+            ));
+        }
+        if self.max_idle_connections > self.max_connections_per_endpoint {
+            return Err(UaipError::InvalidConfiguration(format!(
+                "max_idle_connections ({}) exceeds max_connections_per_endpoint ({})",
+                self.max_idle_connections, self.max_connections_per_endpoint
+            )));
+        }
+        if self.max_lifetime_secs == 0 {
+            return Err(UaipError::InvalidConfiguration(
+                "max_lifetime_secs must be at least 1".to_string(),
+            ));
+        }
+        if self.idle_timeout_secs > self.max_lifetime_secs {
+            return Err(UaipError::InvalidConfiguration(format!(
+                "idle_timeout_secs ({}) exceeds max_lifetime_secs ({})",
+                self.idle_timeout_secs, self.max_lifetime_secs
+            )));
+        }
+        Ok(())
+    }
+
+    /// Maximum open connections (one when pooling is disabled)
+    pub fn max_connections(&self) -> usize {
+        if self.enabled {
+            self.max_connections_per_endpoint
+        } else {
+            1
+        }
+    }
+
+    /// Idle timeout, or `None` if idle connections are kept indefinitely
+    pub fn idle_timeout(&self) -> Option<Duration> {
+        (self.idle_timeout_secs > 0).then(|| Duration::from_secs(self.idle_timeout_secs))
+    }
+
+    /// Maximum connection lifetime
+    pub fn max_lifetime(&self) -> Duration {
+        Duration::from_secs(self.max_lifetime_secs)
+    }
+}
+
 /// Network endpoint information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EndpointInfo {
@@ -478,6 +535,27 @@ mod tests {
         assert_eq!(endpoint.protocol, Protocol::Https);
     }
 
+    #[test]
+    fn test_partial_network_config_deserialization() {
+        let config: NetworkConfig = serde_json::from_str(
+            r#"{"adapters": {"plc-1": {
+                "adapter_type": "modbus",
+                "connection_string": "10.0.5.7:502",
+                "protocol": "modbustcp",
+                "timeout_ms": 2000,
+                "max_retries": 3,
+                "retry_delay_ms": 1000
+            }}}"#,
+        )
+        .unwrap();
+
+        let endpoint = config.get_adapter("plc-1").unwrap();
+        assert_eq!(endpoint.protocol, Protocol::ModbusTcp);
+        assert_eq!(endpoint.timeout(), Duration::from_secs(2));
+        assert!(endpoint.parameters.is_empty());
+        assert!(config.services.contains_key("hub"));
+    }
+
     #[test]
     fn test_auth_config_serialization() {
         let auth = AuthConfig::UsernamePassword {
@@ -496,6 +574,46 @@ mod tests {
         assert_eq!(config.max_connections_per_endpoint, 10);
         assert_eq!(config.max_idle_connections, 5);
         assert!(config.enabled);
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_connection_pool_config_validation() {
+        let invalid = [
+            ConnectionPoolConfig {
+                max_connections_per_endpoint: 0,
+                max_idle_connections: 0,
+                ..Default::default()
+            },
+            ConnectionPoolConfig {
+                max_idle_connections: 20,
+                ..Default::default()
+            },
+            ConnectionPoolConfig {
+                max_lifetime_secs: 0,
+                ..Default::default()
+            },
+            ConnectionPoolConfig {
+                idle_timeout_secs: 3600,
+                ..Default::default()
+            },
+        ];
+        for config in invalid {
+            assert!(matches!(
+                config.validate(),
+                Err(UaipError::InvalidConfiguration(_))
+            ));
+        }
+
+        let config = ConnectionPoolConfig {
+            idle_timeout_secs: 0,
+            enabled: false,
+            ..Default::default()
+        };
+        assert!(config.validate().is_ok());
+        assert_eq!(config.idle_timeout(), None);
+        assert_eq!(config.max_connections(), 1);
+        assert_eq!(config.max_lifetime(), Duration::from_secs(1800));
     }
 
     #[test]
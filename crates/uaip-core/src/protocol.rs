@@ -1,5 +1,9 @@
 //! Protocol constants and version information
 
+use crate::error::{Result, UaipError};
+use crate::message::UaipMessage;
+use crate::version::Version;
+
 /// UAIP Protocol version
 pub const PROTOCOL_VERSION: &str = "1.0";
 
@@ -23,3 +27,135 @@ pub const PRIORITY_LOW: u8 = 3;
 pub const QOS_FIRE_AND_FORGET: u8 = 0;
 pub const QOS_AT_LEAST_ONCE: u8 = 1;
 pub const QOS_EXACTLY_ONCE: u8 = 2;
+
+/// Protocol versions this implementation speaks, oldest first
+pub const SUPPORTED_PROTOCOL_VERSIONS: &[&str] = &[PROTOCOL_VERSION];
+
+/// Parse a `major.minor` protocol version
+pub fn parse_protocol_version(input: &str) -> Result<Version> {
+    if input.split('.').count() != 2 {
+        return Err(UaipError::InvalidParameter(format!(
+            "Invalid protocol version '{}': expected major.minor",
+            input
+        )));
+    }
+    Version::parse(&format!("{}.0", input))
+}
+
+/// Select the highest protocol version offered by a peer that is also supported
+///
+/// Offered versions that do not parse are ignored rather than failing the handshake.
+pub fn negotiate_version(offered: &[String], supported: &[&str]) -> Result<String> {
+    let supported: Vec<Version> = supported
+        .iter()
+        .filter_map(|v| parse_protocol_version(v).ok())
+        .collect();
+
+    offered
+        .iter()
+        .filter_map(|v| parse_protocol_version(v).ok())
+        .filter(|v| supported.contains(v))
+        .max()
+        .map(|v| format!("{}.{}", v.major, v.minor))
+        .ok_or_else(|| {
+            UaipError::UnsupportedVersion(format!(
+                "no common version (offered: [{}], supported: [{}])",
+                offered.join(", "),
+                SUPPORTED_PROTOCOL_VERSIONS.join(", ")
+            ))
+        })
+}
+
+/// Check that a message received on a session can be handled at its negotiated version
+///
+/// The message must share the negotiated major version and be no newer than it.
+pub fn check_message_version(message: &UaipMessage, negotiated: &str) -> Result<()> {
+    let negotiated = parse_protocol_version(negotiated)?;
+    let version = parse_protocol_version(&message.header.version)?;
+
+    if version.major != negotiated.major || version > negotiated {
+        return Err(UaipError::UnsupportedVersion(format!(
+            "message version {} does not match negotiated version {}.{}",
+            message.header.version, negotiated.major, negotiated.minor
+        )));
+    }
+    Ok(())
+}
+
+/// Re-label a message for a peer on the negotiated version
+///
+/// Minor versions only add optional fields, so any message of the same major version can
+/// be sent to the peer; messages of another major version are rejected.
+pub fn adapt_message(mut message: UaipMessage, negotiated: &str) -> Result<UaipMessage> {
+    let target = parse_protocol_version(negotiated)?;
+    let version = parse_protocol_version(&message.header.version)?;
+
+    if version.major != target.major {
+        return Err(UaipError::UnsupportedVersion(format!(
+            "cannot convert a {} message to version {}",
+            message.header.version, negotiated
+        )));
+    }
+    message.header.version = negotiated.to_string();
+    Ok(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::EntityType;
+
+    fn offered(versions: &[&str]) -> Vec<String> {
+        versions.iter().map(|v| v.to_string()).collect()
+    }
+
+    fn message(version: &str) -> UaipMessage {
+        let mut message = UaipMessage::new(
+            "device-1".to_string(),
+            EntityType::Device,
+            "hub".to_string(),
+            EntityType::System,
+        );
+        message.header.version = version.to_string();
+        message
+    }
+
+    #[test]
+    fn test_negotiates_highest_common_version() {
+        let supported = ["1.0", "1.1", "1.2"];
+        assert_eq!(
+            negotiate_version(&offered(&["1.0", "1.1"]), &supported).unwrap(),
+            "1.1"
+        );
+        assert_eq!(
+            negotiate_version(&offered(&["2.0", "1.10", "1.2", "garbage"]), &supported).unwrap(),
+            "1.2"
+        );
+        assert_eq!(
+            negotiate_version(&offered(&[PROTOCOL_VERSION]), SUPPORTED_PROTOCOL_VERSIONS).unwrap(),
+            PROTOCOL_VERSION
+        );
+    }
+
+    #[test]
+    fn test_incompatible_versions_are_rejected() {
+        let err = negotiate_version(&offered(&["2.0", "0.9"]), &["1.0"]).unwrap_err();
+        assert!(matches!(err, UaipError::UnsupportedVersion(_)));
+        assert!(negotiate_version(&[], &["1.0"]).is_err());
+        assert!(parse_protocol_version("1.0.0").is_err());
+    }
+
+    #[test]
+    fn test_messages_checked_against_negotiated_version() {
+        assert!(check_message_version(&message("1.0"), "1.1").is_ok());
+        assert!(check_message_version(&message("1.2"), "1.1").is_err());
+        assert!(check_message_version(&message("2.0"), "2.1").is_ok());
+        assert!(check_message_version(&message("1.0"), "2.0").is_err());
+
+        assert_eq!(
+            adapt_message(message("1.2"), "1.0").unwrap().header.version,
+            "1.0"
+        );
+        assert!(adapt_message(message("2.0"), "1.0").is_err());
+    }
+}
@@ -0,0 +1,242 @@
+//! Semantic version parsing and comparison
+//!
+//! Shared by firmware checks, workflow registration, and protocol negotiation so that
+//! versions are compared numerically rather than as strings.
+
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::{Result, UaipError};
+
+/// Semantic version in `major.minor.patch[-pre]` form
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Version {
+    /// Major version
+    pub major: u64,
+    /// Minor version
+    pub minor: u64,
+    /// Patch version
+    pub patch: u64,
+    /// Dot-separated prerelease identifiers (empty for a release)
+    pub pre: Vec<String>,
+}
+
+impl Version {
+    /// Create a release version
+    pub fn new(major: u64, minor: u64, patch: u64) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+            pre: Vec::new(),
+        }
+    }
+
+    /// Parse a `major.minor.patch[-pre]` version string
+    pub fn parse(input: &str) -> Result<Self> {
+        let invalid = |reason: &str| {
+            UaipError::InvalidParameter(format!("Invalid version '{}': {}", input, reason))
+        };
+
+        let (core, pre) = match input.split_once('-') {
+            Some((core, pre)) => (core, Some(pre)),
+            None => (input, None),
+        };
+
+        let numbers = core
+            .split('.')
+            .map(|part| Self::parse_numeric(part).ok_or_else(|| invalid("expected a number")))
+            .collect::<Result<Vec<u64>>>()?;
+        let [major, minor, patch] = numbers[..] else {
+            return Err(invalid("expected major.minor.patch"));
+        };
+
+        let pre = match pre {
+            Some(pre) => pre
+                .split('.')
+                .map(|id| {
+                    let valid =
+                        !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+                    if !valid {
+                        return Err(invalid("invalid prerelease identifier"));
+                    }
+                    if id.chars().all(|c| c.is_ascii_digit()) && Self::parse_numeric(id).is_none() {
+                        return Err(invalid("numeric prerelease identifier has a leading zero"));
+                    }
+                    Ok(id.to_string())
+                })
+                .collect::<Result<Vec<String>>>()?,
+            None => Vec::new(),
+        };
+
+        Ok(Self {
+            major,
+            minor,
+            patch,
+            pre,
+        })
+    }
+
+    /// Whether this is a prerelease version
+    pub fn is_prerelease(&self) -> bool {
+        !self.pre.is_empty()
+    }
+
+    /// Parse a numeric component, rejecting leading zeros
+    fn parse_numeric(part: &str) -> Option<u64> {
+        if part.is_empty()
+            || !part.chars().all(|c| c.is_ascii_digit())
+            || (part.len() > 1 && part.starts_with('0'))
+        {
+            return None;
+        }
+        part.parse().ok()
+    }
+
+    /// Compare prerelease identifiers by semver precedence
+    fn compare_pre(a: &[String], b: &[String]) -> Ordering {
+        match (a.is_empty(), b.is_empty()) {
+            (true, true) => return Ordering::Equal,
+            // A release ranks above any of its prereleases
+            (true, false) => return Ordering::Greater,
+            (false, true) => return Ordering::Less,
+            (false, false) => {}
+        }
+
+        for (x, y) in a.iter().zip(b) {
+            let ordering = match (x.parse::<u64>(), y.parse::<u64>()) {
+                (Ok(x), Ok(y)) => x.cmp(&y),
+                // Numeric identifiers rank below alphanumeric ones
+                (Ok(_), Err(_)) => Ordering::Less,
+                (Err(_), Ok(_)) => Ordering::Greater,
+                (Err(_), Err(_)) => x.cmp(y),
+            };
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+        }
+
+        a.len().cmp(&b.len())
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.major
+            .cmp(&other.major)
+            .then(self.minor.cmp(&other.minor))
+            .then(self.patch.cmp(&other.patch))
+            .then_with(|| Self::compare_pre(&self.pre, &other.pre))
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl FromStr for Version {
+    type Err = UaipError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse(s)
+    }
+}
+
+impl TryFrom<String> for Version {
+    type Error = UaipError;
+
+    fn try_from(value: String) -> Result<Self> {
+        Self::parse(&value)
+    }
+}
+
+impl From<Version> for String {
+    fn from(version: Version) -> Self {
+        version.to_string()
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+        if !self.pre.is_empty() {
+            write!(f, "-{}", self.pre.join("."))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(s: &str) -> Version {
+        Version::parse(s).unwrap()
+    }
+
+    #[test]
+    fn test_numeric_ordering() {
+        assert!(v("1.10.0") > v("1.9.0"));
+        assert!(v("2.0.0") > v("1.99.99"));
+        assert!(v("1.0.10") > v("1.0.2"));
+        assert_eq!(v("1.2.3"), Version::new(1, 2, 3));
+        assert_eq!(v("1.2.3").to_string(), "1.2.3");
+    }
+
+    #[test]
+    fn test_prerelease_precedence() {
+        let ordered = [
+            "1.0.0-alpha",
+            "1.0.0-alpha.1",
+            "1.0.0-alpha.beta",
+            "1.0.0-beta",
+            "1.0.0-beta.2",
+            "1.0.0-beta.11",
+            "1.0.0-rc.1",
+            "1.0.0",
+        ];
+        for pair in ordered.windows(2) {
+            assert!(v(pair[0]) < v(pair[1]), "{} < {}", pair[0], pair[1]);
+        }
+        assert!(v("1.0.0-rc.1").is_prerelease());
+        assert_eq!(v("1.0.0-rc.1").to_string(), "1.0.0-rc.1");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for input in [
+            "",
+            "1",
+            "1.2",
+            "1.2.3.4",
+            "a.b.c",
+            "01.2.3",
+            "1.2.3-",
+            "1.2.3-a..b",
+        ] {
+            assert!(
+                Version::parse(input).is_err(),
+                "{:?} should not parse",
+                input
+            );
+        }
+        assert!(matches!(
+            "1.x.0".parse::<Version>(),
+            Err(UaipError::InvalidParameter(_))
+        ));
+    }
+
+    #[test]
+    fn test_serde_roundtrip() {
+        let json = serde_json::to_string(&v("2.1.0-beta.1")).unwrap();
+        assert_eq!(json, "\"2.1.0-beta.1\"");
+        let parsed: Version = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, v("2.1.0-beta.1"));
+        assert!(serde_json::from_str::<Version>("\"1.2\"").is_err());
+    }
+}
@@ -0,0 +1,75 @@
+//! Compiles the gRPC service definitions and records build metadata
+//!
+//! `protox` parses the protos in Rust, so building does not need `protoc` installed.
+
+use std::path::Path;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("cargo:rerun-if-changed=proto");
+
+    let descriptors = protox::compile(["hub.proto"], ["proto"])?;
+    tonic_build::configure().compile_fds(descriptors)?;
+
+    emit_build_info();
+    Ok(())
+}
+
+/// Expose the git revision, build time and enabled features to `build_info`
+fn emit_build_info() {
+    println!("cargo:rerun-if-env-changed=UAIP_GIT_SHA");
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+
+    // Builds outside a checkout (e.g. container images) can pass the revision in
+    let git_sha = std::env::var("UAIP_GIT_SHA")
+        .ok()
+        .filter(|sha| !sha.is_empty())
+        .or_else(|| git(&["rev-parse", "--short=12", "HEAD"]))
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=UAIP_GIT_SHA={}", git_sha);
+
+    // Rebuild when HEAD moves; missing paths would force a rebuild every time
+    if let Some(git_dir) = git(&["rev-parse", "--absolute-git-dir"]) {
+        let head = Path::new(&git_dir).join("HEAD");
+        if head.exists() {
+            println!("cargo:rerun-if-changed={}", head.display());
+        }
+        if let Some(reference) = git(&["symbolic-ref", "-q", "HEAD"]) {
+            let reference = Path::new(&git_dir).join(reference);
+            if reference.exists() {
+                println!("cargo:rerun-if-changed={}", reference.display());
+            }
+        }
+    }
+
+    // SOURCE_DATE_EPOCH keeps reproducible builds reproducible
+    let build_timestamp = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.parse::<u64>().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or_default()
+        });
+    println!("cargo:rustc-env=UAIP_BUILD_TIMESTAMP={}", build_timestamp);
+
+    let mut features: Vec<String> = std::env::vars()
+        .filter_map(|(key, _)| key.strip_prefix("CARGO_FEATURE_").map(str::to_string))
+        .map(|feature| feature.to_lowercase().replace('_', "-"))
+        .collect();
+    features.sort();
+    println!("cargo:rustc-env=UAIP_FEATURES={}", features.join(","));
+}
+
+/// Run a git command, returning its trimmed output on success
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let output = String::from_utf8(output.stdout).ok()?;
+    let output = output.trim();
+    (!output.is_empty()).then(|| output.to_string())
+}
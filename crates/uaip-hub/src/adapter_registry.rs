@@ -0,0 +1,102 @@
+//! Protocol Adapter Registry
+//!
+//! Named protocol adapter instances configured on the hub. The registry is built at
+//! startup and shared by the adapter endpoints and adapter health checks.
+
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use uaip_adapters::adapter::{ProtocolAdapter, SharedAdapter};
+use uaip_core::error::{Result, UaipError};
+
+/// An adapter instance registered under a name
+#[derive(Clone)]
+pub struct RegisteredAdapter {
+    pub name: String,
+    pub adapter_type: &'static str,
+    pub adapter: SharedAdapter,
+}
+
+/// Configured adapter instances, in registration order
+#[derive(Clone, Default)]
+pub struct AdapterRegistry {
+    adapters: Vec<RegisteredAdapter>,
+}
+
+impl AdapterRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register an adapter under a unique name
+    pub fn register(
+        &mut self,
+        name: impl Into<String>,
+        adapter: impl ProtocolAdapter + 'static,
+    ) -> Result<SharedAdapter> {
+        let name = name.into();
+        if self.get(&name).is_some() {
+            return Err(UaipError::InvalidConfiguration(format!(
+                "Adapter {} is already registered",
+                name
+            )));
+        }
+
+        let adapter_type = adapter.adapter_type();
+        let adapter: SharedAdapter = Arc::new(Mutex::new(adapter));
+        self.adapters.push(RegisteredAdapter {
+            name,
+            adapter_type,
+            adapter: adapter.clone(),
+        });
+        Ok(adapter)
+    }
+
+    /// Look up an adapter by name
+    pub fn get(&self, name: &str) -> Option<SharedAdapter> {
+        self.adapters
+            .iter()
+            .find(|a| a.name == name)
+            .map(|a| a.adapter.clone())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &RegisteredAdapter> {
+        self.adapters.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.adapters.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.adapters.is_empty()
+    }
+}
+
+#[cfg(all(test, feature = "http"))]
+mod tests {
+    use super::*;
+    use uaip_adapters::http::{HttpAdapter, HttpConfig};
+
+    fn http_adapter() -> HttpAdapter {
+        HttpAdapter::new(HttpConfig {
+            base_url: "http://127.0.0.1:1".to_string(),
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_register_rejects_duplicate_names() {
+        let mut registry = AdapterRegistry::new();
+        registry.register("gateway", http_adapter()).unwrap();
+
+        let duplicate = registry.register("gateway", http_adapter());
+        assert!(matches!(duplicate, Err(UaipError::InvalidConfiguration(_))));
+
+        assert_eq!(registry.len(), 1);
+        assert_eq!(registry.iter().next().unwrap().adapter_type, "http");
+        assert!(registry.get("gateway").is_some());
+        assert!(registry.get("plc-1").is_none());
+    }
+}
@@ -0,0 +1,533 @@
+//! Adapter Probe Targets
+//!
+//! The adapter test and read endpoints make the hub connect to addresses chosen by the
+//! caller. Before any connection is attempted the caller must hold the `admin` scope,
+//! stay within a per-caller probe rate, and name a target the [`TargetPolicy`] permits:
+//! denied hosts are always refused, an allowlist (when set) must match, and loopback,
+//! link-local, cloud metadata and similar addresses are refused unless allowlisted.
+//! Adapters then connect to the addresses that were checked rather than resolving the
+//! host again, and HTTP probes do not follow redirects, so neither DNS rebinding nor a
+//! redirect can move a probe to a refused address.
+
+use axum::http::{HeaderMap, Uri};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+use uaip_core::error::{Result, UaipError};
+
+use crate::handlers::auth::require_scope;
+use crate::middleware::rate_limit::{RateLimitConfig, RateLimitLayer};
+
+/// Cloud metadata endpoints outside the link-local ranges
+const METADATA_ADDRESSES: &[IpAddr] = &[
+    // Alibaba Cloud
+    IpAddr::V4(Ipv4Addr::new(100, 100, 100, 200)),
+    // AWS over IPv6
+    IpAddr::V6(Ipv6Addr::new(0xfd00, 0xec2, 0, 0, 0, 0, 0, 0x254)),
+];
+
+/// Host and port an adapter would connect to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Target {
+    pub host: String,
+    pub port: u16,
+}
+
+impl Target {
+    /// Target of a URL; the port defaults by scheme (`http`, `https`, `opc.tcp`)
+    pub fn from_url(url: &str) -> Result<Self> {
+        let invalid = || UaipError::InvalidParameter(format!("Invalid target URL: {}", url));
+        let uri: Uri = url.parse().map_err(|_| invalid())?;
+        let host = uri
+            .host()
+            .filter(|host| !host.is_empty())
+            .ok_or_else(invalid)?;
+        let port = match (uri.port_u16(), uri.scheme_str()) {
+            (Some(port), _) => port,
+            (None, Some("http")) => 80,
+            (None, Some("https")) => 443,
+            (None, Some("opc.tcp")) => 4840,
+            _ => return Err(invalid()),
+        };
+        Ok(Self {
+            host: host.trim_matches(['[', ']']).to_ascii_lowercase(),
+            port,
+        })
+    }
+
+    /// Target of a `host:port` address
+    pub fn from_address(address: &str) -> Result<Self> {
+        let invalid =
+            || UaipError::InvalidParameter(format!("Invalid target address: {}", address));
+        let (host, port) = split_host_port(address).ok_or_else(invalid)?;
+        match port {
+            Some(port) if !host.is_empty() => Ok(Self {
+                host: host.to_ascii_lowercase(),
+                port,
+            }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl std::fmt::Display for Target {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.host.contains(':') {
+            write!(f, "[{}]:{}", self.host, self.port)
+        } else {
+            write!(f, "{}:{}", self.host, self.port)
+        }
+    }
+}
+
+/// Split `host`, `host:port`, `[v6]` or `[v6]:port`; a bare IPv6 address has no port
+fn split_host_port(value: &str) -> Option<(&str, Option<u16>)> {
+    if let Some(rest) = value.strip_prefix('[') {
+        let (host, rest) = rest.split_once(']')?;
+        let port = match rest.strip_prefix(':') {
+            Some(port) => Some(port.parse().ok()?),
+            None if rest.is_empty() => None,
+            None => return None,
+        };
+        return Some((host, port));
+    }
+    if value.parse::<Ipv6Addr>().is_ok() {
+        return Some((value, None));
+    }
+    match value.rsplit_once(':') {
+        Some((host, port)) => Some((host, Some(port.parse().ok()?))),
+        None => Some((value, None)),
+    }
+}
+
+/// Hosts a rule covers
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum HostPattern {
+    /// Host name, matched case-insensitively against the target as given
+    Name(String),
+    /// Address range in CIDR form; a single address has the full prefix
+    Network(IpAddr, u8),
+}
+
+/// An allowlist or denylist entry: a host name, address or CIDR range, optionally with a port
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TargetRule {
+    host: HostPattern,
+    port: Option<u16>,
+}
+
+impl TargetRule {
+    /// Parse `plc.local`, `plc.local:4840`, `10.0.5.7:502`, `10.0.5.0/24` or `[fd00::1]:4840`
+    pub fn parse(rule: &str) -> Result<Self> {
+        let invalid = || UaipError::InvalidConfiguration(format!("Invalid target rule: {}", rule));
+
+        if let Some((address, prefix)) = rule.split_once('/') {
+            let address: IpAddr = address.parse().map_err(|_| invalid())?;
+            let prefix: u8 = prefix.parse().map_err(|_| invalid())?;
+            if prefix > max_prefix(&address) {
+                return Err(invalid());
+            }
+            return Ok(Self {
+                host: HostPattern::Network(address, prefix),
+                port: None,
+            });
+        }
+
+        let (host, port) = split_host_port(rule).ok_or_else(invalid)?;
+        let host = match host.parse::<IpAddr>() {
+            Ok(address) => HostPattern::Network(address, max_prefix(&address)),
+            Err(_) if !host.is_empty() => HostPattern::Name(host.to_ascii_lowercase()),
+            Err(_) => return Err(invalid()),
+        };
+        Ok(Self { host, port })
+    }
+
+    fn covers_port(&self, port: u16) -> bool {
+        self.port.is_none_or(|p| p == port)
+    }
+
+    fn covers_address(&self, address: IpAddr) -> bool {
+        match self.host {
+            HostPattern::Network(network, prefix) => in_network(address, network, prefix),
+            HostPattern::Name(_) => false,
+        }
+    }
+
+    fn covers_name(&self, host: &str) -> bool {
+        matches!(&self.host, HostPattern::Name(name) if name == host)
+    }
+}
+
+fn max_prefix(address: &IpAddr) -> u8 {
+    match address {
+        IpAddr::V4(_) => 32,
+        IpAddr::V6(_) => 128,
+    }
+}
+
+fn in_network(address: IpAddr, network: IpAddr, prefix: u8) -> bool {
+    match (address.to_canonical(), network.to_canonical()) {
+        (IpAddr::V4(address), IpAddr::V4(network)) => {
+            let mask = u32::MAX.checked_shl(32 - u32::from(prefix)).unwrap_or(0);
+            u32::from(address) & mask == u32::from(network) & mask
+        }
+        (IpAddr::V6(address), IpAddr::V6(network)) => {
+            let mask = u128::MAX.checked_shl(128 - u32::from(prefix)).unwrap_or(0);
+            u128::from(address) & mask == u128::from(network) & mask
+        }
+        _ => false,
+    }
+}
+
+/// Whether an address only makes sense from inside the hub's host or cloud
+pub fn is_internal(address: IpAddr) -> bool {
+    if METADATA_ADDRESSES.contains(&address) {
+        return true;
+    }
+    match address.to_canonical() {
+        IpAddr::V4(v4) => {
+            v4.is_loopback()
+                || v4.is_link_local()
+                || v4.is_unspecified()
+                || v4.is_broadcast()
+                || v4.is_multicast()
+        }
+        IpAddr::V6(v6) => {
+            v6.is_loopback()
+                || v6.is_unicast_link_local()
+                || v6.is_unspecified()
+                || v6.is_multicast()
+        }
+    }
+}
+
+/// Which adapter targets may be probed
+#[derive(Debug, Clone)]
+pub struct TargetPolicy {
+    /// When non-empty, only targets matching an entry are permitted
+    pub allow: Vec<TargetRule>,
+    /// Targets that are always refused
+    pub deny: Vec<TargetRule>,
+    /// Refuse internal addresses (see [`is_internal`]) that are not allowlisted
+    pub block_internal: bool,
+}
+
+impl Default for TargetPolicy {
+    fn default() -> Self {
+        Self {
+            allow: Vec::new(),
+            deny: Vec::new(),
+            block_internal: true,
+        }
+    }
+}
+
+impl TargetPolicy {
+    /// Load the policy from the environment
+    ///
+    /// Reads `ADAPTER_TARGET_ALLOW` and `ADAPTER_TARGET_DENY` (comma separated rules)
+    /// and `ADAPTER_TARGET_BLOCK_INTERNAL`.
+    pub fn from_env() -> Result<Self> {
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    /// Load the policy from a variable lookup
+    pub fn from_vars(lookup: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let rules = |name: &str| -> Result<Vec<TargetRule>> {
+            lookup(name)
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|rule| !rule.is_empty())
+                .map(TargetRule::parse)
+                .collect()
+        };
+        let block_internal = match lookup("ADAPTER_TARGET_BLOCK_INTERNAL") {
+            Some(value) => value.trim().parse().map_err(|_| {
+                UaipError::InvalidConfiguration(format!(
+                    "Invalid ADAPTER_TARGET_BLOCK_INTERNAL: {}",
+                    value
+                ))
+            })?,
+            None => true,
+        };
+
+        Ok(Self {
+            allow: rules("ADAPTER_TARGET_ALLOW")?,
+            deny: rules("ADAPTER_TARGET_DENY")?,
+            block_internal,
+        })
+    }
+
+    /// Check a target, resolving host names first; returns the addresses that passed
+    pub async fn check(&self, target: &Target) -> Result<Vec<SocketAddr>> {
+        let addresses: Vec<IpAddr> = match target.host.parse::<IpAddr>() {
+            Ok(address) => vec![address],
+            Err(_) => tokio::net::lookup_host((target.host.as_str(), target.port))
+                .await
+                .map_err(|e| {
+                    UaipError::InvalidParameter(format!(
+                        "Cannot resolve adapter target {}: {}",
+                        target, e
+                    ))
+                })?
+                .map(|address| address.ip())
+                .collect(),
+        };
+        self.check_resolved(target, &addresses)?;
+        Ok(addresses
+            .into_iter()
+            .map(|address| SocketAddr::new(address, target.port))
+            .collect())
+    }
+
+    /// Check a target whose host resolved to `addresses`
+    ///
+    /// A rule matches a host name by name, or an address range when it holds the
+    /// resolved addresses: any of them for the denylist, all of them for the allowlist.
+    pub fn check_resolved(&self, target: &Target, addresses: &[IpAddr]) -> Result<()> {
+        let refused = |reason: String| {
+            Err(UaipError::NotPermitted(format!(
+                "Adapter target {} is not allowed: {}",
+                target, reason
+            )))
+        };
+
+        let denied = self.deny.iter().any(|rule| {
+            rule.covers_port(target.port)
+                && (rule.covers_name(&target.host)
+                    || addresses.iter().any(|a| rule.covers_address(*a)))
+        });
+        if denied {
+            return refused("denied by ADAPTER_TARGET_DENY".to_string());
+        }
+
+        let allowed = self.allow.iter().any(|rule| {
+            rule.covers_port(target.port)
+                && (rule.covers_name(&target.host)
+                    || (!addresses.is_empty() && addresses.iter().all(|a| rule.covers_address(*a))))
+        });
+        if allowed {
+            return Ok(());
+        }
+        if !self.allow.is_empty() {
+            return refused("not in ADAPTER_TARGET_ALLOW".to_string());
+        }
+
+        if self.block_internal {
+            if let Some(address) = addresses.iter().find(|a| is_internal(**a)) {
+                return refused(format!("{} is an internal address", address));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Probe rate per caller: 10 a minute, bursts of 5
+pub fn default_probe_rate() -> RateLimitConfig {
+    RateLimitConfig {
+        max_requests: 10,
+        window_duration: Duration::from_secs(60),
+        burst_size: 5,
+    }
+}
+
+/// Gate in front of every adapter endpoint that connects to a caller-chosen target
+#[derive(Clone)]
+pub struct AdapterProbeGuard {
+    policy: Arc<TargetPolicy>,
+    limiter: RateLimitLayer,
+}
+
+impl AdapterProbeGuard {
+    pub fn new(policy: TargetPolicy, rate: RateLimitConfig) -> Self {
+        Self {
+            policy: Arc::new(policy),
+            limiter: RateLimitLayer::new(rate),
+        }
+    }
+
+    /// Require the `admin` scope, charge the caller's probe budget and check the target
+    ///
+    /// Returns the checked addresses, which the probe must connect to.
+    pub async fn authorize(&self, headers: &HeaderMap, target: &Target) -> Result<Vec<SocketAddr>> {
+        let claims = require_scope(headers, "admin")?;
+        if !self.limiter.check_rate_limit(&claims.sub).await {
+            tracing::warn!(subject = %claims.sub, "Adapter probe rate limit exceeded");
+            return Err(UaipError::RateLimitExceeded);
+        }
+
+        self.policy.check(target).await.inspect_err(|e| {
+            tracing::warn!(subject = %claims.sub, target = %target, "Adapter probe refused: {}", e);
+        })
+    }
+
+    /// Drop idle rate limit buckets
+    pub async fn cleanup(&self) {
+        self.limiter.cleanup_old_buckets().await;
+    }
+}
+
+impl Default for AdapterProbeGuard {
+    fn default() -> Self {
+        Self::new(TargetPolicy::default(), default_probe_rate())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::auth::jwt_manager;
+
+    fn bearer(subject: &str, scopes: &[&str]) -> HeaderMap {
+        let token = jwt_manager(3600)
+            .generate_token(
+                subject,
+                "admin@example.com",
+                scopes.iter().map(|s| s.to_string()).collect(),
+                None,
+            )
+            .unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "Authorization",
+            format!("Bearer {}", token).parse().unwrap(),
+        );
+        headers
+    }
+
+    fn policy(allow: &str, deny: &str) -> TargetPolicy {
+        TargetPolicy::from_vars(|name| match name {
+            "ADAPTER_TARGET_ALLOW" => Some(allow.to_string()),
+            "ADAPTER_TARGET_DENY" => Some(deny.to_string()),
+            _ => None,
+        })
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_internal_and_metadata_targets_rejected() {
+        let policy = TargetPolicy::default();
+        for url in [
+            "http://169.254.169.254/latest/meta-data/",
+            "http://127.0.0.1:8080",
+            "http://[::1]/",
+            "http://[::ffff:127.0.0.1]/",
+            "http://[fe80::1]/",
+            "http://100.100.100.200/",
+            "opc.tcp://0.0.0.0:4840",
+        ] {
+            let err = policy
+                .check(&Target::from_url(url).unwrap())
+                .await
+                .unwrap_err();
+            assert!(matches!(err, UaipError::NotPermitted(_)), "{}", url);
+        }
+        let err = policy
+            .check(&Target::from_address("localhost:502").unwrap())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("internal address"));
+
+        // Ordinary LAN and public addresses are fine
+        for address in ["192.168.1.50:502", "10.0.0.7:502", "203.0.113.9:502"] {
+            policy
+                .check(&Target::from_address(address).unwrap())
+                .await
+                .unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_allowlisted_target_passes() {
+        let policy = policy(
+            "10.0.5.0/24, plc.factory.local:4840, 127.0.0.1:1502",
+            "10.0.5.66",
+        );
+
+        for address in ["10.0.5.7:502", "127.0.0.1:1502"] {
+            policy
+                .check(&Target::from_address(address).unwrap())
+                .await
+                .unwrap();
+        }
+        let plc = Target::from_url("opc.tcp://PLC.factory.local").unwrap();
+        policy
+            .check_resolved(&plc, &["10.9.9.9".parse().unwrap()])
+            .unwrap();
+
+        // Anything else is outside the allowlist, and the denylist wins over it
+        for address in ["10.0.6.7:502", "127.0.0.1:502", "10.0.5.66:502"] {
+            let err = policy
+                .check(&Target::from_address(address).unwrap())
+                .await
+                .unwrap_err();
+            assert!(matches!(err, UaipError::NotPermitted(_)), "{}", address);
+        }
+        let other_port = Target::from_url("opc.tcp://plc.factory.local:4841").unwrap();
+        assert!(policy
+            .check_resolved(&other_port, &["10.9.9.9".parse().unwrap()])
+            .is_err());
+    }
+
+    #[test]
+    fn test_target_parsing() {
+        assert_eq!(
+            Target::from_url("https://api.example.com/v1").unwrap(),
+            Target {
+                host: "api.example.com".to_string(),
+                port: 443
+            }
+        );
+        assert_eq!(
+            Target::from_address("[fd00::1]:4840").unwrap().to_string(),
+            "[fd00::1]:4840"
+        );
+        assert!(Target::from_url("ftp://example.com").is_err());
+        assert!(Target::from_address("plc.local").is_err());
+
+        assert!(TargetRule::parse("10.0.0.0/33").is_err());
+        assert!(TargetRule::parse("plc:port").is_err());
+        assert!(TargetPolicy::from_vars(|name| {
+            (name == "ADAPTER_TARGET_BLOCK_INTERNAL").then(|| "maybe".to_string())
+        })
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_guard_requires_admin_and_limits_rate() {
+        let guard = AdapterProbeGuard::new(
+            TargetPolicy::default(),
+            RateLimitConfig {
+                max_requests: 1,
+                window_duration: Duration::from_secs(3600),
+                burst_size: 2,
+            },
+        );
+        let target = Target::from_address("192.168.1.50:502").unwrap();
+
+        let err = guard
+            .authorize(&HeaderMap::new(), &target)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, UaipError::AuthenticationFailed(_)));
+        let err = guard
+            .authorize(&bearer("viewer", &["read"]), &target)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, UaipError::AuthorizationFailed(_)));
+
+        let admin = bearer("admin-1", &["admin"]);
+        guard.authorize(&admin, &target).await.unwrap();
+        guard.authorize(&admin, &target).await.unwrap();
+        let err = guard.authorize(&admin, &target).await.unwrap_err();
+        assert!(matches!(err, UaipError::RateLimitExceeded));
+
+        // Budgets are per caller
+        guard
+            .authorize(&bearer("admin-2", &["admin"]), &target)
+            .await
+            .unwrap();
+    }
+}
@@ -1,11 +1,13 @@
 //! AI Session Management
 //!
 //! Manages AI agent sessions, tracks active connections, and handles session lifecycle.
+//! Agents and sessions can optionally be persisted to a [`SessionStore`] and rehydrated on
+//! startup.
 
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 use uuid::Uuid;
 
 use uaip_core::{
@@ -14,6 +16,8 @@ use uaip_core::{
     error::{Result, UaipError},
 };
 
+use crate::session_store::{ContextLimits, SessionStore, TruncationPolicy};
+
 /// AI Session Manager
 pub struct AiSessionManager {
     /// Active sessions indexed by session ID
@@ -27,6 +31,12 @@ pub struct AiSessionManager {
 
     /// Maximum sessions per agent
     max_sessions_per_agent: usize,
+
+    /// Optional persistent session store
+    store: Option<SessionStore>,
+
+    /// Limits applied to session context
+    context_limits: ContextLimits,
 }
 
 impl AiSessionManager {
@@ -37,9 +47,70 @@ impl AiSessionManager {
             agents: Arc::new(RwLock::new(HashMap::new())),
             session_timeout_secs,
             max_sessions_per_agent,
+            store: None,
+            context_limits: ContextLimits::default(),
         }
     }
 
+    /// Persist sessions to the given store
+    pub fn with_store(mut self, store: SessionStore) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    /// Set the context size limits
+    pub fn with_context_limits(mut self, limits: ContextLimits) -> Self {
+        self.context_limits = limits;
+        self
+    }
+
+    /// Rehydrate agents and their non-terminated sessions from the store
+    ///
+    /// Agents are loaded first; sessions whose agent is no longer stored are skipped.
+    /// Returns the number of sessions restored.
+    pub async fn restore_sessions(&self) -> Result<usize> {
+        let store = match &self.store {
+            Some(store) => store,
+            None => return Ok(0),
+        };
+
+        let restored_agents = store.load_agents().await?;
+        let mut agents = self.agents.write().await;
+        for agent in restored_agents {
+            agents.insert(agent.id, agent);
+        }
+
+        let restored = store.load_active().await?;
+        let mut sessions = self.sessions.write().await;
+        let mut count = 0;
+        for session in restored {
+            if !agents.contains_key(&session.agent_id) {
+                warn!(
+                    "Skipping session {}: agent {} is not registered",
+                    session.id, session.agent_id
+                );
+                continue;
+            }
+            sessions.insert(session.id, session);
+            count += 1;
+        }
+
+        info!(
+            "Restored {} AI agents and {} AI sessions from store",
+            agents.len(),
+            count
+        );
+        Ok(count)
+    }
+
+    /// Write a session snapshot to the store, if one is configured
+    async fn persist(&self, session: &AiSession) -> Result<()> {
+        if let Some(store) = &self.store {
+            store.save(session).await?;
+        }
+        Ok(())
+    }
+
     /// Register a new AI agent
     pub async fn register_agent(&self, agent: AiAgent) -> Result<Uuid> {
         let agent_id = agent.id;
@@ -57,6 +128,10 @@ impl AiSessionManager {
             agent.name, agent.agent_type
         );
 
+        if let Some(store) = &self.store {
+            store.save_agent(&agent).await?;
+        }
+
         agents.insert(agent_id, agent);
         Ok(agent_id)
     }
@@ -120,6 +195,8 @@ impl AiSessionManager {
         let session = AiSession::new(agent_id);
         let session_id = session.id;
 
+        self.persist(&session).await?;
+
         let mut sessions = self.sessions.write().await;
         sessions.insert(session_id, session);
 
@@ -154,7 +231,9 @@ impl AiSessionManager {
         session.add_device(device_id.clone());
         debug!("Added device {} to session {}", device_id, session_id);
 
-        Ok(())
+        let snapshot = session.clone();
+        drop(sessions);
+        self.persist(&snapshot).await
     }
 
     /// Remove device from session
@@ -172,7 +251,9 @@ impl AiSessionManager {
         session.remove_device(device_id);
         debug!("Removed device {} from session {}", device_id, session_id);
 
-        Ok(())
+        let snapshot = session.clone();
+        drop(sessions);
+        self.persist(&snapshot).await
     }
 
     /// Update session activity timestamp
@@ -187,6 +268,61 @@ impl AiSessionManager {
         Ok(())
     }
 
+    /// Set a context entry on a session, enforcing the context size limit
+    ///
+    /// Returns the keys dropped to fit the cap (empty if nothing was truncated).
+    pub async fn set_session_context(
+        &self,
+        session_id: &Uuid,
+        key: String,
+        value: serde_json::Value,
+    ) -> Result<Vec<String>> {
+        let mut sessions = self.sessions.write().await;
+
+        let session = sessions.get_mut(session_id).ok_or_else(|| {
+            UaipError::InvalidParameter(format!("Session {} not found", session_id))
+        })?;
+
+        let max_bytes = self.context_limits.max_context_bytes;
+        let mut updated = session.clone();
+        updated.set_context(key.clone(), value);
+
+        let removed = if updated.context_size_bytes() > max_bytes {
+            match self.context_limits.truncation_policy {
+                TruncationPolicy::Reject => {
+                    return Err(UaipError::InvalidParameter(format!(
+                        "Context for session {} would exceed {} bytes",
+                        session_id, max_bytes
+                    )));
+                }
+                TruncationPolicy::DropOldest => updated.truncate_context(max_bytes),
+            }
+        } else {
+            Vec::new()
+        };
+
+        if removed.contains(&key) {
+            return Err(UaipError::InvalidParameter(format!(
+                "Context entry '{}' exceeds the {} byte limit",
+                key, max_bytes
+            )));
+        }
+
+        if !removed.is_empty() {
+            warn!(
+                "Truncated {} context entries from session {}",
+                removed.len(),
+                session_id
+            );
+        }
+
+        *session = updated.clone();
+        drop(sessions);
+        self.persist(&updated).await?;
+
+        Ok(removed)
+    }
+
     /// Terminate a session
     pub async fn terminate_session(&self, session_id: &Uuid) -> Result<()> {
         let mut sessions = self.sessions.write().await;
@@ -198,20 +334,28 @@ impl AiSessionManager {
         session.state = SessionState::Terminated;
         info!("Terminated session {}", session_id);
 
-        Ok(())
+        let snapshot = session.clone();
+        drop(sessions);
+        self.persist(&snapshot).await
     }
 
     /// Terminate all sessions for an agent
     async fn terminate_agent_sessions(&self, agent_id: &Uuid) -> Result<()> {
         let mut sessions = self.sessions.write().await;
 
-        let mut terminated_count = 0;
+        let mut terminated = Vec::new();
         for session in sessions.values_mut() {
             if session.agent_id == *agent_id && session.state == SessionState::Active {
                 session.state = SessionState::Terminated;
-                terminated_count += 1;
+                terminated.push(session.clone());
             }
         }
+        drop(sessions);
+
+        let terminated_count = terminated.len();
+        for session in &terminated {
+            self.persist(session).await?;
+        }
 
         info!(
             "Terminated {} sessions for agent {}",
@@ -235,6 +379,15 @@ impl AiSessionManager {
             sessions.remove(id);
             debug!("Removed expired session {}", id);
         }
+        drop(sessions);
+
+        if let Some(store) = &self.store {
+            for id in &expired_ids {
+                if let Err(e) = store.delete(id).await {
+                    warn!("Failed to delete expired session {} from store: {}", id, e);
+                }
+            }
+        }
 
         let count = expired_ids.len();
         if count > 0 {
@@ -406,4 +559,121 @@ mod tests {
         let result = manager.create_session(agent_id).await;
         assert!(result.is_err());
     }
+
+    #[tokio::test]
+    async fn test_session_save_and_restore() {
+        let store = SessionStore::memory();
+        let manager = AiSessionManager::default().with_store(store.clone());
+        let agent = AiAgent::new("TestAgent".to_string(), AgentType::Conversational);
+        let agent_id = agent.id;
+
+        manager.register_agent(agent).await.unwrap();
+        let session_id = manager.create_session(agent_id).await.unwrap();
+        manager
+            .add_device_to_session(&session_id, "device-123".to_string())
+            .await
+            .unwrap();
+        manager
+            .set_session_context(
+                &session_id,
+                "task".to_string(),
+                serde_json::json!({"step": 2, "goal": "calibrate"}),
+            )
+            .await
+            .unwrap();
+
+        // Simulate a hub restart with a fresh manager over the same store
+        let restarted = AiSessionManager::default().with_store(store);
+        assert_eq!(restarted.restore_sessions().await.unwrap(), 1);
+        assert_eq!(
+            restarted.get_agent(&agent_id).await.unwrap().name,
+            "TestAgent"
+        );
+
+        let session = restarted.get_session(&session_id).await.unwrap();
+        assert_eq!(session.agent_id, agent_id);
+        assert_eq!(session.devices, vec!["device-123".to_string()]);
+        assert_eq!(
+            session.context.get("task"),
+            Some(&serde_json::json!({"step": 2, "goal": "calibrate"}))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sessions_without_agent_not_restored() {
+        let store = SessionStore::memory();
+        store.save(&AiSession::new(Uuid::new_v4())).await.unwrap();
+
+        let restarted = AiSessionManager::default().with_store(store);
+        assert_eq!(restarted.restore_sessions().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_terminated_sessions_not_restored() {
+        let store = SessionStore::memory();
+        let manager = AiSessionManager::default().with_store(store.clone());
+        let agent = AiAgent::new("TestAgent".to_string(), AgentType::Control);
+        let agent_id = agent.id;
+
+        manager.register_agent(agent).await.unwrap();
+        let session_id = manager.create_session(agent_id).await.unwrap();
+        manager.terminate_session(&session_id).await.unwrap();
+
+        let restarted = AiSessionManager::default().with_store(store);
+        assert_eq!(restarted.restore_sessions().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_context_truncation_at_cap() {
+        let manager = AiSessionManager::default().with_context_limits(ContextLimits {
+            max_context_bytes: 100,
+            truncation_policy: TruncationPolicy::DropOldest,
+        });
+        let agent = AiAgent::new("TestAgent".to_string(), AgentType::Conversational);
+        let agent_id = agent.id;
+
+        manager.register_agent(agent).await.unwrap();
+        let session_id = manager.create_session(agent_id).await.unwrap();
+
+        for key in ["a", "b", "c"] {
+            manager
+                .set_session_context(
+                    &session_id,
+                    key.to_string(),
+                    serde_json::json!("x".repeat(30)),
+                )
+                .await
+                .unwrap();
+        }
+
+        let session = manager.get_session(&session_id).await.unwrap();
+        assert!(session.context_size_bytes() <= 100);
+        assert!(!session.context.contains_key("a"));
+        assert!(session.context.contains_key("c"));
+    }
+
+    #[tokio::test]
+    async fn test_context_reject_policy() {
+        let manager = AiSessionManager::default().with_context_limits(ContextLimits {
+            max_context_bytes: 50,
+            truncation_policy: TruncationPolicy::Reject,
+        });
+        let agent = AiAgent::new("TestAgent".to_string(), AgentType::Conversational);
+        let agent_id = agent.id;
+
+        manager.register_agent(agent).await.unwrap();
+        let session_id = manager.create_session(agent_id).await.unwrap();
+
+        let result = manager
+            .set_session_context(
+                &session_id,
+                "big".to_string(),
+                serde_json::json!("x".repeat(100)),
+            )
+            .await;
+        assert!(result.is_err());
+
+        let session = manager.get_session(&session_id).await.unwrap();
+        assert!(session.context.is_empty());
+    }
 }
@@ -1,4 +1,5 @@
-//! API module for REST and WebSocket endpoints
+//! API module for REST, WebSocket, and gRPC endpoints
 
+pub mod grpc;
 pub mod rest;
 pub mod websocket;
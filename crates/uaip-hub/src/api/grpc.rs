@@ -0,0 +1,369 @@
+//! gRPC API
+//!
+//! Exposes device registration, command dispatch, and a live telemetry stream for agents
+//! and internal services. Shares `AppState` and the service functions behind the REST
+//! handlers, so both APIs apply the same validation.
+
+use std::collections::HashSet;
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio_stream::wrappers::errors::BroadcastStreamRecvError;
+use tokio_stream::wrappers::{BroadcastStream, TcpListenerStream};
+use tokio_stream::{Stream, StreamExt};
+use tonic::{Code, Request, Response, Status};
+
+use uaip_core::error::{ErrorCode, ErrorResponse, UaipError};
+
+use crate::api::rest::{
+    AppState, CapabilityDeclaration, CommandRequest, DeviceRegistrationRequest,
+};
+use crate::handlers::auth::{bearer_claims, require_claims};
+use crate::handlers::telemetry::TelemetryEvent;
+use crate::services::devices::{register_device, send_command};
+use crate::tenant::Tenant;
+
+/// Generated protobuf messages and service stubs (`proto/hub.proto`)
+pub mod proto {
+    tonic::include_proto!("uaip.hub.v1");
+}
+
+use proto::hub_service_server::{HubService, HubServiceServer};
+
+/// Stream of telemetry events sent to a subscriber
+pub type TelemetryStream =
+    Pin<Box<dyn Stream<Item = Result<proto::TelemetryEvent, Status>> + Send>>;
+
+/// gRPC service backed by the hub's shared state
+pub struct HubGrpcService {
+    state: Arc<AppState>,
+}
+
+impl HubGrpcService {
+    pub fn new(state: Arc<AppState>) -> Self {
+        Self { state }
+    }
+}
+
+/// Serve the gRPC API on an already bound listener
+pub async fn serve(
+    state: Arc<AppState>,
+    listener: TcpListener,
+) -> Result<(), tonic::transport::Error> {
+    tonic::transport::Server::builder()
+        .add_service(HubServiceServer::new(HubGrpcService::new(state)))
+        .serve_with_incoming(TcpListenerStream::new(listener))
+        .await
+}
+
+#[tonic::async_trait]
+impl HubService for HubGrpcService {
+    type SubscribeTelemetryStream = TelemetryStream;
+
+    async fn register_device(
+        &self,
+        request: Request<proto::RegisterDeviceRequest>,
+    ) -> Result<Response<proto::RegisterDeviceResponse>, Status> {
+        let claims = bearer_claims(&request.metadata().clone().into_headers()).map_err(status)?;
+        let tenant = claims.as_ref().map(Tenant::from_claims).unwrap_or_default();
+        let request = request.into_inner();
+        // Devices may register themselves, in which case the device is the actor
+        let actor = claims.map_or_else(|| request.device_id.clone(), |claims| claims.sub);
+        let capabilities = request
+            .capabilities
+            .into_iter()
+            .map(capability_declaration)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(status)?;
+
+        let response = register_device(
+            &self.state,
+            &tenant,
+            &actor,
+            DeviceRegistrationRequest {
+                device_id: request.device_id,
+                device_type: request.device_type,
+                name: request.name,
+                manufacturer: request.manufacturer,
+                model: request.model,
+                capabilities,
+            },
+        )
+        .await
+        .map_err(status)?;
+
+        Ok(Response::new(proto::RegisterDeviceResponse {
+            device_id: response.device_id,
+            challenge: response.challenge,
+            expires_at: response.expires_at,
+        }))
+    }
+
+    async fn send_command(
+        &self,
+        request: Request<proto::SendCommandRequest>,
+    ) -> Result<Response<proto::SendCommandResponse>, Status> {
+        let claims = require_claims(&request.metadata().clone().into_headers()).map_err(status)?;
+        let tenant = Tenant::from_claims(&claims);
+        let request = request.into_inner();
+        let parameters = if request.parameters_json.is_empty() {
+            None
+        } else {
+            Some(serde_json::from_str(&request.parameters_json).map_err(|e| {
+                Status::invalid_argument(format!("parameters_json is not valid JSON: {}", e))
+            })?)
+        };
+
+        let response = send_command(
+            &self.state,
+            &tenant,
+            &claims,
+            &request.device_id,
+            &CommandRequest {
+                action: request.action,
+                parameters,
+                priority: request.priority,
+                preempt: request.preempt,
+            },
+        )
+        .await
+        .map_err(status)?;
+
+        Ok(Response::new(proto::SendCommandResponse {
+            message_id: response.message_id,
+            status: response.status,
+            queued_at: response.queued_at,
+        }))
+    }
+
+    async fn subscribe_telemetry(
+        &self,
+        request: Request<proto::SubscribeTelemetryRequest>,
+    ) -> Result<Response<Self::SubscribeTelemetryStream>, Status> {
+        let claims = require_claims(&request.metadata().clone().into_headers()).map_err(status)?;
+        let tenant = Tenant::from_claims(&claims);
+        let device_ids: HashSet<String> = request.into_inner().device_ids.into_iter().collect();
+        let events = BroadcastStream::new(self.state.telemetry_feed.subscribe());
+
+        // Subscribers only see readings from devices of their own tenant
+        let stream = events.filter_map(move |event| match event {
+            Ok(event)
+                if event.tenant_id == tenant.id()
+                    && (device_ids.is_empty() || device_ids.contains(&event.device_id)) =>
+            {
+                Some(Ok(event.into()))
+            }
+            Ok(_) => None,
+            Err(BroadcastStreamRecvError::Lagged(skipped)) => {
+                tracing::warn!(
+                    "gRPC telemetry subscriber lagged, skipped {} readings",
+                    skipped
+                );
+                None
+            }
+        });
+        Ok(Response::new(Box::pin(stream)))
+    }
+}
+
+impl From<TelemetryEvent> for proto::TelemetryEvent {
+    fn from(event: TelemetryEvent) -> Self {
+        Self {
+            device_id: event.device_id,
+            metric: event.metric,
+            value_json: event.value.to_string(),
+            unit: event.unit,
+            recorded_at: event.recorded_at.to_rfc3339(),
+        }
+    }
+}
+
+/// Parse a capability entry: a JSON object is a typed definition, anything else a name
+fn capability_declaration(entry: String) -> Result<CapabilityDeclaration, UaipError> {
+    if entry.trim_start().starts_with('{') {
+        serde_json::from_str(&entry).map_err(|e| {
+            UaipError::InvalidParameter(format!("Invalid capability definition: {}", e))
+        })
+    } else {
+        Ok(CapabilityDeclaration::Name(entry))
+    }
+}
+
+/// Map a hub error to the gRPC status matching its REST status code
+fn status(error: UaipError) -> Status {
+    let response: ErrorResponse = error.into();
+    let code = match response.code {
+        ErrorCode::AuthenticationFailed => Code::Unauthenticated,
+        ErrorCode::AuthorizationFailed => Code::PermissionDenied,
+        ErrorCode::DeviceNotFound | ErrorCode::ResourceNotFound => Code::NotFound,
+        ErrorCode::InvalidParameter | ErrorCode::InvalidConfiguration => Code::InvalidArgument,
+        ErrorCode::InvalidDeviceState => Code::FailedPrecondition,
+        ErrorCode::RateLimitExceeded => Code::ResourceExhausted,
+        ErrorCode::ServiceUnavailable => Code::Unavailable,
+        _ => Code::Internal,
+    };
+    Status::new(code, response.message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use proto::hub_service_client::HubServiceClient;
+    use tonic::transport::Channel;
+
+    async fn start_server(state: Arc<AppState>) -> HubServiceClient<Channel> {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve(state, listener));
+
+        HubServiceClient::connect(format!("http://{}", addr))
+            .await
+            .unwrap()
+    }
+
+    fn command(action: &str, parameters_json: &str) -> Request<proto::SendCommandRequest> {
+        let token = crate::handlers::auth::jwt_manager(3600)
+            .generate_token("operator-1", "ops", vec!["command:*:*".to_string()], None)
+            .unwrap();
+        let mut request = Request::new(proto::SendCommandRequest {
+            device_id: "light-1".to_string(),
+            action: action.to_string(),
+            parameters_json: parameters_json.to_string(),
+            priority: Some("high".to_string()),
+            preempt: false,
+        });
+        request.metadata_mut().insert(
+            "authorization",
+            format!("Bearer {}", token).parse().unwrap(),
+        );
+        request
+    }
+
+    #[tokio::test]
+    async fn test_send_command_validates_and_dispatches() {
+        let mut client = start_server(Arc::new(AppState::new())).await;
+
+        let anonymous = command("dimmer", "").into_inner();
+        let err = client.send_command(anonymous).await.unwrap_err();
+        assert_eq!(err.code(), Code::Unauthenticated);
+
+        let err = client.send_command(command("", "")).await.unwrap_err();
+        assert_eq!(err.code(), Code::InvalidArgument);
+        assert!(err.message().contains("action"));
+
+        let err = client
+            .send_command(command("dimmer", "{brightness"))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::InvalidArgument);
+        assert!(err.message().contains("parameters_json"));
+
+        // A valid command reaches the shared command path, which needs a database
+        let err = client
+            .send_command(command("dimmer", r#"{"brightness": 40}"#))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::Unavailable);
+        assert!(err.message().contains("Database not configured"));
+    }
+
+    fn subscription(
+        device_ids: &[&str],
+        tenant: &str,
+    ) -> Request<proto::SubscribeTelemetryRequest> {
+        let token = crate::handlers::auth::jwt_manager(3600)
+            .generate_tenant_token(
+                "dashboard-1",
+                "ops",
+                vec!["telemetry:read".to_string()],
+                None,
+                Some(tenant.to_string()),
+            )
+            .unwrap();
+        let mut request = Request::new(proto::SubscribeTelemetryRequest {
+            device_ids: device_ids.iter().map(|id| id.to_string()).collect(),
+        });
+        request.metadata_mut().insert(
+            "authorization",
+            format!("Bearer {}", token).parse().unwrap(),
+        );
+        request
+    }
+
+    fn reading(tenant_id: &str, device_id: &str, value: f64) -> TelemetryEvent {
+        TelemetryEvent {
+            tenant_id: tenant_id.to_string(),
+            device_id: device_id.to_string(),
+            metric: "temperature".to_string(),
+            value: serde_json::json!(value),
+            unit: Some("C".to_string()),
+            recorded_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_telemetry_requires_authentication() {
+        let mut client = start_server(Arc::new(AppState::new())).await;
+
+        let anonymous = subscription(&[], "default").into_inner();
+        let err = client.subscribe_telemetry(anonymous).await.unwrap_err();
+        assert_eq!(err.code(), Code::Unauthenticated);
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_telemetry_drops_other_tenants() {
+        let state = Arc::new(AppState::new());
+        let mut client = start_server(state.clone()).await;
+
+        let mut stream = client
+            .subscribe_telemetry(subscription(&[], "tenant-a"))
+            .await
+            .unwrap()
+            .into_inner();
+
+        state
+            .telemetry_feed
+            .send(reading("tenant-b", "sensor-1", 18.0))
+            .unwrap();
+        state
+            .telemetry_feed
+            .send(reading("tenant-a", "sensor-2", 21.5))
+            .unwrap();
+
+        let event = stream.message().await.unwrap().unwrap();
+        assert_eq!(event.device_id, "sensor-2");
+        assert_eq!(event.value_json, "21.5");
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_telemetry_filters_devices() {
+        let state = Arc::new(AppState::new());
+        let mut client = start_server(state.clone()).await;
+
+        let mut stream = client
+            .subscribe_telemetry(subscription(&["sensor-1"], "default"))
+            .await
+            .unwrap()
+            .into_inner();
+
+        for (device_id, value) in [("sensor-2", 18.0), ("sensor-1", 21.5)] {
+            state
+                .telemetry_feed
+                .send(TelemetryEvent {
+                    tenant_id: "default".to_string(),
+                    device_id: device_id.to_string(),
+                    metric: "temperature".to_string(),
+                    value: serde_json::json!(value),
+                    unit: Some("C".to_string()),
+                    recorded_at: Utc::now(),
+                })
+                .unwrap();
+        }
+
+        let event = stream.message().await.unwrap().unwrap();
+        assert_eq!(event.device_id, "sensor-1");
+        assert_eq!(event.value_json, "21.5");
+        assert_eq!(event.unit.as_deref(), Some("C"));
+    }
+}
@@ -1,6 +1,7 @@
 //! REST API endpoints
 
 use axum::{
+    extract::DefaultBodyLimit,
     http::StatusCode,
     response::IntoResponse,
     routing::{delete, get, post},
@@ -8,16 +9,55 @@ use axum::{
 };
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
+use tokio::sync::{broadcast, Mutex};
 use tower::ServiceBuilder;
 use tower_http::{cors::CorsLayer, trace::TraceLayer};
 
+use uaip_core::device::Capability;
 use uaip_core::error::{ErrorResponse, UaipError};
+use uaip_orchestrator::automation::AutomationEngine;
+use uaip_orchestrator::events::ExecutionStateChanged;
+use uaip_orchestrator::media_processing::{MetadataAnalyzer, ThumbnailGenerator};
+use uaip_registry::availability::Availability;
+use uaip_router::priority_queue::MessagePriorityQueue;
+use uaip_router::qos::QosHandler;
 
 /// Result type for API handlers
 pub type ApiResult<T> = Result<T, ApiError>;
 
+/// Request body limit for routes without an override
+pub const DEFAULT_BODY_LIMIT: usize = 256 * 1024;
+
+/// Request body limit for registration endpoints
+pub const REGISTRATION_BODY_LIMIT: usize = 16 * 1024;
+
+/// Request body limit for media metadata uploads
+pub const MEDIA_BODY_LIMIT: usize = 1024 * 1024;
+
+/// Request body limit for telemetry batches
+pub const TELEMETRY_BATCH_BODY_LIMIT: usize = 1024 * 1024;
+
+use crate::adapter_registry::AdapterRegistry;
+use crate::adapter_targets::AdapterProbeGuard;
+use crate::ai_session_manager::AiSessionManager;
 use crate::api::websocket;
+use crate::api_keys::ApiKeyStore;
+use crate::audit::AuditLog;
+use crate::automation_store::AutomationStore;
+use crate::command_log::CommandLog;
+use crate::command_throttle::CommandThrottle;
+use crate::config::{CompressionConfig, CorsConfig, MediaProcessingConfig, MessagingConfig};
+use crate::cookie_sessions::CookieSessions;
+use crate::device_types::DeviceTypes;
 use crate::handlers;
+use crate::handlers::executions::EXECUTION_FEED_CAPACITY;
+use crate::handlers::telemetry::{TelemetryEvent, TELEMETRY_FEED_CAPACITY};
+use crate::middleware::rate_limit::{RateLimitConfig, RateLimitLayer};
+use crate::pagination::Paginated;
+use crate::provisioning::ProvisioningTokenStore;
+use crate::query_timing::QueryTimer;
+use crate::scenario_history::ExecutionStore;
+use crate::telemetry_schema::TelemetrySchemas;
 
 /// Application state shared across handlers
 #[derive(Clone)]
@@ -25,17 +65,91 @@ pub struct AppState {
     pub db_pool: Option<sqlx::PgPool>,
     pub redis_client: Option<redis::Client>,
     pub nats_client: Option<async_nats::Client>,
+    pub ws_sessions: Arc<websocket::SessionManager>,
+    /// AI agent sessions, persisted when the hub has a database
+    pub ai_sessions: Arc<AiSessionManager>,
+    pub command_throttle: CommandThrottle,
+    pub command_log: CommandLog,
+    pub audit_log: AuditLog,
+    pub api_keys: ApiKeyStore,
+    pub provisioning_tokens: ProvisioningTokenStore,
+    pub scenario_executions: ExecutionStore,
+    pub automation: Arc<Mutex<AutomationEngine>>,
+    pub automation_store: AutomationStore,
+    pub qos_handler: Arc<QosHandler>,
+    /// TTL, QoS, priority and retry defaults for messages the hub sends
+    pub messaging: MessagingConfig,
+    pub message_queue: Arc<MessagePriorityQueue>,
+    pub telemetry_feed: broadcast::Sender<TelemetryEvent>,
+    /// Metric types, units and ranges per device type
+    pub telemetry_schemas: Arc<TelemetrySchemas>,
+    /// Default capabilities per device type, applied at registration
+    pub device_types: Arc<DeviceTypes>,
+    /// Workflow and scenario execution state changes
+    pub execution_feed: broadcast::Sender<ExecutionStateChanged>,
+    pub adapters: AdapterRegistry,
+    /// Authentication, rate and target checks for adapter test endpoints
+    pub adapter_probes: AdapterProbeGuard,
+    /// Request budget per API key or client address
+    pub rate_limiter: RateLimitLayer,
+    pub thumbnails: ThumbnailGenerator,
+    pub metadata_analyzer: MetadataAnalyzer,
+    pub cors: CorsConfig,
+    pub compression: CompressionConfig,
+    /// Signed-cookie dashboard sessions; `None` disables cookie authentication
+    pub cookie_sessions: Option<CookieSessions>,
+    /// Spans, metrics and slow-query warnings for database queries
+    pub query_timer: QueryTimer,
 }
 
 impl AppState {
     pub fn new() -> Self {
+        let execution_feed = broadcast::channel(EXECUTION_FEED_CAPACITY).0;
+        let mut automation = AutomationEngine::default();
+        automation
+            .scenario_engine
+            .set_event_sink(Arc::new(execution_feed.clone()));
+
         Self {
             db_pool: None,
             redis_client: None,
             nats_client: None,
+            ws_sessions: Arc::new(websocket::SessionManager::new()),
+            ai_sessions: Arc::new(AiSessionManager::default()),
+            command_throttle: CommandThrottle::default(),
+            command_log: CommandLog::memory(),
+            audit_log: AuditLog::memory(),
+            api_keys: ApiKeyStore::memory(),
+            provisioning_tokens: ProvisioningTokenStore::memory(),
+            scenario_executions: ExecutionStore::memory(),
+            automation: Arc::new(Mutex::new(automation)),
+            automation_store: AutomationStore::memory(),
+            qos_handler: Arc::new(QosHandler::new()),
+            messaging: MessagingConfig::default(),
+            message_queue: Arc::new(MessagePriorityQueue::new()),
+            telemetry_feed: broadcast::channel(TELEMETRY_FEED_CAPACITY).0,
+            telemetry_schemas: Arc::new(TelemetrySchemas::default()),
+            device_types: Arc::new(DeviceTypes::default()),
+            execution_feed,
+            adapters: AdapterRegistry::new(),
+            adapter_probes: AdapterProbeGuard::default(),
+            rate_limiter: RateLimitLayer::new(RateLimitConfig::default()),
+            thumbnails: MediaProcessingConfig::default().thumbnail_generator(),
+            metadata_analyzer: MediaProcessingConfig::default().metadata_analyzer(),
+            cors: CorsConfig::default(),
+            compression: CompressionConfig::default(),
+            cookie_sessions: None,
+            query_timer: QueryTimer::default(),
         }
     }
 
+    /// The database pool, or `NotConfigured` when the hub runs without a database
+    pub fn db(&self) -> Result<&sqlx::PgPool, UaipError> {
+        self.db_pool
+            .as_ref()
+            .ok_or_else(|| UaipError::NotConfigured("Database not configured".to_string()))
+    }
+
     pub fn with_db(mut self, pool: sqlx::PgPool) -> Self {
         self.db_pool = Some(pool);
         self
@@ -50,6 +164,125 @@ impl AppState {
         self.nats_client = Some(client);
         self
     }
+
+    pub fn with_command_throttle(mut self, throttle: CommandThrottle) -> Self {
+        self.command_throttle = throttle;
+        self
+    }
+
+    pub fn with_command_log(mut self, command_log: CommandLog) -> Self {
+        self.command_log = command_log;
+        self
+    }
+
+    pub fn with_audit_log(mut self, audit_log: AuditLog) -> Self {
+        self.audit_log = audit_log;
+        self
+    }
+
+    pub fn with_api_keys(mut self, api_keys: ApiKeyStore) -> Self {
+        self.api_keys = api_keys;
+        self
+    }
+
+    pub fn with_provisioning_tokens(mut self, provisioning_tokens: ProvisioningTokenStore) -> Self {
+        self.provisioning_tokens = provisioning_tokens;
+        self
+    }
+
+    pub fn with_execution_store(mut self, scenario_executions: ExecutionStore) -> Self {
+        self.scenario_executions = scenario_executions;
+        self
+    }
+
+    /// Use an automation engine, reporting its scenario executions on the execution feed
+    pub fn with_automation(mut self, mut automation: AutomationEngine) -> Self {
+        automation
+            .scenario_engine
+            .set_event_sink(Arc::new(self.execution_feed.clone()));
+        self.automation = Arc::new(Mutex::new(automation));
+        self
+    }
+
+    pub fn with_automation_store(mut self, automation_store: AutomationStore) -> Self {
+        self.automation_store = automation_store;
+        self
+    }
+
+    pub fn with_qos_handler(mut self, qos_handler: Arc<QosHandler>) -> Self {
+        self.qos_handler = qos_handler;
+        self
+    }
+
+    pub fn with_messaging(mut self, messaging: MessagingConfig) -> Self {
+        self.messaging = messaging;
+        self
+    }
+
+    pub fn with_message_queue(mut self, message_queue: Arc<MessagePriorityQueue>) -> Self {
+        self.message_queue = message_queue;
+        self
+    }
+
+    pub fn with_telemetry_schemas(mut self, telemetry_schemas: TelemetrySchemas) -> Self {
+        self.telemetry_schemas = Arc::new(telemetry_schemas);
+        self
+    }
+
+    pub fn with_device_types(mut self, device_types: DeviceTypes) -> Self {
+        self.device_types = Arc::new(device_types);
+        self
+    }
+
+    pub fn with_adapters(mut self, adapters: AdapterRegistry) -> Self {
+        self.adapters = adapters;
+        self
+    }
+
+    pub fn with_adapter_probes(mut self, adapter_probes: AdapterProbeGuard) -> Self {
+        self.adapter_probes = adapter_probes;
+        self
+    }
+
+    pub fn with_rate_limiter(mut self, rate_limiter: RateLimitLayer) -> Self {
+        self.rate_limiter = rate_limiter;
+        self
+    }
+
+    pub fn with_thumbnail_generator(mut self, thumbnails: ThumbnailGenerator) -> Self {
+        self.thumbnails = thumbnails;
+        self
+    }
+
+    pub fn with_metadata_analyzer(mut self, metadata_analyzer: MetadataAnalyzer) -> Self {
+        self.metadata_analyzer = metadata_analyzer;
+        self
+    }
+
+    pub fn with_cors(mut self, cors: CorsConfig) -> Self {
+        self.cors = cors;
+        self
+    }
+
+    pub fn with_compression(mut self, compression: CompressionConfig) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    pub fn with_cookie_sessions(mut self, cookie_sessions: CookieSessions) -> Self {
+        self.cookie_sessions = Some(cookie_sessions);
+        self
+    }
+
+    pub fn with_query_timer(mut self, query_timer: QueryTimer) -> Self {
+        self.query_timer = query_timer;
+        self
+    }
+
+    pub fn with_ai_sessions(mut self, ai_sessions: AiSessionManager) -> Self {
+        self.ai_sessions = Arc::new(ai_sessions);
+        self
+    }
 }
 
 impl Default for AppState {
@@ -60,62 +293,170 @@ impl Default for AppState {
 
 /// Create the REST API router
 pub fn create_router(state: Arc<AppState>) -> Router {
+    let cors = state.cors.layer().unwrap_or_else(|e| {
+        tracing::error!(
+            "Invalid CORS configuration, denying cross-origin requests: {}",
+            e
+        );
+        CorsLayer::new()
+    });
+    let compression = state.compression.layer();
+
     Router::new()
         // Health check
         .route("/api/v1/system/health", get(handlers::health_check))
+        .route(
+            "/api/v1/system/health/adapters",
+            get(handlers::adapter_health),
+        )
+        .route("/api/v1/system/version", get(handlers::version))
         // Metrics endpoint for Prometheus
         .route("/metrics", get(handlers::metrics::metrics_handler))
+        // Internal state snapshot for diagnostics
+        .route("/debug/state", get(handlers::debug::debug_state))
         // Authentication
         .route("/api/v1/auth/login", post(handlers::auth::login))
-        .route("/api/v1/auth/register", post(handlers::auth::register))
-        .route("/api/v1/auth/change-password", post(handlers::auth::change_password))
+        .route(
+            crate::middleware::cookie_session::SESSION_PATH,
+            post(handlers::sessions::create_session).delete(handlers::sessions::end_session),
+        )
+        .route(
+            "/api/v1/auth/register",
+            post(handlers::auth::register).layer(DefaultBodyLimit::max(REGISTRATION_BODY_LIMIT)),
+        )
+        .route(
+            "/api/v1/auth/change-password",
+            post(handlers::auth::change_password),
+        )
+        .route(
+            "/api/v1/auth/api-keys",
+            get(handlers::api_keys::list_api_keys).post(handlers::api_keys::issue_api_key),
+        )
+        .route(
+            "/api/v1/auth/api-keys/:id",
+            delete(handlers::api_keys::revoke_api_key),
+        )
         // User Management
         .route("/api/v1/users", get(handlers::users::list_users))
-        .route("/api/v1/users/register", post(handlers::users::create_user))
+        .route(
+            "/api/v1/users/register",
+            post(handlers::users::create_user)
+                .layer(DefaultBodyLimit::max(REGISTRATION_BODY_LIMIT)),
+        )
         .route("/api/v1/users/:id", delete(handlers::users::delete_user))
-        .route("/api/v1/users/:id", axum::routing::put(handlers::users::update_user))
-        .route("/api/v1/users/:id/password", axum::routing::put(handlers::users::admin_reset_password))
-        .route("/api/v1/users/:id/status", post(handlers::users::update_user_status))
+        .route(
+            "/api/v1/users/:id",
+            axum::routing::put(handlers::users::update_user),
+        )
+        .route(
+            "/api/v1/users/:id/password",
+            axum::routing::put(handlers::users::admin_reset_password),
+        )
+        .route(
+            "/api/v1/users/:id/status",
+            post(handlers::users::update_user_status),
+        )
+        // Audit
+        .route("/api/v1/audit", get(handlers::audit::query_audit_log))
+        .route(
+            "/api/v1/audit/verify",
+            get(handlers::audit::verify_audit_log),
+        )
         // Devices
         .route("/api/v1/devices", get(handlers::devices::list_devices))
+        .route("/api/v1/devices/:id", get(handlers::devices::get_device))
+        .route(
+            "/api/v1/devices/export",
+            get(handlers::devices::export_devices),
+        )
+        .route(
+            "/api/v1/devices/import",
+            post(handlers::devices::import_devices),
+        )
         .route(
             "/api/v1/devices/register",
-            post(handlers::devices::register_device),
+            post(handlers::devices::register_device)
+                .layer(DefaultBodyLimit::max(REGISTRATION_BODY_LIMIT)),
+        )
+        .route(
+            "/api/v1/devices/provision",
+            post(handlers::provisioning::provision_device)
+                .layer(DefaultBodyLimit::max(REGISTRATION_BODY_LIMIT)),
+        )
+        .route(
+            "/api/v1/provisioning/tokens",
+            get(handlers::provisioning::list_provisioning_tokens)
+                .post(handlers::provisioning::issue_provisioning_token),
         )
         .route(
             "/api/v1/devices/:id/command",
             post(handlers::devices::send_command),
         )
-        // Protocol Adapters
-        .route("/api/v1/adapters", get(handlers::adapters::list_adapters))
         .route(
-            "/api/v1/adapters/http/test",
-            post(handlers::adapters::test_http_adapter),
+            "/api/v1/devices/:id/commands",
+            get(handlers::commands::list_device_commands),
         )
         .route(
-            "/api/v1/adapters/modbus/test",
-            post(handlers::adapters::test_modbus_adapter),
+            "/api/v1/devices/:id/commands/next",
+            get(handlers::commands::next_device_command),
         )
         .route(
-            "/api/v1/adapters/modbus/read",
-            post(handlers::adapters::read_modbus_registers),
+            "/api/v1/devices/:id/commands/:message_id/result",
+            post(handlers::commands::report_command_result),
         )
         .route(
-            "/api/v1/adapters/opcua/test",
-            post(handlers::adapters::test_opcua_adapter),
+            "/api/v1/messages/:id",
+            delete(handlers::commands::cancel_command),
         )
         .route(
-            "/api/v1/adapters/opcua/read",
-            post(handlers::adapters::read_opcua_node),
+            "/api/v1/devices/:id/telemetry/batch",
+            post(handlers::telemetry::ingest_telemetry_batch)
+                .layer(DefaultBodyLimit::max(TELEMETRY_BATCH_BODY_LIMIT)),
+        )
+        .route(
+            "/api/v1/devices/:id/telemetry/replay",
+            post(handlers::telemetry::replay_telemetry),
+        )
+        .route(
+            "/api/v1/devices/:id/heartbeat",
+            post(handlers::telemetry::device_heartbeat)
+                .layer(DefaultBodyLimit::max(TELEMETRY_BATCH_BODY_LIMIT)),
+        )
+        .route(
+            "/api/v1/devices/:id/stream",
+            post(handlers::media::start_device_stream),
+        )
+        // Device groups
+        .route(
+            "/api/v1/groups",
+            get(handlers::groups::list_groups).post(handlers::groups::create_group),
+        )
+        .route(
+            "/api/v1/groups/:id",
+            get(handlers::groups::get_group)
+                .put(handlers::groups::update_group)
+                .delete(handlers::groups::delete_group),
+        )
+        .route(
+            "/api/v1/groups/:id/command",
+            post(handlers::groups::send_group_command),
+        )
+        // Firmware
+        .route(
+            "/api/v1/firmware",
+            get(handlers::firmware::list_firmware_catalog),
         )
         .route(
-            "/api/v1/adapters/webrtc/offer",
-            post(handlers::adapters::create_webrtc_offer),
+            "/api/v1/firmware/:device_type",
+            axum::routing::put(handlers::firmware::upsert_firmware_catalog_entry),
         )
+        // Protocol Adapters
+        .merge(adapter_routes())
         // AI Agents
         .route(
             "/api/v1/ai/agents/register",
-            post(handlers::ai::register_ai_agent),
+            post(handlers::ai::register_ai_agent)
+                .layer(DefaultBodyLimit::max(REGISTRATION_BODY_LIMIT)),
         )
         .route("/api/v1/ai/agents", get(handlers::ai::list_ai_agents))
         .route("/api/v1/ai/sessions", post(handlers::ai::create_ai_session))
@@ -124,10 +465,30 @@ pub fn create_router(state: Arc<AppState>) -> Router {
             get(handlers::ai::get_ai_session),
         )
         // Media Management
-        .route("/api/v1/media/upload", post(handlers::media::upload_media))
+        .route(
+            "/api/v1/media/upload",
+            post(handlers::media::upload_media).layer(DefaultBodyLimit::max(MEDIA_BODY_LIMIT)),
+        )
         .route("/api/v1/media", get(handlers::media::list_media))
         .route("/api/v1/media/:id", get(handlers::media::get_media))
         .route("/api/v1/media/:id", delete(handlers::media::delete_media))
+        // Rules
+        .route("/api/v1/rules", get(handlers::rules::list_rules))
+        .route("/api/v1/rules/:id", get(handlers::rules::get_rule))
+        .route(
+            "/api/v1/rules/evaluate",
+            post(handlers::rules::evaluate_rules),
+        )
+        // Scenarios
+        .route(
+            "/api/v1/scenarios/:scenario_id/executions",
+            get(handlers::scenarios::list_scenario_executions),
+        )
+        // Execution events
+        .route(
+            "/api/v1/events/executions",
+            get(handlers::executions::stream_execution_events),
+        )
         // Streaming
         .route(
             "/api/v1/streaming/sessions",
@@ -139,14 +500,70 @@ pub fn create_router(state: Arc<AppState>) -> Router {
         )
         // WebSocket
         .route("/ws", get(websocket::ws_handler))
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            crate::middleware::cookie_session_middleware,
+        ))
+        // Runs after API key authentication so keys are limited by key, not address
+        .layer(axum::middleware::from_fn_with_state(
+            state.rate_limiter.clone(),
+            crate::middleware::rate_limit::rate_limit_middleware,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            crate::middleware::api_key_middleware,
+        ))
         .layer(
             ServiceBuilder::new()
                 .layer(TraceLayer::new_for_http())
-                .layer(CorsLayer::permissive()),
+                .layer(cors)
+                .layer(compression)
+                .layer(DefaultBodyLimit::max(DEFAULT_BODY_LIMIT)),
         )
         .with_state(state)
 }
 
+/// Routes for the protocol adapters compiled into the hub
+fn adapter_routes() -> Router<Arc<AppState>> {
+    let router = Router::new().route("/api/v1/adapters", get(handlers::adapters::list_adapters));
+
+    #[cfg(feature = "http")]
+    let router = router.route(
+        "/api/v1/adapters/http/test",
+        post(handlers::adapters::test_http_adapter),
+    );
+
+    #[cfg(feature = "modbus")]
+    let router = router
+        .route(
+            "/api/v1/adapters/modbus/test",
+            post(handlers::adapters::test_modbus_adapter),
+        )
+        .route(
+            "/api/v1/adapters/modbus/read",
+            post(handlers::adapters::read_modbus_registers),
+        );
+
+    #[cfg(feature = "opcua")]
+    let router = router
+        .route(
+            "/api/v1/adapters/opcua/test",
+            post(handlers::adapters::test_opcua_adapter),
+        )
+        .route(
+            "/api/v1/adapters/opcua/read",
+            post(handlers::adapters::read_opcua_node),
+        );
+
+    #[cfg(feature = "webrtc")]
+    let router = router.route(
+        "/api/v1/adapters/webrtc/offer",
+        post(handlers::adapters::create_webrtc_offer),
+    );
+
+    router
+}
+
 /// Health check response
 #[derive(Debug, Serialize, Deserialize)]
 pub struct HealthResponse {
@@ -192,7 +609,39 @@ pub struct DeviceRegistrationRequest {
     pub name: String,
     pub manufacturer: Option<String>,
     pub model: Option<String>,
-    pub capabilities: Vec<String>,
+    /// Added to the default capabilities of the device type
+    #[serde(default)]
+    pub capabilities: Vec<CapabilityDeclaration>,
+}
+
+/// A capability declared at registration: a bare name, or a typed definition whose
+/// parameter specs are used to validate commands
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum CapabilityDeclaration {
+    Name(String),
+    Typed(Capability),
+}
+
+impl CapabilityDeclaration {
+    /// Capability name
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Name(name) => name,
+            Self::Typed(capability) => &capability.name,
+        }
+    }
+
+    /// Whether commands with this action are handled by the capability
+    pub fn supports_action(&self, action: &str) -> bool {
+        match self {
+            Self::Name(name) => name == action,
+            Self::Typed(capability) => {
+                capability.name == action
+                    || capability.supported_actions.iter().any(|a| a == action)
+            }
+        }
+    }
 }
 
 /// Device registration response
@@ -204,10 +653,16 @@ pub struct DeviceRegistrationResponse {
 }
 
 /// Device list response
+pub type DeviceListResponse = Paginated<DeviceInfo>;
+
+/// Device detail response
 #[derive(Debug, Serialize)]
-pub struct DeviceListResponse {
-    pub devices: Vec<DeviceInfo>,
-    pub total: usize,
+pub struct DeviceDetailResponse {
+    #[serde(flatten)]
+    pub device: DeviceInfo,
+    pub window_hours: i64,
+    /// Uptime over the window, or null if no status history covers it
+    pub availability: Option<Availability>,
 }
 
 /// Device information
@@ -218,6 +673,9 @@ pub struct DeviceInfo {
     pub device_type: String,
     pub status: String,
     pub last_seen: Option<String>,
+    pub firmware_version: Option<String>,
+    pub latest_firmware_version: Option<String>,
+    pub update_available: bool,
 }
 
 /// Command request
@@ -226,6 +684,9 @@ pub struct CommandRequest {
     pub action: String,
     pub parameters: Option<serde_json::Value>,
     pub priority: Option<String>,
+    /// Cancel the device's pending commands before queuing; critical priority only
+    #[serde(default)]
+    pub preempt: bool,
 }
 
 /// Command response
@@ -234,6 +695,9 @@ pub struct CommandResponse {
     pub message_id: String,
     pub status: String,
     pub queued_at: String,
+    /// Pending commands cancelled by a preempting command
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub preempted: Vec<String>,
 }
 
 /// Error response wrapper for HTTP responses
@@ -248,8 +712,13 @@ impl IntoResponse for ApiError {
             uaip_core::error::ErrorCode::AuthenticationFailed => StatusCode::UNAUTHORIZED,
             uaip_core::error::ErrorCode::AuthorizationFailed => StatusCode::FORBIDDEN,
             uaip_core::error::ErrorCode::DeviceNotFound => StatusCode::NOT_FOUND,
+            uaip_core::error::ErrorCode::ResourceNotFound => StatusCode::NOT_FOUND,
             uaip_core::error::ErrorCode::InvalidParameter => StatusCode::BAD_REQUEST,
+            uaip_core::error::ErrorCode::InvalidConfiguration => StatusCode::BAD_REQUEST,
+            uaip_core::error::ErrorCode::InvalidDeviceState => StatusCode::CONFLICT,
             uaip_core::error::ErrorCode::RateLimitExceeded => StatusCode::TOO_MANY_REQUESTS,
+            uaip_core::error::ErrorCode::ProtocolError => StatusCode::BAD_GATEWAY,
+            uaip_core::error::ErrorCode::ServiceUnavailable => StatusCode::SERVICE_UNAVAILABLE,
             _ => StatusCode::INTERNAL_SERVER_ERROR,
         };
 
@@ -295,6 +764,162 @@ mod tests {
         assert!(json.contains("1.0.0"));
     }
 
+    async fn send(
+        state: AppState,
+        request: axum::http::Request<axum::body::Body>,
+    ) -> axum::response::Response {
+        use tower::Service;
+
+        let mut router = create_router(Arc::new(state)).into_service::<axum::body::Body>();
+        std::future::poll_fn(|cx| router.poll_ready(cx))
+            .await
+            .unwrap();
+        router.call(request).await.unwrap()
+    }
+
+    async fn post_json(path: &str, body: Vec<u8>) -> StatusCode {
+        let request = axum::http::Request::builder()
+            .method("POST")
+            .uri(path)
+            .header("content-type", "application/json")
+            .body(axum::body::Body::from(body))
+            .unwrap();
+
+        send(AppState::new(), request).await.status()
+    }
+
+    async fn preflight(state: AppState, origin: &str) -> axum::response::Response {
+        let request = axum::http::Request::builder()
+            .method("OPTIONS")
+            .uri("/api/v1/devices")
+            .header("origin", origin)
+            .header("access-control-request-method", "GET")
+            .header("access-control-request-headers", "authorization")
+            .body(axum::body::Body::empty())
+            .unwrap();
+
+        send(state, request).await
+    }
+
+    fn dashboard_cors() -> CorsConfig {
+        CorsConfig {
+            allowed_origins: vec!["https://dashboard.example.com".to_string()],
+            ..CorsConfig::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cors_preflight_allowed_origin() {
+        let state = AppState::new().with_cors(dashboard_cors());
+        let response = preflight(state, "https://dashboard.example.com").await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let headers = response.headers();
+        assert_eq!(
+            headers["access-control-allow-origin"],
+            "https://dashboard.example.com"
+        );
+        assert!(headers["access-control-allow-methods"]
+            .to_str()
+            .unwrap()
+            .contains("GET"));
+    }
+
+    #[tokio::test]
+    async fn test_cors_preflight_disallowed_origin() {
+        let state = AppState::new().with_cors(dashboard_cors());
+        let response = preflight(state, "https://evil.example.com").await;
+        assert!(!response
+            .headers()
+            .contains_key("access-control-allow-origin"));
+
+        // The default configuration denies every origin
+        let response = preflight(AppState::new(), "https://dashboard.example.com").await;
+        assert!(!response
+            .headers()
+            .contains_key("access-control-allow-origin"));
+    }
+
+    async fn get_groups(
+        state: AppState,
+        accept_encoding: Option<&str>,
+    ) -> axum::response::Response {
+        let mut request = axum::http::Request::builder().uri("/api/v1/groups");
+        if let Some(encoding) = accept_encoding {
+            request = request.header("accept-encoding", encoding);
+        }
+
+        send(state, request.body(axum::body::Body::empty()).unwrap()).await
+    }
+
+    fn state_with_groups(count: usize) -> AppState {
+        let groups = (0..count)
+            .map(|i| {
+                uaip_core::group::DeviceGroup::new(format!("group-{}", i), format!("Group {}", i))
+                    .with_devices([format!("device-{}", i)])
+                    .with_tenant("default")
+            })
+            .collect();
+        let groups = uaip_core::group::DeviceGroups::from_groups(groups).unwrap();
+
+        AppState::new().with_automation(AutomationEngine::default().with_groups(groups))
+    }
+
+    #[tokio::test]
+    async fn test_large_json_response_is_gzip_encoded() {
+        let response = get_groups(state_with_groups(100), Some("gzip")).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["content-encoding"], "gzip");
+
+        let response = get_groups(state_with_groups(100), None).await;
+        assert!(!response.headers().contains_key("content-encoding"));
+    }
+
+    #[tokio::test]
+    async fn test_small_or_disabled_responses_not_compressed() {
+        let response = get_groups(state_with_groups(1), Some("gzip")).await;
+        assert!(!response.headers().contains_key("content-encoding"));
+
+        let disabled = CompressionConfig {
+            enabled: false,
+            ..CompressionConfig::default()
+        };
+        let state = state_with_groups(100).with_compression(disabled);
+        let response = get_groups(state, Some("gzip, br")).await;
+        assert!(!response.headers().contains_key("content-encoding"));
+    }
+
+    /// A JSON object padded with a filler field to `size` bytes
+    fn padded_body(size: usize) -> Vec<u8> {
+        let filler = "x".repeat(size);
+        format!(
+            r#"{{"device_id":"d1","device_type":"sensor","name":"{}","capabilities":[]}}"#,
+            filler
+        )
+        .into_bytes()
+    }
+
+    #[tokio::test]
+    async fn test_oversized_body_rejected_with_413() {
+        let body = padded_body(REGISTRATION_BODY_LIMIT);
+        let status = post_json("/api/v1/devices/register", body.clone()).await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+
+        // The same body is within the larger default limit on other routes
+        let status = post_json("/api/v1/devices/d1/command", body).await;
+        assert_ne!(status, StatusCode::PAYLOAD_TOO_LARGE);
+
+        let body = padded_body(DEFAULT_BODY_LIMIT);
+        let status = post_json("/api/v1/devices/d1/command", body).await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test]
+    async fn test_body_within_limit_reaches_handler() {
+        let status = post_json("/api/v1/devices/register", padded_body(64)).await;
+        assert_ne!(status, StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
     #[test]
     fn test_login_request_deserialization() {
         let json =
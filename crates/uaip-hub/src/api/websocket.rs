@@ -13,15 +13,36 @@ use std::{collections::HashMap, sync::Arc, time::Duration};
 use tokio::sync::{broadcast, RwLock};
 use tracing::{debug, error, info, warn};
 
+use uaip_core::error::UaipError;
+use uaip_core::message::UaipMessage;
+use uaip_core::protocol::{self, PROTOCOL_VERSION, SUPPORTED_PROTOCOL_VERSIONS};
+
 use crate::api::rest::AppState;
 
 /// WebSocket session ID
 pub type SessionId = String;
 
+/// An in-flight streamed result, routed back to the session that originated it
+#[derive(Debug, Clone)]
+struct ResultStream {
+    /// Session that issued the request
+    session_id: SessionId,
+    /// Session producing the results, bound when the command is dispatched
+    producer: SessionId,
+    /// Next expected chunk sequence number
+    next_sequence: u64,
+}
+
 /// WebSocket session manager
 pub struct SessionManager {
     /// Active sessions (session_id -> sender)
     sessions: Arc<RwLock<HashMap<SessionId, broadcast::Sender<WsMessage>>>>,
+    /// Open result streams (correlation_id -> originating session)
+    streams: Arc<RwLock<HashMap<String, ResultStream>>>,
+    /// Connected devices (device_id -> session announcing the device)
+    devices: Arc<RwLock<HashMap<String, SessionId>>>,
+    /// Negotiated protocol versions (session_id -> version)
+    versions: Arc<RwLock<HashMap<SessionId, String>>>,
     /// Broadcast channel for global events
     broadcast_tx: broadcast::Sender<WsMessage>,
 }
@@ -32,6 +53,9 @@ impl SessionManager {
         let (broadcast_tx, _) = broadcast::channel(100);
         Self {
             sessions: Arc::new(RwLock::new(HashMap::new())),
+            streams: Arc::new(RwLock::new(HashMap::new())),
+            devices: Arc::new(RwLock::new(HashMap::new())),
+            versions: Arc::new(RwLock::new(HashMap::new())),
             broadcast_tx,
         }
     }
@@ -49,9 +73,165 @@ impl SessionManager {
     pub async fn unregister(&self, session_id: &str) {
         let mut sessions = self.sessions.write().await;
         sessions.remove(session_id);
+        drop(sessions);
+        self.versions.write().await.remove(session_id);
+        self.devices
+            .write()
+            .await
+            .retain(|_, device_session| device_session != session_id);
+
+        // Drop any streams that can no longer be delivered or completed
+        self.streams
+            .write()
+            .await
+            .retain(|_, stream| stream.session_id != session_id && stream.producer != session_id);
         debug!("Unregistered WebSocket session: {}", session_id);
     }
 
+    /// Select the protocol version for a session from the versions its peer supports
+    pub async fn negotiate_version(
+        &self,
+        session_id: &str,
+        offered: &[String],
+    ) -> Result<String, UaipError> {
+        let version = protocol::negotiate_version(offered, SUPPORTED_PROTOCOL_VERSIONS)?;
+        self.versions
+            .write()
+            .await
+            .insert(session_id.to_string(), version.clone());
+        Ok(version)
+    }
+
+    /// Protocol version of a session; peers that never negotiated speak the base version
+    pub async fn protocol_version(&self, session_id: &str) -> String {
+        self.versions
+            .read()
+            .await
+            .get(session_id)
+            .cloned()
+            .unwrap_or_else(|| PROTOCOL_VERSION.to_string())
+    }
+
+    /// Validate a message received from a session against its protocol version
+    pub async fn check_message(
+        &self,
+        session_id: &str,
+        message: &UaipMessage,
+    ) -> Result<(), UaipError> {
+        protocol::check_message_version(message, &self.protocol_version(session_id).await)
+    }
+
+    /// Convert a message for delivery to a session at its protocol version
+    pub async fn adapt_message(
+        &self,
+        session_id: &str,
+        message: UaipMessage,
+    ) -> Result<UaipMessage, UaipError> {
+        protocol::adapt_message(message, &self.protocol_version(session_id).await)
+    }
+
+    /// Bind a device to the session that announced it
+    ///
+    /// A device stays bound to its first session until that session disconnects.
+    pub async fn attach_device(&self, device_id: String, session_id: &str) -> Result<(), String> {
+        let mut devices = self.devices.write().await;
+        match devices.get(&device_id) {
+            Some(existing) if existing != session_id => {
+                Err(format!("Device {} is already connected", device_id))
+            }
+            _ => {
+                debug!("Device {} attached to session {}", device_id, session_id);
+                devices.insert(device_id, session_id.to_string());
+                Ok(())
+            }
+        }
+    }
+
+    /// Session a device is connected through, if any
+    pub async fn device_session(&self, device_id: &str) -> Option<SessionId> {
+        self.devices.read().await.get(device_id).cloned()
+    }
+
+    /// Route streamed results for `correlation_id` from `producer` back to `session_id`
+    pub async fn open_stream(
+        &self,
+        correlation_id: String,
+        session_id: SessionId,
+        producer: SessionId,
+    ) {
+        debug!(
+            "Opened result stream {} from session {} to session {}",
+            correlation_id, producer, session_id
+        );
+        self.streams.write().await.insert(
+            correlation_id,
+            ResultStream {
+                session_id,
+                producer,
+                next_sequence: 0,
+            },
+        );
+    }
+
+    /// Forward a result chunk to the session that originated the request
+    ///
+    /// Chunks must arrive in sequence order; the stream is closed after the final chunk.
+    /// Only the session the command was dispatched to may send chunks.
+    pub async fn forward_chunk(
+        &self,
+        sender: &str,
+        correlation_id: &str,
+        sequence: u64,
+        data: serde_json::Value,
+        is_final: bool,
+    ) -> Result<(), String> {
+        let mut streams = self.streams.write().await;
+
+        let stream = streams
+            .get_mut(correlation_id)
+            .ok_or_else(|| format!("No open result stream for {}", correlation_id))?;
+
+        if stream.producer != sender {
+            return Err(format!(
+                "Result stream {} belongs to another session",
+                correlation_id
+            ));
+        }
+
+        if sequence != stream.next_sequence {
+            return Err(format!(
+                "Out-of-order chunk for {}: expected sequence {}, got {}",
+                correlation_id, stream.next_sequence, sequence
+            ));
+        }
+        stream.next_sequence += 1;
+
+        let session_id = stream.session_id.clone();
+        if is_final {
+            streams.remove(correlation_id);
+            debug!("Closed result stream {}", correlation_id);
+        }
+        drop(streams);
+
+        self.send_to_session(
+            &session_id,
+            WsMessage::ResultChunk {
+                correlation_id: correlation_id.to_string(),
+                sequence,
+                data,
+                is_final,
+            },
+        )
+        .await;
+
+        Ok(())
+    }
+
+    /// Get number of open result streams
+    pub async fn stream_count(&self) -> usize {
+        self.streams.read().await.len()
+    }
+
     /// Broadcast message to all sessions
     pub async fn broadcast(&self, message: WsMessage) {
         let _ = self.broadcast_tx.send(message);
@@ -82,6 +262,15 @@ impl Default for SessionManager {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum WsMessage {
+    /// Protocol versions a connecting peer supports
+    Hello {
+        versions: Vec<String>,
+        /// Device the peer connects as, so streamed commands for it are dispatched here
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        device_id: Option<String>,
+    },
+    /// Protocol version selected for the session
+    Welcome { version: String },
     /// Subscribe to device events
     Subscribe { device_id: String },
     /// Unsubscribe from device events
@@ -97,6 +286,16 @@ pub enum WsMessage {
         device_id: String,
         action: String,
         parameters: Option<serde_json::Value>,
+        /// Correlation ID for streamed results (opens a result stream when set)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        correlation_id: Option<String>,
+    },
+    /// Partial or final result chunk emitted by an AI agent
+    ResultChunk {
+        correlation_id: String,
+        sequence: u64,
+        data: serde_json::Value,
+        is_final: bool,
     },
     /// Device event notification
     Event {
@@ -120,18 +319,17 @@ pub enum WsMessage {
 /// WebSocket upgrade handler
 pub async fn ws_handler(
     ws: WebSocketUpgrade,
-    State(_state): State<Arc<AppState>>,
+    State(state): State<Arc<AppState>>,
 ) -> impl IntoResponse {
-    ws.on_upgrade(handle_socket)
+    let session_manager = state.ws_sessions.clone();
+    ws.on_upgrade(move |socket| handle_socket(socket, session_manager))
 }
 
 /// Handle WebSocket connection
-async fn handle_socket(socket: WebSocket) {
+async fn handle_socket(socket: WebSocket, session_manager: Arc<SessionManager>) {
     let session_id = uuid::Uuid::new_v4().to_string();
     info!("New WebSocket connection: {}", session_id);
 
-    // Create session manager (in production, this would be shared via AppState)
-    let session_manager = Arc::new(SessionManager::new());
     let mut rx = session_manager.register(session_id.clone()).await;
 
     let (mut sender, mut receiver) = socket.split();
@@ -223,6 +421,54 @@ async fn handle_message(
                 .map_err(|e| format!("Failed to parse message: {}", e))?;
 
             match ws_message {
+                WsMessage::Hello {
+                    versions,
+                    device_id,
+                } => {
+                    match session_manager
+                        .negotiate_version(session_id, &versions)
+                        .await
+                    {
+                        Ok(version) => {
+                            info!("Session {} negotiated protocol {}", session_id, version);
+                            if let Some(device_id) = device_id {
+                                if let Err(e) =
+                                    session_manager.attach_device(device_id, session_id).await
+                                {
+                                    warn!("Rejecting session {}: {}", session_id, e);
+                                    session_manager
+                                        .send_to_session(
+                                            session_id,
+                                            WsMessage::Error {
+                                                code: "DEVICE_ALREADY_CONNECTED".to_string(),
+                                                message: e,
+                                            },
+                                        )
+                                        .await;
+                                    session_manager.unregister(session_id).await;
+                                    return Ok(());
+                                }
+                            }
+                            session_manager
+                                .send_to_session(session_id, WsMessage::Welcome { version })
+                                .await;
+                        }
+                        Err(e) => {
+                            warn!("Rejecting session {}: {}", session_id, e);
+                            session_manager
+                                .send_to_session(
+                                    session_id,
+                                    WsMessage::Error {
+                                        code: "UNSUPPORTED_VERSION".to_string(),
+                                        message: e.to_string(),
+                                    },
+                                )
+                                .await;
+                            // Dropping the session closes the connection once the error is sent
+                            session_manager.unregister(session_id).await;
+                        }
+                    }
+                }
                 WsMessage::Subscribe { device_id } => {
                     info!("Session {} subscribed to device: {}", session_id, device_id);
                     session_manager
@@ -253,12 +499,47 @@ async fn handle_message(
                 WsMessage::Command {
                     device_id,
                     action,
-                    parameters: _,
+                    parameters,
+                    correlation_id,
                 } => {
                     info!(
                         "Received command from {}: {} on device {}",
                         session_id, action, device_id
                     );
+                    if let Some(correlation_id) = correlation_id {
+                        // Streamed results can only come from the device's own connection
+                        let Some(producer) = session_manager.device_session(&device_id).await
+                        else {
+                            session_manager
+                                .send_to_session(
+                                    session_id,
+                                    WsMessage::Error {
+                                        code: "DEVICE_NOT_CONNECTED".to_string(),
+                                        message: format!("Device {} is not connected", device_id),
+                                    },
+                                )
+                                .await;
+                            return Ok(());
+                        };
+                        session_manager
+                            .open_stream(
+                                correlation_id.clone(),
+                                session_id.to_string(),
+                                producer.clone(),
+                            )
+                            .await;
+                        session_manager
+                            .send_to_session(
+                                &producer,
+                                WsMessage::Command {
+                                    device_id: device_id.clone(),
+                                    action,
+                                    parameters,
+                                    correlation_id: Some(correlation_id),
+                                },
+                            )
+                            .await;
+                    }
                     // TODO: Forward command to device via router
                     session_manager
                         .send_to_session(
@@ -270,6 +551,31 @@ async fn handle_message(
                         )
                         .await;
                 }
+                WsMessage::ResultChunk {
+                    correlation_id,
+                    sequence,
+                    data,
+                    is_final,
+                } => {
+                    debug!(
+                        "Received result chunk {} for {} from session {}",
+                        sequence, correlation_id, session_id
+                    );
+                    if let Err(e) = session_manager
+                        .forward_chunk(session_id, &correlation_id, sequence, data, is_final)
+                        .await
+                    {
+                        session_manager
+                            .send_to_session(
+                                session_id,
+                                WsMessage::Error {
+                                    code: "INVALID_RESULT_CHUNK".to_string(),
+                                    message: e,
+                                },
+                            )
+                            .await;
+                    }
+                }
                 WsMessage::Pong => {
                     debug!("Received pong from session: {}", session_id);
                 }
@@ -370,6 +676,301 @@ mod tests {
         assert!(pong_json.contains("pong"));
     }
 
+    #[tokio::test]
+    async fn test_result_chunks_delivered_in_order() {
+        let manager = SessionManager::new();
+        let mut rx = manager.register("client-1".to_string()).await;
+        manager
+            .open_stream(
+                "corr-1".to_string(),
+                "client-1".to_string(),
+                "agent-1".to_string(),
+            )
+            .await;
+
+        for (sequence, part) in ["part 1", "part 2", "part 3"].iter().enumerate() {
+            manager
+                .forward_chunk(
+                    "agent-1",
+                    "corr-1",
+                    sequence as u64,
+                    serde_json::json!(part),
+                    false,
+                )
+                .await
+                .unwrap();
+        }
+        manager
+            .forward_chunk("agent-1", "corr-1", 3, serde_json::json!("done"), true)
+            .await
+            .unwrap();
+
+        let mut received = Vec::new();
+        for _ in 0..4 {
+            match rx.recv().await.unwrap() {
+                WsMessage::ResultChunk {
+                    correlation_id,
+                    sequence,
+                    data,
+                    is_final,
+                } => {
+                    assert_eq!(correlation_id, "corr-1");
+                    received.push((sequence, data, is_final));
+                }
+                other => panic!("Unexpected message: {:?}", other),
+            }
+        }
+
+        assert_eq!(
+            received,
+            vec![
+                (0, serde_json::json!("part 1"), false),
+                (1, serde_json::json!("part 2"), false),
+                (2, serde_json::json!("part 3"), false),
+                (3, serde_json::json!("done"), true),
+            ]
+        );
+
+        // Stream is closed after the terminal chunk
+        assert_eq!(manager.stream_count().await, 0);
+        assert!(manager
+            .forward_chunk("agent-1", "corr-1", 4, serde_json::json!("late"), false)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_result_chunk_out_of_order_rejected() {
+        let manager = SessionManager::new();
+        let _rx = manager.register("client-1".to_string()).await;
+        manager
+            .open_stream(
+                "corr-2".to_string(),
+                "client-1".to_string(),
+                "agent-1".to_string(),
+            )
+            .await;
+
+        let result = manager
+            .forward_chunk("agent-1", "corr-2", 1, serde_json::json!("skipped"), false)
+            .await;
+        assert!(result.is_err());
+        assert_eq!(manager.stream_count().await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_result_chunks_from_other_sessions_dropped() {
+        let manager = SessionManager::new();
+        let mut rx = manager.register("client-1".to_string()).await;
+        manager
+            .open_stream(
+                "corr-4".to_string(),
+                "client-1".to_string(),
+                "agent-1".to_string(),
+            )
+            .await;
+
+        // Even the first chunk must come from the producer bound at dispatch
+        let result = manager
+            .forward_chunk("intruder", "corr-4", 0, serde_json::json!("forged"), false)
+            .await;
+        assert!(result.unwrap_err().contains("another session"));
+
+        manager
+            .forward_chunk("agent-1", "corr-4", 0, serde_json::json!("real"), false)
+            .await
+            .unwrap();
+        let result = manager
+            .forward_chunk("intruder", "corr-4", 1, serde_json::json!("forged"), true)
+            .await;
+        assert!(result.unwrap_err().contains("another session"));
+
+        // The stream stays open for its producer, and the forged chunks never arrive
+        assert_eq!(manager.stream_count().await, 1);
+        manager
+            .forward_chunk("agent-1", "corr-4", 1, serde_json::json!("done"), true)
+            .await
+            .unwrap();
+        for expected in ["real", "done"] {
+            match rx.recv().await.unwrap() {
+                WsMessage::ResultChunk { data, .. } => assert_eq!(data, expected),
+                other => panic!("Unexpected message: {:?}", other),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unregister_closes_streams() {
+        let manager = SessionManager::new();
+        let _rx = manager.register("client-1".to_string()).await;
+        manager
+            .open_stream(
+                "corr-3".to_string(),
+                "client-1".to_string(),
+                "agent-1".to_string(),
+            )
+            .await;
+
+        manager.unregister("client-1").await;
+        assert_eq!(manager.stream_count().await, 0);
+
+        // Streams also close when their producer disconnects
+        manager
+            .open_stream(
+                "corr-5".to_string(),
+                "client-2".to_string(),
+                "agent-1".to_string(),
+            )
+            .await;
+        manager.unregister("agent-1").await;
+        assert_eq!(manager.stream_count().await, 0);
+    }
+
+    async fn send_as(manager: &SessionManager, session_id: &str, message: WsMessage) {
+        let text = serde_json::to_string(&message).unwrap();
+        handle_message(Message::Text(text), session_id, manager)
+            .await
+            .unwrap();
+    }
+
+    fn streamed_command(device_id: &str) -> WsMessage {
+        WsMessage::Command {
+            device_id: device_id.to_string(),
+            action: "analyze".to_string(),
+            parameters: None,
+            correlation_id: Some("corr-6".to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_streamed_command_binds_device_session() {
+        let manager = SessionManager::new();
+        let mut client_rx = manager.register("client-1".to_string()).await;
+        let mut device_rx = manager.register("device-session".to_string()).await;
+        let _intruder_rx = manager.register("intruder".to_string()).await;
+
+        let hello = WsMessage::Hello {
+            versions: vec![PROTOCOL_VERSION.to_string()],
+            device_id: Some("device-1".to_string()),
+        };
+        send_as(&manager, "device-session", hello).await;
+        assert!(matches!(
+            device_rx.recv().await.unwrap(),
+            WsMessage::Welcome { .. }
+        ));
+
+        send_as(&manager, "client-1", streamed_command("device-1")).await;
+        match device_rx.recv().await.unwrap() {
+            WsMessage::Command { correlation_id, .. } => {
+                assert_eq!(correlation_id.as_deref(), Some("corr-6"))
+            }
+            other => panic!("Unexpected message: {:?}", other),
+        }
+        assert!(matches!(
+            client_rx.recv().await.unwrap(),
+            WsMessage::Ack { .. }
+        ));
+
+        assert!(manager
+            .forward_chunk("intruder", "corr-6", 0, serde_json::json!("forged"), true)
+            .await
+            .is_err());
+        manager
+            .forward_chunk("device-session", "corr-6", 0, serde_json::json!("ok"), true)
+            .await
+            .unwrap();
+        match client_rx.recv().await.unwrap() {
+            WsMessage::ResultChunk { data, .. } => assert_eq!(data, "ok"),
+            other => panic!("Unexpected message: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_streamed_command_requires_connected_device() {
+        let manager = SessionManager::new();
+        let mut rx = manager.register("client-1".to_string()).await;
+
+        send_as(&manager, "client-1", streamed_command("device-1")).await;
+
+        match rx.recv().await.unwrap() {
+            WsMessage::Error { code, .. } => assert_eq!(code, "DEVICE_NOT_CONNECTED"),
+            other => panic!("Unexpected message: {:?}", other),
+        }
+        assert_eq!(manager.stream_count().await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_device_cannot_be_claimed_twice() {
+        let manager = SessionManager::new();
+        manager
+            .attach_device("device-1".to_string(), "device-session")
+            .await
+            .unwrap();
+
+        assert!(manager
+            .attach_device("device-1".to_string(), "intruder")
+            .await
+            .is_err());
+        assert_eq!(
+            manager.device_session("device-1").await.as_deref(),
+            Some("device-session")
+        );
+
+        manager.unregister("device-session").await;
+        assert!(manager.device_session("device-1").await.is_none());
+    }
+
+    async fn send_hello(manager: &SessionManager, versions: &[&str]) {
+        let hello = WsMessage::Hello {
+            versions: versions.iter().map(|v| v.to_string()).collect(),
+            device_id: None,
+        };
+        let text = serde_json::to_string(&hello).unwrap();
+        handle_message(Message::Text(text), "client-1", manager)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_version_negotiation() {
+        let manager = SessionManager::new();
+        let mut rx = manager.register("client-1".to_string()).await;
+
+        send_hello(&manager, &["0.9", PROTOCOL_VERSION, "9.0"]).await;
+
+        match rx.recv().await.unwrap() {
+            WsMessage::Welcome { version } => assert_eq!(version, PROTOCOL_VERSION),
+            other => panic!("Unexpected message: {:?}", other),
+        }
+        assert_eq!(manager.protocol_version("client-1").await, PROTOCOL_VERSION);
+
+        let mut message = UaipMessage::new(
+            "device-1".to_string(),
+            uaip_core::message::EntityType::Device,
+            "hub".to_string(),
+            uaip_core::message::EntityType::System,
+        );
+        assert!(manager.check_message("client-1", &message).await.is_ok());
+        message.header.version = "9.0".to_string();
+        assert!(manager.check_message("client-1", &message).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_incompatible_version_rejected() {
+        let manager = SessionManager::new();
+        let mut rx = manager.register("client-1".to_string()).await;
+
+        send_hello(&manager, &["9.0"]).await;
+
+        match rx.recv().await.unwrap() {
+            WsMessage::Error { code, .. } => assert_eq!(code, "UNSUPPORTED_VERSION"),
+            other => panic!("Unexpected message: {:?}", other),
+        }
+        // The session is dropped, which ends the connection
+        assert_eq!(manager.session_count().await, 0);
+        assert!(rx.recv().await.is_err());
+    }
+
     #[tokio::test]
     async fn test_error_message() {
         let msg = WsMessage::Error {
@@ -0,0 +1,306 @@
+//! API Keys
+//!
+//! Long-lived credentials for machine clients that cannot do an interactive login. A key
+//! resolves to a subject, a set of scopes and a tenant. Only a SHA-256 hash of each key is
+//! stored; the key itself is returned once, when it is issued.
+
+use chrono::{DateTime, Utc};
+use ring::digest;
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use uaip_core::error::{Result, UaipError};
+
+/// Prefix identifying hub API keys
+pub const KEY_PREFIX: &str = "uaip_";
+
+/// Random bytes in a generated key
+const KEY_BYTES: usize = 32;
+
+/// An issued API key, without the key itself
+#[derive(Debug, Clone, PartialEq, Serialize, sqlx::FromRow)]
+pub struct ApiKey {
+    pub id: Uuid,
+    pub name: String,
+    /// Subject requests made with the key act as
+    pub subject: String,
+    pub scopes: Vec<String>,
+    pub tenant_id: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl ApiKey {
+    /// Check that the key can still authenticate at `now`
+    pub fn check_usable(&self, now: DateTime<Utc>) -> Result<()> {
+        if self.revoked_at.is_some() {
+            return Err(UaipError::AuthenticationFailed(
+                "API key has been revoked".to_string(),
+            ));
+        }
+        if self.expires_at.is_some_and(|expires_at| expires_at <= now) {
+            return Err(UaipError::AuthenticationFailed(
+                "API key has expired".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Definition of a key to issue
+#[derive(Debug, Clone)]
+pub struct NewApiKey {
+    pub name: String,
+    pub subject: String,
+    pub scopes: Vec<String>,
+    pub tenant_id: String,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// A newly issued key; `key` is not stored and cannot be retrieved again
+#[derive(Debug, Clone, Serialize)]
+pub struct IssuedApiKey {
+    pub key: String,
+    #[serde(flatten)]
+    pub api_key: ApiKey,
+}
+
+/// SHA-256 hash of a key, as stored
+pub fn hash_key(key: &str) -> String {
+    let hash = digest::digest(&digest::SHA256, key.as_bytes());
+    hash.as_ref().iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Generate a new random key with the given prefix
+pub(crate) fn generate_key(prefix: &str) -> Result<String> {
+    let mut bytes = [0u8; KEY_BYTES];
+    SystemRandom::new()
+        .fill(&mut bytes)
+        .map_err(|_| UaipError::InternalError("Failed to generate key".to_string()))?;
+    let random: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    Ok(format!("{}{}", prefix, random))
+}
+
+/// Backend for API keys
+#[derive(Clone)]
+pub enum ApiKeyStore {
+    /// In-process store keyed by key hash (used for tests and runs without a database)
+    Memory(Arc<Mutex<HashMap<String, ApiKey>>>),
+
+    /// PostgreSQL-backed store (`api_keys` table)
+    Postgres(PgPool),
+}
+
+impl ApiKeyStore {
+    /// Create an in-memory store
+    pub fn memory() -> Self {
+        Self::Memory(Arc::new(Mutex::new(HashMap::new())))
+    }
+
+    /// Create a PostgreSQL-backed store
+    pub fn postgres(pool: PgPool) -> Self {
+        Self::Postgres(pool)
+    }
+
+    /// Issue a key, storing only its hash
+    pub async fn issue(&self, new_key: NewApiKey) -> Result<IssuedApiKey> {
+        let key = generate_key(KEY_PREFIX)?;
+        let key_hash = hash_key(&key);
+        let api_key = ApiKey {
+            id: Uuid::new_v4(),
+            name: new_key.name,
+            subject: new_key.subject,
+            scopes: new_key.scopes,
+            tenant_id: new_key.tenant_id,
+            created_at: Utc::now(),
+            expires_at: new_key.expires_at,
+            revoked_at: None,
+        };
+
+        match self {
+            Self::Memory(keys) => {
+                keys.lock().await.insert(key_hash, api_key.clone());
+            }
+            Self::Postgres(pool) => {
+                sqlx::query(
+                    "INSERT INTO api_keys
+                        (id, name, subject, scopes, tenant_id, key_hash, created_at, expires_at)
+                     VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+                )
+                .bind(api_key.id)
+                .bind(&api_key.name)
+                .bind(&api_key.subject)
+                .bind(&api_key.scopes)
+                .bind(&api_key.tenant_id)
+                .bind(&key_hash)
+                .bind(api_key.created_at)
+                .bind(api_key.expires_at)
+                .execute(pool)
+                .await
+                .map_err(db_error)?;
+            }
+        }
+
+        Ok(IssuedApiKey { key, api_key })
+    }
+
+    /// Resolve a presented key, failing for unknown, revoked and expired keys
+    pub async fn authenticate(&self, key: &str) -> Result<ApiKey> {
+        let key_hash = hash_key(key);
+        let api_key = match self {
+            Self::Memory(keys) => keys.lock().await.get(&key_hash).cloned(),
+            Self::Postgres(pool) => sqlx::query_as::<_, ApiKey>(
+                "SELECT id, name, subject, scopes, tenant_id, created_at, expires_at, revoked_at
+                 FROM api_keys
+                 WHERE key_hash = $1",
+            )
+            .bind(&key_hash)
+            .fetch_optional(pool)
+            .await
+            .map_err(db_error)?,
+        }
+        .ok_or_else(|| UaipError::AuthenticationFailed("Invalid API key".to_string()))?;
+
+        api_key.check_usable(Utc::now())?;
+        Ok(api_key)
+    }
+
+    /// Keys issued for a tenant, newest first
+    pub async fn list(&self, tenant_id: &str) -> Result<Vec<ApiKey>> {
+        match self {
+            Self::Memory(keys) => {
+                let mut keys: Vec<ApiKey> = keys
+                    .lock()
+                    .await
+                    .values()
+                    .filter(|k| k.tenant_id == tenant_id)
+                    .cloned()
+                    .collect();
+                keys.sort_by_key(|k| std::cmp::Reverse(k.created_at));
+                Ok(keys)
+            }
+            Self::Postgres(pool) => sqlx::query_as::<_, ApiKey>(
+                "SELECT id, name, subject, scopes, tenant_id, created_at, expires_at, revoked_at
+                 FROM api_keys
+                 WHERE tenant_id = $1
+                 ORDER BY created_at DESC",
+            )
+            .bind(tenant_id)
+            .fetch_all(pool)
+            .await
+            .map_err(db_error),
+        }
+    }
+
+    /// Revoke one of a tenant's keys; revoking an already revoked key is a no-op
+    pub async fn revoke(&self, tenant_id: &str, id: Uuid) -> Result<ApiKey> {
+        let now = Utc::now();
+        let revoked = match self {
+            Self::Memory(keys) => keys
+                .lock()
+                .await
+                .values_mut()
+                .find(|k| k.id == id && k.tenant_id == tenant_id)
+                .map(|k| {
+                    k.revoked_at.get_or_insert(now);
+                    k.clone()
+                }),
+            Self::Postgres(pool) => sqlx::query_as::<_, ApiKey>(
+                "UPDATE api_keys
+                 SET revoked_at = COALESCE(revoked_at, $3)
+                 WHERE id = $1 AND tenant_id = $2
+                 RETURNING id, name, subject, scopes, tenant_id, created_at, expires_at, revoked_at",
+            )
+            .bind(id)
+            .bind(tenant_id)
+            .bind(now)
+            .fetch_optional(pool)
+            .await
+            .map_err(db_error)?,
+        };
+
+        revoked.ok_or_else(|| UaipError::NotFound(format!("API key not found: {}", id)))
+    }
+}
+
+fn db_error(e: sqlx::Error) -> UaipError {
+    UaipError::DatabaseError(format!("API key store error: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn new_key(expires_at: Option<DateTime<Utc>>) -> NewApiKey {
+        NewApiKey {
+            name: "line-3 gateway".to_string(),
+            subject: "gateway-3".to_string(),
+            scopes: vec!["device:read".to_string()],
+            tenant_id: "acme".to_string(),
+            expires_at,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_valid_key_authenticates() {
+        let store = ApiKeyStore::memory();
+        let issued = store.issue(new_key(None)).await.unwrap();
+        assert!(issued.key.starts_with(KEY_PREFIX));
+
+        let api_key = store.authenticate(&issued.key).await.unwrap();
+        assert_eq!(api_key, issued.api_key);
+        assert_eq!(api_key.subject, "gateway-3");
+
+        // Only the hash is kept
+        let ApiKeyStore::Memory(keys) = &store else {
+            unreachable!()
+        };
+        assert!(keys.lock().await.contains_key(&hash_key(&issued.key)));
+        assert!(!keys.lock().await.contains_key(&issued.key));
+
+        let err = store.authenticate("uaip_guessed").await.unwrap_err();
+        assert!(matches!(err, UaipError::AuthenticationFailed(_)));
+    }
+
+    #[tokio::test]
+    async fn test_revoked_key_fails() {
+        let store = ApiKeyStore::memory();
+        let issued = store.issue(new_key(None)).await.unwrap();
+
+        // Keys can only be revoked by their own tenant
+        let err = store.revoke("globex", issued.api_key.id).await.unwrap_err();
+        assert!(matches!(err, UaipError::NotFound(_)));
+        assert!(store.authenticate(&issued.key).await.is_ok());
+
+        let revoked = store.revoke("acme", issued.api_key.id).await.unwrap();
+        assert!(revoked.revoked_at.is_some());
+        let err = store.authenticate(&issued.key).await.unwrap_err();
+        assert!(err.to_string().contains("revoked"));
+    }
+
+    #[tokio::test]
+    async fn test_expired_key_fails() {
+        let store = ApiKeyStore::memory();
+        let expired = store
+            .issue(new_key(Some(Utc::now() - Duration::seconds(1))))
+            .await
+            .unwrap();
+        let err = store.authenticate(&expired.key).await.unwrap_err();
+        assert!(err.to_string().contains("expired"));
+
+        let current = store
+            .issue(new_key(Some(Utc::now() + Duration::hours(1))))
+            .await
+            .unwrap();
+        assert!(store.authenticate(&current.key).await.is_ok());
+        assert_eq!(store.list("acme").await.unwrap().len(), 2);
+        assert!(store.list("globex").await.unwrap().is_empty());
+    }
+}
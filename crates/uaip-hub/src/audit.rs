@@ -0,0 +1,483 @@
+//! Tamper-evident audit log
+//!
+//! Records security-relevant actions (registrations, command dispatch, rule changes,
+//! authentication) with a SHA-256 hash chain: each entry hashes its own fields together
+//! with the previous entry's hash, so editing or deleting a row breaks verification.
+
+use chrono::{DateTime, SubsecRound, Utc};
+use ring::digest;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use uaip_core::error::{Result, UaipError};
+
+/// Hash that precedes the first entry in the chain
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Advisory lock key serializing appends across hub instances
+const APPEND_LOCK_KEY: i64 = 0x5541_4950_4155_4454; // "UAIPAUDT"
+
+/// Result of an audited action
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditOutcome {
+    Success,
+    Failure,
+}
+
+impl AuditOutcome {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Success => "success",
+            Self::Failure => "failure",
+        }
+    }
+
+    fn parse(value: &str) -> Result<Self> {
+        match value {
+            "success" => Ok(Self::Success),
+            "failure" => Ok(Self::Failure),
+            other => Err(UaipError::InvalidParameter(format!(
+                "Unknown audit outcome: {}",
+                other
+            ))),
+        }
+    }
+}
+
+/// Action to be recorded in the audit log
+#[derive(Debug, Clone)]
+pub struct AuditEvent {
+    pub actor: String,
+    pub action: String,
+    pub target: Option<String>,
+    pub outcome: AuditOutcome,
+    pub details: serde_json::Value,
+}
+
+impl AuditEvent {
+    /// Create a successful event
+    pub fn success(actor: impl Into<String>, action: impl Into<String>) -> Self {
+        Self {
+            actor: actor.into(),
+            action: action.into(),
+            target: None,
+            outcome: AuditOutcome::Success,
+            details: serde_json::json!({}),
+        }
+    }
+
+    /// Create a failed event
+    pub fn failure(actor: impl Into<String>, action: impl Into<String>) -> Self {
+        Self {
+            outcome: AuditOutcome::Failure,
+            ..Self::success(actor, action)
+        }
+    }
+
+    /// Set the target of the action
+    pub fn with_target(mut self, target: impl Into<String>) -> Self {
+        self.target = Some(target.into());
+        self
+    }
+
+    /// Attach structured details
+    pub fn with_details(mut self, details: serde_json::Value) -> Self {
+        self.details = details;
+        self
+    }
+}
+
+/// Recorded audit entry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub sequence: i64,
+    pub timestamp: DateTime<Utc>,
+    pub actor: String,
+    pub action: String,
+    pub target: Option<String>,
+    pub outcome: AuditOutcome,
+    pub details: serde_json::Value,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+impl AuditEntry {
+    fn new(event: AuditEvent, sequence: i64, prev_hash: String) -> Self {
+        let mut entry = Self {
+            sequence,
+            // Postgres stores microseconds; truncate so the hash survives a round trip
+            timestamp: Utc::now().trunc_subsecs(6),
+            actor: event.actor,
+            action: event.action,
+            target: event.target,
+            outcome: event.outcome,
+            details: event.details,
+            prev_hash,
+            hash: String::new(),
+        };
+        entry.hash = entry.compute_hash();
+        entry
+    }
+
+    /// Hash of this entry's fields chained to the previous hash
+    pub fn compute_hash(&self) -> String {
+        let canonical = serde_json::json!([
+            self.sequence,
+            self.timestamp.to_rfc3339(),
+            self.actor,
+            self.action,
+            self.target,
+            self.outcome.as_str(),
+            self.details,
+            self.prev_hash,
+        ]);
+        let hash = digest::digest(&digest::SHA256, canonical.to_string().as_bytes());
+        hash.as_ref().iter().map(|b| format!("{:02x}", b)).collect()
+    }
+}
+
+/// Verify a contiguous run of entries, oldest first
+///
+/// Fails on the first entry whose hash or link to its predecessor does not match.
+pub fn verify_chain(entries: &[AuditEntry]) -> Result<()> {
+    let mut expected_prev = entries.first().map(|e| e.prev_hash.clone());
+    if entries.first().is_some_and(|e| e.sequence == 1) {
+        expected_prev = Some(GENESIS_HASH.to_string());
+    }
+
+    for entry in entries {
+        if Some(&entry.prev_hash) != expected_prev.as_ref() || entry.hash != entry.compute_hash() {
+            return Err(UaipError::InvalidState(format!(
+                "Audit chain broken at entry {}",
+                entry.sequence
+            )));
+        }
+        expected_prev = Some(entry.hash.clone());
+    }
+
+    Ok(())
+}
+
+/// Filters for querying the audit log
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AuditQuery {
+    pub actor: Option<String>,
+    pub action: Option<String>,
+    pub target: Option<String>,
+    pub outcome: Option<AuditOutcome>,
+    #[serde(default, deserialize_with = "crate::timestamp::deserialize_option")]
+    pub since: Option<DateTime<Utc>>,
+    #[serde(default, deserialize_with = "crate::timestamp::deserialize_option")]
+    pub until: Option<DateTime<Utc>>,
+    pub limit: Option<i64>,
+}
+
+impl AuditQuery {
+    fn matches(&self, entry: &AuditEntry) -> bool {
+        self.actor.as_ref().is_none_or(|a| &entry.actor == a)
+            && self.action.as_ref().is_none_or(|a| &entry.action == a)
+            && self
+                .target
+                .as_ref()
+                .is_none_or(|t| entry.target.as_ref() == Some(t))
+            && self.outcome.is_none_or(|o| entry.outcome == o)
+            && self.since.is_none_or(|s| entry.timestamp >= s)
+            && self.until.is_none_or(|u| entry.timestamp <= u)
+    }
+}
+
+/// Backend for the audit log
+#[derive(Clone)]
+pub enum AuditLog {
+    /// In-process log (used for tests and runs without a database)
+    Memory(Arc<Mutex<Vec<AuditEntry>>>),
+
+    /// PostgreSQL-backed log (`audit_events` table)
+    Postgres(PgPool),
+}
+
+impl AuditLog {
+    /// Create an in-memory audit log
+    pub fn memory() -> Self {
+        Self::Memory(Arc::new(Mutex::new(Vec::new())))
+    }
+
+    /// Create a PostgreSQL-backed audit log
+    pub fn postgres(pool: PgPool) -> Self {
+        Self::Postgres(pool)
+    }
+
+    /// Append an event to the chain
+    pub async fn record(&self, event: AuditEvent) -> Result<AuditEntry> {
+        match self {
+            Self::Memory(entries) => {
+                let mut entries = entries.lock().await;
+                let (sequence, prev_hash) = match entries.last() {
+                    Some(last) => (last.sequence + 1, last.hash.clone()),
+                    None => (1, GENESIS_HASH.to_string()),
+                };
+                let entry = AuditEntry::new(event, sequence, prev_hash);
+                entries.push(entry.clone());
+                Ok(entry)
+            }
+            Self::Postgres(pool) => {
+                let mut tx = pool.begin().await.map_err(db_error)?;
+
+                sqlx::query("SELECT pg_advisory_xact_lock($1)")
+                    .bind(APPEND_LOCK_KEY)
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(db_error)?;
+
+                let last: Option<(i64, String)> = sqlx::query_as(
+                    "SELECT sequence, hash FROM audit_events ORDER BY sequence DESC LIMIT 1",
+                )
+                .fetch_optional(&mut *tx)
+                .await
+                .map_err(db_error)?;
+
+                let (sequence, prev_hash) = match last {
+                    Some((sequence, hash)) => (sequence + 1, hash),
+                    None => (1, GENESIS_HASH.to_string()),
+                };
+                let entry = AuditEntry::new(event, sequence, prev_hash);
+
+                sqlx::query(
+                    "INSERT INTO audit_events
+                        (sequence, timestamp, actor, action, target, outcome, details, prev_hash, hash)
+                     VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)",
+                )
+                .bind(entry.sequence)
+                .bind(entry.timestamp)
+                .bind(&entry.actor)
+                .bind(&entry.action)
+                .bind(&entry.target)
+                .bind(entry.outcome.as_str())
+                .bind(&entry.details)
+                .bind(&entry.prev_hash)
+                .bind(&entry.hash)
+                .execute(&mut *tx)
+                .await
+                .map_err(db_error)?;
+
+                tx.commit().await.map_err(db_error)?;
+                Ok(entry)
+            }
+        }
+    }
+
+    /// Append an event, logging instead of failing the caller if the write fails
+    pub async fn record_or_warn(&self, event: AuditEvent) {
+        let action = event.action.clone();
+        if let Err(e) = self.record(event).await {
+            tracing::error!(action = %action, "Failed to write audit entry: {}", e);
+        }
+    }
+
+    /// Query entries matching the filters, newest first
+    pub async fn query(&self, query: &AuditQuery) -> Result<Vec<AuditEntry>> {
+        let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+
+        match self {
+            Self::Memory(entries) => Ok(entries
+                .lock()
+                .await
+                .iter()
+                .rev()
+                .filter(|e| query.matches(e))
+                .take(limit as usize)
+                .cloned()
+                .collect()),
+            Self::Postgres(pool) => {
+                let rows = sqlx::query_as::<_, AuditRow>(
+                    "SELECT sequence, timestamp, actor, action, target, outcome, details, prev_hash, hash
+                     FROM audit_events
+                     WHERE ($1::text IS NULL OR actor = $1)
+                       AND ($2::text IS NULL OR action = $2)
+                       AND ($3::text IS NULL OR target = $3)
+                       AND ($4::text IS NULL OR outcome = $4)
+                       AND ($5::timestamptz IS NULL OR timestamp >= $5)
+                       AND ($6::timestamptz IS NULL OR timestamp <= $6)
+                     ORDER BY sequence DESC
+                     LIMIT $7",
+                )
+                .bind(&query.actor)
+                .bind(&query.action)
+                .bind(&query.target)
+                .bind(query.outcome.map(|o| o.as_str()))
+                .bind(query.since)
+                .bind(query.until)
+                .bind(limit)
+                .fetch_all(pool)
+                .await
+                .map_err(db_error)?;
+
+                rows.into_iter().map(AuditEntry::try_from).collect()
+            }
+        }
+    }
+
+    /// Verify the whole chain, returning the number of entries checked
+    pub async fn verify(&self) -> Result<usize> {
+        let entries = match self {
+            Self::Memory(entries) => entries.lock().await.clone(),
+            Self::Postgres(pool) => sqlx::query_as::<_, AuditRow>(
+                "SELECT sequence, timestamp, actor, action, target, outcome, details, prev_hash, hash
+                 FROM audit_events
+                 ORDER BY sequence",
+            )
+            .fetch_all(pool)
+            .await
+            .map_err(db_error)?
+            .into_iter()
+            .map(AuditEntry::try_from)
+            .collect::<Result<Vec<_>>>()?,
+        };
+
+        if entries.first().is_some_and(|e| e.sequence != 1) {
+            return Err(UaipError::InvalidState(
+                "Audit chain is missing its earliest entries".to_string(),
+            ));
+        }
+        for (expected, entry) in (1..).zip(&entries) {
+            if entry.sequence != expected {
+                return Err(UaipError::InvalidState(format!(
+                    "Audit chain is missing entry {}",
+                    expected
+                )));
+            }
+        }
+
+        verify_chain(&entries)?;
+        Ok(entries.len())
+    }
+}
+
+impl Default for AuditLog {
+    fn default() -> Self {
+        Self::memory()
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct AuditRow {
+    sequence: i64,
+    timestamp: DateTime<Utc>,
+    actor: String,
+    action: String,
+    target: Option<String>,
+    outcome: String,
+    details: serde_json::Value,
+    prev_hash: String,
+    hash: String,
+}
+
+impl TryFrom<AuditRow> for AuditEntry {
+    type Error = UaipError;
+
+    fn try_from(row: AuditRow) -> Result<Self> {
+        Ok(Self {
+            sequence: row.sequence,
+            timestamp: row.timestamp,
+            actor: row.actor,
+            action: row.action,
+            target: row.target,
+            outcome: AuditOutcome::parse(&row.outcome)?,
+            details: row.details,
+            prev_hash: row.prev_hash,
+            hash: row.hash,
+        })
+    }
+}
+
+fn db_error(e: sqlx::Error) -> UaipError {
+    UaipError::DatabaseError(format!("Audit log error: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn log_with_entries() -> AuditLog {
+        let log = AuditLog::memory();
+        log.record(AuditEvent::success("admin@example.com", "user.login"))
+            .await
+            .unwrap();
+        log.record(
+            AuditEvent::success("hub", "device.command")
+                .with_target("device-001")
+                .with_details(serde_json::json!({ "action": "reboot" })),
+        )
+        .await
+        .unwrap();
+        log.record(AuditEvent::failure("unknown", "user.login"))
+            .await
+            .unwrap();
+        log
+    }
+
+    #[tokio::test]
+    async fn test_action_produces_audit_entry() {
+        let log = log_with_entries().await;
+
+        let entries = log
+            .query(&AuditQuery {
+                target: Some("device-001".to_string()),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].actor, "hub");
+        assert_eq!(entries[0].action, "device.command");
+        assert_eq!(entries[0].outcome, AuditOutcome::Success);
+        assert_eq!(entries[0].details["action"], "reboot");
+
+        let failures = log
+            .query(&AuditQuery {
+                outcome: Some(AuditOutcome::Failure),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].sequence, 3);
+    }
+
+    #[tokio::test]
+    async fn test_hash_chain_verifies() {
+        let log = log_with_entries().await;
+        assert_eq!(log.verify().await.unwrap(), 3);
+
+        let entries = log.query(&AuditQuery::default()).await.unwrap();
+        assert_eq!(entries[2].prev_hash, GENESIS_HASH);
+        assert_eq!(entries[1].prev_hash, entries[2].hash);
+        assert_eq!(entries[0].prev_hash, entries[1].hash);
+    }
+
+    #[tokio::test]
+    async fn test_tampered_row_is_detected() {
+        let log = log_with_entries().await;
+        let AuditLog::Memory(entries) = &log else {
+            unreachable!()
+        };
+
+        entries.lock().await[1].target = Some("device-999".to_string());
+        let err = log.verify().await.unwrap_err();
+        assert!(err.to_string().contains("entry 2"));
+    }
+
+    #[tokio::test]
+    async fn test_deleted_row_is_detected() {
+        let log = log_with_entries().await;
+        let AuditLog::Memory(entries) = &log else {
+            unreachable!()
+        };
+
+        entries.lock().await.remove(1);
+        assert!(log.verify().await.is_err());
+    }
+}
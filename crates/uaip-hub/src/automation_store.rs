@@ -0,0 +1,604 @@
+//! Automation Definition Persistence
+//!
+//! Stores rule and scenario definitions as versioned JSON documents so configured
+//! automation survives hub restarts. Definitions are loaded into the automation engine at
+//! startup and written through whenever one is registered, updated or unregistered.
+
+use serde::{de::DeserializeOwned, Serialize};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use uaip_core::error::{Result, UaipError};
+use uaip_orchestrator::automation::AutomationEngine;
+use uaip_orchestrator::rule_engine::Rule;
+use uaip_orchestrator::scenario::Scenario;
+
+/// Upgrades a stored definition by one schema version
+pub type Migration = fn(serde_json::Value) -> Result<serde_json::Value>;
+
+/// Rule upgrades; entry `n` turns a version `n + 1` definition into version `n + 2`
+const RULE_MIGRATIONS: &[Migration] = &[];
+
+/// Scenario upgrades; entry `n` turns a version `n + 1` definition into version `n + 2`
+const SCENARIO_MIGRATIONS: &[Migration] = &[structure_scenario_last_result];
+
+/// Kind of stored automation definition
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DefinitionKind {
+    Rule,
+    Scenario,
+}
+
+impl DefinitionKind {
+    fn table(&self) -> &'static str {
+        match self {
+            Self::Rule => "automation_rules",
+            Self::Scenario => "automation_scenarios",
+        }
+    }
+
+    fn migrations(&self) -> &'static [Migration] {
+        match self {
+            Self::Rule => RULE_MIGRATIONS,
+            Self::Scenario => SCENARIO_MIGRATIONS,
+        }
+    }
+
+    /// Schema version definitions of this kind are written with
+    pub fn schema_version(&self) -> i32 {
+        current_version(self.migrations())
+    }
+}
+
+/// A stored definition with its schema version
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow)]
+pub struct StoredDefinition {
+    pub id: String,
+    pub enabled: bool,
+    pub schema_version: i32,
+    pub definition: serde_json::Value,
+}
+
+fn current_version(migrations: &[Migration]) -> i32 {
+    migrations.len() as i32 + 1
+}
+
+/// Bring a stored definition up to the current schema version
+///
+/// Fails for versions newer than this hub understands.
+pub fn migrate_definition(
+    mut definition: serde_json::Value,
+    version: i32,
+    migrations: &[Migration],
+) -> Result<serde_json::Value> {
+    let current = current_version(migrations);
+    if version < 1 || version > current {
+        return Err(UaipError::InvalidConfiguration(format!(
+            "Unsupported definition schema version {} (current is {})",
+            version, current
+        )));
+    }
+
+    for migration in &migrations[(version - 1) as usize..] {
+        definition = migration(definition)?;
+    }
+    Ok(definition)
+}
+
+/// Scenario v1 -> v2: the `last_result` string becomes `last_result_summary`, and
+/// `last_result` a structured result without action details
+fn structure_scenario_last_result(mut value: serde_json::Value) -> Result<serde_json::Value> {
+    if let Some(summary) = value["last_result"].as_str().map(str::to_string) {
+        let status = if summary == "success" {
+            "completed"
+        } else {
+            "failed"
+        };
+        let at = match &value["last_triggered"] {
+            serde_json::Value::Null => value["updated_at"].clone(),
+            last_triggered => last_triggered.clone(),
+        };
+        value["last_result"] = serde_json::json!({
+            "status": status,
+            "error": null,
+            "action_results": [],
+            "at": at,
+        });
+        value["last_result_summary"] = serde_json::json!(summary);
+    }
+    Ok(value)
+}
+
+/// Backend for rule and scenario definitions
+#[derive(Clone)]
+pub enum AutomationStore {
+    /// In-process store (used for tests and runs without a database)
+    Memory(Arc<Mutex<HashMap<(DefinitionKind, String), StoredDefinition>>>),
+
+    /// PostgreSQL-backed store (`automation_rules` and `automation_scenarios` tables)
+    Postgres(PgPool),
+}
+
+impl AutomationStore {
+    /// Create an in-memory store
+    pub fn memory() -> Self {
+        Self::Memory(Arc::new(Mutex::new(HashMap::new())))
+    }
+
+    /// Create a PostgreSQL-backed store
+    pub fn postgres(pool: PgPool) -> Self {
+        Self::Postgres(pool)
+    }
+
+    /// Load every stored rule and scenario into an automation engine
+    ///
+    /// Definitions that cannot be migrated or decoded are skipped with a warning.
+    /// Returns the number of rules and scenarios loaded.
+    pub async fn load_into(&self, automation: &mut AutomationEngine) -> Result<(usize, usize)> {
+        let rules: Vec<Rule> = self.load(DefinitionKind::Rule).await?;
+        let scenarios: Vec<Scenario> = self.load(DefinitionKind::Scenario).await?;
+        let rule_count = rules.len();
+        let mut scenario_count = 0;
+
+        for rule in rules {
+            automation.rule_engine.add_rule(rule);
+        }
+        for scenario in scenarios {
+            let id = scenario.id.clone();
+            match automation.scenario_engine.register_scenario(scenario) {
+                Ok(()) => scenario_count += 1,
+                Err(e) => tracing::warn!(scenario = %id, "Skipping stored scenario: {}", e),
+            }
+        }
+
+        Ok((rule_count, scenario_count))
+    }
+
+    /// Add a rule to the engine and store it
+    pub async fn register_rule(&self, automation: &mut AutomationEngine, rule: Rule) -> Result<()> {
+        if automation.rule_engine.get_rule(&rule.id).is_some() {
+            return Err(UaipError::InvalidState(format!(
+                "Rule already exists: {}",
+                rule.id
+            )));
+        }
+
+        self.save(DefinitionKind::Rule, &rule.id, rule.enabled, &rule)
+            .await?;
+        automation.rule_engine.add_rule(rule);
+        Ok(())
+    }
+
+    /// Replace a rule in the engine and store it
+    pub async fn update_rule(&self, automation: &mut AutomationEngine, rule: Rule) -> Result<()> {
+        if automation.rule_engine.get_rule(&rule.id).is_none() {
+            return Err(UaipError::NotFound(format!("Rule not found: {}", rule.id)));
+        }
+
+        self.save(DefinitionKind::Rule, &rule.id, rule.enabled, &rule)
+            .await?;
+        automation.rule_engine.update_rule(rule)
+    }
+
+    /// Remove a rule from the engine and the store
+    pub async fn unregister_rule(
+        &self,
+        automation: &mut AutomationEngine,
+        rule_id: &str,
+    ) -> Result<()> {
+        if automation.rule_engine.get_rule(rule_id).is_none() {
+            return Err(UaipError::NotFound(format!("Rule not found: {}", rule_id)));
+        }
+
+        self.delete(DefinitionKind::Rule, rule_id).await?;
+        automation.rule_engine.remove_rule(rule_id);
+        Ok(())
+    }
+
+    /// Add a scenario to the engine and store it
+    pub async fn register_scenario(
+        &self,
+        automation: &mut AutomationEngine,
+        scenario: Scenario,
+    ) -> Result<()> {
+        if automation
+            .scenario_engine
+            .get_scenario(&scenario.id)
+            .is_some()
+        {
+            return Err(UaipError::InvalidState(format!(
+                "Scenario already exists: {}",
+                scenario.id
+            )));
+        }
+
+        self.put_scenario(automation, scenario, None).await
+    }
+
+    /// Replace a scenario in the engine and store it
+    pub async fn update_scenario(
+        &self,
+        automation: &mut AutomationEngine,
+        scenario: Scenario,
+    ) -> Result<()> {
+        let previous = automation
+            .scenario_engine
+            .get_scenario(&scenario.id)
+            .cloned()
+            .ok_or_else(|| UaipError::NotFound(format!("Scenario not found: {}", scenario.id)))?;
+
+        self.put_scenario(automation, scenario, Some(previous))
+            .await
+    }
+
+    /// Remove a scenario from the engine and the store
+    pub async fn unregister_scenario(
+        &self,
+        automation: &mut AutomationEngine,
+        scenario_id: &str,
+    ) -> Result<()> {
+        if automation
+            .scenario_engine
+            .get_scenario(scenario_id)
+            .is_none()
+        {
+            return Err(UaipError::NotFound(format!(
+                "Scenario not found: {}",
+                scenario_id
+            )));
+        }
+
+        self.delete(DefinitionKind::Scenario, scenario_id).await?;
+        automation.scenario_engine.unregister_scenario(scenario_id)
+    }
+
+    /// Register a scenario (which validates it), then store it, restoring `previous` on failure
+    async fn put_scenario(
+        &self,
+        automation: &mut AutomationEngine,
+        scenario: Scenario,
+        previous: Option<Scenario>,
+    ) -> Result<()> {
+        let engine = &mut automation.scenario_engine;
+        engine.register_scenario(scenario.clone())?;
+
+        if let Err(e) = self
+            .save(
+                DefinitionKind::Scenario,
+                &scenario.id,
+                scenario.enabled,
+                &scenario,
+            )
+            .await
+        {
+            match previous {
+                Some(previous) => engine.register_scenario(previous)?,
+                None => engine.unregister_scenario(&scenario.id)?,
+            }
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Insert or replace a definition at the current schema version
+    async fn save<T: Serialize>(
+        &self,
+        kind: DefinitionKind,
+        id: &str,
+        enabled: bool,
+        definition: &T,
+    ) -> Result<()> {
+        let stored = StoredDefinition {
+            id: id.to_string(),
+            enabled,
+            schema_version: kind.schema_version(),
+            definition: serde_json::to_value(definition)?,
+        };
+
+        match self {
+            Self::Memory(definitions) => {
+                definitions
+                    .lock()
+                    .await
+                    .insert((kind, id.to_string()), stored);
+            }
+            Self::Postgres(pool) => {
+                sqlx::query(&format!(
+                    "INSERT INTO {} (id, enabled, schema_version, definition)
+                     VALUES ($1, $2, $3, $4)
+                     ON CONFLICT (id) DO UPDATE
+                     SET enabled = EXCLUDED.enabled,
+                         schema_version = EXCLUDED.schema_version,
+                         definition = EXCLUDED.definition,
+                         updated_at = NOW()",
+                    kind.table()
+                ))
+                .bind(&stored.id)
+                .bind(stored.enabled)
+                .bind(stored.schema_version)
+                .bind(&stored.definition)
+                .execute(pool)
+                .await
+                .map_err(db_error)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Delete a definition
+    async fn delete(&self, kind: DefinitionKind, id: &str) -> Result<()> {
+        match self {
+            Self::Memory(definitions) => {
+                definitions.lock().await.remove(&(kind, id.to_string()));
+            }
+            Self::Postgres(pool) => {
+                sqlx::query(&format!("DELETE FROM {} WHERE id = $1", kind.table()))
+                    .bind(id)
+                    .execute(pool)
+                    .await
+                    .map_err(db_error)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Load and decode all definitions of a kind, upgrading outdated ones in place
+    async fn load<T: DeserializeOwned + Serialize>(&self, kind: DefinitionKind) -> Result<Vec<T>> {
+        let stored: Vec<StoredDefinition> = match self {
+            Self::Memory(definitions) => definitions
+                .lock()
+                .await
+                .iter()
+                .filter(|((k, _), _)| *k == kind)
+                .map(|(_, stored)| stored.clone())
+                .collect(),
+            Self::Postgres(pool) => sqlx::query_as(&format!(
+                "SELECT id, enabled, schema_version, definition FROM {} ORDER BY id",
+                kind.table()
+            ))
+            .fetch_all(pool)
+            .await
+            .map_err(db_error)?,
+        };
+
+        let mut definitions = Vec::with_capacity(stored.len());
+        for row in stored {
+            let decoded = migrate_definition(row.definition, row.schema_version, kind.migrations())
+                .and_then(|value| Ok(serde_json::from_value::<T>(value)?));
+            let definition = match decoded {
+                Ok(definition) => definition,
+                Err(e) => {
+                    tracing::warn!(id = %row.id, "Skipping stored {:?} definition: {}", kind, e);
+                    continue;
+                }
+            };
+
+            if row.schema_version < kind.schema_version() {
+                self.save(kind, &row.id, row.enabled, &definition).await?;
+            }
+            definitions.push(definition);
+        }
+
+        Ok(definitions)
+    }
+}
+
+impl Default for AutomationStore {
+    fn default() -> Self {
+        Self::memory()
+    }
+}
+
+fn db_error(e: sqlx::Error) -> UaipError {
+    UaipError::DatabaseError(format!("Automation store error: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use uaip_orchestrator::rule_engine::ConditionMode;
+    use uaip_orchestrator::scenario::{
+        ScenarioAction, ScenarioActionConfig, ScenarioState, ScenarioTrigger, TriggerType,
+    };
+
+    fn rule(id: &str, priority: i32) -> Rule {
+        Rule {
+            id: id.to_string(),
+            name: id.to_string(),
+            description: None,
+            enabled: true,
+            conditions: vec![],
+            actions: vec![],
+            condition_mode: ConditionMode::All,
+            priority,
+            cooldown_seconds: None,
+            last_executed: None,
+            tenant_id: None,
+            metadata: HashMap::new(),
+        }
+    }
+
+    fn scenario(id: &str) -> Scenario {
+        Scenario {
+            id: id.to_string(),
+            name: id.to_string(),
+            description: None,
+            enabled: true,
+            triggers: vec![ScenarioTrigger {
+                trigger_type: TriggerType::Manual,
+                config: HashMap::new(),
+                conditions: vec![],
+            }],
+            actions: vec![ScenarioActionConfig {
+                action: ScenarioAction::SendNotification,
+                parameters: HashMap::new(),
+                wait: true,
+                timeout_seconds: None,
+            }],
+            state: ScenarioState::Active,
+            metadata: HashMap::new(),
+            execution_count: 0,
+            last_triggered: None,
+            last_result: None,
+            last_result_summary: None,
+            tenant_id: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_definitions_survive_engine_recreation() {
+        let store = AutomationStore::memory();
+        let mut automation = AutomationEngine::default();
+
+        store
+            .register_rule(&mut automation, rule("overheat", 1))
+            .await
+            .unwrap();
+        store
+            .register_rule(&mut automation, rule("temporary", 1))
+            .await
+            .unwrap();
+        store
+            .update_rule(&mut automation, rule("overheat", 5))
+            .await
+            .unwrap();
+        store
+            .unregister_rule(&mut automation, "temporary")
+            .await
+            .unwrap();
+        store
+            .register_scenario(&mut automation, scenario("cool_down"))
+            .await
+            .unwrap();
+        drop(automation);
+
+        let mut restarted = AutomationEngine::default();
+        assert_eq!(store.load_into(&mut restarted).await.unwrap(), (1, 1));
+        assert_eq!(
+            restarted.rule_engine.get_rule("overheat").unwrap().priority,
+            5
+        );
+        assert!(restarted.rule_engine.get_rule("temporary").is_none());
+        assert!(restarted
+            .scenario_engine
+            .get_scenario("cool_down")
+            .is_some());
+    }
+
+    #[tokio::test]
+    async fn test_invalid_definitions_are_not_stored() {
+        let store = AutomationStore::memory();
+        let mut automation = AutomationEngine::default();
+
+        let mut invalid = scenario("empty");
+        invalid.triggers.clear();
+        assert!(store
+            .register_scenario(&mut automation, invalid)
+            .await
+            .is_err());
+        assert!(store
+            .register_rule(&mut automation, rule("missing", 1))
+            .await
+            .is_ok());
+        assert!(store
+            .register_rule(&mut automation, rule("missing", 1))
+            .await
+            .is_err());
+
+        let mut restarted = AutomationEngine::default();
+        assert_eq!(store.load_into(&mut restarted).await.unwrap(), (1, 0));
+    }
+
+    #[test]
+    fn test_migrate_definition_applies_pending_steps() {
+        fn add_priority(mut value: serde_json::Value) -> Result<serde_json::Value> {
+            value["priority"] = serde_json::json!(0);
+            Ok(value)
+        }
+        fn rename_title(mut value: serde_json::Value) -> Result<serde_json::Value> {
+            value["name"] = value["title"].take();
+            Ok(value)
+        }
+        let migrations: &[Migration] = &[add_priority, rename_title];
+
+        let v1 = serde_json::json!({ "title": "Overheat" });
+        assert_eq!(
+            migrate_definition(v1, 1, migrations).unwrap(),
+            serde_json::json!({ "title": null, "name": "Overheat", "priority": 0 })
+        );
+
+        let v2 = serde_json::json!({ "title": "Overheat", "priority": 3 });
+        assert_eq!(
+            migrate_definition(v2, 2, migrations).unwrap()["priority"],
+            3
+        );
+
+        let current = serde_json::json!({ "name": "Overheat" });
+        assert_eq!(
+            migrate_definition(current.clone(), 3, migrations).unwrap(),
+            current
+        );
+        assert!(migrate_definition(current, 4, migrations).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_scenario_last_result_string_is_structured() {
+        let store = AutomationStore::memory();
+        let AutomationStore::Memory(definitions) = &store else {
+            unreachable!()
+        };
+        let mut v1 = serde_json::to_value(scenario("cool_down")).unwrap();
+        v1["last_result"] = serde_json::json!("success");
+        v1.as_object_mut().unwrap().remove("last_result_summary");
+        definitions.lock().await.insert(
+            (DefinitionKind::Scenario, "cool_down".to_string()),
+            StoredDefinition {
+                id: "cool_down".to_string(),
+                enabled: true,
+                schema_version: 1,
+                definition: v1,
+            },
+        );
+
+        let mut automation = AutomationEngine::default();
+        assert_eq!(store.load_into(&mut automation).await.unwrap(), (0, 1));
+        let loaded = automation
+            .scenario_engine
+            .get_scenario("cool_down")
+            .unwrap();
+        assert_eq!(loaded.last_result_summary.as_deref(), Some("success"));
+        let result = loaded.last_result.as_ref().unwrap();
+        assert_eq!(result.status, ScenarioState::Completed);
+        assert_eq!(result.at, loaded.updated_at);
+
+        // The upgraded definition is written back at the current version
+        let stored =
+            definitions.lock().await[&(DefinitionKind::Scenario, "cool_down".to_string())].clone();
+        assert_eq!(stored.schema_version, 2);
+    }
+
+    #[tokio::test]
+    async fn test_load_skips_definitions_from_newer_hubs() {
+        let store = AutomationStore::memory();
+        let AutomationStore::Memory(definitions) = &store else {
+            unreachable!()
+        };
+        definitions.lock().await.insert(
+            (DefinitionKind::Rule, "future".to_string()),
+            StoredDefinition {
+                id: "future".to_string(),
+                enabled: true,
+                schema_version: DefinitionKind::Rule.schema_version() + 1,
+                definition: serde_json::to_value(rule("future", 1)).unwrap(),
+            },
+        );
+
+        let mut automation = AutomationEngine::default();
+        assert_eq!(store.load_into(&mut automation).await.unwrap(), (0, 0));
+    }
+}
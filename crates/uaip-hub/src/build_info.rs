@@ -0,0 +1,37 @@
+//! Build metadata of the running hub
+//!
+//! Values are captured by the build script, so reporting them costs nothing at runtime.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// Version and build details of the running binary
+#[derive(Debug, Clone, Serialize)]
+pub struct BuildInfo {
+    /// Crate version
+    pub version: &'static str,
+    /// Git revision the binary was built from, `unknown` outside a checkout
+    pub git_sha: &'static str,
+    /// When the binary was built
+    pub build_timestamp: Option<DateTime<Utc>>,
+    /// Cargo features enabled for the build
+    pub features: Vec<&'static str>,
+}
+
+impl BuildInfo {
+    /// Build details of this binary
+    pub fn current() -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION"),
+            git_sha: env!("UAIP_GIT_SHA"),
+            build_timestamp: env!("UAIP_BUILD_TIMESTAMP")
+                .parse()
+                .ok()
+                .and_then(|secs| DateTime::from_timestamp(secs, 0)),
+            features: env!("UAIP_FEATURES")
+                .split(',')
+                .filter(|feature| !feature.is_empty())
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,474 @@
+//! Device Command Log
+//!
+//! Commands sent to devices are recorded in `message_log` with a delivery status. Pending
+//! commands can be listed per device and cancelled until they have been delivered. Once
+//! delivered, the device reports a result that completes or fails the command.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use uaip_core::error::{Result, UaipError};
+
+/// Sender recorded for commands issued by the hub
+const HUB_SENDER: &str = "hub";
+
+/// Delivery status of a device command
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CommandStatus {
+    Pending,
+    Delivered,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl CommandStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Delivered => "delivered",
+            Self::Completed => "completed",
+            Self::Failed => "failed",
+            Self::Cancelled => "cancelled",
+        }
+    }
+
+    fn parse(value: &str) -> Result<Self> {
+        match value {
+            "pending" => Ok(Self::Pending),
+            "delivered" => Ok(Self::Delivered),
+            "completed" => Ok(Self::Completed),
+            "failed" => Ok(Self::Failed),
+            "cancelled" => Ok(Self::Cancelled),
+            other => Err(UaipError::InvalidParameter(format!(
+                "Unknown command status: {}",
+                other
+            ))),
+        }
+    }
+}
+
+/// A command queued for a device
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DeviceCommand {
+    pub message_id: String,
+    pub correlation_id: String,
+    pub device_id: String,
+    pub tenant_id: String,
+    pub action: String,
+    pub priority: String,
+    pub parameters: serde_json::Value,
+    pub status: CommandStatus,
+    pub created_at: DateTime<Utc>,
+    pub cancelled_at: Option<DateTime<Utc>>,
+    pub delivered_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+    /// Outcome reported by the device
+    pub result: Option<CommandResult>,
+}
+
+/// Outcome a device reports for a delivered command
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CommandResult {
+    pub success: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// Device state or output after running the command
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
+}
+
+/// Filters for a device's commands
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CommandQuery {
+    /// Only commands in this status
+    #[serde(default)]
+    pub status: Option<CommandStatus>,
+
+    /// Maximum number of commands returned (default 100, max 1000)
+    #[serde(default)]
+    pub limit: Option<i64>,
+}
+
+impl CommandQuery {
+    fn matches(&self, command: &DeviceCommand) -> bool {
+        self.status.is_none_or(|s| command.status == s)
+    }
+}
+
+/// Backend for the command log
+#[derive(Clone)]
+pub enum CommandLog {
+    /// In-process log (used for tests and runs without a database)
+    Memory(Arc<Mutex<Vec<DeviceCommand>>>),
+
+    /// PostgreSQL-backed log (`message_log` table)
+    Postgres(PgPool),
+}
+
+impl CommandLog {
+    /// Create an in-memory command log
+    pub fn memory() -> Self {
+        Self::Memory(Arc::new(Mutex::new(Vec::new())))
+    }
+
+    /// Create a PostgreSQL-backed command log
+    pub fn postgres(pool: PgPool) -> Self {
+        Self::Postgres(pool)
+    }
+
+    /// Record a command
+    pub async fn record(&self, command: &DeviceCommand) -> Result<()> {
+        match self {
+            Self::Memory(commands) => {
+                commands.lock().await.push(command.clone());
+                Ok(())
+            }
+            Self::Postgres(pool) => {
+                sqlx::query(
+                    "INSERT INTO message_log (
+                        id, message_id, correlation_id, sender_id, recipient_id, tenant_id,
+                        action, qos_level, priority, status, payload, created_at
+                     )
+                     VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)",
+                )
+                .bind(uuid::Uuid::new_v4())
+                .bind(&command.message_id)
+                .bind(&command.correlation_id)
+                .bind(HUB_SENDER)
+                .bind(&command.device_id)
+                .bind(&command.tenant_id)
+                .bind(&command.action)
+                .bind(1_i16) // QoS level 1 (at least once)
+                .bind(&command.priority)
+                .bind(command.status.as_str())
+                .bind(&command.parameters)
+                .bind(command.created_at)
+                .execute(pool)
+                .await
+                .map_err(db_error)?;
+                Ok(())
+            }
+        }
+    }
+
+    /// Commands for one of a tenant's devices, newest first
+    pub async fn list(
+        &self,
+        tenant_id: &str,
+        device_id: &str,
+        query: &CommandQuery,
+    ) -> Result<Vec<DeviceCommand>> {
+        let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+
+        match self {
+            Self::Memory(commands) => Ok(commands
+                .lock()
+                .await
+                .iter()
+                .rev()
+                .filter(|c| c.tenant_id == tenant_id && c.device_id == device_id)
+                .filter(|c| query.matches(c))
+                .take(limit as usize)
+                .cloned()
+                .collect()),
+            Self::Postgres(pool) => sqlx::query_as::<_, CommandRow>(
+                "SELECT message_id, correlation_id, recipient_id, tenant_id, action, priority,
+                        payload, status, created_at, cancelled_at, delivered_at, completed_at,
+                        result
+                 FROM message_log
+                 WHERE sender_id = $1 AND tenant_id = $2 AND recipient_id = $3
+                   AND ($4::text IS NULL OR status = $4)
+                 ORDER BY created_at DESC
+                 LIMIT $5",
+            )
+            .bind(HUB_SENDER)
+            .bind(tenant_id)
+            .bind(device_id)
+            .bind(query.status.map(|s| s.as_str()))
+            .bind(limit)
+            .fetch_all(pool)
+            .await
+            .map_err(db_error)?
+            .into_iter()
+            .map(DeviceCommand::try_from)
+            .collect(),
+        }
+    }
+
+    /// Cancel one of a tenant's commands that has not been delivered yet
+    pub async fn cancel(&self, tenant_id: &str, message_id: &str) -> Result<DeviceCommand> {
+        let now = Utc::now();
+
+        let (cancelled, status) = match self {
+            Self::Memory(commands) => {
+                let mut commands = commands.lock().await;
+                let command = commands
+                    .iter_mut()
+                    .find(|c| c.message_id == message_id && c.tenant_id == tenant_id);
+                match command {
+                    Some(command) if command.status == CommandStatus::Pending => {
+                        command.status = CommandStatus::Cancelled;
+                        command.cancelled_at = Some(now);
+                        (Some(command.clone()), None)
+                    }
+                    Some(command) => (None, Some(command.status)),
+                    None => (None, None),
+                }
+            }
+            Self::Postgres(pool) => {
+                let cancelled = sqlx::query_as::<_, CommandRow>(
+                    "UPDATE message_log
+                     SET status = 'cancelled', cancelled_at = $3
+                     WHERE message_id = $1 AND tenant_id = $2 AND sender_id = $4
+                       AND status = 'pending'
+                     RETURNING message_id, correlation_id, recipient_id, tenant_id, action,
+                               priority, payload, status, created_at, cancelled_at,
+                               delivered_at, completed_at, result",
+                )
+                .bind(message_id)
+                .bind(tenant_id)
+                .bind(now)
+                .bind(HUB_SENDER)
+                .fetch_optional(pool)
+                .await
+                .map_err(db_error)?
+                .map(DeviceCommand::try_from)
+                .transpose()?;
+
+                let status = match cancelled {
+                    Some(_) => None,
+                    None => stored_status(pool, tenant_id, None, message_id).await?,
+                };
+                (cancelled, status)
+            }
+        };
+
+        match (cancelled, status) {
+            (Some(command), _) => Ok(command),
+            (None, Some(status)) => Err(UaipError::InvalidState(format!(
+                "Command {} is {} and can no longer be cancelled",
+                message_id,
+                status.as_str()
+            ))),
+            (None, None) => Err(UaipError::NotFound(format!(
+                "Command not found: {}",
+                message_id
+            ))),
+        }
+    }
+
+    /// Mark one of a tenant's pending commands as delivered to its device
+    ///
+    /// # Returns
+    /// * `Option<DeviceCommand>` - The delivered command, or None if it is not pending
+    ///   (for example because it was cancelled while being handed over)
+    pub async fn mark_delivered(
+        &self,
+        tenant_id: &str,
+        message_id: &str,
+    ) -> Result<Option<DeviceCommand>> {
+        let now = Utc::now();
+
+        match self {
+            Self::Memory(commands) => {
+                let mut commands = commands.lock().await;
+                Ok(commands
+                    .iter_mut()
+                    .find(|c| {
+                        c.message_id == message_id
+                            && c.tenant_id == tenant_id
+                            && c.status == CommandStatus::Pending
+                    })
+                    .map(|command| {
+                        command.status = CommandStatus::Delivered;
+                        command.delivered_at = Some(now);
+                        command.clone()
+                    }))
+            }
+            Self::Postgres(pool) => sqlx::query_as::<_, CommandRow>(
+                "UPDATE message_log
+                 SET status = 'delivered', delivered_at = $3
+                 WHERE message_id = $1 AND tenant_id = $2 AND sender_id = $4
+                   AND status = 'pending'
+                 RETURNING message_id, correlation_id, recipient_id, tenant_id, action, priority,
+                           payload, status, created_at, cancelled_at, delivered_at, completed_at,
+                           result",
+            )
+            .bind(message_id)
+            .bind(tenant_id)
+            .bind(now)
+            .bind(HUB_SENDER)
+            .fetch_optional(pool)
+            .await
+            .map_err(db_error)?
+            .map(DeviceCommand::try_from)
+            .transpose(),
+        }
+    }
+
+    /// Record the result a device reports for a command delivered to it
+    ///
+    /// Successful results complete the command and unsuccessful ones fail it. Only
+    /// delivered commands take a result, and only from the device they were sent to.
+    pub async fn record_result(
+        &self,
+        tenant_id: &str,
+        device_id: &str,
+        message_id: &str,
+        result: CommandResult,
+    ) -> Result<DeviceCommand> {
+        let now = Utc::now();
+        let status = if result.success {
+            CommandStatus::Completed
+        } else {
+            CommandStatus::Failed
+        };
+
+        let (updated, current) = match self {
+            Self::Memory(commands) => {
+                let mut commands = commands.lock().await;
+                let command = commands.iter_mut().find(|c| {
+                    c.message_id == message_id
+                        && c.tenant_id == tenant_id
+                        && c.device_id == device_id
+                });
+                match command {
+                    Some(command) if command.status == CommandStatus::Delivered => {
+                        command.status = status;
+                        command.completed_at = Some(now);
+                        command.result = Some(result);
+                        (Some(command.clone()), None)
+                    }
+                    Some(command) => (None, Some(command.status)),
+                    None => (None, None),
+                }
+            }
+            Self::Postgres(pool) => {
+                let result = serde_json::to_value(&result)
+                    .map_err(|e| UaipError::InternalError(e.to_string()))?;
+                let updated = sqlx::query_as::<_, CommandRow>(
+                    "UPDATE message_log
+                     SET status = $4, completed_at = $5, result = $6
+                     WHERE message_id = $1 AND tenant_id = $2 AND recipient_id = $3
+                       AND sender_id = $7 AND status = 'delivered'
+                     RETURNING message_id, correlation_id, recipient_id, tenant_id, action,
+                               priority, payload, status, created_at, cancelled_at,
+                               delivered_at, completed_at, result",
+                )
+                .bind(message_id)
+                .bind(tenant_id)
+                .bind(device_id)
+                .bind(status.as_str())
+                .bind(now)
+                .bind(result)
+                .bind(HUB_SENDER)
+                .fetch_optional(pool)
+                .await
+                .map_err(db_error)?
+                .map(DeviceCommand::try_from)
+                .transpose()?;
+
+                let current = match updated {
+                    Some(_) => None,
+                    None => stored_status(pool, tenant_id, Some(device_id), message_id).await?,
+                };
+                (updated, current)
+            }
+        };
+
+        match (updated, current) {
+            (Some(command), _) => Ok(command),
+            (None, Some(current)) => Err(UaipError::InvalidState(format!(
+                "Command {} is {} and cannot take a result",
+                message_id,
+                current.as_str()
+            ))),
+            (None, None) => Err(UaipError::NotFound(format!(
+                "Command not found: {}",
+                message_id
+            ))),
+        }
+    }
+}
+
+/// Stored status of one of a tenant's commands, optionally only if sent to `device_id`
+async fn stored_status(
+    pool: &PgPool,
+    tenant_id: &str,
+    device_id: Option<&str>,
+    message_id: &str,
+) -> Result<Option<CommandStatus>> {
+    sqlx::query_scalar::<_, String>(
+        "SELECT status FROM message_log
+         WHERE message_id = $1 AND tenant_id = $2 AND sender_id = $3
+           AND ($4::text IS NULL OR recipient_id = $4)",
+    )
+    .bind(message_id)
+    .bind(tenant_id)
+    .bind(HUB_SENDER)
+    .bind(device_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(db_error)?
+    .map(|s| CommandStatus::parse(&s))
+    .transpose()
+}
+
+impl Default for CommandLog {
+    fn default() -> Self {
+        Self::memory()
+    }
+}
+
+/// Row shape of `message_log` for device commands
+#[derive(sqlx::FromRow)]
+struct CommandRow {
+    message_id: String,
+    correlation_id: Option<String>,
+    recipient_id: String,
+    tenant_id: String,
+    action: String,
+    priority: String,
+    payload: Option<serde_json::Value>,
+    status: String,
+    created_at: DateTime<Utc>,
+    cancelled_at: Option<DateTime<Utc>>,
+    delivered_at: Option<DateTime<Utc>>,
+    completed_at: Option<DateTime<Utc>>,
+    result: Option<serde_json::Value>,
+}
+
+impl TryFrom<CommandRow> for DeviceCommand {
+    type Error = UaipError;
+
+    fn try_from(row: CommandRow) -> Result<Self> {
+        Ok(Self {
+            message_id: row.message_id,
+            correlation_id: row.correlation_id.unwrap_or_default(),
+            device_id: row.recipient_id,
+            tenant_id: row.tenant_id,
+            action: row.action,
+            priority: row.priority,
+            parameters: row.payload.unwrap_or_else(|| serde_json::json!({})),
+            status: CommandStatus::parse(&row.status)?,
+            created_at: row.created_at,
+            cancelled_at: row.cancelled_at,
+            delivered_at: row.delivered_at,
+            completed_at: row.completed_at,
+            result: row
+                .result
+                .map(serde_json::from_value)
+                .transpose()
+                .map_err(|e| UaipError::InternalError(format!("Invalid command result: {}", e)))?,
+        })
+    }
+}
+
+fn db_error(e: sqlx::Error) -> UaipError {
+    UaipError::DatabaseError(format!("Command log error: {}", e))
+}
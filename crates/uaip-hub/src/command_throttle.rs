@@ -0,0 +1,291 @@
+//! Per-device command throttling
+//!
+//! Protects devices from command floods with a token bucket per device_id.
+//! Limits are configurable per device type; excess commands are rejected or queued.
+
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use tokio::sync::Mutex;
+
+use uaip_core::error::{Result, UaipError};
+
+use crate::middleware::rate_limit::TokenBucket;
+
+/// Command rate allowed for a single device
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DeviceRateLimit {
+    /// Sustained commands per second
+    commands_per_second: f64,
+    /// Commands allowed back-to-back before throttling
+    burst_size: u32,
+}
+
+impl DeviceRateLimit {
+    /// A limit of `commands_per_second` sustained with bursts of up to `burst_size`
+    ///
+    /// The rate must be positive and finite and the burst at least one command, otherwise
+    /// the bucket could never refill.
+    pub fn new(commands_per_second: f64, burst_size: u32) -> Result<Self> {
+        if !(commands_per_second.is_finite() && commands_per_second > 0.0) {
+            return Err(UaipError::InvalidConfiguration(format!(
+                "Device command rate must be positive, got {}",
+                commands_per_second
+            )));
+        }
+        if burst_size == 0 {
+            return Err(UaipError::InvalidConfiguration(
+                "Device command burst size must be at least 1".to_string(),
+            ));
+        }
+        Ok(Self {
+            commands_per_second,
+            burst_size,
+        })
+    }
+
+    /// Sustained commands per second
+    pub fn commands_per_second(&self) -> f64 {
+        self.commands_per_second
+    }
+
+    /// Commands allowed back-to-back before throttling
+    pub fn burst_size(&self) -> u32 {
+        self.burst_size
+    }
+}
+
+impl Default for DeviceRateLimit {
+    fn default() -> Self {
+        Self {
+            commands_per_second: 5.0,
+            burst_size: 10,
+        }
+    }
+}
+
+/// What to do with a command that exceeds the device's rate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThrottlePolicy {
+    /// Reject immediately with `RateLimitExceeded`
+    Reject,
+    /// Delay the command until a token frees up, rejecting if the wait exceeds `max_delay`
+    Queue { max_delay: Duration },
+}
+
+/// Command throttle configuration
+#[derive(Debug, Clone)]
+pub struct ThrottleConfig {
+    /// Limit for devices without a type-specific entry
+    pub default_limit: DeviceRateLimit,
+    /// Limits keyed by device type
+    pub device_type_limits: HashMap<String, DeviceRateLimit>,
+    /// Policy applied when a device is over its limit
+    pub policy: ThrottlePolicy,
+}
+
+impl Default for ThrottleConfig {
+    fn default() -> Self {
+        Self {
+            default_limit: DeviceRateLimit::default(),
+            device_type_limits: HashMap::new(),
+            policy: ThrottlePolicy::Reject,
+        }
+    }
+}
+
+impl ThrottleConfig {
+    /// Set the limit for a device type
+    pub fn with_device_type_limit(
+        mut self,
+        device_type: impl Into<String>,
+        limit: DeviceRateLimit,
+    ) -> Self {
+        self.device_type_limits.insert(device_type.into(), limit);
+        self
+    }
+
+    /// Set the policy for commands over the limit
+    pub fn with_policy(mut self, policy: ThrottlePolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    fn limit_for(&self, device_type: Option<&str>) -> DeviceRateLimit {
+        device_type
+            .and_then(|t| self.device_type_limits.get(t))
+            .copied()
+            .unwrap_or(self.default_limit)
+    }
+}
+
+/// Token-bucket command throttle keyed by device_id
+#[derive(Clone)]
+pub struct CommandThrottle {
+    buckets: Arc<Mutex<HashMap<String, TokenBucket>>>,
+    config: ThrottleConfig,
+}
+
+impl CommandThrottle {
+    pub fn new(config: ThrottleConfig) -> Self {
+        Self {
+            buckets: Arc::new(Mutex::new(HashMap::new())),
+            config,
+        }
+    }
+
+    /// Admit one command for a device, waiting or failing per the configured policy
+    pub async fn acquire(&self, device_id: &str, device_type: Option<&str>) -> Result<()> {
+        let delay = {
+            let mut buckets = self.buckets.lock().await;
+            let bucket = buckets.entry(device_id.to_string()).or_insert_with(|| {
+                let limit = self.config.limit_for(device_type);
+                TokenBucket::new(limit.burst_size as f64, limit.commands_per_second)
+            });
+
+            if bucket.try_consume(1.0) {
+                return Ok(());
+            }
+
+            match self.config.policy {
+                ThrottlePolicy::Queue { max_delay } if bucket.time_until(1.0) <= max_delay => {
+                    bucket.reserve(1.0)
+                }
+                _ => {
+                    tracing::warn!(device_id = %device_id, "Device command rate exceeded");
+                    return Err(UaipError::RateLimitExceeded);
+                }
+            }
+        };
+
+        tracing::debug!(device_id = %device_id, delay_ms = delay.as_millis() as u64, "Command queued by throttle");
+        tokio::time::sleep(delay).await;
+        Ok(())
+    }
+
+    /// Number of devices with a token bucket
+    pub async fn tracked_devices(&self) -> usize {
+        self.buckets.lock().await.len()
+    }
+
+    /// Drop buckets for devices that have been idle longer than `idle`
+    pub async fn cleanup_idle(&self, idle: Duration) {
+        self.buckets
+            .lock()
+            .await
+            .retain(|_, bucket| bucket.idle_for() < idle);
+    }
+}
+
+impl Default for CommandThrottle {
+    fn default() -> Self {
+        Self::new(ThrottleConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limit(commands_per_second: f64, burst_size: u32) -> DeviceRateLimit {
+        DeviceRateLimit::new(commands_per_second, burst_size).unwrap()
+    }
+
+    #[test]
+    fn test_device_rate_limit_rejects_unusable_limits() {
+        for (rate, burst) in [
+            (0.0, 5),
+            (-1.0, 5),
+            (f64::NAN, 5),
+            (f64::INFINITY, 5),
+            (1.0, 0),
+        ] {
+            assert!(
+                matches!(
+                    DeviceRateLimit::new(rate, burst),
+                    Err(UaipError::InvalidConfiguration(_))
+                ),
+                "rate {} burst {} should be rejected",
+                rate,
+                burst
+            );
+        }
+        assert_eq!(limit(0.5, 1).commands_per_second(), 0.5);
+        assert_eq!(limit(0.5, 1).burst_size(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_commands_within_rate_pass() {
+        let throttle = CommandThrottle::new(ThrottleConfig {
+            default_limit: limit(1.0, 3),
+            ..Default::default()
+        });
+
+        for i in 0..3 {
+            assert!(
+                throttle.acquire("device-1", None).await.is_ok(),
+                "Command {} should pass",
+                i
+            );
+        }
+        // Other devices have their own bucket
+        assert!(throttle.acquire("device-2", None).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_burst_beyond_rate_is_throttled() {
+        let throttle = CommandThrottle::new(ThrottleConfig {
+            default_limit: limit(1.0, 3),
+            ..Default::default()
+        });
+
+        for _ in 0..3 {
+            throttle.acquire("device-1", None).await.unwrap();
+        }
+        assert!(matches!(
+            throttle.acquire("device-1", None).await,
+            Err(UaipError::RateLimitExceeded)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_device_type_limit() {
+        let config = ThrottleConfig {
+            default_limit: limit(1.0, 1),
+            ..Default::default()
+        }
+        .with_device_type_limit("actuator", limit(1.0, 5));
+        let throttle = CommandThrottle::new(config);
+
+        for _ in 0..5 {
+            throttle.acquire("valve-1", Some("actuator")).await.unwrap();
+        }
+        throttle.acquire("sensor-1", Some("sensor")).await.unwrap();
+        assert!(throttle.acquire("sensor-1", Some("sensor")).await.is_err());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_queue_policy_delays_excess_commands() {
+        let config = ThrottleConfig {
+            default_limit: limit(20.0, 1),
+            ..Default::default()
+        }
+        .with_policy(ThrottlePolicy::Queue {
+            max_delay: Duration::from_millis(120),
+        });
+        let throttle = CommandThrottle::new(config);
+
+        throttle.acquire("device-1", None).await.unwrap();
+        let started = tokio::time::Instant::now();
+        throttle.acquire("device-1", None).await.unwrap();
+        assert_eq!(started.elapsed(), Duration::from_millis(50));
+
+        // Concurrent commands queue up until the wait would exceed max_delay
+        let (first, second, third) = tokio::join!(
+            throttle.acquire("device-1", None),
+            throttle.acquire("device-1", None),
+            throttle.acquire("device-1", None),
+        );
+        assert!(first.is_ok());
+        assert!(second.is_ok());
+        assert!(matches!(third, Err(UaipError::RateLimitExceeded)));
+    }
+}
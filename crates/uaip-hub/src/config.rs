@@ -1,3 +1,1604 @@
 //! Configuration management for UAIP Hub
 
-// Placeholder - to be implemented
+use axum::http::{HeaderName, HeaderValue, Method};
+use chrono::{DateTime, Utc};
+use redis::aio::ConnectionManagerConfig;
+use serde::Deserialize;
+use sqlx::postgres::PgPoolOptions;
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tower_http::compression::predicate::{And, Predicate, SizeAbove};
+use tower_http::compression::CompressionLayer;
+use tower_http::cors::{AllowHeaders, AllowMethods, AllowOrigin, CorsLayer};
+
+#[cfg(feature = "modbus")]
+use uaip_adapters::modbus::{ModbusAdapter, ModbusConfig};
+#[cfg(feature = "opcua")]
+use uaip_adapters::opcua::{OpcUaAdapter, OpcUaConfig};
+use uaip_core::error::{Result, UaipError};
+use uaip_core::message::{Priority, QosLevel, RetryPolicy, DEFAULT_TTL_MS};
+use uaip_core::network::{ConnectionPoolConfig, NetworkConfig};
+use uaip_orchestrator::media_processing::{
+    FfmpegFrameExtractor, FfprobeProber, LocalMediaStorage, MetadataAnalyzer, ThumbnailGenerator,
+};
+use uaip_orchestrator::notification::{EmailNotifier, WebhookNotifier};
+use uaip_orchestrator::scenario::ScenarioEngine;
+use uaip_router::qos::{self, QosHandler};
+
+use crate::adapter_registry::AdapterRegistry;
+use crate::command_throttle::{DeviceRateLimit, ThrottleConfig, ThrottlePolicy};
+use crate::polling::PollConfig;
+use crate::session_store::{ContextLimits, TruncationPolicy};
+use crate::telemetry::{Aggregation, Resolution, RetentionCutoffs};
+
+/// Default hub listen address
+pub const DEFAULT_BIND_ADDR: SocketAddr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 8443);
+
+/// Default gRPC listen address
+pub const DEFAULT_GRPC_BIND_ADDR: SocketAddr =
+    SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 50051);
+
+/// Default interval between absence watchdog passes
+pub const DEFAULT_WATCHDOG_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Default interval between adapter endpoint reachability probes
+pub const DEFAULT_ENDPOINT_PROBE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Read the hub listen address from `HUB_BIND_ADDR`, defaulting to `127.0.0.1:8443`
+pub fn bind_addr_from_env() -> Result<SocketAddr> {
+    match std::env::var("HUB_BIND_ADDR") {
+        Ok(value) => parse_bind_addr(&value),
+        Err(_) => Ok(DEFAULT_BIND_ADDR),
+    }
+}
+
+/// Read the gRPC listen address from `GRPC_BIND_ADDR`, defaulting to `127.0.0.1:50051`
+pub fn grpc_bind_addr_from_env() -> Result<SocketAddr> {
+    match std::env::var("GRPC_BIND_ADDR") {
+        Ok(value) => parse_grpc_bind_addr(&value),
+        Err(_) => Ok(DEFAULT_GRPC_BIND_ADDR),
+    }
+}
+
+/// Read the absence watchdog interval from `WATCHDOG_INTERVAL_SECS`, defaulting to 30s
+pub fn watchdog_interval_from_env() -> Result<Duration> {
+    match std::env::var("WATCHDOG_INTERVAL_SECS") {
+        Ok(value) => parse_interval_secs("WATCHDOG_INTERVAL_SECS", &value),
+        Err(_) => Ok(DEFAULT_WATCHDOG_INTERVAL),
+    }
+}
+
+/// Read the adapter endpoint probe interval from `ENDPOINT_PROBE_INTERVAL_SECS`,
+/// defaulting to 60s
+pub fn endpoint_probe_interval_from_env() -> Result<Duration> {
+    match std::env::var("ENDPOINT_PROBE_INTERVAL_SECS") {
+        Ok(value) => parse_interval_secs("ENDPOINT_PROBE_INTERVAL_SECS", &value),
+        Err(_) => Ok(DEFAULT_ENDPOINT_PROBE_INTERVAL),
+    }
+}
+
+/// Parse a positive number of seconds
+fn parse_interval_secs(var: &str, value: &str) -> Result<Duration> {
+    match value.trim().parse() {
+        Ok(secs) if secs > 0 => Ok(Duration::from_secs(secs)),
+        _ => Err(UaipError::InvalidConfiguration(format!(
+            "Invalid {}: {}",
+            var, value
+        ))),
+    }
+}
+
+/// Parse a listen address as `ip:port`, or a bare IP using the default port
+pub fn parse_bind_addr(value: &str) -> Result<SocketAddr> {
+    parse_listen_addr("HUB_BIND_ADDR", value, DEFAULT_BIND_ADDR.port())
+}
+
+/// Parse a gRPC listen address as `ip:port`, or a bare IP using the default gRPC port
+pub fn parse_grpc_bind_addr(value: &str) -> Result<SocketAddr> {
+    parse_listen_addr("GRPC_BIND_ADDR", value, DEFAULT_GRPC_BIND_ADDR.port())
+}
+
+fn parse_listen_addr(var: &str, value: &str, default_port: u16) -> Result<SocketAddr> {
+    let value = value.trim();
+    if let Ok(addr) = value.parse::<SocketAddr>() {
+        return Ok(addr);
+    }
+    value
+        .parse::<IpAddr>()
+        .map(|ip| SocketAddr::new(ip, default_port))
+        .map_err(|_| {
+            UaipError::InvalidConfiguration(format!(
+                "Invalid {} '{}': expected ip:port",
+                var, value
+            ))
+        })
+}
+
+/// CORS settings for the REST API
+///
+/// An entry of `*` allows anything for that list. With no allowed origins, cross-origin
+/// requests receive no CORS headers and browsers reject them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CorsConfig {
+    /// Origins allowed to call the API
+    pub allowed_origins: Vec<String>,
+    /// Methods allowed in cross-origin requests
+    pub allowed_methods: Vec<String>,
+    /// Request headers allowed in cross-origin requests
+    pub allowed_headers: Vec<String>,
+    /// Whether cookies and authorization headers may be sent
+    pub allow_credentials: bool,
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self {
+            allowed_origins: Vec::new(),
+            allowed_methods: ["GET", "POST", "PUT", "DELETE"].map(String::from).to_vec(),
+            allowed_headers: ["authorization", "content-type"].map(String::from).to_vec(),
+            allow_credentials: false,
+        }
+    }
+}
+
+impl CorsConfig {
+    /// Allow any origin, method, and header (development only)
+    pub fn permissive() -> Self {
+        Self {
+            allowed_origins: vec!["*".to_string()],
+            allowed_methods: vec!["*".to_string()],
+            allowed_headers: vec!["*".to_string()],
+            allow_credentials: false,
+        }
+    }
+
+    /// Load CORS settings from the environment
+    ///
+    /// Starts permissive when `DEV_MODE=true` and deny-all otherwise, then applies
+    /// `CORS_ALLOWED_ORIGINS`, `CORS_ALLOWED_METHODS`, `CORS_ALLOWED_HEADERS` (comma
+    /// separated) and `CORS_ALLOW_CREDENTIALS`.
+    pub fn from_env() -> Result<Self> {
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    /// Load CORS settings from a variable lookup
+    pub fn from_vars(lookup: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let flag = |name: &str| -> Result<Option<bool>> {
+            lookup(name)
+                .map(|value| {
+                    value.trim().parse().map_err(|_| {
+                        UaipError::InvalidConfiguration(format!("Invalid {}: {}", name, value))
+                    })
+                })
+                .transpose()
+        };
+        let list = |name: &str| -> Option<Vec<String>> {
+            lookup(name).map(|value| {
+                value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|item| !item.is_empty())
+                    .map(String::from)
+                    .collect()
+            })
+        };
+
+        let mut config = if flag("DEV_MODE")?.unwrap_or(false) {
+            Self::permissive()
+        } else {
+            Self::default()
+        };
+        if let Some(origins) = list("CORS_ALLOWED_ORIGINS") {
+            config.allowed_origins = origins;
+        }
+        if let Some(methods) = list("CORS_ALLOWED_METHODS") {
+            config.allowed_methods = methods;
+        }
+        if let Some(headers) = list("CORS_ALLOWED_HEADERS") {
+            config.allowed_headers = headers;
+        }
+        if let Some(allow_credentials) = flag("CORS_ALLOW_CREDENTIALS")? {
+            config.allow_credentials = allow_credentials;
+        }
+
+        config.layer().map(|_| config)
+    }
+
+    /// Build the CORS layer, rejecting invalid or conflicting settings
+    pub fn layer(&self) -> Result<CorsLayer> {
+        let invalid = |what: &str, value: &str| {
+            UaipError::InvalidConfiguration(format!("Invalid CORS {}: {}", what, value))
+        };
+        let wildcard = |values: &[String]| values.iter().any(|v| v == "*");
+
+        if self.allow_credentials
+            && (wildcard(&self.allowed_origins)
+                || wildcard(&self.allowed_methods)
+                || wildcard(&self.allowed_headers))
+        {
+            return Err(UaipError::InvalidConfiguration(
+                "CORS credentials cannot be combined with a `*` wildcard".to_string(),
+            ));
+        }
+
+        let mut layer = CorsLayer::new().allow_credentials(self.allow_credentials);
+
+        if wildcard(&self.allowed_origins) {
+            layer = layer.allow_origin(AllowOrigin::any());
+        } else if !self.allowed_origins.is_empty() {
+            let origins = self
+                .allowed_origins
+                .iter()
+                .map(|o| HeaderValue::from_str(o).map_err(|_| invalid("origin", o)))
+                .collect::<Result<Vec<_>>>()?;
+            layer = layer.allow_origin(origins);
+        }
+
+        layer = if wildcard(&self.allowed_methods) {
+            layer.allow_methods(AllowMethods::any())
+        } else {
+            let methods = self
+                .allowed_methods
+                .iter()
+                .map(|m| Method::from_bytes(m.as_bytes()).map_err(|_| invalid("method", m)))
+                .collect::<Result<Vec<_>>>()?;
+            layer.allow_methods(methods)
+        };
+
+        layer = if wildcard(&self.allowed_headers) {
+            layer.allow_headers(AllowHeaders::any())
+        } else {
+            let headers = self
+                .allowed_headers
+                .iter()
+                .map(|h| HeaderName::from_bytes(h.as_bytes()).map_err(|_| invalid("header", h)))
+                .collect::<Result<Vec<_>>>()?;
+            layer.allow_headers(headers)
+        };
+
+        Ok(layer)
+    }
+}
+
+/// Response compression settings
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompressionConfig {
+    /// Whether responses are compressed at all
+    pub enabled: bool,
+    /// Smallest response body, in bytes, worth compressing
+    pub min_size_bytes: u16,
+    /// Compressible content types; a `type/*` entry matches the whole type
+    pub content_types: Vec<String>,
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            min_size_bytes: 1024,
+            content_types: ["application/json", "text/*"].map(String::from).to_vec(),
+        }
+    }
+}
+
+impl CompressionConfig {
+    /// Load compression settings from the environment
+    ///
+    /// Reads `COMPRESSION_ENABLED`, `COMPRESSION_MIN_SIZE_BYTES` and
+    /// `COMPRESSION_CONTENT_TYPES` (comma separated); unset values keep their defaults.
+    pub fn from_env() -> Result<Self> {
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    /// Load compression settings from a variable lookup
+    pub fn from_vars(lookup: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let defaults = Self::default();
+        let invalid = |name: &str, value: &str| {
+            UaipError::InvalidConfiguration(format!("Invalid {}: {}", name, value))
+        };
+
+        let enabled = match lookup("COMPRESSION_ENABLED") {
+            Some(value) => value
+                .trim()
+                .parse()
+                .map_err(|_| invalid("COMPRESSION_ENABLED", &value))?,
+            None => defaults.enabled,
+        };
+        let min_size_bytes = match lookup("COMPRESSION_MIN_SIZE_BYTES") {
+            Some(value) => value
+                .trim()
+                .parse()
+                .map_err(|_| invalid("COMPRESSION_MIN_SIZE_BYTES", &value))?,
+            None => defaults.min_size_bytes,
+        };
+        let content_types = match lookup("COMPRESSION_CONTENT_TYPES") {
+            Some(value) => value
+                .split(',')
+                .map(|item| item.trim().to_ascii_lowercase())
+                .filter(|item| !item.is_empty())
+                .map(|item| match item.split_once('/') {
+                    Some((kind, subtype)) if !kind.is_empty() && !subtype.is_empty() => Ok(item),
+                    _ => Err(invalid("COMPRESSION_CONTENT_TYPES", &value)),
+                })
+                .collect::<Result<Vec<_>>>()?,
+            None => defaults.content_types,
+        };
+
+        Ok(Self {
+            enabled,
+            min_size_bytes,
+            content_types,
+        })
+    }
+
+    /// Build the compression layer
+    ///
+    /// gzip and brotli are negotiated from the request's `Accept-Encoding`. Responses
+    /// that already carry a `Content-Encoding` are passed through untouched.
+    pub fn layer(&self) -> CompressionLayer<And<SizeAbove, ContentTypeAllowlist>> {
+        let allowlist = ContentTypeAllowlist(if self.enabled {
+            self.content_types.clone().into()
+        } else {
+            Arc::from([])
+        });
+
+        CompressionLayer::new()
+            .no_deflate()
+            .no_zstd()
+            .compress_when(SizeAbove::new(self.min_size_bytes).and(allowlist))
+    }
+}
+
+/// Compression predicate matching responses against allowed content types
+#[derive(Debug, Clone)]
+pub struct ContentTypeAllowlist(Arc<[String]>);
+
+impl Predicate for ContentTypeAllowlist {
+    fn should_compress<B>(&self, response: &axum::http::Response<B>) -> bool
+    where
+        B: axum::body::HttpBody,
+    {
+        let Some(content_type) = response
+            .headers()
+            .get(axum::http::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+        else {
+            return false;
+        };
+        let essence = content_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+
+        self.0
+            .iter()
+            .any(|allowed| match allowed.strip_suffix("/*") {
+                Some(kind) => essence
+                    .split_once('/')
+                    .is_some_and(|(essence_kind, _)| essence_kind == kind),
+                None => *allowed == essence,
+            })
+    }
+}
+
+/// Defaults for messages the hub sends, where a request does not say otherwise
+#[derive(Debug, Clone, PartialEq)]
+pub struct MessagingConfig {
+    /// Message time-to-live in milliseconds
+    pub default_ttl_ms: u64,
+    /// QoS level of device commands
+    pub default_qos: QosLevel,
+    /// Priority of commands sent without one, or with an unknown one
+    pub default_priority: Priority,
+    /// Redelivery policy for QoS 1 and 2 messages without their own policy
+    pub retry_policy: RetryPolicy,
+}
+
+impl Default for MessagingConfig {
+    fn default() -> Self {
+        Self {
+            default_ttl_ms: DEFAULT_TTL_MS,
+            default_qos: QosLevel::AtLeastOnce,
+            default_priority: Priority::Normal,
+            retry_policy: RetryPolicy::default(),
+        }
+    }
+}
+
+impl MessagingConfig {
+    /// Load messaging defaults from the environment
+    ///
+    /// Reads `DEFAULT_MESSAGE_TTL_MS`, `DEFAULT_MESSAGE_QOS` (`at_most_once`,
+    /// `at_least_once` or `exactly_once`), `DEFAULT_MESSAGE_PRIORITY` and
+    /// `MESSAGE_MAX_ATTEMPTS`; unset values keep their defaults.
+    pub fn from_env() -> Result<Self> {
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    /// Load messaging defaults from a variable lookup
+    pub fn from_vars(lookup: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let defaults = Self::default();
+        let invalid = |name: &str, value: &str| {
+            UaipError::InvalidConfiguration(format!("Invalid {}: {}", name, value))
+        };
+
+        let default_ttl_ms = match lookup("DEFAULT_MESSAGE_TTL_MS") {
+            Some(value) => value
+                .trim()
+                .parse()
+                .ok()
+                .filter(|ttl| *ttl > 0)
+                .ok_or_else(|| invalid("DEFAULT_MESSAGE_TTL_MS", &value))?,
+            None => defaults.default_ttl_ms,
+        };
+        let default_qos = match lookup("DEFAULT_MESSAGE_QOS") {
+            Some(value) => match value.trim().to_ascii_lowercase().as_str() {
+                "at_most_once" => QosLevel::AtMostOnce,
+                "at_least_once" => QosLevel::AtLeastOnce,
+                "exactly_once" => QosLevel::ExactlyOnce,
+                _ => return Err(invalid("DEFAULT_MESSAGE_QOS", &value)),
+            },
+            None => defaults.default_qos,
+        };
+        let default_priority = match lookup("DEFAULT_MESSAGE_PRIORITY") {
+            Some(value) => value
+                .trim()
+                .parse()
+                .map_err(|_| invalid("DEFAULT_MESSAGE_PRIORITY", &value))?,
+            None => defaults.default_priority,
+        };
+        let retry_policy = match lookup("MESSAGE_MAX_ATTEMPTS") {
+            Some(value) => value
+                .trim()
+                .parse()
+                .ok()
+                .filter(|attempts| *attempts > 0)
+                .map(|attempts| defaults.retry_policy.clone().with_max_attempts(attempts))
+                .ok_or_else(|| invalid("MESSAGE_MAX_ATTEMPTS", &value))?,
+            None => defaults.retry_policy,
+        };
+
+        Ok(Self {
+            default_ttl_ms,
+            default_qos,
+            default_priority,
+            retry_policy,
+        })
+    }
+
+    /// QoS handler redelivering QoS 1 and 2 messages under the default retry policy
+    pub fn qos_handler(&self) -> QosHandler {
+        QosHandler::new()
+            .with_retry_policy(qos::QosLevel::AtLeastOnce, self.retry_policy.clone())
+            .with_retry_policy(qos::QosLevel::ExactlyOnce, self.retry_policy.clone())
+    }
+}
+
+/// Telemetry retention and downsampling settings
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetentionConfig {
+    /// Days raw readings are kept before being rolled up into hourly aggregates
+    pub raw_retention_days: u32,
+    /// Days hourly aggregates are kept before being rolled up into daily aggregates
+    pub hourly_retention_days: u32,
+    /// Days daily aggregates are kept
+    pub daily_retention_days: u32,
+    /// How often the retention job runs
+    pub interval: Duration,
+    /// Aggregations stored for metrics without an override
+    pub default_aggregations: Vec<Aggregation>,
+    /// Per-metric aggregation overrides
+    pub metric_aggregations: HashMap<String, Vec<Aggregation>>,
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            raw_retention_days: 7,
+            hourly_retention_days: 90,
+            daily_retention_days: 730,
+            interval: Duration::from_secs(3600),
+            default_aggregations: vec![Aggregation::Avg, Aggregation::Min, Aggregation::Max],
+            metric_aggregations: HashMap::new(),
+        }
+    }
+}
+
+impl RetentionConfig {
+    /// Load retention settings from the environment
+    ///
+    /// Reads `TELEMETRY_RAW_RETENTION_DAYS`, `TELEMETRY_HOURLY_RETENTION_DAYS`,
+    /// `TELEMETRY_DAILY_RETENTION_DAYS`, `TELEMETRY_RETENTION_INTERVAL_SECS`,
+    /// `TELEMETRY_AGGREGATIONS` (e.g. `avg,max`) and `TELEMETRY_METRIC_AGGREGATIONS`
+    /// (e.g. `temperature=avg|min|max,door.open=max`).
+    pub fn from_env() -> Result<Self> {
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    /// Load retention settings from a variable lookup
+    pub fn from_vars(lookup: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let defaults = Self::default();
+        let invalid = |name: &str, value: &str| {
+            UaipError::InvalidConfiguration(format!("Invalid {}: {}", name, value))
+        };
+        let days = |name: &str, default: u32| -> Result<u32> {
+            match lookup(name) {
+                Some(value) => value.trim().parse().map_err(|_| invalid(name, &value)),
+                None => Ok(default),
+            }
+        };
+        let aggregations = |name: &str, value: &str, separator: char| -> Result<Vec<Aggregation>> {
+            let aggregations = value
+                .split(separator)
+                .map(|item| item.trim().parse())
+                .collect::<Result<Vec<Aggregation>>>()
+                .map_err(|_| invalid(name, value))?;
+            Ok(aggregations)
+        };
+
+        let interval = match lookup("TELEMETRY_RETENTION_INTERVAL_SECS") {
+            Some(value) => match value.trim().parse() {
+                Ok(secs) if secs > 0 => Duration::from_secs(secs),
+                _ => return Err(invalid("TELEMETRY_RETENTION_INTERVAL_SECS", &value)),
+            },
+            None => defaults.interval,
+        };
+        let default_aggregations = match lookup("TELEMETRY_AGGREGATIONS") {
+            Some(value) => aggregations("TELEMETRY_AGGREGATIONS", &value, ',')?,
+            None => defaults.default_aggregations,
+        };
+        let mut metric_aggregations = HashMap::new();
+        if let Some(value) = lookup("TELEMETRY_METRIC_AGGREGATIONS") {
+            for entry in value.split(',').filter(|entry| !entry.trim().is_empty()) {
+                let (metric, list) = entry
+                    .split_once('=')
+                    .ok_or_else(|| invalid("TELEMETRY_METRIC_AGGREGATIONS", &value))?;
+                metric_aggregations.insert(
+                    metric.trim().to_string(),
+                    aggregations("TELEMETRY_METRIC_AGGREGATIONS", list, '|')?,
+                );
+            }
+        }
+
+        let config = Self {
+            raw_retention_days: days("TELEMETRY_RAW_RETENTION_DAYS", defaults.raw_retention_days)?,
+            hourly_retention_days: days(
+                "TELEMETRY_HOURLY_RETENTION_DAYS",
+                defaults.hourly_retention_days,
+            )?,
+            daily_retention_days: days(
+                "TELEMETRY_DAILY_RETENTION_DAYS",
+                defaults.daily_retention_days,
+            )?,
+            interval,
+            default_aggregations,
+            metric_aggregations,
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Check that each tier keeps data at least as long as the one before it
+    pub fn validate(&self) -> Result<()> {
+        if self.raw_retention_days == 0 {
+            return Err(UaipError::InvalidConfiguration(
+                "raw telemetry retention must be at least 1 day".to_string(),
+            ));
+        }
+        if self.hourly_retention_days < self.raw_retention_days
+            || self.daily_retention_days < self.hourly_retention_days
+        {
+            return Err(UaipError::InvalidConfiguration(
+                "telemetry retention must not shrink from raw to hourly to daily".to_string(),
+            ));
+        }
+        if self.default_aggregations.is_empty()
+            || self.metric_aggregations.values().any(Vec::is_empty)
+        {
+            return Err(UaipError::InvalidConfiguration(
+                "telemetry aggregations cannot be empty".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Aggregations stored for a metric
+    pub fn aggregations_for(&self, metric: &str) -> &[Aggregation] {
+        self.metric_aggregations
+            .get(metric)
+            .unwrap_or(&self.default_aggregations)
+    }
+
+    /// Cutoffs before which raw rows, hourly and daily aggregates expire
+    ///
+    /// Cutoffs are aligned to bucket boundaries so only complete buckets are rolled up.
+    pub fn cutoffs(&self, now: DateTime<Utc>) -> RetentionCutoffs {
+        let days = |n: u32| chrono::Duration::days(i64::from(n));
+        RetentionCutoffs {
+            raw: Resolution::Hour.bucket_start(now - days(self.raw_retention_days)),
+            hourly: Resolution::Day.bucket_start(now - days(self.hourly_retention_days)),
+            daily: Resolution::Day.bucket_start(now - days(self.daily_retention_days)),
+        }
+    }
+}
+
+/// Media processing settings
+#[derive(Debug, Clone, PartialEq)]
+pub struct MediaProcessingConfig {
+    /// Directory generated media (e.g. thumbnails) is written to
+    pub storage_dir: PathBuf,
+    /// URL prefix the storage directory is served from
+    pub base_url: String,
+    /// ffmpeg binary used to extract video frames
+    pub ffmpeg_path: PathBuf,
+    /// ffprobe binary used to read uploaded file metadata
+    pub ffprobe_path: PathBuf,
+}
+
+impl Default for MediaProcessingConfig {
+    fn default() -> Self {
+        Self {
+            storage_dir: PathBuf::from("data/media"),
+            base_url: "/media".to_string(),
+            ffmpeg_path: PathBuf::from("ffmpeg"),
+            ffprobe_path: PathBuf::from("ffprobe"),
+        }
+    }
+}
+
+impl MediaProcessingConfig {
+    /// Load media processing settings from the environment
+    ///
+    /// Reads `MEDIA_STORAGE_DIR`, `MEDIA_BASE_URL`, `FFMPEG_PATH` and `FFPROBE_PATH`;
+    /// unset values keep their defaults.
+    pub fn from_env() -> Result<Self> {
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    /// Load media processing settings from a variable lookup
+    pub fn from_vars(lookup: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let defaults = Self::default();
+        let non_empty = |name: &str| -> Result<Option<String>> {
+            match lookup(name).map(|value| value.trim().to_string()) {
+                Some(value) if value.is_empty() => Err(UaipError::InvalidConfiguration(format!(
+                    "Invalid {}: value is empty",
+                    name
+                ))),
+                value => Ok(value),
+            }
+        };
+
+        Ok(Self {
+            storage_dir: non_empty("MEDIA_STORAGE_DIR")?
+                .map_or(defaults.storage_dir, PathBuf::from),
+            base_url: non_empty("MEDIA_BASE_URL")?.unwrap_or(defaults.base_url),
+            ffmpeg_path: non_empty("FFMPEG_PATH")?.map_or(defaults.ffmpeg_path, PathBuf::from),
+            ffprobe_path: non_empty("FFPROBE_PATH")?.map_or(defaults.ffprobe_path, PathBuf::from),
+        })
+    }
+
+    /// Build a thumbnail generator using ffmpeg and local storage
+    pub fn thumbnail_generator(&self) -> ThumbnailGenerator {
+        ThumbnailGenerator::new(
+            Arc::new(FfmpegFrameExtractor::new(&self.ffmpeg_path)),
+            Arc::new(LocalMediaStorage::new(&self.storage_dir, &self.base_url)),
+        )
+    }
+
+    /// Build a metadata analyzer using ffprobe
+    pub fn metadata_analyzer(&self) -> MetadataAnalyzer {
+        MetadataAnalyzer::new(Arc::new(FfprobeProber::new(&self.ffprobe_path)))
+    }
+}
+
+/// SMTP relay used by the `email` notification channel
+#[derive(Clone, PartialEq)]
+pub struct SmtpConfig {
+    /// Relay host, reached over STARTTLS
+    pub relay: String,
+    /// Sender address
+    pub from: String,
+    /// Username and password, if the relay requires authentication
+    pub credentials: Option<(String, String)>,
+}
+
+impl std::fmt::Debug for SmtpConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SmtpConfig")
+            .field("relay", &self.relay)
+            .field("from", &self.from)
+            .field(
+                "username",
+                &self.credentials.as_ref().map(|(username, _)| username),
+            )
+            .finish()
+    }
+}
+
+/// Scenario notification channel settings
+#[derive(Debug, Clone, PartialEq)]
+pub struct NotificationConfig {
+    /// Request timeout of the `webhook` channel
+    pub webhook_timeout: Duration,
+    /// Relay of the `email` channel; the channel is unavailable without one
+    pub smtp: Option<SmtpConfig>,
+}
+
+impl Default for NotificationConfig {
+    fn default() -> Self {
+        Self {
+            webhook_timeout: Duration::from_secs(10),
+            smtp: None,
+        }
+    }
+}
+
+impl NotificationConfig {
+    /// Load notification settings from the environment
+    ///
+    /// Reads `NOTIFY_WEBHOOK_TIMEOUT_SECS`, and `SMTP_RELAY` with `SMTP_FROM` (plus
+    /// optional `SMTP_USERNAME` and `SMTP_PASSWORD`) to enable email.
+    pub fn from_env() -> Result<Self> {
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    /// Load notification settings from a variable lookup
+    pub fn from_vars(lookup: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let defaults = Self::default();
+        let invalid = |name: &str, value: &str| {
+            UaipError::InvalidConfiguration(format!("Invalid {}: {}", name, value))
+        };
+        let set = |name: &str| {
+            lookup(name)
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+
+        let webhook_timeout = match lookup("NOTIFY_WEBHOOK_TIMEOUT_SECS") {
+            Some(value) => match value.trim().parse() {
+                Ok(secs) if secs > 0 => Duration::from_secs(secs),
+                _ => return Err(invalid("NOTIFY_WEBHOOK_TIMEOUT_SECS", &value)),
+            },
+            None => defaults.webhook_timeout,
+        };
+        let credentials = match (
+            set("SMTP_USERNAME"),
+            lookup("SMTP_PASSWORD").filter(|password| !password.is_empty()),
+        ) {
+            (Some(username), Some(password)) => Some((username, password)),
+            (None, None) => None,
+            _ => {
+                return Err(UaipError::InvalidConfiguration(
+                    "SMTP_USERNAME and SMTP_PASSWORD must be set together".to_string(),
+                ))
+            }
+        };
+        let smtp = match (set("SMTP_RELAY"), set("SMTP_FROM")) {
+            (Some(relay), Some(from)) => Some(SmtpConfig {
+                relay,
+                from,
+                credentials,
+            }),
+            (None, None) if credentials.is_none() => None,
+            _ => {
+                return Err(UaipError::InvalidConfiguration(
+                    "SMTP_RELAY and SMTP_FROM are both required for email notifications"
+                        .to_string(),
+                ))
+            }
+        };
+
+        Ok(Self {
+            webhook_timeout,
+            smtp,
+        })
+    }
+
+    /// Build a scenario engine with the log, webhook and (if configured) email channels
+    pub fn scenario_engine(&self) -> Result<ScenarioEngine> {
+        let mut engine = ScenarioEngine::new()
+            .with_notifier(Arc::new(WebhookNotifier::new(self.webhook_timeout)?));
+        if let Some(smtp) = &self.smtp {
+            engine = engine.with_notifier(Arc::new(EmailNotifier::new(
+                &smtp.relay,
+                &smtp.from,
+                smtp.credentials.clone(),
+            )?));
+        }
+        Ok(engine)
+    }
+}
+
+impl ContextLimits {
+    /// Load AI session context limits from the environment
+    ///
+    /// Reads `AI_SESSION_MAX_CONTEXT_BYTES` and `AI_SESSION_CONTEXT_TRUNCATION`
+    /// (`drop_oldest` or `reject`); unset values keep their defaults.
+    pub fn from_env() -> Result<Self> {
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    /// Load AI session context limits from a variable lookup
+    pub fn from_vars(lookup: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let defaults = Self::default();
+        let invalid = |name: &str, value: &str| {
+            UaipError::InvalidConfiguration(format!("Invalid {}: {}", name, value))
+        };
+
+        let max_context_bytes = match lookup("AI_SESSION_MAX_CONTEXT_BYTES") {
+            Some(value) => value
+                .trim()
+                .parse()
+                .ok()
+                .filter(|bytes| *bytes > 0)
+                .ok_or_else(|| invalid("AI_SESSION_MAX_CONTEXT_BYTES", &value))?,
+            None => defaults.max_context_bytes,
+        };
+        let truncation_policy = match lookup("AI_SESSION_CONTEXT_TRUNCATION") {
+            Some(value) => match value.trim().to_ascii_lowercase().as_str() {
+                "drop_oldest" => TruncationPolicy::DropOldest,
+                "reject" => TruncationPolicy::Reject,
+                _ => return Err(invalid("AI_SESSION_CONTEXT_TRUNCATION", &value)),
+            },
+            None => defaults.truncation_policy,
+        };
+
+        Ok(Self {
+            max_context_bytes,
+            truncation_policy,
+        })
+    }
+}
+
+impl ThrottleConfig {
+    /// Load per-device command throttling from the environment
+    ///
+    /// Reads `COMMAND_RATE_PER_SECOND` and `COMMAND_BURST` for the default limit,
+    /// `COMMAND_DEVICE_TYPE_LIMITS` as `type=rate/burst` pairs (comma separated),
+    /// `COMMAND_THROTTLE_POLICY` (`reject` or `queue`) and `COMMAND_THROTTLE_MAX_DELAY_MS`
+    /// for queued commands; unset values keep their defaults.
+    pub fn from_env() -> Result<Self> {
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    /// Load per-device command throttling from a variable lookup
+    pub fn from_vars(lookup: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let defaults = Self::default();
+        let invalid = |name: &str, value: &str| {
+            UaipError::InvalidConfiguration(format!("Invalid {}: {}", name, value))
+        };
+
+        let commands_per_second = match lookup("COMMAND_RATE_PER_SECOND") {
+            Some(value) => value
+                .trim()
+                .parse()
+                .map_err(|_| invalid("COMMAND_RATE_PER_SECOND", &value))?,
+            None => defaults.default_limit.commands_per_second(),
+        };
+        let burst_size = match lookup("COMMAND_BURST") {
+            Some(value) => value
+                .trim()
+                .parse()
+                .map_err(|_| invalid("COMMAND_BURST", &value))?,
+            None => defaults.default_limit.burst_size(),
+        };
+        let mut config = Self {
+            default_limit: DeviceRateLimit::new(commands_per_second, burst_size)?,
+            ..defaults
+        };
+
+        if let Some(value) = lookup("COMMAND_DEVICE_TYPE_LIMITS") {
+            for entry in value.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+                let (device_type, rate, burst) = entry
+                    .split_once('=')
+                    .and_then(|(device_type, limit)| {
+                        let (rate, burst) = limit.split_once('/')?;
+                        Some((device_type.trim(), rate.trim(), burst.trim()))
+                    })
+                    .filter(|(device_type, _, _)| !device_type.is_empty())
+                    .ok_or_else(|| invalid("COMMAND_DEVICE_TYPE_LIMITS", entry))?;
+                let limit = DeviceRateLimit::new(
+                    rate.parse()
+                        .map_err(|_| invalid("COMMAND_DEVICE_TYPE_LIMITS", entry))?,
+                    burst
+                        .parse()
+                        .map_err(|_| invalid("COMMAND_DEVICE_TYPE_LIMITS", entry))?,
+                )?;
+                config = config.with_device_type_limit(device_type, limit);
+            }
+        }
+
+        if let Some(value) = lookup("COMMAND_THROTTLE_POLICY") {
+            let policy = match value.trim().to_ascii_lowercase().as_str() {
+                "reject" => ThrottlePolicy::Reject,
+                "queue" => {
+                    let max_delay_ms = match lookup("COMMAND_THROTTLE_MAX_DELAY_MS") {
+                        Some(delay) => delay
+                            .trim()
+                            .parse()
+                            .map_err(|_| invalid("COMMAND_THROTTLE_MAX_DELAY_MS", &delay))?,
+                        None => 1000,
+                    };
+                    ThrottlePolicy::Queue {
+                        max_delay: Duration::from_millis(max_delay_ms),
+                    }
+                }
+                _ => return Err(invalid("COMMAND_THROTTLE_POLICY", &value)),
+            };
+            config = config.with_policy(policy);
+        }
+
+        Ok(config)
+    }
+}
+
+/// Adapter instances and scheduled polls, loaded from a JSON file
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PollingConfig {
+    #[serde(default)]
+    pub adapters: Vec<AdapterConfig>,
+    #[serde(default)]
+    pub polls: Vec<PollConfig>,
+}
+
+/// A named adapter instance created at startup
+#[derive(Debug, Clone, Deserialize)]
+pub struct AdapterConfig {
+    pub name: String,
+    #[serde(flatten)]
+    pub settings: AdapterSettings,
+}
+
+/// Protocol-specific adapter settings, tagged by `type`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum AdapterSettings {
+    #[cfg(feature = "modbus")]
+    Modbus(ModbusConfig),
+    #[cfg(feature = "opcua")]
+    OpcUa(OpcUaConfig),
+}
+
+impl PollingConfig {
+    /// Load the file named by `POLLING_CONFIG`; unset means no adapters and no polls
+    pub fn from_env() -> Result<Self> {
+        match std::env::var("POLLING_CONFIG") {
+            Ok(path) => Self::from_file(path),
+            Err(_) => Ok(Self::default()),
+        }
+    }
+
+    /// Load polling settings from a JSON file
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path).map_err(|e| {
+            UaipError::InvalidConfiguration(format!(
+                "Failed to read polling config {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        Self::from_json(&json)
+    }
+
+    /// Parse polling settings from JSON
+    pub fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json)
+            .map_err(|e| UaipError::InvalidConfiguration(format!("Invalid polling config: {}", e)))
+    }
+
+    /// Create and register the configured adapters
+    // Without the modbus and opcua features there are no adapter settings to match
+    #[cfg_attr(
+        not(any(feature = "modbus", feature = "opcua")),
+        allow(unused_mut, clippy::never_loop)
+    )]
+    pub fn adapter_registry(&self) -> Result<AdapterRegistry> {
+        let mut registry = AdapterRegistry::new();
+        for adapter in &self.adapters {
+            match adapter.settings {
+                #[cfg(feature = "modbus")]
+                AdapterSettings::Modbus(ref config) => {
+                    registry.register(&adapter.name, ModbusAdapter::new(config.clone())?)?
+                }
+                #[cfg(feature = "opcua")]
+                AdapterSettings::OpcUa(ref config) => {
+                    registry.register(&adapter.name, OpcUaAdapter::new(config.clone())?)?
+                }
+            };
+        }
+        Ok(registry)
+    }
+}
+
+/// Load the network configuration file named by `NETWORK_CONFIG`; unset means the
+/// defaults, with no adapter endpoints
+pub fn network_config_from_env() -> Result<NetworkConfig> {
+    match std::env::var("NETWORK_CONFIG") {
+        Ok(path) => network_config_from_file(path),
+        Err(_) => Ok(NetworkConfig::default()),
+    }
+}
+
+/// Load a network configuration from a JSON file
+pub fn network_config_from_file(path: impl AsRef<Path>) -> Result<NetworkConfig> {
+    let path = path.as_ref();
+    let json = std::fs::read_to_string(path).map_err(|e| {
+        UaipError::InvalidConfiguration(format!(
+            "Failed to read network config {}: {}",
+            path.display(),
+            e
+        ))
+    })?;
+    network_config_from_json(&json)
+}
+
+/// Parse a network configuration from JSON
+pub fn network_config_from_json(json: &str) -> Result<NetworkConfig> {
+    serde_json::from_str(json)
+        .map_err(|e| UaipError::InvalidConfiguration(format!("Invalid network config: {}", e)))
+}
+
+/// Load connection pool settings from the environment
+///
+/// Reads `POOL_MAX_CONNECTIONS`, `POOL_MAX_IDLE_CONNECTIONS`, `POOL_IDLE_TIMEOUT_SECS`,
+/// `POOL_MAX_LIFETIME_SECS` and `POOL_ENABLED`; unset values keep their defaults.
+pub fn connection_pool_from_env() -> Result<ConnectionPoolConfig> {
+    connection_pool_from_vars(|name| std::env::var(name).ok())
+}
+
+/// Load connection pool settings from a variable lookup
+pub fn connection_pool_from_vars(
+    lookup: impl Fn(&str) -> Option<String>,
+) -> Result<ConnectionPoolConfig> {
+    fn parse<T: std::str::FromStr>(
+        lookup: &impl Fn(&str) -> Option<String>,
+        name: &str,
+        default: T,
+    ) -> Result<T> {
+        match lookup(name) {
+            Some(value) => value.trim().parse().map_err(|_| {
+                UaipError::InvalidConfiguration(format!("Invalid {}: {}", name, value))
+            }),
+            None => Ok(default),
+        }
+    }
+
+    let defaults = ConnectionPoolConfig::default();
+    let config = ConnectionPoolConfig {
+        max_connections_per_endpoint: parse(
+            &lookup,
+            "POOL_MAX_CONNECTIONS",
+            defaults.max_connections_per_endpoint,
+        )?,
+        max_idle_connections: parse(
+            &lookup,
+            "POOL_MAX_IDLE_CONNECTIONS",
+            defaults.max_idle_connections,
+        )?,
+        idle_timeout_secs: parse(
+            &lookup,
+            "POOL_IDLE_TIMEOUT_SECS",
+            defaults.idle_timeout_secs,
+        )?,
+        max_lifetime_secs: parse(
+            &lookup,
+            "POOL_MAX_LIFETIME_SECS",
+            defaults.max_lifetime_secs,
+        )?,
+        enabled: parse(&lookup, "POOL_ENABLED", defaults.enabled)?,
+    };
+    config.validate()?;
+
+    Ok(config)
+}
+
+/// Build Postgres pool options from validated pool settings
+pub fn pg_pool_options(config: &ConnectionPoolConfig) -> Result<PgPoolOptions> {
+    config.validate()?;
+
+    let max_connections = u32::try_from(config.max_connections()).map_err(|_| {
+        UaipError::InvalidConfiguration(format!(
+            "max_connections_per_endpoint too large: {}",
+            config.max_connections_per_endpoint
+        ))
+    })?;
+
+    Ok(PgPoolOptions::new()
+        .max_connections(max_connections)
+        .idle_timeout(config.idle_timeout())
+        .max_lifetime(Some(config.max_lifetime())))
+}
+
+/// Build Redis connection manager settings from validated pool settings
+///
+/// The connection manager multiplexes one connection, so the connection limit caps
+/// concurrent in-flight requests instead.
+pub fn redis_manager_config(config: &ConnectionPoolConfig) -> Result<ConnectionManagerConfig> {
+    config.validate()?;
+
+    Ok(ConnectionManagerConfig::new().set_concurrency_limit(config.max_connections()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn vars(pairs: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    #[test]
+    fn test_parse_bind_addr() {
+        assert_eq!(
+            parse_bind_addr("0.0.0.0:9000").unwrap(),
+            "0.0.0.0:9000".parse::<SocketAddr>().unwrap()
+        );
+        assert_eq!(
+            parse_bind_addr("[::]:8080").unwrap(),
+            "[::]:8080".parse::<SocketAddr>().unwrap()
+        );
+        assert_eq!(
+            parse_bind_addr(" 10.0.0.5 ").unwrap(),
+            "10.0.0.5:8443".parse::<SocketAddr>().unwrap()
+        );
+
+        for invalid in ["", "localhost:8443", "0.0.0.0:99999", "0.0.0.0:port"] {
+            assert!(matches!(
+                parse_bind_addr(invalid),
+                Err(UaipError::InvalidConfiguration(_))
+            ));
+        }
+
+        assert_eq!(
+            parse_grpc_bind_addr(" 10.0.0.5 ").unwrap(),
+            "10.0.0.5:50051".parse::<SocketAddr>().unwrap()
+        );
+        let err = parse_grpc_bind_addr("grpc").unwrap_err();
+        assert!(err.to_string().contains("GRPC_BIND_ADDR"));
+    }
+
+    #[test]
+    fn test_parse_interval_secs() {
+        assert_eq!(
+            parse_interval_secs("WATCHDOG_INTERVAL_SECS", " 45 ").unwrap(),
+            Duration::from_secs(45)
+        );
+        for invalid in ["0", "-5", "soon"] {
+            let err = parse_interval_secs("WATCHDOG_INTERVAL_SECS", invalid).unwrap_err();
+            assert!(err.to_string().contains("WATCHDOG_INTERVAL_SECS"));
+        }
+    }
+
+    #[test]
+    fn test_network_config_from_json() {
+        let config = network_config_from_json(
+            r#"{"adapters": {"gateway": {
+                "adapter_type": "http",
+                "connection_string": "http://10.0.5.9:8080",
+                "protocol": "http",
+                "timeout_ms": 1500,
+                "max_retries": 0,
+                "retry_delay_ms": 0
+            }}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.get_adapter("gateway").unwrap().timeout(),
+            Duration::from_millis(1500)
+        );
+        assert!(network_config_from_json(r#"{"adapters": {"x": {}}}"#).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_listener_binds_configured_addr() {
+        let addr = parse_bind_addr("127.0.0.1:0").unwrap();
+        let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
+        let local = listener.local_addr().unwrap();
+        assert_eq!(local.ip(), addr.ip());
+        assert_ne!(local.port(), 0);
+    }
+
+    #[test]
+    fn test_cors_config_from_vars() {
+        let production = CorsConfig::from_vars(vars(&[])).unwrap();
+        assert!(production.allowed_origins.is_empty());
+
+        let dev = CorsConfig::from_vars(vars(&[("DEV_MODE", "true")])).unwrap();
+        assert_eq!(dev, CorsConfig::permissive());
+
+        let dashboard = CorsConfig::from_vars(vars(&[
+            (
+                "CORS_ALLOWED_ORIGINS",
+                "https://dashboard.example.com, https://ops.example.com",
+            ),
+            ("CORS_ALLOW_CREDENTIALS", "true"),
+        ]))
+        .unwrap();
+        assert_eq!(dashboard.allowed_origins.len(), 2);
+        assert!(dashboard.allow_credentials);
+
+        // Credentials with a wildcard is rejected up front rather than panicking later
+        let wildcard_credentials =
+            vars(&[("DEV_MODE", "true"), ("CORS_ALLOW_CREDENTIALS", "true")]);
+        assert!(CorsConfig::from_vars(wildcard_credentials).is_err());
+        let bad_method = vars(&[("CORS_ALLOWED_METHODS", "GET,NOT A METHOD")]);
+        assert!(CorsConfig::from_vars(bad_method).is_err());
+    }
+
+    #[test]
+    fn test_messaging_config_from_vars() {
+        assert_eq!(
+            MessagingConfig::from_vars(vars(&[])).unwrap(),
+            MessagingConfig::default()
+        );
+
+        let config = MessagingConfig::from_vars(vars(&[
+            ("DEFAULT_MESSAGE_TTL_MS", "30000"),
+            ("DEFAULT_MESSAGE_QOS", "exactly_once"),
+            ("DEFAULT_MESSAGE_PRIORITY", "high"),
+            ("MESSAGE_MAX_ATTEMPTS", "5"),
+        ]))
+        .unwrap();
+        assert_eq!(config.default_ttl_ms, 30000);
+        assert_eq!(config.default_qos, QosLevel::ExactlyOnce);
+        assert_eq!(config.default_priority, Priority::High);
+        assert_eq!(config.retry_policy.max_attempts, 5);
+
+        for (name, value) in [
+            ("DEFAULT_MESSAGE_TTL_MS", "0"),
+            ("DEFAULT_MESSAGE_QOS", "twice"),
+            ("DEFAULT_MESSAGE_PRIORITY", "urgent"),
+            ("MESSAGE_MAX_ATTEMPTS", "0"),
+        ] {
+            let err = MessagingConfig::from_vars(vars(&[(name, value)])).unwrap_err();
+            assert!(err.to_string().contains(name), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_context_limits_from_vars() {
+        let limits = ContextLimits::from_vars(vars(&[])).unwrap();
+        assert_eq!(
+            limits.max_context_bytes,
+            ContextLimits::default().max_context_bytes
+        );
+        assert_eq!(limits.truncation_policy, TruncationPolicy::DropOldest);
+
+        let limits = ContextLimits::from_vars(vars(&[
+            ("AI_SESSION_MAX_CONTEXT_BYTES", "4096"),
+            ("AI_SESSION_CONTEXT_TRUNCATION", "Reject"),
+        ]))
+        .unwrap();
+        assert_eq!(limits.max_context_bytes, 4096);
+        assert_eq!(limits.truncation_policy, TruncationPolicy::Reject);
+
+        for (name, value) in [
+            ("AI_SESSION_MAX_CONTEXT_BYTES", "0"),
+            ("AI_SESSION_CONTEXT_TRUNCATION", "drop_newest"),
+        ] {
+            let err = ContextLimits::from_vars(vars(&[(name, value)])).unwrap_err();
+            assert!(err.to_string().contains(name), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_throttle_config_from_vars() {
+        let config = ThrottleConfig::from_vars(vars(&[])).unwrap();
+        assert_eq!(config.default_limit, DeviceRateLimit::default());
+        assert!(config.device_type_limits.is_empty());
+        assert_eq!(config.policy, ThrottlePolicy::Reject);
+
+        let config = ThrottleConfig::from_vars(vars(&[
+            ("COMMAND_RATE_PER_SECOND", "2.5"),
+            ("COMMAND_BURST", "4"),
+            ("COMMAND_DEVICE_TYPE_LIMITS", "camera=1/2, valve = 0.5/1"),
+            ("COMMAND_THROTTLE_POLICY", "queue"),
+            ("COMMAND_THROTTLE_MAX_DELAY_MS", "250"),
+        ]))
+        .unwrap();
+        assert_eq!(config.default_limit, DeviceRateLimit::new(2.5, 4).unwrap());
+        assert_eq!(
+            config.device_type_limits.get("camera"),
+            Some(&DeviceRateLimit::new(1.0, 2).unwrap())
+        );
+        assert_eq!(
+            config.device_type_limits.get("valve"),
+            Some(&DeviceRateLimit::new(0.5, 1).unwrap())
+        );
+        assert_eq!(
+            config.policy,
+            ThrottlePolicy::Queue {
+                max_delay: Duration::from_millis(250)
+            }
+        );
+
+        for (name, value) in [
+            ("COMMAND_RATE_PER_SECOND", "fast"),
+            ("COMMAND_BURST", "-1"),
+            ("COMMAND_DEVICE_TYPE_LIMITS", "camera=1"),
+            ("COMMAND_DEVICE_TYPE_LIMITS", "=1/2"),
+            ("COMMAND_THROTTLE_POLICY", "drop"),
+        ] {
+            let err = ThrottleConfig::from_vars(vars(&[(name, value)])).unwrap_err();
+            assert!(err.to_string().contains(name), "{}", err);
+        }
+        let bad_delay = vars(&[
+            ("COMMAND_THROTTLE_POLICY", "queue"),
+            ("COMMAND_THROTTLE_MAX_DELAY_MS", "soon"),
+        ]);
+        assert!(ThrottleConfig::from_vars(bad_delay).is_err());
+        assert!(ThrottleConfig::from_vars(vars(&[("COMMAND_RATE_PER_SECOND", "0")])).is_err());
+        assert!(
+            ThrottleConfig::from_vars(vars(&[("COMMAND_DEVICE_TYPE_LIMITS", "camera=1/0")]))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_compression_config_from_vars() {
+        let config = CompressionConfig::from_vars(vars(&[])).unwrap();
+        assert_eq!(config, CompressionConfig::default());
+
+        let config = CompressionConfig::from_vars(vars(&[
+            ("COMPRESSION_MIN_SIZE_BYTES", "256"),
+            (
+                "COMPRESSION_CONTENT_TYPES",
+                "application/json, Application/XML",
+            ),
+        ]))
+        .unwrap();
+        assert_eq!(config.min_size_bytes, 256);
+        assert_eq!(
+            config.content_types,
+            vec!["application/json", "application/xml"]
+        );
+
+        assert!(
+            CompressionConfig::from_vars(vars(&[("COMPRESSION_MIN_SIZE_BYTES", "1MB")])).is_err()
+        );
+        assert!(
+            CompressionConfig::from_vars(vars(&[("COMPRESSION_CONTENT_TYPES", "json")])).is_err()
+        );
+    }
+
+    #[test]
+    fn test_content_type_allowlist() {
+        let allowlist =
+            ContentTypeAllowlist(["application/json", "text/*"].map(String::from).into());
+        let response = |content_type: &str| {
+            axum::http::Response::builder()
+                .header("content-type", content_type)
+                .body(axum::body::Body::empty())
+                .unwrap()
+        };
+
+        assert!(allowlist.should_compress(&response("application/json")));
+        assert!(allowlist.should_compress(&response("text/plain; charset=utf-8")));
+        assert!(!allowlist.should_compress(&response("video/mp4")));
+        assert!(!allowlist.should_compress(&response("application/jsonx")));
+        assert!(!allowlist.should_compress(&axum::http::Response::new(axum::body::Body::empty())));
+    }
+
+    #[test]
+    fn test_retention_config_from_vars() {
+        let config = RetentionConfig::from_vars(vars(&[])).unwrap();
+        assert_eq!(config, RetentionConfig::default());
+
+        let config = RetentionConfig::from_vars(vars(&[
+            ("TELEMETRY_RAW_RETENTION_DAYS", "3"),
+            ("TELEMETRY_AGGREGATIONS", "avg"),
+            (
+                "TELEMETRY_METRIC_AGGREGATIONS",
+                "door.open=max, energy=min|max",
+            ),
+        ]))
+        .unwrap();
+        assert_eq!(config.raw_retention_days, 3);
+        assert_eq!(config.aggregations_for("temperature"), &[Aggregation::Avg]);
+        assert_eq!(config.aggregations_for("door.open"), &[Aggregation::Max]);
+        assert_eq!(
+            config.aggregations_for("energy"),
+            &[Aggregation::Min, Aggregation::Max]
+        );
+
+        assert!(RetentionConfig::from_vars(vars(&[("TELEMETRY_AGGREGATIONS", "median")])).is_err());
+        assert!(
+            RetentionConfig::from_vars(vars(&[("TELEMETRY_RAW_RETENTION_DAYS", "0")])).is_err()
+        );
+        assert!(
+            RetentionConfig::from_vars(vars(&[("TELEMETRY_HOURLY_RETENTION_DAYS", "1")])).is_err()
+        );
+    }
+
+    #[test]
+    fn test_retention_cutoffs_align_to_buckets() {
+        let config = RetentionConfig::default();
+        let now = "2026-03-20T14:35:10Z".parse::<DateTime<Utc>>().unwrap();
+
+        let cutoffs = config.cutoffs(now);
+        assert_eq!(cutoffs.raw.to_rfc3339(), "2026-03-13T14:00:00+00:00");
+        assert_eq!(cutoffs.hourly.to_rfc3339(), "2025-12-20T00:00:00+00:00");
+        assert_eq!(cutoffs.daily.to_rfc3339(), "2024-03-20T00:00:00+00:00");
+    }
+
+    #[test]
+    fn test_pool_options_reflect_config() {
+        let config = connection_pool_from_vars(vars(&[
+            ("POOL_MAX_CONNECTIONS", "25"),
+            ("POOL_IDLE_TIMEOUT_SECS", "60"),
+            ("POOL_MAX_LIFETIME_SECS", "900"),
+        ]))
+        .unwrap();
+        assert_eq!(config.max_connections_per_endpoint, 25);
+
+        let options = pg_pool_options(&config).unwrap();
+        assert_eq!(options.get_max_connections(), 25);
+        assert_eq!(options.get_idle_timeout(), Some(Duration::from_secs(60)));
+        assert_eq!(options.get_max_lifetime(), Some(Duration::from_secs(900)));
+
+        assert!(redis_manager_config(&config).is_ok());
+    }
+
+    #[test]
+    fn test_pool_config_defaults_and_errors() {
+        let config = connection_pool_from_vars(vars(&[])).unwrap();
+        assert_eq!(
+            pg_pool_options(&config).unwrap().get_max_connections(),
+            ConnectionPoolConfig::default().max_connections_per_endpoint as u32
+        );
+
+        assert!(connection_pool_from_vars(vars(&[("POOL_MAX_CONNECTIONS", "many")])).is_err());
+        assert!(connection_pool_from_vars(vars(&[("POOL_MAX_CONNECTIONS", "0")])).is_err());
+
+        let disabled = connection_pool_from_vars(vars(&[("POOL_ENABLED", "false")])).unwrap();
+        assert_eq!(pg_pool_options(&disabled).unwrap().get_max_connections(), 1);
+    }
+
+    #[test]
+    fn test_media_processing_config_from_vars() {
+        assert_eq!(
+            MediaProcessingConfig::from_vars(vars(&[])).unwrap(),
+            MediaProcessingConfig::default()
+        );
+
+        let config = MediaProcessingConfig::from_vars(vars(&[
+            ("MEDIA_STORAGE_DIR", "/var/lib/uaip/media"),
+            ("MEDIA_BASE_URL", "https://cdn.example.com/media"),
+            ("FFMPEG_PATH", "/usr/local/bin/ffmpeg"),
+            ("FFPROBE_PATH", "/usr/local/bin/ffprobe"),
+        ]))
+        .unwrap();
+        assert_eq!(config.storage_dir, PathBuf::from("/var/lib/uaip/media"));
+        assert_eq!(config.base_url, "https://cdn.example.com/media");
+        assert_eq!(config.ffmpeg_path, PathBuf::from("/usr/local/bin/ffmpeg"));
+        assert_eq!(config.ffprobe_path, PathBuf::from("/usr/local/bin/ffprobe"));
+
+        assert!(matches!(
+            MediaProcessingConfig::from_vars(vars(&[("FFMPEG_PATH", " ")])),
+            Err(UaipError::InvalidConfiguration(_))
+        ));
+    }
+
+    #[test]
+    fn test_notification_config_from_vars() {
+        assert_eq!(
+            NotificationConfig::from_vars(vars(&[])).unwrap(),
+            NotificationConfig::default()
+        );
+
+        let config = NotificationConfig::from_vars(vars(&[
+            ("NOTIFY_WEBHOOK_TIMEOUT_SECS", "3"),
+            ("SMTP_RELAY", "smtp.example.com"),
+            ("SMTP_FROM", "UAIP <alerts@example.com>"),
+            ("SMTP_USERNAME", "alerts"),
+            ("SMTP_PASSWORD", "hunter2"),
+        ]))
+        .unwrap();
+        assert_eq!(config.webhook_timeout, Duration::from_secs(3));
+        let smtp = config.smtp.clone().unwrap();
+        assert_eq!(smtp.relay, "smtp.example.com");
+        assert_eq!(
+            smtp.credentials,
+            Some(("alerts".to_string(), "hunter2".to_string()))
+        );
+        assert!(!format!("{:?}", smtp).contains("hunter2"));
+
+        for invalid in [
+            vec![("NOTIFY_WEBHOOK_TIMEOUT_SECS", "0")],
+            vec![("SMTP_RELAY", "smtp.example.com")],
+            vec![("SMTP_USERNAME", "alerts"), ("SMTP_PASSWORD", "hunter2")],
+            vec![
+                ("SMTP_RELAY", "smtp.example.com"),
+                ("SMTP_FROM", "alerts@example.com"),
+                ("SMTP_USERNAME", "alerts"),
+            ],
+        ] {
+            assert!(matches!(
+                NotificationConfig::from_vars(vars(&invalid)),
+                Err(UaipError::InvalidConfiguration(_))
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_notification_config_registers_channels() {
+        let engine = NotificationConfig::default().scenario_engine().unwrap();
+        assert_eq!(engine.notification_channels(), vec!["log", "webhook"]);
+
+        let config = NotificationConfig {
+            smtp: Some(SmtpConfig {
+                relay: "smtp.example.com".to_string(),
+                from: "alerts@example.com".to_string(),
+                credentials: None,
+            }),
+            ..Default::default()
+        };
+        let engine = config.scenario_engine().unwrap();
+        assert_eq!(
+            engine.notification_channels(),
+            vec!["email", "log", "webhook"]
+        );
+
+        let config = NotificationConfig {
+            smtp: Some(SmtpConfig {
+                relay: "smtp.example.com".to_string(),
+                from: "not an address".to_string(),
+                credentials: None,
+            }),
+            ..Default::default()
+        };
+        assert!(config.scenario_engine().is_err());
+    }
+
+    #[cfg(feature = "modbus")]
+    #[test]
+    fn test_polling_config_from_json() {
+        let config = PollingConfig::from_json(
+            r#"{
+                "adapters": [{
+                    "name": "plc-1",
+                    "type": "modbus",
+                    "server_address": "127.0.0.1:502",
+                    "unit_id": 1,
+                    "connection_timeout": 5,
+                    "read_timeout": 2,
+                    "write_timeout": 2,
+                    "max_retries": 0,
+                    "retry_delay_ms": 100
+                }],
+                "polls": [{
+                    "adapter": "plc-1",
+                    "device_id": "boiler-1",
+                    "interval_ms": 5000,
+                    "points": [{
+                        "metric": "pressure",
+                        "unit": "kPa",
+                        "target": {"protocol": "modbus", "table": "holding_register", "address": 4}
+                    }]
+                }]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(config.polls[0].points[0].metric, "pressure");
+
+        let registry = config.adapter_registry().unwrap();
+        assert_eq!(registry.iter().next().unwrap().adapter_type, "modbus");
+        assert!(registry.get("plc-1").is_some());
+
+        assert!(
+            PollingConfig::from_json(r#"{"adapters": [{"name": "x", "type": "can"}]}"#).is_err()
+        );
+        assert!(PollingConfig::from_json("").is_err());
+    }
+}
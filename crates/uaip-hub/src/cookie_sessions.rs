@@ -0,0 +1,354 @@
+//! Dashboard cookie sessions
+//!
+//! Browser dashboards can sign in to a session carried by a signed, `HttpOnly` cookie
+//! instead of keeping a bearer token where scripts can read it. Each session has a CSRF
+//! token that state-changing requests authenticated by the cookie must echo in the
+//! `X-CSRF-Token` header. Bearer-token and API-key clients are unaffected.
+
+use axum::http::{header::COOKIE, HeaderMap};
+use chrono::{DateTime, Duration, Utc};
+use ring::hmac;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use uaip_auth::jwt::Claims;
+use uaip_core::error::{Result, UaipError};
+
+use crate::api_keys::generate_key;
+
+/// Cookie carrying the signed session ID
+pub const SESSION_COOKIE: &str = "uaip_session";
+
+/// Header in which cookie-authenticated requests echo the session's CSRF token
+pub const CSRF_HEADER: &str = "x-csrf-token";
+
+/// Default session lifetime
+pub const DEFAULT_SESSION_TTL_SECS: i64 = 8 * 3600;
+
+/// Shortest accepted signing secret, in bytes
+const MIN_SECRET_LEN: usize = 32;
+
+/// Identity a session acts as
+#[derive(Debug, Clone, PartialEq)]
+pub struct CookieSession {
+    pub id: String,
+    pub subject: String,
+    pub client_id: String,
+    pub scopes: Vec<String>,
+    pub tenant_id: Option<String>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// A session just started, with the headers and token the browser needs
+#[derive(Debug, Clone)]
+pub struct SessionLogin {
+    pub session: CookieSession,
+    /// `Set-Cookie` value for the session cookie
+    pub cookie: String,
+    pub csrf_token: String,
+}
+
+/// Signed-cookie sessions, held in memory
+#[derive(Clone)]
+pub struct CookieSessions {
+    key: hmac::Key,
+    ttl: Duration,
+    secure: bool,
+    sessions: Arc<RwLock<HashMap<String, CookieSession>>>,
+}
+
+impl CookieSessions {
+    /// Sessions signed with `secret`, lasting `ttl`, with `Secure` cookies
+    pub fn new(secret: &[u8], ttl: Duration) -> Result<Self> {
+        if secret.len() < MIN_SECRET_LEN {
+            return Err(UaipError::InvalidConfiguration(format!(
+                "Session cookie secret must be at least {} bytes",
+                MIN_SECRET_LEN
+            )));
+        }
+        Ok(Self {
+            key: hmac::Key::new(hmac::HMAC_SHA256, secret),
+            ttl,
+            secure: true,
+            sessions: Arc::new(RwLock::new(HashMap::new())),
+        })
+    }
+
+    /// Whether cookies are marked `Secure` (HTTPS only); disable for plain-HTTP development
+    pub fn with_secure(mut self, secure: bool) -> Self {
+        self.secure = secure;
+        self
+    }
+
+    /// Load from `SESSION_COOKIE_SECRET`, `SESSION_COOKIE_TTL_SECS` and
+    /// `SESSION_COOKIE_SECURE`; cookie sessions are disabled when no secret is set
+    pub fn from_env() -> Result<Option<Self>> {
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    /// Load from a variable lookup
+    pub fn from_vars(lookup: impl Fn(&str) -> Option<String>) -> Result<Option<Self>> {
+        let Some(secret) = lookup("SESSION_COOKIE_SECRET") else {
+            return Ok(None);
+        };
+        let invalid = |name: &str, value: &str| {
+            UaipError::InvalidConfiguration(format!("Invalid {}: {}", name, value))
+        };
+
+        let ttl = match lookup("SESSION_COOKIE_TTL_SECS") {
+            Some(value) => value
+                .trim()
+                .parse::<i64>()
+                .ok()
+                .filter(|secs| *secs > 0)
+                .and_then(Duration::try_seconds)
+                .ok_or_else(|| invalid("SESSION_COOKIE_TTL_SECS", &value))?,
+            None => Duration::seconds(DEFAULT_SESSION_TTL_SECS),
+        };
+        let secure = match lookup("SESSION_COOKIE_SECURE") {
+            Some(value) => value
+                .trim()
+                .parse()
+                .map_err(|_| invalid("SESSION_COOKIE_SECURE", &value))?,
+            None => true,
+        };
+
+        Ok(Some(Self::new(secret.as_bytes(), ttl)?.with_secure(secure)))
+    }
+
+    /// Start a session acting as the subject of `claims`
+    pub async fn start(&self, claims: &Claims) -> Result<SessionLogin> {
+        let id = generate_key("")?;
+        let session = CookieSession {
+            id: id.clone(),
+            subject: claims.sub.clone(),
+            client_id: claims.client_id.clone(),
+            scopes: claims.scopes.clone(),
+            tenant_id: claims.tenant_id.clone(),
+            expires_at: Utc::now() + self.ttl,
+        };
+        self.sessions
+            .write()
+            .await
+            .insert(id.clone(), session.clone());
+
+        Ok(SessionLogin {
+            cookie: self.cookie(&format!("{}.{}", id, self.sign("session", &id))),
+            csrf_token: self.csrf_token(&id),
+            session,
+        })
+    }
+
+    /// Session named by a signed cookie value, if it is genuine and current
+    pub async fn authenticate(&self, cookie: &str) -> Result<CookieSession> {
+        let rejected = || UaipError::AuthenticationFailed("Invalid or expired session".to_string());
+        let (id, signature) = cookie.split_once('.').ok_or_else(rejected)?;
+        if !self.verify("session", id, signature) {
+            return Err(rejected());
+        }
+
+        let session = self.sessions.read().await.get(id).cloned();
+        match session {
+            Some(session) if session.expires_at > Utc::now() => Ok(session),
+            Some(_) => {
+                self.end(id).await;
+                Err(rejected())
+            }
+            None => Err(rejected()),
+        }
+    }
+
+    /// Check the CSRF token a request presented for a session
+    pub fn check_csrf(&self, session: &CookieSession, token: Option<&str>) -> Result<()> {
+        match token {
+            Some(token) if self.verify("csrf", &session.id, token) => Ok(()),
+            Some(_) => Err(UaipError::AuthorizationFailed(
+                "Invalid CSRF token".to_string(),
+            )),
+            None => Err(UaipError::AuthorizationFailed(
+                "Missing X-CSRF-Token header".to_string(),
+            )),
+        }
+    }
+
+    /// End a session
+    pub async fn end(&self, id: &str) {
+        self.sessions.write().await.remove(id);
+    }
+
+    /// Drop expired sessions
+    pub async fn cleanup(&self) {
+        let now = Utc::now();
+        self.sessions
+            .write()
+            .await
+            .retain(|_, session| session.expires_at > now);
+    }
+
+    /// `Set-Cookie` value that clears the session cookie
+    pub fn expired_cookie(&self) -> String {
+        self.cookie_attributes("", 0)
+    }
+
+    fn csrf_token(&self, id: &str) -> String {
+        self.sign("csrf", id)
+    }
+
+    fn cookie(&self, value: &str) -> String {
+        self.cookie_attributes(value, self.ttl.num_seconds())
+    }
+
+    fn cookie_attributes(&self, value: &str, max_age: i64) -> String {
+        let secure = if self.secure { "; Secure" } else { "" };
+        format!(
+            "{}={}; Path=/; Max-Age={}; HttpOnly; SameSite=Strict{}",
+            SESSION_COOKIE, value, max_age, secure
+        )
+    }
+
+    /// Hex HMAC of `id`, separated by purpose so a CSRF token never passes as a cookie
+    fn sign(&self, purpose: &str, id: &str) -> String {
+        let tag = hmac::sign(&self.key, format!("{}:{}", purpose, id).as_bytes());
+        tag.as_ref().iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    fn verify(&self, purpose: &str, id: &str, signature: &str) -> bool {
+        let Some(signature) = decode_hex(signature) else {
+            return false;
+        };
+        hmac::verify(
+            &self.key,
+            format!("{}:{}", purpose, id).as_bytes(),
+            &signature,
+        )
+        .is_ok()
+    }
+}
+
+/// Session cookie value in the request's `Cookie` headers, if any
+pub fn session_cookie(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get_all(COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(name, _)| *name == SESSION_COOKIE)
+        .map(|(_, value)| value)
+}
+
+fn decode_hex(value: &str) -> Option<Vec<u8>> {
+    value
+        .as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            let pair = std::str::from_utf8(pair).ok().filter(|p| p.len() == 2)?;
+            u8::from_str_radix(pair, 16).ok()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &[u8] = b"0123456789abcdef0123456789abcdef";
+
+    fn claims() -> Claims {
+        let token = crate::handlers::auth::jwt_manager(3600)
+            .generate_tenant_token(
+                "user-1",
+                "ops@example.com",
+                vec!["device:read".to_string()],
+                None,
+                Some("acme".to_string()),
+            )
+            .unwrap();
+        crate::handlers::auth::jwt_manager(3600)
+            .validate_token(&token)
+            .unwrap()
+    }
+
+    fn cookie_value(set_cookie: &str) -> &str {
+        let pair = set_cookie.split(';').next().unwrap();
+        pair.strip_prefix("uaip_session=").unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_signed_cookie_authenticates_until_logout() {
+        let sessions = CookieSessions::new(SECRET, Duration::hours(1)).unwrap();
+        let login = sessions.start(&claims()).await.unwrap();
+        assert!(login.cookie.contains("HttpOnly; SameSite=Strict; Secure"));
+
+        let session = sessions
+            .authenticate(cookie_value(&login.cookie))
+            .await
+            .unwrap();
+        assert_eq!(session.subject, "user-1");
+        assert_eq!(session.tenant_id.as_deref(), Some("acme"));
+        assert!(sessions
+            .check_csrf(&session, Some(&login.csrf_token))
+            .is_ok());
+
+        // A forged signature, or the CSRF token passed off as a signature, is rejected
+        let forged = format!("{}.{}", session.id, "00".repeat(32));
+        assert!(sessions.authenticate(&forged).await.is_err());
+        let swapped = format!("{}.{}", session.id, login.csrf_token);
+        assert!(sessions.authenticate(&swapped).await.is_err());
+
+        sessions.end(&session.id).await;
+        assert!(sessions
+            .authenticate(cookie_value(&login.cookie))
+            .await
+            .is_err());
+        assert!(sessions.expired_cookie().contains("Max-Age=0"));
+    }
+
+    #[tokio::test]
+    async fn test_expired_session_and_bad_csrf_rejected() {
+        let sessions = CookieSessions::new(SECRET, Duration::seconds(-1))
+            .unwrap()
+            .with_secure(false);
+        let login = sessions.start(&claims()).await.unwrap();
+        assert!(!login.cookie.contains("Secure"));
+        assert!(sessions
+            .authenticate(cookie_value(&login.cookie))
+            .await
+            .is_err());
+
+        let err = sessions
+            .check_csrf(&login.session, Some("not-hex"))
+            .unwrap_err();
+        assert!(matches!(err, UaipError::AuthorizationFailed(_)));
+        assert!(sessions.check_csrf(&login.session, None).is_err());
+    }
+
+    #[test]
+    fn test_configuration_from_vars() {
+        let vars = |pairs: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                pairs
+                    .iter()
+                    .find(|(key, _)| *key == name)
+                    .map(|(_, value)| value.to_string())
+            }
+        };
+
+        assert!(CookieSessions::from_vars(vars(&[])).unwrap().is_none());
+        assert!(CookieSessions::from_vars(vars(&[("SESSION_COOKIE_SECRET", "short")])).is_err());
+        let sessions = CookieSessions::from_vars(vars(&[
+            ("SESSION_COOKIE_SECRET", "0123456789abcdef0123456789abcdef"),
+            ("SESSION_COOKIE_TTL_SECS", "600"),
+            ("SESSION_COOKIE_SECURE", "false"),
+        ]))
+        .unwrap()
+        .unwrap();
+        assert_eq!(sessions.ttl, Duration::seconds(600));
+        assert!(!sessions.secure);
+
+        let mut headers = HeaderMap::new();
+        headers.insert(COOKIE, "theme=dark; uaip_session=abc.def".parse().unwrap());
+        assert_eq!(session_cookie(&headers), Some("abc.def"));
+    }
+}
@@ -0,0 +1,165 @@
+//! Device type registry
+//!
+//! Each known device type implies a set of capabilities, such as `temperature.read`
+//! and `setpoint.write` for a thermostat. Devices registering with a known type are
+//! given those capabilities alongside any they declare themselves, so a device that
+//! declares none can still be commanded. Built-in types can be extended or replaced
+//! from a JSON file.
+
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+use uaip_core::error::{Result, UaipError};
+
+use crate::api::rest::CapabilityDeclaration;
+
+/// Capabilities implied by one device type
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct DeviceTypeDefinition {
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+}
+
+/// Device types keyed by name
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct DeviceTypes {
+    #[serde(default)]
+    pub device_types: HashMap<String, DeviceTypeDefinition>,
+}
+
+impl Default for DeviceTypes {
+    fn default() -> Self {
+        let builtin = |capabilities: &[&str]| DeviceTypeDefinition {
+            capabilities: capabilities.iter().map(|c| c.to_string()).collect(),
+        };
+        Self {
+            device_types: HashMap::from([
+                (
+                    "thermostat".to_string(),
+                    builtin(&["temperature.read", "setpoint.write"]),
+                ),
+                (
+                    "light".to_string(),
+                    builtin(&["power.write", "brightness.write"]),
+                ),
+            ]),
+        }
+    }
+}
+
+impl DeviceTypes {
+    /// Built-in types, extended by the file named by `DEVICE_TYPES` if set
+    pub fn from_env() -> Result<Self> {
+        match std::env::var("DEVICE_TYPES") {
+            Ok(path) => Self::from_file(path),
+            Err(_) => Ok(Self::default()),
+        }
+    }
+
+    /// Built-in types, extended by a JSON file
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path).map_err(|e| {
+            UaipError::InvalidConfiguration(format!(
+                "Failed to read device types {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        Self::from_json(&json)
+    }
+
+    /// Built-in types, extended by JSON; a type defined in both takes the JSON definition
+    pub fn from_json(json: &str) -> Result<Self> {
+        let configured: Self = serde_json::from_str(json)
+            .map_err(|e| UaipError::InvalidConfiguration(format!("Invalid device types: {}", e)))?;
+        let mut types = Self::default();
+        types.device_types.extend(configured.device_types);
+        Ok(types)
+    }
+
+    /// Definition of a device type, if it is known
+    pub fn get(&self, device_type: &str) -> Option<&DeviceTypeDefinition> {
+        self.device_types.get(device_type)
+    }
+
+    /// Declared capabilities followed by the type's defaults not already declared
+    pub fn with_default_capabilities(
+        &self,
+        device_type: &str,
+        declared: &[CapabilityDeclaration],
+    ) -> Vec<CapabilityDeclaration> {
+        let mut capabilities = declared.to_vec();
+        for default in self
+            .get(device_type)
+            .into_iter()
+            .flat_map(|t| &t.capabilities)
+        {
+            if !capabilities.iter().any(|c| c.name() == default) {
+                capabilities.push(CapabilityDeclaration::Name(default.clone()));
+            }
+        }
+        capabilities
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(capabilities: &[CapabilityDeclaration]) -> Vec<&str> {
+        capabilities
+            .iter()
+            .map(CapabilityDeclaration::name)
+            .collect()
+    }
+
+    #[test]
+    fn test_thermostat_gains_default_capabilities() {
+        let types = DeviceTypes::default();
+
+        let capabilities = types.with_default_capabilities("thermostat", &[]);
+        assert_eq!(names(&capabilities), ["temperature.read", "setpoint.write"]);
+
+        // Explicit capabilities are kept, and a default declared explicitly is not repeated
+        let declared: Vec<CapabilityDeclaration> = serde_json::from_value(serde_json::json!([
+            "humidity.read",
+            {
+                "name": "setpoint.write",
+                "capability_type": "actuator",
+                "is_primary": true,
+                "supported_actions": ["set"]
+            }
+        ]))
+        .unwrap();
+        let capabilities = types.with_default_capabilities("thermostat", &declared);
+        assert_eq!(
+            names(&capabilities),
+            ["humidity.read", "setpoint.write", "temperature.read"]
+        );
+        assert!(matches!(capabilities[1], CapabilityDeclaration::Typed(_)));
+
+        // Unknown types get only what they declare
+        assert!(types.with_default_capabilities("sensor", &[]).is_empty());
+    }
+
+    #[test]
+    fn test_configured_types_extend_builtins() {
+        let types = DeviceTypes::from_json(
+            r#"{"device_types": {
+                "thermostat": {"capabilities": ["temperature.read"]},
+                "door": {"capabilities": ["lock", "unlock"]}
+            }}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            types.get("thermostat").unwrap().capabilities,
+            ["temperature.read"]
+        );
+        assert_eq!(types.get("door").unwrap().capabilities, ["lock", "unlock"]);
+        assert!(types.get("light").is_some());
+        assert!(DeviceTypes::from_json("{\"device_types\": []}").is_err());
+    }
+}
@@ -0,0 +1,222 @@
+//! Adapter endpoint reachability probes
+//!
+//! Adapter endpoints configured in the [`NetworkConfig`] are probed at startup and then
+//! on an interval by opening a TCP connection within each endpoint's configured timeout,
+//! so an unreachable endpoint shows in the adapter health report before the first
+//! command to it fails. Health requests read the cached results and never probe.
+
+use futures_util::future::join_all;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
+use tokio::task::JoinHandle;
+
+use uaip_core::error::Result;
+use uaip_core::network::{AdapterEndpoint, NetworkConfig};
+
+use crate::adapter_targets::Target;
+use crate::health::{DependencyHealth, HealthStatus};
+
+/// Background probe of configured adapter endpoints, with the latest results cached
+#[derive(Clone)]
+pub struct EndpointProber {
+    endpoints: Arc<[(String, AdapterEndpoint)]>,
+    interval: Duration,
+    results: Arc<Mutex<HashMap<String, DependencyHealth>>>,
+}
+
+impl EndpointProber {
+    /// Probe `endpoints` every `interval`
+    pub fn new(
+        endpoints: impl IntoIterator<Item = (String, AdapterEndpoint)>,
+        interval: Duration,
+    ) -> Self {
+        let mut endpoints: Vec<_> = endpoints.into_iter().collect();
+        endpoints.sort_by(|a, b| a.0.cmp(&b.0));
+        Self {
+            endpoints: endpoints.into(),
+            interval,
+            results: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Probe the adapter endpoints of a network configuration
+    pub fn from_config(config: &NetworkConfig, interval: Duration) -> Self {
+        Self::new(config.adapters.clone(), interval)
+    }
+
+    pub fn len(&self) -> usize {
+        self.endpoints.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.endpoints.is_empty()
+    }
+
+    /// Probe immediately, then on the interval, in a background task
+    pub fn spawn(self) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(self.interval);
+            loop {
+                interval.tick().await;
+                let unhealthy = self
+                    .probe_all()
+                    .await
+                    .iter()
+                    .filter(|health| health.status == HealthStatus::Unhealthy)
+                    .count();
+                if unhealthy > 0 {
+                    tracing::warn!("{} adapter endpoints are unreachable", unhealthy);
+                }
+            }
+        })
+    }
+
+    /// Probe every endpoint concurrently, caching the results
+    pub async fn probe_all(&self) -> Vec<DependencyHealth> {
+        let results = join_all(
+            self.endpoints
+                .iter()
+                .map(|(name, endpoint)| probe(name, endpoint)),
+        )
+        .await;
+
+        if let Ok(mut cached) = self.results.lock() {
+            for health in &results {
+                cached.insert(health.name.clone(), health.clone());
+            }
+        }
+        results
+    }
+
+    /// Latest result for every endpoint; endpoints not yet probed are degraded
+    pub fn statuses(&self) -> Vec<DependencyHealth> {
+        let cached = self.results.lock().map(|c| c.clone()).unwrap_or_default();
+        self.endpoints
+            .iter()
+            .map(|(name, _)| {
+                cached
+                    .get(name)
+                    .cloned()
+                    .unwrap_or_else(|| DependencyHealth {
+                        name: name.clone(),
+                        status: HealthStatus::Degraded,
+                        response_time_ms: None,
+                        message: Some("Not probed yet".to_string()),
+                    })
+            })
+            .collect()
+    }
+}
+
+/// Host and port an endpoint's connection string names
+pub fn endpoint_target(endpoint: &AdapterEndpoint) -> Result<Target> {
+    if endpoint.connection_string.contains("://") {
+        Target::from_url(&endpoint.connection_string)
+    } else {
+        Target::from_address(&endpoint.connection_string)
+    }
+}
+
+/// Open and drop a TCP connection to one endpoint within its configured timeout
+async fn probe(name: &str, endpoint: &AdapterEndpoint) -> DependencyHealth {
+    let start = Instant::now();
+    let timeout = endpoint.timeout();
+
+    let (status, message) = match endpoint_target(endpoint) {
+        Ok(target) => {
+            let connect = TcpStream::connect((target.host.as_str(), target.port));
+            match tokio::time::timeout(timeout, connect).await {
+                Ok(Ok(_)) => (HealthStatus::Healthy, None),
+                Ok(Err(e)) => (
+                    HealthStatus::Unhealthy,
+                    Some(format!("Endpoint {} unreachable: {}", target, e)),
+                ),
+                Err(_) => (
+                    HealthStatus::Unhealthy,
+                    Some(format!(
+                        "Endpoint {} connect timeout (>{}ms)",
+                        target,
+                        timeout.as_millis()
+                    )),
+                ),
+            }
+        }
+        Err(e) => (HealthStatus::Unhealthy, Some(e.to_string())),
+    };
+
+    DependencyHealth {
+        name: name.to_string(),
+        status,
+        response_time_ms: Some(start.elapsed().as_secs_f64() * 1000.0),
+        message,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::health::{adapter_health_handler, HealthChecker};
+    use axum::http::StatusCode;
+    use axum::Json;
+
+    #[tokio::test]
+    async fn test_unreachable_endpoint_reported_unhealthy() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let reachable = listener.local_addr().unwrap();
+        let closed = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let unreachable = closed.local_addr().unwrap();
+        drop(closed);
+
+        let mut config = NetworkConfig::default();
+        config.add_adapter(
+            "gateway".to_string(),
+            AdapterEndpoint::http(&format!("http://{}", reachable), None),
+        );
+        config.add_adapter(
+            "plc-1".to_string(),
+            AdapterEndpoint::modbus_tcp("127.0.0.1", unreachable.port(), 1),
+        );
+        let prober = EndpointProber::from_config(&config, Duration::from_secs(60));
+        let checker = HealthChecker::new().with_endpoint_probes(prober.clone());
+
+        // Before the first probe, configured endpoints are only degraded
+        let (status, Json(health)) = adapter_health_handler(&checker).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(health.status, HealthStatus::Degraded);
+
+        prober.probe_all().await;
+        let statuses = prober.statuses();
+        assert_eq!(statuses[0].name, "gateway");
+        assert_eq!(statuses[0].status, HealthStatus::Healthy);
+        assert_eq!(statuses[1].name, "plc-1");
+        assert_eq!(statuses[1].status, HealthStatus::Unhealthy);
+        assert!(statuses[1]
+            .message
+            .as_deref()
+            .unwrap()
+            .contains("unreachable"));
+
+        let checker = HealthChecker::new().with_endpoint_probes(prober);
+        let (status, Json(health)) = adapter_health_handler(&checker).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(health.adapters.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_connection_string_reported_unhealthy() {
+        let mut endpoint = AdapterEndpoint::modbus_tcp("127.0.0.1", 502, 1);
+        endpoint.connection_string = "plc-without-port".to_string();
+
+        let prober =
+            EndpointProber::new([("plc-1".to_string(), endpoint)], Duration::from_secs(60));
+        let health = prober.probe_all().await;
+        assert_eq!(health[0].status, HealthStatus::Unhealthy);
+        assert!(health[0]
+            .message
+            .as_deref()
+            .unwrap()
+            .contains("Invalid target address"));
+    }
+}
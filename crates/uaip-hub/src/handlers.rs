@@ -1,18 +1,30 @@
 //! Request handlers module
 
-use axum::{Extension, Json};
+use axum::{http::StatusCode, Extension, Json};
 use std::sync::Arc;
 
 pub mod adapters;
 pub mod ai;
+pub mod api_keys;
+pub mod audit;
 pub mod auth;
 pub mod commands;
+pub mod debug;
 pub mod devices;
+pub mod executions;
+pub mod firmware;
+pub mod groups;
 pub mod media;
 pub mod metrics;
+pub mod provisioning;
+pub mod rules;
+pub mod scenarios;
+pub mod sessions;
+pub mod telemetry;
 pub mod users;
 
-use crate::health::{HealthCheckResponse, HealthChecker};
+use crate::build_info::BuildInfo;
+use crate::health::{AdapterHealthResponse, HealthCheckResponse, HealthChecker};
 
 /// Health check handler
 pub async fn health_check(
@@ -22,6 +34,18 @@ pub async fn health_check(
     Json(health)
 }
 
+/// Protocol adapter health handler
+pub async fn adapter_health(
+    Extension(checker): Extension<Arc<HealthChecker>>,
+) -> (StatusCode, Json<AdapterHealthResponse>) {
+    crate::health::adapter_health_handler(&checker).await
+}
+
+/// Version and build metadata handler; unauthenticated and served without I/O
+pub async fn version() -> Json<BuildInfo> {
+    Json(BuildInfo::current())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -35,4 +59,16 @@ mod tests {
         assert!(!response.0.timestamp.is_empty());
         assert_eq!(response.0.dependencies.len(), 3); // PostgreSQL, Redis, NATS
     }
+
+    #[tokio::test]
+    async fn test_version_reports_crate_version() {
+        let Json(info) = version().await;
+        assert_eq!(info.version, env!("CARGO_PKG_VERSION"));
+        assert!(!info.git_sha.is_empty());
+        assert!(info.build_timestamp.is_some());
+
+        let json = serde_json::to_value(&info).unwrap();
+        assert_eq!(json["version"], env!("CARGO_PKG_VERSION"));
+        assert!(json["features"].is_array());
+    }
 }
@@ -1,29 +1,139 @@
 //! Protocol Adapters Management Handlers
 //!
 //! REST API endpoints for managing and interacting with protocol adapters
-//! (ModBus, OPC UA, WebRTC, HTTP, MQTT, WebSocket).
+//! (ModBus, OPC UA, WebRTC, HTTP, MQTT, WebSocket). Endpoints for an adapter exist only
+//! when its cargo feature is enabled.
 
-use axum::{extract::State, Json};
+#[cfg(any(feature = "http", feature = "modbus", feature = "opcua"))]
+use axum::http::HeaderMap;
+use axum::{
+    extract::{Query, State},
+    Extension, Json,
+};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
-use tracing::{error, info};
+use tracing::info;
 
-use uaip_adapters::{
-    http::{HttpAdapter, HttpConfig},
-    modbus::{ModbusAdapter, ModbusConfig},
-    opcua::{NodeId, OpcUaAdapter, OpcUaConfig, OpcValue},
-    webrtc::{DataChannelConfig, WebRtcAdapter, WebRtcConfig},
-};
+#[cfg(feature = "http")]
+use uaip_adapters::http::{HttpAdapter, HttpConfig};
+#[cfg(feature = "modbus")]
+use uaip_adapters::modbus::{ModbusAdapter, ModbusConfig};
+#[cfg(feature = "opcua")]
+use uaip_adapters::opcua::{NodeId, OpcUaAdapter, OpcUaConfig, OpcValue};
+#[cfg(feature = "webrtc")]
+use uaip_adapters::webrtc::{DataChannelConfig, WebRtcAdapter, WebRtcConfig};
+use uaip_adapters::ENABLED_ADAPTERS;
+use uaip_core::error::{ErrorCode, ErrorResponse, UaipError};
+
+#[cfg(any(feature = "http", feature = "modbus", feature = "opcua"))]
+use crate::adapter_targets::Target;
+#[cfg(any(
+    feature = "http",
+    feature = "modbus",
+    feature = "opcua",
+    feature = "webrtc"
+))]
+use crate::api::rest::ApiError;
+use crate::api::rest::{ApiResult, AppState};
+use crate::health::{HealthChecker, HealthStatus};
+use crate::pagination::{default_page, default_per_page, page_offset, validate_page, Paginated};
+
+/// Query parameters for adapter listing
+#[derive(Debug, Deserialize)]
+pub struct AdapterListQuery {
+    /// Filter by adapter type (http, websocket, mqtt, modbus, opcua, webrtc)
+    #[serde(default)]
+    pub adapter_type: Option<String>,
+
+    /// Filter by status (available, healthy, degraded, unhealthy)
+    #[serde(default)]
+    pub status: Option<String>,
 
-use crate::api::rest::{ApiError, ApiResult, AppState};
+    /// Page number (1-indexed)
+    #[serde(default = "default_page")]
+    pub page: i64,
+
+    /// Items per page
+    #[serde(default = "default_per_page")]
+    pub per_page: i64,
+}
 
-/// List all available protocol adapters
+impl Default for AdapterListQuery {
+    fn default() -> Self {
+        Self {
+            adapter_type: None,
+            status: None,
+            page: default_page(),
+            per_page: default_per_page(),
+        }
+    }
+}
+
+/// List configured adapter instances followed by the catalog of supported adapter types
+///
+/// Instance status comes from the cached adapter health checks.
 pub async fn list_adapters(
-    State(_state): State<Arc<AppState>>,
+    State(state): State<Arc<AppState>>,
+    Extension(checker): Extension<Arc<HealthChecker>>,
+    Query(query): Query<AdapterListQuery>,
 ) -> ApiResult<Json<AdapterListResponse>> {
-    info!("Listing available protocol adapters");
+    info!("Listing protocol adapters");
+
+    validate_page(query.page, query.per_page)?;
+
+    let catalog = adapter_catalog();
+    let health = checker.check_adapters().await;
+    let instances = state.adapters.iter().map(|registered| {
+        let entry = catalog
+            .iter()
+            .find(|c| c.adapter_type == registered.adapter_type);
+        let health = health.adapters.iter().find(|h| h.name == registered.name);
 
-    let adapters = vec![
+        AdapterInfo {
+            adapter_type: registered.adapter_type.to_string(),
+            name: registered.name.clone(),
+            description: entry.map(|c| c.description.clone()).unwrap_or_default(),
+            supported_operations: entry
+                .map(|c| c.supported_operations.clone())
+                .unwrap_or_default(),
+            status: health
+                .map(|h| health_status_name(&h.status))
+                .unwrap_or("unknown")
+                .to_string(),
+            configured: true,
+            message: health.and_then(|h| h.message.clone()),
+        }
+    });
+
+    let matching: Vec<AdapterInfo> = instances
+        .chain(catalog.iter().cloned())
+        .filter(|a| {
+            query
+                .adapter_type
+                .as_ref()
+                .is_none_or(|t| &a.adapter_type == t)
+                && query.status.as_ref().is_none_or(|s| &a.status == s)
+        })
+        .collect();
+
+    let total = matching.len();
+    let adapters = matching
+        .into_iter()
+        .skip(page_offset(query.page, query.per_page) as usize)
+        .take(query.per_page as usize)
+        .collect();
+
+    Ok(Json(AdapterListResponse::new(
+        adapters,
+        total,
+        query.page,
+        query.per_page,
+    )))
+}
+
+/// Adapter types compiled into the hub
+fn adapter_catalog() -> Vec<AdapterInfo> {
+    let catalog = vec![
         AdapterInfo {
             adapter_type: "http".to_string(),
             name: "HTTP/REST Client".to_string(),
@@ -35,6 +145,8 @@ pub async fn list_adapters(
                 "DELETE".to_string(),
             ],
             status: "available".to_string(),
+            configured: false,
+            message: None,
         },
         AdapterInfo {
             adapter_type: "websocket".to_string(),
@@ -42,6 +154,8 @@ pub async fn list_adapters(
             description: "Real-time bidirectional WebSocket communication".to_string(),
             supported_operations: vec!["connect".to_string(), "send".to_string()],
             status: "available".to_string(),
+            configured: false,
+            message: None,
         },
         AdapterInfo {
             adapter_type: "mqtt".to_string(),
@@ -53,6 +167,8 @@ pub async fn list_adapters(
                 "publish".to_string(),
             ],
             status: "available".to_string(),
+            configured: false,
+            message: None,
         },
         AdapterInfo {
             adapter_type: "modbus".to_string(),
@@ -65,6 +181,8 @@ pub async fn list_adapters(
                 "write_single_register".to_string(),
             ],
             status: "available".to_string(),
+            configured: false,
+            message: None,
         },
         AdapterInfo {
             adapter_type: "opcua".to_string(),
@@ -77,6 +195,8 @@ pub async fn list_adapters(
                 "browse_node".to_string(),
             ],
             status: "available".to_string(),
+            configured: false,
+            message: None,
         },
         AdapterInfo {
             adapter_type: "webrtc".to_string(),
@@ -88,19 +208,38 @@ pub async fn list_adapters(
                 "create_data_channel".to_string(),
             ],
             status: "available".to_string(),
+            configured: false,
+            message: None,
         },
     ];
 
-    Ok(Json(AdapterListResponse { adapters, total: 6 }))
+    catalog
+        .into_iter()
+        .filter(|entry| ENABLED_ADAPTERS.contains(&entry.adapter_type.as_str()))
+        .collect()
+}
+
+fn health_status_name(status: &HealthStatus) -> &'static str {
+    match status {
+        HealthStatus::Healthy => "healthy",
+        HealthStatus::Degraded => "degraded",
+        HealthStatus::Unhealthy => "unhealthy",
+    }
 }
 
 /// Test HTTP adapter connection
+#[cfg(feature = "http")]
 pub async fn test_http_adapter(
-    State(_state): State<Arc<AppState>>,
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
     Json(request): Json<HttpTestRequest>,
 ) -> ApiResult<Json<AdapterTestResponse>> {
     info!("Testing HTTP adapter connection to: {}", request.base_url);
 
+    let target = Target::from_url(&request.base_url)?;
+    let addresses = state.adapter_probes.authorize(&headers, &target).await?;
+
+    // Connect only to the checked addresses, and report redirects instead of following them
     let config = HttpConfig {
         base_url: request.base_url.clone(),
         timeout_seconds: request.timeout_seconds.unwrap_or(10),
@@ -110,10 +249,12 @@ pub async fn test_http_adapter(
         auth: request.auth,
         verify_tls: request.verify_tls.unwrap_or(true),
         pool_max_idle_per_host: 10,
+        follow_redirects: false,
+        pinned_addresses: addresses,
     };
 
     let adapter = HttpAdapter::new(config).map_err(|e| {
-        error!("Failed to create HTTP adapter: {}", e);
+        tracing::error!("Failed to create HTTP adapter: {}", e);
         ApiError::from(e)
     })?;
 
@@ -122,19 +263,18 @@ pub async fn test_http_adapter(
         Ok(_) => Ok(Json(AdapterTestResponse {
             success: true,
             message: format!("Successfully connected to {}", request.base_url),
+            session_id: None,
             details: None,
         })),
-        Err(e) => Ok(Json(AdapterTestResponse {
-            success: false,
-            message: format!("Connection failed: {}", e),
-            details: Some(format!("{:?}", e)),
-        })),
+        Err(e) => Ok(Json(AdapterTestResponse::failure(e))),
     }
 }
 
 /// Test Modbus adapter connection
+#[cfg(feature = "modbus")]
 pub async fn test_modbus_adapter(
-    State(_state): State<Arc<AppState>>,
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
     Json(request): Json<ModbusTestRequest>,
 ) -> ApiResult<Json<AdapterTestResponse>> {
     info!(
@@ -142,6 +282,9 @@ pub async fn test_modbus_adapter(
         request.server_address
     );
 
+    let target = Target::from_address(&request.server_address)?;
+    state.adapter_probes.authorize(&headers, &target).await?;
+
     let config = ModbusConfig {
         server_address: request.server_address.clone(),
         unit_id: request.unit_id.unwrap_or(1),
@@ -150,10 +293,11 @@ pub async fn test_modbus_adapter(
         write_timeout: 5,
         max_retries: 1,
         retry_delay_ms: 1000,
+        ..Default::default()
     };
 
     let adapter = ModbusAdapter::new(config).map_err(|e| {
-        error!("Failed to create Modbus adapter: {}", e);
+        tracing::error!("Failed to create Modbus adapter: {}", e);
         ApiError::from(e)
     })?;
 
@@ -165,19 +309,18 @@ pub async fn test_modbus_adapter(
                 "Successfully connected to Modbus server {}",
                 request.server_address
             ),
+            session_id: None,
             details: None,
         })),
-        Err(e) => Ok(Json(AdapterTestResponse {
-            success: false,
-            message: format!("Connection failed: {}", e),
-            details: Some(format!("{:?}", e)),
-        })),
+        Err(e) => Ok(Json(AdapterTestResponse::failure(e))),
     }
 }
 
 /// Read Modbus holding registers
+#[cfg(feature = "modbus")]
 pub async fn read_modbus_registers(
-    State(_state): State<Arc<AppState>>,
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
     Json(request): Json<ModbusReadRequest>,
 ) -> ApiResult<Json<ModbusReadResponse>> {
     info!(
@@ -185,6 +328,9 @@ pub async fn read_modbus_registers(
         request.server_address, request.address, request.count
     );
 
+    let target = Target::from_address(&request.server_address)?;
+    state.adapter_probes.authorize(&headers, &target).await?;
+
     let config = ModbusConfig {
         server_address: request.server_address.clone(),
         unit_id: request.unit_id.unwrap_or(1),
@@ -193,12 +339,13 @@ pub async fn read_modbus_registers(
         write_timeout: 5,
         max_retries: 3,
         retry_delay_ms: 1000,
+        ..Default::default()
     };
 
     let adapter = ModbusAdapter::new(config).map_err(ApiError::from)?;
 
     let values = adapter
-        .read_holding_registers(request.address, request.count)
+        .read_holding_registers_chunked(request.address, request.count)
         .await
         .map_err(ApiError::from)?;
 
@@ -206,8 +353,10 @@ pub async fn read_modbus_registers(
 }
 
 /// Test OPC UA adapter connection
+#[cfg(feature = "opcua")]
 pub async fn test_opcua_adapter(
-    State(_state): State<Arc<AppState>>,
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
     Json(request): Json<OpcUaTestRequest>,
 ) -> ApiResult<Json<AdapterTestResponse>> {
     info!(
@@ -215,6 +364,9 @@ pub async fn test_opcua_adapter(
         request.endpoint_url
     );
 
+    let target = Target::from_url(&request.endpoint_url)?;
+    state.adapter_probes.authorize(&headers, &target).await?;
+
     let config = OpcUaConfig {
         endpoint_url: request.endpoint_url.clone(),
         application_name: "UAIP Hub".to_string(),
@@ -227,6 +379,9 @@ pub async fn test_opcua_adapter(
             .unwrap_or(uaip_adapters::opcua::SecurityPolicy::None),
         username: request.username,
         password: request.password,
+        certificate_path: None,
+        private_key_path: None,
+        trusted_server_certificates: Vec::new(),
         connection_timeout: 10,
         session_timeout: 60,
         request_timeout: 5,
@@ -235,7 +390,7 @@ pub async fn test_opcua_adapter(
     };
 
     let mut adapter = OpcUaAdapter::new(config).map_err(|e| {
-        error!("Failed to create OPC UA adapter: {}", e);
+        tracing::error!("Failed to create OPC UA adapter: {}", e);
         ApiError::from(e)
     })?;
 
@@ -247,19 +402,18 @@ pub async fn test_opcua_adapter(
                 "Successfully connected to OPC UA server {}",
                 request.endpoint_url
             ),
-            details: adapter.get_session_id().map(|s| s.to_string()),
-        })),
-        Err(e) => Ok(Json(AdapterTestResponse {
-            success: false,
-            message: format!("Connection failed: {}", e),
-            details: Some(format!("{:?}", e)),
+            session_id: adapter.get_session_id().map(|s| s.to_string()),
+            details: None,
         })),
+        Err(e) => Ok(Json(AdapterTestResponse::failure(e))),
     }
 }
 
 /// Read OPC UA node value
+#[cfg(feature = "opcua")]
 pub async fn read_opcua_node(
-    State(_state): State<Arc<AppState>>,
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
     Json(request): Json<OpcUaReadRequest>,
 ) -> ApiResult<Json<OpcUaReadResponse>> {
     info!(
@@ -267,6 +421,9 @@ pub async fn read_opcua_node(
         request.endpoint_url, request.node_id
     );
 
+    let target = Target::from_url(&request.endpoint_url)?;
+    state.adapter_probes.authorize(&headers, &target).await?;
+
     let config = OpcUaConfig {
         endpoint_url: request.endpoint_url.clone(),
         application_name: "UAIP Hub".to_string(),
@@ -275,6 +432,9 @@ pub async fn read_opcua_node(
         security_policy: uaip_adapters::opcua::SecurityPolicy::None,
         username: request.username,
         password: request.password,
+        certificate_path: None,
+        private_key_path: None,
+        trusted_server_certificates: Vec::new(),
         connection_timeout: 10,
         session_timeout: 60,
         request_timeout: 5,
@@ -299,6 +459,7 @@ pub async fn read_opcua_node(
 }
 
 /// Create WebRTC offer
+#[cfg(feature = "webrtc")]
 pub async fn create_webrtc_offer(
     State(_state): State<Arc<AppState>>,
     Json(request): Json<WebRtcOfferRequest>,
@@ -317,7 +478,7 @@ pub async fn create_webrtc_offer(
     };
 
     let adapter = WebRtcAdapter::new(config).map_err(|e| {
-        error!("Failed to create WebRTC adapter: {}", e);
+        tracing::error!("Failed to create WebRTC adapter: {}", e);
         ApiError::from(e)
     })?;
 
@@ -331,28 +492,101 @@ pub async fn create_webrtc_offer(
 
 // ===== Request/Response Types =====
 
-#[derive(Debug, Serialize)]
-pub struct AdapterListResponse {
-    pub adapters: Vec<AdapterInfo>,
-    pub total: usize,
-}
+pub type AdapterListResponse = Paginated<AdapterInfo>;
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct AdapterInfo {
     pub adapter_type: String,
     pub name: String,
     pub description: String,
     pub supported_operations: Vec<String>,
     pub status: String,
+    /// Whether this is a configured instance rather than a catalog entry
+    pub configured: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
 pub struct AdapterTestResponse {
     pub success: bool,
     pub message: String,
-    pub details: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<AdapterErrorDetails>,
 }
 
+impl AdapterTestResponse {
+    /// Failed test, with the error broken down into structured details
+    pub fn failure(error: UaipError) -> Self {
+        let message = format!("Connection failed: {}", error);
+        let category = FailureCategory::of(&error);
+        let (protocol, protocol_code) = match &error {
+            UaipError::ProtocolError { protocol, code, .. } => {
+                (Some(protocol.clone()), Some(*code))
+            }
+            _ => (None, None),
+        };
+
+        Self {
+            success: false,
+            message,
+            session_id: None,
+            details: Some(AdapterErrorDetails {
+                code: ErrorResponse::from(error).code,
+                category,
+                protocol,
+                protocol_code,
+            }),
+        }
+    }
+}
+
+/// Why an adapter test failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureCategory {
+    /// No answer within the configured timeout
+    Timeout,
+    /// The connection could not be opened or was dropped
+    Connection,
+    /// The peer answered with an error or a malformed response
+    Protocol,
+    /// The adapter settings were rejected before connecting
+    Configuration,
+    Other,
+}
+
+impl FailureCategory {
+    fn of(error: &UaipError) -> Self {
+        match error {
+            UaipError::Timeout(_) => Self::Timeout,
+            UaipError::ConnectionError(_) => Self::Connection,
+            UaipError::ProtocolError { .. } | UaipError::InvalidMessage(_) => Self::Protocol,
+            UaipError::InvalidConfiguration(_) | UaipError::InvalidParameter(_) => {
+                Self::Configuration
+            }
+            _ => Self::Other,
+        }
+    }
+}
+
+/// Machine-readable description of an adapter test failure
+#[derive(Debug, Clone, Serialize)]
+pub struct AdapterErrorDetails {
+    /// Stable error code, e.g. `CONNECTION_TIMEOUT`
+    pub code: ErrorCode,
+    pub category: FailureCategory,
+    /// Protocol that reported the error
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub protocol: Option<String>,
+    /// Protocol-specific error code, e.g. the Modbus exception code
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub protocol_code: Option<u32>,
+}
+
+#[cfg(feature = "http")]
 #[derive(Debug, Deserialize)]
 pub struct HttpTestRequest {
     pub base_url: String,
@@ -362,6 +596,7 @@ pub struct HttpTestRequest {
     pub verify_tls: Option<bool>,
 }
 
+#[cfg(feature = "modbus")]
 #[derive(Debug, Deserialize)]
 pub struct ModbusTestRequest {
     pub server_address: String,
@@ -369,6 +604,7 @@ pub struct ModbusTestRequest {
     pub connection_timeout: Option<u64>,
 }
 
+#[cfg(feature = "modbus")]
 #[derive(Debug, Deserialize)]
 pub struct ModbusReadRequest {
     pub server_address: String,
@@ -377,11 +613,13 @@ pub struct ModbusReadRequest {
     pub count: u16,
 }
 
+#[cfg(feature = "modbus")]
 #[derive(Debug, Serialize)]
 pub struct ModbusReadResponse {
     pub values: Vec<u16>,
 }
 
+#[cfg(feature = "opcua")]
 #[derive(Debug, Deserialize)]
 pub struct OpcUaTestRequest {
     pub endpoint_url: String,
@@ -391,6 +629,7 @@ pub struct OpcUaTestRequest {
     pub password: Option<String>,
 }
 
+#[cfg(feature = "opcua")]
 #[derive(Debug, Deserialize)]
 pub struct OpcUaReadRequest {
     pub endpoint_url: String,
@@ -399,6 +638,7 @@ pub struct OpcUaReadRequest {
     pub password: Option<String>,
 }
 
+#[cfg(feature = "opcua")]
 #[derive(Debug, Serialize)]
 pub struct OpcUaReadResponse {
     pub node_id: String,
@@ -408,6 +648,7 @@ pub struct OpcUaReadResponse {
     pub status_code: u32,
 }
 
+#[cfg(feature = "webrtc")]
 #[derive(Debug, Deserialize)]
 pub struct WebRtcOfferRequest {
     pub ice_servers: Option<Vec<uaip_adapters::webrtc::IceServer>>,
@@ -417,6 +658,7 @@ pub struct WebRtcOfferRequest {
     pub data_channels: Option<Vec<DataChannelConfig>>,
 }
 
+#[cfg(feature = "webrtc")]
 #[derive(Debug, Serialize)]
 pub struct WebRtcOfferResponse {
     pub sdp_type: String,
@@ -425,6 +667,9 @@ pub struct WebRtcOfferResponse {
 
 #[cfg(test)]
 mod tests {
+    // Some fixtures are only used when every adapter is compiled in
+    #![allow(dead_code)]
+
     use super::*;
 
     #[test]
@@ -435,6 +680,8 @@ mod tests {
             description: "Industrial protocol".to_string(),
             supported_operations: vec!["read".to_string()],
             status: "available".to_string(),
+            configured: false,
+            message: None,
         };
 
         let json = serde_json::to_string(&info).unwrap();
@@ -442,6 +689,7 @@ mod tests {
         assert!(json.contains("available"));
     }
 
+    #[cfg(feature = "modbus")]
     #[test]
     fn test_modbus_read_request() {
         let request = ModbusReadRequest {
@@ -456,4 +704,374 @@ mod tests {
         assert_eq!(request.address, 100);
         assert_eq!(request.count, 10);
     }
+
+    use crate::adapter_registry::AdapterRegistry;
+    use uaip_adapters::adapter::{AdapterOp, AdapterResult, ProtocolAdapter};
+    use uaip_core::error::{Result, UaipError};
+
+    struct FakeAdapter {
+        adapter_type: &'static str,
+        healthy: bool,
+    }
+
+    #[async_trait::async_trait]
+    impl ProtocolAdapter for FakeAdapter {
+        fn adapter_type(&self) -> &'static str {
+            self.adapter_type
+        }
+
+        async fn health_check(&mut self) -> Result<()> {
+            if self.healthy {
+                Ok(())
+            } else {
+                Err(UaipError::ConnectionError("connection refused".to_string()))
+            }
+        }
+
+        async fn execute(&mut self, _op: AdapterOp) -> Result<AdapterResult> {
+            Ok(AdapterResult::Written)
+        }
+    }
+
+    async fn list(
+        registry: &AdapterRegistry,
+        query: AdapterListQuery,
+    ) -> ApiResult<Json<AdapterListResponse>> {
+        let state = Arc::new(AppState::new().with_adapters(registry.clone()));
+        let checker = Arc::new(HealthChecker::new().with_registry(registry));
+        list_adapters(State(state), Extension(checker), Query(query)).await
+    }
+
+    fn registry() -> AdapterRegistry {
+        let mut registry = AdapterRegistry::new();
+        for (name, adapter_type, healthy) in [
+            ("plc-1", "modbus", true),
+            ("plc-2", "modbus", false),
+            ("gateway", "http", true),
+        ] {
+            let adapter = FakeAdapter {
+                adapter_type,
+                healthy,
+            };
+            registry.register(name, adapter).unwrap();
+        }
+        registry
+    }
+
+    #[cfg(all(
+        feature = "http",
+        feature = "modbus",
+        feature = "mqtt",
+        feature = "opcua",
+        feature = "webrtc",
+        feature = "websocket"
+    ))]
+    #[tokio::test]
+    async fn test_list_includes_instances_and_catalog() {
+        let Json(all) = list(&registry(), AdapterListQuery::default())
+            .await
+            .unwrap();
+        assert_eq!(all.total, 9);
+        assert_eq!(all.items.len(), 9);
+
+        let plc = &all.items[1];
+        assert_eq!(
+            (plc.name.as_str(), plc.status.as_str()),
+            ("plc-2", "unhealthy")
+        );
+        assert!(plc.configured);
+        assert!(plc
+            .message
+            .as_deref()
+            .unwrap()
+            .contains("connection refused"));
+        assert!(plc.supported_operations.contains(&"read_coils".to_string()));
+
+        let Json(catalog_only) = list(&AdapterRegistry::new(), AdapterListQuery::default())
+            .await
+            .unwrap();
+        assert_eq!(catalog_only.total, 6);
+        assert!(catalog_only.items.iter().all(|a| !a.configured));
+    }
+
+    #[cfg(all(
+        feature = "http",
+        feature = "modbus",
+        feature = "mqtt",
+        feature = "opcua",
+        feature = "webrtc",
+        feature = "websocket"
+    ))]
+    #[tokio::test]
+    async fn test_list_filters_by_type_and_status() {
+        let registry = registry();
+
+        let Json(modbus) = list(
+            &registry,
+            AdapterListQuery {
+                adapter_type: Some("modbus".to_string()),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(modbus.total, 3);
+
+        let Json(healthy_modbus) = list(
+            &registry,
+            AdapterListQuery {
+                adapter_type: Some("modbus".to_string()),
+                status: Some("healthy".to_string()),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        let names: Vec<&str> = healthy_modbus
+            .items
+            .iter()
+            .map(|a| a.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["plc-1"]);
+        assert_eq!(healthy_modbus.total, 1);
+    }
+
+    #[cfg(all(
+        feature = "http",
+        feature = "modbus",
+        feature = "mqtt",
+        feature = "opcua",
+        feature = "webrtc",
+        feature = "websocket"
+    ))]
+    #[tokio::test]
+    async fn test_list_paginates_with_full_total() {
+        let registry = registry();
+        let page = |page| AdapterListQuery {
+            page,
+            per_page: 4,
+            ..Default::default()
+        };
+
+        let Json(first) = list(&registry, page(1)).await.unwrap();
+        assert_eq!(first.total, 9);
+        assert_eq!(first.items.len(), 4);
+        assert!(first.has_more);
+
+        let Json(last) = list(&registry, page(3)).await.unwrap();
+        assert_eq!(last.total, 9);
+        assert_eq!(last.items.len(), 1);
+        assert!(!last.has_more);
+        assert_eq!(last.items[0].adapter_type, "webrtc");
+
+        assert!(list(&registry, page(0)).await.is_err());
+    }
+
+    async fn catalog_types() -> Vec<String> {
+        let Json(catalog) = list(&AdapterRegistry::new(), AdapterListQuery::default())
+            .await
+            .unwrap();
+        catalog.items.into_iter().map(|a| a.adapter_type).collect()
+    }
+
+    #[tokio::test]
+    async fn test_catalog_lists_compiled_adapters() {
+        let types = catalog_types().await;
+        assert_eq!(types.len(), ENABLED_ADAPTERS.len());
+        for adapter_type in ["http", "modbus", "mqtt", "opcua", "webrtc", "websocket"] {
+            assert_eq!(
+                types.iter().any(|t| t == adapter_type),
+                ENABLED_ADAPTERS.contains(&adapter_type),
+                "{}",
+                adapter_type
+            );
+        }
+    }
+
+    #[cfg(all(
+        feature = "http",
+        not(any(
+            feature = "modbus",
+            feature = "mqtt",
+            feature = "opcua",
+            feature = "webrtc",
+            feature = "websocket"
+        ))
+    ))]
+    #[tokio::test]
+    async fn test_http_only_build_lists_only_http() {
+        assert_eq!(catalog_types().await, vec!["http"]);
+    }
+
+    #[cfg(feature = "modbus")]
+    use std::time::Duration;
+    #[cfg(feature = "modbus")]
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    #[cfg(feature = "modbus")]
+    use tokio::net::TcpListener;
+
+    /// Modbus server that answers every request with `exception`, or never answers
+    #[cfg(feature = "modbus")]
+    async fn modbus_server(exception: Option<u8>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let mut request = [0u8; 12];
+                    if socket.read_exact(&mut request).await.is_err() {
+                        return;
+                    }
+                    match exception {
+                        Some(code) => {
+                            let mut reply = request[..4].to_vec();
+                            reply.extend_from_slice(&[0, 3, request[6], request[7] | 0x80, code]);
+                            let _ = socket.write_all(&reply).await;
+                        }
+                        None => tokio::time::sleep(Duration::from_secs(60)).await,
+                    }
+                });
+            }
+        });
+        addr.to_string()
+    }
+
+    #[cfg(feature = "modbus")]
+    async fn modbus_test_failure(server_address: String) -> AdapterTestResponse {
+        let adapter = ModbusAdapter::new(ModbusConfig {
+            server_address,
+            read_timeout: 1,
+            max_retries: 0,
+            ..Default::default()
+        })
+        .unwrap();
+        AdapterTestResponse::failure(adapter.health_check().await.unwrap_err())
+    }
+
+    #[cfg(feature = "modbus")]
+    #[tokio::test]
+    async fn test_timeout_failure_details() {
+        let response = modbus_test_failure(modbus_server(None).await).await;
+
+        assert!(!response.success);
+        assert_eq!(
+            serde_json::to_value(&response.details).unwrap(),
+            serde_json::json!({"code": "CONNECTION_TIMEOUT", "category": "timeout"})
+        );
+    }
+
+    #[cfg(feature = "modbus")]
+    #[tokio::test]
+    async fn test_protocol_failure_details() {
+        let response = modbus_test_failure(modbus_server(Some(0x02)).await).await;
+
+        assert!(!response.success);
+        assert!(response.message.contains("Illegal data address"));
+        assert_eq!(
+            serde_json::to_value(&response.details).unwrap(),
+            serde_json::json!({
+                "code": "PROTOCOL_ERROR",
+                "category": "protocol",
+                "protocol": "modbus",
+                "protocol_code": 2,
+            })
+        );
+    }
+
+    #[cfg(feature = "http")]
+    #[tokio::test]
+    async fn test_http_probe_requires_admin_and_public_target() {
+        let state = Arc::new(AppState::new());
+        let request = || HttpTestRequest {
+            base_url: "http://169.254.169.254/latest/meta-data/".to_string(),
+            timeout_seconds: Some(1),
+            headers: None,
+            auth: None,
+            verify_tls: None,
+        };
+
+        let err = test_http_adapter(State(state.clone()), HeaderMap::new(), Json(request()))
+            .await
+            .unwrap_err();
+        assert!(matches!(err.0, UaipError::AuthenticationFailed(_)));
+
+        let token = crate::handlers::auth::jwt_manager(3600)
+            .generate_token(
+                "admin-1",
+                "admin@example.com",
+                vec!["admin".to_string()],
+                None,
+            )
+            .unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "Authorization",
+            format!("Bearer {}", token).parse().unwrap(),
+        );
+        let err = test_http_adapter(State(state), headers, Json(request()))
+            .await
+            .unwrap_err();
+        assert!(matches!(err.0, UaipError::NotPermitted(_)));
+    }
+
+    #[cfg(feature = "http")]
+    #[tokio::test]
+    async fn test_http_probe_does_not_follow_redirect_to_metadata() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // An allowlisted server that redirects to the cloud metadata endpoint
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut buf = [0u8; 1024];
+                let _ = socket.read(&mut buf).await;
+                let _ = socket
+                    .write_all(
+                        b"HTTP/1.1 302 Found\r\n\
+                          Location: http://169.254.169.254/latest/meta-data/\r\n\
+                          Content-Length: 0\r\n\r\n",
+                    )
+                    .await;
+            }
+        });
+        let policy = crate::adapter_targets::TargetPolicy::from_vars(|name| {
+            (name == "ADAPTER_TARGET_ALLOW").then(|| address.to_string())
+        })
+        .unwrap();
+        let state = Arc::new(AppState::new().with_adapter_probes(
+            crate::adapter_targets::AdapterProbeGuard::new(
+                policy,
+                crate::adapter_targets::default_probe_rate(),
+            ),
+        ));
+
+        let token = crate::handlers::auth::jwt_manager(3600)
+            .generate_token(
+                "admin-1",
+                "admin@example.com",
+                vec!["admin".to_string()],
+                None,
+            )
+            .unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "Authorization",
+            format!("Bearer {}", token).parse().unwrap(),
+        );
+        let request = HttpTestRequest {
+            base_url: format!("http://{}", address),
+            timeout_seconds: Some(5),
+            headers: None,
+            auth: None,
+            verify_tls: None,
+        };
+        let Json(response) = test_http_adapter(State(state), headers, Json(request))
+            .await
+            .unwrap();
+
+        // The redirect is reported as the probe's result rather than followed
+        assert!(!response.success);
+        assert!(response.message.contains("302"), "{}", response.message);
+    }
 }
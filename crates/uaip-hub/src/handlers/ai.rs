@@ -4,30 +4,34 @@
 
 use axum::{
     extract::{Path, State},
+    http::HeaderMap,
     Json,
 };
 use serde::{Deserialize, Serialize};
-use sqlx::Row;
 use std::sync::Arc;
-use tracing::{error, info};
+use tracing::info;
 use uuid::Uuid;
 
-
 use uaip_core::{
     ai_agent::{AgentConfig, AgentType, AiAgent, AiSession, InteractionType, SessionState},
     device::{Capability, DeviceId},
+    error::UaipError,
 };
 
 use crate::api::rest::{ApiResult, AppState};
+use crate::audit::AuditEvent;
+use crate::handlers::auth::caller_subject;
 
 /// AI Agent Management Endpoints
 ///
 /// Register a new AI agent
 pub async fn register_ai_agent(
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
     Json(request): Json<RegisterAgentRequest>,
 ) -> ApiResult<Json<AgentResponse>> {
     info!("Registering AI agent: {}", request.name);
+    let actor = caller_subject(&headers)?;
 
     // Validate: (Removed check for AgentType::Human as it is not a variant of AgentType enum)
     // The type system ensures strictly valid AgentType values are deserialized.
@@ -49,34 +53,17 @@ pub async fn register_ai_agent(
         agent.config = config;
     }
 
-    let agent_id = agent.id;
+    // The session manager persists the agent so its sessions survive a restart
+    let agent_id = state.ai_sessions.register_agent(agent.clone()).await?;
 
-    // Store in database if available
-    if let Some(pool) = &state.db_pool {
-        match sqlx::query(
-            r#"
-            INSERT INTO ai_agents (id, name, agent_type, version, provider, config, created_at)
-            VALUES ($1, $2, $3, $4, $5, $6, NOW())
-            "#,
+    state
+        .audit_log
+        .record_or_warn(
+            AuditEvent::success(actor, "ai_agent.register")
+                .with_target(agent_id.to_string())
+                .with_details(serde_json::json!({ "name": agent.name })),
         )
-        .bind(agent_id)
-        .bind(&agent.name)
-        .bind(format!("{:?}", agent.agent_type))
-        .bind(&agent.version)
-        .bind(&agent.provider)
-        .bind(serde_json::to_value(&agent.config).unwrap())
-        .execute(pool)
-        .await
-        {
-            Ok(_) => {
-                info!("Stored agent {} in database", agent_id);
-            }
-            Err(e) => {
-                error!("Failed to store agent in database: {}", e);
-                // Continue anyway - registration can work without DB
-            }
-        }
-    }
+        .await;
 
     Ok(Json(AgentResponse {
         id: agent_id,
@@ -96,58 +83,17 @@ pub async fn list_ai_agents(
     info!("Listing AI agents");
 
     let mut agents = Vec::new();
-
-    // Fetch from database if available
-    if let Some(pool) = &state.db_pool {
-        match sqlx::query(
-            r#"
-            SELECT id, name, agent_type, version, provider, scopes, created_at
-            FROM ai_agents
-            ORDER BY created_at DESC
-            "#,
-        )
-        .fetch_all(pool)
-        .await
-        {
-            Ok(records) => {
-                for record in records {
-                    let id: Uuid = record.try_get("id").unwrap_or_default();
-                    let name: String = record.try_get("name").unwrap_or_default();
-                    let agent_type_str: String = record.try_get("agent_type").unwrap_or_default();
-                    let version: String = record.try_get("version").unwrap_or_default();
-                    let provider: String = record.try_get("provider").unwrap_or_default();
-                    let scopes: Vec<String> = record.try_get("scopes").unwrap_or_default();
-                    let created_at: chrono::NaiveDateTime =
-                        record.try_get("created_at").unwrap_or_default();
-
-                    // Parse agent_type from string
-                    let agent_type = match agent_type_str.as_str() {
-                        "Conversational" => AgentType::Conversational,
-                        "Automation" => AgentType::Automation,
-                        "Monitoring" => AgentType::Monitoring,
-                        "Control" => AgentType::Control,
-                        "Diagnostic" => AgentType::Diagnostic,
-                        "Predictive" => AgentType::Predictive,
-                        "MultiPurpose" => AgentType::MultiPurpose,
-                        _ => AgentType::Custom,
-                    };
-
-                    agents.push(AgentSummary {
-                        id,
-                        name,
-                        agent_type,
-                        version,
-                        provider,
-                        scopes,
-                        registered_at: created_at.and_utc(),
-                    });
-                }
-            }
-            Err(e) => {
-                error!("Failed to fetch agents from database: {}", e);
-            }
-        }
+    for agent in state.ai_sessions.list_agents().await {
+        agents.push(AgentSummary {
+            id: agent.id,
+            active_sessions: state.ai_sessions.get_agent_active_sessions(&agent.id).await,
+            name: agent.name,
+            agent_type: agent.agent_type,
+            version: agent.version,
+            provider: agent.provider,
+        });
     }
+    agents.sort_by(|a, b| a.name.cmp(&b.name));
 
     let total = agents.len();
     Ok(Json(AgentListResponse { agents, total }))
@@ -155,45 +101,41 @@ pub async fn list_ai_agents(
 
 /// Create a new AI session
 pub async fn create_ai_session(
-    State(_state): State<Arc<AppState>>,
+    State(state): State<Arc<AppState>>,
     Json(request): Json<CreateSessionRequest>,
 ) -> ApiResult<Json<SessionResponse>> {
     info!("Creating AI session for agent: {}", request.agent_id);
 
-    let session = AiSession::new(request.agent_id);
-    let session_id = session.id;
+    if state
+        .ai_sessions
+        .get_agent(&request.agent_id)
+        .await
+        .is_none()
+    {
+        return Err(UaipError::NotFound(format!("Agent {} not found", request.agent_id)).into());
+    }
 
-    Ok(Json(SessionResponse {
-        session_id,
-        agent_id: session.agent_id,
-        state: session.state,
-        started_at: session.started_at,
-        devices: session.devices,
-    }))
+    let session_id = state.ai_sessions.create_session(request.agent_id).await?;
+    let session = find_session(&state, session_id).await?;
+
+    Ok(Json(session.into()))
 }
 
 /// Get session information
 pub async fn get_ai_session(
-    State(_state): State<Arc<AppState>>,
+    State(state): State<Arc<AppState>>,
     Path(session_id): Path<Uuid>,
 ) -> ApiResult<Json<SessionResponse>> {
     info!("Getting AI session: {}", session_id);
 
-    // Mock session for now
-    let session = AiSession::new(Uuid::new_v4());
+    let session = find_session(&state, session_id).await?;
 
-    Ok(Json(SessionResponse {
-        session_id: session.id,
-        agent_id: session.agent_id,
-        state: session.state,
-        started_at: session.started_at,
-        devices: session.devices,
-    }))
+    Ok(Json(session.into()))
 }
 
 /// Add device to AI session
 pub async fn add_device_to_session(
-    State(_state): State<Arc<AppState>>,
+    State(state): State<Arc<AppState>>,
     Path(session_id): Path<Uuid>,
     Json(request): Json<AddDeviceRequest>,
 ) -> ApiResult<Json<SuccessResponse>> {
@@ -202,6 +144,12 @@ pub async fn add_device_to_session(
         request.device_id, session_id
     );
 
+    find_session(&state, session_id).await?;
+    state
+        .ai_sessions
+        .add_device_to_session(&session_id, request.device_id.clone())
+        .await?;
+
     Ok(Json(SuccessResponse {
         success: true,
         message: format!(
@@ -213,11 +161,14 @@ pub async fn add_device_to_session(
 
 /// Terminate AI session
 pub async fn terminate_ai_session(
-    State(_state): State<Arc<AppState>>,
+    State(state): State<Arc<AppState>>,
     Path(session_id): Path<Uuid>,
 ) -> ApiResult<Json<SuccessResponse>> {
     info!("Terminating AI session: {}", session_id);
 
+    find_session(&state, session_id).await?;
+    state.ai_sessions.terminate_session(&session_id).await?;
+
     Ok(Json(SuccessResponse {
         success: true,
         message: format!("Session {} terminated", session_id),
@@ -226,7 +177,7 @@ pub async fn terminate_ai_session(
 
 /// Send interaction to device via AI session
 pub async fn send_ai_interaction(
-    State(_state): State<Arc<AppState>>,
+    State(state): State<Arc<AppState>>,
     Path(session_id): Path<Uuid>,
     Json(request): Json<InteractionRequest>,
 ) -> ApiResult<Json<InteractionResultResponse>> {
@@ -235,6 +186,12 @@ pub async fn send_ai_interaction(
         session_id, request.device_id, request.interaction_type
     );
 
+    find_session(&state, session_id).await?;
+    state
+        .ai_sessions
+        .update_session_activity(&session_id)
+        .await?;
+
     Ok(Json(InteractionResultResponse {
         request_id: Uuid::new_v4(),
         session_id,
@@ -246,6 +203,15 @@ pub async fn send_ai_interaction(
     }))
 }
 
+/// Look up a session in the session manager
+async fn find_session(state: &AppState, session_id: Uuid) -> Result<AiSession, UaipError> {
+    state
+        .ai_sessions
+        .get_session(&session_id)
+        .await
+        .ok_or_else(|| UaipError::NotFound(format!("Session {} not found", session_id)))
+}
+
 // Request/Response Types
 
 #[derive(Debug, Deserialize)]
@@ -276,8 +242,7 @@ pub struct AgentSummary {
     pub agent_type: AgentType,
     pub version: String,
     pub provider: String,
-    pub scopes: Vec<String>,
-    pub registered_at: chrono::DateTime<chrono::Utc>,
+    pub active_sessions: usize,
 }
 
 #[derive(Debug, Serialize)]
@@ -300,6 +265,18 @@ pub struct SessionResponse {
     pub devices: Vec<DeviceId>,
 }
 
+impl From<AiSession> for SessionResponse {
+    fn from(session: AiSession) -> Self {
+        Self {
+            session_id: session.id,
+            agent_id: session.agent_id,
+            state: session.state,
+            started_at: session.started_at,
+            devices: session.devices,
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct AddDeviceRequest {
     pub device_id: DeviceId,
@@ -364,4 +341,77 @@ mod tests {
         assert_eq!(request.device_id, "device-123");
         assert_eq!(request.interaction_type, InteractionType::Query);
     }
+
+    async fn registered_agent(state: &Arc<AppState>) -> Uuid {
+        let agent = AiAgent::new("TestAgent".to_string(), AgentType::Control);
+        state.ai_sessions.register_agent(agent).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_session_lifecycle_goes_through_manager() {
+        let state = Arc::new(AppState::new());
+        let agent_id = registered_agent(&state).await;
+
+        let Json(created) = create_ai_session(
+            State(state.clone()),
+            Json(CreateSessionRequest { agent_id }),
+        )
+        .await
+        .unwrap();
+        let session_id = created.session_id;
+
+        let Json(added) = add_device_to_session(
+            State(state.clone()),
+            Path(session_id),
+            Json(AddDeviceRequest {
+                device_id: "device-123".to_string(),
+            }),
+        )
+        .await
+        .unwrap();
+        assert!(added.success);
+
+        let Json(session) = get_ai_session(State(state.clone()), Path(session_id))
+            .await
+            .unwrap();
+        assert_eq!(session.agent_id, agent_id);
+        assert_eq!(session.devices, vec!["device-123".to_string()]);
+
+        let Json(terminated) = terminate_ai_session(State(state.clone()), Path(session_id))
+            .await
+            .unwrap();
+        assert!(terminated.success);
+        let Json(session) = get_ai_session(State(state.clone()), Path(session_id))
+            .await
+            .unwrap();
+        assert_eq!(session.state, SessionState::Terminated);
+
+        let Json(agents) = list_ai_agents(State(state)).await.unwrap();
+        assert_eq!(agents.total, 1);
+        assert_eq!(agents.agents[0].active_sessions, 0);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_sessions_and_agents_are_not_found() {
+        let state = Arc::new(AppState::new());
+
+        assert!(matches!(
+            create_ai_session(
+                State(state.clone()),
+                Json(CreateSessionRequest {
+                    agent_id: Uuid::new_v4()
+                }),
+            )
+            .await,
+            Err(crate::api::rest::ApiError(UaipError::NotFound(_)))
+        ));
+        assert!(matches!(
+            get_ai_session(State(state.clone()), Path(Uuid::new_v4())).await,
+            Err(crate::api::rest::ApiError(UaipError::NotFound(_)))
+        ));
+        assert!(matches!(
+            terminate_ai_session(State(state), Path(Uuid::new_v4())).await,
+            Err(crate::api::rest::ApiError(UaipError::NotFound(_)))
+        ));
+    }
 }
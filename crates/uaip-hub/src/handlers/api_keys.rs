@@ -0,0 +1,198 @@
+//! API key management handlers (admin only)
+//!
+//! Keys are issued into, listed for and revoked within the caller's tenant.
+
+use axum::{
+    extract::{Path, State},
+    http::HeaderMap,
+    Json,
+};
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use uaip_core::error::UaipError;
+
+use crate::api::rest::{ApiResult, AppState};
+use crate::api_keys::{ApiKey, IssuedApiKey, NewApiKey};
+use crate::audit::AuditEvent;
+use crate::handlers::auth::require_scope;
+use crate::tenant::Tenant;
+
+/// API key issuance request
+#[derive(Debug, Deserialize)]
+pub struct IssueApiKeyRequest {
+    pub name: String,
+    /// Subject the key acts as (defaults to the key name)
+    pub subject: Option<String>,
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    /// Lifetime in seconds; keys without one never expire
+    pub expires_in_secs: Option<i64>,
+}
+
+/// API key list response
+#[derive(Debug, Serialize)]
+pub struct ApiKeyListResponse {
+    pub api_keys: Vec<ApiKey>,
+    pub total: usize,
+}
+
+/// Issue an API key; the key is only returned in this response
+pub async fn issue_api_key(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<IssueApiKeyRequest>,
+) -> ApiResult<Json<IssuedApiKey>> {
+    let claims = require_scope(&headers, "admin")?;
+
+    if request.name.is_empty() {
+        return Err(UaipError::InvalidParameter("name cannot be empty".to_string()).into());
+    }
+    if request.expires_in_secs.is_some_and(|secs| secs <= 0) {
+        return Err(
+            UaipError::InvalidParameter("expires_in_secs must be positive".to_string()).into(),
+        );
+    }
+    // A key cannot grant more than its issuer holds
+    if let Some(scope) = request.scopes.iter().find(|s| !claims.scopes.contains(s)) {
+        return Err(UaipError::AuthorizationFailed(format!(
+            "Cannot grant scope '{}' the issuer does not hold",
+            scope
+        ))
+        .into());
+    }
+
+    let issued = state
+        .api_keys
+        .issue(NewApiKey {
+            subject: request.subject.unwrap_or_else(|| request.name.clone()),
+            name: request.name,
+            scopes: request.scopes,
+            tenant_id: Tenant::from_claims(&claims).id().to_string(),
+            expires_at: request
+                .expires_in_secs
+                .map(|secs| Utc::now() + Duration::seconds(secs)),
+        })
+        .await?;
+
+    state
+        .audit_log
+        .record_or_warn(
+            AuditEvent::success(&claims.sub, "api_key.issue")
+                .with_target(issued.api_key.id.to_string())
+                .with_details(serde_json::json!({
+                    "name": issued.api_key.name,
+                    "subject": issued.api_key.subject,
+                    "scopes": issued.api_key.scopes,
+                })),
+        )
+        .await;
+
+    Ok(Json(issued))
+}
+
+/// List the tenant's API keys, newest first
+pub async fn list_api_keys(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> ApiResult<Json<ApiKeyListResponse>> {
+    let claims = require_scope(&headers, "admin")?;
+
+    let api_keys = state
+        .api_keys
+        .list(Tenant::from_claims(&claims).id())
+        .await?;
+    let total = api_keys.len();
+
+    Ok(Json(ApiKeyListResponse { api_keys, total }))
+}
+
+/// Revoke one of the tenant's API keys
+pub async fn revoke_api_key(
+    State(state): State<Arc<AppState>>,
+    Path(key_id): Path<Uuid>,
+    headers: HeaderMap,
+) -> ApiResult<Json<ApiKey>> {
+    let claims = require_scope(&headers, "admin")?;
+
+    let revoked = state
+        .api_keys
+        .revoke(Tenant::from_claims(&claims).id(), key_id)
+        .await?;
+
+    state
+        .audit_log
+        .record_or_warn(
+            AuditEvent::success(&claims.sub, "api_key.revoke").with_target(key_id.to_string()),
+        )
+        .await;
+
+    Ok(Json(revoked))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::auth::jwt_manager;
+
+    fn bearer(scopes: &[&str]) -> HeaderMap {
+        let token = jwt_manager(3600)
+            .generate_token(
+                "admin-1",
+                "admin@example.com",
+                scopes.iter().map(|s| s.to_string()).collect(),
+                None,
+            )
+            .unwrap();
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "Authorization",
+            format!("Bearer {}", token).parse().unwrap(),
+        );
+        headers
+    }
+
+    fn request(scopes: &[&str]) -> IssueApiKeyRequest {
+        IssueApiKeyRequest {
+            name: "line-3 gateway".to_string(),
+            subject: None,
+            scopes: scopes.iter().map(|s| s.to_string()).collect(),
+            expires_in_secs: Some(3600),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_issue_cannot_escalate_scopes() {
+        let state = Arc::new(AppState::new());
+        let headers = bearer(&["admin", "device:read"]);
+
+        let result = issue_api_key(
+            State(state.clone()),
+            headers.clone(),
+            Json(request(&["device:write"])),
+        )
+        .await;
+        assert!(matches!(
+            result,
+            Err(crate::api::rest::ApiError(UaipError::AuthorizationFailed(
+                _
+            )))
+        ));
+
+        let Json(issued) = issue_api_key(
+            State(state.clone()),
+            headers.clone(),
+            Json(request(&["device:read"])),
+        )
+        .await
+        .unwrap();
+        assert_eq!(issued.api_key.subject, "line-3 gateway");
+        assert_eq!(issued.api_key.tenant_id, "default");
+
+        let Json(list) = list_api_keys(State(state), headers).await.unwrap();
+        assert_eq!(list.total, 1);
+    }
+}
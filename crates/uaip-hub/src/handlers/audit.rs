@@ -0,0 +1,152 @@
+//! Audit log handlers (admin only)
+
+use axum::{
+    extract::{Query, State},
+    http::HeaderMap,
+    Json,
+};
+use serde::Serialize;
+use std::sync::Arc;
+
+use crate::api::rest::{ApiResult, AppState};
+use crate::audit::{AuditEntry, AuditQuery};
+use crate::handlers::auth::require_scope;
+
+/// Audit query response
+#[derive(Debug, Serialize)]
+pub struct AuditListResponse {
+    pub entries: Vec<AuditEntry>,
+    pub total: usize,
+}
+
+/// Audit chain verification response
+#[derive(Debug, Serialize)]
+pub struct AuditVerifyResponse {
+    pub valid: bool,
+    pub entries_checked: usize,
+    pub error: Option<String>,
+}
+
+/// Query audit entries, newest first
+pub async fn query_audit_log(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<AuditQuery>,
+) -> ApiResult<Json<AuditListResponse>> {
+    require_scope(&headers, "admin")?;
+
+    let entries = state.audit_log.query(&query).await?;
+    let total = entries.len();
+
+    Ok(Json(AuditListResponse { entries, total }))
+}
+
+/// Verify the audit hash chain
+pub async fn verify_audit_log(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> ApiResult<Json<AuditVerifyResponse>> {
+    require_scope(&headers, "admin")?;
+
+    let response = match state.audit_log.verify().await {
+        Ok(entries_checked) => AuditVerifyResponse {
+            valid: true,
+            entries_checked,
+            error: None,
+        },
+        Err(e) => {
+            tracing::warn!("Audit chain verification failed: {}", e);
+            AuditVerifyResponse {
+                valid: false,
+                entries_checked: 0,
+                error: Some(e.to_string()),
+            }
+        }
+    };
+
+    Ok(Json(response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::AuditEvent;
+    use uaip_auth::jwt::JwtManager;
+
+    fn bearer(scopes: Vec<String>) -> HeaderMap {
+        let jwt_secret = std::env::var("JWT_SECRET")
+            .unwrap_or_else(|_| "uaip-development-secret-change-in-production".to_string());
+        let jwt_manager = JwtManager::new(
+            &jwt_secret,
+            "uaip-hub".to_string(),
+            "uaip-api".to_string(),
+            3600,
+        );
+        let token = jwt_manager
+            .generate_token("user-1", "admin@example.com", scopes, None)
+            .unwrap();
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "Authorization",
+            format!("Bearer {}", token).parse().unwrap(),
+        );
+        headers
+    }
+
+    #[tokio::test]
+    async fn test_query_requires_admin() {
+        let state = Arc::new(AppState::new());
+
+        let result = query_audit_log(
+            State(state.clone()),
+            HeaderMap::new(),
+            Query(AuditQuery::default()),
+        )
+        .await;
+        assert!(result.is_err());
+
+        let result = query_audit_log(
+            State(state),
+            bearer(vec!["device:read".to_string()]),
+            Query(AuditQuery::default()),
+        )
+        .await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_query_filters_entries() {
+        let state = Arc::new(AppState::new());
+        state
+            .audit_log
+            .record(AuditEvent::success("hub", "device.register").with_target("device-001"))
+            .await
+            .unwrap();
+        state
+            .audit_log
+            .record(AuditEvent::success("hub", "device.command").with_target("device-001"))
+            .await
+            .unwrap();
+
+        let query = AuditQuery {
+            action: Some("device.command".to_string()),
+            ..Default::default()
+        };
+        let Json(response) = query_audit_log(
+            State(state.clone()),
+            bearer(vec!["admin".to_string()]),
+            Query(query),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.total, 1);
+        assert_eq!(response.entries[0].action, "device.command");
+
+        let Json(verify) = verify_audit_log(State(state), bearer(vec!["admin".to_string()]))
+            .await
+            .unwrap();
+        assert!(verify.valid);
+        assert_eq!(verify.entries_checked, 2);
+    }
+}
@@ -4,10 +4,12 @@ use axum::{extract::State, Json};
 use std::sync::Arc;
 use serde::Deserialize;
 
-use uaip_auth::jwt::JwtManager;
+use uaip_auth::jwt::{Claims, JwtManager};
 use uaip_core::error::UaipError;
 
 use crate::api::rest::{ApiResult, AppState, LoginRequest, LoginResponse, RegisterRequest};
+use crate::audit::{AuditEvent, AuditLog};
+use crate::tenant::DEFAULT_TENANT;
 
 
 
@@ -17,9 +19,7 @@ pub async fn register(
     State(state): State<Arc<AppState>>,
     Json(request): Json<RegisterRequest>,
 ) -> ApiResult<Json<LoginResponse>> {
-    let db_pool = state.db_pool.as_ref().ok_or_else(|| {
-        UaipError::InternalError("Database not configured".to_string())
-    })?;
+    let db_pool = state.db()?;
 
     // 1. Validate Password Strength (Basic)
     if request.password.len() < 12 {
@@ -111,7 +111,9 @@ pub async fn register(
         &request.name,
         scopes,
         false,
+        DEFAULT_TENANT,
         db_pool,
+        &state.audit_log,
         true
     ).await
 }
@@ -121,10 +123,7 @@ pub async fn login(
     State(state): State<Arc<AppState>>,
     Json(request): Json<LoginRequest>,
 ) -> ApiResult<Json<LoginResponse>> {
-    let db_pool = state
-        .db_pool
-        .as_ref()
-        .ok_or_else(|| UaipError::InternalError("Database not configured".to_string()))?;
+    let db_pool = state.db()?;
 
     // Validate request inputs (basic)
     if request.client_id.is_empty() {
@@ -149,10 +148,12 @@ pub async fn login(
         role: String,
         active: bool,
         require_password_change: bool,
+        tenant_id: String,
     }
 
     let user = sqlx::query_as::<_, UserRecord>(
-        "SELECT id, email, password_hash, name, role, active, require_password_change FROM users WHERE email = $1"
+        "SELECT id, email, password_hash, name, role, active, require_password_change, tenant_id
+         FROM users WHERE email = $1"
     )
     .bind(&request.client_id)
     .fetch_optional(db_pool)
@@ -174,14 +175,14 @@ pub async fn login(
         })?;
 
         if !password_valid {
-             // Log audit... (omitted for brevity in this refactor, but should be here)
+             log_audit_event(&state.audit_log, &user.id.to_string(), "user", "login", false, Some("Invalid credentials")).await;
              return Err(UaipError::AuthenticationFailed("Invalid credentials".to_string()).into());
         }
 
         // Map Role to Scopes (Logic from users.rs)
         let scopes: Vec<String> = match user.role.as_str() {
             "admin" => vec!["admin".into(), "device:read".into(), "device:write".into(), "ai:read".into(), "ai:write".into()],
-            "operator" => vec!["device:read".into(), "device:write".into(), "ai:read".into()],
+            "operator" => vec!["device:read".into(), "device:write".into(), "command:*:*".into(), "ai:read".into()],
             _ => vec!["device:read".into(), "ai:read".into()],
         };
 
@@ -191,7 +192,9 @@ pub async fn login(
             &user.name, // Wait, I didn't select name in the query above!
             scopes, 
             user.require_password_change, 
+            &user.tenant_id,
             db_pool,
+            &state.audit_log,
             true // is_user
         ).await;
     }
@@ -205,10 +208,12 @@ pub async fn login(
         client_secret_hash: String,
         scopes: Vec<String>,
         active: bool,
+        tenant_id: String,
     }
 
     let agent = sqlx::query_as::<_, AiAgent>(
-        "SELECT id, client_id, name, client_secret_hash, scopes, active FROM ai_agents WHERE client_id = $1"
+        "SELECT id, client_id, name, client_secret_hash, scopes, active, tenant_id
+         FROM ai_agents WHERE client_id = $1"
     )
     .bind(&request.client_id)
     .fetch_optional(db_pool)
@@ -231,6 +236,7 @@ pub async fn login(
     })?;
 
     if !password_valid {
+        log_audit_event(&state.audit_log, &agent.id.to_string(), "ai_agent", "login", false, Some("Invalid credentials")).await;
         return Err(UaipError::AuthenticationFailed("Invalid credentials".to_string()).into());
     }
 
@@ -246,34 +252,44 @@ pub async fn login(
         &agent.name, 
         scopes, 
         false, // Agents don't have password change requirement
+        &agent.tenant_id,
         db_pool, 
+        &state.audit_log,
         false // is_agent
     ).await;
 }
 
 // Helper to generate token response (avoids duplication)
+#[allow(clippy::too_many_arguments)]
 async fn generate_token_response(
     id: &str,
     client_id: &str,
     _name: &str, // unused for now but good to pass
     scopes: Vec<String>,
     require_password_change: bool,
+    tenant_id: &str,
     db_pool: &sqlx::PgPool,
+    audit_log: &AuditLog,
     is_user: bool,
 ) -> ApiResult<Json<LoginResponse>> {
     let jwt_secret = std::env::var("JWT_SECRET").unwrap_or_else(|_| "uaip-development-secret-change-in-production".to_string());
     
     let jwt_manager = JwtManager::new(&jwt_secret, "uaip-hub".to_string(), "uaip-api".to_string(), 3600);
-    let access_token = jwt_manager.generate_token(id, client_id, scopes.clone(), None).map_err(|e| {
-        tracing::error!("Failed to generate token: {}", e);
-        UaipError::InternalError("Failed to generate token".to_string())
-    })?;
+    let tenant_id = Some(tenant_id.to_string());
+    let access_token = jwt_manager
+        .generate_tenant_token(id, client_id, scopes.clone(), None, tenant_id.clone())
+        .map_err(|e| {
+            tracing::error!("Failed to generate token: {}", e);
+            UaipError::InternalError("Failed to generate token".to_string())
+        })?;
 
     let refresh_jwt_manager = JwtManager::new(&jwt_secret, "uaip-hub".to_string(), "uaip-api".to_string(), 604800);
-    let refresh_token = refresh_jwt_manager.generate_token(id, client_id, scopes.clone(), None).map_err(|e| {
-        tracing::error!("Failed to generate refresh token: {}", e);
-        UaipError::InternalError("Failed to generate refresh token".to_string())
-    })?;
+    let refresh_token = refresh_jwt_manager
+        .generate_tenant_token(id, client_id, scopes.clone(), None, tenant_id)
+        .map_err(|e| {
+            tracing::error!("Failed to generate refresh token: {}", e);
+            UaipError::InternalError("Failed to generate refresh token".to_string())
+        })?;
 
     // Update last_authenticated
     let update_query = if is_user {
@@ -289,7 +305,7 @@ async fn generate_token_response(
     sqlx::query(update_query).bind(uuid_id).execute(db_pool).await.ok();
 
     // Log success
-    log_audit_event(db_pool, id, if is_user { "user" } else { "ai_agent" }, "login", true, None).await;
+    log_audit_event(audit_log, id, if is_user { "user" } else { "ai_agent" }, "login", true, None).await;
 
     Ok(Json(LoginResponse {
         access_token,
@@ -354,9 +370,7 @@ pub async fn change_password(
     })?;
 
     // 2. Database interaction
-    let db_pool = state.db_pool.as_ref().ok_or_else(|| {
-        UaipError::InternalError("Database not configured".to_string())
-    })?;
+    let db_pool = state.db()?;
 
     // 3. Verify current password
     // 3. Verify current password and update
@@ -422,28 +436,73 @@ pub async fn change_password(
 
 /// Log an audit event
 async fn log_audit_event(
-    pool: &sqlx::PgPool,
+    audit_log: &AuditLog,
     entity_id: &str,
     entity_type: &str,
     action: &str,
     success: bool,
     error_message: Option<&str>,
 ) {
-    sqlx::query(
-        "INSERT INTO audit_log (entity_id, entity_type, action, success, error_message, metadata)
-         VALUES ($1, $2, $3, $4, $5, '{}')",
-    )
-    .bind(entity_id)
-    .bind(entity_type)
-    .bind(action)
-    .bind(success)
-    .bind(error_message)
-    .execute(pool)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to log audit event: {}", e);
+    let event = if success {
+        AuditEvent::success(entity_id, format!("{}.{}", entity_type, action))
+    } else {
+        AuditEvent::failure(entity_id, format!("{}.{}", entity_type, action))
+    };
+    let details = match error_message {
+        Some(message) => serde_json::json!({ "error": message }),
+        None => serde_json::json!({}),
+    };
+
+    audit_log.record_or_warn(event.with_details(details)).await;
+}
+
+/// Validate the bearer token in `headers` and require the given scope
+pub(crate) fn require_scope(
+    headers: &axum::http::HeaderMap,
+    scope: &str,
+) -> Result<Claims, UaipError> {
+    let claims = require_claims(headers)?;
+
+    if !claims.scopes.iter().any(|s| s == scope) {
+        return Err(UaipError::AuthorizationFailed(format!("Scope '{}' required", scope)));
+    }
+
+    Ok(claims)
+}
+
+/// Validate the bearer token in `headers`, rejecting requests without one
+pub(crate) fn require_claims(headers: &axum::http::HeaderMap) -> Result<Claims, UaipError> {
+    bearer_claims(headers)?.ok_or_else(|| {
+        UaipError::AuthenticationFailed("Missing Authorization header".to_string())
+    })
+}
+
+/// Audit actor for a request: its bearer token's subject, or `anonymous` without one
+pub(crate) fn caller_subject(headers: &axum::http::HeaderMap) -> Result<String, UaipError> {
+    Ok(bearer_claims(headers)?.map_or_else(|| "anonymous".to_string(), |claims| claims.sub))
+}
+
+/// Validate the bearer token in `headers`, if the request carries one
+pub(crate) fn bearer_claims(headers: &axum::http::HeaderMap) -> Result<Option<Claims>, UaipError> {
+    let Some(header) = headers.get("Authorization") else {
+        return Ok(None);
+    };
+    let token = header
+        .to_str()
+        .map_err(|_| UaipError::AuthenticationFailed("Invalid Authorization header".to_string()))?
+        .strip_prefix("Bearer ")
+        .ok_or_else(|| UaipError::AuthenticationFailed("Invalid token type".to_string()))?;
+
+    jwt_manager(3600).validate_token(token).map(Some).map_err(|_| {
+        UaipError::AuthenticationFailed("Invalid or expired token".to_string())
     })
-    .ok();
+}
+
+/// JWT manager for hub-issued tokens with the given lifetime
+pub(crate) fn jwt_manager(expiry_seconds: i64) -> JwtManager {
+    let jwt_secret = std::env::var("JWT_SECRET")
+        .unwrap_or_else(|_| "uaip-development-secret-change-in-production".to_string());
+    JwtManager::new(&jwt_secret, "uaip-hub".to_string(), "uaip-api".to_string(), expiry_seconds)
 }
 
 #[cfg(test)]
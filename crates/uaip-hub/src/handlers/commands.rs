@@ -1,3 +1,368 @@
 //! Command handlers
+//!
+//! Visibility into the commands queued for a device, and cancellation of commands that
+//! have not been delivered yet. Devices long-poll for their next command and report its
+//! result once they have run it.
 
-// Placeholder - to be implemented
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::api::rest::{ApiResult, AppState};
+use crate::audit::AuditEvent;
+use crate::command_log::{CommandQuery, CommandResult, DeviceCommand};
+use crate::handlers::auth::caller_subject;
+use crate::services;
+use crate::tenant::Tenant;
+
+/// Longest a device can wait for its next command in one request
+const MAX_COMMAND_WAIT_SECS: u64 = 60;
+
+/// Device command list response
+#[derive(Debug, Serialize)]
+pub struct CommandListResponse {
+    pub commands: Vec<DeviceCommand>,
+    pub total: usize,
+}
+
+/// Long-poll parameters for a device's next command
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct NextCommandQuery {
+    /// Seconds to wait when no command is pending (default 0, max 60)
+    #[serde(default)]
+    pub wait_secs: Option<u64>,
+}
+
+/// List a device's commands, newest first
+pub async fn list_device_commands(
+    State(state): State<Arc<AppState>>,
+    Path(device_id): Path<String>,
+    Query(query): Query<CommandQuery>,
+    headers: HeaderMap,
+) -> ApiResult<Json<CommandListResponse>> {
+    let tenant = Tenant::from_headers(&headers)?;
+
+    let commands = state
+        .command_log
+        .list(tenant.id(), &device_id, &query)
+        .await?;
+    let total = commands.len();
+
+    Ok(Json(CommandListResponse { commands, total }))
+}
+
+/// Cancel a command that has not been delivered yet
+pub async fn cancel_command(
+    State(state): State<Arc<AppState>>,
+    Path(message_id): Path<String>,
+    headers: HeaderMap,
+) -> ApiResult<Json<DeviceCommand>> {
+    let tenant = Tenant::from_headers(&headers)?;
+    let actor = caller_subject(&headers)?;
+
+    let command = state.command_log.cancel(tenant.id(), &message_id).await?;
+    state.message_queue.remove(&message_id).await;
+
+    state
+        .audit_log
+        .record_or_warn(
+            AuditEvent::success(actor, "device.command.cancel")
+                .with_target(&command.device_id)
+                .with_details(serde_json::json!({
+                    "action": command.action,
+                    "message_id": command.message_id,
+                })),
+        )
+        .await;
+
+    Ok(Json(command))
+}
+
+/// Hand a device its next pending command and mark it delivered
+///
+/// Waits up to `wait_secs` for a command to be queued; responds 204 if none was.
+pub async fn next_device_command(
+    State(state): State<Arc<AppState>>,
+    Path(device_id): Path<String>,
+    Query(query): Query<NextCommandQuery>,
+    headers: HeaderMap,
+) -> ApiResult<Response> {
+    let tenant = Tenant::from_headers(&headers)?;
+
+    let wait = Duration::from_secs(query.wait_secs.unwrap_or(0).min(MAX_COMMAND_WAIT_SECS));
+    let command = services::devices::next_command(&state, &tenant, &device_id, wait).await?;
+
+    Ok(match command {
+        Some(command) => Json(command).into_response(),
+        None => StatusCode::NO_CONTENT.into_response(),
+    })
+}
+
+/// Record the result a device reports for a command delivered to it
+pub async fn report_command_result(
+    State(state): State<Arc<AppState>>,
+    Path((device_id, message_id)): Path<(String, String)>,
+    headers: HeaderMap,
+    Json(result): Json<CommandResult>,
+) -> ApiResult<Json<DeviceCommand>> {
+    let tenant = Tenant::from_headers(&headers)?;
+
+    let command = state
+        .command_log
+        .record_result(tenant.id(), &device_id, &message_id, result)
+        .await?;
+
+    let event = if command.result.as_ref().is_some_and(|r| r.success) {
+        AuditEvent::success(&device_id, "device.command.result")
+    } else {
+        AuditEvent::failure(&device_id, "device.command.result")
+    };
+    state
+        .audit_log
+        .record_or_warn(event.with_details(serde_json::json!({
+            "action": command.action,
+            "message_id": command.message_id,
+        })))
+        .await;
+
+    Ok(Json(command))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::rest::{ApiError, CommandRequest};
+    use crate::command_log::CommandStatus;
+    use crate::services::devices::command_message;
+    use chrono::Utc;
+    use uaip_core::error::UaipError;
+    use uaip_core::message::Priority;
+
+    async fn queue_command(state: &AppState, message_id: &str, status: CommandStatus) {
+        let request = CommandRequest {
+            action: "open".to_string(),
+            parameters: None,
+            priority: None,
+            preempt: false,
+        };
+        state
+            .command_log
+            .record(&DeviceCommand {
+                message_id: message_id.to_string(),
+                correlation_id: format!("corr-{}", message_id),
+                device_id: "valve-1".to_string(),
+                tenant_id: "default".to_string(),
+                action: request.action.clone(),
+                priority: "normal".to_string(),
+                parameters: serde_json::json!({}),
+                status,
+                created_at: Utc::now(),
+                cancelled_at: None,
+                delivered_at: None,
+                completed_at: None,
+                result: None,
+            })
+            .await
+            .unwrap();
+        if status == CommandStatus::Pending {
+            state
+                .message_queue
+                .push(command_message(
+                    &state.messaging,
+                    "valve-1",
+                    message_id,
+                    "corr",
+                    &request,
+                    Priority::Normal,
+                ))
+                .await;
+        }
+    }
+
+    fn pending() -> Query<CommandQuery> {
+        Query(CommandQuery {
+            status: Some(CommandStatus::Pending),
+            limit: None,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_list_pending_commands() {
+        let state = Arc::new(AppState::new());
+        queue_command(&state, "msg-1", CommandStatus::Pending).await;
+        queue_command(&state, "msg-2", CommandStatus::Delivered).await;
+        queue_command(&state, "msg-3", CommandStatus::Pending).await;
+
+        let Json(list) = list_device_commands(
+            State(state.clone()),
+            Path("valve-1".to_string()),
+            pending(),
+            HeaderMap::new(),
+        )
+        .await
+        .unwrap();
+        let ids: Vec<&str> = list
+            .commands
+            .iter()
+            .map(|c| c.message_id.as_str())
+            .collect();
+        assert_eq!(ids, ["msg-3", "msg-1"]);
+
+        let Json(other) = list_device_commands(
+            State(state),
+            Path("valve-2".to_string()),
+            pending(),
+            HeaderMap::new(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(other.total, 0);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_undelivered_command() {
+        let state = Arc::new(AppState::new());
+        queue_command(&state, "msg-1", CommandStatus::Pending).await;
+        queue_command(&state, "msg-2", CommandStatus::Delivered).await;
+
+        let Json(cancelled) = cancel_command(
+            State(state.clone()),
+            Path("msg-1".to_string()),
+            HeaderMap::new(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(cancelled.status, CommandStatus::Cancelled);
+        assert!(cancelled.cancelled_at.is_some());
+        // No longer waiting for delivery
+        assert!(state.message_queue.is_empty().await);
+
+        for message_id in ["msg-1", "msg-2"] {
+            let result = cancel_command(
+                State(state.clone()),
+                Path(message_id.to_string()),
+                HeaderMap::new(),
+            )
+            .await;
+            assert!(matches!(result, Err(ApiError(UaipError::InvalidState(_)))));
+        }
+        let result = cancel_command(
+            State(state.clone()),
+            Path("msg-404".to_string()),
+            HeaderMap::new(),
+        )
+        .await;
+        assert!(matches!(result, Err(ApiError(UaipError::NotFound(_)))));
+
+        let Json(list) = list_device_commands(
+            State(state),
+            Path("valve-1".to_string()),
+            pending(),
+            HeaderMap::new(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(list.total, 0);
+    }
+
+    async fn poll(state: &Arc<AppState>, wait_secs: u64) -> Response {
+        next_device_command(
+            State(state.clone()),
+            Path("valve-1".to_string()),
+            Query(NextCommandQuery {
+                wait_secs: Some(wait_secs),
+            }),
+            HeaderMap::new(),
+        )
+        .await
+        .unwrap()
+    }
+
+    async fn report(
+        state: &Arc<AppState>,
+        device_id: &str,
+        message_id: &str,
+        success: bool,
+    ) -> ApiResult<Json<DeviceCommand>> {
+        report_command_result(
+            State(state.clone()),
+            Path((device_id.to_string(), message_id.to_string())),
+            HeaderMap::new(),
+            Json(CommandResult {
+                success,
+                message: Some("Valve opened".to_string()),
+                data: Some(serde_json::json!({"open": true})),
+            }),
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_waiting_device_receives_and_acks_command() {
+        let state = Arc::new(AppState::new());
+        assert_eq!(poll(&state, 0).await.status(), StatusCode::NO_CONTENT);
+
+        // The device is already waiting when the command is queued
+        let device = tokio::spawn({
+            let state = state.clone();
+            async move { poll(&state, 5).await }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        queue_command(&state, "msg-1", CommandStatus::Pending).await;
+
+        let response = tokio::time::timeout(Duration::from_secs(2), device)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let command: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(command["message_id"], "msg-1");
+        assert_eq!(command["action"], "open");
+        assert_eq!(command["status"], "delivered");
+        assert!(state.message_queue.is_empty().await);
+
+        // Delivered commands can no longer be cancelled, only acked
+        let result = cancel_command(
+            State(state.clone()),
+            Path("msg-1".to_string()),
+            HeaderMap::new(),
+        )
+        .await;
+        assert!(matches!(result, Err(ApiError(UaipError::InvalidState(_)))));
+
+        let Json(acked) = report(&state, "valve-1", "msg-1", true).await.unwrap();
+        assert_eq!(acked.status, CommandStatus::Completed);
+        assert!(acked.delivered_at.is_some() && acked.completed_at.is_some());
+        assert_eq!(
+            acked.result.unwrap().data,
+            Some(serde_json::json!({"open": true}))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_result_requires_delivery_to_the_device() {
+        let state = Arc::new(AppState::new());
+        queue_command(&state, "msg-1", CommandStatus::Pending).await;
+
+        let result = report(&state, "valve-1", "msg-1", true).await;
+        assert!(matches!(result, Err(ApiError(UaipError::InvalidState(_)))));
+
+        assert_eq!(poll(&state, 0).await.status(), StatusCode::OK);
+        let result = report(&state, "valve-2", "msg-1", true).await;
+        assert!(matches!(result, Err(ApiError(UaipError::NotFound(_)))));
+
+        let Json(failed) = report(&state, "valve-1", "msg-1", false).await.unwrap();
+        assert_eq!(failed.status, CommandStatus::Failed);
+        let result = report(&state, "valve-1", "msg-1", true).await;
+        assert!(matches!(result, Err(ApiError(UaipError::InvalidState(_)))));
+    }
+}
@@ -0,0 +1,221 @@
+//! Internal state snapshot for diagnostics (admin only)
+//!
+//! Everything here is read from in-memory counters; no database, cache or adapter
+//! round-trips are made, so the endpoint is safe to poll while diagnosing an incident.
+
+use axum::{extract::State, http::HeaderMap, Extension, Json};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::sync::Arc;
+
+use uaip_router::priority_queue::PriorityStats;
+use uaip_router::qos::{QosStats, TrackedCounts};
+
+use crate::api::rest::{ApiResult, AppState};
+use crate::handlers::auth::require_scope;
+use crate::health::{HealthCacheStats, HealthChecker};
+
+/// Snapshot of the hub's internal state
+#[derive(Debug, Serialize)]
+pub struct DebugState {
+    pub generated_at: DateTime<Utc>,
+    pub executions: ExecutionState,
+    pub qos: QosState,
+    pub queue: PriorityStats,
+    pub caches: CacheState,
+    pub sessions: SessionState,
+}
+
+/// Scenario executions held by the automation engine
+#[derive(Debug, Serialize)]
+pub struct ExecutionState {
+    /// Executions that have not finished yet
+    pub active_scenario_executions: usize,
+    /// Executions in memory, including finished ones not yet handed to the history
+    pub retained_scenario_executions: usize,
+    pub registered_scenarios: usize,
+    pub registered_rules: usize,
+}
+
+/// QoS delivery tracking
+#[derive(Debug, Serialize)]
+pub struct QosState {
+    /// Messages awaiting acknowledgment
+    pub tracked: TrackedCounts,
+    pub stats: QosStats,
+}
+
+/// In-process caches
+#[derive(Debug, Serialize)]
+pub struct CacheState {
+    /// Health check cache; absent when no health checker is installed
+    pub health: Option<HealthCacheStats>,
+    /// Devices with a command throttle bucket
+    pub throttled_devices: usize,
+}
+
+/// Connected clients
+#[derive(Debug, Serialize)]
+pub struct SessionState {
+    pub websocket_sessions: usize,
+    pub result_streams: usize,
+    pub telemetry_subscribers: usize,
+}
+
+/// Snapshot engine, queue, cache and session counters
+pub async fn debug_state(
+    State(state): State<Arc<AppState>>,
+    health_checker: Option<Extension<Arc<HealthChecker>>>,
+    headers: HeaderMap,
+) -> ApiResult<Json<DebugState>> {
+    require_scope(&headers, "admin")?;
+
+    let executions = {
+        let automation = state.automation.lock().await;
+        let scenarios = &automation.scenario_engine;
+        ExecutionState {
+            active_scenario_executions: scenarios.get_active_executions().len(),
+            retained_scenario_executions: scenarios.execution_count(),
+            registered_scenarios: scenarios.get_all_scenarios().len(),
+            registered_rules: automation.rule_engine.rule_count(),
+        }
+    };
+
+    Ok(Json(DebugState {
+        generated_at: Utc::now(),
+        executions,
+        qos: QosState {
+            tracked: state.qos_handler.tracked_by_qos().await,
+            stats: state.qos_handler.get_stats().await,
+        },
+        queue: state.message_queue.stats_by_priority().await,
+        caches: CacheState {
+            health: health_checker.map(|Extension(checker)| checker.cache_stats()),
+            throttled_devices: state.command_throttle.tracked_devices().await,
+        },
+        sessions: SessionState {
+            websocket_sessions: state.ws_sessions.session_count().await,
+            result_streams: state.ws_sessions.stream_count().await,
+            telemetry_subscribers: state.telemetry_feed.receiver_count(),
+        },
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::auth::jwt_manager;
+    use std::collections::HashMap;
+    use uaip_core::message::{EntityType, Priority, UaipMessage};
+    use uaip_orchestrator::scenario::{
+        Scenario, ScenarioAction, ScenarioActionConfig, ScenarioState, ScenarioTrigger, TriggerType,
+    };
+    use uaip_router::qos::QosLevel;
+
+    fn bearer(scopes: &[&str]) -> HeaderMap {
+        let token = jwt_manager(3600)
+            .generate_token(
+                "admin-1",
+                "admin@example.com",
+                scopes.iter().map(|s| s.to_string()).collect(),
+                None,
+            )
+            .unwrap();
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "Authorization",
+            format!("Bearer {}", token).parse().unwrap(),
+        );
+        headers
+    }
+
+    fn scenario() -> Scenario {
+        Scenario {
+            id: "lights-off".to_string(),
+            name: "Lights off".to_string(),
+            description: None,
+            enabled: true,
+            triggers: vec![ScenarioTrigger {
+                trigger_type: TriggerType::Manual,
+                config: HashMap::new(),
+                conditions: vec![],
+            }],
+            actions: vec![ScenarioActionConfig {
+                action: ScenarioAction::SendNotification,
+                parameters: HashMap::new(),
+                wait: true,
+                timeout_seconds: None,
+            }],
+            state: ScenarioState::Active,
+            metadata: HashMap::new(),
+            execution_count: 0,
+            last_triggered: None,
+            last_result: None,
+            last_result_summary: None,
+            tenant_id: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn message() -> UaipMessage {
+        UaipMessage::new(
+            "device-1".to_string(),
+            EntityType::Device,
+            "agent-1".to_string(),
+            EntityType::AiAgent,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_debug_state_reflects_execution_and_tracked_message() {
+        let state = Arc::new(AppState::new());
+        {
+            let mut automation = state.automation.lock().await;
+            automation
+                .scenario_engine
+                .register_scenario(scenario())
+                .unwrap();
+            automation
+                .scenario_engine
+                .trigger_scenario("lights-off", HashMap::new())
+                .unwrap();
+        }
+        state
+            .qos_handler
+            .handle_message(message(), QosLevel::AtLeastOnce)
+            .await
+            .unwrap();
+        state
+            .message_queue
+            .push(message().with_priority(Priority::High))
+            .await;
+
+        let Json(snapshot) = debug_state(State(state.clone()), None, bearer(&["admin"]))
+            .await
+            .unwrap();
+        assert_eq!(snapshot.executions.active_scenario_executions, 1);
+        assert_eq!(snapshot.executions.registered_scenarios, 1);
+        assert_eq!(snapshot.qos.tracked.at_least_once, 1);
+        assert_eq!(snapshot.qos.tracked.exactly_once, 0);
+        assert_eq!(snapshot.queue.total, 1);
+        assert_eq!(snapshot.queue.high, 1);
+        assert!(snapshot.caches.health.is_none());
+
+        // Reading the snapshot must not drain anything
+        assert_eq!(state.message_queue.len().await, 1);
+        assert_eq!(state.qos_handler.tracked_count().await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_debug_state_requires_admin() {
+        let state = Arc::new(AppState::new());
+
+        let result = debug_state(State(state.clone()), None, bearer(&["device:read"])).await;
+        assert!(result.is_err());
+        assert!(debug_state(State(state), None, HeaderMap::new())
+            .await
+            .is_err());
+    }
+}
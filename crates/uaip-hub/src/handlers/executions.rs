@@ -0,0 +1,263 @@
+//! Execution state event stream
+//!
+//! Workflow and scenario state transitions are published on the hub's execution feed,
+//! streamed to clients as server-sent events and, when NATS is configured, forwarded
+//! to it.
+
+use axum::{
+    extract::State,
+    http::HeaderMap,
+    response::sse::{Event, KeepAlive, Sse},
+};
+use futures_util::{Stream, StreamExt};
+use std::convert::Infallible;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use tokio_stream::wrappers::{errors::BroadcastStreamRecvError, BroadcastStream};
+
+use uaip_orchestrator::events::{ExecutionKind, ExecutionStateChanged};
+
+use crate::api::rest::{ApiResult, AppState};
+use crate::handlers::auth::require_claims;
+use crate::tenant::Tenant;
+
+/// Events buffered for live subscribers; slower subscribers skip ahead
+pub const EXECUTION_FEED_CAPACITY: usize = 256;
+
+/// NATS subject prefix; events go to `uaip.executions.<kind>`
+pub const EXECUTION_SUBJECT_PREFIX: &str = "uaip.executions";
+
+/// NATS subject of an event
+pub fn nats_subject(event: &ExecutionStateChanged) -> String {
+    let kind = match event.kind {
+        ExecutionKind::Workflow => "workflow",
+        ExecutionKind::Scenario => "scenario",
+    };
+    format!("{}.{}", EXECUTION_SUBJECT_PREFIX, kind)
+}
+
+/// Whether an execution event may be shown to a tenant
+///
+/// Executions of unscoped scenarios, and workflow executions, are visible to every tenant.
+pub fn visible_to(event: &ExecutionStateChanged, tenant: &Tenant) -> bool {
+    event
+        .tenant_id
+        .as_deref()
+        .is_none_or(|tenant_id| tenant_id == tenant.id())
+}
+
+/// Stream the caller's execution state changes as server-sent `execution_state` events
+pub async fn stream_execution_events(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> ApiResult<Sse<impl Stream<Item = Result<Event, Infallible>>>> {
+    require_claims(&headers)?;
+    let tenant = Tenant::from_headers(&headers)?;
+    let events = BroadcastStream::new(state.execution_feed.subscribe());
+
+    let stream = events.filter_map(move |event| {
+        let event = match event {
+            Ok(event) if visible_to(&event, &tenant) => Event::default()
+                .event("execution_state")
+                .json_data(&event)
+                .ok()
+                .map(Ok),
+            Ok(_) => None,
+            Err(BroadcastStreamRecvError::Lagged(skipped)) => {
+                tracing::warn!(
+                    "Execution event subscriber lagged, skipped {} events",
+                    skipped
+                );
+                None
+            }
+        };
+        std::future::ready(event)
+    });
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+/// Publish every event on the feed to NATS until the feed closes
+pub fn spawn_nats_forwarder(
+    client: async_nats::Client,
+    mut events: broadcast::Receiver<ExecutionStateChanged>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            let event = match events.recv().await {
+                Ok(event) => event,
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::warn!(
+                        "NATS forwarder lagged, skipped {} execution events",
+                        skipped
+                    );
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+
+            let payload = match serde_json::to_vec(&event) {
+                Ok(payload) => payload,
+                Err(e) => {
+                    tracing::warn!("Failed to serialize execution event: {}", e);
+                    continue;
+                }
+            };
+            if let Err(e) = client.publish(nats_subject(&event), payload.into()).await {
+                tracing::warn!("Failed to publish execution event to NATS: {}", e);
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::response::IntoResponse;
+    use chrono::Utc;
+    use std::collections::HashMap;
+    use uaip_orchestrator::scenario::{
+        Scenario, ScenarioAction, ScenarioActionConfig, ScenarioState, ScenarioTrigger, TriggerType,
+    };
+
+    fn scenario() -> Scenario {
+        Scenario {
+            id: "lights-off".to_string(),
+            name: "Lights off".to_string(),
+            description: None,
+            enabled: true,
+            triggers: vec![ScenarioTrigger {
+                trigger_type: TriggerType::Manual,
+                config: HashMap::new(),
+                conditions: vec![],
+            }],
+            actions: vec![ScenarioActionConfig {
+                action: ScenarioAction::SendNotification,
+                parameters: HashMap::new(),
+                wait: true,
+                timeout_seconds: None,
+            }],
+            state: ScenarioState::Active,
+            metadata: HashMap::new(),
+            execution_count: 0,
+            last_triggered: None,
+            last_result: None,
+            last_result_summary: None,
+            tenant_id: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn tenant_bearer(tenant: &str) -> HeaderMap {
+        let token = crate::handlers::auth::jwt_manager(3600)
+            .generate_tenant_token(
+                "operator-1",
+                "ops@example.com",
+                vec![],
+                None,
+                Some(tenant.to_string()),
+            )
+            .unwrap();
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "Authorization",
+            format!("Bearer {}", token).parse().unwrap(),
+        );
+        headers
+    }
+
+    #[tokio::test]
+    async fn test_stream_requires_authentication() {
+        let state = Arc::new(AppState::new());
+        let result = stream_execution_events(State(state), HeaderMap::new()).await;
+        assert!(matches!(
+            result,
+            Err(crate::api::rest::ApiError(
+                uaip_core::error::UaipError::AuthenticationFailed(_)
+            ))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_other_tenants_executions_are_not_streamed() {
+        let state = Arc::new(AppState::new());
+        let response = stream_execution_events(State(state.clone()), tenant_bearer("tenant-b"))
+            .await
+            .unwrap()
+            .into_response();
+
+        let own_execution = {
+            let mut automation = state.automation.lock().await;
+            let engine = &mut automation.scenario_engine;
+            let mut foreign = scenario();
+            foreign.id = "tenant-a-lights".to_string();
+            foreign.tenant_id = Some("tenant-a".to_string());
+            engine.register_scenario(foreign).unwrap();
+            let mut own = scenario();
+            own.tenant_id = Some("tenant-b".to_string());
+            engine.register_scenario(own).unwrap();
+
+            engine
+                .trigger_scenario("tenant-a-lights", HashMap::new())
+                .unwrap();
+            engine
+                .trigger_scenario("lights-off", HashMap::new())
+                .unwrap()
+        };
+
+        let mut body = response.into_body().into_data_stream();
+        let mut text = String::new();
+        while !text.contains("\n\n") {
+            let chunk = body.next().await.unwrap().unwrap();
+            text.push_str(std::str::from_utf8(&chunk).unwrap());
+        }
+
+        let event: ExecutionStateChanged = text
+            .lines()
+            .find_map(|line| line.strip_prefix("data: "))
+            .map(|data| serde_json::from_str(data).unwrap())
+            .unwrap();
+        assert_eq!(event.execution_id, own_execution);
+        assert_eq!(event.tenant_id.as_deref(), Some("tenant-b"));
+    }
+
+    #[tokio::test]
+    async fn test_scenario_lifecycle_is_streamed() {
+        let state = Arc::new(AppState::new());
+        let response = stream_execution_events(State(state.clone()), tenant_bearer("default"))
+            .await
+            .unwrap()
+            .into_response();
+
+        let execution_id = {
+            let mut automation = state.automation.lock().await;
+            let engine = &mut automation.scenario_engine;
+            engine.register_scenario(scenario()).unwrap();
+            let id = engine
+                .trigger_scenario("lights-off", HashMap::new())
+                .unwrap();
+            engine.execute_actions(&id).await.unwrap();
+            id
+        };
+
+        let mut body = response.into_body().into_data_stream();
+        let mut text = String::new();
+        while text.matches("event: execution_state").count() < 2 {
+            let chunk = body.next().await.unwrap().unwrap();
+            text.push_str(std::str::from_utf8(&chunk).unwrap());
+        }
+
+        let events: Vec<ExecutionStateChanged> = text
+            .lines()
+            .filter_map(|line| line.strip_prefix("data: "))
+            .map(|data| serde_json::from_str(data).unwrap())
+            .collect();
+        assert_eq!(events.len(), 2);
+        assert!(events.iter().all(|e| e.execution_id == execution_id));
+        assert_eq!(events[0].new, "executing");
+        assert_eq!(events[1].old.as_deref(), Some("executing"));
+        assert_eq!(events[1].new, "completed");
+        assert_eq!(nats_subject(&events[1]), "uaip.executions.scenario");
+    }
+}
@@ -0,0 +1,249 @@
+//! Firmware catalog handlers
+//!
+//! Tracks the latest firmware release per device type so device responses can flag
+//! devices that are behind.
+
+use axum::{
+    extract::{Path, State},
+    http::HeaderMap,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use uaip_core::error::UaipError;
+use uaip_core::version::Version;
+
+use crate::api::rest::{ApiResult, AppState};
+use crate::handlers::auth::require_scope;
+
+/// Firmware catalog entry request
+#[derive(Debug, Deserialize)]
+pub struct FirmwareCatalogRequest {
+    pub latest_version: String,
+    pub release_notes: Option<String>,
+}
+
+/// Firmware catalog entry
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct FirmwareCatalogEntry {
+    pub device_type: String,
+    pub latest_version: String,
+    pub release_notes: Option<String>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Firmware catalog list response
+#[derive(Debug, Serialize)]
+pub struct FirmwareCatalogResponse {
+    pub entries: Vec<FirmwareCatalogEntry>,
+}
+
+/// Parse a firmware version as semver
+///
+/// Accepts a leading `v` and fills in a missing minor or patch component.
+pub fn parse_firmware_version(version: &str) -> Option<Version> {
+    let version = version.trim();
+    let version = version.strip_prefix('v').unwrap_or(version);
+    if let Ok(parsed) = Version::parse(version) {
+        return Some(parsed);
+    }
+
+    let (core, pre) = match version.split_once('-') {
+        Some((core, pre)) => (core, format!("-{}", pre)),
+        None => (version, String::new()),
+    };
+    let parts = core.split('.').count();
+    if parts > 2 {
+        return None;
+    }
+    let padded = format!("{}{}{}", core, ".0".repeat(3 - parts), pre);
+    Version::parse(&padded).ok()
+}
+
+/// Whether a device on `current` should update to `latest`
+///
+/// Returns false when either version cannot be parsed.
+pub fn firmware_update_available(current: &str, latest: &str) -> bool {
+    match (
+        parse_firmware_version(current),
+        parse_firmware_version(latest),
+    ) {
+        (Some(current), Some(latest)) => current < latest,
+        _ => false,
+    }
+}
+
+/// List firmware catalog entries
+pub async fn list_firmware_catalog(
+    State(state): State<Arc<AppState>>,
+) -> ApiResult<Json<FirmwareCatalogResponse>> {
+    let db_pool = state.db()?;
+
+    let entries = sqlx::query_as::<_, FirmwareCatalogEntry>(
+        "SELECT device_type, latest_version, release_notes, updated_at
+         FROM firmware_catalog
+         ORDER BY device_type",
+    )
+    .fetch_all(db_pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch firmware catalog: {}", e);
+        UaipError::InternalError("Failed to query firmware catalog".to_string())
+    })?;
+
+    Ok(Json(FirmwareCatalogResponse { entries }))
+}
+
+/// Register or update the latest firmware version for a device type
+///
+/// The catalog is shared by all tenants, so changes require the `admin` scope.
+pub async fn upsert_firmware_catalog_entry(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(device_type): Path<String>,
+    Json(request): Json<FirmwareCatalogRequest>,
+) -> ApiResult<Json<FirmwareCatalogEntry>> {
+    require_scope(&headers, "admin")?;
+
+    if device_type.is_empty() {
+        return Err(UaipError::InvalidParameter("device_type cannot be empty".to_string()).into());
+    }
+
+    if parse_firmware_version(&request.latest_version).is_none() {
+        return Err(UaipError::InvalidParameter(format!(
+            "latest_version '{}' is not a valid semantic version",
+            request.latest_version
+        ))
+        .into());
+    }
+
+    let db_pool = state.db()?;
+
+    let entry = sqlx::query_as::<_, FirmwareCatalogEntry>(
+        "INSERT INTO firmware_catalog (device_type, latest_version, release_notes, updated_at)
+         VALUES ($1, $2, $3, NOW())
+         ON CONFLICT (device_type) DO UPDATE
+         SET latest_version = EXCLUDED.latest_version,
+             release_notes = EXCLUDED.release_notes,
+             updated_at = NOW()
+         RETURNING device_type, latest_version, release_notes, updated_at",
+    )
+    .bind(&device_type)
+    .bind(request.latest_version.trim())
+    .bind(&request.release_notes)
+    .fetch_one(db_pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to update firmware catalog: {}", e);
+        UaipError::InternalError("Failed to update firmware catalog".to_string())
+    })?;
+
+    tracing::info!(
+        "Firmware catalog updated: {} -> {}",
+        entry.device_type,
+        entry.latest_version
+    );
+
+    Ok(Json(entry))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_update_available_when_behind_latest() {
+        assert!(firmware_update_available("1.2.3", "1.3.0"));
+        assert!(firmware_update_available("v1.9", "1.10.0"));
+    }
+
+    #[test]
+    fn test_no_update_at_latest() {
+        assert!(!firmware_update_available("2.0.0", "2.0.0"));
+        assert!(!firmware_update_available("2.0", "v2.0.0"));
+        assert!(!firmware_update_available("2.1.0", "2.0.0"));
+    }
+
+    #[test]
+    fn test_unparseable_version() {
+        assert!(parse_firmware_version("build-42").is_none());
+        assert!(!firmware_update_available("build-42", "1.0.0"));
+        assert!(!firmware_update_available("1.0.0", "latest"));
+    }
+
+    fn bearer(scopes: &[&str]) -> HeaderMap {
+        let token = crate::handlers::auth::jwt_manager(3600)
+            .generate_token(
+                "operator-1",
+                "ops@example.com",
+                scopes.iter().map(|s| s.to_string()).collect(),
+                None,
+            )
+            .unwrap();
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "Authorization",
+            format!("Bearer {}", token).parse().unwrap(),
+        );
+        headers
+    }
+
+    fn request(latest_version: &str) -> Json<FirmwareCatalogRequest> {
+        Json(FirmwareCatalogRequest {
+            latest_version: latest_version.to_string(),
+            release_notes: None,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_upsert_rejects_invalid_version() {
+        let state = Arc::new(AppState::new());
+
+        let result = upsert_firmware_catalog_entry(
+            State(state),
+            bearer(&["admin"]),
+            Path("sensor".to_string()),
+            request("not-a-version"),
+        )
+        .await;
+        assert!(matches!(
+            result,
+            Err(crate::api::rest::ApiError(UaipError::InvalidParameter(_)))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_upsert_requires_admin() {
+        let state = Arc::new(AppState::new());
+
+        let result = upsert_firmware_catalog_entry(
+            State(state.clone()),
+            HeaderMap::new(),
+            Path("sensor".to_string()),
+            request("2.0.0"),
+        )
+        .await;
+        assert!(matches!(
+            result,
+            Err(crate::api::rest::ApiError(UaipError::AuthenticationFailed(
+                _
+            )))
+        ));
+
+        let result = upsert_firmware_catalog_entry(
+            State(state),
+            bearer(&["device:read"]),
+            Path("sensor".to_string()),
+            request("2.0.0"),
+        )
+        .await;
+        assert!(matches!(
+            result,
+            Err(crate::api::rest::ApiError(UaipError::AuthorizationFailed(
+                _
+            )))
+        ));
+    }
+}
@@ -0,0 +1,712 @@
+//! Device group handlers
+//!
+//! Groups live in the automation engine so rule conditions and scenario triggers can
+//! reference them; they are persisted to the database when one is configured. Each group
+//! belongs to a tenant and is only visible to it. Changing groups requires the `admin`
+//! scope, since group membership decides group-scoped command grants.
+
+use axum::{
+    extract::{Path, State},
+    http::HeaderMap,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::sync::Arc;
+
+use uaip_core::error::UaipError;
+use uaip_core::group::{DeviceGroup, DeviceGroups};
+
+use crate::api::rest::{ApiResult, AppState, CommandRequest};
+use crate::audit::AuditEvent;
+use crate::handlers::auth::{require_claims, require_scope};
+use crate::services::devices::send_command;
+use crate::tenant::Tenant;
+
+/// Group creation request
+#[derive(Debug, Deserialize)]
+pub struct CreateGroupRequest {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub device_ids: Vec<String>,
+    #[serde(default)]
+    pub subgroup_ids: Vec<String>,
+}
+
+/// Group update request (replaces the group definition)
+#[derive(Debug, Deserialize)]
+pub struct UpdateGroupRequest {
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub device_ids: Vec<String>,
+    #[serde(default)]
+    pub subgroup_ids: Vec<String>,
+}
+
+/// Group with its resolved device membership
+#[derive(Debug, Serialize)]
+pub struct GroupResponse {
+    #[serde(flatten)]
+    pub group: DeviceGroup,
+    /// Devices in the group and all nested groups
+    pub members: Vec<String>,
+}
+
+/// Group list response
+#[derive(Debug, Serialize)]
+pub struct GroupListResponse {
+    pub groups: Vec<GroupResponse>,
+    pub total: usize,
+}
+
+/// Outcome of a group command for one device
+#[derive(Debug, Serialize)]
+pub struct GroupCommandResult {
+    pub device_id: String,
+    pub message_id: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Group command response
+#[derive(Debug, Serialize)]
+pub struct GroupCommandResponse {
+    pub group_id: String,
+    pub dispatched: usize,
+    pub failed: usize,
+    pub results: Vec<GroupCommandResult>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct GroupRow {
+    id: String,
+    name: String,
+    description: Option<String>,
+    device_ids: Vec<String>,
+    subgroup_ids: Vec<String>,
+    tenant_id: String,
+}
+
+/// Load all device groups from the database
+pub async fn load_device_groups(pool: &PgPool) -> Result<DeviceGroups, UaipError> {
+    let rows = sqlx::query_as::<_, GroupRow>(
+        "SELECT id, name, description, device_ids, subgroup_ids, tenant_id FROM device_groups",
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| UaipError::DatabaseError(format!("Failed to load device groups: {}", e)))?;
+
+    DeviceGroups::from_groups(
+        rows.into_iter()
+            .map(|row| DeviceGroup {
+                id: row.id,
+                name: row.name,
+                description: row.description,
+                device_ids: row.device_ids,
+                subgroup_ids: row.subgroup_ids,
+                tenant_id: Some(row.tenant_id),
+            })
+            .collect(),
+    )
+}
+
+fn group_response(groups: &DeviceGroups, group: &DeviceGroup) -> GroupResponse {
+    GroupResponse {
+        group: group.clone(),
+        members: groups.members(&group.id).unwrap_or_default(),
+    }
+}
+
+fn validate_group(group: &DeviceGroup) -> Result<(), UaipError> {
+    if group.id.is_empty() {
+        return Err(UaipError::InvalidParameter(
+            "id cannot be empty".to_string(),
+        ));
+    }
+    if group.name.is_empty() {
+        return Err(UaipError::InvalidParameter(
+            "name cannot be empty".to_string(),
+        ));
+    }
+    if group.device_ids.iter().any(|id| id.is_empty()) {
+        return Err(UaipError::InvalidParameter(
+            "device_ids cannot contain empty IDs".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Validate and store a tenant's group, persisting it before updating the in-memory set
+///
+/// Group IDs are unique across tenants; another tenant's group is never replaced.
+async fn save_group(
+    state: &AppState,
+    tenant: &Tenant,
+    actor: &str,
+    group: DeviceGroup,
+    create: bool,
+) -> ApiResult<GroupResponse> {
+    validate_group(&group)?;
+    let group = group.with_tenant(tenant.id());
+
+    let mut automation = state.automation.lock().await;
+    let existing = automation.groups.get(&group.id);
+    let owned = existing.is_some_and(|g| g.tenant_id == group.tenant_id);
+    if create && existing.is_some() {
+        return Err(UaipError::InvalidState(format!("Group already exists: {}", group.id)).into());
+    }
+    if !create && !owned {
+        return Err(UaipError::NotFound(format!("Group not found: {}", group.id)).into());
+    }
+
+    let mut groups = automation.groups.clone();
+    groups.insert(group.clone())?;
+
+    if let Some(db_pool) = &state.db_pool {
+        sqlx::query(
+            "INSERT INTO device_groups (id, name, description, device_ids, subgroup_ids, tenant_id)
+             VALUES ($1, $2, $3, $4, $5, $6)
+             ON CONFLICT (id) DO UPDATE
+             SET name = EXCLUDED.name,
+                 description = EXCLUDED.description,
+                 device_ids = EXCLUDED.device_ids,
+                 subgroup_ids = EXCLUDED.subgroup_ids,
+                 updated_at = NOW()
+             WHERE device_groups.tenant_id = EXCLUDED.tenant_id",
+        )
+        .bind(&group.id)
+        .bind(&group.name)
+        .bind(&group.description)
+        .bind(&group.device_ids)
+        .bind(&group.subgroup_ids)
+        .bind(tenant.id())
+        .execute(db_pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to save device group: {}", e);
+            UaipError::InternalError("Failed to save device group".to_string())
+        })?;
+    }
+
+    let response = group_response(&groups.for_tenant(tenant.id()), &group);
+    automation.groups = groups;
+    drop(automation);
+
+    let action = if create {
+        "group.create"
+    } else {
+        "group.update"
+    };
+    state
+        .audit_log
+        .record_or_warn(
+            AuditEvent::success(actor, action)
+                .with_target(&group.id)
+                .with_details(serde_json::json!({
+                    "device_ids": group.device_ids,
+                    "subgroup_ids": group.subgroup_ids,
+                })),
+        )
+        .await;
+
+    Ok(response)
+}
+
+/// List the tenant's device groups
+pub async fn list_groups(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> ApiResult<Json<GroupListResponse>> {
+    let tenant = Tenant::from_headers(&headers)?;
+    let tenant_groups = state.automation.lock().await.groups.for_tenant(tenant.id());
+    let groups: Vec<GroupResponse> = tenant_groups
+        .list()
+        .into_iter()
+        .map(|group| group_response(&tenant_groups, group))
+        .collect();
+    let total = groups.len();
+
+    Ok(Json(GroupListResponse { groups, total }))
+}
+
+/// Get one of the tenant's device groups with its resolved members
+pub async fn get_group(
+    State(state): State<Arc<AppState>>,
+    Path(group_id): Path<String>,
+    headers: HeaderMap,
+) -> ApiResult<Json<GroupResponse>> {
+    let tenant = Tenant::from_headers(&headers)?;
+    let groups = state.automation.lock().await.groups.for_tenant(tenant.id());
+    let group = groups
+        .get(&group_id)
+        .ok_or_else(|| UaipError::NotFound(format!("Group not found: {}", group_id)))?;
+
+    Ok(Json(group_response(&groups, group)))
+}
+
+/// Create a device group in the caller's tenant
+pub async fn create_group(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<CreateGroupRequest>,
+) -> ApiResult<Json<GroupResponse>> {
+    let claims = require_scope(&headers, "admin")?;
+    let group = DeviceGroup {
+        id: request.id,
+        name: request.name,
+        description: request.description,
+        device_ids: request.device_ids,
+        subgroup_ids: request.subgroup_ids,
+        tenant_id: None,
+    };
+
+    let tenant = Tenant::from_claims(&claims);
+    Ok(Json(
+        save_group(&state, &tenant, &claims.sub, group, true).await?,
+    ))
+}
+
+/// Replace the definition of one of the tenant's device groups
+pub async fn update_group(
+    State(state): State<Arc<AppState>>,
+    Path(group_id): Path<String>,
+    headers: HeaderMap,
+    Json(request): Json<UpdateGroupRequest>,
+) -> ApiResult<Json<GroupResponse>> {
+    let claims = require_scope(&headers, "admin")?;
+    let group = DeviceGroup {
+        id: group_id,
+        name: request.name,
+        description: request.description,
+        device_ids: request.device_ids,
+        subgroup_ids: request.subgroup_ids,
+        tenant_id: None,
+    };
+
+    let tenant = Tenant::from_claims(&claims);
+    Ok(Json(
+        save_group(&state, &tenant, &claims.sub, group, false).await?,
+    ))
+}
+
+/// Delete one of the tenant's device groups that is not nested in another group
+pub async fn delete_group(
+    State(state): State<Arc<AppState>>,
+    Path(group_id): Path<String>,
+    headers: HeaderMap,
+) -> ApiResult<Json<serde_json::Value>> {
+    let claims = require_scope(&headers, "admin")?;
+    let tenant = Tenant::from_claims(&claims);
+
+    let mut automation = state.automation.lock().await;
+    if automation
+        .groups
+        .for_tenant(tenant.id())
+        .get(&group_id)
+        .is_none()
+    {
+        return Err(UaipError::NotFound(format!("Group not found: {}", group_id)).into());
+    }
+    let mut groups = automation.groups.clone();
+    groups.remove(&group_id)?;
+
+    if let Some(db_pool) = &state.db_pool {
+        sqlx::query("DELETE FROM device_groups WHERE id = $1 AND tenant_id = $2")
+            .bind(&group_id)
+            .bind(tenant.id())
+            .execute(db_pool)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to delete device group: {}", e);
+                UaipError::InternalError("Failed to delete device group".to_string())
+            })?;
+    }
+
+    automation.groups = groups;
+    drop(automation);
+
+    state
+        .audit_log
+        .record_or_warn(AuditEvent::success(&claims.sub, "group.delete").with_target(&group_id))
+        .await;
+
+    Ok(Json(serde_json::json!({
+        "id": group_id,
+        "deleted": true,
+    })))
+}
+
+/// Send a command to every device in a group, including nested groups
+///
+/// Each device is queued independently; failures are reported per device. Members
+/// outside the caller's tenant fail as not found.
+pub async fn send_group_command(
+    State(state): State<Arc<AppState>>,
+    Path(group_id): Path<String>,
+    headers: HeaderMap,
+    Json(request): Json<CommandRequest>,
+) -> ApiResult<Json<GroupCommandResponse>> {
+    let claims = require_claims(&headers)?;
+    let tenant = Tenant::from_claims(&claims);
+    if request.action.is_empty() {
+        return Err(UaipError::InvalidParameter("action cannot be empty".to_string()).into());
+    }
+
+    let members = state
+        .automation
+        .lock()
+        .await
+        .groups
+        .for_tenant(tenant.id())
+        .members(&group_id)?;
+
+    let mut results = Vec::with_capacity(members.len());
+    for device_id in members {
+        let result = match send_command(&state, &tenant, &claims, &device_id, &request).await {
+            Ok(response) => GroupCommandResult {
+                device_id,
+                message_id: Some(response.message_id),
+                error: None,
+            },
+            Err(e) => GroupCommandResult {
+                device_id,
+                message_id: None,
+                error: Some(e.to_string()),
+            },
+        };
+        results.push(result);
+    }
+
+    let dispatched = results.iter().filter(|r| r.error.is_none()).count();
+    tracing::info!(
+        "Group command {} for {}: {} dispatched, {} failed",
+        request.action,
+        group_id,
+        dispatched,
+        results.len() - dispatched
+    );
+
+    Ok(Json(GroupCommandResponse {
+        group_id,
+        dispatched,
+        failed: results.len() - dispatched,
+        results,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bearer(scopes: &[&str]) -> HeaderMap {
+        tenant_bearer(scopes, "default")
+    }
+
+    fn tenant_bearer(scopes: &[&str], tenant_id: &str) -> HeaderMap {
+        let token = crate::handlers::auth::jwt_manager(3600)
+            .generate_tenant_token(
+                "operator-1",
+                "ops@example.com",
+                scopes.iter().map(|s| s.to_string()).collect(),
+                None,
+                Some(tenant_id.to_string()),
+            )
+            .unwrap();
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "Authorization",
+            format!("Bearer {}", token).parse().unwrap(),
+        );
+        headers
+    }
+
+    fn create_request(id: &str, device_ids: &[&str], subgroup_ids: &[&str]) -> CreateGroupRequest {
+        CreateGroupRequest {
+            id: id.to_string(),
+            name: id.to_string(),
+            description: None,
+            device_ids: device_ids.iter().map(|s| s.to_string()).collect(),
+            subgroup_ids: subgroup_ids.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    async fn create(
+        state: &Arc<AppState>,
+        request: CreateGroupRequest,
+    ) -> ApiResult<GroupResponse> {
+        create_group(State(state.clone()), bearer(&["admin"]), Json(request))
+            .await
+            .map(|Json(response)| response)
+    }
+
+    #[tokio::test]
+    async fn test_group_membership() {
+        let state = Arc::new(AppState::new());
+        create(
+            &state,
+            create_request("kitchen", &["light-1", "sensor-1"], &[]),
+        )
+        .await
+        .unwrap();
+        let floor = create(
+            &state,
+            create_request("floor-1", &["light-2"], &["kitchen"]),
+        )
+        .await
+        .unwrap();
+        assert_eq!(floor.members, vec!["light-1", "light-2", "sensor-1"]);
+
+        // Duplicate IDs and unknown subgroups are rejected
+        assert!(create(&state, create_request("kitchen", &[], &[]))
+            .await
+            .is_err());
+        assert!(create(&state, create_request("hall", &[], &["garage"]))
+            .await
+            .is_err());
+
+        let Json(list) = list_groups(State(state.clone()), bearer(&[]))
+            .await
+            .unwrap();
+        assert_eq!(list.total, 2);
+
+        // Nested groups cannot be deleted out from under their parent
+        assert!(delete_group(
+            State(state.clone()),
+            Path("kitchen".to_string()),
+            bearer(&["admin"])
+        )
+        .await
+        .is_err());
+        let Json(deleted) = delete_group(
+            State(state.clone()),
+            Path("floor-1".to_string()),
+            bearer(&["admin"]),
+        )
+        .await
+        .unwrap();
+        assert_eq!(deleted["deleted"], true);
+
+        // Changes are audited under the caller's subject
+        let audit = state
+            .audit_log
+            .query(&crate::audit::AuditQuery::default())
+            .await
+            .unwrap();
+        assert!(audit.len() >= 3);
+        assert!(audit.iter().all(|entry| entry.actor == "operator-1"));
+        assert!(
+            get_group(State(state), Path("floor-1".to_string()), bearer(&[]))
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_update_rejects_membership_cycle() {
+        let state = Arc::new(AppState::new());
+        create(&state, create_request("kitchen", &["sensor-1"], &[]))
+            .await
+            .unwrap();
+        create(&state, create_request("floor-1", &[], &["kitchen"]))
+            .await
+            .unwrap();
+
+        let request = UpdateGroupRequest {
+            name: "Kitchen".to_string(),
+            description: None,
+            device_ids: vec!["sensor-1".to_string()],
+            subgroup_ids: vec!["floor-1".to_string()],
+        };
+        let result = update_group(
+            State(state.clone()),
+            Path("kitchen".to_string()),
+            bearer(&["admin"]),
+            Json(request),
+        )
+        .await;
+        assert!(matches!(
+            result,
+            Err(crate::api::rest::ApiError(UaipError::InvalidConfiguration(
+                _
+            )))
+        ));
+
+        let Json(kitchen) = get_group(State(state), Path("kitchen".to_string()), bearer(&[]))
+            .await
+            .unwrap();
+        assert!(kitchen.group.subgroup_ids.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_group_command_targets_nested_members() {
+        let state = Arc::new(AppState::new());
+        create(&state, create_request("kitchen", &["light-1"], &[]))
+            .await
+            .unwrap();
+        create(
+            &state,
+            create_request("floor-1", &["light-2"], &["kitchen"]),
+        )
+        .await
+        .unwrap();
+
+        let request = CommandRequest {
+            action: "turn_off".to_string(),
+            parameters: None,
+            priority: None,
+            preempt: false,
+        };
+        let Json(response) = send_group_command(
+            State(state.clone()),
+            Path("floor-1".to_string()),
+            bearer(&["admin"]),
+            Json(request),
+        )
+        .await
+        .unwrap();
+
+        // Every member is targeted; without a database each queue attempt fails
+        let targeted: Vec<&str> = response
+            .results
+            .iter()
+            .map(|r| r.device_id.as_str())
+            .collect();
+        assert_eq!(targeted, vec!["light-1", "light-2"]);
+        assert_eq!(response.dispatched, 0);
+        assert_eq!(response.failed, 2);
+
+        let request = CommandRequest {
+            action: "turn_off".to_string(),
+            parameters: None,
+            priority: None,
+            preempt: false,
+        };
+        assert!(send_group_command(
+            State(state.clone()),
+            Path("garage".to_string()),
+            bearer(&["admin"]),
+            Json(request)
+        )
+        .await
+        .is_err());
+
+        // Anonymous group commands are rejected before any member is targeted
+        let request = CommandRequest {
+            action: "turn_off".to_string(),
+            parameters: None,
+            priority: None,
+            preempt: false,
+        };
+        let result = send_group_command(
+            State(state),
+            Path("floor-1".to_string()),
+            HeaderMap::new(),
+            Json(request),
+        )
+        .await;
+        assert!(matches!(
+            result,
+            Err(crate::api::rest::ApiError(UaipError::AuthenticationFailed(
+                _
+            )))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_groups_are_isolated_between_tenants() {
+        let state = Arc::new(AppState::new());
+        create(&state, create_request("kitchen", &["light-1"], &[]))
+            .await
+            .unwrap();
+        let acme = || tenant_bearer(&["admin"], "acme");
+
+        let Json(list) = list_groups(State(state.clone()), acme()).await.unwrap();
+        assert_eq!(list.total, 0);
+        let result = get_group(State(state.clone()), Path("kitchen".to_string()), acme()).await;
+        assert!(matches!(
+            result,
+            Err(crate::api::rest::ApiError(UaipError::NotFound(_)))
+        ));
+
+        let request = UpdateGroupRequest {
+            name: "Taken".to_string(),
+            description: None,
+            device_ids: vec!["light-9".to_string()],
+            subgroup_ids: vec![],
+        };
+        let result = update_group(
+            State(state.clone()),
+            Path("kitchen".to_string()),
+            acme(),
+            Json(request),
+        )
+        .await;
+        assert!(matches!(
+            result,
+            Err(crate::api::rest::ApiError(UaipError::NotFound(_)))
+        ));
+        let result = delete_group(State(state.clone()), Path("kitchen".to_string()), acme()).await;
+        assert!(matches!(
+            result,
+            Err(crate::api::rest::ApiError(UaipError::NotFound(_)))
+        ));
+
+        let request = CommandRequest {
+            action: "turn_off".to_string(),
+            parameters: None,
+            priority: None,
+            preempt: false,
+        };
+        let result = send_group_command(
+            State(state.clone()),
+            Path("kitchen".to_string()),
+            acme(),
+            Json(request),
+        )
+        .await;
+        assert!(matches!(
+            result,
+            Err(crate::api::rest::ApiError(UaipError::NotFound(_)))
+        ));
+
+        // The owning tenant still sees its group unchanged
+        let Json(kitchen) = get_group(State(state), Path("kitchen".to_string()), bearer(&[]))
+            .await
+            .unwrap();
+        assert_eq!(kitchen.group.device_ids, vec!["light-1"]);
+    }
+
+    #[tokio::test]
+    async fn test_group_changes_require_admin() {
+        let state = Arc::new(AppState::new());
+
+        let result = create_group(
+            State(state.clone()),
+            HeaderMap::new(),
+            Json(create_request("kitchen", &[], &[])),
+        )
+        .await;
+        assert!(matches!(
+            result,
+            Err(crate::api::rest::ApiError(UaipError::AuthenticationFailed(
+                _
+            )))
+        ));
+
+        let result = create_group(
+            State(state),
+            bearer(&["device:read"]),
+            Json(create_request("kitchen", &[], &[])),
+        )
+        .await;
+        assert!(matches!(
+            result,
+            Err(crate::api::rest::ApiError(UaipError::AuthorizationFailed(
+                _
+            )))
+        ));
+    }
+}
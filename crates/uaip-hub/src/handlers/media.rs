@@ -4,7 +4,7 @@
 
 use axum::{
     extract::{Path, Query, State},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
     Json,
 };
 use serde::{Deserialize, Serialize};
@@ -14,70 +14,32 @@ use tracing::{error, info};
 use uuid::Uuid;
 
 use uaip_core::error::UaipError;
-use uaip_orchestrator::media::{
-    AccessLevel, MediaDimensions, MediaType, StreamProtocol, StreamQuality,
-};
+use uaip_orchestrator::media::{MediaDimensions, StreamProtocol, StreamQuality};
 use uaip_orchestrator::streaming::StreamingStats;
 
 use crate::api::rest::{ApiError, ApiResult, AppState};
-
-/// Upload media file request
-#[derive(Debug, Deserialize)]
-pub struct UploadMediaRequest {
-    pub filename: String,
-    pub media_type: MediaType,
-    pub format: String,
-    pub mime_type: String,
-    pub size_bytes: u64,
-    pub duration_secs: Option<f64>,
-    pub width: Option<u32>,
-    pub height: Option<u32>,
-    pub codec_video: Option<String>,
-    pub codec_audio: Option<String>,
-    pub bitrate_kbps: Option<u32>,
-    pub framerate_fps: Option<f32>,
-    pub storage_path: String,
-    pub url: Option<String>,
-    pub thumbnail_url: Option<String>,
-    pub tags: Vec<String>,
-    pub source_device_id: Option<Uuid>,
-    pub access_level: Option<AccessLevel>,
-}
-
-/// Media file response
-#[derive(Debug, Serialize)]
-pub struct MediaFileResponse {
-    pub id: Uuid,
-    pub filename: String,
-    pub media_type: String,
-    pub format: String,
-    pub mime_type: String,
-    pub size_bytes: u64,
-    pub duration_secs: Option<f64>,
-    pub dimensions: Option<MediaDimensions>,
-    pub storage_path: String,
-    pub url: Option<String>,
-    pub thumbnail_url: Option<String>,
-    pub tags: Vec<String>,
-    pub status: String,
-    pub uploaded_at: String,
-}
+use crate::pagination::{default_page, default_per_page, page_offset, validate_page, Paginated};
+use crate::services;
+use crate::services::media::{DeviceStreamRequest, MediaFileResponse, UploadMediaRequest};
+use crate::tenant::Tenant;
 
 /// Media list query parameters
 #[derive(Debug, Deserialize)]
 pub struct MediaListQuery {
     pub media_type: Option<String>,
     pub status: Option<String>,
-    pub limit: Option<i64>,
-    pub offset: Option<i64>,
+
+    /// Page number (1-indexed)
+    #[serde(default = "default_page")]
+    pub page: i64,
+
+    /// Items per page
+    #[serde(default = "default_per_page")]
+    pub per_page: i64,
 }
 
 /// Media list response
-#[derive(Debug, Serialize)]
-pub struct MediaListResponse {
-    pub media_files: Vec<MediaFileResponse>,
-    pub total: usize,
-}
+pub type MediaListResponse = Paginated<MediaFileResponse>;
 
 /// Create streaming session request
 #[derive(Debug, Deserialize)]
@@ -101,309 +63,268 @@ pub struct StreamSessionResponse {
     pub clients_count: usize,
     pub started_at: String,
     pub stream_url: Option<String>,
+    /// Camera serving the stream, for device streams
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device_id: Option<String>,
     pub stats: StreamingStats,
 }
 
 /// Upload a media file
 pub async fn upload_media(
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
     Json(request): Json<UploadMediaRequest>,
 ) -> ApiResult<Json<MediaFileResponse>> {
-    info!("Uploading media file: {}", request.filename);
-
-    let media_id = Uuid::new_v4();
-    let access_level = request.access_level.unwrap_or(AccessLevel::Private);
-
-    // Store in database if available
-    if let Some(pool) = &state.db_pool {
-        match sqlx::query(
-            r#"
-            INSERT INTO media_files (
-                id, filename, media_type, format, mime_type, size_bytes,
-                duration_secs, width, height, codec_video, codec_audio,
-                bitrate_kbps, framerate_fps, storage_path, url, thumbnail_url,
-                tags, status, source_device_id, access_level
-            )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20)
-            "#,
-        )
-        .bind(media_id)
-        .bind(&request.filename)
-        .bind(format!("{:?}", request.media_type).to_lowercase())
-        .bind(&request.format)
-        .bind(&request.mime_type)
-        .bind(request.size_bytes as i64)
-        .bind(request.duration_secs)
-        .bind(request.width.map(|w| w as i32))
-        .bind(request.height.map(|h| h as i32))
-        .bind(&request.codec_video)
-        .bind(&request.codec_audio)
-        .bind(request.bitrate_kbps.map(|b| b as i32))
-        .bind(request.framerate_fps)
-        .bind(&request.storage_path)
-        .bind(&request.url)
-        .bind(&request.thumbnail_url)
-        .bind(&request.tags)
-        .bind("pending")
-        .bind(request.source_device_id)
-        .bind(format!("{:?}", access_level).to_lowercase())
-        .execute(pool)
-        .await
-        {
-            Ok(_) => {
-                info!("Stored media file {} in database", media_id);
-            }
-            Err(e) => {
-                error!("Failed to store media file in database: {}", e);
-                return Err(ApiError(UaipError::DatabaseError(format!(
-                    "Failed to store media: {}",
-                    e
-                ))));
-            }
-        }
-    }
-
-    let dimensions = if request.width.is_some() && request.height.is_some() {
-        Some(MediaDimensions {
-            width: request.width.unwrap(),
-            height: request.height.unwrap(),
-        })
-    } else {
-        None
-    };
-
-    Ok(Json(MediaFileResponse {
-        id: media_id,
-        filename: request.filename,
-        media_type: format!("{:?}", request.media_type),
-        format: request.format,
-        mime_type: request.mime_type,
-        size_bytes: request.size_bytes,
-        duration_secs: request.duration_secs,
-        dimensions,
-        storage_path: request.storage_path,
-        url: request.url,
-        thumbnail_url: request.thumbnail_url,
-        tags: request.tags,
-        status: "pending".to_string(),
-        uploaded_at: chrono::Utc::now().to_rfc3339(),
-    }))
+    let tenant = Tenant::from_headers(&headers)?;
+    let response = services::media::upload_media(&state, &tenant, request).await?;
+    Ok(Json(response))
 }
 
-/// List media files
+/// List the caller's media files
 pub async fn list_media(
     State(state): State<Arc<AppState>>,
     Query(query): Query<MediaListQuery>,
+    headers: HeaderMap,
 ) -> ApiResult<Json<MediaListResponse>> {
-    info!("Listing media files");
+    let tenant = Tenant::from_headers(&headers)?;
+    info!("Listing media files for tenant {}", tenant.id());
 
-    let mut media_files = Vec::new();
+    validate_page(query.page, query.per_page)?;
+    let pool = state.db()?;
+    let (where_clause, bind_values) = media_filter(&tenant, &query);
 
-    if let Some(pool) = &state.db_pool {
-        let limit = query.limit.unwrap_or(50).min(100);
-        let offset = query.offset.unwrap_or(0);
-
-        let mut sql = String::from(
-            "SELECT id, filename, media_type, format, mime_type, size_bytes,
-             duration_secs, width, height, storage_path, url, thumbnail_url,
-             tags, status, uploaded_at
-             FROM media_files WHERE 1=1",
-        );
+    let count_sql = format!("SELECT COUNT(*) FROM media_files {}", where_clause);
+    let mut count_query = sqlx::query_scalar::<_, i64>(&count_sql);
+    for value in &bind_values {
+        count_query = count_query.bind(value);
+    }
+    let total = count_query.fetch_one(pool).await.map_err(|e| {
+        error!("Failed to count media files: {}", e);
+        UaipError::DatabaseError(format!("Failed to count media: {}", e))
+    })?;
+
+    let sql = format!(
+        "SELECT id, filename, media_type, format, mime_type, size_bytes,
+         duration_secs, width, height, storage_path, url, thumbnail_url,
+         tags, status, uploaded_at
+         FROM media_files {}
+         ORDER BY uploaded_at DESC
+         LIMIT ${} OFFSET ${}",
+        where_clause,
+        bind_values.len() + 1,
+        bind_values.len() + 2
+    );
+    let mut list_query = sqlx::query(&sql);
+    for value in &bind_values {
+        list_query = list_query.bind(value);
+    }
+    let records = list_query
+        .bind(query.per_page)
+        .bind(page_offset(query.page, query.per_page))
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            error!("Failed to fetch media files from database: {}", e);
+            UaipError::DatabaseError(format!("Failed to list media: {}", e))
+        })?;
+
+    let mut media_files = Vec::with_capacity(records.len());
+    for record in records {
+        let id: Uuid = record.try_get("id").unwrap_or_default();
+        let filename: String = record.try_get("filename").unwrap_or_default();
+        let media_type: String = record.try_get("media_type").unwrap_or_default();
+        let format: String = record.try_get("format").unwrap_or_default();
+        let mime_type: String = record.try_get("mime_type").unwrap_or_default();
+        let size_bytes: i64 = record.try_get("size_bytes").unwrap_or_default();
+        let duration_secs: Option<f64> = record.try_get("duration_secs").ok();
+        let width: Option<i32> = record.try_get("width").ok();
+        let height: Option<i32> = record.try_get("height").ok();
+        let storage_path: String = record.try_get("storage_path").unwrap_or_default();
+        let url: Option<String> = record.try_get("url").ok();
+        let thumbnail_url: Option<String> = record.try_get("thumbnail_url").ok();
+        let tags: Vec<String> = record.try_get("tags").unwrap_or_default();
+        let status: String = record.try_get("status").unwrap_or_default();
+        let uploaded_at: chrono::DateTime<chrono::Utc> =
+            record.try_get("uploaded_at").unwrap_or_default();
+
+        let dimensions = if let (Some(w), Some(h)) = (width, height) {
+            Some(MediaDimensions {
+                width: w as u32,
+                height: h as u32,
+            })
+        } else {
+            None
+        };
+
+        media_files.push(MediaFileResponse {
+            id,
+            filename,
+            media_type,
+            format,
+            mime_type,
+            size_bytes: size_bytes as u64,
+            duration_secs,
+            dimensions,
+            storage_path,
+            url,
+            thumbnail_url,
+            tags,
+            status,
+            uploaded_at: uploaded_at.to_rfc3339(),
+        });
+    }
 
-        if let Some(ref media_type) = query.media_type {
-            sql.push_str(&format!(" AND media_type = '{}'", media_type));
-        }
+    Ok(Json(MediaListResponse::new(
+        media_files,
+        total as usize,
+        query.page,
+        query.per_page,
+    )))
+}
 
-        if let Some(ref status) = query.status {
-            sql.push_str(&format!(" AND status = '{}'", status));
-        }
+/// Build the WHERE clause and its bind values for a media listing
+///
+/// The tenant condition is always first, so filters cannot widen the listing beyond
+/// the caller's tenant.
+fn media_filter(tenant: &Tenant, query: &MediaListQuery) -> (String, Vec<String>) {
+    let mut conditions = vec!["tenant_id = $1".to_string()];
+    let mut bind_values = vec![tenant.id().to_string()];
+
+    if let Some(media_type) = &query.media_type {
+        conditions.push(format!("media_type = ${}", conditions.len() + 1));
+        bind_values.push(media_type.clone());
+    }
 
-        sql.push_str(" ORDER BY uploaded_at DESC");
-        sql.push_str(&format!(" LIMIT {} OFFSET {}", limit, offset));
-
-        match sqlx::query(&sql).fetch_all(pool).await {
-            Ok(records) => {
-                for record in records {
-                    let id: Uuid = record.try_get("id").unwrap_or_default();
-                    let filename: String = record.try_get("filename").unwrap_or_default();
-                    let media_type: String = record.try_get("media_type").unwrap_or_default();
-                    let format: String = record.try_get("format").unwrap_or_default();
-                    let mime_type: String = record.try_get("mime_type").unwrap_or_default();
-                    let size_bytes: i64 = record.try_get("size_bytes").unwrap_or_default();
-                    let duration_secs: Option<f64> = record.try_get("duration_secs").ok();
-                    let width: Option<i32> = record.try_get("width").ok();
-                    let height: Option<i32> = record.try_get("height").ok();
-                    let storage_path: String = record.try_get("storage_path").unwrap_or_default();
-                    let url: Option<String> = record.try_get("url").ok();
-                    let thumbnail_url: Option<String> = record.try_get("thumbnail_url").ok();
-                    let tags: Vec<String> = record.try_get("tags").unwrap_or_default();
-                    let status: String = record.try_get("status").unwrap_or_default();
-                    let uploaded_at: chrono::NaiveDateTime =
-                        record.try_get("uploaded_at").unwrap_or_default();
-
-                    let dimensions = if let (Some(w), Some(h)) = (width, height) {
-                        Some(MediaDimensions {
-                            width: w as u32,
-                            height: h as u32,
-                        })
-                    } else {
-                        None
-                    };
-
-                    media_files.push(MediaFileResponse {
-                        id,
-                        filename,
-                        media_type,
-                        format,
-                        mime_type,
-                        size_bytes: size_bytes as u64,
-                        duration_secs,
-                        dimensions,
-                        storage_path,
-                        url,
-                        thumbnail_url,
-                        tags,
-                        status,
-                        uploaded_at: uploaded_at.and_utc().to_rfc3339(),
-                    });
-                }
-            }
-            Err(e) => {
-                error!("Failed to fetch media files from database: {}", e);
-            }
-        }
+    if let Some(status) = &query.status {
+        conditions.push(format!("status = ${}", conditions.len() + 1));
+        bind_values.push(status.clone());
     }
 
-    let total = media_files.len();
-    Ok(Json(MediaListResponse { media_files, total }))
+    (format!("WHERE {}", conditions.join(" AND ")), bind_values)
 }
 
-/// Get media file by ID
+/// Get one of the caller's media files by ID
 pub async fn get_media(
     State(state): State<Arc<AppState>>,
     Path(media_id): Path<Uuid>,
+    headers: HeaderMap,
 ) -> ApiResult<Json<MediaFileResponse>> {
+    let tenant = Tenant::from_headers(&headers)?;
     info!("Getting media file: {}", media_id);
 
-    if let Some(pool) = &state.db_pool {
-        match sqlx::query(
-            r#"
-            SELECT id, filename, media_type, format, mime_type, size_bytes,
-                   duration_secs, width, height, storage_path, url, thumbnail_url,
-                   tags, status, uploaded_at
-            FROM media_files
-            WHERE id = $1
-            "#,
-        )
-        .bind(media_id)
-        .fetch_one(pool)
-        .await
-        {
-            Ok(record) => {
-                let id: Uuid = record.try_get("id").unwrap_or_default();
-                let filename: String = record.try_get("filename").unwrap_or_default();
-                let media_type: String = record.try_get("media_type").unwrap_or_default();
-                let format: String = record.try_get("format").unwrap_or_default();
-                let mime_type: String = record.try_get("mime_type").unwrap_or_default();
-                let size_bytes: i64 = record.try_get("size_bytes").unwrap_or_default();
-                let duration_secs: Option<f64> = record.try_get("duration_secs").ok();
-                let width: Option<i32> = record.try_get("width").ok();
-                let height: Option<i32> = record.try_get("height").ok();
-                let storage_path: String = record.try_get("storage_path").unwrap_or_default();
-                let url: Option<String> = record.try_get("url").ok();
-                let thumbnail_url: Option<String> = record.try_get("thumbnail_url").ok();
-                let tags: Vec<String> = record.try_get("tags").unwrap_or_default();
-                let status: String = record.try_get("status").unwrap_or_default();
-                let uploaded_at: chrono::NaiveDateTime =
-                    record.try_get("uploaded_at").unwrap_or_default();
-
-                let dimensions = if let (Some(w), Some(h)) = (width, height) {
-                    Some(MediaDimensions {
-                        width: w as u32,
-                        height: h as u32,
-                    })
-                } else {
-                    None
-                };
-
-                return Ok(Json(MediaFileResponse {
-                    id,
-                    filename,
-                    media_type,
-                    format,
-                    mime_type,
-                    size_bytes: size_bytes as u64,
-                    duration_secs,
-                    dimensions,
-                    storage_path,
-                    url,
-                    thumbnail_url,
-                    tags,
-                    status,
-                    uploaded_at: uploaded_at.and_utc().to_rfc3339(),
-                }));
-            }
-            Err(e) => {
-                error!("Failed to fetch media file from database: {}", e);
-                return Err(ApiError(UaipError::NotFound(format!(
-                    "Media file {} not found",
-                    media_id
-                ))));
-            }
+    let pool = state.db()?;
+    match sqlx::query(
+        r#"
+        SELECT id, filename, media_type, format, mime_type, size_bytes,
+               duration_secs, width, height, storage_path, url, thumbnail_url,
+               tags, status, uploaded_at
+        FROM media_files
+        WHERE id = $1 AND tenant_id = $2
+        "#,
+    )
+    .bind(media_id)
+    .bind(tenant.id())
+    .fetch_one(pool)
+    .await
+    {
+        Ok(record) => {
+            let id: Uuid = record.try_get("id").unwrap_or_default();
+            let filename: String = record.try_get("filename").unwrap_or_default();
+            let media_type: String = record.try_get("media_type").unwrap_or_default();
+            let format: String = record.try_get("format").unwrap_or_default();
+            let mime_type: String = record.try_get("mime_type").unwrap_or_default();
+            let size_bytes: i64 = record.try_get("size_bytes").unwrap_or_default();
+            let duration_secs: Option<f64> = record.try_get("duration_secs").ok();
+            let width: Option<i32> = record.try_get("width").ok();
+            let height: Option<i32> = record.try_get("height").ok();
+            let storage_path: String = record.try_get("storage_path").unwrap_or_default();
+            let url: Option<String> = record.try_get("url").ok();
+            let thumbnail_url: Option<String> = record.try_get("thumbnail_url").ok();
+            let tags: Vec<String> = record.try_get("tags").unwrap_or_default();
+            let status: String = record.try_get("status").unwrap_or_default();
+            let uploaded_at: chrono::DateTime<chrono::Utc> =
+                record.try_get("uploaded_at").unwrap_or_default();
+
+            let dimensions = if let (Some(w), Some(h)) = (width, height) {
+                Some(MediaDimensions {
+                    width: w as u32,
+                    height: h as u32,
+                })
+            } else {
+                None
+            };
+
+            Ok(Json(MediaFileResponse {
+                id,
+                filename,
+                media_type,
+                format,
+                mime_type,
+                size_bytes: size_bytes as u64,
+                duration_secs,
+                dimensions,
+                storage_path,
+                url,
+                thumbnail_url,
+                tags,
+                status,
+                uploaded_at: uploaded_at.to_rfc3339(),
+            }))
+        }
+        Err(e) => {
+            error!("Failed to fetch media file from database: {}", e);
+            Err(ApiError(UaipError::NotFound(format!(
+                "Media file {} not found",
+                media_id
+            ))))
         }
     }
-
-    Err(ApiError(UaipError::NotFound(format!(
-        "Media file {} not found",
-        media_id
-    ))))
 }
 
-/// Delete media file
+/// Delete one of the caller's media files
 pub async fn delete_media(
     State(state): State<Arc<AppState>>,
     Path(media_id): Path<Uuid>,
+    headers: HeaderMap,
 ) -> ApiResult<StatusCode> {
+    let tenant = Tenant::from_headers(&headers)?;
     info!("Deleting media file: {}", media_id);
 
-    if let Some(pool) = &state.db_pool {
-        match sqlx::query("DELETE FROM media_files WHERE id = $1")
-            .bind(media_id)
-            .execute(pool)
-            .await
-        {
-            Ok(result) => {
-                if result.rows_affected() > 0 {
-                    info!("Deleted media file {}", media_id);
-                    return Ok(StatusCode::NO_CONTENT);
-                } else {
-                    return Err(ApiError(UaipError::NotFound(format!(
-                        "Media file {} not found",
-                        media_id
-                    ))));
-                }
-            }
-            Err(e) => {
-                error!("Failed to delete media file: {}", e);
-                return Err(ApiError(UaipError::DatabaseError(format!(
-                    "Failed to delete media: {}",
-                    e
-                ))));
+    let pool = state.db()?;
+    match sqlx::query("DELETE FROM media_files WHERE id = $1 AND tenant_id = $2")
+        .bind(media_id)
+        .bind(tenant.id())
+        .execute(pool)
+        .await
+    {
+        Ok(result) => {
+            if result.rows_affected() > 0 {
+                info!("Deleted media file {}", media_id);
+                Ok(StatusCode::NO_CONTENT)
+            } else {
+                Err(ApiError(UaipError::NotFound(format!(
+                    "Media file {} not found",
+                    media_id
+                ))))
             }
         }
+        Err(e) => {
+            error!("Failed to delete media file: {}", e);
+            Err(ApiError(UaipError::DatabaseError(format!(
+                "Failed to delete media: {}",
+                e
+            ))))
+        }
     }
-
-    Err(ApiError(UaipError::NotFound(format!(
-        "Media file {} not found",
-        media_id
-    ))))
 }
 
-/// Create streaming session
+/// Create a streaming session for one of the caller's media files
 pub async fn create_stream_session(
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
     Json(request): Json<CreateStreamRequest>,
 ) -> ApiResult<Json<StreamSessionResponse>> {
+    let tenant = Tenant::from_headers(&headers)?;
     info!("Creating streaming session for media: {}", request.media_id);
 
     let session_id = Uuid::new_v4();
@@ -420,7 +341,9 @@ pub async fn create_stream_session(
                 id, media_id, protocol, quality, adaptive,
                 segment_duration_secs, is_live
             )
-            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            SELECT $1, id, $3, $4, $5, $6, $7
+            FROM media_files
+            WHERE id = $2 AND tenant_id = $8
             "#,
         )
         .bind(session_id)
@@ -430,9 +353,16 @@ pub async fn create_stream_session(
         .bind(adaptive)
         .bind(segment_duration)
         .bind(is_live)
+        .bind(tenant.id())
         .execute(pool)
         .await
         {
+            Ok(result) if result.rows_affected() == 0 => {
+                return Err(ApiError(UaipError::NotFound(format!(
+                    "Media file {} not found",
+                    request.media_id
+                ))));
+            }
             Ok(_) => {
                 info!("Created stream session {} in database", session_id);
             }
@@ -455,64 +385,89 @@ pub async fn create_stream_session(
         clients_count: 0,
         started_at: chrono::Utc::now().to_rfc3339(),
         stream_url: None,
+        device_id: None,
+        stats: StreamingStats::default(),
+    }))
+}
+
+/// Start a stream from one of the caller's camera devices
+pub async fn start_device_stream(
+    State(state): State<Arc<AppState>>,
+    Path(device_id): Path<String>,
+    headers: HeaderMap,
+    Json(request): Json<DeviceStreamRequest>,
+) -> ApiResult<Json<StreamSessionResponse>> {
+    let tenant = Tenant::from_headers(&headers)?;
+    let config =
+        services::media::start_device_stream(&state, &tenant, &device_id, &request).await?;
+
+    Ok(Json(StreamSessionResponse {
+        id: config.id,
+        media_id: config.media_id,
+        protocol: format!("{:?}", config.protocol),
+        quality: format!("{:?}", config.quality),
+        state: "Initializing".to_string(),
+        clients_count: 0,
+        started_at: chrono::Utc::now().to_rfc3339(),
+        stream_url: Some(config.stream_url),
+        device_id: config.source_device,
         stats: StreamingStats::default(),
     }))
 }
 
-/// Get streaming session
+/// Get a streaming session for one of the caller's media files
 pub async fn get_stream_session(
     State(state): State<Arc<AppState>>,
     Path(session_id): Path<Uuid>,
+    headers: HeaderMap,
 ) -> ApiResult<Json<StreamSessionResponse>> {
+    let tenant = Tenant::from_headers(&headers)?;
     info!("Getting streaming session: {}", session_id);
 
-    if let Some(pool) = &state.db_pool {
-        match sqlx::query(
-            r#"
-            SELECT id, media_id, protocol, quality, stream_url, created_at
-            FROM stream_configs
-            WHERE id = $1 AND active = TRUE
-            "#,
-        )
-        .bind(session_id)
-        .fetch_one(pool)
-        .await
-        {
-            Ok(record) => {
-                let id: Uuid = record.try_get("id").unwrap_or_default();
-                let media_id: Uuid = record.try_get("media_id").unwrap_or_default();
-                let protocol: String = record.try_get("protocol").unwrap_or_default();
-                let quality: String = record.try_get("quality").unwrap_or_default();
-                let stream_url: Option<String> = record.try_get("stream_url").ok();
-                let created_at: chrono::NaiveDateTime =
-                    record.try_get("created_at").unwrap_or_default();
-
-                return Ok(Json(StreamSessionResponse {
-                    id,
-                    media_id,
-                    protocol,
-                    quality,
-                    state: "Streaming".to_string(),
-                    clients_count: 0,
-                    started_at: created_at.and_utc().to_rfc3339(),
-                    stream_url,
-                    stats: StreamingStats::default(),
-                }));
-            }
-            Err(e) => {
-                error!("Failed to fetch stream session from database: {}", e);
-                return Err(ApiError(UaipError::NotFound(format!(
-                    "Stream session {} not found",
-                    session_id
-                ))));
-            }
+    let pool = state.db()?;
+    match sqlx::query(
+        r#"
+        SELECT s.id, s.media_id, s.protocol, s.quality, s.stream_url, s.created_at
+        FROM stream_configs s
+        JOIN media_files m ON m.id = s.media_id
+        WHERE s.id = $1 AND s.active = TRUE AND m.tenant_id = $2
+        "#,
+    )
+    .bind(session_id)
+    .bind(tenant.id())
+    .fetch_one(pool)
+    .await
+    {
+        Ok(record) => {
+            let id: Uuid = record.try_get("id").unwrap_or_default();
+            let media_id: Uuid = record.try_get("media_id").unwrap_or_default();
+            let protocol: String = record.try_get("protocol").unwrap_or_default();
+            let quality: String = record.try_get("quality").unwrap_or_default();
+            let stream_url: Option<String> = record.try_get("stream_url").ok();
+            let created_at: chrono::DateTime<chrono::Utc> =
+                record.try_get("created_at").unwrap_or_default();
+
+            Ok(Json(StreamSessionResponse {
+                id,
+                media_id,
+                protocol,
+                quality,
+                state: "Streaming".to_string(),
+                clients_count: 0,
+                started_at: created_at.to_rfc3339(),
+                stream_url,
+                device_id: None,
+                stats: StreamingStats::default(),
+            }))
+        }
+        Err(e) => {
+            error!("Failed to fetch stream session from database: {}", e);
+            Err(ApiError(UaipError::NotFound(format!(
+                "Stream session {} not found",
+                session_id
+            ))))
         }
     }
-
-    Err(ApiError(UaipError::NotFound(format!(
-        "Stream session {} not found",
-        session_id
-    ))))
 }
 
 #[cfg(test)]
@@ -534,4 +489,35 @@ mod tests {
         let request: Result<UploadMediaRequest, _> = serde_json::from_str(json);
         assert!(request.is_ok());
     }
+
+    #[test]
+    fn test_media_filter_binds_values() {
+        let query: MediaListQuery =
+            serde_json::from_str(r#"{"status": "ready' OR '1'='1"}"#).unwrap();
+        assert_eq!((query.page, query.per_page), (1, 50));
+
+        let (where_clause, bind_values) = media_filter(&Tenant::default(), &query);
+        assert_eq!(where_clause, "WHERE tenant_id = $1 AND status = $2");
+        assert_eq!(bind_values[1], "ready' OR '1'='1");
+    }
+
+    #[tokio::test]
+    async fn test_get_media_without_database_is_unavailable() {
+        use axum::response::IntoResponse;
+
+        let state = Arc::new(AppState::new());
+        let result = get_media(State(state.clone()), Path(Uuid::new_v4()), HeaderMap::new()).await;
+        let err = result.err().unwrap();
+        assert!(matches!(err.0, UaipError::NotConfigured(_)));
+        assert_eq!(
+            err.into_response().status(),
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+
+        let result = delete_media(State(state), Path(Uuid::new_v4()), HeaderMap::new()).await;
+        assert_eq!(
+            result.err().unwrap().into_response().status(),
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+    }
 }
@@ -1,9 +1,15 @@
 //! Metrics handler for Prometheus endpoint
 
-use axum::{http::StatusCode, response::IntoResponse};
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
+use std::sync::Arc;
+
+use crate::api::rest::AppState;
+use crate::metrics::Metrics;
 
 /// GET /metrics - Prometheus metrics endpoint
-pub async fn metrics_handler() -> impl IntoResponse {
+pub async fn metrics_handler(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    Metrics::update_qos_stats(&state.qos_handler.get_stats().await);
+
     match crate::metrics::Metrics::gather_metrics() {
         Ok(metrics) => (StatusCode::OK, metrics),
         Err(e) => (
@@ -17,9 +23,83 @@ pub async fn metrics_handler() -> impl IntoResponse {
 mod tests {
     use super::*;
 
+    use uaip_core::message::{EntityType, UaipMessage};
+    use uaip_router::qos::QosLevel;
+
     #[tokio::test]
     async fn test_metrics_handler() {
-        let response = metrics_handler().await.into_response();
+        let response = metrics_handler(State(Arc::new(AppState::new())))
+            .await
+            .into_response();
         assert_eq!(response.status(), StatusCode::OK);
     }
+
+    #[tokio::test]
+    async fn test_qos_stats_exported() {
+        let state = Arc::new(AppState::new());
+        let qos = &state.qos_handler;
+        let mut message_ids = Vec::new();
+        for level in [
+            QosLevel::AtMostOnce,
+            QosLevel::AtLeastOnce,
+            QosLevel::AtLeastOnce,
+            QosLevel::ExactlyOnce,
+        ] {
+            let message = UaipMessage::new(
+                "device-1".to_string(),
+                EntityType::Device,
+                "agent-1".to_string(),
+                EntityType::AiAgent,
+            );
+            message_ids.push(message.header.message_id.clone());
+            qos.handle_message(message, level).await.unwrap();
+        }
+        qos.acknowledge_qos1(&message_ids[1]).await.unwrap();
+        qos.retry_message(&message_ids[2]).await.unwrap();
+        qos.acknowledge_qos2_pubrec(&message_ids[3]).await.unwrap();
+        qos.acknowledge_qos2_pubcomp(&message_ids[3]).await.unwrap();
+
+        let response = metrics_handler(State(state.clone())).await.into_response();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let metrics = String::from_utf8(body.to_vec()).unwrap();
+
+        let stats = qos.get_stats().await;
+        for (line, expected) in [
+            (
+                r#"uaip_qos_messages{level="0",outcome="sent"}"#,
+                stats.qos0_sent,
+            ),
+            (
+                r#"uaip_qos_messages{level="1",outcome="sent"}"#,
+                stats.qos1_sent,
+            ),
+            (
+                r#"uaip_qos_messages{level="1",outcome="acked"}"#,
+                stats.qos1_acked,
+            ),
+            (
+                r#"uaip_qos_messages{level="2",outcome="sent"}"#,
+                stats.qos2_sent,
+            ),
+            (
+                r#"uaip_qos_messages{level="2",outcome="completed"}"#,
+                stats.qos2_completed,
+            ),
+            ("uaip_qos_retries", stats.retries),
+            ("uaip_qos_failures", stats.failures),
+        ] {
+            assert!(
+                metrics.contains(&format!("{} {}\n", line, expected)),
+                "missing {} {}",
+                line,
+                expected
+            );
+        }
+        assert_eq!(
+            (stats.qos1_sent, stats.qos1_acked, stats.retries),
+            (2, 1, 1)
+        );
+    }
 }
@@ -0,0 +1,232 @@
+//! Device provisioning handlers
+//!
+//! Admins issue provisioning tokens into their tenant; devices present a token once to
+//! register themselves without any other credentials.
+
+use axum::{extract::State, http::HeaderMap, Json};
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use uaip_core::error::UaipError;
+
+use crate::api::rest::{
+    ApiResult, AppState, DeviceRegistrationRequest, DeviceRegistrationResponse,
+};
+use crate::audit::AuditEvent;
+use crate::handlers::auth::require_scope;
+use crate::provisioning::{IssuedProvisioningToken, NewProvisioningToken, ProvisioningToken};
+use crate::services;
+use crate::tenant::Tenant;
+
+/// Default token lifetime
+const DEFAULT_TOKEN_LIFETIME_SECS: i64 = 24 * 60 * 60;
+
+/// Longest token lifetime that can be requested
+const MAX_TOKEN_LIFETIME_SECS: i64 = 30 * 24 * 60 * 60;
+
+/// Provisioning token issuance request
+#[derive(Debug, Deserialize)]
+pub struct IssueProvisioningTokenRequest {
+    /// Device type the token may register
+    pub device_type: String,
+    /// Lifetime in seconds (default 24 hours, max 30 days)
+    pub expires_in_secs: Option<i64>,
+}
+
+/// Provisioning token list response
+#[derive(Debug, Serialize)]
+pub struct ProvisioningTokenListResponse {
+    pub provisioning_tokens: Vec<ProvisioningToken>,
+    pub total: usize,
+}
+
+/// Self-registration request from a device
+#[derive(Debug, Deserialize)]
+pub struct ProvisionDeviceRequest {
+    pub provisioning_token: String,
+    #[serde(flatten)]
+    pub device: DeviceRegistrationRequest,
+}
+
+/// Issue a provisioning token; the token is only returned in this response
+pub async fn issue_provisioning_token(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<IssueProvisioningTokenRequest>,
+) -> ApiResult<Json<IssuedProvisioningToken>> {
+    let claims = require_scope(&headers, "admin")?;
+
+    if request.device_type.is_empty() {
+        return Err(UaipError::InvalidParameter("device_type cannot be empty".to_string()).into());
+    }
+    let lifetime = request
+        .expires_in_secs
+        .unwrap_or(DEFAULT_TOKEN_LIFETIME_SECS);
+    if !(1..=MAX_TOKEN_LIFETIME_SECS).contains(&lifetime) {
+        return Err(UaipError::InvalidParameter(format!(
+            "expires_in_secs must be between 1 and {}",
+            MAX_TOKEN_LIFETIME_SECS
+        ))
+        .into());
+    }
+
+    let issued = state
+        .provisioning_tokens
+        .issue(NewProvisioningToken {
+            device_type: request.device_type,
+            tenant_id: Tenant::from_claims(&claims).id().to_string(),
+            expires_at: Utc::now() + Duration::seconds(lifetime),
+        })
+        .await?;
+
+    state
+        .audit_log
+        .record_or_warn(
+            AuditEvent::success(&claims.sub, "provisioning_token.issue")
+                .with_target(issued.provisioning_token.id.to_string())
+                .with_details(serde_json::json!({
+                    "device_type": issued.provisioning_token.device_type,
+                    "expires_at": issued.provisioning_token.expires_at,
+                })),
+        )
+        .await;
+
+    Ok(Json(issued))
+}
+
+/// List the tenant's provisioning tokens, newest first
+pub async fn list_provisioning_tokens(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> ApiResult<Json<ProvisioningTokenListResponse>> {
+    let claims = require_scope(&headers, "admin")?;
+
+    let provisioning_tokens = state
+        .provisioning_tokens
+        .list(Tenant::from_claims(&claims).id())
+        .await?;
+    let total = provisioning_tokens.len();
+
+    Ok(Json(ProvisioningTokenListResponse {
+        provisioning_tokens,
+        total,
+    }))
+}
+
+/// Register a device into the tenant of the provisioning token it presents
+pub async fn provision_device(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<ProvisionDeviceRequest>,
+) -> ApiResult<Json<DeviceRegistrationResponse>> {
+    let response =
+        services::devices::provision_device(&state, &request.provisioning_token, request.device)
+            .await?;
+    Ok(Json(response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::rest::ApiError;
+    use crate::handlers::auth::jwt_manager;
+
+    fn admin() -> HeaderMap {
+        let token = jwt_manager(3600)
+            .generate_token(
+                "admin-1",
+                "admin@example.com",
+                vec!["admin".to_string()],
+                None,
+            )
+            .unwrap();
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "Authorization",
+            format!("Bearer {}", token).parse().unwrap(),
+        );
+        headers
+    }
+
+    fn provision(token: &str, device_type: &str) -> Json<ProvisionDeviceRequest> {
+        Json(ProvisionDeviceRequest {
+            provisioning_token: token.to_string(),
+            device: DeviceRegistrationRequest {
+                device_id: "probe-1".to_string(),
+                device_type: device_type.to_string(),
+                name: "Soil probe".to_string(),
+                manufacturer: None,
+                model: None,
+                capabilities: vec![],
+            },
+        })
+    }
+
+    #[tokio::test]
+    async fn test_issued_token_provisions_once() {
+        let state = Arc::new(AppState::new());
+        let Json(issued) = issue_provisioning_token(
+            State(state.clone()),
+            admin(),
+            Json(IssueProvisioningTokenRequest {
+                device_type: "sensor".to_string(),
+                expires_in_secs: None,
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(issued.provisioning_token.tenant_id, "default");
+
+        // Registration fails without a database, which must not burn the token
+        let result =
+            provision_device(State(state.clone()), provision(&issued.token, "sensor")).await;
+        assert!(matches!(result, Err(ApiError(UaipError::NotConfigured(_)))));
+        let Json(list) = list_provisioning_tokens(State(state.clone()), admin())
+            .await
+            .unwrap();
+        assert!(list.provisioning_tokens[0].consumed_at.is_none());
+
+        // Once consumed, the token is rejected
+        state
+            .provisioning_tokens
+            .consume(&issued.token, "sensor", "probe-1")
+            .await
+            .unwrap();
+        let result =
+            provision_device(State(state.clone()), provision(&issued.token, "sensor")).await;
+        assert!(matches!(
+            result,
+            Err(ApiError(UaipError::AuthenticationFailed(_)))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_issue_requires_admin_and_bounded_lifetime() {
+        let state = Arc::new(AppState::new());
+        let request = |expires_in_secs| {
+            Json(IssueProvisioningTokenRequest {
+                device_type: "sensor".to_string(),
+                expires_in_secs,
+            })
+        };
+
+        assert!(
+            issue_provisioning_token(State(state.clone()), HeaderMap::new(), request(None))
+                .await
+                .is_err()
+        );
+        for expires_in_secs in [0, MAX_TOKEN_LIFETIME_SECS + 1] {
+            let result = issue_provisioning_token(
+                State(state.clone()),
+                admin(),
+                request(Some(expires_in_secs)),
+            )
+            .await;
+            assert!(matches!(
+                result,
+                Err(ApiError(UaipError::InvalidParameter(_)))
+            ));
+        }
+    }
+}
@@ -0,0 +1,460 @@
+//! Rule handlers
+
+use axum::{
+    extract::{Path, State},
+    http::HeaderMap,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use uaip_core::error::UaipError;
+use uaip_orchestrator::rule_engine::{Action, EvaluationContext, Rule};
+
+use crate::api::rest::{ApiResult, AppState};
+use crate::handlers::auth::require_scope;
+use crate::services::devices::device_capabilities;
+use crate::services::telemetry::run_automation;
+use crate::tenant::Tenant;
+
+/// A rule with its cooldown state at the time of the response
+#[derive(Debug, Serialize)]
+pub struct RuleResponse {
+    #[serde(flatten)]
+    pub rule: Rule,
+    /// Whether the rule is cooling down and will not fire yet
+    pub in_cooldown: bool,
+    /// Seconds until the rule may fire again
+    pub cooldown_remaining_secs: Option<u64>,
+}
+
+impl RuleResponse {
+    /// Describe a rule's cooldown state at `now`
+    pub fn at(rule: Rule, now: DateTime<Utc>) -> Self {
+        let cooldown_remaining_secs = rule.cooldown_remaining_secs(now);
+        Self {
+            rule,
+            in_cooldown: cooldown_remaining_secs.is_some(),
+            cooldown_remaining_secs,
+        }
+    }
+}
+
+/// Rule list response
+#[derive(Debug, Serialize)]
+pub struct RuleListResponse {
+    pub rules: Vec<RuleResponse>,
+    pub total: usize,
+}
+
+/// Whether a tenant can see a rule; unscoped rules are visible to every tenant
+fn visible_to(rule: &Rule, tenant: &Tenant) -> bool {
+    rule.tenant_id.as_deref().is_none_or(|id| id == tenant.id())
+}
+
+/// List the caller's rules, highest priority first
+pub async fn list_rules(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> ApiResult<Json<RuleListResponse>> {
+    let tenant = Tenant::from_headers(&headers)?;
+
+    let now = Utc::now();
+    let rules: Vec<RuleResponse> = state
+        .automation
+        .lock()
+        .await
+        .rule_engine
+        .get_all_rules()
+        .into_iter()
+        .filter(|rule| visible_to(rule, &tenant))
+        .map(|rule| RuleResponse::at(rule, now))
+        .collect();
+    let total = rules.len();
+
+    Ok(Json(RuleListResponse { rules, total }))
+}
+
+/// Get a rule with its cooldown state
+pub async fn get_rule(
+    State(state): State<Arc<AppState>>,
+    Path(rule_id): Path<String>,
+    headers: HeaderMap,
+) -> ApiResult<Json<RuleResponse>> {
+    let tenant = Tenant::from_headers(&headers)?;
+
+    let rule = state
+        .automation
+        .lock()
+        .await
+        .rule_engine
+        .get_rule(&rule_id)
+        .filter(|rule| visible_to(rule, &tenant))
+        .ok_or_else(|| UaipError::NotFound(format!("Rule not found: {}", rule_id)))?;
+
+    Ok(Json(RuleResponse::at(rule, Utc::now())))
+}
+
+/// Ad-hoc context to evaluate the caller's rules against
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RuleEvaluationRequest {
+    /// Telemetry values by metric
+    #[serde(default)]
+    pub telemetry: HashMap<String, serde_json::Value>,
+
+    /// Device state values by device ID, then field
+    #[serde(default)]
+    pub device_states: HashMap<String, HashMap<String, serde_json::Value>>,
+
+    /// Evaluation time (defaults to now)
+    #[serde(default, deserialize_with = "crate::timestamp::deserialize_option")]
+    pub timestamp: Option<DateTime<Utc>>,
+
+    /// Run the triggered rules instead of only reporting them
+    #[serde(default)]
+    pub execute: bool,
+}
+
+/// An action of a triggered rule with the devices it targets
+#[derive(Debug, Serialize)]
+pub struct TriggeredAction {
+    #[serde(flatten)]
+    pub action: Action,
+    /// Devices matched by the action's device, group and capability selectors
+    pub target_devices: Vec<String>,
+}
+
+/// A rule that triggered, with the actions it carries
+#[derive(Debug, Serialize)]
+pub struct TriggeredRule {
+    pub rule_id: String,
+    pub actions: Vec<TriggeredAction>,
+}
+
+/// Rule evaluation report
+#[derive(Debug, Serialize)]
+pub struct RuleEvaluationResponse {
+    /// Whether the rules were run; dry runs only report what would run
+    pub executed: bool,
+    pub triggered_rules: Vec<TriggeredRule>,
+    /// Scenario executions started by the triggered rules (execute mode only)
+    pub scenario_executions: Vec<String>,
+}
+
+/// Evaluate rules against an ad-hoc context
+///
+/// Dry runs ignore cooldowns and change no rule state. With `execute` set the context
+/// is handled like ingested telemetry: cooldowns apply and triggered scenarios run, so
+/// the caller needs the `admin` scope and the rules run in the caller's tenant.
+/// Capability selectors match no devices when the hub runs without a database.
+pub async fn evaluate_rules(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<RuleEvaluationRequest>,
+) -> ApiResult<Json<RuleEvaluationResponse>> {
+    let tenant = if request.execute {
+        Tenant::from_claims(&require_scope(&headers, "admin")?)
+    } else {
+        Tenant::from_headers(&headers)?
+    };
+
+    let mut context = EvaluationContext::new().with_tenant(tenant.id().to_string());
+    context.telemetry = request.telemetry;
+    context.device_states = request.device_states;
+    if let Some(timestamp) = request.timestamp {
+        context.timestamp = timestamp;
+    }
+
+    let (rule_ids, scenario_executions) = if request.execute {
+        let result = run_automation(&state, &context).await?;
+        (result.triggered_rules, result.scenario_executions)
+    } else {
+        (state.automation.lock().await.preview(&context), Vec::new())
+    };
+
+    let triggered: Vec<(String, Vec<Action>)> = {
+        let automation = state.automation.lock().await;
+        rule_ids
+            .into_iter()
+            .map(|rule_id| {
+                let actions = automation
+                    .rule_engine
+                    .get_rule(&rule_id)
+                    .map(|rule| rule.actions)
+                    .unwrap_or_default();
+                (rule_id, actions)
+            })
+            .collect()
+    };
+
+    let targets_capability = triggered
+        .iter()
+        .flat_map(|(_, actions)| actions)
+        .any(|action| action.capability.is_some());
+    if targets_capability && state.db_pool.is_some() {
+        context.capabilities = device_capabilities(&state, &tenant).await?;
+    }
+
+    let automation = state.automation.lock().await;
+    let triggered_rules = triggered
+        .into_iter()
+        .map(|(rule_id, actions)| TriggeredRule {
+            rule_id,
+            actions: actions
+                .into_iter()
+                .map(|action| TriggeredAction {
+                    target_devices: automation.target_devices(&action, &context),
+                    action,
+                })
+                .collect(),
+        })
+        .collect();
+
+    Ok(Json(RuleEvaluationResponse {
+        executed: request.execute,
+        triggered_rules,
+        scenario_executions,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uaip_orchestrator::automation::AutomationEngine;
+    use uaip_orchestrator::rule_engine::{
+        ActionType, Condition, ConditionMode, Operator, Rule, RuleEngine,
+    };
+    use uaip_orchestrator::scenario::ScenarioEngine;
+
+    fn state() -> Arc<AppState> {
+        let mut rule_engine = RuleEngine::new();
+        rule_engine.add_rule(Rule {
+            id: "overheat".to_string(),
+            name: "Overheat".to_string(),
+            description: None,
+            enabled: true,
+            conditions: vec![Condition {
+                field: "temperature".to_string(),
+                operator: Operator::GreaterThan,
+                value: serde_json::json!(30.0),
+                device_id: None,
+                group_id: None,
+                aggregate: None,
+            }],
+            actions: vec![Action {
+                action_type: ActionType::SendCommand,
+                device_id: Some("fan-1".to_string()),
+                group_id: None,
+                capability: None,
+                parameters: HashMap::from([("speed".to_string(), serde_json::json!("high"))]),
+            }],
+            condition_mode: ConditionMode::All,
+            priority: 1,
+            cooldown_seconds: Some(300),
+            last_executed: None,
+            tenant_id: None,
+            metadata: HashMap::new(),
+        });
+
+        Arc::new(
+            AppState::new()
+                .with_automation(AutomationEngine::new(rule_engine, ScenarioEngine::new())),
+        )
+    }
+
+    fn bearer(scopes: &[&str]) -> HeaderMap {
+        let token = crate::handlers::auth::jwt_manager(3600)
+            .generate_token(
+                "operator-1",
+                "ops@example.com",
+                scopes.iter().map(|s| s.to_string()).collect(),
+                None,
+            )
+            .unwrap();
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "Authorization",
+            format!("Bearer {}", token).parse().unwrap(),
+        );
+        headers
+    }
+
+    fn request(temperature: f64, execute: bool) -> Json<RuleEvaluationRequest> {
+        Json(RuleEvaluationRequest {
+            telemetry: HashMap::from([("temperature".to_string(), serde_json::json!(temperature))]),
+            execute,
+            ..Default::default()
+        })
+    }
+
+    async fn triggered(state: &Arc<AppState>, temperature: f64, execute: bool) -> Vec<String> {
+        let Json(response) = evaluate_rules(
+            State(state.clone()),
+            bearer(&["admin"]),
+            request(temperature, execute),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.executed, execute);
+        response
+            .triggered_rules
+            .into_iter()
+            .map(|rule| rule.rule_id)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_reports_actions_without_side_effects() {
+        let state = state();
+
+        let Json(response) =
+            evaluate_rules(State(state.clone()), HeaderMap::new(), request(35.0, false))
+                .await
+                .unwrap();
+        assert_eq!(response.triggered_rules.len(), 1);
+        let actions = &response.triggered_rules[0].actions;
+        assert_eq!(actions[0].action.action_type, ActionType::SendCommand);
+        assert_eq!(actions[0].action.device_id.as_deref(), Some("fan-1"));
+        assert_eq!(actions[0].target_devices, ["fan-1"]);
+
+        // Repeated dry runs neither start nor respect the cooldown
+        assert_eq!(triggered(&state, 35.0, false).await, ["overheat"]);
+        assert!(triggered(&state, 20.0, false).await.is_empty());
+        let automation = state.automation.lock().await;
+        let rule = automation.rule_engine.get_rule("overheat").unwrap();
+        assert!(rule.last_executed.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_execute_requires_admin() {
+        let state = state();
+
+        let result =
+            evaluate_rules(State(state.clone()), HeaderMap::new(), request(35.0, true)).await;
+        assert!(matches!(
+            result,
+            Err(crate::api::rest::ApiError(UaipError::AuthenticationFailed(
+                _
+            )))
+        ));
+        let result = evaluate_rules(
+            State(state.clone()),
+            bearer(&["device:read"]),
+            request(35.0, true),
+        )
+        .await;
+        assert!(matches!(
+            result,
+            Err(crate::api::rest::ApiError(UaipError::AuthorizationFailed(
+                _
+            )))
+        ));
+
+        // Refused executions leave the cooldown untouched
+        let automation = state.automation.lock().await;
+        let rule = automation.rule_engine.get_rule("overheat").unwrap();
+        assert!(rule.last_executed.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_group_and_capability_targets_resolve_per_action() {
+        use uaip_core::group::{DeviceGroup, DeviceGroups};
+
+        let state = state();
+        {
+            let mut automation = state.automation.lock().await;
+            automation.groups = DeviceGroups::from_groups(vec![DeviceGroup::new("hall", "Hall")
+                .with_devices(["fan-2", "fan-3"])
+                .with_tenant("default")])
+            .unwrap();
+            let mut rule = automation.rule_engine.get_rule("overheat").unwrap();
+            let mut group_action = rule.actions[0].clone();
+            group_action.device_id = None;
+            group_action.group_id = Some("hall".to_string());
+            let mut capability_action = group_action.clone();
+            capability_action.group_id = None;
+            capability_action.capability = Some("thermostat".to_string());
+            rule.actions = vec![group_action, capability_action];
+            automation.rule_engine.update_rule(rule).unwrap();
+        }
+
+        let Json(response) = evaluate_rules(State(state), HeaderMap::new(), request(35.0, false))
+            .await
+            .unwrap();
+        let actions = &response.triggered_rules[0].actions;
+        assert_eq!(actions[0].target_devices, ["fan-2", "fan-3"]);
+        // Without a database no device capabilities are known
+        assert!(actions[1].target_devices.is_empty());
+
+        let json = serde_json::to_value(&actions[1]).unwrap();
+        assert_eq!(json["capability"], "thermostat");
+        assert_eq!(json["target_devices"], serde_json::json!([]));
+    }
+
+    #[tokio::test]
+    async fn test_execute_respects_cooldown() {
+        let state = state();
+
+        assert_eq!(triggered(&state, 35.0, true).await, ["overheat"]);
+        assert!(state
+            .automation
+            .lock()
+            .await
+            .rule_engine
+            .get_rule("overheat")
+            .unwrap()
+            .last_executed
+            .is_some());
+
+        // Cooling down: execute mode skips the rule, a dry run still reports it
+        assert!(triggered(&state, 36.0, true).await.is_empty());
+        assert_eq!(triggered(&state, 36.0, false).await, ["overheat"]);
+    }
+
+    #[tokio::test]
+    async fn test_idle_rule_reports_no_cooldown() {
+        let state = state();
+
+        let Json(list) = list_rules(State(state.clone()), HeaderMap::new())
+            .await
+            .unwrap();
+        assert_eq!(list.total, 1);
+        assert!(!list.rules[0].in_cooldown);
+        assert_eq!(list.rules[0].cooldown_remaining_secs, None);
+
+        let json = serde_json::to_value(&list.rules[0]).unwrap();
+        assert_eq!(json["id"], "overheat");
+        assert_eq!(json["in_cooldown"], false);
+        assert!(json["cooldown_remaining_secs"].is_null());
+
+        let result = get_rule(State(state), Path("missing".to_string()), HeaderMap::new()).await;
+        assert!(matches!(
+            result,
+            Err(crate::api::rest::ApiError(UaipError::NotFound(_)))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_recently_fired_rule_reports_remaining_cooldown() {
+        let state = state();
+        let fired_at = Utc::now() - chrono::Duration::seconds(60);
+        {
+            let mut automation = state.automation.lock().await;
+            let mut rule = automation.rule_engine.get_rule("overheat").unwrap();
+            rule.last_executed = Some(fired_at);
+            automation.rule_engine.update_rule(rule).unwrap();
+        }
+
+        let Json(rule) = get_rule(State(state), Path("overheat".to_string()), HeaderMap::new())
+            .await
+            .unwrap();
+        assert!(rule.in_cooldown);
+        // 300s cooldown, fired a minute ago
+        let remaining = rule.cooldown_remaining_secs.unwrap();
+        assert!((239..=240).contains(&remaining), "{}", remaining);
+        assert_eq!(rule.rule.last_executed, Some(fired_at));
+    }
+}
@@ -0,0 +1,163 @@
+//! Scenario handlers
+
+use axum::{
+    extract::{Path, Query, State},
+    http::HeaderMap,
+    Json,
+};
+use std::sync::Arc;
+
+use uaip_core::error::UaipError;
+
+use crate::api::rest::{ApiResult, AppState};
+use crate::handlers::auth::require_claims;
+use crate::scenario_history::{ExecutionPage, ExecutionQuery};
+use crate::tenant::Tenant;
+
+/// List a scenario's executions, newest first
+///
+/// Only scenarios that are registered and unscoped or owned by the caller's tenant are
+/// listed; any other scenario ID is reported as not found.
+pub async fn list_scenario_executions(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(scenario_id): Path<String>,
+    Query(query): Query<ExecutionQuery>,
+) -> ApiResult<Json<ExecutionPage>> {
+    require_claims(&headers)?;
+    let tenant = Tenant::from_headers(&headers)?;
+    let visible = state
+        .automation
+        .lock()
+        .await
+        .scenario_engine
+        .get_scenario(&scenario_id)
+        .is_some_and(|scenario| {
+            scenario
+                .tenant_id
+                .as_deref()
+                .is_none_or(|tenant_id| tenant_id == tenant.id())
+        });
+    if !visible {
+        return Err(UaipError::NotFound(format!("Scenario not found: {}", scenario_id)).into());
+    }
+
+    let page = state
+        .scenario_executions
+        .query(&scenario_id, &query)
+        .await?;
+
+    Ok(Json(page))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use std::collections::HashMap;
+    use uaip_orchestrator::scenario::{
+        Scenario, ScenarioAction, ScenarioActionConfig, ScenarioExecution, ScenarioState,
+        ScenarioTrigger, TriggerType,
+    };
+
+    fn tenant_bearer(tenant: &str) -> HeaderMap {
+        let token = crate::handlers::auth::jwt_manager(3600)
+            .generate_tenant_token(
+                "operator-1",
+                "ops@example.com",
+                vec![],
+                None,
+                Some(tenant.to_string()),
+            )
+            .unwrap();
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "Authorization",
+            format!("Bearer {}", token).parse().unwrap(),
+        );
+        headers
+    }
+
+    async fn state_with_execution(tenant_id: &str) -> Arc<AppState> {
+        let state = Arc::new(AppState::new());
+        state
+            .automation
+            .lock()
+            .await
+            .scenario_engine
+            .register_scenario(Scenario {
+                id: "night-mode".to_string(),
+                name: "Night mode".to_string(),
+                description: None,
+                enabled: true,
+                triggers: vec![ScenarioTrigger {
+                    trigger_type: TriggerType::Manual,
+                    config: HashMap::new(),
+                    conditions: vec![],
+                }],
+                actions: vec![ScenarioActionConfig {
+                    action: ScenarioAction::SendNotification,
+                    parameters: HashMap::new(),
+                    wait: true,
+                    timeout_seconds: None,
+                }],
+                state: ScenarioState::Active,
+                metadata: HashMap::new(),
+                execution_count: 0,
+                last_triggered: None,
+                last_result: None,
+                last_result_summary: None,
+                tenant_id: Some(tenant_id.to_string()),
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            })
+            .unwrap();
+        state
+            .scenario_executions
+            .record(&ScenarioExecution {
+                id: "execution-1".to_string(),
+                scenario_id: "night-mode".to_string(),
+                trigger: TriggerType::Manual,
+                state: ScenarioState::Completed,
+                trigger_context: HashMap::new(),
+                actions_executed: Vec::new(),
+                error: None,
+                started_at: Utc::now(),
+                completed_at: Some(Utc::now()),
+            })
+            .await
+            .unwrap();
+        state
+    }
+
+    async fn list(state: &Arc<AppState>, headers: HeaderMap) -> ApiResult<Json<ExecutionPage>> {
+        list_scenario_executions(
+            State(state.clone()),
+            headers,
+            Path("night-mode".to_string()),
+            Query(ExecutionQuery::default()),
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_executions_are_isolated_between_tenants() {
+        let state = state_with_execution("tenant-a").await;
+
+        let page = list(&state, tenant_bearer("tenant-a")).await.unwrap();
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.items[0].id, "execution-1");
+
+        assert!(matches!(
+            list(&state, tenant_bearer("tenant-b")).await,
+            Err(crate::api::rest::ApiError(UaipError::NotFound(_)))
+        ));
+        assert!(matches!(
+            list(&state, HeaderMap::new()).await,
+            Err(crate::api::rest::ApiError(UaipError::AuthenticationFailed(
+                _
+            )))
+        ));
+    }
+}
@@ -0,0 +1,250 @@
+//! Dashboard session handlers
+//!
+//! Sign in with the same credentials as `/api/v1/auth/login`, but receive a session
+//! cookie and CSRF token instead of bearer tokens.
+
+use axum::{
+    extract::State,
+    http::{header::SET_COOKIE, StatusCode},
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::sync::Arc;
+
+use uaip_auth::jwt::Claims;
+use uaip_core::error::UaipError;
+
+use crate::api::rest::{ApiResult, AppState, LoginRequest};
+use crate::audit::AuditEvent;
+use crate::cookie_sessions::CookieSessions;
+use crate::handlers::auth::{self, jwt_manager};
+use crate::middleware::cookie_session::SessionIdentity;
+
+/// Session login response; the session itself travels in the cookie
+#[derive(Debug, Serialize)]
+pub struct SessionResponse {
+    /// Echo in `X-CSRF-Token` on state-changing requests
+    pub csrf_token: String,
+    pub expires_at: DateTime<Utc>,
+    pub scopes: Vec<String>,
+    pub require_password_change: bool,
+}
+
+/// Sign in to a cookie session
+pub async fn create_session(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<LoginRequest>,
+) -> ApiResult<Response> {
+    let sessions = enabled(&state)?;
+    let Json(login) = auth::login(State(state.clone()), Json(request)).await?;
+    let claims = jwt_manager(3600).validate_token(&login.access_token)?;
+    start_session(sessions, &claims, login.require_password_change).await
+}
+
+/// End the caller's cookie session and clear the cookie
+pub async fn end_session(
+    State(state): State<Arc<AppState>>,
+    identity: Option<Extension<SessionIdentity>>,
+) -> ApiResult<Response> {
+    let sessions = enabled(&state)?;
+    if let Some(Extension(identity)) = identity {
+        sessions.end(&identity.session_id).await;
+        state
+            .audit_log
+            .record_or_warn(AuditEvent::success(identity.subject, "session.logout"))
+            .await;
+    }
+    Ok((
+        StatusCode::NO_CONTENT,
+        [(SET_COOKIE, sessions.expired_cookie())],
+    )
+        .into_response())
+}
+
+/// Start a session for an authenticated subject, setting its cookie
+pub(crate) async fn start_session(
+    sessions: &CookieSessions,
+    claims: &Claims,
+    require_password_change: bool,
+) -> ApiResult<Response> {
+    let login = sessions.start(claims).await?;
+    let response = SessionResponse {
+        csrf_token: login.csrf_token,
+        expires_at: login.session.expires_at,
+        scopes: login.session.scopes,
+        require_password_change,
+    };
+    Ok(([(SET_COOKIE, login.cookie)], Json(response)).into_response())
+}
+
+fn enabled(state: &AppState) -> Result<&CookieSessions, UaipError> {
+    state
+        .cookie_sessions
+        .as_ref()
+        .ok_or_else(|| UaipError::NotConfigured("Cookie sessions are not enabled".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::rest::create_router;
+    use crate::cookie_sessions::CSRF_HEADER;
+    use axum::body::Body;
+    use axum::http::header::{AUTHORIZATION, COOKIE};
+
+    fn admin_claims() -> Claims {
+        let token = jwt_manager(3600)
+            .generate_tenant_token(
+                "admin-1",
+                "admin@example.com",
+                vec!["admin".to_string()],
+                None,
+                Some("acme".to_string()),
+            )
+            .unwrap();
+        jwt_manager(3600).validate_token(&token).unwrap()
+    }
+
+    fn state() -> Arc<AppState> {
+        let sessions = CookieSessions::new(
+            b"0123456789abcdef0123456789abcdef",
+            chrono::Duration::hours(1),
+        )
+        .unwrap();
+        Arc::new(AppState::new().with_cookie_sessions(sessions))
+    }
+
+    /// Sign in, returning the `Cookie` header value and CSRF token
+    async fn sign_in(state: &AppState) -> (String, String) {
+        let response = start_session(
+            state.cookie_sessions.as_ref().unwrap(),
+            &admin_claims(),
+            false,
+        )
+        .await
+        .unwrap();
+        let set_cookie = response.headers()[SET_COOKIE].to_str().unwrap().to_string();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+        let cookie = set_cookie.split(';').next().unwrap().to_string();
+        (cookie, body["csrf_token"].as_str().unwrap().to_string())
+    }
+
+    async fn send(state: Arc<AppState>, request: axum::http::Request<Body>) -> StatusCode {
+        use tower::Service;
+
+        let mut router = create_router(state).into_service::<Body>();
+        std::future::poll_fn(|cx| router.poll_ready(cx))
+            .await
+            .unwrap();
+        router.call(request).await.unwrap().status()
+    }
+
+    fn issue_key(cookie: &str, csrf: Option<&str>) -> axum::http::Request<Body> {
+        let mut request = axum::http::Request::post("/api/v1/auth/api-keys")
+            .header(COOKIE, cookie)
+            .header("content-type", "application/json");
+        if let Some(csrf) = csrf {
+            request = request.header(CSRF_HEADER, csrf);
+        }
+        request
+            .body(Body::from(r#"{"name": "dashboard"}"#))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_login_sets_http_only_session_cookie() {
+        let state = state();
+        let response = start_session(
+            state.cookie_sessions.as_ref().unwrap(),
+            &admin_claims(),
+            true,
+        )
+        .await
+        .unwrap();
+        let set_cookie = response.headers()[SET_COOKIE].to_str().unwrap();
+        assert!(set_cookie.starts_with("uaip_session="));
+        assert!(set_cookie.contains("HttpOnly"));
+        assert!(set_cookie.contains("SameSite=Strict"));
+
+        // Without a configured secret, session login is unavailable
+        let request = LoginRequest {
+            grant_type: "client_credentials".to_string(),
+            client_id: "admin@example.com".to_string(),
+            client_secret: Some("secret".to_string()),
+            scope: None,
+        };
+        let result = create_session(State(Arc::new(AppState::new())), Json(request)).await;
+        assert!(matches!(result, Err(e) if matches!(e.0, UaipError::NotConfigured(_))));
+    }
+
+    #[tokio::test]
+    async fn test_cookie_authenticates_request() {
+        let state = state();
+        let (cookie, _) = sign_in(&state).await;
+
+        let request = |cookie: &str| {
+            axum::http::Request::get("/api/v1/auth/api-keys")
+                .header(COOKIE, cookie)
+                .body(Body::empty())
+                .unwrap()
+        };
+        assert_eq!(send(state.clone(), request(&cookie)).await, StatusCode::OK);
+
+        let tampered = format!("{}0", cookie);
+        assert_eq!(
+            send(state.clone(), request(&tampered)).await,
+            StatusCode::UNAUTHORIZED
+        );
+        let anonymous = axum::http::Request::get("/api/v1/auth/api-keys")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(send(state, anonymous).await, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_state_changing_cookie_request_requires_csrf_token() {
+        let state = state();
+        let (cookie, csrf) = sign_in(&state).await;
+
+        assert_eq!(
+            send(state.clone(), issue_key(&cookie, None)).await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            send(state.clone(), issue_key(&cookie, Some("00ff"))).await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            send(state.clone(), issue_key(&cookie, Some(&csrf))).await,
+            StatusCode::OK
+        );
+
+        // Bearer clients need no CSRF token, even when a cookie is also present
+        let token = jwt_manager(3600)
+            .generate_tenant_token("admin-1", "admin", vec!["admin".to_string()], None, None)
+            .unwrap();
+        let mut request = issue_key(&cookie, None);
+        request
+            .headers_mut()
+            .insert(AUTHORIZATION, format!("Bearer {}", token).parse().unwrap());
+        assert_eq!(send(state.clone(), request).await, StatusCode::OK);
+
+        // Logging out ends the session
+        let logout = axum::http::Request::delete("/api/v1/auth/session")
+            .header(COOKIE, &cookie)
+            .header(CSRF_HEADER, &csrf)
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(send(state.clone(), logout).await, StatusCode::NO_CONTENT);
+        assert_eq!(
+            send(state, issue_key(&cookie, Some(&csrf))).await,
+            StatusCode::UNAUTHORIZED
+        );
+    }
+}
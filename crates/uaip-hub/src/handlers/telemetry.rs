@@ -0,0 +1,416 @@
+//! Device telemetry ingestion handlers
+
+use axum::{
+    extract::{Path, State},
+    http::HeaderMap,
+    Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use uaip_core::device::DeviceStatus;
+use uaip_core::error::UaipError;
+use uaip_orchestrator::anomaly::Anomaly;
+use uaip_orchestrator::automation::ReplayReport;
+use uaip_orchestrator::rule_engine::EvaluationContext;
+
+use crate::api::rest::{ApiResult, AppState};
+use crate::services;
+use crate::telemetry_schema::DeviceTypeSchema;
+use crate::tenant::Tenant;
+
+/// Maximum number of readings accepted in one batch
+pub const MAX_BATCH_SIZE: usize = 1000;
+
+/// Maximum length of a metric name
+const MAX_METRIC_LEN: usize = 100;
+
+/// Allowed clock skew for reading timestamps ahead of the hub clock
+const MAX_FUTURE_SKEW_SECS: i64 = 300;
+
+/// Readings buffered for live subscribers; slower subscribers skip ahead
+pub const TELEMETRY_FEED_CAPACITY: usize = 1024;
+
+/// Longest a paced replay may take
+const MAX_REPLAY_DURATION_SECS: f64 = 300.0;
+
+/// A single telemetry reading
+#[derive(Debug, Clone, Deserialize)]
+pub struct TelemetryReading {
+    pub metric: String,
+    pub value: serde_json::Value,
+    pub unit: Option<String>,
+    #[serde(default, deserialize_with = "crate::timestamp::deserialize_option")]
+    pub timestamp: Option<DateTime<Utc>>,
+}
+
+/// An accepted reading, as published to live telemetry subscribers
+#[derive(Debug, Clone, Serialize)]
+pub struct TelemetryEvent {
+    /// Tenant the reporting device belongs to
+    pub tenant_id: String,
+    pub device_id: String,
+    pub metric: String,
+    pub value: serde_json::Value,
+    pub unit: Option<String>,
+    pub recorded_at: DateTime<Utc>,
+}
+
+/// Per-reading ingestion result
+#[derive(Debug, Serialize)]
+pub struct ReadingResult {
+    pub index: usize,
+    pub metric: String,
+    pub accepted: bool,
+    pub error: Option<String>,
+}
+
+/// Batch ingestion report
+#[derive(Debug, Serialize)]
+pub struct TelemetryBatchResponse {
+    pub device_id: String,
+    pub accepted: usize,
+    pub rejected: usize,
+    pub results: Vec<ReadingResult>,
+    pub triggered_rules: Vec<String>,
+    /// Scenario executions started by triggered rules and detected anomalies
+    pub scenario_executions: Vec<String>,
+    /// Readings flagged as outliers for their device metric
+    pub anomalies: Vec<Anomaly>,
+}
+
+/// Heartbeat from a device, optionally carrying telemetry
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct HeartbeatRequest {
+    /// Device-reported status (online, error or maintenance); online when omitted
+    pub status: Option<DeviceStatus>,
+
+    /// Readings taken since the previous heartbeat
+    #[serde(default)]
+    pub telemetry: Vec<TelemetryReading>,
+}
+
+/// Heartbeat acknowledgment
+#[derive(Debug, Serialize)]
+pub struct HeartbeatResponse {
+    pub device_id: String,
+    pub status: DeviceStatus,
+    pub last_seen: DateTime<Utc>,
+    /// Ingestion report; absent for heartbeat-only messages
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub telemetry: Option<TelemetryBatchResponse>,
+}
+
+/// Check a single reading, returning the reason it is rejected
+pub fn validate_reading(reading: &TelemetryReading, now: DateTime<Utc>) -> Result<(), String> {
+    if reading.metric.is_empty() {
+        return Err("metric cannot be empty".to_string());
+    }
+    if reading.metric.len() > MAX_METRIC_LEN {
+        return Err(format!("metric exceeds {} characters", MAX_METRIC_LEN));
+    }
+    if !reading
+        .metric
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+    {
+        return Err("metric may only contain letters, digits, '_', '-' and '.'".to_string());
+    }
+
+    match &reading.value {
+        serde_json::Value::Number(_) | serde_json::Value::Bool(_) => {}
+        serde_json::Value::String(s) if !s.is_empty() => {}
+        _ => return Err("value must be a number, boolean or non-empty string".to_string()),
+    }
+
+    if let Some(timestamp) = reading.timestamp {
+        if timestamp > now + Duration::seconds(MAX_FUTURE_SKEW_SECS) {
+            return Err("timestamp is in the future".to_string());
+        }
+    }
+
+    Ok(())
+}
+
+/// Validate a batch and build one evaluation context from the accepted readings
+///
+/// Readings are also checked against the device type's schema, when it has one. Later
+/// readings of the same metric override earlier ones in the context.
+pub fn evaluate_batch(
+    device_id: &str,
+    readings: &[TelemetryReading],
+    schema: Option<&DeviceTypeSchema>,
+    now: DateTime<Utc>,
+) -> (Vec<ReadingResult>, EvaluationContext) {
+    let mut results = Vec::with_capacity(readings.len());
+    let mut context = EvaluationContext::new();
+    let mut device_state = HashMap::new();
+    let mut latest: Option<DateTime<Utc>> = None;
+
+    for (index, reading) in readings.iter().enumerate() {
+        let outcome = validate_reading(reading, now)
+            .and_then(|_| schema.map_or(Ok(()), |schema| schema.check(reading)));
+        if outcome.is_ok() {
+            context
+                .telemetry
+                .insert(reading.metric.clone(), reading.value.clone());
+            device_state.insert(reading.metric.clone(), reading.value.clone());
+
+            let timestamp = reading.timestamp.unwrap_or(now);
+            latest = Some(latest.map_or(timestamp, |l| l.max(timestamp)));
+        }
+
+        results.push(ReadingResult {
+            index,
+            metric: reading.metric.clone(),
+            accepted: outcome.is_ok(),
+            error: outcome.err(),
+        });
+    }
+
+    if !device_state.is_empty() {
+        context = context.with_device_state(device_id.to_string(), device_state);
+    }
+    context.timestamp = latest.unwrap_or(now);
+
+    (results, context)
+}
+
+/// Window of stored telemetry to replay
+#[derive(Debug, Clone, Deserialize)]
+pub struct TelemetryReplayRequest {
+    /// Start of the window (inclusive)
+    #[serde(deserialize_with = "crate::timestamp::deserialize")]
+    pub from: DateTime<Utc>,
+    /// End of the window (exclusive)
+    #[serde(deserialize_with = "crate::timestamp::deserialize")]
+    pub to: DateTime<Utc>,
+    /// Replay at this multiple of real time; omit to replay without pauses
+    pub speed: Option<f64>,
+}
+
+/// Ingest a batch of telemetry readings from a device
+pub async fn ingest_telemetry_batch(
+    State(state): State<Arc<AppState>>,
+    Path(device_id): Path<String>,
+    headers: HeaderMap,
+    Json(readings): Json<Vec<TelemetryReading>>,
+) -> ApiResult<Json<TelemetryBatchResponse>> {
+    let tenant = Tenant::from_headers(&headers)?;
+    if device_id.is_empty() {
+        return Err(UaipError::InvalidParameter("device_id cannot be empty".to_string()).into());
+    }
+    if readings.is_empty() {
+        return Err(UaipError::InvalidParameter("batch cannot be empty".to_string()).into());
+    }
+    if readings.len() > MAX_BATCH_SIZE {
+        return Err(UaipError::InvalidParameter(format!(
+            "batch exceeds {} readings",
+            MAX_BATCH_SIZE
+        ))
+        .into());
+    }
+
+    let db_pool = state.db()?;
+
+    let device_exists: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM devices WHERE device_id = $1 AND tenant_id = $2)",
+    )
+    .bind(&device_id)
+    .bind(tenant.id())
+    .fetch_one(db_pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to query device: {}", e);
+        UaipError::InternalError("Failed to verify device".to_string())
+    })?;
+    if !device_exists {
+        return Err(UaipError::DeviceNotFound(format!("Device '{}' not found", device_id)).into());
+    }
+
+    let report =
+        services::telemetry::record_readings(&state, &tenant, &device_id, &readings).await?;
+    Ok(Json(report))
+}
+
+/// Replay a device's stored telemetry through a copy of the rules
+///
+/// Dry run only: reports what would have triggered without dispatching anything.
+pub async fn replay_telemetry(
+    State(state): State<Arc<AppState>>,
+    Path(device_id): Path<String>,
+    headers: HeaderMap,
+    Json(request): Json<TelemetryReplayRequest>,
+) -> ApiResult<Json<ReplayReport>> {
+    let tenant = Tenant::from_headers(&headers)?;
+    if request.from >= request.to {
+        return Err(UaipError::InvalidParameter("from must be before to".to_string()).into());
+    }
+    if let Some(speed) = request.speed {
+        let window = (request.to - request.from).num_milliseconds() as f64 / 1000.0;
+        if window / speed > MAX_REPLAY_DURATION_SECS {
+            return Err(UaipError::InvalidParameter(format!(
+                "replay would take longer than {} seconds; increase speed",
+                MAX_REPLAY_DURATION_SECS
+            ))
+            .into());
+        }
+    }
+
+    let report = services::telemetry::replay_telemetry(
+        &state,
+        &tenant,
+        &device_id,
+        request.from,
+        request.to,
+        request.speed,
+    )
+    .await?;
+    Ok(Json(report))
+}
+
+/// Record a heartbeat and any telemetry it carries in one call
+pub async fn device_heartbeat(
+    State(state): State<Arc<AppState>>,
+    Path(device_id): Path<String>,
+    headers: HeaderMap,
+    Json(request): Json<HeartbeatRequest>,
+) -> ApiResult<Json<HeartbeatResponse>> {
+    let tenant = Tenant::from_headers(&headers)?;
+    let response =
+        services::telemetry::record_heartbeat(&state, &tenant, &device_id, request).await?;
+    Ok(Json(response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uaip_orchestrator::automation::AutomationEngine;
+    use uaip_orchestrator::rule_engine::{Condition, ConditionMode, Operator, Rule, RuleEngine};
+    use uaip_orchestrator::scenario::ScenarioEngine;
+
+    fn reading(metric: &str, value: serde_json::Value) -> TelemetryReading {
+        TelemetryReading {
+            metric: metric.to_string(),
+            value,
+            unit: None,
+            timestamp: None,
+        }
+    }
+
+    fn condition(field: &str, operator: Operator, value: serde_json::Value) -> Condition {
+        Condition {
+            field: field.to_string(),
+            operator,
+            value,
+            device_id: None,
+            group_id: None,
+            aggregate: None,
+        }
+    }
+
+    #[test]
+    fn test_mixed_batch_reports_each_reading() {
+        let now = Utc::now();
+        let readings = vec![
+            reading("temperature", serde_json::json!(21.5)),
+            reading("", serde_json::json!(1)),
+            reading("humidity", serde_json::json!(null)),
+            reading("door.open", serde_json::json!(true)),
+            TelemetryReading {
+                timestamp: Some(now + Duration::hours(1)),
+                ..reading("pressure", serde_json::json!(1013))
+            },
+        ];
+
+        let (results, context) = evaluate_batch("sensor-1", &readings, None, now);
+        let accepted: Vec<bool> = results.iter().map(|r| r.accepted).collect();
+        assert_eq!(accepted, vec![true, false, false, true, false]);
+        assert!(results[1].error.is_some());
+        assert!(results[4].error.as_deref().unwrap().contains("future"));
+
+        assert_eq!(context.telemetry.len(), 2);
+        assert_eq!(
+            context.get_device_value("sensor-1", "temperature"),
+            Some(&serde_json::json!(21.5))
+        );
+        assert!(context.get_value("humidity").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_cross_metric_rule_fires_off_batch() {
+        let mut rule_engine = RuleEngine::new();
+        rule_engine.add_rule(Rule {
+            id: "muggy".to_string(),
+            name: "Hot and humid".to_string(),
+            description: None,
+            enabled: true,
+            conditions: vec![
+                condition("temperature", Operator::GreaterThan, serde_json::json!(30)),
+                condition("humidity", Operator::GreaterThan, serde_json::json!(70)),
+            ],
+            actions: vec![],
+            condition_mode: ConditionMode::All,
+            priority: 1,
+            cooldown_seconds: None,
+            last_executed: None,
+            tenant_id: None,
+            metadata: HashMap::new(),
+        });
+        let mut automation = AutomationEngine::new(rule_engine, ScenarioEngine::new());
+
+        let readings = vec![
+            reading("temperature", serde_json::json!(33.0)),
+            reading("humidity", serde_json::json!(82)),
+        ];
+        let (_, context) = evaluate_batch("sensor-1", &readings, None, Utc::now());
+
+        let result = automation.ingest_telemetry(&context).await.unwrap();
+        assert_eq!(result.triggered_rules, vec!["muggy".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_ingest_rejects_oversized_batch() {
+        let state = Arc::new(AppState::new());
+        let readings = vec![reading("temperature", serde_json::json!(1)); MAX_BATCH_SIZE + 1];
+
+        let result = ingest_telemetry_batch(
+            State(state),
+            Path("sensor-1".to_string()),
+            HeaderMap::new(),
+            Json(readings),
+        )
+        .await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_replay_validates_window() {
+        let state = Arc::new(AppState::new());
+        let to = Utc::now();
+        let replay = |from, speed| {
+            replay_telemetry(
+                State(state.clone()),
+                Path("sensor-1".to_string()),
+                HeaderMap::new(),
+                Json(TelemetryReplayRequest { from, to, speed }),
+            )
+        };
+
+        for (from, speed) in [(to, None), (to - Duration::hours(1), Some(2.0))] {
+            let result = replay(from, speed).await;
+            assert!(matches!(
+                result,
+                Err(crate::api::rest::ApiError(UaipError::InvalidParameter(_)))
+            ));
+        }
+        // A valid window still needs the stored readings
+        let result = replay(to - Duration::hours(1), Some(60.0)).await;
+        assert!(matches!(
+            result,
+            Err(crate::api::rest::ApiError(UaipError::NotConfigured(_)))
+        ));
+    }
+}
@@ -1,5 +1,6 @@
 use axum::{
     extract::{Path, State},
+    http::HeaderMap,
     Json,
 };
 use serde::{Deserialize, Serialize};
@@ -8,6 +9,8 @@ use uuid::Uuid;
 use sqlx::Row;
 use uaip_core::error::UaipError;
 use crate::api::rest::{ApiResult, AppState};
+use crate::audit::AuditEvent;
+use crate::handlers::auth::caller_subject;
 
 /// User registration request
 #[derive(Debug, Deserialize)]
@@ -41,11 +44,11 @@ pub struct UserListResponse {
 /// Create a new user (Human)
 pub async fn create_user(
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
     Json(request): Json<CreateUserRequest>,
 ) -> ApiResult<Json<UserInfo>> {
-    let db_pool = state.db_pool.as_ref().ok_or_else(|| {
-        UaipError::InternalError("Database not configured".to_string())
-    })?;
+    let actor = caller_subject(&headers)?;
+    let db_pool = state.db()?;
 
     // Check if user already exists
     let exists: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM users WHERE email = $1)")
@@ -142,11 +145,20 @@ pub async fn create_user(
         UaipError::InternalError("Database error".to_string())
     })?;
 
+    state
+        .audit_log
+        .record_or_warn(
+            AuditEvent::success(actor, "user.create")
+                .with_target(user_id.to_string())
+                .with_details(serde_json::json!({ "email": request.email, "role": role })),
+        )
+        .await;
+
     let user_info = UserInfo {
         id: user_id,
         name: request.name,
         email: request.email,
-        role: role,
+        role,
         active: true,
         last_login: None,
         created_at: now,
@@ -159,9 +171,7 @@ pub async fn create_user(
 pub async fn list_users(
     State(state): State<Arc<AppState>>,
 ) -> ApiResult<Json<UserListResponse>> {
-    let db_pool = state.db_pool.as_ref().ok_or_else(|| {
-        UaipError::InternalError("Database not configured".to_string())
-    })?;
+    let db_pool = state.db()?;
 
     // Fetch users directly from users table
     let users = sqlx::query_as::<_, UserInfo>(
@@ -187,9 +197,7 @@ pub async fn delete_user(
     State(state): State<Arc<AppState>>,
     Path(id): Path<Uuid>,
 ) -> ApiResult<Json<bool>> {
-    let db_pool = state.db_pool.as_ref().ok_or_else(|| {
-        UaipError::InternalError("Database not configured".to_string())
-    })?;
+    let db_pool = state.db()?;
 
     // Cascade delete handles entity_roles usually, but let's be explicit if FK not set up for that table (it likely is)
     // Actually entity_roles might not have FK constraint to users yet because users is new.
@@ -233,9 +241,7 @@ pub async fn update_user_status(
     Path(id): Path<Uuid>,
     Json(request): Json<UpdateUserStatusRequest>,
 ) -> ApiResult<Json<bool>> {
-    let db_pool = state.db_pool.as_ref().ok_or_else(|| {
-        UaipError::InternalError("Database not configured".to_string())
-    })?;
+    let db_pool = state.db()?;
 
     let result = sqlx::query("UPDATE users SET active = $1 WHERE id = $2")
         .bind(request.active)
@@ -268,9 +274,7 @@ pub async fn update_user(
     Path(id): Path<Uuid>,
     Json(request): Json<UpdateUserRequest>,
 ) -> ApiResult<Json<bool>> {
-    let db_pool = state.db_pool.as_ref().ok_or_else(|| {
-        UaipError::InternalError("Database not configured".to_string())
-    })?;
+    let db_pool = state.db()?;
 
     let mut transaction = db_pool.begin().await.map_err(|e| {
         tracing::error!("Failed to start transaction: {}", e);
@@ -381,9 +385,7 @@ pub async fn admin_reset_password(
     Path(id): Path<Uuid>,
     Json(request): Json<AdminResetPasswordRequest>,
 ) -> ApiResult<Json<bool>> {
-    let db_pool = state.db_pool.as_ref().ok_or_else(|| {
-        UaipError::InternalError("Database not configured".to_string())
-    })?;
+    let db_pool = state.db()?;
 
     // Hash new password
     if request.new_password.len() < 8 {
@@ -3,10 +3,16 @@
 //! Provides comprehensive health status for the UAIP Hub and its dependencies
 
 use axum::{http::StatusCode, Json};
+use futures_util::future::join_all;
 use serde::{Deserialize, Serialize};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
+use uaip_adapters::adapter::SharedAdapter;
+
+use crate::adapter_registry::AdapterRegistry;
+use crate::endpoint_probe::EndpointProber;
+
 /// Overall health status
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
@@ -35,6 +41,22 @@ pub struct HealthCheckResponse {
     pub dependencies: Vec<DependencyHealth>,
 }
 
+/// Protocol adapter health response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdapterHealthResponse {
+    pub status: HealthStatus,
+    pub timestamp: String,
+    pub adapters: Vec<DependencyHealth>,
+}
+
+/// Age of the cached health results, if any are cached
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthCacheStats {
+    pub ttl_ms: u64,
+    pub health_age_ms: Option<u64>,
+    pub adapter_health_age_ms: Option<u64>,
+}
+
 /// Cached health check result
 #[derive(Debug, Clone)]
 struct CachedHealth {
@@ -42,6 +64,13 @@ struct CachedHealth {
     cached_at: Instant,
 }
 
+/// Cached adapter health result
+#[derive(Debug, Clone)]
+struct CachedAdapterHealth {
+    result: AdapterHealthResponse,
+    cached_at: Instant,
+}
+
 /// Health checker service with caching and circuit breaker
 pub struct HealthChecker {
     start_time: Instant,
@@ -50,6 +79,10 @@ pub struct HealthChecker {
     nats_client: Option<async_nats::Client>,
     cache: Arc<Mutex<Option<CachedHealth>>>,
     cache_ttl: Duration,
+    adapters: Vec<(String, SharedAdapter)>,
+    adapter_cache: Arc<Mutex<Option<CachedAdapterHealth>>>,
+    adapter_timeout: Duration,
+    endpoint_probes: Option<EndpointProber>,
 }
 
 impl HealthChecker {
@@ -61,6 +94,10 @@ impl HealthChecker {
             nats_client: None,
             cache: Arc::new(Mutex::new(None)),
             cache_ttl: Duration::from_secs(5), // 5 second cache TTL
+            adapters: Vec::new(),
+            adapter_cache: Arc::new(Mutex::new(None)),
+            adapter_timeout: Duration::from_secs(3),
+            endpoint_probes: None,
         }
     }
 
@@ -79,6 +116,117 @@ impl HealthChecker {
         self
     }
 
+    /// Register a protocol adapter to include in adapter health checks
+    pub fn with_adapter(mut self, name: impl Into<String>, adapter: SharedAdapter) -> Self {
+        self.adapters.push((name.into(), adapter));
+        self
+    }
+
+    /// Register every adapter in the registry for adapter health checks
+    pub fn with_registry(mut self, registry: &AdapterRegistry) -> Self {
+        for registered in registry.iter() {
+            self = self.with_adapter(registered.name.clone(), registered.adapter.clone());
+        }
+        self
+    }
+
+    /// Set how long each adapter health check may take
+    pub fn with_adapter_timeout(mut self, timeout: Duration) -> Self {
+        self.adapter_timeout = timeout;
+        self
+    }
+
+    /// Include the cached reachability of configured adapter endpoints in adapter health
+    pub fn with_endpoint_probes(mut self, prober: EndpointProber) -> Self {
+        self.endpoint_probes = Some(prober);
+        self
+    }
+
+    /// Ages of the cached health results, without running any checks
+    pub fn cache_stats(&self) -> HealthCacheStats {
+        let age_ms = |cached_at: Instant| cached_at.elapsed().as_millis() as u64;
+        HealthCacheStats {
+            ttl_ms: self.cache_ttl.as_millis() as u64,
+            health_age_ms: self
+                .cache
+                .lock()
+                .ok()
+                .and_then(|cache| cache.as_ref().map(|c| age_ms(c.cached_at))),
+            adapter_health_age_ms: self
+                .adapter_cache
+                .lock()
+                .ok()
+                .and_then(|cache| cache.as_ref().map(|c| age_ms(c.cached_at))),
+        }
+    }
+
+    /// Check every registered adapter concurrently, with caching
+    ///
+    /// Configured adapter endpoints report their latest background probe.
+    pub async fn check_adapters(&self) -> AdapterHealthResponse {
+        if let Ok(cache_guard) = self.adapter_cache.lock() {
+            if let Some(cached) = cache_guard.as_ref() {
+                if cached.cached_at.elapsed() < self.cache_ttl {
+                    tracing::debug!("Returning cached adapter health result");
+                    return cached.result.clone();
+                }
+            }
+        }
+
+        let mut adapters = join_all(
+            self.adapters
+                .iter()
+                .map(|(name, adapter)| self.check_adapter(name, adapter)),
+        )
+        .await;
+        if let Some(prober) = &self.endpoint_probes {
+            adapters.extend(prober.statuses());
+        }
+
+        let result = AdapterHealthResponse {
+            status: self.determine_overall_status(&adapters),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            adapters,
+        };
+
+        if let Ok(mut cache_guard) = self.adapter_cache.lock() {
+            *cache_guard = Some(CachedAdapterHealth {
+                result: result.clone(),
+                cached_at: Instant::now(),
+            });
+        }
+
+        result
+    }
+
+    /// Run a single adapter health check with timeout
+    async fn check_adapter(&self, name: &str, adapter: &SharedAdapter) -> DependencyHealth {
+        let start = Instant::now();
+        let check = async { adapter.lock().await.health_check().await };
+
+        let (status, message) = match tokio::time::timeout(self.adapter_timeout, check).await {
+            Ok(Ok(())) => (HealthStatus::Healthy, None),
+            Ok(Err(e)) => (
+                HealthStatus::Unhealthy,
+                Some(format!("Adapter check failed: {}", e)),
+            ),
+            Err(_) => (
+                HealthStatus::Unhealthy,
+                Some(format!(
+                    "Adapter check timeout (>{}ms)",
+                    self.adapter_timeout.as_millis()
+                )),
+            ),
+        };
+
+        DependencyHealth {
+            name: name.to_string(),
+            status,
+            response_time_ms: Some(start.elapsed().as_secs_f64() * 1000.0),
+            message,
+        }
+    }
+
     /// Perform complete health check with caching
     pub async fn check_health(&self) -> HealthCheckResponse {
         // Check if we have a valid cached result
@@ -309,6 +457,20 @@ pub async fn health_check_handler(
     (status_code, Json(health))
 }
 
+/// Adapter health check handler
+pub async fn adapter_health_handler(
+    checker: &HealthChecker,
+) -> (StatusCode, Json<AdapterHealthResponse>) {
+    let health = checker.check_adapters().await;
+
+    let status_code = match health.status {
+        HealthStatus::Healthy | HealthStatus::Degraded => StatusCode::OK,
+        HealthStatus::Unhealthy => StatusCode::SERVICE_UNAVAILABLE,
+    };
+
+    (status_code, Json(health))
+}
+
 /// Liveness probe - simple check that service is running
 pub async fn liveness_probe() -> StatusCode {
     StatusCode::OK
@@ -336,6 +498,8 @@ mod tests {
         assert!(!health.version.is_empty());
         assert!(!health.timestamp.is_empty());
         assert!(!health.dependencies.is_empty());
+        assert!(checker.cache_stats().health_age_ms.is_some());
+        assert!(checker.cache_stats().adapter_health_age_ms.is_none());
     }
 
     #[tokio::test]
@@ -395,6 +559,94 @@ mod tests {
         );
     }
 
+    use uaip_adapters::adapter::{AdapterOp, AdapterResult, ProtocolAdapter};
+    use uaip_core::error::{Result, UaipError};
+
+    struct FakeAdapter {
+        result: std::result::Result<(), String>,
+        delay: Duration,
+        calls: usize,
+    }
+
+    impl FakeAdapter {
+        fn new(
+            result: std::result::Result<(), String>,
+            delay: Duration,
+        ) -> Arc<tokio::sync::Mutex<Self>> {
+            Arc::new(tokio::sync::Mutex::new(Self {
+                result,
+                delay,
+                calls: 0,
+            }))
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl ProtocolAdapter for FakeAdapter {
+        fn adapter_type(&self) -> &'static str {
+            "fake"
+        }
+
+        async fn health_check(&mut self) -> Result<()> {
+            self.calls += 1;
+            tokio::time::sleep(self.delay).await;
+            self.result.clone().map_err(UaipError::ConnectionError)
+        }
+
+        async fn execute(&mut self, _op: AdapterOp) -> Result<AdapterResult> {
+            Ok(AdapterResult::Written)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failing_adapter_reported_unhealthy() {
+        let plc = FakeAdapter::new(Err("connection refused".to_string()), Duration::ZERO);
+        let checker = HealthChecker::new()
+            .with_adapter("http-gateway", FakeAdapter::new(Ok(()), Duration::ZERO))
+            .with_adapter("plc-1", plc.clone())
+            .with_adapter("opcua-1", FakeAdapter::new(Ok(()), Duration::from_secs(5)))
+            .with_adapter_timeout(Duration::from_millis(50));
+
+        let (status, Json(health)) = adapter_health_handler(&checker).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(health.status, HealthStatus::Unhealthy);
+
+        let statuses: Vec<(&str, &HealthStatus)> = health
+            .adapters
+            .iter()
+            .map(|a| (a.name.as_str(), &a.status))
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                ("http-gateway", &HealthStatus::Healthy),
+                ("plc-1", &HealthStatus::Unhealthy),
+                ("opcua-1", &HealthStatus::Unhealthy),
+            ]
+        );
+        assert!(health.adapters[1]
+            .message
+            .as_deref()
+            .unwrap()
+            .contains("connection refused"));
+        assert!(health.adapters[2]
+            .message
+            .as_deref()
+            .unwrap()
+            .contains("timeout"));
+
+        // A second request within the cache TTL does not touch the adapters
+        checker.check_adapters().await;
+        assert_eq!(plc.lock().await.calls, 1);
+    }
+
+    #[tokio::test]
+    async fn test_no_adapters_is_healthy() {
+        let (status, Json(health)) = adapter_health_handler(&HealthChecker::new()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(health.adapters.is_empty());
+    }
+
     #[tokio::test]
     async fn test_liveness_probe() {
         let status = liveness_probe().await;
@@ -2,12 +2,34 @@
 //!
 //! Core components for the UAIP Hub service
 
+pub mod adapter_registry;
+pub mod adapter_targets;
 pub mod ai_session_manager;
 pub mod api;
+pub mod api_keys;
+pub mod audit;
+pub mod automation_store;
+pub mod build_info;
+pub mod command_log;
+pub mod command_throttle;
 pub mod config;
+pub mod cookie_sessions;
+pub mod device_types;
+pub mod endpoint_probe;
 pub mod handlers;
 pub mod health;
 pub mod metrics;
 pub mod middleware;
+pub mod pagination;
+pub mod polling;
+pub mod provisioning;
+pub mod query_timing;
+pub mod scenario_history;
+pub mod services;
+pub mod session_store;
 pub mod shutdown;
 pub mod telemetry;
+pub mod telemetry_schema;
+pub mod tenant;
+pub mod timestamp;
+pub mod watchdog;
@@ -3,15 +3,46 @@
 //! The central orchestration service that coordinates all components.
 
 use anyhow::Result;
-use std::net::SocketAddr;
+use std::future::IntoFuture;
 use std::sync::Arc;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+use uaip_orchestrator::automation::AutomationEngine;
+use uaip_orchestrator::rule_engine::RuleEngine;
 
 use uaip_hub::{
-    api::rest::{create_router, AppState},
+    adapter_targets::{default_probe_rate, AdapterProbeGuard, TargetPolicy},
+    ai_session_manager::AiSessionManager,
+    api::{
+        grpc,
+        rest::{create_router, AppState},
+    },
+    api_keys::ApiKeyStore,
+    audit::AuditLog,
+    automation_store::AutomationStore,
+    command_log::CommandLog,
+    command_throttle::{CommandThrottle, ThrottleConfig},
+    config::{
+        bind_addr_from_env, connection_pool_from_env, endpoint_probe_interval_from_env,
+        grpc_bind_addr_from_env, network_config_from_env, pg_pool_options, redis_manager_config,
+        watchdog_interval_from_env, CompressionConfig, CorsConfig, MediaProcessingConfig,
+        MessagingConfig, NotificationConfig, PollingConfig, RetentionConfig,
+    },
+    cookie_sessions::CookieSessions,
+    device_types::DeviceTypes,
+    endpoint_probe::EndpointProber,
+    handlers::{executions, groups::load_device_groups},
     health::HealthChecker,
-    middleware::RateLimitLayer,
-    shutdown::shutdown_signal,
+    polling::PollingScheduler,
+    provisioning::ProvisioningTokenStore,
+    query_timing::QueryTimer,
+    scenario_history::ExecutionStore,
+    session_store::{ContextLimits, SessionStore},
+    shutdown::{
+        track_in_flight, InFlightRequests, ShutdownConfig, ShutdownHandler, ShutdownOutcome,
+    },
+    telemetry::TelemetryRetention,
+    telemetry_schema::TelemetrySchemas,
+    watchdog::AbsenceWatchdog,
 };
 
 #[tokio::main]
@@ -36,25 +67,30 @@ async fn main() -> Result<()> {
 
     tracing::info!(version = env!("CARGO_PKG_VERSION"), "UAIP Hub starting");
 
+    // Load connection pool settings
+    let pool_config = connection_pool_from_env()?;
+    tracing::info!(
+        max_connections = pool_config.max_connections(),
+        idle_timeout_secs = pool_config.idle_timeout_secs,
+        max_lifetime_secs = pool_config.max_lifetime_secs,
+        "Connection pool configured"
+    );
+
     // Initialize database connection (optional)
     let db_pool = match std::env::var("DATABASE_URL") {
         Ok(url) => {
             tracing::info!("Connecting to PostgreSQL database...");
-            match sqlx::postgres::PgPoolOptions::new()
-                .max_connections(5)
-                .connect(&url)
-                .await
-            {
+            match pg_pool_options(&pool_config)?.connect(&url).await {
                 Ok(pool) => {
                     tracing::info!("PostgreSQL connection established");
-                    
+
                     // Run migrations
                     tracing::info!("Running database migrations...");
                     match sqlx::migrate!("../../migrations").run(&pool).await {
                         Ok(_) => tracing::info!("Migrations applied successfully"),
                         Err(e) => tracing::error!("Failed to apply migrations: {}", e),
                     }
-                    
+
                     Some(pool)
                 }
                 Err(e) => {
@@ -77,7 +113,10 @@ async fn main() -> Result<()> {
             match redis::Client::open(url.as_str()) {
                 Ok(client) => {
                     // Test the connection
-                    match client.get_multiplexed_async_connection().await {
+                    match client
+                        .get_connection_manager_with_config(redis_manager_config(&pool_config)?)
+                        .await
+                    {
                         Ok(_) => {
                             tracing::info!("Redis connection established");
                             Some(client)
@@ -124,50 +163,162 @@ async fn main() -> Result<()> {
     };
 
     // Create application state with connections
-    let mut state = AppState::new();
+    let media_config = MediaProcessingConfig::from_env()?;
+    let polling_config = PollingConfig::from_env()?;
+    let messaging = MessagingConfig::from_env()?;
+    let mut ai_sessions =
+        AiSessionManager::default().with_context_limits(ContextLimits::from_env()?);
+    let mut state = AppState::new()
+        .with_qos_handler(Arc::new(messaging.qos_handler()))
+        .with_messaging(messaging)
+        .with_adapters(polling_config.adapter_registry()?)
+        .with_telemetry_schemas(TelemetrySchemas::from_env()?)
+        .with_device_types(DeviceTypes::from_env()?)
+        .with_adapter_probes(AdapterProbeGuard::new(
+            TargetPolicy::from_env()?,
+            default_probe_rate(),
+        ))
+        .with_cors(CorsConfig::from_env()?)
+        .with_compression(CompressionConfig::from_env()?)
+        .with_query_timer(QueryTimer::from_env()?)
+        .with_command_throttle(CommandThrottle::new(ThrottleConfig::from_env()?))
+        .with_thumbnail_generator(media_config.thumbnail_generator())
+        .with_metadata_analyzer(media_config.metadata_analyzer());
+    let notifications = NotificationConfig::from_env()?;
+    let mut automation = AutomationEngine::new(RuleEngine::new(), notifications.scenario_engine()?);
+    tracing::info!(
+        channels = ?automation.scenario_engine.notification_channels(),
+        "Scenario notification channels registered"
+    );
     if let Some(pool) = db_pool.clone() {
-        state = state.with_db(pool);
+        match load_device_groups(&pool).await {
+            Ok(groups) => {
+                tracing::info!("Loaded {} device groups", groups.len());
+                automation = automation.with_groups(groups);
+            }
+            Err(e) => tracing::warn!("Failed to load device groups: {}", e),
+        }
+        let automation_store = AutomationStore::postgres(pool.clone());
+        match automation_store.load_into(&mut automation).await {
+            Ok((rules, scenarios)) => {
+                tracing::info!("Loaded {} rules and {} scenarios", rules, scenarios)
+            }
+            Err(e) => tracing::warn!("Failed to load automation definitions: {}", e),
+        }
+        // Pick up AI agents and the sessions that were active before the restart
+        ai_sessions = ai_sessions.with_store(SessionStore::postgres(pool.clone()));
+        if let Err(e) = ai_sessions.restore_sessions().await {
+            tracing::warn!("Failed to restore AI sessions: {}", e);
+        }
+        state = state
+            .with_automation_store(automation_store)
+            .with_audit_log(AuditLog::postgres(pool.clone()))
+            .with_command_log(CommandLog::postgres(pool.clone()))
+            .with_api_keys(ApiKeyStore::postgres(pool.clone()))
+            .with_provisioning_tokens(ProvisioningTokenStore::postgres(pool.clone()))
+            .with_execution_store(ExecutionStore::postgres(pool.clone()))
+            .with_db(pool);
     }
+    state = state
+        .with_ai_sessions(ai_sessions)
+        .with_automation(automation);
     if let Some(client) = redis_client.clone() {
         state = state.with_redis(client);
     }
     if let Some(client) = nats_client.clone() {
         state = state.with_nats(client);
     }
+    if let Some(sessions) = CookieSessions::from_env()? {
+        tracing::info!("Cookie sessions enabled for dashboards");
+        state = state.with_cookie_sessions(sessions);
+    }
     let state = Arc::new(state);
 
+    // Forward execution state changes to NATS
+    if let Some(client) = state.nats_client.clone() {
+        executions::spawn_nats_forwarder(client, state.execution_feed.subscribe());
+    }
+
     // Create health checker with connections
-    let mut health_checker = HealthChecker::new();
+    let mut health_checker = HealthChecker::new().with_registry(&state.adapters);
     if let Some(pool) = db_pool {
         health_checker = health_checker.with_db(pool);
     }
     if let Some(client) = redis_client {
         health_checker = health_checker.with_redis(client);
     }
+    let endpoint_probes = EndpointProber::from_config(
+        &network_config_from_env()?,
+        endpoint_probe_interval_from_env()?,
+    );
+    if !endpoint_probes.is_empty() {
+        tracing::info!("Probing {} adapter endpoints", endpoint_probes.len());
+        endpoint_probes.clone().spawn();
+        health_checker = health_checker.with_endpoint_probes(endpoint_probes);
+    }
+    let in_flight = InFlightRequests::default();
+    let mut shutdown_handler =
+        ShutdownHandler::new(ShutdownConfig::from_env()?).with_in_flight(in_flight.clone());
     if let Some(client) = nats_client {
-        health_checker = health_checker.with_nats(client);
+        health_checker = health_checker.with_nats(client.clone());
+        shutdown_handler = shutdown_handler.with_nats(client);
     }
     let health_checker = Arc::new(health_checker);
 
-    // Create rate limiter
-    let rate_limiter = RateLimitLayer::new(Default::default());
-
     // Spawn rate limiter cleanup task
-    let cleanup_limiter = rate_limiter.clone();
+    let cleanup_limiter = state.rate_limiter.clone();
+    let cleanup_throttle = state.command_throttle.clone();
+    let cleanup_probes = state.adapter_probes.clone();
+    let cleanup_sessions = state.cookie_sessions.clone();
     tokio::spawn(async move {
         let mut interval = tokio::time::interval(std::time::Duration::from_secs(300)); // 5 minutes
         loop {
             interval.tick().await;
             cleanup_limiter.cleanup_old_buckets().await;
+            cleanup_probes.cleanup().await;
+            if let Some(sessions) = &cleanup_sessions {
+                sessions.cleanup().await;
+            }
+            cleanup_throttle
+                .cleanup_idle(std::time::Duration::from_secs(300))
+                .await;
             tracing::debug!("Rate limiter buckets cleaned up");
         }
     });
 
+    // Spawn telemetry downsampling and retention task
+    if let Some(pool) = state.db_pool.clone() {
+        tokio::spawn(TelemetryRetention::new(pool, RetentionConfig::from_env()?).run());
+        AbsenceWatchdog::new(state.clone(), watchdog_interval_from_env()?).spawn();
+    }
+
+    // Spawn scheduled device polling
+    if !polling_config.polls.is_empty() {
+        tracing::info!("Starting {} scheduled polls", polling_config.polls.len());
+        PollingScheduler::new(state.clone(), polling_config.polls)?.spawn();
+    }
+
+    // Start gRPC server on its own port
+    let grpc_addr = grpc_bind_addr_from_env()?;
+    let grpc_listener = tokio::net::TcpListener::bind(grpc_addr).await?;
+    tracing::info!(address = %grpc_addr, "gRPC server listening");
+    let grpc_state = state.clone();
+    tokio::spawn(async move {
+        if let Err(e) = grpc::serve(grpc_state, grpc_listener).await {
+            tracing::error!("gRPC server failed: {}", e);
+        }
+    });
+
     // Create router with all middleware
-    let app = create_router(state).layer(axum::Extension(health_checker));
+    let app = create_router(state)
+        .layer(axum::Extension(health_checker))
+        .layer(axum::middleware::from_fn_with_state(
+            in_flight,
+            track_in_flight,
+        ));
 
     // Bind to address
-    let addr = SocketAddr::from(([127, 0, 0, 1], 8443));
+    let addr = bind_addr_from_env()?;
     tracing::info!(
         address = %addr,
         "HTTP server listening"
@@ -176,11 +327,21 @@ async fn main() -> Result<()> {
     // Start server with graceful shutdown
     let listener = tokio::net::TcpListener::bind(addr).await?;
 
-    axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown_signal())
-        .await?;
+    let server = axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown_handler.triggered())
+        .into_future();
 
-    tracing::info!("UAIP Hub shut down gracefully");
+    match shutdown_handler.run(server).await? {
+        ShutdownOutcome::Graceful => tracing::info!("UAIP Hub shut down gracefully"),
+        ShutdownOutcome::Forced { abandoned } => {
+            tracing::warn!(
+                abandoned = abandoned.len(),
+                "UAIP Hub shut down before all requests finished"
+            );
+            // Abandoned connection tasks would otherwise keep the runtime alive
+            std::process::exit(1);
+        }
+    }
 
     Ok(())
 }
@@ -5,9 +5,10 @@
 
 use lazy_static::lazy_static;
 use prometheus::{
-    register_counter_vec, register_gauge_vec, register_histogram_vec, CounterVec, Encoder,
-    GaugeVec, HistogramVec, TextEncoder,
+    register_counter_vec, register_gauge, register_gauge_vec, register_histogram_vec, CounterVec,
+    Encoder, Gauge, GaugeVec, HistogramVec, TextEncoder,
 };
+use uaip_router::qos::QosStats;
 
 lazy_static! {
     /// Total number of HTTP requests
@@ -135,6 +136,28 @@ lazy_static! {
     )
     .unwrap();
 
+    /// QoS message deliveries by level and outcome
+    pub static ref QOS_MESSAGES: GaugeVec = register_gauge_vec!(
+        "uaip_qos_messages",
+        "QoS message deliveries by level and outcome",
+        &["level", "outcome"]
+    )
+    .unwrap();
+
+    /// QoS delivery retries
+    pub static ref QOS_RETRIES: Gauge = register_gauge!(
+        "uaip_qos_retries",
+        "Number of QoS delivery retries"
+    )
+    .unwrap();
+
+    /// QoS deliveries that exhausted their retries
+    pub static ref QOS_FAILURES: Gauge = register_gauge!(
+        "uaip_qos_failures",
+        "Number of QoS deliveries that exhausted their retries"
+    )
+    .unwrap();
+
     /// System resource usage
     pub static ref SYSTEM_RESOURCES: GaugeVec = register_gauge_vec!(
         "uaip_system_resources",
@@ -256,6 +279,23 @@ impl Metrics {
         SYSTEM_RESOURCES.with_label_values(&[resource]).set(value);
     }
 
+    /// Mirror the QoS handler statistics
+    pub fn update_qos_stats(stats: &QosStats) {
+        for (level, outcome, value) in [
+            ("0", "sent", stats.qos0_sent),
+            ("1", "sent", stats.qos1_sent),
+            ("1", "acked", stats.qos1_acked),
+            ("2", "sent", stats.qos2_sent),
+            ("2", "completed", stats.qos2_completed),
+        ] {
+            QOS_MESSAGES
+                .with_label_values(&[level, outcome])
+                .set(value as f64);
+        }
+        QOS_RETRIES.set(stats.retries as f64);
+        QOS_FAILURES.set(stats.failures as f64);
+    }
+
     /// Gather all metrics and encode as Prometheus text format
     pub fn gather_metrics() -> Result<String, String> {
         let encoder = TextEncoder::new();
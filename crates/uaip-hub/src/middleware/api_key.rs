@@ -0,0 +1,179 @@
+//! API key authentication middleware
+//!
+//! Resolves `Authorization: ApiKey <key>` (or `X-API-Key: <key>`) to the key's subject,
+//! scopes and tenant. The key is exchanged for a short-lived bearer token, so handlers
+//! authorize API-key requests exactly like requests from an interactive login.
+
+use axum::{
+    extract::{Request, State},
+    http::{header::AUTHORIZATION, HeaderMap, HeaderValue},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::sync::Arc;
+use tracing::warn;
+use uuid::Uuid;
+
+use uaip_core::error::UaipError;
+
+use crate::api::rest::{ApiError, AppState};
+use crate::api_keys::ApiKey;
+use crate::handlers::auth::jwt_manager;
+
+/// Header carrying an API key, as an alternative to `Authorization: ApiKey <key>`
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Lifetime of the bearer token a key is exchanged for, in seconds
+const EXCHANGED_TOKEN_LIFETIME_SECS: i64 = 60;
+
+/// Identity of a request authenticated by API key, added to the request extensions
+#[derive(Debug, Clone)]
+pub struct ApiKeyIdentity {
+    pub key_id: Uuid,
+    pub subject: String,
+}
+
+/// API key presented in the request headers, if any
+pub fn presented_api_key(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("ApiKey "))
+        .or_else(|| headers.get(API_KEY_HEADER).and_then(|v| v.to_str().ok()))
+        .map(str::trim)
+}
+
+/// Authenticate requests that present an API key; other requests pass through unchanged
+pub async fn api_key_middleware(
+    State(state): State<Arc<AppState>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let Some(key) = presented_api_key(request.headers()) else {
+        return next.run(request).await;
+    };
+
+    let api_key = match state.api_keys.authenticate(key).await {
+        Ok(api_key) => api_key,
+        Err(e) => {
+            warn!(path = %request.uri().path(), "API key rejected: {}", e);
+            return ApiError(e).into_response();
+        }
+    };
+
+    let authorization = match exchange_token(&api_key) {
+        Ok(authorization) => authorization,
+        Err(e) => return ApiError(e).into_response(),
+    };
+
+    let headers = request.headers_mut();
+    headers.remove(API_KEY_HEADER);
+    headers.insert(AUTHORIZATION, authorization);
+    request.extensions_mut().insert(ApiKeyIdentity {
+        key_id: api_key.id,
+        subject: api_key.subject,
+    });
+
+    next.run(request).await
+}
+
+/// Bearer authorization header carrying the key's subject, scopes and tenant
+fn exchange_token(api_key: &ApiKey) -> Result<HeaderValue, UaipError> {
+    let token = jwt_manager(EXCHANGED_TOKEN_LIFETIME_SECS).generate_tenant_token(
+        &api_key.subject,
+        &format!("api-key:{}", api_key.id),
+        api_key.scopes.clone(),
+        None,
+        Some(api_key.tenant_id.clone()),
+    )?;
+
+    HeaderValue::from_str(&format!("Bearer {}", token))
+        .map_err(|_| UaipError::InternalError("Failed to exchange API key".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::rest::create_router;
+    use crate::api_keys::NewApiKey;
+    use axum::{body::Body, http::StatusCode};
+    use chrono::{Duration, Utc};
+
+    async fn get_audit_log(state: Arc<AppState>, key: &str) -> StatusCode {
+        use tower::Service;
+
+        let request = axum::http::Request::builder()
+            .uri("/api/v1/audit")
+            .header(AUTHORIZATION, format!("ApiKey {}", key))
+            .body(Body::empty())
+            .unwrap();
+
+        let mut router = create_router(state).into_service::<Body>();
+        std::future::poll_fn(|cx| router.poll_ready(cx))
+            .await
+            .unwrap();
+        router.call(request).await.unwrap().status()
+    }
+
+    async fn issue(state: &AppState, expires_at: Option<chrono::DateTime<Utc>>) -> String {
+        state
+            .api_keys
+            .issue(NewApiKey {
+                name: "auditor".to_string(),
+                subject: "audit-exporter".to_string(),
+                scopes: vec!["admin".to_string()],
+                tenant_id: "default".to_string(),
+                expires_at,
+            })
+            .await
+            .unwrap()
+            .key
+    }
+
+    #[tokio::test]
+    async fn test_api_key_authorizes_protected_route() {
+        let state = Arc::new(AppState::new());
+        let key = issue(&state, None).await;
+
+        assert_eq!(get_audit_log(state.clone(), &key).await, StatusCode::OK);
+        assert_eq!(
+            get_audit_log(state, "uaip_unknown").await,
+            StatusCode::UNAUTHORIZED
+        );
+    }
+
+    #[tokio::test]
+    async fn test_revoked_and_expired_keys_are_rejected() {
+        let state = Arc::new(AppState::new());
+        let key = issue(&state, None).await;
+        let id = state.api_keys.list("default").await.unwrap()[0].id;
+        state.api_keys.revoke("default", id).await.unwrap();
+        assert_eq!(
+            get_audit_log(state.clone(), &key).await,
+            StatusCode::UNAUTHORIZED
+        );
+
+        let expired = issue(&state, Some(Utc::now() - Duration::minutes(1))).await;
+        assert_eq!(
+            get_audit_log(state, &expired).await,
+            StatusCode::UNAUTHORIZED
+        );
+    }
+
+    #[test]
+    fn test_key_accepted_from_either_header() {
+        let mut headers = HeaderMap::new();
+        assert!(presented_api_key(&headers).is_none());
+
+        headers.insert(API_KEY_HEADER, "uaip_abc".parse().unwrap());
+        assert_eq!(presented_api_key(&headers), Some("uaip_abc"));
+
+        headers.insert(AUTHORIZATION, "ApiKey uaip_def".parse().unwrap());
+        assert_eq!(presented_api_key(&headers), Some("uaip_def"));
+
+        // Bearer tokens are left to the JWT checks
+        headers.remove(API_KEY_HEADER);
+        headers.insert(AUTHORIZATION, "Bearer token".parse().unwrap());
+        assert!(presented_api_key(&headers).is_none());
+    }
+}
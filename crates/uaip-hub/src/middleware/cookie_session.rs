@@ -0,0 +1,101 @@
+//! Cookie session authentication middleware
+//!
+//! Requests without an `Authorization` header that carry a dashboard session cookie act
+//! as the session's subject; state-changing ones must also echo the session's CSRF token.
+//! Like API keys, the session is exchanged for a short-lived bearer token so handlers
+//! authorize cookie requests exactly like bearer requests.
+
+use axum::{
+    extract::{Request, State},
+    http::{header::AUTHORIZATION, HeaderValue, Method},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::sync::Arc;
+use tracing::warn;
+
+use uaip_core::error::UaipError;
+
+use crate::api::rest::{ApiError, AppState};
+use crate::cookie_sessions::{session_cookie, CookieSession, CSRF_HEADER};
+use crate::handlers::auth::jwt_manager;
+
+/// Session login/logout path; a stale cookie does not block signing in again
+pub const SESSION_PATH: &str = "/api/v1/auth/session";
+
+/// Lifetime of the bearer token a session is exchanged for, in seconds
+const EXCHANGED_TOKEN_LIFETIME_SECS: i64 = 60;
+
+/// Identity of a request authenticated by session cookie, added to the request extensions
+#[derive(Debug, Clone)]
+pub struct SessionIdentity {
+    pub session_id: String,
+    pub subject: String,
+}
+
+/// Authenticate requests that present a session cookie; other requests pass through
+pub async fn cookie_session_middleware(
+    State(state): State<Arc<AppState>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let Some(sessions) = &state.cookie_sessions else {
+        return next.run(request).await;
+    };
+    // Bearer tokens and API keys take precedence over the cookie
+    if request.headers().contains_key(AUTHORIZATION) {
+        return next.run(request).await;
+    }
+    let Some(cookie) = session_cookie(request.headers()) else {
+        return next.run(request).await;
+    };
+
+    let session = match sessions.authenticate(cookie).await {
+        Ok(session) => session,
+        Err(_) if request.uri().path() == SESSION_PATH => return next.run(request).await,
+        Err(e) => {
+            warn!(path = %request.uri().path(), "Session cookie rejected: {}", e);
+            return ApiError(e).into_response();
+        }
+    };
+
+    if !matches!(
+        *request.method(),
+        Method::GET | Method::HEAD | Method::OPTIONS
+    ) {
+        let token = request
+            .headers()
+            .get(CSRF_HEADER)
+            .and_then(|v| v.to_str().ok());
+        if let Err(e) = sessions.check_csrf(&session, token) {
+            warn!(path = %request.uri().path(), "CSRF check failed: {}", e);
+            return ApiError(e).into_response();
+        }
+    }
+
+    let authorization = match exchange_token(&session) {
+        Ok(authorization) => authorization,
+        Err(e) => return ApiError(e).into_response(),
+    };
+    request.headers_mut().insert(AUTHORIZATION, authorization);
+    request.extensions_mut().insert(SessionIdentity {
+        session_id: session.id,
+        subject: session.subject,
+    });
+
+    next.run(request).await
+}
+
+/// Bearer authorization header carrying the session's subject, scopes and tenant
+fn exchange_token(session: &CookieSession) -> Result<HeaderValue, UaipError> {
+    let token = jwt_manager(EXCHANGED_TOKEN_LIFETIME_SECS).generate_tenant_token(
+        &session.subject,
+        &session.client_id,
+        session.scopes.clone(),
+        Some(session.id.clone()),
+        session.tenant_id.clone(),
+    )?;
+
+    HeaderValue::from_str(&format!("Bearer {}", token))
+        .map_err(|_| UaipError::InternalError("Failed to exchange session".to_string()))
+}
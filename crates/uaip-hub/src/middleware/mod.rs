@@ -1,7 +1,11 @@
 //! Middleware modules for request processing
 
+pub mod api_key;
+pub mod cookie_session;
 pub mod logging;
 pub mod rate_limit;
 
+pub use api_key::api_key_middleware;
+pub use cookie_session::cookie_session_middleware;
 pub use logging::logging_middleware;
 pub use rate_limit::RateLimitLayer;
@@ -3,19 +3,17 @@
 //! Implements token bucket algorithm for rate limiting
 
 use axum::{
-    extract::Request,
+    extract::{Request, State},
     http::StatusCode,
     middleware::Next,
     response::{IntoResponse, Response},
 };
-use std::{
-    collections::HashMap,
-    sync::Arc,
-    time::{Duration, Instant},
-};
-use tokio::sync::RwLock;
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use tokio::{sync::RwLock, time::Instant};
 use tracing::warn;
 
+use super::api_key::ApiKeyIdentity;
+
 /// Rate limiter configuration
 #[derive(Debug, Clone)]
 pub struct RateLimitConfig {
@@ -39,7 +37,7 @@ impl Default for RateLimitConfig {
 
 /// Token bucket for rate limiting
 #[derive(Debug)]
-struct TokenBucket {
+pub(crate) struct TokenBucket {
     tokens: f64,
     last_refill: Instant,
     max_tokens: f64,
@@ -47,7 +45,7 @@ struct TokenBucket {
 }
 
 impl TokenBucket {
-    fn new(max_tokens: f64, refill_rate: f64) -> Self {
+    pub(crate) fn new(max_tokens: f64, refill_rate: f64) -> Self {
         Self {
             tokens: max_tokens,
             last_refill: Instant::now(),
@@ -56,7 +54,7 @@ impl TokenBucket {
         }
     }
 
-    fn try_consume(&mut self, tokens: f64) -> bool {
+    pub(crate) fn try_consume(&mut self, tokens: f64) -> bool {
         self.refill();
         if self.tokens >= tokens {
             self.tokens -= tokens;
@@ -66,6 +64,32 @@ impl TokenBucket {
         }
     }
 
+    /// Take tokens even if the bucket runs dry, returning how long until they are covered
+    pub(crate) fn reserve(&mut self, tokens: f64) -> Duration {
+        self.refill();
+        self.tokens -= tokens;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.refill_rate)
+        }
+    }
+
+    /// Time until the given number of tokens is available
+    pub(crate) fn time_until(&mut self, tokens: f64) -> Duration {
+        self.refill();
+        if self.tokens >= tokens {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64((tokens - self.tokens) / self.refill_rate)
+        }
+    }
+
+    /// Time since the bucket was last touched
+    pub(crate) fn idle_for(&self) -> Duration {
+        self.last_refill.elapsed()
+    }
+
     fn refill(&mut self) {
         let now = Instant::now();
         let elapsed = now.duration_since(self.last_refill).as_secs_f64();
@@ -112,24 +136,35 @@ impl RateLimitLayer {
     }
 }
 
+/// Bucket key for a request: its API key when authenticated with one, else the client IP
+///
+/// Requests must pass through the API key middleware first for keys to be recognized.
+pub fn rate_limit_key(request: &Request) -> String {
+    if let Some(identity) = request.extensions().get::<ApiKeyIdentity>() {
+        return format!("api-key:{}", identity.key_id);
+    }
+
+    request
+        .headers()
+        .get("x-forwarded-for")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("unknown")
+        .to_string()
+}
+
 /// Rate limiting middleware
 pub async fn rate_limit_middleware(
+    State(limiter): State<RateLimitLayer>,
     request: Request,
     next: Next,
-    limiter: RateLimitLayer,
 ) -> Response {
-    // Extract client identifier (IP address or user ID)
-    let client_ip = request
-        .headers()
-        .get("x-forwarded-for")
-        .and_then(|v| v.to_str().ok())
-        .unwrap_or("unknown")
-        .to_string();
+    // Extract client identifier (API key or IP address)
+    let client_key = rate_limit_key(&request);
 
     // Check rate limit
-    if !limiter.check_rate_limit(&client_ip).await {
+    if !limiter.check_rate_limit(&client_key).await {
         warn!(
-            client_ip = %client_ip,
+            client_key = %client_key,
             "Rate limit exceeded"
         );
 
@@ -199,6 +234,30 @@ mod tests {
         assert!(limiter.check_rate_limit("test_ip").await);
     }
 
+    #[test]
+    fn test_api_key_requests_share_a_bucket_per_key() {
+        let request = |key_id: Option<uuid::Uuid>| {
+            let mut request = Request::builder()
+                .header("x-forwarded-for", "10.0.0.7")
+                .body(axum::body::Body::empty())
+                .unwrap();
+            if let Some(key_id) = key_id {
+                request.extensions_mut().insert(ApiKeyIdentity {
+                    key_id,
+                    subject: "gateway-3".to_string(),
+                });
+            }
+            request
+        };
+
+        let key_id = uuid::Uuid::new_v4();
+        assert_eq!(rate_limit_key(&request(None)), "10.0.0.7");
+        assert_eq!(
+            rate_limit_key(&request(Some(key_id))),
+            format!("api-key:{}", key_id)
+        );
+    }
+
     #[tokio::test]
     async fn test_cleanup_old_buckets() {
         let config = RateLimitConfig::default();
@@ -212,4 +271,49 @@ mod tests {
         limiter.cleanup_old_buckets().await;
         assert_eq!(limiter.buckets.read().await.len(), 2);
     }
+
+    #[tokio::test]
+    async fn test_router_limits_each_api_key_separately() {
+        use crate::api::rest::{create_router, AppState};
+        use crate::api_keys::NewApiKey;
+        use axum::{body::Body, http::StatusCode};
+        use tower::Service;
+
+        let state = Arc::new(AppState::new().with_rate_limiter(RateLimitLayer::new(
+            RateLimitConfig {
+                max_requests: 1,
+                window_duration: Duration::from_secs(3600),
+                burst_size: 2,
+            },
+        )));
+        let issue = |name: &str| {
+            state.api_keys.issue(NewApiKey {
+                name: name.to_string(),
+                subject: name.to_string(),
+                scopes: vec!["admin".to_string()],
+                tenant_id: "default".to_string(),
+                expires_at: None,
+            })
+        };
+        let first = issue("exporter-1").await.unwrap().key;
+        let second = issue("exporter-2").await.unwrap().key;
+
+        let mut router = create_router(state).into_service::<Body>();
+        let mut get = |key: &str| {
+            let request = axum::http::Request::builder()
+                .uri("/api/v1/audit")
+                .header("x-forwarded-for", "10.0.0.7")
+                .header("authorization", format!("ApiKey {}", key))
+                .body(Body::empty())
+                .unwrap();
+            let response = router.call(request);
+            async move { response.await.unwrap().status() }
+        };
+
+        // Both keys come from one address, yet each has its own budget
+        assert_eq!(get(&first).await, StatusCode::OK);
+        assert_eq!(get(&first).await, StatusCode::OK);
+        assert_eq!(get(&first).await, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(get(&second).await, StatusCode::OK);
+    }
 }
@@ -0,0 +1,102 @@
+//! List Pagination
+//!
+//! Paged list endpoints take a 1-indexed `page` and a `per_page` and answer with a
+//! [`Paginated`] envelope carrying the total across all pages.
+
+use serde::Serialize;
+
+use uaip_core::error::{Result, UaipError};
+
+/// Largest page a list endpoint returns
+pub const MAX_PER_PAGE: i64 = 100;
+
+pub fn default_page() -> i64 {
+    1
+}
+
+pub fn default_per_page() -> i64 {
+    50
+}
+
+/// Check 1-indexed page parameters
+pub fn validate_page(page: i64, per_page: i64) -> Result<()> {
+    if page < 1 {
+        return Err(UaipError::InvalidParameter("page must be >= 1".to_string()));
+    }
+    if !(1..=MAX_PER_PAGE).contains(&per_page) {
+        return Err(UaipError::InvalidParameter(format!(
+            "per_page must be between 1 and {}",
+            MAX_PER_PAGE
+        )));
+    }
+    Ok(())
+}
+
+/// Items before page `page`
+pub fn page_offset(page: i64, per_page: i64) -> i64 {
+    (page - 1).saturating_mul(per_page)
+}
+
+/// One page of a list endpoint's results
+#[derive(Debug, Serialize)]
+pub struct Paginated<T> {
+    pub items: Vec<T>,
+    /// Matching items across all pages
+    pub total: usize,
+    /// Page number (1-indexed)
+    pub page: i64,
+    pub per_page: i64,
+    /// Whether later pages hold more items
+    pub has_more: bool,
+}
+
+impl<T> Paginated<T> {
+    /// Wrap page `page` of `total` matching items
+    pub fn new(items: Vec<T>, total: usize, page: i64, per_page: i64) -> Self {
+        let shown = page.saturating_mul(per_page);
+        Self {
+            items,
+            total,
+            page,
+            per_page,
+            has_more: usize::try_from(shown).is_ok_and(|shown| shown < total),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pagination_reports_more_pages() {
+        // 5 items, 2 per page
+        let pages: Vec<Paginated<usize>> = [vec![0, 1], vec![2, 3], vec![4], vec![]]
+            .into_iter()
+            .zip(1..)
+            .map(|(items, page)| Paginated::new(items, 5, page, 2))
+            .collect();
+        let has_more: Vec<bool> = pages.iter().map(|page| page.has_more).collect();
+        assert_eq!(has_more, [true, true, false, false]);
+        assert!(pages.iter().all(|page| page.total == 5));
+
+        let json = serde_json::to_value(&pages[0]).unwrap();
+        assert_eq!(json["items"], serde_json::json!([0, 1]));
+        assert_eq!(json["page"], 1);
+        assert_eq!(json["per_page"], 2);
+
+        // A page that exactly ends the list has nothing after it
+        assert!(!Paginated::new(vec![0, 1], 2, 1, 2).has_more);
+    }
+
+    #[test]
+    fn test_page_parameters_are_bounded() {
+        assert!(validate_page(0, 50).is_err());
+        assert!(validate_page(1, 0).is_err());
+        assert!(validate_page(1, MAX_PER_PAGE + 1).is_err());
+        assert!(validate_page(i64::MAX, MAX_PER_PAGE).is_ok());
+
+        assert_eq!(page_offset(3, 20), 40);
+        assert_eq!(page_offset(i64::MAX, MAX_PER_PAGE), i64::MAX);
+    }
+}
@@ -0,0 +1,484 @@
+//! Scheduled Device Polling
+//!
+//! Periodically reads configured points through Modbus and OPC UA adapters and records
+//! the values as telemetry for a device, so polled devices feed rule evaluation the same
+//! way devices that push telemetry do.
+
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio::time::{Instant, MissedTickBehavior};
+
+use uaip_adapters::adapter::{AdapterOp, AdapterResult, AdapterValue, SharedAdapter, Target};
+use uaip_core::error::{Result, UaipError};
+
+use crate::api::rest::AppState;
+use crate::handlers::telemetry::TelemetryReading;
+use crate::services::telemetry::record_readings;
+use crate::tenant::Tenant;
+
+/// Longest a deadband-filtered point goes without a recorded sample, by default
+pub const DEFAULT_KEEPALIVE: Duration = Duration::from_secs(300);
+
+/// A value read on every poll
+#[derive(Debug, Clone, Deserialize)]
+pub struct PollPoint {
+    /// Metric the value is recorded as
+    pub metric: String,
+    /// What to read, in the adapter's native terms
+    pub target: Target,
+    #[serde(default)]
+    pub unit: Option<String>,
+    /// Smallest change from the last recorded value that is recorded again
+    #[serde(default)]
+    pub deadband: Option<Deadband>,
+    /// Record a sample at least this often even when the value stays within the
+    /// deadband, in milliseconds
+    #[serde(default)]
+    pub keepalive_ms: Option<u64>,
+}
+
+impl PollPoint {
+    fn keepalive(&self) -> Duration {
+        self.keepalive_ms
+            .map_or(DEFAULT_KEEPALIVE, Duration::from_millis)
+    }
+}
+
+/// Change threshold for a polled value, e.g. `{"absolute": 0.5}` or `{"percent": 2}`
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Deadband {
+    /// Change in the value's own units
+    Absolute(f64),
+    /// Change relative to the last recorded value
+    Percent(f64),
+}
+
+impl Deadband {
+    /// Whether moving from `last` to `value` goes beyond the deadband
+    pub fn exceeded(&self, last: f64, value: f64) -> bool {
+        let change = (value - last).abs();
+        match *self {
+            Self::Absolute(band) => change > band,
+            Self::Percent(percent) => change > last.abs() * percent / 100.0,
+        }
+    }
+
+    fn is_valid(&self) -> bool {
+        match *self {
+            Self::Absolute(band) | Self::Percent(band) => band.is_finite() && band >= 0.0,
+        }
+    }
+}
+
+/// Last recorded sample of a point
+#[derive(Debug, Clone)]
+struct RecordedSample {
+    value: serde_json::Value,
+    at: Instant,
+}
+
+/// Drops polled readings that stay within their point's deadband
+///
+/// Values are compared with the last recorded value, not the last polled one, so slow
+/// drift is still recorded once it adds up to more than the deadband.
+#[derive(Debug, Default)]
+pub struct DeadbandFilter {
+    recorded: HashMap<String, RecordedSample>,
+}
+
+impl DeadbandFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep the readings that should be recorded and remember them as the last recorded
+    pub fn filter(
+        &mut self,
+        poll: &PollConfig,
+        readings: Vec<TelemetryReading>,
+        now: Instant,
+    ) -> Vec<TelemetryReading> {
+        readings
+            .into_iter()
+            .filter(|reading| {
+                let Some(point) = poll.points.iter().find(|p| p.metric == reading.metric) else {
+                    return true;
+                };
+                let record = match self.recorded.get(&reading.metric) {
+                    None => true,
+                    Some(last) if now.duration_since(last.at) >= point.keepalive() => true,
+                    Some(last) => match (point.deadband, reading.value.as_f64()) {
+                        (None, _) => true,
+                        (Some(deadband), Some(value)) => last
+                            .value
+                            .as_f64()
+                            .is_none_or(|last| deadband.exceeded(last, value)),
+                        // Non-numeric values are recorded whenever they change
+                        (Some(_), None) => last.value != reading.value,
+                    },
+                };
+                if record {
+                    self.recorded.insert(
+                        reading.metric.clone(),
+                        RecordedSample {
+                            value: reading.value.clone(),
+                            at: now,
+                        },
+                    );
+                }
+                record
+            })
+            .collect()
+    }
+}
+
+/// Points read through one adapter on a fixed interval
+#[derive(Debug, Clone, Deserialize)]
+pub struct PollConfig {
+    /// Name of a registered adapter
+    pub adapter: String,
+    /// Device the readings are recorded for
+    pub device_id: String,
+    /// Tenant that owns the device (the default tenant when unset)
+    #[serde(default)]
+    pub tenant_id: Option<String>,
+    pub points: Vec<PollPoint>,
+    /// Time between polls, in milliseconds
+    pub interval_ms: u64,
+}
+
+/// Runs the configured polls against the hub's adapter registry
+pub struct PollingScheduler {
+    state: Arc<AppState>,
+    polls: Vec<(PollConfig, SharedAdapter)>,
+}
+
+impl PollingScheduler {
+    /// Check each poll and resolve its adapter in the state's registry
+    pub fn new(state: Arc<AppState>, polls: Vec<PollConfig>) -> Result<Self> {
+        let polls = polls
+            .into_iter()
+            .map(|poll| {
+                let adapter = resolve_adapter(&state, &poll)?;
+                Ok((poll, adapter))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self { state, polls })
+    }
+
+    /// Start one task per poll
+    ///
+    /// First polls are spread across each interval so polls sharing an interval do not
+    /// all fire at once.
+    pub fn spawn(self) -> Vec<JoinHandle<()>> {
+        let count = self.polls.len() as u32;
+        self.polls
+            .into_iter()
+            .enumerate()
+            .map(|(index, (poll, adapter))| {
+                let interval = Duration::from_millis(poll.interval_ms);
+                let offset = interval * index as u32 / count;
+                tokio::spawn(run_poll(self.state.clone(), poll, adapter, offset))
+            })
+            .collect()
+    }
+}
+
+/// Check a poll and look up its adapter
+fn resolve_adapter(state: &AppState, poll: &PollConfig) -> Result<SharedAdapter> {
+    let invalid = |reason: &str| {
+        UaipError::InvalidConfiguration(format!(
+            "Poll of {} for {}: {}",
+            poll.adapter, poll.device_id, reason
+        ))
+    };
+    if poll.interval_ms == 0 {
+        return Err(invalid("interval_ms must be greater than 0"));
+    }
+    if poll.points.is_empty() {
+        return Err(invalid("no points configured"));
+    }
+    if poll
+        .points
+        .iter()
+        .any(|point| matches!(point.target, Target::Modbus { count, .. } if count != 1))
+    {
+        return Err(invalid("each Modbus point must read a single value"));
+    }
+    if poll
+        .points
+        .iter()
+        .any(|point| point.deadband.is_some_and(|d| !d.is_valid()) || point.keepalive_ms == Some(0))
+    {
+        return Err(invalid(
+            "deadbands must be non-negative and keepalive_ms greater than 0",
+        ));
+    }
+    state
+        .adapters
+        .get(&poll.adapter)
+        .ok_or_else(|| invalid("adapter is not registered"))
+}
+
+async fn run_poll(
+    state: Arc<AppState>,
+    poll: PollConfig,
+    adapter: SharedAdapter,
+    offset: Duration,
+) {
+    let mut ticks = tokio::time::interval_at(
+        Instant::now() + offset,
+        Duration::from_millis(poll.interval_ms),
+    );
+    ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut deadbands = DeadbandFilter::new();
+    let tenant = poll
+        .tenant_id
+        .as_deref()
+        .map(Tenant::new)
+        .unwrap_or_default();
+
+    loop {
+        let now = ticks.tick().await;
+        let readings = deadbands.filter(&poll, poll_once(&poll, &adapter).await, now);
+        if readings.is_empty() {
+            continue;
+        }
+        match record_readings(&state, &tenant, &poll.device_id, &readings).await {
+            Ok(report) if report.rejected > 0 => tracing::warn!(
+                "Rejected {} polled readings for {}",
+                report.rejected,
+                poll.device_id
+            ),
+            Ok(_) => {}
+            Err(e) => tracing::error!("Failed to record polled readings: {}", e),
+        }
+    }
+}
+
+/// Read every point once; points that fail are logged and skipped
+pub async fn poll_once(poll: &PollConfig, adapter: &SharedAdapter) -> Vec<TelemetryReading> {
+    let mut adapter = adapter.lock().await;
+    let mut readings = Vec::with_capacity(poll.points.len());
+
+    for point in &poll.points {
+        let op = AdapterOp::Read {
+            target: point.target.clone(),
+        };
+        match adapter.execute(op).await.map(reading_value) {
+            Ok(Some(value)) => readings.push(TelemetryReading {
+                metric: point.metric.clone(),
+                value,
+                unit: point.unit.clone(),
+                timestamp: None,
+            }),
+            Ok(None) => tracing::warn!(
+                "Poll of {} returned no single value for {}",
+                poll.adapter,
+                point.metric
+            ),
+            Err(e) => tracing::warn!(
+                "Poll of {} failed for {}: {}",
+                poll.adapter,
+                point.metric,
+                e
+            ),
+        }
+    }
+
+    readings
+}
+
+/// Scalar JSON value of a read, unwrapping single register and coil reads
+fn reading_value(result: AdapterResult) -> Option<serde_json::Value> {
+    match result {
+        AdapterResult::Value(AdapterValue::Registers(values)) if values.len() == 1 => {
+            Some(serde_json::json!(values[0]))
+        }
+        AdapterResult::Value(AdapterValue::Bits(values)) if values.len() == 1 => {
+            Some(serde_json::json!(values[0]))
+        }
+        #[cfg(feature = "opcua")]
+        AdapterResult::Value(AdapterValue::Opc(value)) => Some(value.to_json()),
+        AdapterResult::Value(AdapterValue::Json(value)) => Some(value),
+        #[cfg(feature = "opcua")]
+        AdapterResult::DataValue(data_value) => Some(data_value.value.to_json()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uaip_adapters::adapter::{ModbusTable, ProtocolAdapter};
+
+    use crate::adapter_registry::AdapterRegistry;
+
+    /// Holding register whose value goes up by one on every read
+    struct CountingRegister {
+        value: u16,
+    }
+
+    #[async_trait::async_trait]
+    impl ProtocolAdapter for CountingRegister {
+        fn adapter_type(&self) -> &'static str {
+            "modbus"
+        }
+
+        async fn health_check(&mut self) -> Result<()> {
+            Ok(())
+        }
+
+        async fn execute(&mut self, _op: AdapterOp) -> Result<AdapterResult> {
+            self.value += 1;
+            Ok(AdapterResult::Value(AdapterValue::Registers(vec![
+                self.value,
+            ])))
+        }
+    }
+
+    fn poll(adapter: &str, count: u16) -> PollConfig {
+        PollConfig {
+            adapter: adapter.to_string(),
+            device_id: "boiler-1".to_string(),
+            tenant_id: None,
+            points: vec![PollPoint {
+                metric: "pressure".to_string(),
+                target: Target::Modbus {
+                    table: ModbusTable::HoldingRegister,
+                    address: 0,
+                    count,
+                },
+                unit: Some("kPa".to_string()),
+                deadband: None,
+                keepalive_ms: None,
+            }],
+            interval_ms: 100,
+        }
+    }
+
+    fn state() -> Arc<AppState> {
+        let mut registry = AdapterRegistry::new();
+        registry
+            .register("plc-1", CountingRegister { value: 0 })
+            .unwrap();
+        Arc::new(AppState::new().with_adapters(registry))
+    }
+
+    #[tokio::test]
+    async fn test_poll_produces_telemetry_at_interval() {
+        let state = state();
+        let mut feed = state.telemetry_feed.subscribe();
+        let tasks = PollingScheduler::new(state, vec![poll("plc-1", 1)])
+            .unwrap()
+            .spawn();
+
+        let mut events = Vec::new();
+        for _ in 0..3 {
+            let event = tokio::time::timeout(Duration::from_secs(1), feed.recv())
+                .await
+                .unwrap()
+                .unwrap();
+            events.push(event);
+        }
+        tasks.iter().for_each(JoinHandle::abort);
+
+        let values: Vec<_> = events.iter().map(|e| e.value.clone()).collect();
+        assert_eq!(values, vec![serde_json::json!(1), 2.into(), 3.into()]);
+        assert!(events
+            .iter()
+            .all(|e| e.device_id == "boiler-1" && e.unit.as_deref() == Some("kPa")));
+
+        for pair in events.windows(2) {
+            let gap = (pair[1].recorded_at - pair[0].recorded_at).num_milliseconds();
+            assert!((80..=300).contains(&gap), "poll gap was {}ms", gap);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rejects_invalid_polls() {
+        assert!(PollingScheduler::new(state(), vec![poll("plc-2", 1)]).is_err());
+        assert!(PollingScheduler::new(state(), vec![poll("plc-1", 4)]).is_err());
+        assert!(PollingScheduler::new(
+            state(),
+            vec![PollConfig {
+                interval_ms: 0,
+                ..poll("plc-1", 1)
+            }]
+        )
+        .is_err());
+    }
+
+    fn reading(value: serde_json::Value) -> TelemetryReading {
+        TelemetryReading {
+            metric: "pressure".to_string(),
+            value,
+            unit: None,
+            timestamp: None,
+        }
+    }
+
+    fn deadband_poll(deadband: Deadband) -> PollConfig {
+        let mut poll = poll("plc-1", 1);
+        poll.points[0].deadband = Some(deadband);
+        poll.points[0].keepalive_ms = Some(60_000);
+        poll
+    }
+
+    /// Values recorded when polling `values` one second apart
+    fn recorded(poll: &PollConfig, values: &[f64]) -> Vec<f64> {
+        let mut filter = DeadbandFilter::new();
+        let start = Instant::now();
+        values
+            .iter()
+            .enumerate()
+            .flat_map(|(i, value)| {
+                let now = start + Duration::from_secs(i as u64);
+                filter.filter(poll, vec![reading(serde_json::json!(value))], now)
+            })
+            .map(|reading| reading.value.as_f64().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_absolute_deadband_suppresses_small_changes() {
+        let poll = deadband_poll(Deadband::Absolute(0.5));
+
+        // 10.3 and 10.4 stay within 0.5 of the recorded 10.0; 10.6 crosses it
+        assert_eq!(
+            recorded(&poll, &[10.0, 10.3, 10.4, 10.6, 10.2, 9.9]),
+            vec![10.0, 10.6, 9.9]
+        );
+    }
+
+    #[test]
+    fn test_percent_deadband_is_relative_to_recorded_value() {
+        let poll = deadband_poll(Deadband::Percent(10.0));
+
+        assert_eq!(
+            recorded(&poll, &[200.0, 215.0, 185.0, 221.0, 240.0]),
+            vec![200.0, 221.0]
+        );
+    }
+
+    #[test]
+    fn test_keepalive_records_unchanged_value() {
+        let mut poll = deadband_poll(Deadband::Absolute(1.0));
+        poll.points[0].keepalive_ms = Some(2_500);
+
+        // Polled once a second: the unchanged value is re-recorded after 3 seconds
+        assert_eq!(recorded(&poll, &[5.0; 7]), vec![5.0, 5.0, 5.0]);
+
+        let mut filter = DeadbandFilter::new();
+        let now = Instant::now();
+        let door = |value: &str| vec![reading(serde_json::json!(value))];
+        assert_eq!(filter.filter(&poll, door("open"), now).len(), 1);
+        assert!(filter.filter(&poll, door("open"), now).is_empty());
+        assert_eq!(filter.filter(&poll, door("closed"), now).len(), 1);
+    }
+}
@@ -0,0 +1,331 @@
+//! Device Provisioning Tokens
+//!
+//! Pre-issued, single-use tokens a device presents once to register itself. Each token
+//! is bound to a tenant and a device type and expires; only its SHA-256 hash is stored.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use uaip_core::error::{Result, UaipError};
+
+use crate::api_keys::{generate_key, hash_key};
+
+/// Prefix identifying provisioning tokens
+pub const TOKEN_PREFIX: &str = "uaip_prov_";
+
+/// An issued provisioning token, without the token itself
+#[derive(Debug, Clone, PartialEq, Serialize, sqlx::FromRow)]
+pub struct ProvisioningToken {
+    pub id: Uuid,
+    /// Device type the token may register
+    pub device_type: String,
+    /// Tenant devices registered with the token join
+    pub tenant_id: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub consumed_at: Option<DateTime<Utc>>,
+    /// Device registered with the token
+    pub device_id: Option<String>,
+}
+
+impl ProvisioningToken {
+    /// Check that the token can register a device of `device_type` at `now`
+    pub fn check_usable(&self, device_type: &str, now: DateTime<Utc>) -> Result<()> {
+        if self.consumed_at.is_some() {
+            return Err(UaipError::AuthenticationFailed(
+                "Provisioning token has already been used".to_string(),
+            ));
+        }
+        if self.expires_at <= now {
+            return Err(UaipError::AuthenticationFailed(
+                "Provisioning token has expired".to_string(),
+            ));
+        }
+        if self.device_type != device_type {
+            return Err(UaipError::AuthorizationFailed(format!(
+                "Provisioning token is for device type '{}'",
+                self.device_type
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Definition of a token to issue
+#[derive(Debug, Clone)]
+pub struct NewProvisioningToken {
+    pub device_type: String,
+    pub tenant_id: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// A newly issued token; `token` is not stored and cannot be retrieved again
+#[derive(Debug, Clone, Serialize)]
+pub struct IssuedProvisioningToken {
+    pub token: String,
+    #[serde(flatten)]
+    pub provisioning_token: ProvisioningToken,
+}
+
+/// Backend for provisioning tokens
+#[derive(Clone)]
+pub enum ProvisioningTokenStore {
+    /// In-process store keyed by token hash (used for tests and runs without a database)
+    Memory(Arc<Mutex<HashMap<String, ProvisioningToken>>>),
+
+    /// PostgreSQL-backed store (`provisioning_tokens` table)
+    Postgres(PgPool),
+}
+
+impl ProvisioningTokenStore {
+    /// Create an in-memory store
+    pub fn memory() -> Self {
+        Self::Memory(Arc::new(Mutex::new(HashMap::new())))
+    }
+
+    /// Create a PostgreSQL-backed store
+    pub fn postgres(pool: PgPool) -> Self {
+        Self::Postgres(pool)
+    }
+
+    /// Issue a token, storing only its hash
+    pub async fn issue(&self, new_token: NewProvisioningToken) -> Result<IssuedProvisioningToken> {
+        let token = generate_key(TOKEN_PREFIX)?;
+        let token_hash = hash_key(&token);
+        let provisioning_token = ProvisioningToken {
+            id: Uuid::new_v4(),
+            device_type: new_token.device_type,
+            tenant_id: new_token.tenant_id,
+            created_at: Utc::now(),
+            expires_at: new_token.expires_at,
+            consumed_at: None,
+            device_id: None,
+        };
+
+        match self {
+            Self::Memory(tokens) => {
+                tokens
+                    .lock()
+                    .await
+                    .insert(token_hash, provisioning_token.clone());
+            }
+            Self::Postgres(pool) => {
+                sqlx::query(
+                    "INSERT INTO provisioning_tokens
+                        (id, device_type, tenant_id, token_hash, created_at, expires_at)
+                     VALUES ($1, $2, $3, $4, $5, $6)",
+                )
+                .bind(provisioning_token.id)
+                .bind(&provisioning_token.device_type)
+                .bind(&provisioning_token.tenant_id)
+                .bind(&token_hash)
+                .bind(provisioning_token.created_at)
+                .bind(provisioning_token.expires_at)
+                .execute(pool)
+                .await
+                .map_err(db_error)?;
+            }
+        }
+
+        Ok(IssuedProvisioningToken {
+            token,
+            provisioning_token,
+        })
+    }
+
+    /// Consume a presented token to register `device_id`
+    ///
+    /// Fails for unknown, used and expired tokens and for tokens issued for another
+    /// device type. A token can only be consumed once, even by concurrent callers.
+    pub async fn consume(
+        &self,
+        token: &str,
+        device_type: &str,
+        device_id: &str,
+    ) -> Result<ProvisioningToken> {
+        let token_hash = hash_key(token);
+        let now = Utc::now();
+        let invalid = || UaipError::AuthenticationFailed("Invalid provisioning token".to_string());
+
+        match self {
+            Self::Memory(tokens) => {
+                let mut tokens = tokens.lock().await;
+                let provisioning_token = tokens.get_mut(&token_hash).ok_or_else(invalid)?;
+                provisioning_token.check_usable(device_type, now)?;
+                provisioning_token.consumed_at = Some(now);
+                provisioning_token.device_id = Some(device_id.to_string());
+                Ok(provisioning_token.clone())
+            }
+            Self::Postgres(pool) => {
+                let provisioning_token = sqlx::query_as::<_, ProvisioningToken>(
+                    "SELECT id, device_type, tenant_id, created_at, expires_at, consumed_at,
+                            device_id
+                     FROM provisioning_tokens
+                     WHERE token_hash = $1",
+                )
+                .bind(&token_hash)
+                .fetch_optional(pool)
+                .await
+                .map_err(db_error)?
+                .ok_or_else(invalid)?;
+                provisioning_token.check_usable(device_type, now)?;
+
+                sqlx::query_as::<_, ProvisioningToken>(
+                    "UPDATE provisioning_tokens
+                     SET consumed_at = $2, device_id = $3
+                     WHERE id = $1 AND consumed_at IS NULL
+                     RETURNING id, device_type, tenant_id, created_at, expires_at, consumed_at,
+                               device_id",
+                )
+                .bind(provisioning_token.id)
+                .bind(now)
+                .bind(device_id)
+                .fetch_optional(pool)
+                .await
+                .map_err(db_error)?
+                .ok_or_else(|| {
+                    UaipError::AuthenticationFailed(
+                        "Provisioning token has already been used".to_string(),
+                    )
+                })
+            }
+        }
+    }
+
+    /// Make a consumed token usable again after the registration it was used for failed
+    pub async fn release(&self, id: Uuid) -> Result<()> {
+        match self {
+            Self::Memory(tokens) => {
+                if let Some(token) = tokens.lock().await.values_mut().find(|t| t.id == id) {
+                    token.consumed_at = None;
+                    token.device_id = None;
+                }
+            }
+            Self::Postgres(pool) => {
+                sqlx::query(
+                    "UPDATE provisioning_tokens SET consumed_at = NULL, device_id = NULL
+                     WHERE id = $1",
+                )
+                .bind(id)
+                .execute(pool)
+                .await
+                .map_err(db_error)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Tokens issued for a tenant, newest first
+    pub async fn list(&self, tenant_id: &str) -> Result<Vec<ProvisioningToken>> {
+        match self {
+            Self::Memory(tokens) => {
+                let mut tokens: Vec<ProvisioningToken> = tokens
+                    .lock()
+                    .await
+                    .values()
+                    .filter(|t| t.tenant_id == tenant_id)
+                    .cloned()
+                    .collect();
+                tokens.sort_by_key(|t| std::cmp::Reverse(t.created_at));
+                Ok(tokens)
+            }
+            Self::Postgres(pool) => sqlx::query_as::<_, ProvisioningToken>(
+                "SELECT id, device_type, tenant_id, created_at, expires_at, consumed_at, device_id
+                 FROM provisioning_tokens
+                 WHERE tenant_id = $1
+                 ORDER BY created_at DESC",
+            )
+            .bind(tenant_id)
+            .fetch_all(pool)
+            .await
+            .map_err(db_error),
+        }
+    }
+}
+
+impl Default for ProvisioningTokenStore {
+    fn default() -> Self {
+        Self::memory()
+    }
+}
+
+fn db_error(e: sqlx::Error) -> UaipError {
+    UaipError::DatabaseError(format!("Provisioning token store error: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn new_token(expires_at: DateTime<Utc>) -> NewProvisioningToken {
+        NewProvisioningToken {
+            device_type: "sensor".to_string(),
+            tenant_id: "acme".to_string(),
+            expires_at,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_token_is_single_use() {
+        let store = ProvisioningTokenStore::memory();
+        let issued = store
+            .issue(new_token(Utc::now() + Duration::hours(1)))
+            .await
+            .unwrap();
+        assert!(issued.token.starts_with(TOKEN_PREFIX));
+
+        // A token for sensors cannot register a camera
+        let err = store
+            .consume(&issued.token, "camera", "cam-1")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, UaipError::AuthorizationFailed(_)));
+
+        let consumed = store
+            .consume(&issued.token, "sensor", "probe-1")
+            .await
+            .unwrap();
+        assert_eq!(consumed.tenant_id, "acme");
+        assert_eq!(consumed.device_id.as_deref(), Some("probe-1"));
+
+        let err = store
+            .consume(&issued.token, "sensor", "probe-2")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("already been used"));
+
+        // Released tokens can be presented again
+        store.release(consumed.id).await.unwrap();
+        assert!(store
+            .consume(&issued.token, "sensor", "probe-2")
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_expired_token_fails() {
+        let store = ProvisioningTokenStore::memory();
+        let issued = store
+            .issue(new_token(Utc::now() - Duration::seconds(1)))
+            .await
+            .unwrap();
+
+        let err = store
+            .consume(&issued.token, "sensor", "probe-1")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("expired"));
+        let err = store
+            .consume("uaip_prov_guessed", "sensor", "probe-1")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, UaipError::AuthenticationFailed(_)));
+        assert_eq!(store.list("acme").await.unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,234 @@
+//! Database query timing
+//!
+//! Queries run through a [`QueryTimer`] get a `db.query` span with the query's name, row
+//! count and duration, feed the `uaip_db_query_duration_seconds` histogram, and log a
+//! warning when slower than the configured threshold. Only the static query name is
+//! recorded; SQL text and bound values, which may hold secrets, never reach the logs.
+
+use lazy_static::lazy_static;
+use prometheus::{register_histogram_vec, HistogramVec};
+use sqlx::postgres::PgQueryResult;
+use std::future::Future;
+use std::time::{Duration, Instant};
+use tracing::{field, Instrument};
+
+use uaip_core::error::{Result, UaipError};
+
+/// Default slow-query warning threshold
+pub const DEFAULT_SLOW_QUERY_MS: u64 = 500;
+
+lazy_static! {
+    /// Database query duration in seconds
+    pub static ref DB_QUERY_DURATION: HistogramVec = register_histogram_vec!(
+        "uaip_db_query_duration_seconds",
+        "Database query duration in seconds",
+        &["query"],
+        vec![0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0]
+    )
+    .unwrap();
+}
+
+/// Rows a query returned or affected
+pub trait QueryRows {
+    fn rows(&self) -> u64;
+}
+
+impl<T> QueryRows for Vec<T> {
+    fn rows(&self) -> u64 {
+        self.len() as u64
+    }
+}
+
+impl<T> QueryRows for Option<T> {
+    fn rows(&self) -> u64 {
+        self.is_some() as u64
+    }
+}
+
+impl QueryRows for PgQueryResult {
+    fn rows(&self) -> u64 {
+        self.rows_affected()
+    }
+}
+
+/// A scalar such as `COUNT(*)`, read from one row
+impl QueryRows for i64 {
+    fn rows(&self) -> u64 {
+        1
+    }
+}
+
+/// Records timings of database queries
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueryTimer {
+    slow_threshold: Duration,
+}
+
+impl Default for QueryTimer {
+    fn default() -> Self {
+        Self::new(Duration::from_millis(DEFAULT_SLOW_QUERY_MS))
+    }
+}
+
+impl QueryTimer {
+    /// Warn about queries taking at least `slow_threshold`
+    pub fn new(slow_threshold: Duration) -> Self {
+        Self { slow_threshold }
+    }
+
+    /// Load the slow-query threshold from `DB_SLOW_QUERY_MS`
+    pub fn from_env() -> Result<Self> {
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    /// Load the slow-query threshold from a variable lookup
+    pub fn from_vars(lookup: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        match lookup("DB_SLOW_QUERY_MS") {
+            Some(value) => value
+                .trim()
+                .parse()
+                .map(|ms| Self::new(Duration::from_millis(ms)))
+                .map_err(|_| {
+                    UaipError::InvalidConfiguration(format!("Invalid DB_SLOW_QUERY_MS: {}", value))
+                }),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn slow_threshold(&self) -> Duration {
+        self.slow_threshold
+    }
+
+    /// Run a query named `name`, recording its span, duration and row count
+    pub async fn time<T, E>(
+        &self,
+        name: &'static str,
+        query: impl Future<Output = std::result::Result<T, E>>,
+    ) -> std::result::Result<T, E>
+    where
+        T: QueryRows,
+    {
+        let span = tracing::info_span!(
+            "db.query",
+            query = name,
+            rows = field::Empty,
+            duration_ms = field::Empty
+        );
+        let started = Instant::now();
+        let result = query.instrument(span.clone()).await;
+        let elapsed = started.elapsed();
+
+        DB_QUERY_DURATION
+            .with_label_values(&[name])
+            .observe(elapsed.as_secs_f64());
+        let duration_ms = elapsed.as_millis() as u64;
+        span.record("duration_ms", duration_ms);
+        if let Ok(rows) = &result {
+            span.record("rows", rows.rows());
+        }
+
+        if elapsed >= self.slow_threshold {
+            let _entered = span.enter();
+            tracing::warn!(
+                query = name,
+                duration_ms,
+                threshold_ms = self.slow_threshold.as_millis() as u64,
+                "Slow database query"
+            );
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+    use tracing_subscriber::fmt::format::FmtSpan;
+
+    /// Log output captured from a test subscriber
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Captured {
+        fn subscriber(&self) -> impl tracing::Subscriber {
+            let writer = self.clone();
+            tracing_subscriber::fmt()
+                .with_writer(move || writer.clone())
+                .with_span_events(FmtSpan::CLOSE)
+                .with_ansi(false)
+                .finish()
+        }
+
+        fn output(&self) -> String {
+            String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_query_span_and_metric_recorded() {
+        let logs = Captured::default();
+        let _guard = tracing::subscriber::set_default(logs.subscriber());
+        let observed = || {
+            DB_QUERY_DURATION
+                .with_label_values(&["test.fetch"])
+                .get_sample_count()
+        };
+        let before = observed();
+
+        let timer = QueryTimer::new(Duration::from_secs(60));
+        let rows = timer
+            .time("test.fetch", async { Ok::<_, sqlx::Error>(vec![1, 2, 3]) })
+            .await
+            .unwrap();
+        assert_eq!(rows, [1, 2, 3]);
+        assert_eq!(observed(), before + 1);
+
+        let output = logs.output();
+        assert!(output.contains("db.query"), "{}", output);
+        assert!(output.contains("query=\"test.fetch\""), "{}", output);
+        assert!(output.contains("rows=3"), "{}", output);
+        assert!(!output.contains("WARN"), "{}", output);
+    }
+
+    #[tokio::test]
+    async fn test_slow_query_logs_warning() {
+        let logs = Captured::default();
+        let _guard = tracing::subscriber::set_default(logs.subscriber());
+
+        let timer = QueryTimer::new(Duration::from_millis(10));
+        let slow = async {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            Ok::<_, sqlx::Error>(Some("secret-value"))
+        };
+        timer.time("test.slow", slow).await.unwrap();
+
+        let output = logs.output();
+        assert!(output.contains("WARN"), "{}", output);
+        assert!(output.contains("Slow database query"), "{}", output);
+        assert!(output.contains("threshold_ms=10"), "{}", output);
+        assert!(!output.contains("secret-value"), "{}", output);
+    }
+
+    #[test]
+    fn test_threshold_from_vars() {
+        let timer = QueryTimer::from_vars(|_| Some("250".to_string())).unwrap();
+        assert_eq!(timer.slow_threshold(), Duration::from_millis(250));
+        assert_eq!(
+            QueryTimer::from_vars(|_| None).unwrap(),
+            QueryTimer::default()
+        );
+        assert!(QueryTimer::from_vars(|_| Some("fast".to_string())).is_err());
+    }
+}
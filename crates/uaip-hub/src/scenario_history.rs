@@ -0,0 +1,393 @@
+//! Scenario Execution History
+//!
+//! Persists finished scenario executions so their history survives restarts and can be
+//! filtered and paged through without keeping every execution in the scenario engine.
+
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use sqlx::PgPool;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use uaip_core::error::{Result, UaipError};
+use uaip_orchestrator::scenario::{ScenarioExecution, ScenarioState};
+
+use crate::pagination::{default_page, default_per_page, page_offset, validate_page, Paginated};
+
+/// Filters and pagination for execution history
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExecutionQuery {
+    /// Only executions in this state
+    #[serde(default)]
+    pub state: Option<ScenarioState>,
+
+    /// Only executions started at or after this time
+    #[serde(default, deserialize_with = "crate::timestamp::deserialize_option")]
+    pub since: Option<DateTime<Utc>>,
+
+    /// Only executions started at or before this time
+    #[serde(default, deserialize_with = "crate::timestamp::deserialize_option")]
+    pub until: Option<DateTime<Utc>>,
+
+    /// Page number (1-indexed)
+    #[serde(default = "default_page")]
+    pub page: i64,
+
+    /// Items per page
+    #[serde(default = "default_per_page")]
+    pub per_page: i64,
+}
+
+impl Default for ExecutionQuery {
+    fn default() -> Self {
+        Self {
+            state: None,
+            since: None,
+            until: None,
+            page: default_page(),
+            per_page: default_per_page(),
+        }
+    }
+}
+
+impl ExecutionQuery {
+    fn offset(&self) -> i64 {
+        page_offset(self.page, self.per_page)
+    }
+
+    fn matches(&self, execution: &ScenarioExecution) -> bool {
+        self.state.as_ref().is_none_or(|s| &execution.state == s)
+            && self.since.is_none_or(|s| execution.started_at >= s)
+            && self.until.is_none_or(|u| execution.started_at <= u)
+    }
+}
+
+/// One page of execution history, newest first
+pub type ExecutionPage = Paginated<ScenarioExecution>;
+
+/// Backend for scenario execution history
+#[derive(Clone)]
+pub enum ExecutionStore {
+    /// In-process store (used for tests and runs without a database)
+    Memory(Arc<Mutex<Vec<ScenarioExecution>>>),
+
+    /// PostgreSQL-backed store (`scenario_executions` table)
+    Postgres(PgPool),
+}
+
+impl ExecutionStore {
+    /// Create an in-memory store
+    pub fn memory() -> Self {
+        Self::Memory(Arc::new(Mutex::new(Vec::new())))
+    }
+
+    /// Create a PostgreSQL-backed store
+    pub fn postgres(pool: PgPool) -> Self {
+        Self::Postgres(pool)
+    }
+
+    /// Save (insert or replace) an execution
+    pub async fn record(&self, execution: &ScenarioExecution) -> Result<()> {
+        match self {
+            Self::Memory(executions) => {
+                let mut executions = executions.lock().await;
+                executions.retain(|e| e.id != execution.id);
+                executions.push(execution.clone());
+            }
+            Self::Postgres(pool) => {
+                sqlx::query(
+                    "INSERT INTO scenario_executions
+                        (id, scenario_id, trigger, state, trigger_context, actions_executed,
+                         error, started_at, completed_at)
+                     VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+                     ON CONFLICT (id) DO UPDATE SET
+                        state = EXCLUDED.state,
+                        actions_executed = EXCLUDED.actions_executed,
+                        error = EXCLUDED.error,
+                        completed_at = EXCLUDED.completed_at",
+                )
+                .bind(&execution.id)
+                .bind(&execution.scenario_id)
+                .bind(serde_json::to_value(&execution.trigger)?)
+                .bind(state_to_str(&execution.state)?)
+                .bind(serde_json::to_value(&execution.trigger_context)?)
+                .bind(serde_json::to_value(&execution.actions_executed)?)
+                .bind(&execution.error)
+                .bind(execution.started_at)
+                .bind(execution.completed_at)
+                .execute(pool)
+                .await
+                .map_err(db_error)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Save an execution, logging instead of failing the caller if the write fails
+    pub async fn record_or_warn(&self, execution: &ScenarioExecution) {
+        if let Err(e) = self.record(execution).await {
+            tracing::error!(execution = %execution.id, "Failed to store scenario execution: {}", e);
+        }
+    }
+
+    /// Executions of a scenario matching the filters, sorted by `started_at` descending
+    pub async fn query(&self, scenario_id: &str, query: &ExecutionQuery) -> Result<ExecutionPage> {
+        validate_page(query.page, query.per_page)?;
+
+        let (executions, total) = match self {
+            Self::Memory(executions) => {
+                let mut matched: Vec<ScenarioExecution> = executions
+                    .lock()
+                    .await
+                    .iter()
+                    .filter(|e| e.scenario_id == scenario_id && query.matches(e))
+                    .cloned()
+                    .collect();
+                matched.sort_by(|a, b| b.started_at.cmp(&a.started_at).then(b.id.cmp(&a.id)));
+
+                let total = matched.len();
+                let page = matched
+                    .into_iter()
+                    .skip(query.offset() as usize)
+                    .take(query.per_page as usize)
+                    .collect();
+                (page, total)
+            }
+            Self::Postgres(pool) => {
+                let state = query.state.as_ref().map(state_to_str).transpose()?;
+                const FILTER: &str = "WHERE scenario_id = $1
+                       AND ($2::text IS NULL OR state = $2)
+                       AND ($3::timestamptz IS NULL OR started_at >= $3)
+                       AND ($4::timestamptz IS NULL OR started_at <= $4)";
+
+                let total: i64 = sqlx::query_scalar(&format!(
+                    "SELECT COUNT(*) FROM scenario_executions {}",
+                    FILTER
+                ))
+                .bind(scenario_id)
+                .bind(&state)
+                .bind(query.since)
+                .bind(query.until)
+                .fetch_one(pool)
+                .await
+                .map_err(db_error)?;
+
+                let rows = sqlx::query_as::<_, ExecutionRow>(&format!(
+                    "SELECT id, scenario_id, trigger, state, trigger_context, actions_executed,
+                            error, started_at, completed_at
+                     FROM scenario_executions
+                     {}
+                     ORDER BY started_at DESC, id DESC
+                     LIMIT $5 OFFSET $6",
+                    FILTER
+                ))
+                .bind(scenario_id)
+                .bind(&state)
+                .bind(query.since)
+                .bind(query.until)
+                .bind(query.per_page)
+                .bind(query.offset())
+                .fetch_all(pool)
+                .await
+                .map_err(db_error)?;
+
+                let executions = rows
+                    .into_iter()
+                    .map(ScenarioExecution::try_from)
+                    .collect::<Result<Vec<_>>>()?;
+                (executions, total as usize)
+            }
+        };
+
+        Ok(ExecutionPage::new(
+            executions,
+            total,
+            query.page,
+            query.per_page,
+        ))
+    }
+}
+
+impl Default for ExecutionStore {
+    fn default() -> Self {
+        Self::memory()
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct ExecutionRow {
+    id: String,
+    scenario_id: String,
+    trigger: serde_json::Value,
+    state: String,
+    trigger_context: serde_json::Value,
+    actions_executed: serde_json::Value,
+    error: Option<String>,
+    started_at: DateTime<Utc>,
+    completed_at: Option<DateTime<Utc>>,
+}
+
+impl TryFrom<ExecutionRow> for ScenarioExecution {
+    type Error = UaipError;
+
+    fn try_from(row: ExecutionRow) -> Result<Self> {
+        Ok(Self {
+            id: row.id,
+            scenario_id: row.scenario_id,
+            trigger: serde_json::from_value(row.trigger)?,
+            state: serde_json::from_value(serde_json::Value::String(row.state))?,
+            trigger_context: serde_json::from_value(row.trigger_context)?,
+            actions_executed: serde_json::from_value(row.actions_executed)?,
+            error: row.error,
+            started_at: row.started_at,
+            completed_at: row.completed_at,
+        })
+    }
+}
+
+/// Stored name of an execution state (its serde name)
+fn state_to_str(state: &ScenarioState) -> Result<String> {
+    match serde_json::to_value(state)? {
+        serde_json::Value::String(state) => Ok(state),
+        other => Err(UaipError::InternalError(format!(
+            "Unexpected scenario state encoding: {}",
+            other
+        ))),
+    }
+}
+
+fn db_error(e: sqlx::Error) -> UaipError {
+    UaipError::DatabaseError(format!("Scenario history error: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use std::collections::HashMap;
+    use uaip_orchestrator::scenario::TriggerType;
+
+    fn execution(
+        id: &str,
+        scenario_id: &str,
+        state: ScenarioState,
+        minutes_ago: i64,
+    ) -> ScenarioExecution {
+        ScenarioExecution {
+            id: id.to_string(),
+            scenario_id: scenario_id.to_string(),
+            trigger: TriggerType::Manual,
+            state,
+            trigger_context: HashMap::new(),
+            actions_executed: Vec::new(),
+            error: None,
+            started_at: Utc::now() - Duration::minutes(minutes_ago),
+            completed_at: None,
+        }
+    }
+
+    async fn store_with_history() -> ExecutionStore {
+        let store = ExecutionStore::memory();
+        for (i, state) in [
+            ScenarioState::Completed,
+            ScenarioState::Failed,
+            ScenarioState::Completed,
+            ScenarioState::Completed,
+            ScenarioState::Failed,
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            store
+                .record(&execution(
+                    &format!("exec-{}", i),
+                    "lights",
+                    state,
+                    i as i64,
+                ))
+                .await
+                .unwrap();
+        }
+        store
+            .record(&execution("other", "alarm", ScenarioState::Completed, 0))
+            .await
+            .unwrap();
+        store
+    }
+
+    fn ids(page: &ExecutionPage) -> Vec<&str> {
+        page.items.iter().map(|e| e.id.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_filter_by_state() {
+        let store = store_with_history().await;
+
+        let failed = store
+            .query(
+                "lights",
+                &ExecutionQuery {
+                    state: Some(ScenarioState::Failed),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(failed.total, 2);
+        assert_eq!(ids(&failed), vec!["exec-1", "exec-4"]);
+
+        let recent = store
+            .query(
+                "lights",
+                &ExecutionQuery {
+                    since: Some(Utc::now() - Duration::seconds(150)),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(ids(&recent), vec!["exec-0", "exec-1", "exec-2"]);
+    }
+
+    #[tokio::test]
+    async fn test_pages_newest_first() {
+        let store = store_with_history().await;
+        let page = |page| ExecutionQuery {
+            page,
+            per_page: 2,
+            ..Default::default()
+        };
+
+        let first = store.query("lights", &page(1)).await.unwrap();
+        assert_eq!(first.total, 5);
+        assert_eq!(ids(&first), vec!["exec-0", "exec-1"]);
+        assert!(first.has_more);
+
+        let last = store.query("lights", &page(3)).await.unwrap();
+        assert_eq!(ids(&last), vec!["exec-4"]);
+        assert!(!last.has_more);
+
+        let past_end = store.query("lights", &page(4)).await.unwrap();
+        assert!(past_end.items.is_empty());
+        assert_eq!(past_end.total, 5);
+
+        assert!(store.query("lights", &page(0)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_record_replaces_execution() {
+        let store = ExecutionStore::memory();
+        let mut running = execution("exec-0", "lights", ScenarioState::Executing, 0);
+        store.record(&running).await.unwrap();
+
+        running.state = ScenarioState::Completed;
+        store.record(&running).await.unwrap();
+
+        let page = store
+            .query("lights", &ExecutionQuery::default())
+            .await
+            .unwrap();
+        assert_eq!(page.total, 1);
+        assert_eq!(page.items[0].state, ScenarioState::Completed);
+    }
+}
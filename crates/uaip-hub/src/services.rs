@@ -0,0 +1,10 @@
+//! Service layer
+//!
+//! Core hub operations shared by the REST handlers, the gRPC API, and internal callers.
+//! Services take the shared `AppState` and return domain results or `UaipError`, leaving
+//! request extraction and response encoding to each API.
+
+pub mod device_backup;
+pub mod devices;
+pub mod media;
+pub mod telemetry;
@@ -0,0 +1,352 @@
+//! Device registry backup
+//!
+//! A tenant's devices export as NDJSON, one [`DeviceExport`] per line, read page by page
+//! so the export never holds the whole registry in memory. The same format imports into
+//! a registry, skipping devices whose IDs are already taken.
+
+use axum::body::Bytes;
+use chrono::{DateTime, Utc};
+use futures_util::{stream, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::future::Future;
+
+use uaip_core::error::UaipError;
+
+use crate::api::rest::AppState;
+use crate::tenant::Tenant;
+
+/// Devices read per export query
+pub const EXPORT_PAGE_SIZE: i64 = 500;
+
+/// Longest accepted import line
+pub const MAX_IMPORT_LINE_BYTES: usize = 1024 * 1024;
+
+/// A device as exported and imported
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct DeviceExport {
+    pub device_id: String,
+    pub mac_address: String,
+    pub manufacturer: String,
+    pub model: String,
+    pub firmware_version: Option<String>,
+    pub status: String,
+    #[serde(deserialize_with = "crate::timestamp::deserialize")]
+    pub registered_at: DateTime<Utc>,
+    #[serde(default, deserialize_with = "crate::timestamp::deserialize_option")]
+    pub last_seen: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub configuration: serde_json::Value,
+    #[serde(default)]
+    pub capabilities: serde_json::Value,
+    #[serde(default)]
+    pub metadata: serde_json::Value,
+}
+
+/// Outcome of an import
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct DeviceImportResponse {
+    pub imported: usize,
+    /// Devices not imported because their ID is already registered
+    pub skipped: Vec<String>,
+}
+
+/// Stream the tenant's devices as NDJSON
+pub fn export_devices(
+    state: &AppState,
+    tenant: Tenant,
+) -> Result<impl Stream<Item = Result<Bytes, UaipError>> + Send + 'static, UaipError> {
+    let pool = state.db()?.clone();
+    Ok(export_ndjson(EXPORT_PAGE_SIZE, move |after, limit| {
+        let pool = pool.clone();
+        let tenant = tenant.clone();
+        async move { export_page(&pool, &tenant, after, limit).await }
+    }))
+}
+
+/// Import NDJSON devices into the tenant in one transaction
+///
+/// Devices whose ID is already registered are skipped; a malformed line aborts the import.
+pub async fn import_devices<S, E>(
+    state: &AppState,
+    tenant: &Tenant,
+    body: S,
+) -> Result<DeviceImportResponse, UaipError>
+where
+    S: Stream<Item = Result<Bytes, E>>,
+    E: std::fmt::Display,
+{
+    let pool = state.db()?;
+    let failed = |e: sqlx::Error| {
+        tracing::error!("Device import transaction failed: {}", e);
+        UaipError::InternalError("Failed to import devices".to_string())
+    };
+    let mut tx = pool.begin().await.map_err(failed)?;
+
+    let mut response = DeviceImportResponse::default();
+    let mut decoder = DeviceImportDecoder::default();
+    let mut body = std::pin::pin!(body);
+    while let Some(chunk) = body.next().await {
+        let chunk = chunk.map_err(|e| {
+            UaipError::InvalidParameter(format!("Failed to read import body: {}", e))
+        })?;
+        let devices = decoder.push(&chunk)?;
+        response.record(&devices, insert_devices(&mut tx, tenant, &devices).await?);
+    }
+    let devices: Vec<_> = decoder.finish()?.into_iter().collect();
+    response.record(&devices, insert_devices(&mut tx, tenant, &devices).await?);
+
+    tx.commit().await.map_err(failed)?;
+    Ok(response)
+}
+
+impl DeviceImportResponse {
+    fn record(&mut self, devices: &[DeviceExport], skipped: Vec<String>) {
+        self.imported += devices.len() - skipped.len();
+        self.skipped.extend(skipped);
+    }
+}
+
+/// Stream devices as NDJSON, one page at a time
+///
+/// `fetch_page(after, limit)` returns up to `limit` devices ordered by device ID,
+/// starting after the given ID.
+pub fn export_ndjson<F, Fut>(
+    page_size: i64,
+    fetch_page: F,
+) -> impl Stream<Item = Result<Bytes, UaipError>>
+where
+    F: FnMut(Option<String>, i64) -> Fut,
+    Fut: Future<Output = Result<Vec<DeviceExport>, UaipError>>,
+{
+    stream::try_unfold(
+        (fetch_page, Some(None::<String>)),
+        move |(mut fetch_page, cursor)| async move {
+            let Some(after) = cursor else {
+                return Ok(None);
+            };
+            let page = fetch_page(after, page_size).await?;
+            if page.is_empty() {
+                return Ok(None);
+            }
+
+            let next = (page.len() as i64 >= page_size)
+                .then(|| page.last().map(|device| device.device_id.clone()));
+            let mut lines = Vec::new();
+            for device in &page {
+                serde_json::to_writer(&mut lines, device).map_err(|e| {
+                    UaipError::InternalError(format!("Failed to encode device: {}", e))
+                })?;
+                lines.push(b'\n');
+            }
+            Ok(Some((Bytes::from(lines), (fetch_page, next))))
+        },
+    )
+}
+
+/// Incremental NDJSON decoder for imports; chunks may split lines anywhere
+#[derive(Debug, Default)]
+pub struct DeviceImportDecoder {
+    buffer: Vec<u8>,
+    line: usize,
+}
+
+impl DeviceImportDecoder {
+    /// Decode the complete lines available after adding a chunk
+    pub fn push(&mut self, chunk: &[u8]) -> Result<Vec<DeviceExport>, UaipError> {
+        self.buffer.extend_from_slice(chunk);
+        let mut devices = Vec::new();
+        while let Some(end) = self.buffer.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=end).collect();
+            devices.extend(self.decode(&line)?);
+        }
+        if self.buffer.len() > MAX_IMPORT_LINE_BYTES {
+            return Err(UaipError::InvalidParameter(format!(
+                "line {} exceeds {} bytes",
+                self.line + 1,
+                MAX_IMPORT_LINE_BYTES
+            )));
+        }
+        Ok(devices)
+    }
+
+    /// Decode a final line without a trailing newline
+    pub fn finish(mut self) -> Result<Option<DeviceExport>, UaipError> {
+        let line = std::mem::take(&mut self.buffer);
+        self.decode(&line)
+    }
+
+    fn decode(&mut self, line: &[u8]) -> Result<Option<DeviceExport>, UaipError> {
+        self.line += 1;
+        if line.iter().all(u8::is_ascii_whitespace) {
+            return Ok(None);
+        }
+        let device: DeviceExport = serde_json::from_slice(line).map_err(|e| {
+            UaipError::InvalidParameter(format!("line {}: invalid device: {}", self.line, e))
+        })?;
+        if device.device_id.is_empty() {
+            return Err(UaipError::InvalidParameter(format!(
+                "line {}: device_id cannot be empty",
+                self.line
+            )));
+        }
+        Ok(Some(device))
+    }
+}
+
+/// A page of the tenant's devices ordered by device ID, starting after `after`
+async fn export_page(
+    pool: &PgPool,
+    tenant: &Tenant,
+    after: Option<String>,
+    limit: i64,
+) -> Result<Vec<DeviceExport>, UaipError> {
+    sqlx::query_as(
+        "SELECT device_id, mac_address, manufacturer, model, firmware_version, status,
+                registered_at, last_seen,
+                COALESCE(configuration, '{}'::jsonb) AS configuration,
+                COALESCE(capabilities, '[]'::jsonb) AS capabilities,
+                COALESCE(metadata, '{}'::jsonb) AS metadata
+         FROM devices
+         WHERE tenant_id = $1 AND ($2::text IS NULL OR device_id > $2)
+         ORDER BY device_id
+         LIMIT $3",
+    )
+    .bind(tenant.id())
+    .bind(after)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to export devices: {}", e);
+        UaipError::InternalError("Failed to query devices".to_string())
+    })
+}
+
+/// Insert imported devices into the tenant, returning the IDs that were already taken
+async fn insert_devices(
+    tx: &mut sqlx::PgConnection,
+    tenant: &Tenant,
+    devices: &[DeviceExport],
+) -> Result<Vec<String>, UaipError> {
+    let mut skipped = Vec::new();
+    for device in devices {
+        let inserted = sqlx::query(
+            "INSERT INTO devices (id, device_id, mac_address, manufacturer, model,
+                                  firmware_version, status, registered_at, last_seen,
+                                  configuration, capabilities, metadata, tenant_id)
+             SELECT $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13
+             WHERE NOT EXISTS (SELECT 1 FROM devices WHERE device_id = $2)",
+        )
+        .bind(uuid::Uuid::new_v4())
+        .bind(&device.device_id)
+        .bind(&device.mac_address)
+        .bind(&device.manufacturer)
+        .bind(&device.model)
+        .bind(&device.firmware_version)
+        .bind(&device.status)
+        .bind(device.registered_at)
+        .bind(device.last_seen)
+        .bind(&device.configuration)
+        .bind(&device.capabilities)
+        .bind(&device.metadata)
+        .bind(tenant.id())
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to import device {}: {}", device.device_id, e);
+            UaipError::InternalError(format!("Failed to import device {}", device.device_id))
+        })?;
+        if inserted.rows_affected() == 0 {
+            skipped.push(device.device_id.clone());
+        }
+    }
+    Ok(skipped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::TryStreamExt;
+    use std::collections::BTreeMap;
+    use std::sync::{Arc, Mutex};
+
+    fn device(device_id: &str) -> DeviceExport {
+        DeviceExport {
+            device_id: device_id.to_string(),
+            mac_address: format!("00:00:00:00:00:{}", &device_id[device_id.len() - 2..]),
+            manufacturer: "Acme".to_string(),
+            model: "T-1000".to_string(),
+            firmware_version: Some("1.2.0".to_string()),
+            status: "online".to_string(),
+            registered_at: "2024-05-01T12:00:00Z".parse().unwrap(),
+            last_seen: None,
+            configuration: serde_json::json!({}),
+            capabilities: serde_json::json!(["temperature", {"name": "dimmer"}]),
+            metadata: serde_json::json!({"name": device_id, "device_type": "sensor"}),
+        }
+    }
+
+    type Registry = Arc<Mutex<BTreeMap<String, DeviceExport>>>;
+
+    /// Page through an in-memory registry like `export_page`
+    fn fetch_from(
+        registry: Registry,
+    ) -> impl FnMut(Option<String>, i64) -> std::future::Ready<Result<Vec<DeviceExport>, UaipError>>
+    {
+        move |after, limit| {
+            let registry = registry.lock().unwrap();
+            let page = registry
+                .values()
+                .filter(|d| after.as_ref().is_none_or(|after| &d.device_id > after))
+                .take(limit as usize)
+                .cloned()
+                .collect();
+            std::future::ready(Ok(page))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_export_reimports_into_clean_registry() {
+        let source: Registry = Arc::new(Mutex::new(
+            (1..=5)
+                .map(|i| device(&format!("sensor-{:02}", i)))
+                .map(|d| (d.device_id.clone(), d))
+                .collect(),
+        ));
+
+        let chunks: Vec<Bytes> = export_ndjson(2, fetch_from(source.clone()))
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(chunks.len(), 3);
+        let ndjson: Vec<u8> = chunks.concat();
+        assert_eq!(ndjson.iter().filter(|b| **b == b'\n').count(), 5);
+
+        // Import in chunks that split lines mid-record
+        let mut clean = BTreeMap::new();
+        let mut decoder = DeviceImportDecoder::default();
+        for chunk in ndjson.chunks(37) {
+            for device in decoder.push(chunk).unwrap() {
+                clean.insert(device.device_id.clone(), device);
+            }
+        }
+        assert!(decoder.finish().unwrap().is_none());
+        assert_eq!(clean, *source.lock().unwrap());
+    }
+
+    #[test]
+    fn test_invalid_import_line_is_reported() {
+        let mut decoder = DeviceImportDecoder::default();
+        let valid = serde_json::to_string(&device("sensor-01")).unwrap();
+        let input = format!("{}\n\n{{\"device_id\": \"x\"}}\n", valid);
+
+        let err = decoder.push(input.as_bytes()).unwrap_err();
+        assert!(err.to_string().contains("line 3"), "{}", err);
+
+        // A final line without a newline still imports
+        let mut decoder = DeviceImportDecoder::default();
+        assert!(decoder.push(valid.as_bytes()).unwrap().is_empty());
+        assert_eq!(decoder.finish().unwrap(), Some(device("sensor-01")));
+    }
+}
@@ -0,0 +1,1327 @@
+//! Device operations: listing, registration, and command dispatch
+
+use chrono::{Duration, Utc};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use uaip_auth::jwt::Claims;
+use uaip_auth::rbac::Permission;
+use uaip_core::error::UaipError;
+use uaip_core::group::DeviceGroups;
+use uaip_core::message::{Action, EntityType, Priority, UaipMessage};
+use uaip_registry::availability::availability;
+use uaip_registry::repository::DeviceRepository;
+
+use crate::api::rest::{
+    AppState, CapabilityDeclaration, CommandRequest, CommandResponse, DeviceDetailResponse,
+    DeviceInfo, DeviceListResponse, DeviceRegistrationRequest, DeviceRegistrationResponse,
+};
+use crate::audit::AuditEvent;
+use crate::command_log::{CommandQuery, CommandStatus, DeviceCommand};
+use crate::config::MessagingConfig;
+use crate::handlers::firmware::firmware_update_available;
+use crate::pagination::{default_page, default_per_page, page_offset, validate_page};
+use crate::tenant::Tenant;
+
+/// Query parameters for device listing
+#[derive(Debug, Deserialize)]
+pub struct DeviceListQuery {
+    /// Filter by status (online, offline, error, maintenance, deactivated)
+    #[serde(default)]
+    pub status: Option<String>,
+
+    /// Filter by manufacturer
+    #[serde(default)]
+    pub manufacturer: Option<String>,
+
+    /// Page number (1-indexed)
+    #[serde(default = "default_page")]
+    pub page: i64,
+
+    /// Items per page
+    #[serde(default = "default_per_page")]
+    pub per_page: i64,
+
+    /// Sort by field (id, device_id, status, last_seen, registered_at)
+    #[serde(default = "default_sort_by")]
+    pub sort_by: String,
+
+    /// Sort order (asc, desc)
+    #[serde(default = "default_sort_order")]
+    pub sort_order: String,
+}
+
+fn default_sort_by() -> String {
+    "registered_at".to_string()
+}
+
+fn default_sort_order() -> String {
+    "desc".to_string()
+}
+
+/// Device record from database
+#[derive(Debug, sqlx::FromRow)]
+struct DeviceRow {
+    #[allow(dead_code)]
+    id: sqlx::types::Uuid,
+    device_id: String,
+    manufacturer: String,
+    model: String,
+    device_type: Option<String>,
+    firmware_version: Option<String>,
+    latest_firmware_version: Option<String>,
+    status: String,
+    last_seen: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl From<DeviceRow> for DeviceInfo {
+    fn from(d: DeviceRow) -> Self {
+        let update_available = match (&d.firmware_version, &d.latest_firmware_version) {
+            (Some(current), Some(latest)) => firmware_update_available(current, latest),
+            _ => false,
+        };
+
+        DeviceInfo {
+            device_id: d.device_id,
+            name: format!("{} {}", d.manufacturer, d.model),
+            // Fall back to manufacturer for devices registered without a type
+            device_type: d.device_type.unwrap_or(d.manufacturer),
+            status: d.status,
+            last_seen: d.last_seen.map(|dt| dt.to_rfc3339()),
+            firmware_version: d.firmware_version,
+            latest_firmware_version: d.latest_firmware_version,
+            update_available,
+        }
+    }
+}
+
+/// Longest availability window, in hours (30 days)
+pub const MAX_WINDOW_HOURS: i64 = 720;
+
+/// Build the WHERE clause and its bind values for a device listing
+///
+/// The tenant condition is always first, so filters cannot widen the listing beyond
+/// the caller's tenant.
+fn device_filter(tenant: &Tenant, query: &DeviceListQuery) -> (String, Vec<String>) {
+    let mut conditions = vec!["tenant_id = $1".to_string()];
+    let mut bind_values = vec![tenant.id().to_string()];
+
+    if let Some(status) = &query.status {
+        conditions.push(format!("status = ${}", conditions.len() + 1));
+        bind_values.push(status.clone());
+    }
+
+    if let Some(manufacturer) = &query.manufacturer {
+        conditions.push(format!("manufacturer = ${}", conditions.len() + 1));
+        bind_values.push(manufacturer.clone());
+    }
+
+    (format!("WHERE {}", conditions.join(" AND ")), bind_values)
+}
+
+/// List the tenant's devices with filtering, pagination, and sorting
+pub async fn list_devices(
+    state: &AppState,
+    tenant: &Tenant,
+    query: &DeviceListQuery,
+) -> Result<DeviceListResponse, UaipError> {
+    validate_page(query.page, query.per_page)?;
+
+    // Validate sort_by field
+    let valid_sort_fields = ["id", "device_id", "status", "last_seen", "registered_at"];
+    if !valid_sort_fields.contains(&query.sort_by.as_str()) {
+        return Err(UaipError::InvalidParameter(format!(
+            "sort_by must be one of: {}",
+            valid_sort_fields.join(", ")
+        )));
+    }
+
+    // Validate sort_order
+    let sort_order = match query.sort_order.to_lowercase().as_str() {
+        "asc" => "ASC",
+        "desc" => "DESC",
+        _ => {
+            return Err(UaipError::InvalidParameter(
+                "sort_order must be 'asc' or 'desc'".to_string(),
+            ))
+        }
+    };
+
+    // Get database pool
+    let db_pool = state.db()?;
+
+    // Build query with filters
+    let (where_clause, bind_values) = device_filter(tenant, query);
+
+    // Calculate offset
+    let offset = page_offset(query.page, query.per_page);
+
+    // Build SQL query - Note: Using format! here for ORDER BY is safe since we've validated the values
+    let sql_query = format!(
+        "SELECT id, device_id, manufacturer, model,
+                metadata->>'device_type' AS device_type, firmware_version,
+                (SELECT latest_version FROM firmware_catalog
+                 WHERE firmware_catalog.device_type = devices.metadata->>'device_type')
+                    AS latest_firmware_version,
+                status, last_seen
+         FROM devices
+         {}
+         ORDER BY {} {}
+         LIMIT ${} OFFSET ${}",
+        where_clause,
+        query.sort_by,
+        sort_order,
+        bind_values.len() + 1,
+        bind_values.len() + 2
+    );
+
+    // Count query
+    let count_query = format!("SELECT COUNT(*) as count FROM devices {}", where_clause);
+
+    // Execute count query
+    let mut count_query_builder = sqlx::query_scalar::<_, i64>(&count_query);
+    for value in &bind_values {
+        count_query_builder = count_query_builder.bind(value);
+    }
+    let total = state
+        .query_timer
+        .time("devices.count", count_query_builder.fetch_one(db_pool))
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to count devices: {}", e);
+            UaipError::InternalError("Failed to query devices".to_string())
+        })?;
+
+    // Execute main query
+    let mut query_builder = sqlx::query_as::<_, DeviceRow>(&sql_query);
+    for value in &bind_values {
+        query_builder = query_builder.bind(value);
+    }
+    query_builder = query_builder.bind(query.per_page).bind(offset);
+
+    let devices = state
+        .query_timer
+        .time("devices.list", query_builder.fetch_all(db_pool))
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch devices: {}", e);
+            UaipError::InternalError("Failed to query devices".to_string())
+        })?;
+
+    // Transform to DeviceInfo
+    let device_infos: Vec<DeviceInfo> = devices.into_iter().map(DeviceInfo::from).collect();
+
+    tracing::debug!(
+        "Listed {} devices (total: {}, page: {}, per_page: {})",
+        device_infos.len(),
+        total,
+        query.page,
+        query.per_page
+    );
+
+    Ok(DeviceListResponse::new(
+        device_infos,
+        total as usize,
+        query.page,
+        query.per_page,
+    ))
+}
+
+/// Get one of the tenant's devices with its availability over a rolling window
+pub async fn get_device(
+    state: &AppState,
+    tenant: &Tenant,
+    device_id: &str,
+    window_hours: i64,
+) -> Result<DeviceDetailResponse, UaipError> {
+    if !(1..=MAX_WINDOW_HOURS).contains(&window_hours) {
+        return Err(UaipError::InvalidParameter(format!(
+            "window_hours must be between 1 and {}",
+            MAX_WINDOW_HOURS
+        )));
+    }
+
+    let db_pool = state.db()?;
+
+    let query = sqlx::query_as::<_, DeviceRow>(
+        "SELECT id, device_id, manufacturer, model,
+                metadata->>'device_type' AS device_type, firmware_version,
+                (SELECT latest_version FROM firmware_catalog
+                 WHERE firmware_catalog.device_type = devices.metadata->>'device_type')
+                    AS latest_firmware_version,
+                status, last_seen
+         FROM devices
+         WHERE device_id = $1 AND tenant_id = $2",
+    )
+    .bind(device_id)
+    .bind(tenant.id());
+    let device = state
+        .query_timer
+        .time("devices.get", query.fetch_optional(db_pool))
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch device: {}", e);
+            UaipError::InternalError("Failed to query device".to_string())
+        })?
+        .ok_or_else(|| UaipError::DeviceNotFound(format!("Device '{}' not found", device_id)))?;
+
+    let now = Utc::now();
+    let since = now - Duration::hours(window_hours);
+    let history = DeviceRepository::new(db_pool.clone())
+        .status_history(device_id, since)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch status history for {}: {}", device_id, e);
+            UaipError::InternalError("Failed to query device".to_string())
+        })?;
+
+    Ok(DeviceDetailResponse {
+        device: device.into(),
+        window_hours,
+        availability: availability(&history, since, now),
+    })
+}
+
+/// Declared capability names of the tenant's devices, by device ID
+pub async fn device_capabilities(
+    state: &AppState,
+    tenant: &Tenant,
+) -> Result<HashMap<String, Vec<String>>, UaipError> {
+    let db_pool = state.db()?;
+
+    let query = sqlx::query_as("SELECT device_id, capabilities FROM devices WHERE tenant_id = $1")
+        .bind(tenant.id());
+    let devices: Vec<(String, serde_json::Value)> = state
+        .query_timer
+        .time("devices.capabilities", query.fetch_all(db_pool))
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch device capabilities: {}", e);
+            UaipError::InternalError("Failed to query devices".to_string())
+        })?;
+
+    Ok(devices
+        .into_iter()
+        .map(|(device_id, capabilities)| {
+            let names = capability_declarations(&capabilities)
+                .iter()
+                .map(|declaration| declaration.name().to_string())
+                .collect();
+            (device_id, names)
+        })
+        .collect())
+}
+
+/// Register a new device for the tenant (initiates 3-step challenge)
+///
+/// Device IDs are unique across tenants. `actor` is recorded in the audit log.
+pub async fn register_device(
+    state: &AppState,
+    tenant: &Tenant,
+    actor: &str,
+    request: DeviceRegistrationRequest,
+) -> Result<DeviceRegistrationResponse, UaipError> {
+    validate_registration(&request)?;
+    let capabilities = state
+        .device_types
+        .with_default_capabilities(&request.device_type, &request.capabilities);
+
+    // Get database pool
+    let db_pool = state.db()?;
+
+    // Check if device already exists
+    let query = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM devices WHERE device_id = $1")
+        .bind(&request.device_id);
+    let existing = state
+        .query_timer
+        .time("devices.exists", query.fetch_one(db_pool))
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to check device existence: {}", e);
+            UaipError::InternalError("Failed to check device".to_string())
+        })?;
+
+    if existing > 0 {
+        return Err(UaipError::InvalidParameter(format!(
+            "Device with ID '{}' already exists",
+            request.device_id
+        )));
+    }
+
+    // Generate registration challenge
+    let challenge = format!("challenge_{}", uuid::Uuid::new_v4());
+    let expires_at = chrono::Utc::now() + chrono::Duration::minutes(5);
+
+    // TODO: Step 1 of 3-step challenge:
+    // - Store challenge in temporary table with expiry
+    // - Return challenge to device
+    // Step 2: Device signs challenge with private key
+    // Step 3: Hub verifies signature and creates certificate
+
+    // For now, just insert the device directly (simplified registration)
+    let device_uuid = uuid::Uuid::new_v4();
+
+    // Generate a placeholder MAC address
+    let mac_address = format!(
+        "00:00:00:{:02x}:{:02x}:{:02x}",
+        device_uuid.as_bytes()[0],
+        device_uuid.as_bytes()[1],
+        device_uuid.as_bytes()[2]
+    );
+
+    let insert = sqlx::query(
+        "INSERT INTO devices (id, device_id, mac_address, manufacturer, model, firmware_version, status, capabilities, metadata, tenant_id)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)"
+    )
+    .bind(device_uuid)
+    .bind(&request.device_id)
+    .bind(&mac_address)
+    .bind(request.manufacturer.as_deref().unwrap_or("Unknown"))
+    .bind(request.model.as_deref().unwrap_or("Unknown"))
+    .bind("1.0.0") // Default firmware version
+    .bind("offline") // Initially offline until first heartbeat
+    .bind(serde_json::to_value(&capabilities).unwrap_or(serde_json::json!([])))
+    .bind(serde_json::json!({
+        "name": request.name,
+        "device_type": request.device_type
+    }))
+    .bind(tenant.id());
+    state
+        .query_timer
+        .time("devices.insert", insert.execute(db_pool))
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to register device: {}", e);
+            UaipError::InternalError("Failed to register device".to_string())
+        })?;
+
+    tracing::info!(
+        "Device registered: {} ({})",
+        request.device_id,
+        request.name
+    );
+
+    state
+        .audit_log
+        .record_or_warn(
+            AuditEvent::success(actor, "device.register")
+                .with_target(&request.device_id)
+                .with_details(serde_json::json!({
+                    "device_type": request.device_type,
+                    "tenant_id": tenant.id(),
+                })),
+        )
+        .await;
+
+    Ok(DeviceRegistrationResponse {
+        device_id: request.device_id,
+        challenge,
+        expires_at: expires_at.to_rfc3339(),
+    })
+}
+
+/// Register a device by presenting a provisioning token
+///
+/// The device joins the token's tenant. The token is consumed when registration
+/// succeeds and released again if registration fails, so it is never burned by a
+/// rejected attempt.
+pub async fn provision_device(
+    state: &AppState,
+    token: &str,
+    request: DeviceRegistrationRequest,
+) -> Result<DeviceRegistrationResponse, UaipError> {
+    validate_registration(&request)?;
+
+    let provisioning_token = state
+        .provisioning_tokens
+        .consume(token, &request.device_type, &request.device_id)
+        .await?;
+    let tenant = Tenant::new(provisioning_token.tenant_id.clone());
+
+    // The device provisions itself, so it is the actor
+    let device_id = request.device_id.clone();
+    match register_device(state, &tenant, &device_id, request).await {
+        Ok(response) => {
+            state
+                .audit_log
+                .record_or_warn(
+                    AuditEvent::success(&device_id, "device.provision")
+                        .with_target(&response.device_id)
+                        .with_details(serde_json::json!({
+                            "provisioning_token_id": provisioning_token.id,
+                            "tenant_id": tenant.id(),
+                        })),
+                )
+                .await;
+            Ok(response)
+        }
+        Err(e) => {
+            if let Err(release_error) = state
+                .provisioning_tokens
+                .release(provisioning_token.id)
+                .await
+            {
+                tracing::warn!(
+                    "Failed to release provisioning token {}: {}",
+                    provisioning_token.id,
+                    release_error
+                );
+            }
+            Err(e)
+        }
+    }
+}
+
+/// Check the fields of a registration request
+fn validate_registration(request: &DeviceRegistrationRequest) -> Result<(), UaipError> {
+    // Validate device_id
+    if request.device_id.is_empty() {
+        return Err(UaipError::InvalidParameter(
+            "device_id cannot be empty".to_string(),
+        ));
+    }
+
+    // Validate name
+    if request.name.is_empty() {
+        return Err(UaipError::InvalidParameter(
+            "name cannot be empty".to_string(),
+        ));
+    }
+
+    // Validate typed capability definitions
+    for declaration in &request.capabilities {
+        if let CapabilityDeclaration::Typed(capability) = declaration {
+            capability.validate()?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Check that `claims` may send `action` to a device
+///
+/// Requires the `admin` scope, a `command:<device_type>:<action>` scope, or a
+/// `group:<group_id>:command:<action>` scope for a group containing the device, where
+/// `*` matches any device type, group or action.
+pub fn authorize_command(
+    claims: &Claims,
+    device_id: &str,
+    device_type: Option<&str>,
+    action: &str,
+    groups: &DeviceGroups,
+) -> Result<(), UaipError> {
+    if claims.scopes.iter().any(|scope| scope == "admin") {
+        return Ok(());
+    }
+    let grants: Vec<Permission> = claims
+        .scopes
+        .iter()
+        .filter_map(|scope| Permission::parse(scope).ok())
+        .collect();
+    let granted = |permission: &Permission| grants.iter().any(|g| permission.matches(g));
+
+    let required = Permission::command(device_type.unwrap_or("unknown"), action);
+    if granted(&required) {
+        return Ok(());
+    }
+    let group_granted = groups
+        .resolve_all()
+        .into_iter()
+        .filter(|(_, members)| members.iter().any(|member| member == device_id))
+        .any(|(group_id, _)| granted(&Permission::group_command(&group_id, action)));
+    if group_granted {
+        return Ok(());
+    }
+
+    Err(UaipError::AuthorizationFailed(format!(
+        "Permission '{}' required",
+        required.to_string_repr()
+    )))
+}
+
+/// Validate, authorize, throttle, and queue a command for one of the tenant's devices
+///
+/// The `caller` must pass [`authorize_command`]. A critical command with `preempt` set
+/// first cancels the device's pending commands. Devices of other tenants are reported as
+/// not found.
+pub async fn send_command(
+    state: &AppState,
+    tenant: &Tenant,
+    caller: &Claims,
+    device_id: &str,
+    request: &CommandRequest,
+) -> Result<CommandResponse, UaipError> {
+    // Validate device_id
+    if device_id.is_empty() {
+        return Err(UaipError::InvalidParameter(
+            "device_id cannot be empty".to_string(),
+        ));
+    }
+
+    // Validate action
+    if request.action.is_empty() {
+        return Err(UaipError::InvalidParameter(
+            "action cannot be empty".to_string(),
+        ));
+    }
+
+    let priority = command_priority(request, &state.messaging);
+    if request.preempt && priority != Priority::Critical {
+        return Err(UaipError::InvalidParameter(
+            "preempt requires critical priority".to_string(),
+        ));
+    }
+
+    // Get database pool
+    let db_pool = state.db()?;
+
+    // Verify device exists and get its UUID, type, and capabilities
+    let query = sqlx::query_as(
+        "SELECT id, metadata->>'device_type', capabilities FROM devices
+         WHERE device_id = $1 AND tenant_id = $2",
+    )
+    .bind(device_id)
+    .bind(tenant.id());
+    let device: Option<(sqlx::types::Uuid, Option<String>, serde_json::Value)> = state
+        .query_timer
+        .time("devices.command_target", query.fetch_optional(db_pool))
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to query device: {}", e);
+            UaipError::InternalError("Failed to verify device".to_string())
+        })?;
+
+    let (_device_uuid, device_type, capabilities) = device
+        .ok_or_else(|| UaipError::DeviceNotFound(format!("Device '{}' not found", device_id)))?;
+
+    let groups = state.automation.lock().await.groups.for_tenant(tenant.id());
+    authorize_command(
+        caller,
+        device_id,
+        device_type.as_deref(),
+        &request.action,
+        &groups,
+    )?;
+
+    validate_command(device_id, &capabilities, request)?;
+
+    // Throttle commands per device
+    state
+        .command_throttle
+        .acquire(device_id, device_type.as_deref())
+        .await?;
+
+    // Record the command as pending until it is delivered
+    let message_id = format!("msg_{}", uuid::Uuid::new_v4());
+    let correlation_id = uuid::Uuid::new_v4().to_string();
+    let queued_at = Utc::now();
+
+    let preempted = if request.preempt {
+        preempt_pending_commands(state, tenant, &caller.sub, device_id, &message_id).await?
+    } else {
+        Vec::new()
+    };
+
+    state
+        .command_log
+        .record(&DeviceCommand {
+            message_id: message_id.clone(),
+            correlation_id: correlation_id.clone(),
+            device_id: device_id.to_string(),
+            tenant_id: tenant.id().to_string(),
+            action: request.action.clone(),
+            priority: priority.as_str().to_string(),
+            parameters: request.parameters.clone().unwrap_or(serde_json::json!({})),
+            status: CommandStatus::Pending,
+            created_at: queued_at,
+            cancelled_at: None,
+            delivered_at: None,
+            completed_at: None,
+            result: None,
+        })
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to create message: {}", e);
+            UaipError::InternalError("Failed to queue command".to_string())
+        })?;
+
+    // Hand the command to the delivery queue, ordered by priority
+    state
+        .message_queue
+        .push(command_message(
+            &state.messaging,
+            device_id,
+            &message_id,
+            &correlation_id,
+            request,
+            priority,
+        ))
+        .await;
+
+    tracing::info!(
+        "Command queued: {} for device {} (message_id: {})",
+        request.action,
+        device_id,
+        message_id
+    );
+
+    state
+        .audit_log
+        .record_or_warn(
+            AuditEvent::success(&caller.sub, "device.command")
+                .with_target(device_id)
+                .with_details(serde_json::json!({
+                    "action": request.action,
+                    "message_id": message_id,
+                })),
+        )
+        .await;
+
+    Ok(CommandResponse {
+        message_id,
+        status: "queued".to_string(),
+        queued_at: queued_at.to_rfc3339(),
+        preempted: preempted.into_iter().map(|c| c.message_id).collect(),
+    })
+}
+
+/// Cancel a device's pending commands ahead of the preempting command `message_id`
+///
+/// Commands delivered in the meantime are left alone. The preemption is audited.
+pub async fn preempt_pending_commands(
+    state: &AppState,
+    tenant: &Tenant,
+    actor: &str,
+    device_id: &str,
+    message_id: &str,
+) -> Result<Vec<DeviceCommand>, UaipError> {
+    let pending = state
+        .command_log
+        .list(
+            tenant.id(),
+            device_id,
+            &CommandQuery {
+                status: Some(CommandStatus::Pending),
+                limit: Some(1000),
+            },
+        )
+        .await?;
+
+    let mut cancelled = Vec::new();
+    for command in pending {
+        match state
+            .command_log
+            .cancel(tenant.id(), &command.message_id)
+            .await
+        {
+            Ok(command) => {
+                state.message_queue.remove(&command.message_id).await;
+                cancelled.push(command);
+            }
+            // Delivered since it was listed
+            Err(UaipError::InvalidState(_)) => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    if !cancelled.is_empty() {
+        let ids: Vec<&str> = cancelled.iter().map(|c| c.message_id.as_str()).collect();
+        tracing::info!(
+            "Command {} preempted {} pending commands for device {}",
+            message_id,
+            ids.len(),
+            device_id
+        );
+        state
+            .audit_log
+            .record_or_warn(
+                AuditEvent::success(actor, "device.command.preempt")
+                    .with_target(device_id)
+                    .with_details(serde_json::json!({
+                        "message_id": message_id,
+                        "cancelled": ids,
+                    })),
+            )
+            .await;
+    }
+
+    Ok(cancelled)
+}
+
+/// Hand a device its next pending command, waiting up to `wait` for one to be queued
+///
+/// Commands are handed over in priority order and marked delivered. Queued commands for
+/// a device with the same ID in another tenant are left in the queue.
+pub async fn next_command(
+    state: &AppState,
+    tenant: &Tenant,
+    device_id: &str,
+    wait: std::time::Duration,
+) -> Result<Option<DeviceCommand>, UaipError> {
+    let deadline = tokio::time::Instant::now() + wait;
+    let mut pushed = state.message_queue.subscribe();
+    let pending_query = CommandQuery {
+        status: Some(CommandStatus::Pending),
+        limit: Some(1000),
+    };
+
+    loop {
+        let pending: std::collections::HashSet<String> = state
+            .command_log
+            .list(tenant.id(), device_id, &pending_query)
+            .await?
+            .into_iter()
+            .map(|command| command.message_id)
+            .collect();
+        let message = state
+            .message_queue
+            .pop_where(|m| {
+                m.header.recipient.id == device_id && pending.contains(&m.header.message_id)
+            })
+            .await;
+
+        match message {
+            Some(message) => {
+                let delivered = state
+                    .command_log
+                    .mark_delivered(tenant.id(), &message.header.message_id)
+                    .await?;
+                if delivered.is_some() {
+                    tracing::info!(
+                        "Command delivered: {} to device {}",
+                        message.header.message_id,
+                        device_id
+                    );
+                    return Ok(delivered);
+                }
+                // Cancelled while being handed over; look for the next one
+            }
+            None => {
+                if !matches!(
+                    tokio::time::timeout_at(deadline, pushed.changed()).await,
+                    Ok(Ok(()))
+                ) {
+                    return Ok(None);
+                }
+            }
+        }
+    }
+}
+
+/// Check that the device advertises the command's action and that the parameters match
+/// the capability's parameter specs
+///
+/// Parameters of capabilities declared by name only are not checked.
+fn validate_command(
+    device_id: &str,
+    capabilities: &serde_json::Value,
+    request: &CommandRequest,
+) -> Result<(), UaipError> {
+    let declared = capability_declarations(capabilities);
+
+    let Some(declaration) = declared.iter().find(|d| d.supports_action(&request.action)) else {
+        let names: Vec<&str> = declared.iter().map(CapabilityDeclaration::name).collect();
+        return Err(UaipError::InvalidParameter(format!(
+            "device '{}' does not support action '{}' (capabilities: [{}])",
+            device_id,
+            request.action,
+            names.join(", ")
+        )));
+    };
+    let CapabilityDeclaration::Typed(capability) = declaration else {
+        return Ok(());
+    };
+
+    match &request.parameters {
+        None => capability.validate_parameters(&serde_json::Map::new()),
+        Some(serde_json::Value::Object(parameters)) => capability.validate_parameters(parameters),
+        Some(other) => Err(UaipError::InvalidParameter(format!(
+            "parameters for action '{}' must be an object, got {}",
+            request.action, other
+        ))),
+    }
+}
+
+/// Parse a device's stored capabilities, skipping entries that do not parse
+pub(crate) fn capability_declarations(
+    capabilities: &serde_json::Value,
+) -> Vec<CapabilityDeclaration> {
+    capabilities
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|c| serde_json::from_value(c.clone()).ok())
+        .collect()
+}
+
+/// Priority a command is queued at; absent or unknown values take the configured default
+pub fn command_priority(request: &CommandRequest, defaults: &MessagingConfig) -> Priority {
+    request
+        .priority
+        .as_deref()
+        .and_then(|p| p.parse().ok())
+        .unwrap_or_else(|| defaults.default_priority.clone())
+}
+
+/// Build the routed message for a queued device command, with the configured TTL and QoS
+pub fn command_message(
+    defaults: &MessagingConfig,
+    device_id: &str,
+    message_id: &str,
+    correlation_id: &str,
+    request: &CommandRequest,
+    priority: Priority,
+) -> UaipMessage {
+    let mut message = UaipMessage::new(
+        "hub".to_string(),
+        EntityType::System,
+        device_id.to_string(),
+        EntityType::Device,
+    )
+    .with_correlation_id(correlation_id.to_string())
+    .with_priority(priority)
+    .with_qos(defaults.default_qos.clone())
+    .with_action(Action::Execute);
+
+    message.header.message_id = message_id.to_string();
+    message.header.ttl = defaults.default_ttl_ms;
+    message.payload.capability = Some(request.action.clone());
+    if let Some(serde_json::Value::Object(parameters)) = &request.parameters {
+        message.payload.parameters = Some(parameters.clone().into_iter().collect());
+    }
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uaip_core::message::QosLevel;
+    use uaip_router::priority_queue::MessagePriorityQueue;
+
+    fn list_query() -> DeviceListQuery {
+        serde_json::from_value(serde_json::json!({})).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_list_devices_validates_before_querying() {
+        let state = AppState::new();
+
+        for query in [
+            DeviceListQuery {
+                page: 0,
+                ..list_query()
+            },
+            DeviceListQuery {
+                per_page: 101,
+                ..list_query()
+            },
+            DeviceListQuery {
+                sort_by: "name; DROP TABLE devices".to_string(),
+                ..list_query()
+            },
+            DeviceListQuery {
+                sort_order: "sideways".to_string(),
+                ..list_query()
+            },
+        ] {
+            let err = list_devices(&state, &Tenant::default(), &query)
+                .await
+                .unwrap_err();
+            assert!(matches!(err, UaipError::InvalidParameter(_)), "{:?}", query);
+        }
+
+        // A valid query only fails for lack of a database
+        let err = list_devices(&state, &Tenant::default(), &list_query())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, UaipError::NotConfigured(_)));
+    }
+
+    #[test]
+    fn test_device_filter_is_scoped_to_tenant() {
+        let query = DeviceListQuery {
+            status: Some("online".to_string()),
+            manufacturer: Some("Acme".to_string()),
+            ..list_query()
+        };
+
+        let (where_clause, binds) = device_filter(&Tenant::new("tenant-a"), &query);
+        assert_eq!(
+            where_clause,
+            "WHERE tenant_id = $1 AND status = $2 AND manufacturer = $3"
+        );
+        assert_eq!(binds, vec!["tenant-a", "online", "Acme"]);
+
+        // Without filters a listing still only covers the caller's tenant
+        let (where_clause, binds) = device_filter(&Tenant::new("tenant-b"), &list_query());
+        assert_eq!(where_clause, "WHERE tenant_id = $1");
+        assert_eq!(binds, vec!["tenant-b"]);
+    }
+
+    #[tokio::test]
+    async fn test_register_device_requires_id_and_name() {
+        let state = AppState::new();
+        let request = |device_id: &str, name: &str| DeviceRegistrationRequest {
+            device_id: device_id.to_string(),
+            device_type: "sensor".to_string(),
+            name: name.to_string(),
+            manufacturer: None,
+            model: None,
+            capabilities: vec![],
+        };
+
+        let err = register_device(&state, &Tenant::default(), "probe-1", request("", "Probe"))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("device_id"));
+        let err = register_device(
+            &state,
+            &Tenant::default(),
+            "probe-1",
+            request("probe-1", ""),
+        )
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains("name"));
+    }
+
+    fn dimmer_capabilities() -> serde_json::Value {
+        serde_json::json!([
+            "status_led",
+            {
+                "name": "dimmer",
+                "capability_type": "actuator",
+                "is_primary": true,
+                "supported_actions": ["set"],
+                "parameters": {
+                    "brightness": { "param_type": "integer", "required": true, "min": 0, "max": 100 }
+                }
+            }
+        ])
+    }
+
+    #[tokio::test]
+    async fn test_register_device_rejects_invalid_capability_range() {
+        let state = AppState::new();
+        let mut capabilities: Vec<CapabilityDeclaration> =
+            serde_json::from_value(dimmer_capabilities()).unwrap();
+        assert!(matches!(capabilities[0], CapabilityDeclaration::Name(_)));
+        let CapabilityDeclaration::Typed(dimmer) = &mut capabilities[1] else {
+            panic!("expected typed capability");
+        };
+        let brightness = dimmer.parameters.as_mut().unwrap();
+        brightness.get_mut("brightness").unwrap().min = Some(200.0);
+
+        let request = DeviceRegistrationRequest {
+            device_id: "light-1".to_string(),
+            device_type: "light".to_string(),
+            name: "Hall light".to_string(),
+            manufacturer: None,
+            model: None,
+            capabilities,
+        };
+
+        let err = register_device(&state, &Tenant::default(), "probe-1", request)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, UaipError::InvalidParameter(_)));
+        assert!(err.to_string().contains("brightness"));
+    }
+
+    fn command(action: &str, parameters: serde_json::Value) -> CommandRequest {
+        CommandRequest {
+            action: action.to_string(),
+            parameters: Some(parameters),
+            priority: None,
+            preempt: false,
+        }
+    }
+
+    #[test]
+    fn test_command_parameters_checked_against_capability_range() {
+        let capabilities = dimmer_capabilities();
+
+        let in_range = command("dimmer", serde_json::json!({ "brightness": 40 }));
+        assert!(validate_command("light-1", &capabilities, &in_range).is_ok());
+        let by_action = command("set", serde_json::json!({ "brightness": 40 }));
+        assert!(validate_command("light-1", &capabilities, &by_action).is_ok());
+
+        let out_of_range = command("dimmer", serde_json::json!({ "brightness": 140 }));
+        let err = validate_command("light-1", &capabilities, &out_of_range).unwrap_err();
+        assert!(matches!(err, UaipError::InvalidParameter(_)));
+
+        // Capabilities declared by name only do not check parameters
+        let untyped = command("status_led", serde_json::json!({ "brightness": 140 }));
+        assert!(validate_command("light-1", &capabilities, &untyped).is_ok());
+    }
+
+    #[test]
+    fn test_command_rejects_unsupported_action() {
+        let request = command("open_door", serde_json::json!({}));
+        let err = validate_command("light-1", &dimmer_capabilities(), &request).unwrap_err();
+
+        let message = err.to_string();
+        assert!(matches!(err, UaipError::InvalidParameter(_)));
+        assert!(message.contains("open_door"));
+        assert!(message.contains("status_led, dimmer"));
+    }
+
+    #[test]
+    fn test_command_rejects_malformed_parameters() {
+        let capabilities = dimmer_capabilities();
+
+        for parameters in [
+            serde_json::json!({ "brightness": "bright" }),
+            serde_json::json!({}),
+            serde_json::json!([40]),
+        ] {
+            let request = command("dimmer", parameters.clone());
+            let result = validate_command("light-1", &capabilities, &request);
+            assert!(
+                matches!(result, Err(UaipError::InvalidParameter(_))),
+                "{} should be rejected",
+                parameters
+            );
+        }
+    }
+
+    #[test]
+    fn test_device_list_query_defaults() {
+        let query = DeviceListQuery {
+            status: None,
+            manufacturer: None,
+            page: default_page(),
+            per_page: default_per_page(),
+            sort_by: default_sort_by(),
+            sort_order: default_sort_order(),
+        };
+
+        assert_eq!(query.page, 1);
+        assert_eq!(query.per_page, 50);
+        assert_eq!(query.sort_by, "registered_at");
+        assert_eq!(query.sort_order, "desc");
+    }
+
+    #[tokio::test]
+    async fn test_critical_command_dequeued_before_low() {
+        let queue = MessagePriorityQueue::new();
+        let command = |action: &str, priority: &str| CommandRequest {
+            action: action.to_string(),
+            parameters: Some(serde_json::json!({ "level": 1 })),
+            priority: Some(priority.to_string()),
+            preempt: false,
+        };
+
+        for (message_id, request) in [
+            ("msg-low", command("report_telemetry", "low")),
+            ("msg-critical", command("emergency_stop", "critical")),
+        ] {
+            let priority = request.priority.as_deref().unwrap().parse().unwrap();
+            queue
+                .push(command_message(
+                    &MessagingConfig::default(),
+                    "device-1",
+                    message_id,
+                    "corr",
+                    &request,
+                    priority,
+                ))
+                .await;
+        }
+
+        let first = queue.pop().await.unwrap();
+        assert_eq!(first.header.message_id, "msg-critical");
+        assert_eq!(first.header.priority, Priority::Critical);
+        assert_eq!(first.payload.capability.as_deref(), Some("emergency_stop"));
+        assert_eq!(first.header.recipient.id, "device-1");
+        assert_eq!(
+            first.payload.parameters.unwrap()["level"],
+            serde_json::json!(1)
+        );
+        assert_eq!(queue.pop().await.unwrap().header.message_id, "msg-low");
+    }
+
+    async fn queue_pending(state: &AppState, device_id: &str, message_id: &str) {
+        let request = command("open", serde_json::json!({}));
+        state
+            .command_log
+            .record(&DeviceCommand {
+                message_id: message_id.to_string(),
+                correlation_id: "corr".to_string(),
+                device_id: device_id.to_string(),
+                tenant_id: "default".to_string(),
+                action: request.action.clone(),
+                priority: "normal".to_string(),
+                parameters: serde_json::json!({}),
+                status: CommandStatus::Pending,
+                created_at: Utc::now(),
+                cancelled_at: None,
+                delivered_at: None,
+                completed_at: None,
+                result: None,
+            })
+            .await
+            .unwrap();
+        state
+            .message_queue
+            .push(command_message(
+                &state.messaging,
+                device_id,
+                message_id,
+                "corr",
+                &request,
+                Priority::Normal,
+            ))
+            .await;
+    }
+
+    #[tokio::test]
+    async fn test_configured_default_priority_applies_to_unspecified_commands() {
+        let defaults = MessagingConfig {
+            default_ttl_ms: 30_000,
+            default_qos: QosLevel::ExactlyOnce,
+            default_priority: Priority::High,
+            ..MessagingConfig::default()
+        };
+        let unspecified = command("open", serde_json::json!({}));
+        let unknown = CommandRequest {
+            priority: Some("urgent".to_string()),
+            ..command("open", serde_json::json!({}))
+        };
+        let low = CommandRequest {
+            priority: Some("low".to_string()),
+            ..command("open", serde_json::json!({}))
+        };
+        assert_eq!(command_priority(&unspecified, &defaults), Priority::High);
+        assert_eq!(command_priority(&unknown, &defaults), Priority::High);
+        assert_eq!(command_priority(&low, &defaults), Priority::Low);
+        assert_eq!(
+            command_priority(&unspecified, &MessagingConfig::default()),
+            Priority::Normal
+        );
+
+        let queue = MessagePriorityQueue::new();
+        let priority = command_priority(&unspecified, &defaults);
+        queue
+            .push(command_message(
+                &defaults,
+                "valve-1",
+                "msg-1",
+                "corr",
+                &unspecified,
+                priority,
+            ))
+            .await;
+        let queued = queue.pop().await.unwrap();
+        assert_eq!(queued.header.priority, Priority::High);
+        assert_eq!(queued.header.ttl, 30_000);
+        assert_eq!(queued.metadata.qos, QosLevel::ExactlyOnce);
+    }
+
+    #[tokio::test]
+    async fn test_critical_preempt_cancels_pending_commands() {
+        let state = AppState::new();
+        let tenant = Tenant::default();
+        queue_pending(&state, "valve-1", "msg-normal").await;
+        queue_pending(&state, "valve-2", "msg-other").await;
+
+        let cancelled =
+            preempt_pending_commands(&state, &tenant, "operator-1", "valve-1", "msg-stop")
+                .await
+                .unwrap();
+        assert_eq!(cancelled.len(), 1);
+        assert_eq!(cancelled[0].message_id, "msg-normal");
+        assert_eq!(cancelled[0].status, CommandStatus::Cancelled);
+
+        // Only the other device's command is still waiting for delivery
+        assert_eq!(state.message_queue.len().await, 1);
+        assert_eq!(
+            state.message_queue.peek().await.unwrap().header.message_id,
+            "msg-other"
+        );
+
+        let audit = state
+            .audit_log
+            .query(&crate::audit::AuditQuery {
+                action: Some("device.command.preempt".to_string()),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(audit.len(), 1);
+        assert_eq!(audit[0].actor, "operator-1");
+        assert_eq!(audit[0].target.as_deref(), Some("valve-1"));
+        assert_eq!(
+            audit[0].details,
+            serde_json::json!({"message_id": "msg-stop", "cancelled": ["msg-normal"]})
+        );
+
+        // Nothing left to preempt, nothing audited
+        assert!(
+            preempt_pending_commands(&state, &tenant, "operator-1", "valve-1", "msg-stop-2")
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    fn claims(scopes: &[&str]) -> Claims {
+        let manager = crate::handlers::auth::jwt_manager(3600);
+        let scopes = scopes.iter().map(|s| s.to_string()).collect();
+        let token = manager
+            .generate_token("user-1", "ops@example.com", scopes, None)
+            .unwrap();
+        manager.validate_token(&token).unwrap()
+    }
+
+    #[test]
+    fn test_role_may_toggle_light_but_not_unlock_door() {
+        let groups = DeviceGroups::new();
+        let lighting = claims(&["device:write", "command:light:toggle"]);
+
+        assert!(authorize_command(&lighting, "light-1", Some("light"), "toggle", &groups).is_ok());
+        let err =
+            authorize_command(&lighting, "door-1", Some("door"), "unlock", &groups).unwrap_err();
+        assert!(matches!(err, UaipError::AuthorizationFailed(ref m)
+            if m.contains("command:door:unlock")));
+        assert!(authorize_command(&lighting, "light-1", Some("light"), "dim", &groups).is_err());
+
+        // Wildcards and admins may command anything
+        let operator = claims(&["command:*:*"]);
+        assert!(authorize_command(&operator, "door-1", Some("door"), "unlock", &groups).is_ok());
+        let admin = claims(&["admin"]);
+        assert!(authorize_command(&admin, "door-1", None, "unlock", &groups).is_ok());
+    }
+
+    #[test]
+    fn test_group_scoped_command_grant() {
+        use uaip_core::group::DeviceGroup;
+
+        let groups = DeviceGroups::from_groups(vec![
+            DeviceGroup::new("lobby", "Lobby").with_devices(["door-1"]),
+            DeviceGroup::new("building", "Building").with_subgroups(["lobby"]),
+        ])
+        .unwrap();
+        let concierge = claims(&["group:building:command:unlock"]);
+
+        assert!(authorize_command(&concierge, "door-1", Some("door"), "unlock", &groups).is_ok());
+        assert!(authorize_command(&concierge, "door-2", Some("door"), "unlock", &groups).is_err());
+        assert!(authorize_command(&concierge, "door-1", Some("door"), "lock", &groups).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_preempt_requires_critical_priority() {
+        let state = AppState::new();
+        let request = CommandRequest {
+            priority: Some("high".to_string()),
+            preempt: true,
+            ..command("open", serde_json::json!({}))
+        };
+
+        let admin = claims(&["admin"]);
+        let err = send_command(&state, &Tenant::default(), &admin, "valve-1", &request)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("critical"));
+    }
+}
@@ -0,0 +1,587 @@
+//! Media operations: uploads, their post-processing jobs, and device streams
+
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::sync::Arc;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use uaip_core::device::{VideoStreamSource, VIDEO_STREAM_CAPABILITY};
+use uaip_core::error::UaipError;
+use uaip_orchestrator::media::{
+    AccessLevel, MediaDimensions, MediaFile, MediaProcessingJob, MediaType, ProcessingOperation,
+    StreamConfig, StreamProtocol, StreamQuality,
+};
+use uaip_orchestrator::media_processing::default_thumbnail;
+
+use crate::api::rest::{AppState, CapabilityDeclaration};
+use crate::services::devices::capability_declarations;
+use crate::tenant::Tenant;
+
+/// Upload media file request
+#[derive(Debug, Deserialize)]
+pub struct UploadMediaRequest {
+    pub filename: String,
+    pub media_type: MediaType,
+    pub format: String,
+    pub mime_type: String,
+    pub size_bytes: u64,
+    pub duration_secs: Option<f64>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub codec_video: Option<String>,
+    pub codec_audio: Option<String>,
+    pub bitrate_kbps: Option<u32>,
+    pub framerate_fps: Option<f32>,
+    pub storage_path: String,
+    pub url: Option<String>,
+    pub thumbnail_url: Option<String>,
+    pub tags: Vec<String>,
+    pub source_device_id: Option<Uuid>,
+    pub access_level: Option<AccessLevel>,
+}
+
+/// Start a stream from a camera device
+#[derive(Debug, Default, Deserialize)]
+pub struct DeviceStreamRequest {
+    /// One of the device's advertised protocols; defaults to the first supported one
+    pub protocol: Option<StreamProtocol>,
+    /// One of the device's advertised resolutions; defaults to the first one
+    pub resolution: Option<String>,
+}
+
+/// Media file response
+#[derive(Debug, Serialize)]
+pub struct MediaFileResponse {
+    pub id: Uuid,
+    pub filename: String,
+    pub media_type: String,
+    pub format: String,
+    pub mime_type: String,
+    pub size_bytes: u64,
+    pub duration_secs: Option<f64>,
+    pub dimensions: Option<MediaDimensions>,
+    pub storage_path: String,
+    pub url: Option<String>,
+    pub thumbnail_url: Option<String>,
+    pub tags: Vec<String>,
+    pub status: String,
+    pub uploaded_at: String,
+}
+
+/// Upload a media file owned by the tenant
+///
+/// Probing and thumbnail generation continue in the background after this returns.
+pub async fn upload_media(
+    state: &Arc<AppState>,
+    tenant: &Tenant,
+    request: UploadMediaRequest,
+) -> Result<MediaFileResponse, UaipError> {
+    info!("Uploading media file: {}", request.filename);
+
+    let media_id = Uuid::new_v4();
+    let access_level = request.access_level.unwrap_or(AccessLevel::Private);
+
+    // Store in database if available
+    if let Some(pool) = &state.db_pool {
+        match sqlx::query(
+            r#"
+            INSERT INTO media_files (
+                id, filename, media_type, format, mime_type, size_bytes,
+                duration_secs, width, height, codec_video, codec_audio,
+                bitrate_kbps, framerate_fps, storage_path, url, thumbnail_url,
+                tags, status, source_device_id, access_level, tenant_id
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21)
+            "#,
+        )
+        .bind(media_id)
+        .bind(&request.filename)
+        .bind(format!("{:?}", request.media_type).to_lowercase())
+        .bind(&request.format)
+        .bind(&request.mime_type)
+        .bind(request.size_bytes as i64)
+        .bind(request.duration_secs)
+        .bind(request.width.map(|w| w as i32))
+        .bind(request.height.map(|h| h as i32))
+        .bind(&request.codec_video)
+        .bind(&request.codec_audio)
+        .bind(request.bitrate_kbps.map(|b| b as i32))
+        .bind(request.framerate_fps)
+        .bind(&request.storage_path)
+        .bind(&request.url)
+        .bind(&request.thumbnail_url)
+        .bind(&request.tags)
+        .bind("pending")
+        .bind(request.source_device_id)
+        .bind(format!("{:?}", access_level).to_lowercase())
+        .bind(tenant.id())
+        .execute(pool)
+        .await
+        {
+            Ok(_) => {
+                info!("Stored media file {} in database", media_id);
+            }
+            Err(e) => {
+                error!("Failed to store media file in database: {}", e);
+                return Err(UaipError::DatabaseError(format!(
+                    "Failed to store media: {}",
+                    e
+                )));
+            }
+        }
+    }
+
+    let dimensions = match (request.width, request.height) {
+        (Some(width), Some(height)) => Some(MediaDimensions { width, height }),
+        _ => None,
+    };
+
+    // Probe the file and generate a poster frame in the background
+    let mut media = MediaFile::new(
+        request.filename.clone(),
+        request.media_type,
+        request.format.clone(),
+    );
+    media.id = media_id;
+    media.storage_path = request.storage_path.clone();
+    media.duration_secs = request.duration_secs;
+    media.dimensions = dimensions;
+    media.thumbnail_url = request.thumbnail_url.clone();
+    tokio::spawn(process_upload(state.clone(), media));
+
+    Ok(MediaFileResponse {
+        id: media_id,
+        filename: request.filename,
+        media_type: format!("{:?}", request.media_type),
+        format: request.format,
+        mime_type: request.mime_type,
+        size_bytes: request.size_bytes,
+        duration_secs: request.duration_secs,
+        dimensions,
+        storage_path: request.storage_path,
+        url: request.url,
+        thumbnail_url: request.thumbnail_url,
+        tags: request.tags,
+        status: "pending".to_string(),
+        uploaded_at: chrono::Utc::now().to_rfc3339(),
+    })
+}
+
+/// Run post-upload jobs, recording them in the database if available
+///
+/// Metadata analysis replaces the client's claims with probed values; videos
+/// uploaded without a thumbnail then get a poster frame.
+async fn process_upload(state: Arc<AppState>, mut media: MediaFile) {
+    let pool = state.db_pool.as_ref();
+
+    if media.media_type != MediaType::Document {
+        let mut job = MediaProcessingJob::new(media.id, ProcessingOperation::AnalyzeMetadata);
+        record_job_start(pool, &job).await;
+        match state.metadata_analyzer.run(&mut job, &mut media).await {
+            Ok(()) => {
+                info!("Analyzed metadata for media {}", media.id);
+                store_metadata(pool, &media).await;
+            }
+            Err(e) => warn!("Metadata analysis failed for media {}: {}", media.id, e),
+        }
+        record_job_result(pool, &job).await;
+    }
+
+    if media.media_type == MediaType::Video && media.thumbnail_url.is_none() {
+        let mut job = MediaProcessingJob::new(media.id, default_thumbnail(&media));
+        record_job_start(pool, &job).await;
+        match state.thumbnails.run(&mut job, &mut media).await {
+            Ok(()) => {
+                info!("Generated thumbnail for media {}", media.id);
+                if let (Some(pool), Some(url)) = (pool, &media.thumbnail_url) {
+                    if let Err(e) =
+                        sqlx::query("UPDATE media_files SET thumbnail_url = $1 WHERE id = $2")
+                            .bind(url)
+                            .bind(media.id)
+                            .execute(pool)
+                            .await
+                    {
+                        error!("Failed to store thumbnail URL for {}: {}", media.id, e);
+                    }
+                }
+            }
+            Err(e) => warn!("Thumbnail generation failed for media {}: {}", media.id, e),
+        }
+        record_job_result(pool, &job).await;
+    }
+}
+
+/// Configure a stream from one of the tenant's camera devices
+///
+/// The device must advertise a `video.stream` capability; the stream is served from
+/// the source it describes.
+pub async fn start_device_stream(
+    state: &AppState,
+    tenant: &Tenant,
+    device_id: &str,
+    request: &DeviceStreamRequest,
+) -> Result<StreamConfig, UaipError> {
+    let capabilities: Option<serde_json::Value> = sqlx::query_scalar(
+        "SELECT capabilities FROM devices WHERE device_id = $1 AND tenant_id = $2",
+    )
+    .bind(device_id)
+    .bind(tenant.id())
+    .fetch_optional(state.db()?)
+    .await
+    .map_err(|e| {
+        error!("Failed to fetch device capabilities: {}", e);
+        UaipError::InternalError("Failed to query devices".to_string())
+    })?;
+    let capabilities = capabilities
+        .ok_or_else(|| UaipError::NotFound(format!("Device {} not found", device_id)))?;
+
+    let config = device_stream_config(device_id, &capabilities, request)?;
+    info!(
+        "Configured {:?} stream {} from device {}",
+        config.protocol, config.id, device_id
+    );
+    Ok(config)
+}
+
+/// Build a device stream from the source advertised in its stored capabilities
+pub fn device_stream_config(
+    device_id: &str,
+    capabilities: &serde_json::Value,
+    request: &DeviceStreamRequest,
+) -> Result<StreamConfig, UaipError> {
+    let source = capability_declarations(capabilities)
+        .into_iter()
+        .find_map(|declaration| match declaration {
+            CapabilityDeclaration::Typed(c) if c.name == VIDEO_STREAM_CAPABILITY => c.stream,
+            _ => None,
+        })
+        .ok_or_else(|| {
+            UaipError::InvalidParameter(format!(
+                "device '{}' does not advertise a {} capability",
+                device_id, VIDEO_STREAM_CAPABILITY
+            ))
+        })?;
+
+    let protocol = match request.protocol {
+        Some(protocol) if source.supports_protocol(&protocol_name(protocol)) => protocol,
+        Some(protocol) => {
+            return Err(UaipError::InvalidParameter(format!(
+                "device '{}' does not stream over {} (protocols: [{}])",
+                device_id,
+                protocol_name(protocol),
+                source.protocols.join(", ")
+            )))
+        }
+        None => first_known_protocol(&source).ok_or_else(|| {
+            UaipError::InvalidParameter(format!(
+                "device '{}' advertises no supported streaming protocol",
+                device_id
+            ))
+        })?,
+    };
+
+    let resolution = match &request.resolution {
+        Some(resolution) if source.resolutions.contains(resolution) => Some(resolution),
+        Some(resolution) => {
+            return Err(UaipError::InvalidParameter(format!(
+                "device '{}' does not stream at {} (resolutions: [{}])",
+                device_id,
+                resolution,
+                source.resolutions.join(", ")
+            )))
+        }
+        None => source.resolutions.first(),
+    };
+
+    let mut config = StreamConfig::from_device(device_id, &source.url, protocol);
+    if let Some((_, height)) = resolution.and_then(|r| VideoStreamSource::parse_resolution(r)) {
+        // A camera produces one resolution per stream
+        config.quality = StreamQuality::for_height(height);
+        config.adaptive = false;
+        config.quality_levels = vec![config.quality];
+    }
+    Ok(config)
+}
+
+/// Protocol name as devices advertise it
+fn protocol_name(protocol: StreamProtocol) -> String {
+    match serde_json::to_value(protocol) {
+        Ok(serde_json::Value::String(name)) => name,
+        _ => format!("{:?}", protocol),
+    }
+}
+
+fn first_known_protocol(source: &VideoStreamSource) -> Option<StreamProtocol> {
+    source
+        .protocols
+        .iter()
+        .find_map(|p| serde_json::from_value(serde_json::json!(p.to_uppercase())).ok())
+}
+
+/// Store probed technical metadata for a media file
+async fn store_metadata(pool: Option<&PgPool>, media: &MediaFile) {
+    let Some(pool) = pool else {
+        return;
+    };
+
+    let codec = media.codec.as_ref();
+    if let Err(e) = sqlx::query(
+        "UPDATE media_files
+         SET duration_secs = $1, width = $2, height = $3, codec_video = $4, codec_audio = $5,
+             bitrate_kbps = $6, framerate_fps = $7
+         WHERE id = $8",
+    )
+    .bind(media.duration_secs)
+    .bind(media.dimensions.map(|d| d.width as i32))
+    .bind(media.dimensions.map(|d| d.height as i32))
+    .bind(codec.and_then(|c| c.video.as_deref()))
+    .bind(codec.and_then(|c| c.audio.as_deref()))
+    .bind(media.bitrate_kbps.map(|b| b as i32))
+    .bind(media.framerate_fps)
+    .bind(media.id)
+    .execute(pool)
+    .await
+    {
+        error!("Failed to store metadata for {}: {}", media.id, e);
+    }
+}
+
+/// Insert a processing job as running
+async fn record_job_start(pool: Option<&PgPool>, job: &MediaProcessingJob) {
+    let Some(pool) = pool else {
+        return;
+    };
+
+    let config = serde_json::to_value(&job.operation).unwrap_or_default();
+    if let Err(e) = sqlx::query(
+        "INSERT INTO media_processing_jobs (id, media_id, operation_type, operation_config, status)
+         VALUES ($1, $2, $3, $4, 'running')",
+    )
+    .bind(job.id)
+    .bind(job.media_id)
+    .bind(config["type"].as_str().unwrap_or_default())
+    .bind(&config)
+    .execute(pool)
+    .await
+    {
+        error!("Failed to record media job {}: {}", job.id, e);
+    }
+}
+
+/// Store the outcome of a processing job
+async fn record_job_result(pool: Option<&PgPool>, job: &MediaProcessingJob) {
+    let Some(pool) = pool else {
+        return;
+    };
+
+    if let Err(e) = sqlx::query(
+        "UPDATE media_processing_jobs
+         SET status = $1, progress = $2, error_message = $3, started_at = $4, completed_at = $5
+         WHERE id = $6",
+    )
+    .bind(format!("{:?}", job.status).to_lowercase())
+    .bind(job.progress)
+    .bind(&job.error)
+    .bind(job.started_at)
+    .bind(job.completed_at)
+    .bind(job.id)
+    .execute(pool)
+    .await
+    {
+        error!("Failed to update media job {}: {}", job.id, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uaip_orchestrator::media_processing::{
+        FrameExtractor, LocalMediaStorage, MediaProbe, MediaProber, MetadataAnalyzer,
+        ThumbnailGenerator,
+    };
+
+    /// Records extraction requests and signals each one
+    #[derive(Default)]
+    struct RecordingExtractor {
+        paths: std::sync::Mutex<Vec<String>>,
+        extracted: tokio::sync::Notify,
+    }
+
+    #[async_trait::async_trait]
+    impl FrameExtractor for RecordingExtractor {
+        async fn extract_frame(
+            &self,
+            video_path: &str,
+            _timestamp_secs: f32,
+            _width: u32,
+            _height: u32,
+        ) -> uaip_core::error::Result<Vec<u8>> {
+            self.paths.lock().unwrap().push(video_path.to_string());
+            self.extracted.notify_one();
+            Ok(vec![0xFF, 0xD8])
+        }
+    }
+
+    /// Reports fixed metadata and records the probed paths
+    #[derive(Default)]
+    struct RecordingProber {
+        paths: std::sync::Mutex<Vec<String>>,
+    }
+
+    #[async_trait::async_trait]
+    impl MediaProber for RecordingProber {
+        async fn probe(&self, path: &str) -> uaip_core::error::Result<MediaProbe> {
+            self.paths.lock().unwrap().push(path.to_string());
+            Ok(MediaProbe {
+                duration_secs: Some(12.5),
+                dimensions: Some(MediaDimensions::new(1280, 720)),
+                ..MediaProbe::default()
+            })
+        }
+    }
+
+    fn camera_capabilities() -> serde_json::Value {
+        serde_json::json!([
+            "snapshot",
+            {
+                "name": "video.stream",
+                "capability_type": "video_stream",
+                "is_primary": true,
+                "supported_actions": [],
+                "stream": {
+                    "url": "rtsp://10.0.0.7/main",
+                    "protocols": ["rtsp", "hls", "webrtc"],
+                    "resolutions": ["1920x1080", "640x480"]
+                }
+            }
+        ])
+    }
+
+    #[test]
+    fn test_camera_stream_uses_advertised_source() {
+        let config = device_stream_config(
+            "cam-1",
+            &camera_capabilities(),
+            &DeviceStreamRequest::default(),
+        )
+        .unwrap();
+        assert_eq!(config.source_device.as_deref(), Some("cam-1"));
+        assert_eq!(config.stream_url, "rtsp://10.0.0.7/main");
+        assert!(config.is_live);
+        assert!(config.media_id.is_nil());
+        // RTSP is not a hub protocol; HLS is the first supported one
+        assert_eq!(config.protocol, StreamProtocol::Hls);
+        assert_eq!(config.quality, StreamQuality::FullHd);
+
+        let request = DeviceStreamRequest {
+            protocol: Some(StreamProtocol::WebRtc),
+            resolution: Some("640x480".to_string()),
+        };
+        let config = device_stream_config("cam-1", &camera_capabilities(), &request).unwrap();
+        assert_eq!(config.protocol, StreamProtocol::WebRtc);
+        assert_eq!(config.quality, StreamQuality::Medium);
+
+        for request in [
+            DeviceStreamRequest {
+                protocol: Some(StreamProtocol::Rtmp),
+                resolution: None,
+            },
+            DeviceStreamRequest {
+                protocol: None,
+                resolution: Some("3840x2160".to_string()),
+            },
+        ] {
+            assert!(device_stream_config("cam-1", &camera_capabilities(), &request).is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_non_camera_device_cannot_stream() {
+        let thermostat = serde_json::json!(["temperature", "set_temperature"]);
+        let err = device_stream_config("thermo-1", &thermostat, &DeviceStreamRequest::default())
+            .unwrap_err();
+        assert!(matches!(err, UaipError::InvalidParameter(_)));
+        assert!(err.to_string().contains("video.stream"));
+
+        // A bare `video.stream` name does not describe a source either
+        let named = serde_json::json!(["video.stream"]);
+        assert!(device_stream_config("cam-2", &named, &DeviceStreamRequest::default()).is_err());
+
+        let result = start_device_stream(
+            &AppState::new(),
+            &Tenant::default(),
+            "cam-1",
+            &DeviceStreamRequest::default(),
+        )
+        .await;
+        assert!(matches!(result, Err(UaipError::NotConfigured(_))));
+    }
+
+    fn upload_request(media_type: &str, storage_path: &str) -> UploadMediaRequest {
+        serde_json::from_value(serde_json::json!({
+            "filename": "clip",
+            "media_type": media_type,
+            "format": "mp4",
+            "mime_type": "video/mp4",
+            "size_bytes": 1024,
+            "storage_path": storage_path,
+            "tags": [],
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_video_upload_triggers_thumbnail() {
+        let root = std::env::temp_dir().join(format!("uaip-media-{}", Uuid::new_v4()));
+        let extractor = Arc::new(RecordingExtractor::default());
+        let prober = Arc::new(RecordingProber::default());
+        let state = Arc::new(
+            AppState::new()
+                .with_thumbnail_generator(ThumbnailGenerator::new(
+                    extractor.clone(),
+                    Arc::new(LocalMediaStorage::new(&root, "/media")),
+                ))
+                .with_metadata_analyzer(MetadataAnalyzer::new(prober.clone())),
+        );
+
+        let image = upload_media(
+            &state,
+            &Tenant::default(),
+            upload_request("image", "/p.png"),
+        )
+        .await
+        .unwrap();
+        assert!(image.thumbnail_url.is_none());
+        let video = upload_media(
+            &state,
+            &Tenant::default(),
+            upload_request("video", "/v.mp4"),
+        )
+        .await
+        .unwrap();
+
+        tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            extractor.extracted.notified(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(*extractor.paths.lock().unwrap(), vec!["/v.mp4".to_string()]);
+
+        // The video is probed before its frame is taken
+        assert!(prober.paths.lock().unwrap().contains(&"/v.mp4".to_string()));
+
+        // The job stores the frame after extraction; wait for it to land
+        let thumbnail = root.join(format!("thumbnails/{}.jpg", video.id));
+        for _ in 0..50 {
+            if thumbnail.exists() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        assert!(thumbnail.exists());
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
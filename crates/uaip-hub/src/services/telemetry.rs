@@ -0,0 +1,696 @@
+//! Telemetry operations: storing readings, publishing them, evaluating rules,
+//! flagging anomalies and replaying stored readings
+
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use tokio::sync::MutexGuard;
+
+use uaip_core::device::DeviceStatus;
+use uaip_core::error::UaipError;
+use uaip_orchestrator::automation::{
+    run_actions, AnomalyResult, AutomationEngine, IngestResult, RecordedReading, ReplayReport,
+};
+use uaip_orchestrator::rule_engine::EvaluationContext;
+
+use crate::api::rest::AppState;
+use crate::handlers::telemetry::{
+    evaluate_batch, HeartbeatRequest, HeartbeatResponse, ReadingResult, TelemetryBatchResponse,
+    TelemetryEvent, TelemetryReading, MAX_BATCH_SIZE,
+};
+use crate::tenant::Tenant;
+
+/// Record a batch of readings for a device
+///
+/// Readings are checked against the device type's telemetry schema and carry its units.
+/// Accepted readings are stored when a database is configured, published to live
+/// subscribers, and evaluated once as a batch against the tenant's rules and unscoped
+/// rules. Numeric readings are then checked for anomalies. The caller is responsible for
+/// checking that the device exists.
+pub async fn record_readings(
+    state: &AppState,
+    tenant: &Tenant,
+    device_id: &str,
+    readings: &[TelemetryReading],
+) -> Result<TelemetryBatchResponse, UaipError> {
+    let now = Utc::now();
+    let device_type = if state.telemetry_schemas.is_empty() {
+        None
+    } else {
+        device_type(state, tenant, device_id).await?
+    };
+    let schema = state
+        .telemetry_schemas
+        .for_device_type(device_type.as_deref());
+    let annotated;
+    let readings = match schema {
+        Some(schema) => {
+            annotated = schema.annotate(readings);
+            &annotated[..]
+        }
+        None => readings,
+    };
+
+    let (results, context) = evaluate_batch(device_id, readings, schema, now);
+    let context = context.with_tenant(tenant.id().to_string());
+
+    if let Some(db_pool) = &state.db_pool {
+        store_readings(db_pool, device_id, readings, &results, now).await?;
+    }
+
+    // Publish to live subscribers; sending only fails when nobody is listening
+    for (reading, result) in readings.iter().zip(&results) {
+        if result.accepted {
+            let _ = state.telemetry_feed.send(TelemetryEvent {
+                tenant_id: tenant.id().to_string(),
+                device_id: device_id.to_string(),
+                metric: reading.metric.clone(),
+                value: reading.value.clone(),
+                unit: reading.unit.clone(),
+                recorded_at: reading.timestamp.unwrap_or(now),
+            });
+        }
+    }
+
+    let accepted = results.iter().filter(|r| r.accepted).count();
+
+    // Evaluate rules once against the whole batch
+    let (triggered_rules, mut scenario_executions) = if accepted > 0 {
+        match run_automation(state, &context).await {
+            Ok(result) => (result.triggered_rules, result.scenario_executions),
+            Err(e) => {
+                tracing::warn!("Rule evaluation failed for {}: {}", device_id, e);
+                (Vec::new(), Vec::new())
+            }
+        }
+    } else {
+        (Vec::new(), Vec::new())
+    };
+
+    let anomalies = match detect_anomalies(state, device_id, readings, &results, now).await {
+        Ok(result) => {
+            scenario_executions.extend(result.scenario_executions);
+            result.anomalies
+        }
+        Err(e) => {
+            tracing::warn!("Anomaly detection failed for {}: {}", device_id, e);
+            Vec::new()
+        }
+    };
+
+    tracing::debug!(
+        "Telemetry batch from {}: {} accepted, {} rejected",
+        device_id,
+        accepted,
+        results.len() - accepted
+    );
+
+    Ok(TelemetryBatchResponse {
+        device_id: device_id.to_string(),
+        accepted,
+        rejected: results.len() - accepted,
+        results,
+        triggered_rules,
+        scenario_executions,
+        anomalies,
+    })
+}
+
+/// Record a device heartbeat and the telemetry bundled with it
+///
+/// Updates the device's status and `last_seen` when a database is configured, failing
+/// if the device is unknown to the tenant or deactivated. Bundled readings are then
+/// handled exactly like a telemetry batch.
+pub async fn record_heartbeat(
+    state: &AppState,
+    tenant: &Tenant,
+    device_id: &str,
+    request: HeartbeatRequest,
+) -> Result<HeartbeatResponse, UaipError> {
+    let status = request.status.unwrap_or(DeviceStatus::Online);
+    let status_name = match status {
+        DeviceStatus::Online => "online",
+        DeviceStatus::Error => "error",
+        DeviceStatus::Maintenance => "maintenance",
+        DeviceStatus::Offline | DeviceStatus::Deactivated => {
+            return Err(UaipError::InvalidParameter(
+                "devices may only report online, error or maintenance".to_string(),
+            ))
+        }
+    };
+    if request.telemetry.len() > MAX_BATCH_SIZE {
+        return Err(UaipError::InvalidParameter(format!(
+            "batch exceeds {} readings",
+            MAX_BATCH_SIZE
+        )));
+    }
+
+    let now = Utc::now();
+    if let Some(db_pool) = &state.db_pool {
+        let updated = sqlx::query(
+            "UPDATE devices SET status = $1, last_seen = $2
+             WHERE device_id = $3 AND tenant_id = $4 AND status <> 'deactivated'",
+        )
+        .bind(status_name)
+        .bind(now)
+        .bind(device_id)
+        .bind(tenant.id())
+        .execute(db_pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to record heartbeat: {}", e);
+            UaipError::InternalError("Failed to record heartbeat".to_string())
+        })?;
+        if updated.rows_affected() == 0 {
+            return Err(UaipError::DeviceNotFound(format!(
+                "Device '{}' not found",
+                device_id
+            )));
+        }
+    }
+
+    let telemetry = if request.telemetry.is_empty() {
+        None
+    } else {
+        Some(record_readings(state, tenant, device_id, &request.telemetry).await?)
+    };
+
+    Ok(HeartbeatResponse {
+        device_id: device_id.to_string(),
+        status,
+        last_seen: now,
+        telemetry,
+    })
+}
+
+/// Evaluate rules against a context and run the scenarios they trigger
+///
+/// The automation lock is released while scenario actions run. Finished scenario
+/// executions are moved to the persistent execution history.
+pub async fn run_automation(
+    state: &AppState,
+    context: &EvaluationContext,
+) -> Result<IngestResult, UaipError> {
+    let (triggered_rules, pending) = state.automation.lock().await.start_telemetry(context)?;
+    let completed = run_actions(pending).await;
+
+    let mut automation = state.automation.lock().await;
+    let scenario_executions = automation.complete(completed)?;
+    archive_executions(state, automation, &scenario_executions).await;
+
+    Ok(IngestResult {
+        triggered_rules,
+        scenario_executions,
+    })
+}
+
+/// Most stored readings one replay reads
+pub const MAX_REPLAY_READINGS: i64 = 10_000;
+
+/// Replay a device's stored readings from `from` (inclusive) to `to` (exclusive)
+/// through a copy of the automation rules
+///
+/// Nothing is dispatched and live rule state is untouched; the report lists every rule
+/// that would have triggered and the scenarios it would have started.
+pub async fn replay_telemetry(
+    state: &AppState,
+    tenant: &Tenant,
+    device_id: &str,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    speed: Option<f64>,
+) -> Result<ReplayReport, UaipError> {
+    let db_pool = state.db()?;
+
+    let device_exists: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM devices WHERE device_id = $1 AND tenant_id = $2)",
+    )
+    .bind(device_id)
+    .bind(tenant.id())
+    .fetch_one(db_pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to query device: {}", e);
+        UaipError::InternalError("Failed to verify device".to_string())
+    })?;
+    if !device_exists {
+        return Err(UaipError::DeviceNotFound(format!(
+            "Device '{}' not found",
+            device_id
+        )));
+    }
+
+    let rows: Vec<(String, serde_json::Value, DateTime<Utc>)> = sqlx::query_as(
+        "SELECT metric, value, recorded_at FROM device_telemetry
+         WHERE device_id = $1 AND recorded_at >= $2 AND recorded_at < $3
+         ORDER BY recorded_at, id
+         LIMIT $4",
+    )
+    .bind(device_id)
+    .bind(from)
+    .bind(to)
+    .bind(MAX_REPLAY_READINGS + 1)
+    .fetch_all(db_pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to load telemetry: {}", e);
+        UaipError::InternalError("Failed to load telemetry".to_string())
+    })?;
+    if rows.len() as i64 > MAX_REPLAY_READINGS {
+        return Err(UaipError::InvalidParameter(format!(
+            "window holds more than {} readings",
+            MAX_REPLAY_READINGS
+        )));
+    }
+
+    let readings: Vec<RecordedReading> = rows
+        .into_iter()
+        .map(|(metric, value, recorded_at)| RecordedReading {
+            metric,
+            value,
+            recorded_at,
+        })
+        .collect();
+
+    // Copy the rules so a paced replay does not hold up live ingestion
+    let sandbox = state.automation.lock().await.sandbox()?;
+    sandbox
+        .replay(device_id, Some(tenant.id()), &readings, speed)
+        .await
+}
+
+/// Feed accepted numeric readings to the anomaly detector and run the scenarios the
+/// resulting anomaly events trigger
+async fn detect_anomalies(
+    state: &AppState,
+    device_id: &str,
+    readings: &[TelemetryReading],
+    results: &[ReadingResult],
+    now: DateTime<Utc>,
+) -> Result<AnomalyResult, UaipError> {
+    let numeric = readings
+        .iter()
+        .zip(results)
+        .filter(|(_, result)| result.accepted)
+        .filter_map(|(reading, _)| {
+            let value = reading.value.as_f64()?;
+            Some((
+                reading.metric.as_str(),
+                value,
+                reading.timestamp.unwrap_or(now),
+            ))
+        });
+
+    let (anomalies, pending) = state
+        .automation
+        .lock()
+        .await
+        .start_anomalies(device_id, numeric)?;
+    let completed = run_actions(pending).await;
+
+    let mut automation = state.automation.lock().await;
+    let scenario_executions = automation.complete(completed)?;
+    archive_executions(state, automation, &scenario_executions).await;
+
+    Ok(AnomalyResult {
+        anomalies,
+        scenario_executions,
+    })
+}
+
+/// Move finished scenario executions to the persistent execution history
+pub(crate) async fn archive_executions(
+    state: &AppState,
+    mut automation: MutexGuard<'_, AutomationEngine>,
+    execution_ids: &[String],
+) {
+    let finished: Vec<_> = execution_ids
+        .iter()
+        .filter_map(|id| automation.scenario_engine.take_execution(id))
+        .collect();
+    drop(automation);
+    for execution in &finished {
+        state.scenario_executions.record_or_warn(execution).await;
+    }
+}
+
+/// Type of a device, from its registration metadata; `None` without a database
+async fn device_type(
+    state: &AppState,
+    tenant: &Tenant,
+    device_id: &str,
+) -> Result<Option<String>, UaipError> {
+    let Some(db_pool) = &state.db_pool else {
+        return Ok(None);
+    };
+    let device_type: Option<Option<String>> = sqlx::query_scalar(
+        "SELECT metadata->>'device_type' FROM devices WHERE device_id = $1 AND tenant_id = $2",
+    )
+    .bind(device_id)
+    .bind(tenant.id())
+    .fetch_optional(db_pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to query device type: {}", e);
+        UaipError::InternalError("Failed to verify device".to_string())
+    })?;
+
+    Ok(device_type.flatten())
+}
+
+/// Insert all accepted readings in one transaction
+async fn store_readings(
+    db_pool: &PgPool,
+    device_id: &str,
+    readings: &[TelemetryReading],
+    results: &[ReadingResult],
+    now: DateTime<Utc>,
+) -> Result<(), UaipError> {
+    let mut tx = db_pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to begin transaction: {}", e);
+        UaipError::InternalError("Failed to store telemetry".to_string())
+    })?;
+
+    for (reading, result) in readings.iter().zip(results) {
+        if !result.accepted {
+            continue;
+        }
+
+        sqlx::query(
+            "INSERT INTO device_telemetry (device_id, metric, value, unit, recorded_at)
+             VALUES ($1, $2, $3, $4, $5)",
+        )
+        .bind(device_id)
+        .bind(&reading.metric)
+        .bind(&reading.value)
+        .bind(&reading.unit)
+        .bind(reading.timestamp.unwrap_or(now))
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to insert telemetry: {}", e);
+            UaipError::InternalError("Failed to store telemetry".to_string())
+        })?;
+    }
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit telemetry: {}", e);
+        UaipError::InternalError("Failed to store telemetry".to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use uaip_orchestrator::automation::AutomationEngine;
+    use uaip_orchestrator::rule_engine::{Condition, ConditionMode, Operator, Rule, RuleEngine};
+    use uaip_orchestrator::scenario::ScenarioEngine;
+
+    #[tokio::test]
+    async fn test_record_readings_publishes_accepted_without_database() {
+        let state = AppState::new();
+        let mut feed = state.telemetry_feed.subscribe();
+        let readings = vec![
+            TelemetryReading {
+                metric: "temperature".to_string(),
+                value: json!(21.5),
+                unit: Some("C".to_string()),
+                timestamp: None,
+            },
+            TelemetryReading {
+                metric: "humidity".to_string(),
+                value: json!(null),
+                unit: None,
+                timestamp: None,
+            },
+        ];
+
+        let report = record_readings(&state, &Tenant::default(), "sensor-1", &readings)
+            .await
+            .unwrap();
+        assert_eq!((report.accepted, report.rejected), (1, 1));
+
+        let event = feed.try_recv().unwrap();
+        assert_eq!(event.metric, "temperature");
+        assert_eq!(event.tenant_id, "default");
+        assert!(feed.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_tenant_rules_only_see_their_tenant() {
+        let mut rule_engine = RuleEngine::new();
+        rule_engine.add_rule(Rule {
+            id: "tenant-a-overheat".to_string(),
+            name: "Overheat".to_string(),
+            description: None,
+            enabled: true,
+            conditions: vec![Condition {
+                field: "temperature".to_string(),
+                operator: Operator::GreaterThan,
+                value: json!(30),
+                device_id: None,
+                group_id: None,
+                aggregate: None,
+            }],
+            actions: vec![],
+            condition_mode: ConditionMode::All,
+            priority: 1,
+            cooldown_seconds: None,
+            last_executed: None,
+            tenant_id: Some("tenant-a".to_string()),
+            metadata: HashMap::new(),
+        });
+        let state = AppState::new()
+            .with_automation(AutomationEngine::new(rule_engine, ScenarioEngine::new()));
+        let readings = vec![TelemetryReading {
+            metric: "temperature".to_string(),
+            value: json!(35.0),
+            unit: None,
+            timestamp: None,
+        }];
+
+        let report = record_readings(&state, &Tenant::new("tenant-b"), "sensor-b", &readings)
+            .await
+            .unwrap();
+        assert!(report.triggered_rules.is_empty());
+
+        let report = record_readings(&state, &Tenant::new("tenant-a"), "sensor-a", &readings)
+            .await
+            .unwrap();
+        assert_eq!(report.triggered_rules, vec!["tenant-a-overheat"]);
+    }
+
+    #[tokio::test]
+    async fn test_heartbeat_with_telemetry_feeds_rules() {
+        let mut rule_engine = RuleEngine::new();
+        rule_engine.add_rule(Rule {
+            id: "low-battery".to_string(),
+            name: "Low battery".to_string(),
+            description: None,
+            enabled: true,
+            conditions: vec![Condition {
+                field: "battery".to_string(),
+                operator: Operator::LessThan,
+                value: json!(15),
+                device_id: None,
+                group_id: None,
+                aggregate: None,
+            }],
+            actions: vec![],
+            condition_mode: ConditionMode::All,
+            priority: 1,
+            cooldown_seconds: None,
+            last_executed: None,
+            tenant_id: None,
+            metadata: HashMap::new(),
+        });
+        let state = AppState::new()
+            .with_automation(AutomationEngine::new(rule_engine, ScenarioEngine::new()));
+        let mut feed = state.telemetry_feed.subscribe();
+
+        let request = HeartbeatRequest {
+            status: Some(DeviceStatus::Error),
+            telemetry: vec![TelemetryReading {
+                metric: "battery".to_string(),
+                value: json!(9),
+                unit: Some("%".to_string()),
+                timestamp: None,
+            }],
+        };
+        let response = record_heartbeat(&state, &Tenant::default(), "tag-7", request)
+            .await
+            .unwrap();
+
+        assert_eq!(response.status, DeviceStatus::Error);
+        let report = response.telemetry.unwrap();
+        assert_eq!(report.accepted, 1);
+        assert_eq!(report.triggered_rules, vec!["low-battery"]);
+        assert_eq!(feed.try_recv().unwrap().metric, "battery");
+    }
+
+    #[tokio::test]
+    async fn test_heartbeat_only() {
+        let state = AppState::new();
+        let mut feed = state.telemetry_feed.subscribe();
+
+        let response = record_heartbeat(
+            &state,
+            &Tenant::default(),
+            "tag-7",
+            HeartbeatRequest::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status, DeviceStatus::Online);
+        assert!(response.telemetry.is_none());
+        assert!(feed.try_recv().is_err());
+
+        let offline = HeartbeatRequest {
+            status: Some(DeviceStatus::Offline),
+            ..Default::default()
+        };
+        let result = record_heartbeat(&state, &Tenant::default(), "tag-7", offline).await;
+        assert!(matches!(result, Err(UaipError::InvalidParameter(_))));
+    }
+
+    #[tokio::test]
+    async fn test_outlier_reading_is_reported_as_anomaly() {
+        use uaip_orchestrator::anomaly::{AnomalyConfig, AnomalyDetector};
+
+        let automation = AutomationEngine::default().with_anomaly_detector(AnomalyDetector::new(
+            AnomalyConfig {
+                min_samples: 10,
+                ..Default::default()
+            },
+        ));
+        let state = AppState::new().with_automation(automation);
+        let reading = |metric: &str, value| TelemetryReading {
+            metric: metric.to_string(),
+            value,
+            unit: None,
+            timestamp: None,
+        };
+
+        for i in 0..10 {
+            let readings = [
+                reading("temperature", json!(21.0 + f64::from(i % 2))),
+                reading("status", json!("ok")),
+            ];
+            let report = record_readings(&state, &Tenant::default(), "sensor-1", &readings)
+                .await
+                .unwrap();
+            assert!(report.anomalies.is_empty());
+        }
+
+        let readings = [reading("temperature", json!(45.0))];
+        let report = record_readings(&state, &Tenant::default(), "sensor-1", &readings)
+            .await
+            .unwrap();
+        assert_eq!(report.anomalies.len(), 1);
+        assert_eq!(report.anomalies[0].metric, "temperature");
+        assert!(report.anomalies[0].z_score > 3.0);
+    }
+
+    /// Notifier that records whether the automation engine was unlocked while it ran
+    struct LockProbe {
+        automation: Arc<tokio::sync::Mutex<AutomationEngine>>,
+        unlocked: Arc<std::sync::atomic::AtomicBool>,
+    }
+
+    #[async_trait::async_trait]
+    impl uaip_orchestrator::notification::Notifier for LockProbe {
+        fn channel(&self) -> &str {
+            "probe"
+        }
+
+        async fn send(
+            &self,
+            _notification: &uaip_orchestrator::notification::Notification,
+        ) -> uaip_core::error::Result<serde_json::Value> {
+            let unlocked = self.automation.try_lock().is_ok();
+            self.unlocked
+                .store(unlocked, std::sync::atomic::Ordering::SeqCst);
+            Ok(json!({}))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_scenario_actions_run_without_the_automation_lock() {
+        use uaip_orchestrator::scenario::{
+            Scenario, ScenarioAction, ScenarioActionConfig, ScenarioState, ScenarioTrigger,
+            TriggerType,
+        };
+
+        let state = AppState::new();
+        let unlocked = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        {
+            let mut automation = state.automation.lock().await;
+            automation.rule_engine.add_rule(Rule {
+                id: "overheat".to_string(),
+                name: "Overheat".to_string(),
+                description: None,
+                enabled: true,
+                conditions: vec![Condition {
+                    field: "temperature".to_string(),
+                    operator: Operator::GreaterThan,
+                    value: json!(30),
+                    device_id: None,
+                    group_id: None,
+                    aggregate: None,
+                }],
+                actions: vec![],
+                condition_mode: ConditionMode::All,
+                priority: 1,
+                cooldown_seconds: None,
+                last_executed: None,
+                tenant_id: None,
+                metadata: HashMap::new(),
+            });
+            automation.scenario_engine = ScenarioEngine::new().with_notifier(Arc::new(LockProbe {
+                automation: state.automation.clone(),
+                unlocked: unlocked.clone(),
+            }));
+            automation
+                .scenario_engine
+                .register_scenario(Scenario {
+                    id: "page-operator".to_string(),
+                    name: "Page operator".to_string(),
+                    description: None,
+                    enabled: true,
+                    triggers: vec![ScenarioTrigger {
+                        trigger_type: TriggerType::RuleTriggered,
+                        config: HashMap::from([("rule_id".to_string(), json!("overheat"))]),
+                        conditions: vec![],
+                    }],
+                    actions: vec![ScenarioActionConfig {
+                        action: ScenarioAction::SendNotification,
+                        parameters: HashMap::from([("channel".to_string(), json!("probe"))]),
+                        wait: true,
+                        timeout_seconds: None,
+                    }],
+                    state: ScenarioState::Active,
+                    metadata: HashMap::new(),
+                    execution_count: 0,
+                    last_triggered: None,
+                    last_result: None,
+                    last_result_summary: None,
+                    tenant_id: None,
+                    created_at: Utc::now(),
+                    updated_at: Utc::now(),
+                })
+                .unwrap();
+        }
+
+        let mut context = EvaluationContext::new().with_tenant("default".to_string());
+        context
+            .telemetry
+            .insert("temperature".to_string(), json!(35.0));
+        let result = run_automation(&state, &context).await.unwrap();
+
+        assert_eq!(result.triggered_rules, vec!["overheat"]);
+        assert_eq!(result.scenario_executions.len(), 1);
+        assert!(unlocked.load(std::sync::atomic::Ordering::SeqCst));
+    }
+}
@@ -0,0 +1,535 @@
+//! AI Session Persistence
+//!
+//! Persists AI session state and conversation context so sessions survive hub restarts.
+//! Sessions and the agents that own them are stored in Redis or PostgreSQL and rehydrated by
+//! the session manager on startup.
+
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Row};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::debug;
+use uuid::Uuid;
+
+use uaip_core::{
+    ai_agent::{AgentConfig, AgentType, AiAgent, AiSession, SessionState, SessionStats},
+    error::{Result, UaipError},
+};
+
+/// Redis key prefix for persisted sessions
+const REDIS_KEY_PREFIX: &str = "uaip:ai_session:";
+
+/// Redis set holding the IDs of all persisted sessions
+const REDIS_INDEX_KEY: &str = "uaip:ai_sessions";
+
+/// Redis key prefix for persisted agents
+const REDIS_AGENT_KEY_PREFIX: &str = "uaip:ai_agent:";
+
+/// Redis set holding the IDs of all persisted agents
+const REDIS_AGENT_INDEX_KEY: &str = "uaip:ai_agents";
+
+/// What to do when a session context grows beyond the configured cap
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TruncationPolicy {
+    /// Drop the oldest context entries until the context fits
+    DropOldest,
+
+    /// Reject the update that would exceed the cap
+    Reject,
+}
+
+/// Limits applied to session context
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContextLimits {
+    /// Maximum serialized context size in bytes
+    pub max_context_bytes: usize,
+
+    /// Policy applied when the cap is exceeded
+    pub truncation_policy: TruncationPolicy,
+}
+
+impl Default for ContextLimits {
+    fn default() -> Self {
+        Self {
+            max_context_bytes: 64 * 1024, // 64 KB
+            truncation_policy: TruncationPolicy::DropOldest,
+        }
+    }
+}
+
+/// Backend used to persist AI sessions
+#[derive(Clone)]
+pub enum SessionStore {
+    /// In-process store (no persistence across restarts, used for tests and local runs)
+    Memory {
+        sessions: Arc<RwLock<HashMap<Uuid, AiSession>>>,
+        agents: Arc<RwLock<HashMap<Uuid, AiAgent>>>,
+    },
+
+    /// Redis-backed store
+    Redis(redis::Client),
+
+    /// PostgreSQL-backed store (`ai_sessions` and `ai_agents` tables)
+    Postgres(PgPool),
+}
+
+impl SessionStore {
+    /// Create an in-memory store
+    pub fn memory() -> Self {
+        Self::Memory {
+            sessions: Arc::new(RwLock::new(HashMap::new())),
+            agents: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Create a Redis-backed store
+    pub fn redis(client: redis::Client) -> Self {
+        Self::Redis(client)
+    }
+
+    /// Create a PostgreSQL-backed store
+    pub fn postgres(pool: PgPool) -> Self {
+        Self::Postgres(pool)
+    }
+
+    /// Save (insert or replace) a session
+    pub async fn save(&self, session: &AiSession) -> Result<()> {
+        match self {
+            Self::Memory { sessions, .. } => {
+                sessions.write().await.insert(session.id, session.clone());
+            }
+            Self::Redis(client) => {
+                let mut conn = redis_connection(client).await?;
+                let value = serde_json::to_string(session)?;
+
+                redis::pipe()
+                    .set(redis_key(&session.id), value)
+                    .ignore()
+                    .sadd(REDIS_INDEX_KEY, session.id.to_string())
+                    .ignore()
+                    .query_async::<()>(&mut conn)
+                    .await
+                    .map_err(|e| UaipError::DatabaseError(format!("Redis error: {}", e)))?;
+            }
+            Self::Postgres(pool) => {
+                sqlx::query(
+                    r#"
+                    INSERT INTO ai_sessions (
+                        id, agent_id, state, started_at, last_activity_at, context_data,
+                        context_order, devices, commands_sent, responses_received,
+                        errors_count, bytes_sent, bytes_received, avg_response_time_ms
+                    )
+                    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+                    ON CONFLICT (id) DO UPDATE SET
+                        state = EXCLUDED.state,
+                        last_activity_at = EXCLUDED.last_activity_at,
+                        context_data = EXCLUDED.context_data,
+                        context_order = EXCLUDED.context_order,
+                        devices = EXCLUDED.devices,
+                        commands_sent = EXCLUDED.commands_sent,
+                        responses_received = EXCLUDED.responses_received,
+                        errors_count = EXCLUDED.errors_count,
+                        bytes_sent = EXCLUDED.bytes_sent,
+                        bytes_received = EXCLUDED.bytes_received,
+                        avg_response_time_ms = EXCLUDED.avg_response_time_ms
+                    "#,
+                )
+                .bind(session.id)
+                .bind(session.agent_id)
+                .bind(state_to_str(session.state))
+                .bind(session.started_at)
+                .bind(session.last_activity_at)
+                .bind(serde_json::to_value(&session.context)?)
+                .bind(serde_json::to_value(&session.context_order)?)
+                .bind(serde_json::to_value(&session.devices)?)
+                .bind(session.stats.commands_sent as i64)
+                .bind(session.stats.responses_received as i64)
+                .bind(session.stats.errors_count as i64)
+                .bind(session.stats.bytes_sent as i64)
+                .bind(session.stats.bytes_received as i64)
+                .bind(session.stats.avg_response_time_ms as f32)
+                .execute(pool)
+                .await
+                .map_err(|e| UaipError::DatabaseError(format!("Failed to save session: {}", e)))?;
+            }
+        }
+
+        debug!("Persisted session {}", session.id);
+        Ok(())
+    }
+
+    /// Load a single session
+    pub async fn load(&self, session_id: &Uuid) -> Result<Option<AiSession>> {
+        match self {
+            Self::Memory { sessions, .. } => Ok(sessions.read().await.get(session_id).cloned()),
+            Self::Redis(client) => {
+                let mut conn = redis_connection(client).await?;
+                let value: Option<String> = redis::cmd("GET")
+                    .arg(redis_key(session_id))
+                    .query_async(&mut conn)
+                    .await
+                    .map_err(|e| UaipError::DatabaseError(format!("Redis error: {}", e)))?;
+
+                match value {
+                    Some(json) => Ok(Some(serde_json::from_str(&json)?)),
+                    None => Ok(None),
+                }
+            }
+            Self::Postgres(pool) => {
+                let row = sqlx::query(&format!("{} WHERE id = $1", POSTGRES_SELECT))
+                    .bind(session_id)
+                    .fetch_optional(pool)
+                    .await
+                    .map_err(|e| {
+                        UaipError::DatabaseError(format!("Failed to load session: {}", e))
+                    })?;
+
+                row.map(|row| session_from_row(&row)).transpose()
+            }
+        }
+    }
+
+    /// Load all sessions that are not terminated
+    pub async fn load_active(&self) -> Result<Vec<AiSession>> {
+        let sessions = match self {
+            Self::Memory { sessions, .. } => sessions.read().await.values().cloned().collect(),
+            Self::Redis(client) => {
+                let mut conn = redis_connection(client).await?;
+                let ids: Vec<String> = redis::cmd("SMEMBERS")
+                    .arg(REDIS_INDEX_KEY)
+                    .query_async(&mut conn)
+                    .await
+                    .map_err(|e| UaipError::DatabaseError(format!("Redis error: {}", e)))?;
+
+                if ids.is_empty() {
+                    return Ok(Vec::new());
+                }
+
+                let keys: Vec<String> = ids
+                    .iter()
+                    .map(|id| format!("{}{}", REDIS_KEY_PREFIX, id))
+                    .collect();
+                let values: Vec<Option<String>> = redis::cmd("MGET")
+                    .arg(&keys)
+                    .query_async(&mut conn)
+                    .await
+                    .map_err(|e| UaipError::DatabaseError(format!("Redis error: {}", e)))?;
+
+                values
+                    .into_iter()
+                    .flatten()
+                    .map(|json| serde_json::from_str::<AiSession>(&json))
+                    .collect::<std::result::Result<Vec<_>, _>>()?
+            }
+            Self::Postgres(pool) => {
+                let rows = sqlx::query(&format!("{} WHERE state <> 'terminated'", POSTGRES_SELECT))
+                    .fetch_all(pool)
+                    .await
+                    .map_err(|e| {
+                        UaipError::DatabaseError(format!("Failed to load sessions: {}", e))
+                    })?;
+
+                rows.iter()
+                    .map(session_from_row)
+                    .collect::<Result<Vec<_>>>()?
+            }
+        };
+
+        Ok(sessions
+            .into_iter()
+            .filter(|s: &AiSession| s.state != SessionState::Terminated)
+            .collect())
+    }
+
+    /// Delete a session
+    pub async fn delete(&self, session_id: &Uuid) -> Result<()> {
+        match self {
+            Self::Memory { sessions, .. } => {
+                sessions.write().await.remove(session_id);
+            }
+            Self::Redis(client) => {
+                let mut conn = redis_connection(client).await?;
+                redis::pipe()
+                    .del(redis_key(session_id))
+                    .ignore()
+                    .srem(REDIS_INDEX_KEY, session_id.to_string())
+                    .ignore()
+                    .query_async::<()>(&mut conn)
+                    .await
+                    .map_err(|e| UaipError::DatabaseError(format!("Redis error: {}", e)))?;
+            }
+            Self::Postgres(pool) => {
+                sqlx::query("DELETE FROM ai_sessions WHERE id = $1")
+                    .bind(session_id)
+                    .execute(pool)
+                    .await
+                    .map_err(|e| {
+                        UaipError::DatabaseError(format!("Failed to delete session: {}", e))
+                    })?;
+            }
+        }
+
+        debug!("Deleted persisted session {}", session_id);
+        Ok(())
+    }
+
+    /// Save (insert or replace) an agent
+    ///
+    /// Persisted sessions reference their agent, so an agent must be saved before any of
+    /// its sessions.
+    pub async fn save_agent(&self, agent: &AiAgent) -> Result<()> {
+        match self {
+            Self::Memory { agents, .. } => {
+                agents.write().await.insert(agent.id, agent.clone());
+            }
+            Self::Redis(client) => {
+                let mut conn = redis_connection(client).await?;
+                let value = serde_json::to_string(agent)?;
+
+                redis::pipe()
+                    .set(format!("{}{}", REDIS_AGENT_KEY_PREFIX, agent.id), value)
+                    .ignore()
+                    .sadd(REDIS_AGENT_INDEX_KEY, agent.id.to_string())
+                    .ignore()
+                    .query_async::<()>(&mut conn)
+                    .await
+                    .map_err(|e| UaipError::DatabaseError(format!("Redis error: {}", e)))?;
+            }
+            Self::Postgres(pool) => {
+                // `ai_agents` doubles as the OAuth client table. Agents registered here get an
+                // unguessable secret, so they cannot sign in with client credentials. The
+                // full agent is kept in `metadata`; `agent_type` only separates AI from Human.
+                let secret_hash = bcrypt::hash(Uuid::new_v4().to_string(), bcrypt::DEFAULT_COST)
+                    .map_err(|e| UaipError::InternalError(format!("Hashing failed: {}", e)))?;
+
+                sqlx::query(
+                    r#"
+                    INSERT INTO ai_agents (
+                        id, client_id, name, client_secret_hash, agent_type, version, provider,
+                        config, metadata
+                    )
+                    VALUES ($1, $2, $3, $4, 'AI', $5, $6, $7, $8)
+                    ON CONFLICT (id) DO UPDATE SET
+                        name = EXCLUDED.name,
+                        version = EXCLUDED.version,
+                        provider = EXCLUDED.provider,
+                        config = EXCLUDED.config,
+                        metadata = EXCLUDED.metadata
+                    "#,
+                )
+                .bind(agent.id)
+                .bind(agent.id.to_string())
+                .bind(&agent.name)
+                .bind(secret_hash)
+                .bind(&agent.version)
+                .bind(&agent.provider)
+                .bind(serde_json::to_value(&agent.config)?)
+                .bind(serde_json::to_value(agent)?)
+                .execute(pool)
+                .await
+                .map_err(|e| UaipError::DatabaseError(format!("Failed to save agent: {}", e)))?;
+            }
+        }
+
+        debug!("Persisted agent {}", agent.id);
+        Ok(())
+    }
+
+    /// Load all persisted agents
+    pub async fn load_agents(&self) -> Result<Vec<AiAgent>> {
+        match self {
+            Self::Memory { agents, .. } => Ok(agents.read().await.values().cloned().collect()),
+            Self::Redis(client) => {
+                let mut conn = redis_connection(client).await?;
+                let ids: Vec<String> = redis::cmd("SMEMBERS")
+                    .arg(REDIS_AGENT_INDEX_KEY)
+                    .query_async(&mut conn)
+                    .await
+                    .map_err(|e| UaipError::DatabaseError(format!("Redis error: {}", e)))?;
+
+                if ids.is_empty() {
+                    return Ok(Vec::new());
+                }
+
+                let keys: Vec<String> = ids
+                    .iter()
+                    .map(|id| format!("{}{}", REDIS_AGENT_KEY_PREFIX, id))
+                    .collect();
+                let values: Vec<Option<String>> = redis::cmd("MGET")
+                    .arg(&keys)
+                    .query_async(&mut conn)
+                    .await
+                    .map_err(|e| UaipError::DatabaseError(format!("Redis error: {}", e)))?;
+
+                Ok(values
+                    .into_iter()
+                    .flatten()
+                    .map(|json| serde_json::from_str::<AiAgent>(&json))
+                    .collect::<std::result::Result<Vec<_>, _>>()?)
+            }
+            Self::Postgres(pool) => {
+                let rows = sqlx::query(
+                    r#"
+                    SELECT id, name, version, provider, config, metadata
+                    FROM ai_agents
+                    WHERE active
+                    "#,
+                )
+                .fetch_all(pool)
+                .await
+                .map_err(|e| UaipError::DatabaseError(format!("Failed to load agents: {}", e)))?;
+
+                Ok(rows.iter().map(agent_from_row).collect())
+            }
+        }
+    }
+}
+
+const POSTGRES_SELECT: &str = r#"
+    SELECT id, agent_id, state, started_at, last_activity_at, context_data, context_order,
+           devices, commands_sent, responses_received, errors_count, bytes_sent,
+           bytes_received, avg_response_time_ms
+    FROM ai_sessions
+"#;
+
+fn redis_key(session_id: &Uuid) -> String {
+    format!("{}{}", REDIS_KEY_PREFIX, session_id)
+}
+
+async fn redis_connection(client: &redis::Client) -> Result<redis::aio::MultiplexedConnection> {
+    client
+        .get_multiplexed_async_connection()
+        .await
+        .map_err(|e| UaipError::DatabaseError(format!("Redis connection failed: {}", e)))
+}
+
+fn state_to_str(state: SessionState) -> &'static str {
+    match state {
+        SessionState::Active => "active",
+        SessionState::Paused => "paused",
+        SessionState::Terminating => "terminating",
+        SessionState::Terminated => "terminated",
+        SessionState::Error => "error",
+    }
+}
+
+/// Rebuild an agent from its `ai_agents` row
+///
+/// Rows written by [`SessionStore::save_agent`] carry the full agent in `metadata`; other
+/// OAuth clients are loaded as custom agents from their columns.
+fn agent_from_row(row: &sqlx::postgres::PgRow) -> AiAgent {
+    let metadata: Option<serde_json::Value> = row.get("metadata");
+    if let Some(agent) = metadata.and_then(|value| serde_json::from_value(value).ok()) {
+        return agent;
+    }
+
+    let config: Option<serde_json::Value> = row.get("config");
+    let mut agent = AiAgent::new(row.get("name"), AgentType::Custom);
+    agent.id = row.get("id");
+    if let Some(version) = row.get::<Option<String>, _>("version") {
+        agent.version = version;
+    }
+    if let Some(provider) = row.get::<Option<String>, _>("provider") {
+        agent.provider = provider;
+    }
+    agent.config = config
+        .and_then(|value| serde_json::from_value::<AgentConfig>(value).ok())
+        .unwrap_or_default();
+    agent
+}
+
+fn session_from_row(row: &sqlx::postgres::PgRow) -> Result<AiSession> {
+    let state: String = row.get("state");
+    let state: SessionState = serde_json::from_value(serde_json::Value::String(state))?;
+
+    let context: Option<serde_json::Value> = row.get("context_data");
+    let context_order: Option<serde_json::Value> = row.get("context_order");
+    let devices: Option<serde_json::Value> = row.get("devices");
+
+    Ok(AiSession {
+        id: row.get("id"),
+        agent_id: row.get("agent_id"),
+        devices: devices
+            .map(serde_json::from_value)
+            .transpose()?
+            .unwrap_or_default(),
+        state,
+        started_at: row.get("started_at"),
+        last_activity_at: row.get("last_activity_at"),
+        context: context
+            .map(serde_json::from_value)
+            .transpose()?
+            .unwrap_or_default(),
+        context_order: context_order
+            .map(serde_json::from_value)
+            .transpose()?
+            .unwrap_or_default(),
+        stats: SessionStats {
+            commands_sent: row.get::<Option<i64>, _>("commands_sent").unwrap_or(0) as u64,
+            responses_received: row.get::<Option<i64>, _>("responses_received").unwrap_or(0) as u64,
+            errors_count: row.get::<Option<i64>, _>("errors_count").unwrap_or(0) as u64,
+            bytes_sent: row.get::<Option<i64>, _>("bytes_sent").unwrap_or(0) as u64,
+            bytes_received: row.get::<Option<i64>, _>("bytes_received").unwrap_or(0) as u64,
+            avg_response_time_ms: row
+                .get::<Option<f32>, _>("avg_response_time_ms")
+                .unwrap_or(0.0) as f64,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_context_limits_default() {
+        let limits = ContextLimits::default();
+        assert_eq!(limits.max_context_bytes, 64 * 1024);
+        assert_eq!(limits.truncation_policy, TruncationPolicy::DropOldest);
+    }
+
+    #[test]
+    fn test_state_to_str_matches_serde() {
+        for state in [
+            SessionState::Active,
+            SessionState::Paused,
+            SessionState::Terminating,
+            SessionState::Terminated,
+            SessionState::Error,
+        ] {
+            let json = serde_json::to_value(state).unwrap();
+            assert_eq!(json.as_str().unwrap(), state_to_str(state));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_memory_store_save_load_delete() {
+        let store = SessionStore::memory();
+        let mut session = AiSession::new(Uuid::new_v4());
+        session.set_context("goal".to_string(), serde_json::json!("inspect line 3"));
+
+        store.save(&session).await.unwrap();
+
+        let loaded = store.load(&session.id).await.unwrap().unwrap();
+        assert_eq!(loaded.context.get("goal"), session.context.get("goal"));
+        assert_eq!(store.load_active().await.unwrap().len(), 1);
+
+        store.delete(&session.id).await.unwrap();
+        assert!(store.load(&session.id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_memory_store_save_load_agents() {
+        let store = SessionStore::memory();
+        let agent = AiAgent::new("TestAgent".to_string(), AgentType::Monitoring);
+
+        store.save_agent(&agent).await.unwrap();
+
+        let agents = store.load_agents().await.unwrap();
+        assert_eq!(agents.len(), 1);
+        assert_eq!(agents[0].id, agent.id);
+        assert_eq!(agents[0].agent_type, AgentType::Monitoring);
+    }
+}
@@ -1,11 +1,26 @@
 //! Graceful shutdown handler for production deployments
 //!
-//! Ensures clean shutdown of all connections and resources
+//! Ensures clean shutdown of all connections and resources. Once a signal arrives,
+//! in-flight requests get a bounded grace period; requests still running after it are
+//! logged and abandoned so the process always exits.
 
-use std::time::Duration;
+use axum::{
+    extract::{Request, State},
+    http::Method,
+    middleware::Next,
+    response::Response,
+};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tokio::signal;
+use tokio::sync::watch;
 use tracing::{error, info, warn};
 
+use uaip_core::error::{Result, UaipError};
+
 /// Graceful shutdown configuration
 #[derive(Debug, Clone)]
 pub struct ShutdownConfig {
@@ -13,6 +28,8 @@ pub struct ShutdownConfig {
     pub grace_period: Duration,
     /// Whether to force shutdown after grace period
     pub force_after_grace_period: bool,
+    /// Maximum time to wait for pending NATS publishes to flush
+    pub nats_drain_timeout: Duration,
 }
 
 impl Default for ShutdownConfig {
@@ -20,22 +37,204 @@ impl Default for ShutdownConfig {
         Self {
             grace_period: Duration::from_secs(30),
             force_after_grace_period: true,
+            nats_drain_timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+impl ShutdownConfig {
+    /// Load shutdown settings from the environment
+    ///
+    /// Reads `SHUTDOWN_GRACE_PERIOD_SECS` and `SHUTDOWN_FORCE_AFTER_GRACE_PERIOD`; unset
+    /// values keep their defaults.
+    pub fn from_env() -> Result<Self> {
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    /// Load shutdown settings from a variable lookup
+    pub fn from_vars(lookup: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let defaults = Self::default();
+        let invalid = |name: &str, value: &str| {
+            UaipError::InvalidConfiguration(format!("Invalid {}: {}", name, value))
+        };
+
+        let grace_period = match lookup("SHUTDOWN_GRACE_PERIOD_SECS") {
+            Some(value) => value
+                .trim()
+                .parse()
+                .map(Duration::from_secs)
+                .map_err(|_| invalid("SHUTDOWN_GRACE_PERIOD_SECS", &value))?,
+            None => defaults.grace_period,
+        };
+        let force_after_grace_period = match lookup("SHUTDOWN_FORCE_AFTER_GRACE_PERIOD") {
+            Some(value) => value
+                .trim()
+                .parse()
+                .map_err(|_| invalid("SHUTDOWN_FORCE_AFTER_GRACE_PERIOD", &value))?,
+            None => defaults.force_after_grace_period,
+        };
+
+        Ok(Self {
+            grace_period,
+            force_after_grace_period,
+            ..defaults
+        })
+    }
+}
+
+/// A request that has not produced a response yet
+#[derive(Debug, Clone)]
+struct InFlightRequest {
+    method: Method,
+    path: String,
+    started_at: Instant,
+}
+
+/// Requests currently being handled, reported if shutdown has to abandon them
+#[derive(Debug, Clone, Default)]
+pub struct InFlightRequests {
+    next_id: Arc<AtomicU64>,
+    requests: Arc<Mutex<HashMap<u64, InFlightRequest>>>,
+}
+
+impl InFlightRequests {
+    /// Track a request until the returned guard is dropped
+    fn start(&self, method: Method, path: &str) -> InFlightGuard {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.lock().insert(
+            id,
+            InFlightRequest {
+                method,
+                path: path.to_string(),
+                started_at: Instant::now(),
+            },
+        );
+        InFlightGuard {
+            requests: self.clone(),
+            id,
         }
     }
+
+    /// Number of requests being handled
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Requests being handled, oldest first, as `METHOD /path (elapsed)`
+    pub fn describe(&self) -> Vec<String> {
+        let mut requests: Vec<InFlightRequest> = self.lock().values().cloned().collect();
+        requests.sort_by_key(|request| request.started_at);
+        requests
+            .iter()
+            .map(|request| {
+                format!(
+                    "{} {} ({:.1}s)",
+                    request.method,
+                    request.path,
+                    request.started_at.elapsed().as_secs_f64()
+                )
+            })
+            .collect()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<u64, InFlightRequest>> {
+        self.requests.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Removes a request from [`InFlightRequests`] when it completes or is cancelled
+struct InFlightGuard {
+    requests: InFlightRequests,
+    id: u64,
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.requests.lock().remove(&self.id);
+    }
+}
+
+/// Middleware recording each request in [`InFlightRequests`] while it is handled
+pub async fn track_in_flight(
+    State(in_flight): State<InFlightRequests>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let _guard = in_flight.start(request.method().clone(), request.uri().path());
+    next.run(request).await
+}
+
+/// How the server stopped
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShutdownOutcome {
+    /// Every in-flight request finished within the grace period
+    Graceful,
+    /// The grace period elapsed and these requests were abandoned
+    Forced { abandoned: Vec<String> },
 }
 
 /// Shutdown signal handler
 pub struct ShutdownHandler {
     config: ShutdownConfig,
+    nats_client: Option<async_nats::Client>,
+    in_flight: InFlightRequests,
+    triggered: watch::Sender<bool>,
 }
 
 impl ShutdownHandler {
     pub fn new(config: ShutdownConfig) -> Self {
-        Self { config }
+        Self {
+            config,
+            nats_client: None,
+            in_flight: InFlightRequests::default(),
+            triggered: watch::Sender::new(false),
+        }
+    }
+
+    /// Drain this NATS connection during shutdown
+    pub fn with_nats(mut self, client: async_nats::Client) -> Self {
+        self.nats_client = Some(client);
+        self
+    }
+
+    /// Report these requests if the grace period elapses
+    pub fn with_in_flight(mut self, in_flight: InFlightRequests) -> Self {
+        self.in_flight = in_flight;
+        self
     }
 
-    /// Wait for shutdown signal (SIGTERM, SIGINT, or Ctrl+C)
+    /// Start shutting down without waiting for a signal
+    pub fn trigger(&self) {
+        self.triggered.send_replace(true);
+    }
+
+    /// Resolves once shutdown has started, for the server's graceful shutdown signal
+    pub fn triggered(&self) -> impl Future<Output = ()> + Send + 'static {
+        let mut triggered = self.triggered.subscribe();
+        async move {
+            // The sender lives in the handler; if it is gone nothing can trigger shutdown
+            if triggered.wait_for(|triggered| *triggered).await.is_err() {
+                std::future::pending::<()>().await;
+            }
+        }
+    }
+
+    /// Wait for shutdown signal (SIGTERM, SIGINT, Ctrl+C, or [`Self::trigger`])
     pub async fn wait_for_signal(&self) {
+        tokio::select! {
+            _ = self.wait_for_os_signal() => {}
+            _ = self.triggered() => {
+                info!("Shutdown requested, initiating graceful shutdown");
+            }
+        }
+        self.trigger();
+    }
+
+    async fn wait_for_os_signal(&self) {
         #[cfg(unix)]
         {
             self.wait_for_signal_unix().await
@@ -62,8 +261,6 @@ impl ShutdownHandler {
                 info!("Received SIGINT signal, initiating graceful shutdown");
             }
         }
-
-        self.perform_shutdown().await;
     }
 
     #[cfg(not(unix))]
@@ -73,20 +270,66 @@ impl ShutdownHandler {
             .expect("Failed to install Ctrl+C handler");
 
         info!("Received Ctrl+C, initiating graceful shutdown");
-        self.perform_shutdown().await;
     }
 
-    /// Perform graceful shutdown steps
-    async fn perform_shutdown(&self) {
+    /// Run a server until it stops, bounding its graceful shutdown by the grace period
+    ///
+    /// `server` must start its graceful shutdown once [`Self::triggered`] resolves.
+    /// When the grace period elapses first and `force_after_grace_period` is set, the
+    /// requests still in flight are logged and `server` is dropped; the caller should
+    /// then exit, as connection tasks may still be running.
+    pub async fn run<F, E>(&self, server: F) -> std::result::Result<ShutdownOutcome, E>
+    where
+        F: Future<Output = std::result::Result<(), E>>,
+    {
+        tokio::pin!(server);
+
+        tokio::select! {
+            result = &mut server => return result.map(|()| ShutdownOutcome::Graceful),
+            _ = self.wait_for_signal() => {}
+        }
+
         info!(
             grace_period_secs = self.config.grace_period.as_secs(),
             "Starting graceful shutdown"
         );
 
-        // Step 1: Stop accepting new connections
-        info!("Step 1/4: Stopping new connections");
+        // Step 1: Stop accepting new connections and wait for in-flight requests
+        info!(
+            in_flight = self.in_flight.len(),
+            "Step 1/4: Waiting for in-flight requests"
+        );
+        let outcome = match tokio::time::timeout(self.config.grace_period, &mut server).await {
+            Ok(result) => {
+                result?;
+                ShutdownOutcome::Graceful
+            }
+            Err(_) if self.config.force_after_grace_period => {
+                let abandoned = self.in_flight.describe();
+                warn!(
+                    in_flight = ?abandoned,
+                    "Grace period elapsed, abandoning {} in-flight requests",
+                    abandoned.len()
+                );
+                ShutdownOutcome::Forced { abandoned }
+            }
+            Err(_) => {
+                warn!(
+                    in_flight = ?self.in_flight.describe(),
+                    "Grace period elapsed, still waiting for in-flight requests"
+                );
+                server.await?;
+                ShutdownOutcome::Graceful
+            }
+        };
+
+        self.perform_shutdown().await;
+        Ok(outcome)
+    }
 
-        // Step 2: Close existing connections gracefully
+    /// Perform the shutdown steps that follow the server stopping
+    async fn perform_shutdown(&self) {
+        // Step 2: Close remaining connections
         info!("Step 2/4: Closing existing connections");
         self.close_connections().await;
 
@@ -98,7 +341,7 @@ impl ShutdownHandler {
         info!("Step 4/4: Cleaning up resources");
         self.cleanup_resources().await;
 
-        info!("Graceful shutdown completed successfully");
+        info!("Shutdown steps completed");
     }
 
     async fn close_connections(&self) {
@@ -106,20 +349,23 @@ impl ShutdownHandler {
         // - WebSocket connections
         // - Database connections
         // - Redis connections
-        // - NATS connections
 
-        // Wait for existing requests to complete (with timeout)
-        let timeout = tokio::time::sleep(self.config.grace_period);
-        tokio::pin!(timeout);
+        self.drain_nats().await;
+    }
 
-        tokio::select! {
-            _ = timeout => {
-                if self.config.force_after_grace_period {
-                    warn!("Grace period elapsed, forcing shutdown");
-                } else {
-                    info!("Grace period elapsed, continuing shutdown");
-                }
-            }
+    /// Flush publishes still buffered in the NATS client, bounded by `nats_drain_timeout`
+    async fn drain_nats(&self) {
+        let Some(client) = &self.nats_client else {
+            return;
+        };
+
+        match tokio::time::timeout(self.config.nats_drain_timeout, client.flush()).await {
+            Ok(Ok(())) => info!("NATS publishes flushed"),
+            Ok(Err(e)) => error!(error = %e, "Failed to flush NATS publishes"),
+            Err(_) => warn!(
+                timeout_secs = self.config.nats_drain_timeout.as_secs(),
+                "Timed out flushing NATS publishes"
+            ),
         }
     }
 
@@ -167,6 +413,7 @@ mod tests {
         let config = ShutdownConfig::default();
         assert_eq!(config.grace_period, Duration::from_secs(30));
         assert!(config.force_after_grace_period);
+        assert_eq!(config.nats_drain_timeout, Duration::from_secs(5));
     }
 
     #[tokio::test]
@@ -175,10 +422,141 @@ mod tests {
         assert_eq!(handler.config.grace_period, Duration::from_secs(30));
     }
 
+    /// Minimal NATS server speaking just enough protocol for a client to connect and
+    /// publish; returns its URL and the subjects published to it.
+    async fn fake_nats_server() -> (String, tokio::sync::mpsc::UnboundedReceiver<String>) {
+        use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("nats://{}", listener.local_addr().unwrap());
+        let (published_tx, published_rx) = tokio::sync::mpsc::unbounded_channel();
+
+        tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let (read, mut write) = socket.into_split();
+            write
+                .write_all(
+                    b"INFO {\"server_id\":\"test\",\"version\":\"2.10.0\",\"proto\":1,\
+                      \"max_payload\":1048576,\"headers\":true}\r\n",
+                )
+                .await
+                .unwrap();
+
+            let mut lines = BufReader::new(read).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                if line.starts_with("PING") {
+                    write.write_all(b"PONG\r\n").await.unwrap();
+                } else if let Some(publish) = line.strip_prefix("PUB ") {
+                    let subject = publish.split(' ').next().unwrap().to_string();
+                    // Skip the payload line
+                    lines.next_line().await.unwrap();
+                    published_tx.send(subject).unwrap();
+                }
+            }
+        });
+
+        (url, published_rx)
+    }
+
+    #[tokio::test]
+    async fn test_drain_nats_flushes_pending_publishes() {
+        let (url, mut published) = fake_nats_server().await;
+        let client = async_nats::connect(&url).await.unwrap();
+        let handler = ShutdownHandler::default().with_nats(client.clone());
+
+        client
+            .publish("uaip.telemetry".to_string(), "last".into())
+            .await
+            .unwrap();
+        handler.drain_nats().await;
+
+        let subject = tokio::time::timeout(Duration::from_secs(1), published.recv())
+            .await
+            .unwrap();
+        assert_eq!(subject.as_deref(), Some("uaip.telemetry"));
+    }
+
     #[tokio::test]
     async fn test_flush_metrics() {
         let handler = ShutdownHandler::default();
         handler.flush_metrics_and_logs().await;
         // Should complete without panicking
     }
+
+    #[test]
+    fn test_shutdown_config_from_vars() {
+        let config = ShutdownConfig::from_vars(|name| match name {
+            "SHUTDOWN_GRACE_PERIOD_SECS" => Some("5".to_string()),
+            _ => None,
+        })
+        .unwrap();
+        assert_eq!(config.grace_period, Duration::from_secs(5));
+        assert!(config.force_after_grace_period);
+
+        let result = ShutdownConfig::from_vars(|name| {
+            (name == "SHUTDOWN_GRACE_PERIOD_SECS").then(|| "soon".to_string())
+        });
+        assert!(matches!(result, Err(UaipError::InvalidConfiguration(_))));
+    }
+
+    #[tokio::test]
+    async fn test_hung_request_does_not_block_exit() {
+        use axum::{routing::get, Router};
+        use std::future::IntoFuture;
+        use tokio::io::AsyncWriteExt;
+
+        let in_flight = InFlightRequests::default();
+        let app = Router::new()
+            .route("/hang", get(std::future::pending::<()>))
+            .layer(axum::middleware::from_fn_with_state(
+                in_flight.clone(),
+                track_in_flight,
+            ));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let handler = Arc::new(
+            ShutdownHandler::new(ShutdownConfig {
+                grace_period: Duration::from_millis(200),
+                ..Default::default()
+            })
+            .with_in_flight(in_flight.clone()),
+        );
+        let server = axum::serve(listener, app)
+            .with_graceful_shutdown(handler.triggered())
+            .into_future();
+        let run = tokio::spawn({
+            let handler = handler.clone();
+            async move { handler.run(server).await }
+        });
+
+        let mut client = tokio::net::TcpStream::connect(addr).await.unwrap();
+        client
+            .write_all(b"GET /hang HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .await
+            .unwrap();
+        while in_flight.is_empty() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        // Nothing triggered yet: the server keeps running
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert!(!run.is_finished());
+
+        let started = Instant::now();
+        handler.trigger();
+        let outcome = tokio::time::timeout(Duration::from_secs(5), run)
+            .await
+            .expect("shutdown did not finish")
+            .unwrap()
+            .unwrap();
+        assert!(started.elapsed() >= Duration::from_millis(200));
+        match outcome {
+            ShutdownOutcome::Forced { abandoned } => {
+                assert_eq!(abandoned.len(), 1);
+                assert!(abandoned[0].starts_with("GET /hang"), "{}", abandoned[0]);
+            }
+            ShutdownOutcome::Graceful => panic!("hung request finished"),
+        }
+    }
 }
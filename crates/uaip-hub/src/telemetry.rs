@@ -1,3 +1,437 @@
-//! Telemetry and health monitoring
+//! Telemetry retention and downsampling
+//!
+//! Raw readings are kept for a configured number of days, then rolled up into
+//! hourly aggregates; hourly aggregates are later rolled up into daily ones.
 
-// Placeholder - to be implemented
+use chrono::{DateTime, DurationRound, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use uaip_core::error::{Result, UaipError};
+
+use crate::config::RetentionConfig;
+
+/// Aggregation stored for a downsampled metric
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Aggregation {
+    Avg,
+    Min,
+    Max,
+}
+
+impl FromStr for Aggregation {
+    type Err = UaipError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "avg" => Ok(Self::Avg),
+            "min" => Ok(Self::Min),
+            "max" => Ok(Self::Max),
+            other => Err(UaipError::InvalidParameter(format!(
+                "Unknown aggregation: {}",
+                other
+            ))),
+        }
+    }
+}
+
+/// Rollup bucket size
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Resolution {
+    Hour,
+    Day,
+}
+
+impl Resolution {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Hour => "hour",
+            Self::Day => "day",
+        }
+    }
+
+    /// Start of the bucket containing `timestamp`
+    pub fn bucket_start(&self, timestamp: DateTime<Utc>) -> DateTime<Utc> {
+        let size = match self {
+            Self::Hour => TimeDelta::hours(1),
+            Self::Day => TimeDelta::days(1),
+        };
+        timestamp.duration_trunc(size).unwrap_or(timestamp)
+    }
+}
+
+/// Points in time before which data of each tier expires
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetentionCutoffs {
+    /// Raw readings before this are rolled up into hourly aggregates
+    pub raw: DateTime<Utc>,
+    /// Hourly aggregates before this are rolled up into daily aggregates
+    pub hourly: DateTime<Utc>,
+    /// Daily aggregates before this are deleted
+    pub daily: DateTime<Utc>,
+}
+
+/// A numeric telemetry reading
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow)]
+pub struct Sample {
+    pub device_id: String,
+    pub metric: String,
+    pub recorded_at: DateTime<Utc>,
+    pub value: f64,
+}
+
+/// Aggregated readings of one metric over one bucket
+#[derive(Debug, Clone, PartialEq, Serialize, sqlx::FromRow)]
+pub struct Rollup {
+    pub device_id: String,
+    pub metric: String,
+    pub bucket_start: DateTime<Utc>,
+    pub sample_count: i64,
+    pub avg_value: Option<f64>,
+    pub min_value: Option<f64>,
+    pub max_value: Option<f64>,
+}
+
+impl Rollup {
+    /// Fold another rollup of the same metric into this one
+    fn merge(&mut self, other: &Rollup) {
+        let total = self.sample_count + other.sample_count;
+        self.avg_value = match (self.avg_value, other.avg_value) {
+            (Some(a), Some(b)) if total > 0 => {
+                Some((a * self.sample_count as f64 + b * other.sample_count as f64) / total as f64)
+            }
+            (a, b) => a.or(b),
+        };
+        self.min_value = fold(self.min_value, other.min_value, f64::min);
+        self.max_value = fold(self.max_value, other.max_value, f64::max);
+        self.sample_count = total;
+    }
+
+    /// Drop aggregations that are not configured for the metric
+    fn retain(mut self, aggregations: &[Aggregation]) -> Self {
+        if !aggregations.contains(&Aggregation::Avg) {
+            self.avg_value = None;
+        }
+        if !aggregations.contains(&Aggregation::Min) {
+            self.min_value = None;
+        }
+        if !aggregations.contains(&Aggregation::Max) {
+            self.max_value = None;
+        }
+        self
+    }
+}
+
+fn fold(a: Option<f64>, b: Option<f64>, f: fn(f64, f64) -> f64) -> Option<f64> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(f(a, b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Roll raw samples up into hourly aggregates
+pub fn rollup_samples(samples: &[Sample], config: &RetentionConfig) -> Vec<Rollup> {
+    let rollups = samples.iter().map(|sample| Rollup {
+        device_id: sample.device_id.clone(),
+        metric: sample.metric.clone(),
+        bucket_start: Resolution::Hour.bucket_start(sample.recorded_at),
+        sample_count: 1,
+        avg_value: Some(sample.value),
+        min_value: Some(sample.value),
+        max_value: Some(sample.value),
+    });
+    merge_into(rollups, Resolution::Hour, config)
+}
+
+/// Roll rollups up into coarser buckets
+pub fn rollup_to(
+    rollups: &[Rollup],
+    resolution: Resolution,
+    config: &RetentionConfig,
+) -> Vec<Rollup> {
+    merge_into(rollups.iter().cloned(), resolution, config)
+}
+
+fn merge_into(
+    rollups: impl Iterator<Item = Rollup>,
+    resolution: Resolution,
+    config: &RetentionConfig,
+) -> Vec<Rollup> {
+    let mut buckets: BTreeMap<(String, String, DateTime<Utc>), Rollup> = BTreeMap::new();
+
+    for mut rollup in rollups {
+        rollup.bucket_start = resolution.bucket_start(rollup.bucket_start);
+        let key = (
+            rollup.device_id.clone(),
+            rollup.metric.clone(),
+            rollup.bucket_start,
+        );
+        match buckets.get_mut(&key) {
+            Some(existing) => existing.merge(&rollup),
+            None => {
+                buckets.insert(key, rollup);
+            }
+        }
+    }
+
+    buckets
+        .into_values()
+        .map(|rollup| {
+            let aggregations = config.aggregations_for(&rollup.metric);
+            rollup.retain(aggregations)
+        })
+        .collect()
+}
+
+/// Rows affected by one retention pass
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RetentionReport {
+    pub raw_pruned: u64,
+    pub hourly_written: usize,
+    pub hourly_pruned: u64,
+    pub daily_written: usize,
+    pub daily_pruned: u64,
+}
+
+/// Background job that downsamples and prunes the telemetry tables
+#[derive(Clone)]
+pub struct TelemetryRetention {
+    pool: PgPool,
+    config: RetentionConfig,
+}
+
+impl TelemetryRetention {
+    pub fn new(pool: PgPool, config: RetentionConfig) -> Self {
+        Self { pool, config }
+    }
+
+    /// Run the job on the configured interval until the task is dropped
+    pub async fn run(self) {
+        let mut interval = tokio::time::interval(self.config.interval);
+        loop {
+            interval.tick().await;
+            match self.run_once(Utc::now()).await {
+                Ok(report) => tracing::debug!("Telemetry retention pass: {:?}", report),
+                Err(e) => tracing::error!("Telemetry retention pass failed: {}", e),
+            }
+        }
+    }
+
+    /// Roll up and prune everything past the cutoffs for `now`
+    pub async fn run_once(&self, now: DateTime<Utc>) -> Result<RetentionReport> {
+        let cutoffs = self.config.cutoffs(now);
+        let mut report = RetentionReport::default();
+        let mut tx = self.pool.begin().await.map_err(db_error)?;
+
+        // Raw readings -> hourly. Non-numeric readings are pruned without a rollup.
+        let samples: Vec<Sample> = sqlx::query_as(
+            "SELECT device_id, metric, recorded_at,
+                    CASE jsonb_typeof(value)
+                        WHEN 'number' THEN (value #>> '{}')::double precision
+                        ELSE CASE WHEN value = 'true'::jsonb THEN 1.0 ELSE 0.0 END
+                    END AS value
+             FROM device_telemetry
+             WHERE recorded_at < $1 AND jsonb_typeof(value) IN ('number', 'boolean')",
+        )
+        .bind(cutoffs.raw)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(db_error)?;
+
+        let hourly = rollup_samples(&samples, &self.config);
+        upsert_rollups(&mut tx, Resolution::Hour, &hourly).await?;
+        report.hourly_written = hourly.len();
+        report.raw_pruned = sqlx::query("DELETE FROM device_telemetry WHERE recorded_at < $1")
+            .bind(cutoffs.raw)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?
+            .rows_affected();
+
+        // Hourly -> daily
+        let expired_hourly: Vec<Rollup> = sqlx::query_as(
+            "SELECT device_id, metric, bucket_start, sample_count, avg_value, min_value, max_value
+             FROM device_telemetry_rollups
+             WHERE resolution = 'hour' AND bucket_start < $1",
+        )
+        .bind(cutoffs.hourly)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(db_error)?;
+
+        let daily = rollup_to(&expired_hourly, Resolution::Day, &self.config);
+        upsert_rollups(&mut tx, Resolution::Day, &daily).await?;
+        report.daily_written = daily.len();
+        report.hourly_pruned = prune_rollups(&mut tx, Resolution::Hour, cutoffs.hourly).await?;
+        report.daily_pruned = prune_rollups(&mut tx, Resolution::Day, cutoffs.daily).await?;
+
+        tx.commit().await.map_err(db_error)?;
+        Ok(report)
+    }
+}
+
+/// Insert rollups, merging with any rollup already stored for the same bucket
+async fn upsert_rollups(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    resolution: Resolution,
+    rollups: &[Rollup],
+) -> Result<()> {
+    for rollup in rollups {
+        sqlx::query(
+            "INSERT INTO device_telemetry_rollups
+                (device_id, metric, resolution, bucket_start, sample_count,
+                 avg_value, min_value, max_value)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+             ON CONFLICT (device_id, metric, resolution, bucket_start) DO UPDATE SET
+                avg_value = (device_telemetry_rollups.avg_value * device_telemetry_rollups.sample_count
+                             + EXCLUDED.avg_value * EXCLUDED.sample_count)
+                            / (device_telemetry_rollups.sample_count + EXCLUDED.sample_count),
+                min_value = LEAST(device_telemetry_rollups.min_value, EXCLUDED.min_value),
+                max_value = GREATEST(device_telemetry_rollups.max_value, EXCLUDED.max_value),
+                sample_count = device_telemetry_rollups.sample_count + EXCLUDED.sample_count",
+        )
+        .bind(&rollup.device_id)
+        .bind(&rollup.metric)
+        .bind(resolution.as_str())
+        .bind(rollup.bucket_start)
+        .bind(rollup.sample_count)
+        .bind(rollup.avg_value)
+        .bind(rollup.min_value)
+        .bind(rollup.max_value)
+        .execute(&mut **tx)
+        .await
+        .map_err(db_error)?;
+    }
+    Ok(())
+}
+
+async fn prune_rollups(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    resolution: Resolution,
+    cutoff: DateTime<Utc>,
+) -> Result<u64> {
+    let result = sqlx::query(
+        "DELETE FROM device_telemetry_rollups WHERE resolution = $1 AND bucket_start < $2",
+    )
+    .bind(resolution.as_str())
+    .bind(cutoff)
+    .execute(&mut **tx)
+    .await
+    .map_err(db_error)?;
+    Ok(result.rows_affected())
+}
+
+fn db_error(e: sqlx::Error) -> UaipError {
+    UaipError::DatabaseError(format!("Telemetry retention error: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn sample(metric: &str, recorded_at: &str, value: f64) -> Sample {
+        Sample {
+            device_id: "sensor-1".to_string(),
+            metric: metric.to_string(),
+            recorded_at: recorded_at.parse().unwrap(),
+            value,
+        }
+    }
+
+    fn config() -> RetentionConfig {
+        RetentionConfig {
+            metric_aggregations: HashMap::from([("door.open".to_string(), vec![Aggregation::Max])]),
+            ..RetentionConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_hourly_rollup_aggregates() {
+        let samples = vec![
+            sample("temperature", "2026-03-01T10:05:00Z", 20.0),
+            sample("temperature", "2026-03-01T10:45:00Z", 24.0),
+            sample("temperature", "2026-03-01T10:59:59Z", 19.0),
+            sample("temperature", "2026-03-01T11:00:00Z", 30.0),
+            sample("door.open", "2026-03-01T10:10:00Z", 0.0),
+            sample("door.open", "2026-03-01T10:20:00Z", 1.0),
+        ];
+
+        let rollups = rollup_samples(&samples, &config());
+        assert_eq!(rollups.len(), 3);
+
+        let door = &rollups[0];
+        assert_eq!(door.metric, "door.open");
+        assert_eq!(door.sample_count, 2);
+        assert_eq!(
+            (door.avg_value, door.min_value, door.max_value),
+            (None, None, Some(1.0))
+        );
+
+        let ten = &rollups[1];
+        assert_eq!(ten.bucket_start.to_rfc3339(), "2026-03-01T10:00:00+00:00");
+        assert_eq!(ten.sample_count, 3);
+        assert_eq!(ten.avg_value, Some(21.0));
+        assert_eq!(ten.min_value, Some(19.0));
+        assert_eq!(ten.max_value, Some(24.0));
+
+        let eleven = &rollups[2];
+        assert_eq!(
+            eleven.bucket_start.to_rfc3339(),
+            "2026-03-01T11:00:00+00:00"
+        );
+        assert_eq!(eleven.sample_count, 1);
+        assert_eq!(eleven.avg_value, Some(30.0));
+    }
+
+    #[test]
+    fn test_daily_rollup_weights_hourly_averages() {
+        let samples = vec![
+            sample("temperature", "2026-03-01T01:00:00Z", 10.0),
+            sample("temperature", "2026-03-01T23:00:00Z", 20.0),
+            sample("temperature", "2026-03-01T23:30:00Z", 20.0),
+            sample("temperature", "2026-03-01T23:45:00Z", 30.0),
+            sample("temperature", "2026-03-02T00:00:00Z", 99.0),
+        ];
+        let hourly = rollup_samples(&samples, &config());
+        assert_eq!(hourly.len(), 3);
+
+        let daily = rollup_to(&hourly, Resolution::Day, &config());
+        assert_eq!(daily.len(), 2);
+        assert_eq!(
+            daily[0].bucket_start.to_rfc3339(),
+            "2026-03-01T00:00:00+00:00"
+        );
+        assert_eq!(daily[0].sample_count, 4);
+        assert_eq!(daily[0].avg_value, Some(20.0));
+        assert_eq!(daily[0].min_value, Some(10.0));
+        assert_eq!(daily[0].max_value, Some(30.0));
+        assert_eq!(daily[1].sample_count, 1);
+    }
+
+    #[test]
+    fn test_only_samples_past_cutoff_are_rolled_up() {
+        let config = config();
+        let now = "2026-03-10T12:30:00Z".parse::<DateTime<Utc>>().unwrap();
+        let cutoffs = config.cutoffs(now);
+
+        let samples = vec![
+            sample("temperature", "2026-03-03T11:59:59Z", 1.0),
+            sample("temperature", "2026-03-03T12:00:00Z", 2.0),
+            sample("temperature", "2026-03-09T08:00:00Z", 3.0),
+        ];
+        let (expired, kept): (Vec<Sample>, Vec<Sample>) = samples
+            .into_iter()
+            .partition(|s| s.recorded_at < cutoffs.raw);
+        assert_eq!(expired.len(), 1);
+        assert_eq!(kept.len(), 2);
+
+        let rollups = rollup_samples(&expired, &config);
+        assert_eq!(rollups.len(), 1);
+        assert_eq!(rollups[0].avg_value, Some(1.0));
+    }
+}
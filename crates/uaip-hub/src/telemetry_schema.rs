@@ -0,0 +1,268 @@
+//! Telemetry schemas
+//!
+//! A schema describes the metrics a device type reports: each metric's value type,
+//! unit and allowed range. Readings from devices of a described type are checked
+//! against it and carry the schema's unit when they report none, so stored and
+//! streamed readings say whether `temperature` is °C or °F.
+
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+use uaip_core::error::{Result, UaipError};
+
+use crate::handlers::telemetry::TelemetryReading;
+
+/// Value type of a metric
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MetricType {
+    Number,
+    Integer,
+    Boolean,
+    String,
+}
+
+impl MetricType {
+    fn accepts(&self, value: &serde_json::Value) -> bool {
+        match self {
+            Self::Number => value.is_number(),
+            Self::Integer => value.is_i64() || value.is_u64(),
+            Self::Boolean => value.is_boolean(),
+            Self::String => value.is_string(),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Number => "number",
+            Self::Integer => "integer",
+            Self::Boolean => "boolean",
+            Self::String => "string",
+        }
+    }
+}
+
+/// Description of one metric
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct MetricSchema {
+    #[serde(rename = "type")]
+    pub metric_type: MetricType,
+    pub unit: Option<String>,
+    /// Smallest allowed value (inclusive), for numeric metrics
+    pub min: Option<f64>,
+    /// Largest allowed value (inclusive), for numeric metrics
+    pub max: Option<f64>,
+}
+
+impl MetricSchema {
+    /// Check a reading of this metric, returning the reason it is rejected
+    pub fn check(&self, reading: &TelemetryReading) -> std::result::Result<(), String> {
+        if !self.metric_type.accepts(&reading.value) {
+            return Err(format!("value must be a {}", self.metric_type.name()));
+        }
+        if let (Some(unit), Some(expected)) = (&reading.unit, &self.unit) {
+            if unit != expected {
+                return Err(format!("unit '{}' does not match '{}'", unit, expected));
+            }
+        }
+        if let Some(value) = reading.value.as_f64() {
+            if let Some(min) = self.min.filter(|min| value < *min) {
+                return Err(format!("value {} is below the minimum {}", value, min));
+            }
+            if let Some(max) = self.max.filter(|max| value > *max) {
+                return Err(format!("value {} is above the maximum {}", value, max));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Metrics reported by one device type
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct DeviceTypeSchema {
+    #[serde(default)]
+    pub metrics: HashMap<String, MetricSchema>,
+    /// Reject metrics the schema does not describe
+    #[serde(default)]
+    pub strict: bool,
+}
+
+impl DeviceTypeSchema {
+    /// Check a reading, returning the reason it is rejected
+    pub fn check(&self, reading: &TelemetryReading) -> std::result::Result<(), String> {
+        match self.metrics.get(&reading.metric) {
+            Some(metric) => metric.check(reading),
+            None if self.strict => Err(format!("unknown metric '{}'", reading.metric)),
+            None => Ok(()),
+        }
+    }
+
+    /// Copy readings, giving those without a unit the unit of their metric
+    pub fn annotate(&self, readings: &[TelemetryReading]) -> Vec<TelemetryReading> {
+        readings
+            .iter()
+            .map(|reading| {
+                let unit = reading.unit.clone().or_else(|| {
+                    self.metrics
+                        .get(&reading.metric)
+                        .and_then(|metric| metric.unit.clone())
+                });
+                TelemetryReading {
+                    unit,
+                    ..reading.clone()
+                }
+            })
+            .collect()
+    }
+}
+
+/// Telemetry schemas keyed by device type, loaded from a JSON file
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct TelemetrySchemas {
+    #[serde(default)]
+    pub device_types: HashMap<String, DeviceTypeSchema>,
+}
+
+impl TelemetrySchemas {
+    /// Load the file named by `TELEMETRY_SCHEMA`; unset means no schemas
+    pub fn from_env() -> Result<Self> {
+        match std::env::var("TELEMETRY_SCHEMA") {
+            Ok(path) => Self::from_file(path),
+            Err(_) => Ok(Self::default()),
+        }
+    }
+
+    /// Load schemas from a JSON file
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path).map_err(|e| {
+            UaipError::InvalidConfiguration(format!(
+                "Failed to read telemetry schema {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        Self::from_json(&json)
+    }
+
+    /// Parse schemas from JSON
+    pub fn from_json(json: &str) -> Result<Self> {
+        let schemas: Self = serde_json::from_str(json).map_err(|e| {
+            UaipError::InvalidConfiguration(format!("Invalid telemetry schema: {}", e))
+        })?;
+        for (device_type, schema) in &schemas.device_types {
+            for (metric, spec) in &schema.metrics {
+                if let (Some(min), Some(max)) = (spec.min, spec.max) {
+                    if min > max {
+                        return Err(UaipError::InvalidConfiguration(format!(
+                            "Telemetry schema {}.{}: min exceeds max",
+                            device_type, metric
+                        )));
+                    }
+                }
+            }
+        }
+        Ok(schemas)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.device_types.is_empty()
+    }
+
+    /// Schema of a device type, if it has one
+    pub fn for_device_type(&self, device_type: Option<&str>) -> Option<&DeviceTypeSchema> {
+        self.device_types.get(device_type?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const SCHEMA: &str = r#"{
+        "device_types": {
+            "thermostat": {
+                "strict": true,
+                "metrics": {
+                    "temperature": {"type": "number", "unit": "°C", "min": -40, "max": 85},
+                    "setpoint_reached": {"type": "boolean"}
+                }
+            }
+        }
+    }"#;
+
+    fn reading(metric: &str, value: serde_json::Value, unit: Option<&str>) -> TelemetryReading {
+        TelemetryReading {
+            metric: metric.to_string(),
+            value,
+            unit: unit.map(str::to_string),
+            timestamp: None,
+        }
+    }
+
+    fn thermostat() -> DeviceTypeSchema {
+        TelemetrySchemas::from_json(SCHEMA)
+            .unwrap()
+            .for_device_type(Some("thermostat"))
+            .unwrap()
+            .clone()
+    }
+
+    #[test]
+    fn test_conforming_reading_is_annotated() {
+        let schema = thermostat();
+        let readings = [
+            reading("temperature", json!(21.5), None),
+            reading("temperature", json!(22), Some("°C")),
+            reading("setpoint_reached", json!(true), None),
+        ];
+
+        let annotated = schema.annotate(&readings);
+        assert!(annotated.iter().all(|r| schema.check(r).is_ok()));
+        assert_eq!(annotated[0].unit.as_deref(), Some("°C"));
+        assert_eq!(annotated[2].unit, None);
+    }
+
+    #[test]
+    fn test_out_of_range_and_mistyped_values_rejected() {
+        let schema = thermostat();
+
+        let err = schema
+            .check(&reading("temperature", json!(120), None))
+            .unwrap_err();
+        assert!(err.contains("above the maximum 85"));
+        assert!(schema
+            .check(&reading("temperature", json!(-41), None))
+            .is_err());
+        assert!(schema
+            .check(&reading("temperature", json!("warm"), None))
+            .is_err());
+        let err = schema
+            .check(&reading("temperature", json!(70), Some("°F")))
+            .unwrap_err();
+        assert!(err.contains("does not match"));
+    }
+
+    #[test]
+    fn test_unknown_metric_rejected_only_when_strict() {
+        let mut schema = thermostat();
+        let humidity = reading("humidity", json!(40), None);
+
+        assert_eq!(
+            schema.check(&humidity).unwrap_err(),
+            "unknown metric 'humidity'"
+        );
+        schema.strict = false;
+        assert!(schema.check(&humidity).is_ok());
+
+        let schemas = TelemetrySchemas::from_json(SCHEMA).unwrap();
+        assert!(schemas.for_device_type(Some("light")).is_none());
+        assert!(schemas.for_device_type(None).is_none());
+        assert!(TelemetrySchemas::from_json(
+            r#"{"device_types": {"x": {"metrics": {"t": {"type": "number", "min": 2, "max": 1}}}}}"#
+        )
+        .is_err());
+    }
+}
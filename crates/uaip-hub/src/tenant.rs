@@ -0,0 +1,107 @@
+//! Tenant Scoping
+//!
+//! Every device, media file and tenant-owned rule belongs to a tenant. Requests are
+//! scoped to the tenant named in their bearer token; requests without one fall back to a
+//! single implicit tenant so deployments without auth keep working.
+
+use axum::http::HeaderMap;
+use uaip_auth::jwt::Claims;
+use uaip_core::error::UaipError;
+
+use crate::handlers::auth::bearer_claims;
+
+/// Tenant used when a request carries no token, or a token without a tenant claim
+pub const DEFAULT_TENANT: &str = "default";
+
+/// The tenant a request acts on behalf of
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tenant(String);
+
+impl Tenant {
+    pub fn new(id: impl Into<String>) -> Self {
+        Self(id.into())
+    }
+
+    /// Tenant named in a token's claims
+    pub fn from_claims(claims: &Claims) -> Self {
+        claims
+            .tenant_id
+            .as_deref()
+            .map(Self::new)
+            .unwrap_or_default()
+    }
+
+    /// Tenant of the bearer token in `headers`
+    ///
+    /// Requests without an Authorization header use the default tenant; a token that
+    /// fails validation is rejected rather than treated as anonymous.
+    pub fn from_headers(headers: &HeaderMap) -> Result<Self, UaipError> {
+        Ok(bearer_claims(headers)?
+            .map(|claims| Self::from_claims(&claims))
+            .unwrap_or_default())
+    }
+
+    pub fn id(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Default for Tenant {
+    fn default() -> Self {
+        Self::new(DEFAULT_TENANT)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uaip_auth::jwt::JwtManager;
+
+    fn bearer(tenant_id: Option<&str>) -> HeaderMap {
+        let token = JwtManager::new(
+            "uaip-development-secret-change-in-production",
+            "uaip-hub".to_string(),
+            "uaip-api".to_string(),
+            3600,
+        )
+        .generate_tenant_token(
+            "user-1",
+            "ops@example.com",
+            vec![],
+            None,
+            tenant_id.map(String::from),
+        )
+        .unwrap();
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "Authorization",
+            format!("Bearer {}", token).parse().unwrap(),
+        );
+        headers
+    }
+
+    #[test]
+    fn test_tenant_from_token_claim() {
+        let tenant = Tenant::from_headers(&bearer(Some("acme"))).unwrap();
+        assert_eq!(tenant.id(), "acme");
+
+        // Tokens issued before tenants existed, and anonymous requests, share the default
+        let tenant = Tenant::from_headers(&bearer(None)).unwrap();
+        assert_eq!(tenant, Tenant::default());
+        assert_eq!(
+            Tenant::from_headers(&HeaderMap::new()).unwrap().id(),
+            DEFAULT_TENANT
+        );
+    }
+
+    #[test]
+    fn test_invalid_token_is_rejected() {
+        let mut headers = HeaderMap::new();
+        headers.insert("Authorization", "Bearer not-a-token".parse().unwrap());
+        assert!(matches!(
+            Tenant::from_headers(&headers),
+            Err(UaipError::AuthenticationFailed(_))
+        ));
+    }
+}
@@ -0,0 +1,86 @@
+//! Timestamp handling
+//!
+//! Timestamps are stored as `timestamptz` and exchanged as RFC 3339. Inbound timestamps
+//! must carry an offset: a naive time may be the sender's local time, and reading it as
+//! UTC would silently shift it.
+
+use chrono::{DateTime, Utc};
+use serde::{de::Error, Deserialize, Deserializer};
+
+use uaip_core::error::{Result, UaipError};
+
+/// Parse an RFC 3339 timestamp with an offset, e.g. `2024-05-01T12:00:00Z`
+pub fn parse_timestamp(value: &str) -> Result<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|timestamp| timestamp.with_timezone(&Utc))
+        .map_err(|_| UaipError::InvalidParameter(timestamp_error(value)))
+}
+
+fn timestamp_error(value: &str) -> String {
+    format!(
+        "timestamp '{}' must be RFC 3339 with an offset, e.g. 2024-05-01T12:00:00Z",
+        value
+    )
+}
+
+/// Deserialize a timestamp with [`parse_timestamp`]
+pub fn deserialize<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<DateTime<Utc>, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    parse_timestamp(&value).map_err(|_| D::Error::custom(timestamp_error(&value)))
+}
+
+/// Deserialize an optional timestamp with [`parse_timestamp`]; use with `#[serde(default)]`
+pub fn deserialize_option<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Option<DateTime<Utc>>, D::Error> {
+    match Option::<String>::deserialize(deserializer)? {
+        Some(value) => parse_timestamp(&value)
+            .map(Some)
+            .map_err(|_| D::Error::custom(timestamp_error(&value))),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::telemetry::TelemetryReading;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_offset_timestamp_round_trips_as_utc() {
+        let parsed = parse_timestamp("2024-05-01T14:30:00+02:00").unwrap();
+        assert_eq!(parsed, Utc.with_ymd_and_hms(2024, 5, 1, 12, 30, 0).unwrap());
+        assert_eq!(parse_timestamp(&parsed.to_rfc3339()).unwrap(), parsed);
+
+        let reading: TelemetryReading = serde_json::from_value(serde_json::json!({
+            "metric": "temperature",
+            "value": 21.5,
+            "timestamp": "2024-05-01T07:30:00-05:00"
+        }))
+        .unwrap();
+        assert_eq!(reading.timestamp, Some(parsed));
+    }
+
+    #[test]
+    fn test_naive_timestamp_rejected() {
+        for naive in ["2024-05-01T12:30:00", "2024-05-01 12:30:00", "2024-05-01"] {
+            let err = parse_timestamp(naive).unwrap_err();
+            assert!(matches!(err, UaipError::InvalidParameter(_)), "{}", naive);
+        }
+
+        let err = serde_json::from_value::<TelemetryReading>(serde_json::json!({
+            "metric": "temperature",
+            "value": 21.5,
+            "timestamp": "2024-05-01T12:30:00"
+        }))
+        .unwrap_err();
+        assert!(err.to_string().contains("must be RFC 3339 with an offset"));
+
+        let reading: TelemetryReading =
+            serde_json::from_value(serde_json::json!({"metric": "t", "value": 1})).unwrap();
+        assert_eq!(reading.timestamp, None);
+    }
+}
@@ -0,0 +1,179 @@
+//! Absence watchdog
+//!
+//! Scenarios with an `Absence` trigger react to a device going silent. The watchdog
+//! periodically checks devices' `last_seen` times against those triggers and runs the
+//! scenarios that fire; a device's next heartbeat re-arms its trigger.
+
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+use uaip_core::error::{Result, UaipError};
+use uaip_orchestrator::automation::run_actions;
+
+use crate::api::rest::AppState;
+use crate::services::telemetry::archive_executions;
+
+/// Background job evaluating absence triggers against the device registry
+pub struct AbsenceWatchdog {
+    state: Arc<AppState>,
+    interval: Duration,
+}
+
+impl AbsenceWatchdog {
+    pub fn new(state: Arc<AppState>, interval: Duration) -> Self {
+        Self { state, interval }
+    }
+
+    /// Run the watchdog on its interval in a background task
+    pub fn spawn(self) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(self.interval);
+            loop {
+                interval.tick().await;
+                match self.run_once(Utc::now()).await {
+                    Ok(executions) if !executions.is_empty() => {
+                        tracing::info!("Absence watchdog started {} scenarios", executions.len())
+                    }
+                    Ok(_) => {}
+                    Err(e) => tracing::error!("Absence watchdog pass failed: {}", e),
+                }
+            }
+        })
+    }
+
+    /// Check every device's last-seen time as of `now`
+    pub async fn run_once(&self, now: DateTime<Utc>) -> Result<Vec<String>> {
+        let rows: Vec<(String, DateTime<Utc>)> = sqlx::query_as(
+            "SELECT device_id, MAX(last_seen) FROM devices
+             WHERE last_seen IS NOT NULL AND status <> 'deactivated'
+             GROUP BY device_id",
+        )
+        .fetch_all(self.state.db()?)
+        .await
+        .map_err(|e| UaipError::DatabaseError(format!("Failed to load last seen: {}", e)))?;
+
+        check_absences(&self.state, &rows.into_iter().collect(), now).await
+    }
+}
+
+/// Run the scenarios whose absence triggers fire for the given last-seen times
+///
+/// Finished executions are moved to the persistent execution history.
+pub async fn check_absences(
+    state: &AppState,
+    last_seen: &HashMap<String, DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> Result<Vec<String>> {
+    let pending = state
+        .automation
+        .lock()
+        .await
+        .start_absences(last_seen, now)?;
+    let completed = run_actions(pending).await;
+
+    let mut automation = state.automation.lock().await;
+    let executions = automation.complete(completed)?;
+    archive_executions(state, automation, &executions).await;
+    Ok(executions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DEFAULT_WATCHDOG_INTERVAL;
+    use crate::scenario_history::ExecutionQuery;
+    use uaip_orchestrator::scenario::{
+        Scenario, ScenarioAction, ScenarioActionConfig, ScenarioState, ScenarioTrigger, TriggerType,
+    };
+
+    fn watchdog_scenario() -> Scenario {
+        Scenario {
+            id: "pump-watchdog".to_string(),
+            name: "Pump silent".to_string(),
+            description: None,
+            enabled: true,
+            triggers: vec![ScenarioTrigger {
+                trigger_type: TriggerType::Absence,
+                config: HashMap::from([
+                    ("device_id".to_string(), serde_json::json!("pump-1")),
+                    ("timeout_seconds".to_string(), serde_json::json!(120)),
+                ]),
+                conditions: vec![],
+            }],
+            actions: vec![ScenarioActionConfig {
+                action: ScenarioAction::SendNotification,
+                parameters: HashMap::new(),
+                wait: true,
+                timeout_seconds: None,
+            }],
+            state: ScenarioState::Active,
+            metadata: HashMap::new(),
+            execution_count: 0,
+            last_triggered: None,
+            last_result: None,
+            last_result_summary: None,
+            tenant_id: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_silent_device_runs_watchdog_scenario_once() {
+        let state = AppState::new();
+        state
+            .automation
+            .lock()
+            .await
+            .scenario_engine
+            .register_scenario(watchdog_scenario())
+            .unwrap();
+        let seen = Utc::now();
+        let mut last_seen = HashMap::from([("pump-1".to_string(), seen)]);
+        let at = |seconds| seen + chrono::Duration::seconds(seconds);
+
+        assert!(check_absences(&state, &last_seen, at(60))
+            .await
+            .unwrap()
+            .is_empty());
+        let fired = check_absences(&state, &last_seen, at(180)).await.unwrap();
+        assert_eq!(fired.len(), 1);
+        assert!(check_absences(&state, &last_seen, at(240))
+            .await
+            .unwrap()
+            .is_empty());
+
+        // The finished execution went to the history
+        let history = state
+            .scenario_executions
+            .query("pump-watchdog", &ExecutionQuery::default())
+            .await
+            .unwrap();
+        assert_eq!(history.total, 1);
+        assert_eq!(history.items[0].trigger, TriggerType::Absence);
+
+        // Back online, then silent again
+        last_seen.insert("pump-1".to_string(), at(300));
+        assert!(check_absences(&state, &last_seen, at(360))
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            check_absences(&state, &last_seen, at(421))
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_once_needs_database() {
+        let watchdog = AbsenceWatchdog::new(Arc::new(AppState::new()), DEFAULT_WATCHDOG_INTERVAL);
+        let result = watchdog.run_once(Utc::now()).await;
+        assert!(matches!(result, Err(UaipError::NotConfigured(_))));
+    }
+}
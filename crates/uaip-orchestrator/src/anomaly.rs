@@ -0,0 +1,269 @@
+//! Telemetry Anomaly Detection
+//!
+//! Keeps an exponentially weighted mean and variance per device metric and flags
+//! readings more than `sigma_threshold` standard deviations from the mean. The model is
+//! online: each series holds a few numbers, never the readings themselves, and the
+//! number of series is capped.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::scenario::DeviceEvent;
+
+/// Event type of the device events raised for anomalies
+pub const ANOMALY_EVENT_TYPE: &str = "telemetry_anomaly";
+
+/// Anomaly detection settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnomalyConfig {
+    /// Distance from the mean, in standard deviations, beyond which a reading is flagged
+    pub sigma_threshold: f64,
+
+    /// Readings a series needs before it can flag anything
+    pub min_samples: u64,
+
+    /// Approximate number of recent readings the mean and variance reflect
+    pub window: u32,
+
+    /// Most series tracked at once; the least recently updated one is dropped beyond it
+    pub max_series: usize,
+}
+
+impl Default for AnomalyConfig {
+    fn default() -> Self {
+        Self {
+            sigma_threshold: 3.0,
+            min_samples: 30,
+            window: 100,
+            max_series: 10_000,
+        }
+    }
+}
+
+/// Running statistics of one device metric
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SeriesStats {
+    /// Readings seen
+    pub count: u64,
+    pub mean: f64,
+    pub variance: f64,
+    /// Time of the latest reading
+    pub updated_at: DateTime<Utc>,
+}
+
+impl SeriesStats {
+    pub fn std_dev(&self) -> f64 {
+        self.variance.sqrt()
+    }
+
+    /// Fold in a reading
+    ///
+    /// The weight is `1/n` until the window fills, which gives the exact mean and
+    /// variance of the readings so far, and then stays at the window's EWMA weight.
+    fn update(&mut self, value: f64, window: u32, timestamp: DateTime<Utc>) {
+        self.count += 1;
+        let alpha = (1.0 / self.count as f64).max(2.0 / (f64::from(window) + 1.0));
+        let diff = value - self.mean;
+        let increment = alpha * diff;
+        self.mean += increment;
+        self.variance = (1.0 - alpha) * (self.variance + diff * increment);
+        self.updated_at = timestamp;
+    }
+}
+
+/// A reading flagged as an outlier
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Anomaly {
+    pub device_id: String,
+    pub metric: String,
+    pub value: f64,
+    /// Mean of the series before the reading
+    pub mean: f64,
+    /// Standard deviation of the series before the reading
+    pub std_dev: f64,
+    /// Signed distance from the mean in standard deviations
+    pub z_score: f64,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl Anomaly {
+    /// Device event that `DeviceEvent` scenario triggers can match
+    ///
+    /// Trigger conditions can check the `metric`, `value`, `mean`, `std_dev` and
+    /// `z_score` fields.
+    pub fn to_event(&self) -> DeviceEvent {
+        let mut event = DeviceEvent::new(&self.device_id, ANOMALY_EVENT_TYPE)
+            .with_data("metric", serde_json::json!(self.metric))
+            .with_data("value", serde_json::json!(self.value))
+            .with_data("mean", serde_json::json!(self.mean))
+            .with_data("std_dev", serde_json::json!(self.std_dev))
+            .with_data("z_score", serde_json::json!(self.z_score));
+        event.timestamp = self.timestamp;
+        event
+    }
+}
+
+/// Per device metric outlier detector
+#[derive(Debug, Clone, Default)]
+pub struct AnomalyDetector {
+    config: AnomalyConfig,
+    /// Statistics by device ID, then metric
+    series: HashMap<(String, String), SeriesStats>,
+}
+
+impl AnomalyDetector {
+    /// Create a detector
+    pub fn new(config: AnomalyConfig) -> Self {
+        Self {
+            config,
+            series: HashMap::new(),
+        }
+    }
+
+    /// Check a reading against its series, then add it to the series
+    ///
+    /// Readings are only judged once the series has `min_samples` readings and some
+    /// spread; until then they only train the model. Flagged readings are added too, so
+    /// a lasting shift in level stops being flagged as the statistics catch up.
+    pub fn observe(
+        &mut self,
+        device_id: &str,
+        metric: &str,
+        value: f64,
+        timestamp: DateTime<Utc>,
+    ) -> Option<Anomaly> {
+        if !value.is_finite() {
+            return None;
+        }
+
+        let key = (device_id.to_string(), metric.to_string());
+        if !self.series.contains_key(&key) && self.series.len() >= self.config.max_series {
+            self.evict_oldest();
+        }
+
+        let stats = self.series.entry(key).or_insert(SeriesStats {
+            count: 0,
+            mean: 0.0,
+            variance: 0.0,
+            updated_at: timestamp,
+        });
+
+        let std_dev = stats.std_dev();
+        let anomaly = (stats.count >= self.config.min_samples && std_dev > f64::EPSILON)
+            .then(|| (value - stats.mean) / std_dev)
+            .filter(|z_score| z_score.abs() > self.config.sigma_threshold)
+            .map(|z_score| Anomaly {
+                device_id: device_id.to_string(),
+                metric: metric.to_string(),
+                value,
+                mean: stats.mean,
+                std_dev,
+                z_score,
+                timestamp,
+            });
+
+        stats.update(value, self.config.window, timestamp);
+        anomaly
+    }
+
+    /// Statistics of a series, if it is tracked
+    pub fn stats(&self, device_id: &str, metric: &str) -> Option<&SeriesStats> {
+        self.series
+            .get(&(device_id.to_string(), metric.to_string()))
+    }
+
+    /// Number of tracked series
+    pub fn series_count(&self) -> usize {
+        self.series.len()
+    }
+
+    fn evict_oldest(&mut self) {
+        let oldest = self
+            .series
+            .iter()
+            .min_by_key(|(_, stats)| stats.updated_at)
+            .map(|(key, _)| key.clone());
+        if let Some(key) = oldest {
+            self.series.remove(&key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Readings alternating around 20.0 with a spread of 0.5
+    fn train(detector: &mut AnomalyDetector, device_id: &str, readings: usize) {
+        for i in 0..readings {
+            let value = 20.0 + if i % 2 == 0 { 0.5 } else { -0.5 };
+            assert!(detector
+                .observe(device_id, "temperature", value, Utc::now())
+                .is_none());
+        }
+    }
+
+    #[test]
+    fn test_outlier_is_flagged_but_normal_values_are_not() {
+        let mut detector = AnomalyDetector::new(AnomalyConfig::default());
+        train(&mut detector, "sensor-1", 50);
+
+        let stats = detector.stats("sensor-1", "temperature").unwrap();
+        assert!((stats.mean - 20.0).abs() < 0.1);
+        assert!((stats.std_dev() - 0.5).abs() < 0.05);
+
+        for value in [20.0, 20.9, 19.2] {
+            assert!(detector
+                .observe("sensor-1", "temperature", value, Utc::now())
+                .is_none());
+        }
+
+        let anomaly = detector
+            .observe("sensor-1", "temperature", 35.0, Utc::now())
+            .unwrap();
+        assert!(anomaly.z_score > 3.0);
+        assert_eq!(anomaly.metric, "temperature");
+
+        let event = anomaly.to_event();
+        assert_eq!(event.event_type, ANOMALY_EVENT_TYPE);
+        assert_eq!(event.device_id, "sensor-1");
+        assert_eq!(event.data["value"], serde_json::json!(35.0));
+    }
+
+    #[test]
+    fn test_new_series_only_trains() {
+        let mut detector = AnomalyDetector::new(AnomalyConfig {
+            min_samples: 10,
+            ..Default::default()
+        });
+        train(&mut detector, "sensor-1", 9);
+        assert!(detector
+            .observe("sensor-1", "temperature", 1000.0, Utc::now())
+            .is_none());
+
+        // Series are independent per device
+        train(&mut detector, "sensor-2", 5);
+        assert!(detector
+            .observe("sensor-2", "temperature", f64::NAN, Utc::now())
+            .is_none());
+        assert_eq!(detector.stats("sensor-2", "temperature").unwrap().count, 5);
+    }
+
+    #[test]
+    fn test_series_count_is_bounded() {
+        let mut detector = AnomalyDetector::new(AnomalyConfig {
+            max_series: 2,
+            ..Default::default()
+        });
+        let start = Utc::now();
+        for (i, device_id) in ["a", "b", "c"].iter().enumerate() {
+            let timestamp = start + chrono::Duration::seconds(i as i64);
+            detector.observe(device_id, "temperature", 20.0, timestamp);
+        }
+
+        assert_eq!(detector.series_count(), 2);
+        assert!(detector.stats("a", "temperature").is_none());
+        assert!(detector.stats("c", "temperature").is_some());
+    }
+}
@@ -0,0 +1,648 @@
+//! Automation Pipeline
+//!
+//! Connects the rule engine to the scenario engine so that scenarios with a
+//! `RuleTriggered` trigger fire automatically when their rule matches ingested telemetry.
+//! Telemetry anomalies are raised as device events for `DeviceEvent` triggers.
+//! Recorded telemetry can be replayed through a copy of the rules to see what would have
+//! triggered.
+//!
+//! Each entry point also comes in a `start_*` form that only starts the scenario
+//! executions, returning their actions to run with [`run_actions`] and record with
+//! [`AutomationEngine::complete`]. Callers sharing the engine behind a lock use these
+//! to release it while actions such as webhook notifications run.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+use uaip_core::error::{Result, UaipError};
+use uaip_core::group::DeviceGroups;
+
+use crate::anomaly::{Anomaly, AnomalyDetector};
+use crate::rule_engine::{Action, EvaluationContext, Rule, RuleEngine};
+use crate::scenario::{CompletedActions, DeviceEvent, PendingActions, ScenarioEngine};
+
+/// Outcome of ingesting a telemetry sample
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IngestResult {
+    /// Rules that triggered on the sample
+    pub triggered_rules: Vec<String>,
+
+    /// Scenario executions started by those rules
+    pub scenario_executions: Vec<String>,
+}
+
+/// Outcome of checking readings for anomalies
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AnomalyResult {
+    /// Readings flagged as outliers
+    pub anomalies: Vec<Anomaly>,
+
+    /// Scenario executions started by the anomaly events
+    pub scenario_executions: Vec<String>,
+}
+
+/// A stored telemetry reading to replay
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedReading {
+    pub metric: String,
+    pub value: serde_json::Value,
+    pub recorded_at: DateTime<Utc>,
+}
+
+/// A rule that triggered during a replay
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReplayTrigger {
+    /// Time of the readings the rule triggered on
+    pub recorded_at: DateTime<Utc>,
+    pub rule_id: String,
+
+    /// Scenarios the rule would have started
+    pub scenarios: Vec<String>,
+}
+
+/// Outcome of replaying recorded telemetry
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ReplayReport {
+    /// Readings replayed
+    pub readings: usize,
+
+    /// Rule evaluations, one per distinct timestamp
+    pub evaluations: usize,
+
+    /// Triggered rules in replay order
+    pub triggers: Vec<ReplayTrigger>,
+}
+
+/// Rule engine and scenario engine wired together
+pub struct AutomationEngine {
+    /// Rule engine evaluated on every telemetry sample
+    pub rule_engine: RuleEngine,
+
+    /// Scenario engine fired by triggered rules
+    pub scenario_engine: ScenarioEngine,
+
+    /// Device groups referenced by rule conditions and scenario triggers
+    pub groups: DeviceGroups,
+
+    /// Outlier detector fed with numeric telemetry
+    pub anomaly_detector: AnomalyDetector,
+}
+
+impl AutomationEngine {
+    /// Create a new automation engine
+    pub fn new(rule_engine: RuleEngine, scenario_engine: ScenarioEngine) -> Self {
+        Self {
+            rule_engine,
+            scenario_engine,
+            groups: DeviceGroups::new(),
+            anomaly_detector: AnomalyDetector::default(),
+        }
+    }
+
+    /// Use a set of device groups
+    pub fn with_groups(mut self, groups: DeviceGroups) -> Self {
+        self.groups = groups;
+        self
+    }
+
+    /// Use an anomaly detector
+    pub fn with_anomaly_detector(mut self, anomaly_detector: AnomalyDetector) -> Self {
+        self.anomaly_detector = anomaly_detector;
+        self
+    }
+
+    /// Evaluate rules against a telemetry sample and run the scenarios they trigger
+    ///
+    /// Group membership is resolved from `groups` unless the context already provides it.
+    pub async fn ingest_telemetry(&mut self, context: &EvaluationContext) -> Result<IngestResult> {
+        let (triggered_rules, pending) = self.start_telemetry(context)?;
+        let scenario_executions = self.complete(run_actions(pending).await)?;
+
+        if !scenario_executions.is_empty() {
+            tracing::info!(
+                rules = triggered_rules.len(),
+                scenarios = scenario_executions.len(),
+                "Rule-triggered scenarios executed"
+            );
+        }
+
+        Ok(IngestResult {
+            triggered_rules,
+            scenario_executions,
+        })
+    }
+
+    /// Evaluate rules against a telemetry sample and start the scenarios they trigger
+    ///
+    /// Returns the triggered rules and the started executions' actions.
+    pub fn start_telemetry(
+        &mut self,
+        context: &EvaluationContext,
+    ) -> Result<(Vec<String>, Vec<PendingActions>)> {
+        let context = &self.resolve_groups(context);
+
+        let triggered_rules = self.rule_engine.evaluate(context);
+        let mut executions = Vec::new();
+        for rule_id in &triggered_rules {
+            executions.extend(
+                self.scenario_engine
+                    .handle_rule_triggered(rule_id, context)?,
+            );
+        }
+
+        Ok((triggered_rules, self.pending(&executions)?))
+    }
+
+    /// Run the scenarios a device event triggers
+    pub async fn ingest_event(&mut self, event: &DeviceEvent) -> Result<Vec<String>> {
+        let pending = self.start_event(event)?;
+        self.complete(run_actions(pending).await)
+    }
+
+    /// Start the scenarios a device event triggers
+    pub fn start_event(&mut self, event: &DeviceEvent) -> Result<Vec<PendingActions>> {
+        let executions = self.scenario_engine.evaluate_event(event)?;
+        self.pending(&executions)
+    }
+
+    /// Run the scenarios whose absence triggers fire for devices' last-seen times
+    ///
+    /// Group members are resolved from `groups`.
+    pub async fn check_absences(
+        &mut self,
+        last_seen: &HashMap<String, DateTime<Utc>>,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<String>> {
+        let pending = self.start_absences(last_seen, now)?;
+        self.complete(run_actions(pending).await)
+    }
+
+    /// Start the scenarios whose absence triggers fire for devices' last-seen times
+    pub fn start_absences(
+        &mut self,
+        last_seen: &HashMap<String, DateTime<Utc>>,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<PendingActions>> {
+        let groups = self.groups.resolve_all();
+        let executions = self
+            .scenario_engine
+            .evaluate_absence(last_seen, &groups, now)?;
+        self.pending(&executions)
+    }
+
+    /// Feed numeric readings of a device to the anomaly detector
+    ///
+    /// Each flagged reading is raised as a device event, running the scenarios it
+    /// triggers.
+    pub async fn detect_anomalies<'a>(
+        &mut self,
+        device_id: &str,
+        readings: impl IntoIterator<Item = (&'a str, f64, DateTime<Utc>)>,
+    ) -> Result<AnomalyResult> {
+        let (anomalies, pending) = self.start_anomalies(device_id, readings)?;
+        let scenario_executions = self.complete(run_actions(pending).await)?;
+
+        Ok(AnomalyResult {
+            anomalies,
+            scenario_executions,
+        })
+    }
+
+    /// Feed numeric readings of a device to the anomaly detector and start the scenarios
+    /// the resulting anomaly events trigger
+    pub fn start_anomalies<'a>(
+        &mut self,
+        device_id: &str,
+        readings: impl IntoIterator<Item = (&'a str, f64, DateTime<Utc>)>,
+    ) -> Result<(Vec<Anomaly>, Vec<PendingActions>)> {
+        let anomalies: Vec<Anomaly> = readings
+            .into_iter()
+            .filter_map(|(metric, value, timestamp)| {
+                self.anomaly_detector
+                    .observe(device_id, metric, value, timestamp)
+            })
+            .collect();
+
+        let mut pending = Vec::new();
+        for anomaly in &anomalies {
+            tracing::info!(
+                device_id,
+                metric = %anomaly.metric,
+                value = anomaly.value,
+                z_score = anomaly.z_score,
+                "Telemetry anomaly detected"
+            );
+            pending.extend(self.start_event(&anomaly.to_event())?);
+        }
+
+        Ok((anomalies, pending))
+    }
+
+    /// Record actions run with [`run_actions`], returning the completed execution IDs
+    pub fn complete(&mut self, completed: Vec<CompletedActions>) -> Result<Vec<String>> {
+        completed
+            .into_iter()
+            .map(|actions| {
+                let execution_id = actions.execution_id().to_string();
+                self.scenario_engine.complete_actions(actions)?;
+                Ok(execution_id)
+            })
+            .collect()
+    }
+
+    fn pending(&self, executions: &[String]) -> Result<Vec<PendingActions>> {
+        executions
+            .iter()
+            .map(|execution_id| self.scenario_engine.pending_actions(execution_id))
+            .collect()
+    }
+
+    /// Copy of the rules, scenarios and groups with rule cooldowns and history reset
+    ///
+    /// Evaluating the copy leaves this engine's state untouched.
+    pub fn sandbox(&self) -> Result<AutomationEngine> {
+        let mut rule_engine = RuleEngine::new();
+        for rule in self.rule_engine.get_all_rules() {
+            rule_engine.add_rule(Rule {
+                last_executed: None,
+                ..rule
+            });
+        }
+
+        let mut scenario_engine = ScenarioEngine::new();
+        for scenario in self.scenario_engine.get_all_scenarios() {
+            scenario_engine.register_scenario(scenario.clone())?;
+        }
+
+        Ok(Self::new(rule_engine, scenario_engine).with_groups(self.groups.clone()))
+    }
+
+    /// Replay a device's recorded readings through the rules in time order
+    ///
+    /// Readings sharing a timestamp are evaluated together, like a telemetry batch, and
+    /// cooldowns are measured on the recorded timeline. Scenarios are matched but never
+    /// run. With a `speed`, evaluations are paced at the recorded gaps divided by `speed`;
+    /// without one they run back to back. Rule cooldowns and history of this engine are
+    /// updated, so replay on a [`sandbox`](Self::sandbox) to keep live state untouched.
+    pub async fn replay(
+        &self,
+        device_id: &str,
+        tenant_id: Option<&str>,
+        readings: &[RecordedReading],
+        speed: Option<f64>,
+    ) -> Result<ReplayReport> {
+        if speed.is_some_and(|speed| !speed.is_finite() || speed <= 0.0) {
+            return Err(UaipError::InvalidParameter(
+                "speed must be a positive number".to_string(),
+            ));
+        }
+
+        let mut readings: Vec<&RecordedReading> = readings.iter().collect();
+        readings.sort_by_key(|reading| reading.recorded_at);
+
+        let mut report = ReplayReport {
+            readings: readings.len(),
+            ..Default::default()
+        };
+        let mut previous: Option<DateTime<Utc>> = None;
+
+        for batch in readings.chunk_by(|a, b| a.recorded_at == b.recorded_at) {
+            let recorded_at = batch[0].recorded_at;
+            if let (Some(speed), Some(previous)) = (speed, previous) {
+                let gap = (recorded_at - previous).to_std().unwrap_or_default();
+                tokio::time::sleep(Duration::from_secs_f64(gap.as_secs_f64() / speed)).await;
+            }
+            previous = Some(recorded_at);
+
+            let values: HashMap<String, serde_json::Value> = batch
+                .iter()
+                .map(|reading| (reading.metric.clone(), reading.value.clone()))
+                .collect();
+            let mut context =
+                EvaluationContext::new().with_device_state(device_id.to_string(), values.clone());
+            context.telemetry = values;
+            context.timestamp = recorded_at;
+            if let Some(tenant_id) = tenant_id {
+                context = context.with_tenant(tenant_id.to_string());
+            }
+            let context = self.resolve_groups(&context);
+
+            report.evaluations += 1;
+            for rule_id in self.rule_engine.evaluate_at(&context, recorded_at) {
+                report.triggers.push(ReplayTrigger {
+                    recorded_at,
+                    scenarios: self.scenario_engine.scenarios_for_rule(&rule_id, &context),
+                    rule_id,
+                });
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Rules that would trigger on a context, without running anything
+    ///
+    /// Cooldowns are ignored and no rule or scenario state changes.
+    pub fn preview(&self, context: &EvaluationContext) -> Vec<String> {
+        self.rule_engine.preview(&self.resolve_groups(context))
+    }
+
+    /// Devices an action targets, with group membership filled in from `groups`
+    pub fn target_devices(&self, action: &Action, context: &EvaluationContext) -> Vec<String> {
+        action.target_devices(&self.resolve_groups(context))
+    }
+
+    /// Copy of the context with group membership filled in from `groups`
+    ///
+    /// A context with a tenant only sees that tenant's groups.
+    fn resolve_groups(&self, context: &EvaluationContext) -> EvaluationContext {
+        let mut context = context.clone();
+        let groups = match &context.tenant_id {
+            Some(tenant_id) => self.groups.for_tenant(tenant_id),
+            None => self.groups.clone(),
+        };
+        for (group_id, members) in groups.resolve_all() {
+            context.groups.entry(group_id).or_insert(members);
+        }
+        context
+    }
+}
+
+impl Default for AutomationEngine {
+    fn default() -> Self {
+        Self::new(RuleEngine::new(), ScenarioEngine::new())
+    }
+}
+
+/// Run started executions' actions, one execution after another
+pub async fn run_actions(pending: Vec<PendingActions>) -> Vec<CompletedActions> {
+    let mut completed = Vec::with_capacity(pending.len());
+    for actions in pending {
+        completed.push(actions.run().await);
+    }
+    completed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rule_engine::{Condition, ConditionMode, Operator, Rule};
+    use crate::scenario::{
+        Scenario, ScenarioAction, ScenarioActionConfig, ScenarioState, ScenarioTrigger, TriggerType,
+    };
+    use chrono::Utc;
+    use std::collections::HashMap;
+
+    fn overheat_rule() -> Rule {
+        Rule {
+            id: "overheat".to_string(),
+            name: "Overheat".to_string(),
+            description: None,
+            enabled: true,
+            conditions: vec![Condition {
+                field: "temperature".to_string(),
+                operator: Operator::GreaterThan,
+                value: serde_json::json!(30.0),
+                device_id: None,
+                group_id: None,
+                aggregate: None,
+            }],
+            actions: vec![],
+            condition_mode: ConditionMode::All,
+            priority: 1,
+            cooldown_seconds: None,
+            last_executed: None,
+            tenant_id: None,
+            metadata: HashMap::new(),
+        }
+    }
+
+    fn cooling_scenario() -> Scenario {
+        Scenario {
+            id: "start_cooling".to_string(),
+            name: "Start Cooling".to_string(),
+            description: None,
+            enabled: true,
+            triggers: vec![ScenarioTrigger {
+                trigger_type: TriggerType::RuleTriggered,
+                config: {
+                    let mut config = HashMap::new();
+                    config.insert("rule_id".to_string(), serde_json::json!("overheat"));
+                    config
+                },
+                conditions: vec![],
+            }],
+            actions: vec![ScenarioActionConfig {
+                action: ScenarioAction::SendNotification,
+                parameters: HashMap::new(),
+                wait: true,
+                timeout_seconds: None,
+            }],
+            state: ScenarioState::Active,
+            metadata: HashMap::new(),
+            execution_count: 0,
+            last_triggered: None,
+            last_result: None,
+            last_result_summary: None,
+            tenant_id: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_telemetry_triggers_rule_and_scenario() {
+        let mut rule_engine = RuleEngine::new();
+        rule_engine.add_rule(overheat_rule());
+        let mut scenario_engine = ScenarioEngine::new();
+        scenario_engine
+            .register_scenario(cooling_scenario())
+            .unwrap();
+        let mut automation = AutomationEngine::new(rule_engine, scenario_engine);
+
+        let cool = EvaluationContext::new()
+            .with_telemetry("temperature".to_string(), serde_json::json!(22.0));
+        let result = automation.ingest_telemetry(&cool).await.unwrap();
+        assert!(result.triggered_rules.is_empty());
+        assert!(result.scenario_executions.is_empty());
+
+        let hot = EvaluationContext::new()
+            .with_telemetry("temperature".to_string(), serde_json::json!(35.0));
+        let result = automation.ingest_telemetry(&hot).await.unwrap();
+        assert_eq!(result.triggered_rules, vec!["overheat".to_string()]);
+        assert_eq!(result.scenario_executions.len(), 1);
+
+        let execution = automation
+            .scenario_engine
+            .get_execution(&result.scenario_executions[0])
+            .unwrap();
+        assert_eq!(execution.scenario_id, "start_cooling");
+        assert_eq!(execution.trigger, TriggerType::RuleTriggered);
+        assert_eq!(execution.state, ScenarioState::Completed);
+        assert_eq!(
+            execution.trigger_context.get("temperature"),
+            Some(&serde_json::json!(35.0))
+        );
+        assert_eq!(execution.actions_executed.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_group_condition_resolves_nested_groups() {
+        use uaip_core::group::DeviceGroup;
+
+        let mut rule = overheat_rule();
+        rule.conditions[0].group_id = Some("floor-1".to_string());
+        let mut rule_engine = RuleEngine::new();
+        rule_engine.add_rule(rule);
+
+        let groups = DeviceGroups::from_groups(vec![
+            DeviceGroup::new("kitchen", "Kitchen").with_devices(["sensor-1"]),
+            DeviceGroup::new("floor-1", "First floor").with_subgroups(["kitchen"]),
+        ])
+        .unwrap();
+        let mut automation =
+            AutomationEngine::new(rule_engine, ScenarioEngine::new()).with_groups(groups);
+
+        let state = HashMap::from([("temperature".to_string(), serde_json::json!(35.0))]);
+        let outside =
+            EvaluationContext::new().with_device_state("sensor-9".to_string(), state.clone());
+        let result = automation.ingest_telemetry(&outside).await.unwrap();
+        assert!(result.triggered_rules.is_empty());
+
+        let inside = EvaluationContext::new().with_device_state("sensor-1".to_string(), state);
+        let result = automation.ingest_telemetry(&inside).await.unwrap();
+        assert_eq!(result.triggered_rules, vec!["overheat".to_string()]);
+
+        // Telemetry of a tenant only sees that tenant's groups
+        let other_tenant = inside.clone().with_tenant("globex".to_string());
+        let result = automation.ingest_telemetry(&other_tenant).await.unwrap();
+        assert!(result.triggered_rules.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_anomaly_fires_device_event_scenario() {
+        use crate::anomaly::{AnomalyConfig, ANOMALY_EVENT_TYPE};
+
+        let mut scenario = cooling_scenario();
+        scenario.id = "investigate".to_string();
+        scenario.triggers[0] = ScenarioTrigger {
+            trigger_type: TriggerType::DeviceEvent,
+            config: HashMap::from([(
+                "event_type".to_string(),
+                serde_json::json!(ANOMALY_EVENT_TYPE),
+            )]),
+            conditions: vec![],
+        };
+        let mut scenario_engine = ScenarioEngine::new();
+        scenario_engine.register_scenario(scenario).unwrap();
+        let mut automation = AutomationEngine::new(RuleEngine::new(), scenario_engine)
+            .with_anomaly_detector(AnomalyDetector::new(AnomalyConfig {
+                min_samples: 10,
+                ..Default::default()
+            }));
+
+        let normal: Vec<(&str, f64, _)> = (0..20)
+            .map(|i| ("temperature", 21.0 + (i % 3) as f64 * 0.1, Utc::now()))
+            .collect();
+        let result = automation
+            .detect_anomalies("sensor-1", normal)
+            .await
+            .unwrap();
+        assert!(result.anomalies.is_empty());
+        assert!(result.scenario_executions.is_empty());
+
+        let result = automation
+            .detect_anomalies("sensor-1", [("temperature", 60.0, Utc::now())])
+            .await
+            .unwrap();
+        assert_eq!(result.anomalies.len(), 1);
+        assert_eq!(result.scenario_executions.len(), 1);
+
+        let execution = automation
+            .scenario_engine
+            .get_execution(&result.scenario_executions[0])
+            .unwrap();
+        assert_eq!(execution.scenario_id, "investigate");
+        assert_eq!(execution.state, ScenarioState::Completed);
+        assert_eq!(
+            execution.trigger_context.get("metric"),
+            Some(&serde_json::json!("temperature"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_replay_reports_triggers_in_order_without_side_effects() {
+        let mut overheat = overheat_rule();
+        overheat.cooldown_seconds = Some(60);
+        let mut humid = overheat_rule();
+        humid.id = "humid".to_string();
+        humid.conditions[0].field = "humidity".to_string();
+        humid.conditions[0].value = serde_json::json!(80.0);
+        let mut rule_engine = RuleEngine::new();
+        rule_engine.add_rule(overheat);
+        rule_engine.add_rule(humid);
+        let mut scenario_engine = ScenarioEngine::new();
+        scenario_engine
+            .register_scenario(cooling_scenario())
+            .unwrap();
+        let automation = AutomationEngine::new(rule_engine, scenario_engine);
+
+        let start = Utc::now() - chrono::Duration::hours(1);
+        let at = |secs| start + chrono::Duration::seconds(secs);
+        let reading = |metric: &str, value: f64, secs| RecordedReading {
+            metric: metric.to_string(),
+            value: serde_json::json!(value),
+            recorded_at: at(secs),
+        };
+        // Out of order on purpose; replay sorts by time
+        let readings = [
+            reading("temperature", 40.0, 90),
+            reading("temperature", 25.0, 0),
+            reading("temperature", 35.0, 10),
+            reading("temperature", 36.0, 20),
+            reading("humidity", 85.0, 30),
+            reading("temperature", 31.0, 30),
+        ];
+
+        let sandbox = automation.sandbox().unwrap();
+        let started = std::time::Instant::now();
+        let report = sandbox
+            .replay("sensor-1", None, &readings, Some(2000.0))
+            .await
+            .unwrap();
+        // 90 recorded seconds at 2000x
+        assert!(started.elapsed() >= Duration::from_millis(40));
+
+        assert_eq!(report.readings, 6);
+        assert_eq!(report.evaluations, 5);
+        let triggers: Vec<(DateTime<Utc>, &str, Vec<String>)> = report
+            .triggers
+            .iter()
+            .map(|t| (t.recorded_at, t.rule_id.as_str(), t.scenarios.clone()))
+            .collect();
+        let cooling = vec!["start_cooling".to_string()];
+        // The reading at 20s and 30s falls in the overheat cooldown started at 10s
+        assert_eq!(
+            triggers,
+            [
+                (at(10), "overheat", cooling.clone()),
+                (at(30), "humid", vec![]),
+                (at(90), "overheat", cooling),
+            ]
+        );
+
+        // The live engine neither fired rules nor ran scenarios
+        assert!(automation
+            .rule_engine
+            .get_rule("overheat")
+            .unwrap()
+            .last_executed
+            .is_none());
+        assert_eq!(automation.scenario_engine.execution_count(), 0);
+        assert_eq!(sandbox.scenario_engine.execution_count(), 0);
+
+        let result = sandbox.replay("sensor-1", None, &readings, Some(0.0)).await;
+        assert!(result.is_err());
+    }
+}
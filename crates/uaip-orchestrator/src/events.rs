@@ -0,0 +1,163 @@
+//! Execution State Events
+//!
+//! Workflow and scenario engines report every execution state transition as an
+//! [`ExecutionStateChanged`] event. Events go to the attached sink; until a sink is
+//! attached they are buffered, up to [`EVENT_BUFFER_CAPACITY`], and handed over when it is.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+/// Most events kept while no sink is attached; the oldest are dropped beyond it
+pub const EVENT_BUFFER_CAPACITY: usize = 1024;
+
+/// Engine an execution belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExecutionKind {
+    Workflow,
+    Scenario,
+}
+
+/// An execution moved from one state to another
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExecutionStateChanged {
+    pub kind: ExecutionKind,
+    pub execution_id: String,
+    /// State before the transition; `None` when the execution starts
+    pub old: Option<String>,
+    pub new: String,
+    pub at: DateTime<Utc>,
+    /// Tenant owning the execution's scenario; `None` for unscoped scenarios and workflows
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant_id: Option<String>,
+}
+
+impl ExecutionStateChanged {
+    /// Transition happening now, with states named as they serialize
+    pub fn new<S: Serialize>(
+        kind: ExecutionKind,
+        execution_id: &str,
+        old: Option<&S>,
+        new: &S,
+    ) -> Self {
+        Self {
+            kind,
+            execution_id: execution_id.to_string(),
+            old: old.map(state_name),
+            new: state_name(new),
+            at: Utc::now(),
+            tenant_id: None,
+        }
+    }
+}
+
+fn state_name<S: Serialize>(state: &S) -> String {
+    match serde_json::to_value(state) {
+        Ok(serde_json::Value::String(name)) => name,
+        Ok(value) => value.to_string(),
+        Err(_) => String::new(),
+    }
+}
+
+/// Destination for execution state events
+pub trait ExecutionEventSink: Send + Sync {
+    fn emit(&self, event: ExecutionStateChanged);
+}
+
+impl ExecutionEventSink for broadcast::Sender<ExecutionStateChanged> {
+    fn emit(&self, event: ExecutionStateChanged) {
+        // No subscribers is not an error
+        let _ = self.send(event);
+    }
+}
+
+/// Emitter held by an engine
+#[derive(Default)]
+pub struct ExecutionEvents {
+    sink: Option<Arc<dyn ExecutionEventSink>>,
+    /// Events emitted before a sink was attached
+    buffer: VecDeque<ExecutionStateChanged>,
+}
+
+impl ExecutionEvents {
+    /// Attach a sink, handing it the buffered events first
+    pub fn set_sink(&mut self, sink: Arc<dyn ExecutionEventSink>) {
+        for event in self.buffer.drain(..) {
+            sink.emit(event);
+        }
+        self.sink = Some(sink);
+    }
+
+    /// Send an event to the sink, or buffer it while there is none
+    pub fn emit(&mut self, event: ExecutionStateChanged) {
+        tracing::debug!(
+            execution_id = %event.execution_id,
+            old = ?event.old,
+            new = %event.new,
+            "Execution state changed"
+        );
+        match &self.sink {
+            Some(sink) => sink.emit(event),
+            None => {
+                if self.buffer.len() >= EVENT_BUFFER_CAPACITY {
+                    self.buffer.pop_front();
+                }
+                self.buffer.push_back(event);
+            }
+        }
+    }
+
+    /// Events waiting for a sink
+    pub fn buffered(&self) -> impl Iterator<Item = &ExecutionStateChanged> {
+        self.buffer.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workflow::WorkflowState;
+
+    #[test]
+    fn test_events_are_buffered_until_a_sink_is_attached() {
+        let mut events = ExecutionEvents::default();
+        for i in 0..EVENT_BUFFER_CAPACITY + 1 {
+            events.emit(ExecutionStateChanged::new(
+                ExecutionKind::Workflow,
+                &i.to_string(),
+                None,
+                &WorkflowState::Running,
+            ));
+        }
+        assert_eq!(events.buffered().count(), EVENT_BUFFER_CAPACITY);
+
+        let (sender, mut receiver) = broadcast::channel(EVENT_BUFFER_CAPACITY * 2);
+        events.set_sink(Arc::new(sender));
+        assert_eq!(events.buffered().count(), 0);
+
+        // The oldest event was dropped
+        let first = receiver.try_recv().unwrap();
+        assert_eq!(first.execution_id, "1");
+        assert_eq!(first.old, None);
+        assert_eq!(first.new, "running");
+
+        events.emit(ExecutionStateChanged::new(
+            ExecutionKind::Workflow,
+            "next",
+            Some(&WorkflowState::Running),
+            &WorkflowState::Completed,
+        ));
+        let last = std::iter::from_fn(|| receiver.try_recv().ok())
+            .last()
+            .unwrap();
+        assert_eq!(last.execution_id, "next");
+        assert_eq!(last.old.as_deref(), Some("running"));
+        assert_eq!(
+            serde_json::to_value(&last).unwrap()["kind"],
+            serde_json::json!("workflow")
+        );
+    }
+}
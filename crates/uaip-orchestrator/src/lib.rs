@@ -2,7 +2,12 @@
 //!
 //! This crate handles scenario execution, rule evaluation, workflow management, and media processing.
 
+pub mod anomaly;
+pub mod automation;
+pub mod events;
 pub mod media;
+pub mod media_processing;
+pub mod notification;
 pub mod rule_engine;
 pub mod scenario;
 pub mod streaming;
@@ -167,7 +167,7 @@ pub enum MediaType {
 }
 
 /// Media dimensions (width x height)
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct MediaDimensions {
     pub width: u32,
     pub height: u32,
@@ -200,7 +200,7 @@ impl MediaDimensions {
 }
 
 /// Codec information
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct CodecInfo {
     /// Video codec (e.g., H.264, VP9, AV1)
     pub video: Option<String>,
@@ -272,6 +272,10 @@ pub struct StreamConfig {
 
     /// Buffer size in seconds
     pub buffer_secs: f32,
+
+    /// Device serving the stream itself; `media_id` is nil for such streams
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_device: Option<String>,
 }
 
 impl StreamConfig {
@@ -292,6 +296,7 @@ impl StreamConfig {
             stream_url: String::new(),
             is_live: false,
             buffer_secs: 30.0,
+            source_device: None,
         }
     }
 
@@ -303,6 +308,15 @@ impl StreamConfig {
         config.segment_duration_secs = 2.0;
         config
     }
+
+    /// Create configuration for a device's live feed served from `stream_url`
+    pub fn from_device(device_id: &str, stream_url: &str, protocol: StreamProtocol) -> Self {
+        let mut config = Self::live(Uuid::nil());
+        config.protocol = protocol;
+        config.stream_url = stream_url.to_string();
+        config.source_device = Some(device_id.to_string());
+        config
+    }
 }
 
 /// Streaming protocol
@@ -363,6 +377,19 @@ impl StreamQuality {
             StreamQuality::UltraHd => Some(2160),
         }
     }
+
+    /// Highest preset not taller than `height`, or `Low` below 240p
+    pub fn for_height(height: u32) -> Self {
+        [
+            StreamQuality::UltraHd,
+            StreamQuality::FullHd,
+            StreamQuality::High,
+            StreamQuality::Medium,
+        ]
+        .into_iter()
+        .find(|quality| quality.height().is_some_and(|h| h <= height))
+        .unwrap_or(StreamQuality::Low)
+    }
 }
 
 /// Media processing job
@@ -559,6 +586,9 @@ mod tests {
         assert_eq!(StreamQuality::Low.height(), Some(240));
         assert_eq!(StreamQuality::High.height(), Some(720));
         assert_eq!(StreamQuality::FullHd.height(), Some(1080));
+        assert_eq!(StreamQuality::for_height(1080), StreamQuality::FullHd);
+        assert_eq!(StreamQuality::for_height(600), StreamQuality::Medium);
+        assert_eq!(StreamQuality::for_height(120), StreamQuality::Low);
     }
 
     #[test]
@@ -580,6 +610,7 @@ mod tests {
         assert!(config.is_live);
         assert_eq!(config.protocol, StreamProtocol::WebRtc);
         assert_eq!(config.buffer_secs, 2.0);
+        assert_eq!(config.source_device, None);
     }
 
     #[test]
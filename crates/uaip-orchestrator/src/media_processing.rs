@@ -0,0 +1,586 @@
+//! Media Processing Jobs
+//!
+//! Executes `ProcessingOperation`s against stored media. Thumbnail generation
+//! extracts a video frame through a pluggable `FrameExtractor` (ffmpeg by
+//! default) and stores the image through `MediaStorage`. Metadata analysis
+//! probes the file through a `MediaProber` (ffprobe by default).
+
+use async_trait::async_trait;
+use chrono::Utc;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use tokio::process::Command;
+use uaip_core::error::{Result, UaipError};
+
+use crate::media::{
+    CodecInfo, JobStatus, MediaDimensions, MediaFile, MediaProcessingJob, MediaType,
+    ProcessingOperation,
+};
+
+/// Default thumbnail width in pixels
+pub const THUMBNAIL_WIDTH: u32 = 320;
+
+/// Storage backend for generated media artifacts
+#[async_trait]
+pub trait MediaStorage: Send + Sync {
+    /// Store `data` under `key`, returning the URL it is served from
+    async fn put(&self, key: &str, content_type: &str, data: Vec<u8>) -> Result<String>;
+}
+
+/// Stores media under a local directory served at a base URL
+#[derive(Debug, Clone)]
+pub struct LocalMediaStorage {
+    root: PathBuf,
+    base_url: String,
+}
+
+impl LocalMediaStorage {
+    /// Create storage rooted at `root`, with files served under `base_url`
+    pub fn new(root: impl Into<PathBuf>, base_url: impl Into<String>) -> Self {
+        Self {
+            root: root.into(),
+            base_url: base_url.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl MediaStorage for LocalMediaStorage {
+    async fn put(&self, key: &str, _content_type: &str, data: Vec<u8>) -> Result<String> {
+        let relative = Path::new(key);
+        if !relative
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)))
+        {
+            return Err(UaipError::InvalidParameter(format!(
+                "Invalid storage key: {}",
+                key
+            )));
+        }
+
+        let path = self.root.join(relative);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await.map_err(|e| {
+                UaipError::InternalError(format!("Failed to create {}: {}", parent.display(), e))
+            })?;
+        }
+        tokio::fs::write(&path, data).await.map_err(|e| {
+            UaipError::InternalError(format!("Failed to write {}: {}", path.display(), e))
+        })?;
+
+        Ok(format!("{}/{}", self.base_url.trim_end_matches('/'), key))
+    }
+}
+
+/// Extracts a single frame from a video as a JPEG image
+#[async_trait]
+pub trait FrameExtractor: Send + Sync {
+    async fn extract_frame(
+        &self,
+        video_path: &str,
+        timestamp_secs: f32,
+        width: u32,
+        height: u32,
+    ) -> Result<Vec<u8>>;
+}
+
+/// Extracts frames by running the `ffmpeg` command line tool
+#[derive(Debug, Clone)]
+pub struct FfmpegFrameExtractor {
+    binary: PathBuf,
+}
+
+impl FfmpegFrameExtractor {
+    /// Use the ffmpeg binary at `binary`
+    pub fn new(binary: impl Into<PathBuf>) -> Self {
+        Self {
+            binary: binary.into(),
+        }
+    }
+}
+
+impl Default for FfmpegFrameExtractor {
+    fn default() -> Self {
+        Self::new("ffmpeg")
+    }
+}
+
+#[async_trait]
+impl FrameExtractor for FfmpegFrameExtractor {
+    async fn extract_frame(
+        &self,
+        video_path: &str,
+        timestamp_secs: f32,
+        width: u32,
+        height: u32,
+    ) -> Result<Vec<u8>> {
+        let output = Command::new(&self.binary)
+            .args(["-v", "error", "-ss"])
+            .arg(format!("{:.3}", timestamp_secs))
+            .arg("-i")
+            .arg(video_path)
+            .args(["-frames:v", "1", "-vf"])
+            .arg(format!("scale={}:{}", width, height))
+            .args(["-f", "image2pipe", "-c:v", "mjpeg", "pipe:1"])
+            .kill_on_drop(true)
+            .output()
+            .await
+            .map_err(|e| {
+                UaipError::ResourceUnavailable(format!(
+                    "Failed to run {}: {}",
+                    self.binary.display(),
+                    e
+                ))
+            })?;
+
+        if !output.status.success() || output.stdout.is_empty() {
+            return Err(UaipError::InternalError(format!(
+                "ffmpeg failed to extract frame from {}: {}",
+                video_path,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        Ok(output.stdout)
+    }
+}
+
+/// Thumbnail operation for a newly uploaded video
+///
+/// Takes a frame one second in (or halfway through shorter videos) at
+/// `THUMBNAIL_WIDTH`, keeping the video's aspect ratio when it is known.
+pub fn default_thumbnail(media: &MediaFile) -> ProcessingOperation {
+    let timestamp_secs = media
+        .duration_secs
+        .map_or(1.0, |duration| (duration / 2.0).min(1.0)) as f32;
+    let height = media
+        .dimensions
+        .filter(|d| d.width > 0)
+        .map_or(THUMBNAIL_WIDTH * 9 / 16, |d| {
+            // Even heights keep common encoders happy
+            ((THUMBNAIL_WIDTH as f32 / d.aspect_ratio()) as u32 / 2 * 2).max(2)
+        });
+
+    ProcessingOperation::GenerateThumbnail {
+        timestamp_secs,
+        width: THUMBNAIL_WIDTH,
+        height,
+    }
+}
+
+/// Runs thumbnail generation jobs
+#[derive(Clone)]
+pub struct ThumbnailGenerator {
+    extractor: Arc<dyn FrameExtractor>,
+    storage: Arc<dyn MediaStorage>,
+}
+
+impl ThumbnailGenerator {
+    /// Create a generator from a frame extractor and storage backend
+    pub fn new(extractor: Arc<dyn FrameExtractor>, storage: Arc<dyn MediaStorage>) -> Self {
+        Self { extractor, storage }
+    }
+
+    /// Run a `GenerateThumbnail` job for `media`
+    ///
+    /// Updates the job status as it runs and sets the media's `thumbnail_url` on
+    /// success. Failures are recorded on the job and returned.
+    pub async fn run(&self, job: &mut MediaProcessingJob, media: &mut MediaFile) -> Result<()> {
+        start_job(job);
+        let result = self.generate(&job.operation, media).await;
+        finish_job(job, result.map(|url| media.thumbnail_url = Some(url)))
+    }
+
+    async fn generate(&self, operation: &ProcessingOperation, media: &MediaFile) -> Result<String> {
+        let ProcessingOperation::GenerateThumbnail {
+            timestamp_secs,
+            width,
+            height,
+        } = *operation
+        else {
+            return Err(UaipError::InvalidParameter(
+                "Job is not a thumbnail operation".to_string(),
+            ));
+        };
+        if media.media_type != MediaType::Video {
+            return Err(UaipError::InvalidParameter(format!(
+                "Cannot generate a thumbnail for {:?} media",
+                media.media_type
+            )));
+        }
+        if width == 0 || height == 0 {
+            return Err(UaipError::InvalidParameter(
+                "Thumbnail dimensions must be non-zero".to_string(),
+            ));
+        }
+
+        let frame = self
+            .extractor
+            .extract_frame(&media.storage_path, timestamp_secs, width, height)
+            .await?;
+        self.storage
+            .put(&format!("thumbnails/{}.jpg", media.id), "image/jpeg", frame)
+            .await
+    }
+}
+
+/// Technical metadata read from a media file
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MediaProbe {
+    pub duration_secs: Option<f64>,
+    pub dimensions: Option<MediaDimensions>,
+    pub codec: Option<CodecInfo>,
+    pub bitrate_kbps: Option<u32>,
+    pub framerate_fps: Option<f32>,
+}
+
+/// Reads technical metadata from a stored media file
+#[async_trait]
+pub trait MediaProber: Send + Sync {
+    async fn probe(&self, path: &str) -> Result<MediaProbe>;
+}
+
+/// Probes files by running the `ffprobe` command line tool
+#[derive(Debug, Clone)]
+pub struct FfprobeProber {
+    binary: PathBuf,
+}
+
+impl FfprobeProber {
+    /// Use the ffprobe binary at `binary`
+    pub fn new(binary: impl Into<PathBuf>) -> Self {
+        Self {
+            binary: binary.into(),
+        }
+    }
+}
+
+impl Default for FfprobeProber {
+    fn default() -> Self {
+        Self::new("ffprobe")
+    }
+}
+
+#[async_trait]
+impl MediaProber for FfprobeProber {
+    async fn probe(&self, path: &str) -> Result<MediaProbe> {
+        let output = Command::new(&self.binary)
+            .args(["-v", "error", "-print_format", "json"])
+            .args(["-show_format", "-show_streams"])
+            .arg(path)
+            .kill_on_drop(true)
+            .output()
+            .await
+            .map_err(|e| {
+                UaipError::ResourceUnavailable(format!(
+                    "Failed to run {}: {}",
+                    self.binary.display(),
+                    e
+                ))
+            })?;
+
+        if !output.status.success() {
+            return Err(UaipError::InternalError(format!(
+                "ffprobe failed to read {}: {}",
+                path,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        parse_ffprobe(&serde_json::from_slice(&output.stdout)?)
+    }
+}
+
+/// Read a probe result from `ffprobe -print_format json -show_format -show_streams`
+pub fn parse_ffprobe(output: &serde_json::Value) -> Result<MediaProbe> {
+    let streams = output["streams"]
+        .as_array()
+        .ok_or_else(|| UaipError::InvalidMessage("ffprobe output has no streams".to_string()))?;
+    let stream = |kind: &str| {
+        streams
+            .iter()
+            .find(|s| s["codec_type"].as_str() == Some(kind))
+    };
+    // ffprobe reports most numbers as strings
+    let number = |value: &serde_json::Value| {
+        value
+            .as_f64()
+            .or_else(|| value.as_str().and_then(|s| s.parse().ok()))
+    };
+    let string = |value: &serde_json::Value| value.as_str().map(String::from);
+
+    let video = stream("video");
+    let audio = stream("audio");
+    let format = &output["format"];
+
+    let dimensions = video.and_then(|v| {
+        let width = v["width"].as_u64()?;
+        let height = v["height"].as_u64()?;
+        Some(MediaDimensions::new(
+            u32::try_from(width).ok()?,
+            u32::try_from(height).ok()?,
+        ))
+    });
+    let framerate_fps = video
+        .and_then(|v| v["avg_frame_rate"].as_str())
+        .and_then(|rate| {
+            let (num, den) = rate.split_once('/').unwrap_or((rate, "1"));
+            let (num, den) = (num.parse::<f32>().ok()?, den.parse::<f32>().ok()?);
+            (num > 0.0 && den > 0.0).then(|| num / den)
+        });
+    let codec = (video.is_some() || audio.is_some()).then(|| CodecInfo {
+        video: video.and_then(|v| string(&v["codec_name"])),
+        audio: audio.and_then(|a| string(&a["codec_name"])),
+        profile: video.or(audio).and_then(|s| string(&s["profile"])),
+    });
+
+    Ok(MediaProbe {
+        duration_secs: number(&format["duration"]).filter(|d| *d > 0.0),
+        dimensions,
+        codec,
+        bitrate_kbps: number(&format["bit_rate"]).map(|bps| (bps / 1000.0).round() as u32),
+        framerate_fps,
+    })
+}
+
+/// Runs metadata analysis jobs
+#[derive(Clone)]
+pub struct MetadataAnalyzer {
+    prober: Arc<dyn MediaProber>,
+}
+
+impl MetadataAnalyzer {
+    /// Create an analyzer from a prober
+    pub fn new(prober: Arc<dyn MediaProber>) -> Self {
+        Self { prober }
+    }
+
+    /// Run an `AnalyzeMetadata` job for `media`
+    ///
+    /// On success the probed duration, dimensions, codec, bitrate and frame rate
+    /// replace whatever was recorded for the media, including fields the probe
+    /// could not find. Failures are recorded on the job and returned.
+    pub async fn run(&self, job: &mut MediaProcessingJob, media: &mut MediaFile) -> Result<()> {
+        start_job(job);
+        let result = match job.operation {
+            ProcessingOperation::AnalyzeMetadata => self.prober.probe(&media.storage_path).await,
+            _ => Err(UaipError::InvalidParameter(
+                "Job is not a metadata analysis".to_string(),
+            )),
+        };
+
+        finish_job(
+            job,
+            result.map(|probe| {
+                media.duration_secs = probe.duration_secs;
+                media.dimensions = probe.dimensions;
+                media.codec = probe.codec;
+                media.bitrate_kbps = probe.bitrate_kbps;
+                media.framerate_fps = probe.framerate_fps;
+            }),
+        )
+    }
+}
+
+fn start_job(job: &mut MediaProcessingJob) {
+    job.status = JobStatus::Running;
+    job.started_at = Some(Utc::now());
+}
+
+/// Record the outcome of a job, passing the result through
+fn finish_job(job: &mut MediaProcessingJob, result: Result<()>) -> Result<()> {
+    job.completed_at = Some(Utc::now());
+    match &result {
+        Ok(()) => {
+            job.status = JobStatus::Completed;
+            job.progress = 100.0;
+        }
+        Err(e) => {
+            job.status = JobStatus::Failed;
+            job.error = Some(e.to_string());
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use tokio::sync::Mutex;
+
+    /// Returns a fixed frame and records the requests it receives
+    #[derive(Default)]
+    struct MockExtractor {
+        requests: Mutex<Vec<(String, f32, u32, u32)>>,
+    }
+
+    #[async_trait]
+    impl FrameExtractor for MockExtractor {
+        async fn extract_frame(
+            &self,
+            video_path: &str,
+            timestamp_secs: f32,
+            width: u32,
+            height: u32,
+        ) -> Result<Vec<u8>> {
+            self.requests.lock().await.push((
+                video_path.to_string(),
+                timestamp_secs,
+                width,
+                height,
+            ));
+            Ok(vec![0xFF, 0xD8, 0xFF])
+        }
+    }
+
+    #[derive(Default)]
+    struct MemoryStorage {
+        objects: Mutex<HashMap<String, Vec<u8>>>,
+    }
+
+    #[async_trait]
+    impl MediaStorage for MemoryStorage {
+        async fn put(&self, key: &str, _content_type: &str, data: Vec<u8>) -> Result<String> {
+            self.objects.lock().await.insert(key.to_string(), data);
+            Ok(format!("memory://{}", key))
+        }
+    }
+
+    fn video() -> MediaFile {
+        let mut media = MediaFile::new("clip.mp4".to_string(), MediaType::Video, "mp4".to_string());
+        media.storage_path = "/videos/clip.mp4".to_string();
+        media.duration_secs = Some(12.0);
+        media.dimensions = Some(MediaDimensions::new(1920, 1080));
+        media
+    }
+
+    #[tokio::test]
+    async fn test_thumbnail_job_sets_url() {
+        let extractor = Arc::new(MockExtractor::default());
+        let storage = Arc::new(MemoryStorage::default());
+        let generator = ThumbnailGenerator::new(extractor.clone(), storage.clone());
+
+        let mut media = video();
+        let mut job = MediaProcessingJob::new(media.id, default_thumbnail(&media));
+        generator.run(&mut job, &mut media).await.unwrap();
+
+        assert_eq!(job.status, JobStatus::Completed);
+        assert_eq!(job.progress, 100.0);
+        assert!(job.completed_at.is_some());
+
+        let key = format!("thumbnails/{}.jpg", media.id);
+        assert_eq!(media.thumbnail_url, Some(format!("memory://{}", key)));
+        assert!(storage.objects.lock().await.contains_key(&key));
+        assert_eq!(
+            *extractor.requests.lock().await,
+            vec![("/videos/clip.mp4".to_string(), 1.0, 320, 180)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_thumbnail_job_rejects_non_video() {
+        let generator = ThumbnailGenerator::new(
+            Arc::new(MockExtractor::default()),
+            Arc::new(MemoryStorage::default()),
+        );
+
+        let mut media =
+            MediaFile::new("photo.png".to_string(), MediaType::Image, "png".to_string());
+        let mut job = MediaProcessingJob::new(media.id, default_thumbnail(&media));
+        assert!(generator.run(&mut job, &mut media).await.is_err());
+        assert_eq!(job.status, JobStatus::Failed);
+        assert!(job.error.is_some());
+        assert!(media.thumbnail_url.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_local_storage_writes_under_root() {
+        let root = std::env::temp_dir().join(format!("uaip-media-{}", uuid::Uuid::new_v4()));
+        let storage = LocalMediaStorage::new(&root, "http://hub/media/");
+
+        let url = storage
+            .put("thumbnails/a.jpg", "image/jpeg", vec![1, 2, 3])
+            .await
+            .unwrap();
+        assert_eq!(url, "http://hub/media/thumbnails/a.jpg");
+        assert_eq!(
+            std::fs::read(root.join("thumbnails/a.jpg")).unwrap(),
+            vec![1, 2, 3]
+        );
+        assert!(storage
+            .put("../escape.jpg", "image/jpeg", vec![])
+            .await
+            .is_err());
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    struct MockProber(MediaProbe);
+
+    #[async_trait]
+    impl MediaProber for MockProber {
+        async fn probe(&self, _path: &str) -> Result<MediaProbe> {
+            Ok(self.0.clone())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_metadata_job_overwrites_client_claims() {
+        let probe = MediaProbe {
+            duration_secs: Some(93.5),
+            dimensions: Some(MediaDimensions::new(1280, 720)),
+            codec: Some(CodecInfo {
+                video: Some("h264".to_string()),
+                audio: Some("aac".to_string()),
+                profile: Some("High".to_string()),
+            }),
+            bitrate_kbps: Some(2400),
+            framerate_fps: None,
+        };
+        let analyzer = MetadataAnalyzer::new(Arc::new(MockProber(probe.clone())));
+
+        // The client claimed different values, including a frame rate the probe lacks
+        let mut media = video();
+        media.framerate_fps = Some(60.0);
+        let mut job = MediaProcessingJob::new(media.id, ProcessingOperation::AnalyzeMetadata);
+        analyzer.run(&mut job, &mut media).await.unwrap();
+
+        assert_eq!(job.status, JobStatus::Completed);
+        assert_eq!(media.duration_secs, probe.duration_secs);
+        assert_eq!(media.dimensions, probe.dimensions);
+        assert_eq!(media.codec, probe.codec);
+        assert_eq!(media.bitrate_kbps, Some(2400));
+        assert_eq!(media.framerate_fps, None);
+    }
+
+    #[test]
+    fn test_parse_ffprobe_output() {
+        let output = serde_json::json!({
+            "streams": [
+                {
+                    "codec_type": "video",
+                    "codec_name": "h264",
+                    "profile": "Main",
+                    "width": 1920,
+                    "height": 1080,
+                    "avg_frame_rate": "30000/1001"
+                },
+                { "codec_type": "audio", "codec_name": "opus" }
+            ],
+            "format": { "duration": "12.480000", "bit_rate": "4512345" }
+        });
+
+        let probe = parse_ffprobe(&output).unwrap();
+        assert_eq!(probe.duration_secs, Some(12.48));
+        assert_eq!(probe.dimensions, Some(MediaDimensions::new(1920, 1080)));
+        assert_eq!(probe.bitrate_kbps, Some(4512));
+        assert!((probe.framerate_fps.unwrap() - 29.97).abs() < 0.01);
+        let codec = probe.codec.unwrap();
+        assert_eq!(codec.video.as_deref(), Some("h264"));
+        assert_eq!(codec.audio.as_deref(), Some("opus"));
+        assert_eq!(codec.profile.as_deref(), Some("Main"));
+
+        assert!(parse_ffprobe(&serde_json::json!({})).is_err());
+    }
+}
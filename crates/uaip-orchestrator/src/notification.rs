@@ -0,0 +1,315 @@
+//! Notification Channels
+//!
+//! Pluggable delivery backends for the `send_notification` scenario action.
+//! Messages are templated from the trigger context using `{{field}}` placeholders.
+
+use async_trait::async_trait;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+use uaip_core::error::{Result, UaipError};
+
+/// Channel used when an action does not name one
+pub const DEFAULT_CHANNEL: &str = "log";
+
+/// A rendered notification ready for delivery
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Notification {
+    /// Channel-specific target (webhook URL, email address)
+    pub target: Option<String>,
+
+    /// Optional subject line
+    pub subject: Option<String>,
+
+    /// Rendered message body
+    pub message: String,
+
+    /// Trigger context the message was rendered from
+    pub context: HashMap<String, serde_json::Value>,
+}
+
+impl Notification {
+    /// Build a notification from `send_notification` action parameters
+    ///
+    /// The message comes from the `template` parameter, falling back to `message`.
+    pub fn from_parameters(
+        parameters: &HashMap<String, serde_json::Value>,
+        context: &HashMap<String, serde_json::Value>,
+    ) -> Self {
+        let param = |key: &str| {
+            parameters
+                .get(key)
+                .and_then(|v| v.as_str())
+                .map(String::from)
+        };
+        let template = param("template").or_else(|| param("message"));
+
+        Self {
+            target: param("target"),
+            subject: param("subject").map(|s| render_template(&s, context)),
+            message: render_template(template.as_deref().unwrap_or_default(), context),
+            context: context.clone(),
+        }
+    }
+}
+
+/// Replace `{{field}}` placeholders with values from the context
+///
+/// Unknown placeholders are left untouched.
+pub fn render_template(template: &str, context: &HashMap<String, serde_json::Value>) -> String {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        rendered.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            rest = &rest[start..];
+            break;
+        };
+
+        let key = after[..end].trim();
+        match context.get(key) {
+            Some(serde_json::Value::String(s)) => rendered.push_str(s),
+            Some(value) => rendered.push_str(&value.to_string()),
+            None => rendered.push_str(&rest[start..start + end + 4]),
+        }
+        rest = &after[end + 2..];
+    }
+
+    rendered.push_str(rest);
+    rendered
+}
+
+/// Delivery backend for scenario notifications
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    /// Channel name selected by the action `channel` parameter
+    fn channel(&self) -> &str;
+
+    /// Deliver a notification, returning channel-specific delivery details
+    async fn send(&self, notification: &Notification) -> Result<serde_json::Value>;
+}
+
+/// Writes notifications to the application log
+#[derive(Debug, Clone, Default)]
+pub struct LogNotifier;
+
+#[async_trait]
+impl Notifier for LogNotifier {
+    fn channel(&self) -> &str {
+        "log"
+    }
+
+    async fn send(&self, notification: &Notification) -> Result<serde_json::Value> {
+        tracing::info!(
+            target = notification.target.as_deref().unwrap_or("-"),
+            subject = notification.subject.as_deref().unwrap_or("-"),
+            "Scenario notification: {}",
+            notification.message
+        );
+        Ok(serde_json::json!({}))
+    }
+}
+
+/// POSTs notifications as JSON to the target URL
+#[derive(Debug, Clone)]
+pub struct WebhookNotifier {
+    client: Client,
+}
+
+impl WebhookNotifier {
+    /// Create a webhook notifier with the given request timeout
+    pub fn new(timeout: Duration) -> Result<Self> {
+        let client = Client::builder().timeout(timeout).build().map_err(|e| {
+            UaipError::ConnectionError(format!("Failed to create HTTP client: {}", e))
+        })?;
+        Ok(Self { client })
+    }
+}
+
+#[async_trait]
+impl Notifier for WebhookNotifier {
+    fn channel(&self) -> &str {
+        "webhook"
+    }
+
+    async fn send(&self, notification: &Notification) -> Result<serde_json::Value> {
+        let url = notification.target.as_deref().ok_or_else(|| {
+            UaipError::InvalidParameter("Webhook notification requires a target URL".to_string())
+        })?;
+
+        let response = self
+            .client
+            .post(url)
+            .json(&serde_json::json!({
+                "subject": notification.subject,
+                "message": notification.message,
+                "context": notification.context,
+            }))
+            .send()
+            .await
+            .map_err(|e| UaipError::ConnectionError(format!("Webhook request failed: {}", e)))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(UaipError::ConnectionError(format!(
+                "Webhook returned status {}",
+                status
+            )));
+        }
+
+        Ok(serde_json::json!({ "status": status.as_u16() }))
+    }
+}
+
+/// Sends notifications as plain-text email over SMTP
+#[derive(Clone)]
+pub struct EmailNotifier {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl EmailNotifier {
+    /// Create an email notifier using STARTTLS against the given relay
+    pub fn new(relay: &str, from: &str, credentials: Option<(String, String)>) -> Result<Self> {
+        let mut builder = AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(relay)
+            .map_err(|e| UaipError::InvalidConfiguration(format!("Invalid SMTP relay: {}", e)))?;
+        if let Some((username, password)) = credentials {
+            builder = builder.credentials(Credentials::new(username, password));
+        }
+        Self::from_transport(builder.build(), from)
+    }
+
+    /// Create an email notifier for an unencrypted local relay
+    pub fn unencrypted(host: &str, port: u16, from: &str) -> Result<Self> {
+        let transport = AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host)
+            .port(port)
+            .build();
+        Self::from_transport(transport, from)
+    }
+
+    fn from_transport(transport: AsyncSmtpTransport<Tokio1Executor>, from: &str) -> Result<Self> {
+        let from = from.parse().map_err(|e| {
+            UaipError::InvalidConfiguration(format!("Invalid sender address: {}", e))
+        })?;
+        Ok(Self { transport, from })
+    }
+}
+
+#[async_trait]
+impl Notifier for EmailNotifier {
+    fn channel(&self) -> &str {
+        "email"
+    }
+
+    async fn send(&self, notification: &Notification) -> Result<serde_json::Value> {
+        let to: Mailbox = notification
+            .target
+            .as_deref()
+            .ok_or_else(|| {
+                UaipError::InvalidParameter("Email notification requires a target".to_string())
+            })?
+            .parse()
+            .map_err(|e| UaipError::InvalidParameter(format!("Invalid email address: {}", e)))?;
+
+        let email = Message::builder()
+            .from(self.from.clone())
+            .to(to)
+            .subject(
+                notification
+                    .subject
+                    .clone()
+                    .unwrap_or_else(|| "UAIP notification".to_string()),
+            )
+            .body(notification.message.clone())
+            .map_err(|e| UaipError::InvalidMessage(format!("Failed to build email: {}", e)))?;
+
+        let response = self
+            .transport
+            .send(email)
+            .await
+            .map_err(|e| UaipError::ConnectionError(format!("SMTP delivery failed: {}", e)))?;
+
+        Ok(serde_json::json!({ "smtp_code": response.code().to_string() }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn context() -> HashMap<String, serde_json::Value> {
+        let mut context = HashMap::new();
+        context.insert("device".to_string(), serde_json::json!("sensor-1"));
+        context.insert("temperature".to_string(), serde_json::json!(42.5));
+        context
+    }
+
+    #[test]
+    fn test_render_template() {
+        let rendered = render_template(
+            "{{device}} reported {{ temperature }}C ({{missing}})",
+            &context(),
+        );
+        assert_eq!(rendered, "sensor-1 reported 42.5C ({{missing}})");
+        assert_eq!(
+            render_template("unclosed {{device", &context()),
+            "unclosed {{device"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_webhook_notifier_posts_message() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/alerts"))
+            .and(body_partial_json(
+                serde_json::json!({ "message": "sensor-1 is at 42.5" }),
+            ))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let mut parameters = HashMap::new();
+        parameters.insert(
+            "target".to_string(),
+            serde_json::json!(format!("{}/alerts", server.uri())),
+        );
+        parameters.insert(
+            "template".to_string(),
+            serde_json::json!("{{device}} is at {{temperature}}"),
+        );
+        let notification = Notification::from_parameters(&parameters, &context());
+
+        let notifier = WebhookNotifier::new(Duration::from_secs(5)).unwrap();
+        let result = notifier.send(&notification).await.unwrap();
+        assert_eq!(result["status"], 200);
+    }
+
+    #[tokio::test]
+    async fn test_webhook_notifier_reports_failure_status() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(503))
+            .mount(&server)
+            .await;
+
+        let notification = Notification {
+            target: Some(server.uri()),
+            subject: None,
+            message: "down".to_string(),
+            context: HashMap::new(),
+        };
+
+        let notifier = WebhookNotifier::new(Duration::from_secs(5)).unwrap();
+        assert!(notifier.send(&notification).await.is_err());
+    }
+}
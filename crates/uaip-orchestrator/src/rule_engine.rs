@@ -2,10 +2,15 @@
 //!
 //! Provides a JSON-based rule engine for automating device behaviors based on conditions.
 //! Rules can trigger actions when specified conditions are met.
+//!
+//! Evaluation takes `&self`: cooldown timestamps and last-seen values use interior
+//! mutability, so a shared engine can be evaluated concurrently under a read lock.
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
 use uaip_core::{error::Result, error::UaipError};
 
 /// A rule that can be evaluated
@@ -42,11 +47,22 @@ pub struct Rule {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub last_executed: Option<DateTime<Utc>>,
 
+    /// Tenant that owns the rule; unscoped rules see every tenant's telemetry
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant_id: Option<String>,
+
     /// Metadata
     #[serde(default)]
     pub metadata: HashMap<String, serde_json::Value>,
 }
 
+impl Rule {
+    /// Seconds left before the rule may fire again, if it is cooling down at `now`
+    pub fn cooldown_remaining_secs(&self, now: DateTime<Utc>) -> Option<u64> {
+        RuleEngine::cooldown_remaining(self.cooldown_seconds, self.last_executed, now)
+    }
+}
+
 /// How to combine multiple conditions
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
@@ -66,11 +82,68 @@ pub struct Condition {
     /// Operator to apply
     pub operator: Operator,
 
-    /// Value to compare against
+    /// Value to compare against, or `{"field_ref": "<field>"}` to compare against the
+    /// current value of another field
     pub value: serde_json::Value,
 
     /// Device ID filter (optional)
     pub device_id: Option<String>,
+
+    /// Device group filter (optional); matches if any member device satisfies the condition
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group_id: Option<String>,
+
+    /// Aggregate `field` across devices (optional); `operator` and `value` then compare
+    /// the aggregate. Devices come from `group_id`, or all device states if unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aggregate: Option<Aggregate>,
+}
+
+impl Condition {
+    /// Field named by a `{"field_ref": "<field>"}` value, if the value references one
+    pub fn field_ref(&self) -> Option<&str> {
+        match &self.value {
+            serde_json::Value::Object(map) if map.len() == 1 => map.get("field_ref")?.as_str(),
+            _ => None,
+        }
+    }
+}
+
+/// Aggregation of one field across a set of devices
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Aggregate {
+    /// How matched values are combined
+    pub function: AggregateFunction,
+
+    /// Per-device test on the field; devices without the field never match
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filter: Option<DeviceFilter>,
+}
+
+/// Per-device test used by an aggregate
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DeviceFilter {
+    /// Operator to apply to each device's value
+    pub operator: Operator,
+
+    /// Value to compare against
+    pub value: serde_json::Value,
+}
+
+/// Aggregate functions
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AggregateFunction {
+    /// Number of devices passing the filter
+    Count,
+    /// Whether any device passes the filter
+    Any,
+    /// Whether every device reporting the field passes the filter (false if none do)
+    All,
+    /// Sum of numeric values of devices passing the filter
+    Sum,
+    /// Mean of numeric values of devices passing the filter (no value if none do)
+    Avg,
 }
 
 /// Comparison operators
@@ -99,6 +172,9 @@ pub enum Operator {
     In,
     /// Not in list
     NotIn,
+    /// Value changed since the previous evaluation (edge trigger).
+    /// With a non-null `value`, only matches a change *to* that value.
+    Changed,
 }
 
 /// An action to execute
@@ -110,10 +186,51 @@ pub struct Action {
     /// Target device ID (for device actions)
     pub device_id: Option<String>,
 
+    /// Target device group (optional); the action fans out to every member
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group_id: Option<String>,
+
+    /// Capability the targeted devices must declare (optional). Without `device_id` or
+    /// `group_id` the action targets every device declaring it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capability: Option<String>,
+
     /// Action parameters
     pub parameters: HashMap<String, serde_json::Value>,
 }
 
+impl Action {
+    /// Devices the action targets, resolved against the context's groups and capabilities
+    ///
+    /// Sorted and without duplicates; empty for actions without a device target and for
+    /// selectors nothing matches.
+    pub fn target_devices(&self, context: &EvaluationContext) -> Vec<String> {
+        let mut devices: Vec<String> = if self.device_id.is_none() && self.group_id.is_none() {
+            match &self.capability {
+                Some(_) => context.capabilities.keys().cloned().collect(),
+                None => Vec::new(),
+            }
+        } else {
+            self.device_id
+                .iter()
+                .chain(
+                    self.group_id
+                        .iter()
+                        .flat_map(|group_id| context.group_members(group_id)),
+                )
+                .cloned()
+                .collect()
+        };
+
+        if let Some(capability) = &self.capability {
+            devices.retain(|device_id| context.has_capability(device_id, capability));
+        }
+        devices.sort();
+        devices.dedup();
+        devices
+    }
+}
+
 /// Types of actions
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "snake_case")]
@@ -132,6 +249,19 @@ pub enum ActionType {
     ExecuteRule,
 }
 
+/// A would-be rule trigger reported by a backtest
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TriggerEvent {
+    /// Rule that would have fired
+    pub rule_id: String,
+
+    /// Timestamp of the telemetry sample that triggered the rule
+    pub timestamp: DateTime<Utc>,
+
+    /// Index of the triggering sample in the replayed series
+    pub sample_index: usize,
+}
+
 /// Rule evaluation context
 #[derive(Debug, Clone)]
 pub struct EvaluationContext {
@@ -143,6 +273,15 @@ pub struct EvaluationContext {
 
     /// Current timestamp
     pub timestamp: DateTime<Utc>,
+
+    /// Resolved device group membership
+    pub groups: HashMap<String, Vec<String>>,
+
+    /// Tenant the telemetry belongs to
+    pub tenant_id: Option<String>,
+
+    /// Declared capability names by device ID, for capability-targeted actions
+    pub capabilities: HashMap<String, Vec<String>>,
 }
 
 impl EvaluationContext {
@@ -152,9 +291,18 @@ impl EvaluationContext {
             telemetry: HashMap::new(),
             device_states: HashMap::new(),
             timestamp: Utc::now(),
+            groups: HashMap::new(),
+            tenant_id: None,
+            capabilities: HashMap::new(),
         }
     }
 
+    /// Scope the context to a tenant
+    pub fn with_tenant(mut self, tenant_id: String) -> Self {
+        self.tenant_id = Some(tenant_id);
+        self
+    }
+
     /// Add telemetry data
     pub fn with_telemetry(mut self, key: String, value: serde_json::Value) -> Self {
         self.telemetry.insert(key, value);
@@ -171,6 +319,30 @@ impl EvaluationContext {
         self
     }
 
+    /// Add a device group with its resolved members
+    pub fn with_group(mut self, group_id: String, members: Vec<String>) -> Self {
+        self.groups.insert(group_id, members);
+        self
+    }
+
+    /// Members of a device group (empty if the group is unknown)
+    pub fn group_members(&self, group_id: &str) -> &[String] {
+        self.groups.get(group_id).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Add a device with its declared capability names
+    pub fn with_capabilities(mut self, device_id: String, capabilities: Vec<String>) -> Self {
+        self.capabilities.insert(device_id, capabilities);
+        self
+    }
+
+    /// Whether a device declares a capability (false for unknown devices)
+    pub fn has_capability(&self, device_id: &str, capability: &str) -> bool {
+        self.capabilities
+            .get(device_id)
+            .is_some_and(|capabilities| capabilities.iter().any(|c| c == capability))
+    }
+
     /// Get a value from the context by field path
     pub fn get_value(&self, field: &str) -> Option<&serde_json::Value> {
         // Support dot notation: "device.temperature" or just "temperature"
@@ -198,48 +370,134 @@ impl Default for EvaluationContext {
     }
 }
 
+/// Last-seen values keyed by field (or `device_id/field` for device state)
+type ValueHistory = HashMap<String, serde_json::Value>;
+
+/// Stored in [`LoadedRule::last_executed`] for rules that have not fired
+const NEVER_EXECUTED: i64 = i64::MIN;
+
+/// A loaded rule with its cooldown state
+#[derive(Debug)]
+struct LoadedRule {
+    rule: Rule,
+
+    /// Nanoseconds since the epoch of the last firing, or [`NEVER_EXECUTED`]
+    ///
+    /// Kept outside `rule` so evaluation can start cooldowns through a shared reference.
+    last_executed: AtomicI64,
+}
+
+impl LoadedRule {
+    fn new(rule: Rule) -> Self {
+        // Timestamps outside the nanosecond range (years 1677-2262) count as never
+        let last_executed = rule
+            .last_executed
+            .and_then(|t| t.timestamp_nanos_opt())
+            .unwrap_or(NEVER_EXECUTED);
+        Self {
+            rule,
+            last_executed: AtomicI64::new(last_executed),
+        }
+    }
+
+    fn decode(nanos: i64) -> Option<DateTime<Utc>> {
+        (nanos != NEVER_EXECUTED).then(|| DateTime::from_timestamp_nanos(nanos))
+    }
+
+    fn last_executed(&self) -> Option<DateTime<Utc>> {
+        Self::decode(self.last_executed.load(Ordering::Acquire))
+    }
+
+    /// The rule with its current `last_executed`
+    fn snapshot(&self) -> Rule {
+        Rule {
+            last_executed: self.last_executed(),
+            ..self.rule.clone()
+        }
+    }
+
+    /// Record a firing at `now`, unless a concurrent evaluation started the cooldown first
+    fn try_fire(&self, now: DateTime<Utc>) -> bool {
+        let now_nanos = now.timestamp_nanos_opt().unwrap_or(i64::MAX);
+        let mut current = self.last_executed.load(Ordering::Acquire);
+        loop {
+            if RuleEngine::in_cooldown(self.rule.cooldown_seconds, Self::decode(current), now) {
+                return false;
+            }
+            // Never move the timestamp backwards past a later concurrent firing
+            let next = current.max(now_nanos);
+            match self.last_executed.compare_exchange_weak(
+                current,
+                next,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => return true,
+                Err(actual) => current = actual,
+            }
+        }
+    }
+}
+
 /// Rule engine for evaluating and executing rules
+#[derive(Debug)]
 pub struct RuleEngine {
-    /// Loaded rules
-    rules: Vec<Rule>,
+    /// Loaded rules, highest priority first
+    rules: Vec<LoadedRule>,
+
+    /// Values seen on the previous evaluation, used by edge-triggered conditions
+    last_seen: RwLock<ValueHistory>,
 }
 
 impl RuleEngine {
     /// Create a new rule engine
     pub fn new() -> Self {
-        Self { rules: Vec::new() }
+        Self {
+            rules: Vec::new(),
+            last_seen: RwLock::new(HashMap::new()),
+        }
     }
 
     /// Add a rule to the engine
     pub fn add_rule(&mut self, rule: Rule) {
-        self.rules.push(rule);
+        self.rules.push(LoadedRule::new(rule));
         // Sort by priority (highest first)
-        self.rules.sort_by(|a, b| b.priority.cmp(&a.priority));
+        self.rules
+            .sort_by_key(|r| std::cmp::Reverse(r.rule.priority));
     }
 
     /// Remove a rule by ID
     pub fn remove_rule(&mut self, rule_id: &str) -> bool {
         let initial_len = self.rules.len();
-        self.rules.retain(|r| r.id != rule_id);
+        self.rules.retain(|r| r.rule.id != rule_id);
         self.rules.len() < initial_len
     }
 
-    /// Get a rule by ID
-    pub fn get_rule(&self, rule_id: &str) -> Option<&Rule> {
-        self.rules.iter().find(|r| r.id == rule_id)
+    /// Get a rule by ID, with its current `last_executed`
+    pub fn get_rule(&self, rule_id: &str) -> Option<Rule> {
+        self.rules
+            .iter()
+            .find(|r| r.rule.id == rule_id)
+            .map(LoadedRule::snapshot)
     }
 
-    /// Get all rules
-    pub fn get_all_rules(&self) -> &[Rule] {
-        &self.rules
+    /// Get all rules, highest priority first, with their current `last_executed`
+    pub fn get_all_rules(&self) -> Vec<Rule> {
+        self.rules.iter().map(LoadedRule::snapshot).collect()
+    }
+
+    /// Number of loaded rules
+    pub fn rule_count(&self) -> usize {
+        self.rules.len()
     }
 
     /// Update a rule
     pub fn update_rule(&mut self, rule: Rule) -> Result<()> {
-        if let Some(pos) = self.rules.iter().position(|r| r.id == rule.id) {
-            self.rules[pos] = rule;
+        if let Some(pos) = self.rules.iter().position(|r| r.rule.id == rule.id) {
+            self.rules[pos] = LoadedRule::new(rule);
             // Re-sort by priority
-            self.rules.sort_by(|a, b| b.priority.cmp(&a.priority));
+            self.rules
+                .sort_by_key(|r| std::cmp::Reverse(r.rule.priority));
             Ok(())
         } else {
             Err(UaipError::NotFound(format!("Rule not found: {}", rule.id)))
@@ -247,37 +505,167 @@ impl RuleEngine {
     }
 
     /// Evaluate all enabled rules and return triggered rule IDs
-    pub fn evaluate(&mut self, context: &EvaluationContext) -> Vec<String> {
+    ///
+    /// Safe to call concurrently: each cooldown is started atomically, so a rule with a
+    /// cooldown fires for only one of several simultaneous evaluations.
+    pub fn evaluate(&self, context: &EvaluationContext) -> Vec<String> {
+        self.evaluate_at(context, Utc::now())
+    }
+
+    /// Evaluate all enabled rules with cooldowns measured at `now`
+    ///
+    /// Lets recorded telemetry be replayed on its own timeline.
+    pub fn evaluate_at(&self, context: &EvaluationContext, now: DateTime<Utc>) -> Vec<String> {
         let mut triggered = Vec::new();
-        let now = Utc::now();
 
-        for rule in &mut self.rules {
-            if !rule.enabled {
-                continue;
-            }
+        {
+            let history = self.history();
+            for loaded in &self.rules {
+                let rule = &loaded.rule;
+                if !rule.enabled || !Self::in_scope(rule, context) {
+                    continue;
+                }
 
-            // Check cooldown
-            if let Some(cooldown) = rule.cooldown_seconds {
-                if let Some(last_executed) = rule.last_executed {
-                    let elapsed = now.signed_duration_since(last_executed);
-                    if elapsed.num_seconds() < cooldown as i64 {
-                        continue; // Still in cooldown
-                    }
+                // Check cooldown
+                if Self::in_cooldown(rule.cooldown_seconds, loaded.last_executed(), now) {
+                    continue;
                 }
-            }
 
-            // Evaluate conditions
-            if Self::evaluate_conditions(rule, context) {
-                triggered.push(rule.id.clone());
-                rule.last_executed = Some(now);
+                // Evaluate conditions
+                if Self::evaluate_conditions(rule, context, &history) && loaded.try_fire(now) {
+                    triggered.push(rule.id.clone());
+                }
             }
         }
 
+        Self::record_values(&mut self.history_mut(), context);
+
         triggered
     }
 
+    /// Rules that would trigger on a context, ignoring cooldowns
+    ///
+    /// Unlike [`evaluate`](Self::evaluate) this changes nothing: `last_executed` and the
+    /// last-seen values used by edge-triggered conditions are left as they are.
+    pub fn preview(&self, context: &EvaluationContext) -> Vec<String> {
+        let history = self.history();
+        self.rules
+            .iter()
+            .map(|loaded| &loaded.rule)
+            .filter(|rule| rule.enabled && Self::in_scope(rule, context))
+            .filter(|rule| Self::evaluate_conditions(rule, context, &history))
+            .map(|rule| rule.id.clone())
+            .collect()
+    }
+
+    /// Get the value seen for a field on the previous evaluation
+    pub fn last_seen_value(
+        &self,
+        device_id: Option<&str>,
+        field: &str,
+    ) -> Option<serde_json::Value> {
+        self.history()
+            .get(&Self::history_key(device_id, field))
+            .cloned()
+    }
+
+    /// Forget all last-seen values (edge-triggered conditions re-arm)
+    pub fn clear_history(&self) {
+        self.history_mut().clear();
+    }
+
+    fn history(&self) -> RwLockReadGuard<'_, ValueHistory> {
+        self.last_seen.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn history_mut(&self) -> RwLockWriteGuard<'_, ValueHistory> {
+        self.last_seen.write().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Record the values in a context as the new last-seen values
+    fn record_values(history: &mut ValueHistory, context: &EvaluationContext) {
+        for (field, value) in &context.telemetry {
+            history.insert(Self::history_key(None, field), value.clone());
+        }
+        for (device_id, state) in &context.device_states {
+            for (field, value) in state {
+                history.insert(Self::history_key(Some(device_id), field), value.clone());
+            }
+        }
+    }
+
+    /// Key under which a field's last-seen value is stored
+    fn history_key(device_id: Option<&str>, field: &str) -> String {
+        match device_id {
+            Some(device_id) => format!("{}/{}", device_id, field),
+            None => field.to_string(),
+        }
+    }
+
+    /// Replay a telemetry series through a rule and report each would-be trigger
+    ///
+    /// Samples are evaluated in timestamp order and cooldown is measured against the
+    /// sample timestamps rather than the wall clock. The rule's own `last_executed`
+    /// and `enabled` flag are ignored so undeployed rules can be tested.
+    pub fn backtest(rule: &Rule, telemetry_series: &[EvaluationContext]) -> Vec<TriggerEvent> {
+        let mut order: Vec<usize> = (0..telemetry_series.len()).collect();
+        order.sort_by_key(|&i| telemetry_series[i].timestamp);
+
+        let mut events = Vec::new();
+        let mut last_fired: Option<DateTime<Utc>> = None;
+        let mut history = ValueHistory::new();
+
+        for index in order {
+            let sample = &telemetry_series[index];
+
+            if !Self::in_cooldown(rule.cooldown_seconds, last_fired, sample.timestamp)
+                && Self::evaluate_conditions(rule, sample, &history)
+            {
+                events.push(TriggerEvent {
+                    rule_id: rule.id.clone(),
+                    timestamp: sample.timestamp,
+                    sample_index: index,
+                });
+                last_fired = Some(sample.timestamp);
+            }
+
+            Self::record_values(&mut history, sample);
+        }
+
+        events
+    }
+
+    /// Check whether a rule may see the context's telemetry
+    fn in_scope(rule: &Rule, context: &EvaluationContext) -> bool {
+        rule.tenant_id.is_none() || rule.tenant_id == context.tenant_id
+    }
+
+    /// Check whether a rule is still cooling down at `now`
+    fn in_cooldown(
+        cooldown_seconds: Option<u64>,
+        last_executed: Option<DateTime<Utc>>,
+        now: DateTime<Utc>,
+    ) -> bool {
+        Self::cooldown_remaining(cooldown_seconds, last_executed, now).is_some()
+    }
+
+    /// Whole seconds of cooldown left at `now`, `None` once the rule may fire
+    fn cooldown_remaining(
+        cooldown_seconds: Option<u64>,
+        last_executed: Option<DateTime<Utc>>,
+        now: DateTime<Utc>,
+    ) -> Option<u64> {
+        let elapsed = now.signed_duration_since(last_executed?).num_seconds();
+        let remaining = cooldown_seconds? as i64 - elapsed;
+        (remaining > 0).then_some(remaining as u64)
+    }
+
     /// Evaluate conditions for a rule
-    fn evaluate_conditions(rule: &Rule, context: &EvaluationContext) -> bool {
+    fn evaluate_conditions(
+        rule: &Rule,
+        context: &EvaluationContext,
+        history: &ValueHistory,
+    ) -> bool {
         if rule.conditions.is_empty() {
             return true; // No conditions means always true
         }
@@ -286,18 +674,71 @@ impl RuleEngine {
             ConditionMode::All => rule
                 .conditions
                 .iter()
-                .all(|c| Self::evaluate_condition(c, context)),
+                .all(|c| Self::evaluate_condition_with_history(c, context, history)),
             ConditionMode::Any => rule
                 .conditions
                 .iter()
-                .any(|c| Self::evaluate_condition(c, context)),
+                .any(|c| Self::evaluate_condition_with_history(c, context, history)),
         }
     }
 
-    /// Evaluate a single condition
+    /// Evaluate a single condition without value history
+    #[cfg(test)]
     fn evaluate_condition(condition: &Condition, context: &EvaluationContext) -> bool {
+        Self::evaluate_condition_with_history(condition, context, &ValueHistory::new())
+    }
+
+    /// Evaluate a single condition
+    fn evaluate_condition_with_history(
+        condition: &Condition,
+        context: &EvaluationContext,
+        history: &ValueHistory,
+    ) -> bool {
+        if let Some(aggregate) = &condition.aggregate {
+            let aggregated = Self::aggregate_value(condition, aggregate, context);
+            return match (aggregated, Self::expected_value(condition, None, context)) {
+                // Aggregates keep no history, so they never report a change
+                (Some(actual), Some(expected)) => {
+                    Self::compare(&condition.operator, &actual, expected)
+                }
+                _ => false,
+            };
+        }
+
+        if let Some(group_id) = &condition.group_id {
+            return context.group_members(group_id).iter().any(|device_id| {
+                Self::evaluate_condition_for(condition, Some(device_id), context, history)
+            });
+        }
+
+        // Each device reporting the field is compared with its own previous value, so
+        // devices sharing a field name do not look like changes of one another
+        if condition.operator == Operator::Changed && condition.device_id.is_none() {
+            let mut reporters = context
+                .device_states
+                .iter()
+                .filter(|(_, state)| state.contains_key(&condition.field))
+                .map(|(device_id, _)| device_id.as_str())
+                .peekable();
+            if reporters.peek().is_some() {
+                return reporters.any(|device_id| {
+                    Self::evaluate_condition_for(condition, Some(device_id), context, history)
+                });
+            }
+        }
+
+        Self::evaluate_condition_for(condition, condition.device_id.as_deref(), context, history)
+    }
+
+    /// Evaluate a condition against one device, or against telemetry if `device_id` is `None`
+    fn evaluate_condition_for(
+        condition: &Condition,
+        device_id: Option<&str>,
+        context: &EvaluationContext,
+        history: &ValueHistory,
+    ) -> bool {
         // Get the value to compare
-        let actual_value = if let Some(device_id) = &condition.device_id {
+        let actual_value = if let Some(device_id) = device_id {
             context.get_device_value(device_id, &condition.field)
         } else {
             context.get_value(&condition.field)
@@ -307,28 +748,106 @@ impl RuleEngine {
             Some(v) => v,
             None => return false, // Field not found
         };
+        let Some(expected) = Self::expected_value(condition, device_id, context) else {
+            return false; // Referenced field not found
+        };
 
-        // Perform comparison based on operator
-        match condition.operator {
-            Operator::Equals => actual_value == &condition.value,
-            Operator::NotEquals => actual_value != &condition.value,
-            Operator::GreaterThan => {
-                Self::compare_numbers(actual_value, &condition.value, |a, b| a > b)
-            }
-            Operator::GreaterThanOrEqual => {
-                Self::compare_numbers(actual_value, &condition.value, |a, b| a >= b)
-            }
-            Operator::LessThan => {
-                Self::compare_numbers(actual_value, &condition.value, |a, b| a < b)
-            }
-            Operator::LessThanOrEqual => {
-                Self::compare_numbers(actual_value, &condition.value, |a, b| a <= b)
-            }
-            Operator::Contains => Self::contains(actual_value, &condition.value),
-            Operator::NotContains => !Self::contains(actual_value, &condition.value),
-            Operator::Matches => Self::matches_regex(actual_value, &condition.value),
-            Operator::In => Self::in_list(actual_value, &condition.value),
-            Operator::NotIn => !Self::in_list(actual_value, &condition.value),
+        if condition.operator == Operator::Changed {
+            let key = Self::history_key(device_id, &condition.field);
+            return match history.get(&key) {
+                // No previous observation: nothing to compare against
+                None => false,
+                Some(previous) if previous == actual_value => false,
+                Some(_) => expected.is_null() || actual_value == expected,
+            };
+        }
+
+        Self::compare(&condition.operator, actual_value, expected)
+    }
+
+    /// The value a condition compares against: its constant `value`, or the current value
+    /// of the field it references, looked up on the device before the shared telemetry
+    ///
+    /// Returns `None` when the referenced field is absent.
+    fn expected_value<'a>(
+        condition: &'a Condition,
+        device_id: Option<&str>,
+        context: &'a EvaluationContext,
+    ) -> Option<&'a serde_json::Value> {
+        let Some(field) = condition.field_ref() else {
+            return Some(&condition.value);
+        };
+        device_id
+            .and_then(|device_id| context.get_device_value(device_id, field))
+            .or_else(|| context.get_value(field))
+    }
+
+    /// Compute an aggregate over the devices a condition selects
+    ///
+    /// Returns `None` when the aggregate has no value (an average of no devices).
+    fn aggregate_value(
+        condition: &Condition,
+        aggregate: &Aggregate,
+        context: &EvaluationContext,
+    ) -> Option<serde_json::Value> {
+        let values: Vec<&serde_json::Value> = match &condition.group_id {
+            Some(group_id) => context
+                .group_members(group_id)
+                .iter()
+                .filter_map(|device_id| context.get_device_value(device_id, &condition.field))
+                .collect(),
+            None => context
+                .device_states
+                .values()
+                .filter_map(|state| state.get(&condition.field))
+                .collect(),
+        };
+        let passes = |value: &serde_json::Value| {
+            aggregate
+                .filter
+                .as_ref()
+                .is_none_or(|f| Self::compare(&f.operator, value, &f.value))
+        };
+        let numbers: Vec<f64> = values
+            .iter()
+            .filter(|v| passes(v))
+            .filter_map(|v| v.as_f64())
+            .collect();
+
+        match aggregate.function {
+            AggregateFunction::Count => Some(serde_json::json!(values
+                .iter()
+                .filter(|v| passes(v))
+                .count())),
+            AggregateFunction::Any => Some(serde_json::json!(values.iter().any(|v| passes(v)))),
+            AggregateFunction::All => Some(serde_json::json!(
+                !values.is_empty() && values.iter().all(|v| passes(v))
+            )),
+            AggregateFunction::Sum => Some(serde_json::json!(numbers.iter().sum::<f64>())),
+            AggregateFunction::Avg => (!numbers.is_empty())
+                .then(|| serde_json::json!(numbers.iter().sum::<f64>() / numbers.len() as f64)),
+        }
+    }
+
+    /// Apply an operator to a value; `Changed` needs history and never matches here
+    fn compare(
+        operator: &Operator,
+        actual: &serde_json::Value,
+        expected: &serde_json::Value,
+    ) -> bool {
+        match operator {
+            Operator::Equals => actual == expected,
+            Operator::NotEquals => actual != expected,
+            Operator::GreaterThan => Self::compare_numbers(actual, expected, |a, b| a > b),
+            Operator::GreaterThanOrEqual => Self::compare_numbers(actual, expected, |a, b| a >= b),
+            Operator::LessThan => Self::compare_numbers(actual, expected, |a, b| a < b),
+            Operator::LessThanOrEqual => Self::compare_numbers(actual, expected, |a, b| a <= b),
+            Operator::Contains => Self::contains(actual, expected),
+            Operator::NotContains => !Self::contains(actual, expected),
+            Operator::Matches => Self::matches_regex(actual, expected),
+            Operator::In => Self::in_list(actual, expected),
+            Operator::NotIn => !Self::in_list(actual, expected),
+            Operator::Changed => false,
         }
     }
 
@@ -396,6 +915,7 @@ mod tests {
             priority: 10,
             cooldown_seconds: Some(60),
             last_executed: None,
+            tenant_id: None,
             metadata: HashMap::new(),
         };
 
@@ -418,6 +938,7 @@ mod tests {
             priority: 5,
             cooldown_seconds: None,
             last_executed: None,
+            tenant_id: None,
             metadata: HashMap::new(),
         };
 
@@ -435,6 +956,8 @@ mod tests {
             operator: Operator::Equals,
             value: serde_json::json!(25.0),
             device_id: None,
+            group_id: None,
+            aggregate: None,
         };
 
         let context = EvaluationContext::new()
@@ -455,6 +978,8 @@ mod tests {
             operator: Operator::GreaterThan,
             value: serde_json::json!(25.0),
             device_id: None,
+            group_id: None,
+            aggregate: None,
         };
 
         let context = EvaluationContext::new()
@@ -468,6 +993,262 @@ mod tests {
         assert!(!RuleEngine::evaluate_condition(&condition, &context2));
     }
 
+    #[test]
+    fn test_strings_never_match_ordering_operators() {
+        let context =
+            EvaluationContext::new().with_telemetry("status".to_string(), serde_json::json!("b"));
+
+        for operator in [
+            Operator::GreaterThan,
+            Operator::GreaterThanOrEqual,
+            Operator::LessThan,
+            Operator::LessThanOrEqual,
+        ] {
+            for value in ["a", "b", "c"] {
+                let condition = Condition {
+                    field: "status".to_string(),
+                    operator: operator.clone(),
+                    value: serde_json::json!(value),
+                    device_id: None,
+                    group_id: None,
+                    aggregate: None,
+                };
+                assert!(!RuleEngine::evaluate_condition(&condition, &context));
+            }
+        }
+    }
+
+    fn cross_field(device_id: Option<&str>) -> Condition {
+        Condition {
+            field: "indoor_temp".to_string(),
+            operator: Operator::GreaterThan,
+            value: serde_json::json!({ "field_ref": "outdoor_temp" }),
+            device_id: device_id.map(str::to_string),
+            group_id: None,
+            aggregate: None,
+        }
+    }
+
+    #[test]
+    fn test_cross_field_comparison() {
+        let condition = cross_field(None);
+        assert_eq!(condition.field_ref(), Some("outdoor_temp"));
+
+        let warmer = EvaluationContext::new()
+            .with_telemetry("indoor_temp".to_string(), serde_json::json!(21.5))
+            .with_telemetry("outdoor_temp".to_string(), serde_json::json!(4));
+        assert!(RuleEngine::evaluate_condition(&condition, &warmer));
+
+        let colder = EvaluationContext::new()
+            .with_telemetry("indoor_temp".to_string(), serde_json::json!(18.0))
+            .with_telemetry("outdoor_temp".to_string(), serde_json::json!(25.0));
+        assert!(!RuleEngine::evaluate_condition(&condition, &colder));
+
+        // Ordering stays numeric-only, so string fields never match
+        let mut later = cross_field(None);
+        later.field = "updated".to_string();
+        later.value = serde_json::json!({ "field_ref": "checked" });
+        let context = EvaluationContext::new()
+            .with_telemetry("updated".to_string(), serde_json::json!("2024-05-02"))
+            .with_telemetry("checked".to_string(), serde_json::json!("2024-05-01"));
+        assert!(!RuleEngine::evaluate_condition(&later, &context));
+
+        // A device's own field is preferred over shared telemetry of the same name
+        let mut state = HashMap::new();
+        state.insert("indoor_temp".to_string(), serde_json::json!(19.0));
+        state.insert("outdoor_temp".to_string(), serde_json::json!(22.0));
+        let device = warmer.with_device_state("thermostat-1".to_string(), state);
+        assert!(!RuleEngine::evaluate_condition(
+            &cross_field(Some("thermostat-1")),
+            &device
+        ));
+    }
+
+    #[test]
+    fn test_cross_field_missing_reference_is_false() {
+        let context = EvaluationContext::new()
+            .with_telemetry("indoor_temp".to_string(), serde_json::json!(21.5));
+
+        for operator in [Operator::GreaterThan, Operator::NotEquals, Operator::NotIn] {
+            let condition = Condition {
+                operator,
+                ..cross_field(None)
+            };
+            assert!(!RuleEngine::evaluate_condition(&condition, &context));
+        }
+
+        // An object with other keys is still a constant
+        let mut constant = cross_field(None);
+        constant.operator = Operator::NotEquals;
+        constant.value = serde_json::json!({ "field_ref": "outdoor_temp", "unit": "C" });
+        assert_eq!(constant.field_ref(), None);
+        assert!(RuleEngine::evaluate_condition(&constant, &context));
+    }
+
+    #[test]
+    fn test_condition_on_device_group() {
+        let condition = Condition {
+            field: "temperature".to_string(),
+            operator: Operator::GreaterThan,
+            value: serde_json::json!(25.0),
+            device_id: None,
+            group_id: Some("kitchen".to_string()),
+            aggregate: None,
+        };
+
+        let mut hot = HashMap::new();
+        hot.insert("temperature".to_string(), serde_json::json!(30.0));
+        let context = EvaluationContext::new()
+            .with_device_state("sensor-2".to_string(), hot)
+            .with_group(
+                "kitchen".to_string(),
+                vec!["sensor-1".to_string(), "sensor-2".to_string()],
+            );
+        assert!(RuleEngine::evaluate_condition(&condition, &context));
+
+        // A matching device outside the group does not count
+        let mut outside = context.clone();
+        outside
+            .groups
+            .insert("kitchen".to_string(), vec!["sensor-1".to_string()]);
+        assert!(!RuleEngine::evaluate_condition(&condition, &outside));
+
+        // Unknown groups have no members
+        let mut unknown = context;
+        unknown.groups.clear();
+        assert!(!RuleEngine::evaluate_condition(&condition, &unknown));
+    }
+
+    fn motion_sensors(active: &[bool]) -> EvaluationContext {
+        let mut context = EvaluationContext::new();
+        for (i, motion) in active.iter().enumerate() {
+            let mut state = HashMap::new();
+            state.insert("motion".to_string(), serde_json::json!(motion));
+            state.insert("power".to_string(), serde_json::json!(i as f64 * 10.0));
+            context = context.with_device_state(format!("motion-{}", i), state);
+        }
+        context
+    }
+
+    fn aggregate_condition(
+        field: &str,
+        function: AggregateFunction,
+        filter: Option<DeviceFilter>,
+        operator: Operator,
+        value: serde_json::Value,
+    ) -> Condition {
+        Condition {
+            field: field.to_string(),
+            operator,
+            value,
+            device_id: None,
+            group_id: None,
+            aggregate: Some(Aggregate { function, filter }),
+        }
+    }
+
+    fn motion_active() -> Option<DeviceFilter> {
+        Some(DeviceFilter {
+            operator: Operator::Equals,
+            value: serde_json::json!(true),
+        })
+    }
+
+    #[test]
+    fn test_aggregate_count_threshold() {
+        let more_than_three = aggregate_condition(
+            "motion",
+            AggregateFunction::Count,
+            motion_active(),
+            Operator::GreaterThan,
+            serde_json::json!(3),
+        );
+
+        let three = motion_sensors(&[true, true, false, true, false]);
+        assert!(!RuleEngine::evaluate_condition(&more_than_three, &three));
+
+        let four = motion_sensors(&[true, true, true, true, false]);
+        assert!(RuleEngine::evaluate_condition(&more_than_three, &four));
+
+        // Restricting to a group only counts its members
+        let mut grouped = more_than_three.clone();
+        grouped.group_id = Some("hall".to_string());
+        let four_in_hall = four.clone().with_group(
+            "hall".to_string(),
+            vec!["motion-0".to_string(), "motion-4".to_string()],
+        );
+        assert!(!RuleEngine::evaluate_condition(&grouped, &four_in_hall));
+    }
+
+    #[test]
+    fn test_aggregate_any_all() {
+        let any = aggregate_condition(
+            "motion",
+            AggregateFunction::Any,
+            motion_active(),
+            Operator::Equals,
+            serde_json::json!(true),
+        );
+        let all = aggregate_condition(
+            "motion",
+            AggregateFunction::All,
+            motion_active(),
+            Operator::Equals,
+            serde_json::json!(true),
+        );
+
+        let some = motion_sensors(&[false, true, false]);
+        assert!(RuleEngine::evaluate_condition(&any, &some));
+        assert!(!RuleEngine::evaluate_condition(&all, &some));
+
+        let every = motion_sensors(&[true, true, true]);
+        assert!(RuleEngine::evaluate_condition(&all, &every));
+
+        // No devices reporting the field is never "all"
+        assert!(!RuleEngine::evaluate_condition(
+            &all,
+            &EvaluationContext::new()
+        ));
+    }
+
+    #[test]
+    fn test_aggregate_sum_avg() {
+        // power readings are 0, 10, 20, 30
+        let context = motion_sensors(&[true, false, true, true]);
+
+        let sum = aggregate_condition(
+            "power",
+            AggregateFunction::Sum,
+            None,
+            Operator::GreaterThanOrEqual,
+            serde_json::json!(60),
+        );
+        assert!(RuleEngine::evaluate_condition(&sum, &context));
+
+        let avg = aggregate_condition(
+            "power",
+            AggregateFunction::Avg,
+            None,
+            Operator::GreaterThan,
+            serde_json::json!(15),
+        );
+        assert!(!RuleEngine::evaluate_condition(&avg, &context));
+
+        // Filtering drops the idle device from the mean: (10 + 20 + 30) / 3
+        let mut busy = avg.clone();
+        busy.aggregate.as_mut().unwrap().filter = Some(DeviceFilter {
+            operator: Operator::GreaterThan,
+            value: serde_json::json!(0),
+        });
+        assert!(RuleEngine::evaluate_condition(&busy, &context));
+
+        // An average over no devices has no value
+        assert!(!RuleEngine::evaluate_condition(
+            &avg,
+            &EvaluationContext::new()
+        ));
+    }
+
     #[test]
     fn test_condition_mode_all() {
         let mut engine = RuleEngine::new();
@@ -483,12 +1264,16 @@ mod tests {
                     operator: Operator::GreaterThan,
                     value: serde_json::json!(25.0),
                     device_id: None,
+                    group_id: None,
+                    aggregate: None,
                 },
                 Condition {
                     field: "humidity".to_string(),
                     operator: Operator::LessThan,
                     value: serde_json::json!(50.0),
                     device_id: None,
+                    group_id: None,
+                    aggregate: None,
                 },
             ],
             actions: vec![],
@@ -496,6 +1281,7 @@ mod tests {
             priority: 1,
             cooldown_seconds: None,
             last_executed: None,
+            tenant_id: None,
             metadata: HashMap::new(),
         };
 
@@ -534,6 +1320,7 @@ mod tests {
             priority: 1,
             cooldown_seconds: None,
             last_executed: None,
+            tenant_id: None,
             metadata: HashMap::new(),
         };
 
@@ -548,6 +1335,7 @@ mod tests {
             priority: 10,
             cooldown_seconds: None,
             last_executed: None,
+            tenant_id: None,
             metadata: HashMap::new(),
         };
 
@@ -558,4 +1346,349 @@ mod tests {
         assert_eq!(rules[0].id, "rule_002"); // Higher priority first
         assert_eq!(rules[1].id, "rule_001");
     }
+
+    fn sample_at(seconds: i64, temperature: f64) -> EvaluationContext {
+        let mut context = EvaluationContext::new()
+            .with_telemetry("temperature".to_string(), serde_json::json!(temperature));
+        context.timestamp = DateTime::from_timestamp(1_700_000_000 + seconds, 0).unwrap();
+        context
+    }
+
+    fn over_temperature_rule(cooldown_seconds: Option<u64>) -> Rule {
+        Rule {
+            id: "overheat".to_string(),
+            name: "Overheat".to_string(),
+            description: None,
+            enabled: true,
+            conditions: vec![Condition {
+                field: "temperature".to_string(),
+                operator: Operator::GreaterThan,
+                value: serde_json::json!(30.0),
+                device_id: None,
+                group_id: None,
+                aggregate: None,
+            }],
+            actions: vec![],
+            condition_mode: ConditionMode::All,
+            priority: 1,
+            cooldown_seconds,
+            last_executed: None,
+            tenant_id: None,
+            metadata: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_tenant_rule_ignores_other_tenants() {
+        let mut engine = RuleEngine::new();
+        engine.add_rule(Rule {
+            tenant_id: Some("acme".to_string()),
+            ..over_temperature_rule(None)
+        });
+
+        let hot = sample_at(0, 35.0);
+        assert!(engine
+            .evaluate(&hot.clone().with_tenant("globex".to_string()))
+            .is_empty());
+        assert!(engine.evaluate(&hot.clone()).is_empty());
+        assert_eq!(
+            engine.evaluate(&hot.with_tenant("acme".to_string())),
+            vec!["overheat"]
+        );
+    }
+
+    #[test]
+    fn test_preview_ignores_cooldown_and_changes_nothing() {
+        let mut engine = RuleEngine::new();
+        engine.add_rule(over_temperature_rule(Some(60)));
+        let hot = sample_at(0, 35.0);
+
+        assert_eq!(engine.preview(&hot), vec!["overheat"]);
+        assert!(engine.get_rule("overheat").unwrap().last_executed.is_none());
+        assert!(engine.last_seen_value(None, "temperature").is_none());
+
+        assert_eq!(engine.evaluate(&hot), vec!["overheat"]);
+        assert!(engine.evaluate(&hot).is_empty());
+        assert_eq!(engine.preview(&hot), vec!["overheat"]);
+        assert!(engine.preview(&sample_at(0, 20.0)).is_empty());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_evaluation_under_read_lock() {
+        use std::sync::Arc;
+        use tokio::sync::RwLock;
+
+        let mut engine = RuleEngine::new();
+        engine.add_rule(over_temperature_rule(Some(300)));
+        let mut every_time = over_temperature_rule(None);
+        every_time.id = "log_heat".to_string();
+        engine.add_rule(every_time);
+        let engine = Arc::new(RwLock::new(engine));
+
+        let tasks: Vec<_> = (0..16)
+            .map(|_| {
+                let engine = engine.clone();
+                tokio::spawn(async move {
+                    let mut triggered = Vec::new();
+                    for _ in 0..50 {
+                        triggered.extend(engine.read().await.evaluate(&sample_at(0, 35.0)));
+                        tokio::task::yield_now().await;
+                    }
+                    triggered
+                })
+            })
+            .collect();
+        let mut triggered = Vec::new();
+        for task in tasks {
+            triggered.extend(
+                tokio::time::timeout(std::time::Duration::from_secs(10), task)
+                    .await
+                    .expect("evaluation deadlocked")
+                    .unwrap(),
+            );
+        }
+
+        // The cooldown is started exactly once; the rule without one fires every time
+        let count = |rule_id: &str| triggered.iter().filter(|id| *id == rule_id).count();
+        assert_eq!(count("overheat"), 1);
+        assert_eq!(count("log_heat"), 16 * 50);
+
+        let engine = engine.read().await;
+        let rule = engine.get_rule("overheat").unwrap();
+        assert!(rule.cooldown_remaining_secs(Utc::now()).is_some());
+        assert_eq!(
+            engine.last_seen_value(None, "temperature"),
+            Some(serde_json::json!(35.0))
+        );
+    }
+
+    #[test]
+    fn test_backtest_reports_each_trigger() {
+        let rule = over_temperature_rule(None);
+        let series = vec![
+            sample_at(0, 25.0),
+            sample_at(10, 35.0),
+            sample_at(20, 36.0),
+            sample_at(30, 20.0),
+        ];
+
+        let events = RuleEngine::backtest(&rule, &series);
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].sample_index, 1);
+        assert_eq!(events[0].timestamp, series[1].timestamp);
+        assert_eq!(events[1].sample_index, 2);
+        assert!(rule.last_executed.is_none());
+    }
+
+    #[test]
+    fn test_backtest_cooldown_uses_series_timestamps() {
+        let rule = over_temperature_rule(Some(60));
+        let series = vec![
+            sample_at(0, 35.0),
+            sample_at(30, 35.0),  // suppressed: 30s after first trigger
+            sample_at(59, 35.0),  // suppressed: still inside 60s cooldown
+            sample_at(60, 35.0),  // fires: cooldown elapsed
+            sample_at(100, 35.0), // suppressed
+            sample_at(200, 35.0), // fires
+        ];
+
+        let events = RuleEngine::backtest(&rule, &series);
+        let indices: Vec<usize> = events.iter().map(|e| e.sample_index).collect();
+        assert_eq!(indices, vec![0, 3, 5]);
+    }
+
+    #[test]
+    fn test_backtest_orders_samples_by_timestamp() {
+        let rule = over_temperature_rule(Some(60));
+        let series = vec![sample_at(30, 35.0), sample_at(0, 35.0)];
+
+        let events = RuleEngine::backtest(&rule, &series);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].sample_index, 1);
+    }
+
+    fn door_rule(target: serde_json::Value) -> Rule {
+        Rule {
+            id: "door_opened".to_string(),
+            name: "Door Opened".to_string(),
+            description: None,
+            enabled: true,
+            conditions: vec![Condition {
+                field: "door".to_string(),
+                operator: Operator::Changed,
+                value: target,
+                device_id: Some("door-1".to_string()),
+                group_id: None,
+                aggregate: None,
+            }],
+            actions: vec![],
+            condition_mode: ConditionMode::All,
+            priority: 1,
+            cooldown_seconds: None,
+            last_executed: None,
+            tenant_id: None,
+            metadata: HashMap::new(),
+        }
+    }
+
+    fn door_state(state: &str) -> EvaluationContext {
+        let mut device_state = HashMap::new();
+        device_state.insert("door".to_string(), serde_json::json!(state));
+        EvaluationContext::new().with_device_state("door-1".to_string(), device_state)
+    }
+
+    #[test]
+    fn test_changed_fires_on_transition() {
+        let mut engine = RuleEngine::new();
+        engine.add_rule(door_rule(serde_json::Value::Null));
+
+        // First observation only establishes the baseline
+        assert!(engine.evaluate(&door_state("closed")).is_empty());
+        assert_eq!(engine.evaluate(&door_state("open")), vec!["door_opened"]);
+        assert_eq!(
+            engine.last_seen_value(Some("door-1"), "door"),
+            Some(serde_json::json!("open"))
+        );
+    }
+
+    fn reading(device_id: &str, state: &str) -> EvaluationContext {
+        let mut device_state = HashMap::new();
+        device_state.insert("door".to_string(), serde_json::json!(state));
+        EvaluationContext::new()
+            .with_telemetry("door".to_string(), serde_json::json!(state))
+            .with_device_state(device_id.to_string(), device_state)
+    }
+
+    #[test]
+    fn test_changed_without_device_tracks_each_reporting_device() {
+        let mut rule = door_rule(serde_json::Value::Null);
+        rule.conditions[0].device_id = None;
+        let mut engine = RuleEngine::new();
+        engine.add_rule(rule);
+
+        assert!(engine.evaluate(&reading("door-1", "closed")).is_empty());
+        assert!(engine.evaluate(&reading("door-2", "open")).is_empty());
+        // Neither door has changed since its own last report
+        assert!(engine.evaluate(&reading("door-1", "closed")).is_empty());
+        assert!(engine.evaluate(&reading("door-2", "open")).is_empty());
+
+        assert_eq!(
+            engine.evaluate(&reading("door-1", "open")),
+            vec!["door_opened"]
+        );
+        assert!(engine.evaluate(&reading("door-2", "open")).is_empty());
+
+        // Telemetry without a reporting device still uses the field's shared history
+        let shared = |state: &str| {
+            EvaluationContext::new().with_telemetry("door".to_string(), serde_json::json!(state))
+        };
+        assert!(engine.evaluate(&shared("open")).is_empty());
+        assert_eq!(engine.evaluate(&shared("closed")), vec!["door_opened"]);
+    }
+
+    #[test]
+    fn test_changed_ignores_repeated_value() {
+        let mut engine = RuleEngine::new();
+        engine.add_rule(door_rule(serde_json::Value::Null));
+
+        engine.evaluate(&door_state("closed"));
+        assert_eq!(engine.evaluate(&door_state("open")).len(), 1);
+        assert!(engine.evaluate(&door_state("open")).is_empty());
+        assert!(engine.evaluate(&door_state("open")).is_empty());
+    }
+
+    #[test]
+    fn test_changed_to_specific_value() {
+        let mut engine = RuleEngine::new();
+        engine.add_rule(door_rule(serde_json::json!("open")));
+
+        engine.evaluate(&door_state("open"));
+        assert!(engine.evaluate(&door_state("closed")).is_empty());
+        assert_eq!(engine.evaluate(&door_state("open")).len(), 1);
+    }
+
+    #[test]
+    fn test_backtest_honors_changed_operator() {
+        let rule = door_rule(serde_json::json!("open"));
+        let series: Vec<EvaluationContext> = ["closed", "open", "open", "closed", "open"]
+            .iter()
+            .enumerate()
+            .map(|(i, state)| {
+                let mut context = door_state(state);
+                context.timestamp = DateTime::from_timestamp(1_700_000_000 + i as i64, 0).unwrap();
+                context
+            })
+            .collect();
+
+        let events = RuleEngine::backtest(&rule, &series);
+        let indices: Vec<usize> = events.iter().map(|e| e.sample_index).collect();
+        assert_eq!(indices, vec![1, 4]);
+    }
+
+    fn thermostat_context() -> EvaluationContext {
+        let thermostat = || vec!["thermostat".to_string(), "temperature".to_string()];
+        EvaluationContext::new()
+            .with_capabilities("hall-thermostat".to_string(), thermostat())
+            .with_capabilities("office-thermostat".to_string(), thermostat())
+            .with_capabilities("hall-light".to_string(), vec!["switch".to_string()])
+            .with_group(
+                "hall".to_string(),
+                vec!["hall-thermostat".to_string(), "hall-light".to_string()],
+            )
+    }
+
+    fn targeted_action(group_id: Option<&str>, capability: Option<&str>) -> Action {
+        Action {
+            action_type: ActionType::SendCommand,
+            device_id: None,
+            group_id: group_id.map(str::to_string),
+            capability: capability.map(str::to_string),
+            parameters: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_capability_target_resolves_matching_devices() {
+        let context = thermostat_context();
+
+        let all_thermostats = targeted_action(None, Some("thermostat"));
+        assert_eq!(
+            all_thermostats.target_devices(&context),
+            ["hall-thermostat", "office-thermostat"]
+        );
+
+        // A capability narrows a group to the members declaring it
+        let hall_thermostats = targeted_action(Some("hall"), Some("thermostat"));
+        assert_eq!(
+            hall_thermostats.target_devices(&context),
+            ["hall-thermostat"]
+        );
+        assert_eq!(
+            targeted_action(Some("hall"), None).target_devices(&context),
+            ["hall-light", "hall-thermostat"]
+        );
+
+        let mut single = targeted_action(None, Some("switch"));
+        single.device_id = Some("hall-light".to_string());
+        assert_eq!(single.target_devices(&context), ["hall-light"]);
+    }
+
+    #[test]
+    fn test_unmatched_target_resolves_to_no_devices() {
+        let context = thermostat_context();
+
+        assert!(targeted_action(None, Some("camera"))
+            .target_devices(&context)
+            .is_empty());
+        assert!(targeted_action(Some("garage"), None)
+            .target_devices(&context)
+            .is_empty());
+        // Notifications and other actions without a device target
+        assert!(targeted_action(None, None)
+            .target_devices(&context)
+            .is_empty());
+
+        let json = serde_json::to_value(targeted_action(None, None)).unwrap();
+        assert!(json.get("group_id").is_none() && json.get("capability").is_none());
+    }
 }
@@ -5,10 +5,15 @@
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::Arc;
 use uaip_core::error::{Result, UaipError};
 use uuid::Uuid;
 
+use crate::events::{ExecutionEventSink, ExecutionEvents, ExecutionKind, ExecutionStateChanged};
+use crate::notification::{LogNotifier, Notification, Notifier, DEFAULT_CHANNEL};
+use crate::rule_engine::EvaluationContext;
+
 /// Scenario execution state
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
@@ -25,6 +30,9 @@ pub enum ScenarioState {
     Failed,
 }
 
+/// `DeviceEvent` trigger `event_type` matching every event type
+pub const ANY_EVENT_TYPE: &str = "*";
+
 /// Trigger type for scenarios
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "snake_case")]
@@ -41,6 +49,8 @@ pub enum TriggerType {
     Webhook,
     /// Trigger on system event
     SystemEvent,
+    /// Trigger when a device stays silent longer than a timeout
+    Absence,
 }
 
 /// Scenario trigger configuration
@@ -141,7 +151,15 @@ pub struct Scenario {
     pub last_triggered: Option<DateTime<Utc>>,
 
     /// Last execution result
-    pub last_result: Option<String>,
+    pub last_result: Option<ScenarioResult>,
+
+    /// Summary of the last execution result (`success` or `failed`)
+    #[serde(default)]
+    pub last_result_summary: Option<String>,
+
+    /// Tenant that owns the scenario; unscoped scenarios are shared by every tenant
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant_id: Option<String>,
 
     /// Created timestamp
     pub created_at: DateTime<Utc>,
@@ -150,6 +168,41 @@ pub struct Scenario {
     pub updated_at: DateTime<Utc>,
 }
 
+/// An event reported by a device, matched against `DeviceEvent` scenario triggers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceEvent {
+    /// Reporting device
+    pub device_id: String,
+
+    /// Event type (e.g. `door_opened`)
+    pub event_type: String,
+
+    /// Event fields, checked by trigger conditions
+    #[serde(default)]
+    pub data: HashMap<String, serde_json::Value>,
+
+    /// Event timestamp
+    pub timestamp: DateTime<Utc>,
+}
+
+impl DeviceEvent {
+    /// Create an event without data, timestamped now
+    pub fn new(device_id: impl Into<String>, event_type: impl Into<String>) -> Self {
+        Self {
+            device_id: device_id.into(),
+            event_type: event_type.into(),
+            data: HashMap::new(),
+            timestamp: Utc::now(),
+        }
+    }
+
+    /// Add an event field
+    pub fn with_data(mut self, field: impl Into<String>, value: serde_json::Value) -> Self {
+        self.data.insert(field.into(), value);
+        self
+    }
+}
+
 /// Scenario execution record
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScenarioExecution {
@@ -204,6 +257,165 @@ pub struct ActionExecution {
     pub completed_at: Option<DateTime<Utc>>,
 }
 
+/// Outcome of a scenario's latest execution
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScenarioResult {
+    /// `completed`, or `failed` if any action failed
+    pub status: ScenarioState,
+
+    /// The first failed action and why it failed
+    pub error: Option<String>,
+
+    /// Results of the executed actions, in order
+    #[serde(default)]
+    pub action_results: Vec<ActionExecution>,
+
+    /// When the execution finished
+    pub at: DateTime<Utc>,
+}
+
+impl ScenarioResult {
+    /// Result of an execution that ran `actions`, failed by the first action with an error
+    pub fn from_actions(actions: &[ActionExecution], at: DateTime<Utc>) -> Self {
+        let error = actions.iter().enumerate().find_map(|(index, action)| {
+            action.error.as_ref().map(|error| {
+                format!(
+                    "Action {} ({:?}) failed: {}",
+                    index + 1,
+                    action.action,
+                    error
+                )
+            })
+        });
+
+        Self {
+            status: if error.is_some() {
+                ScenarioState::Failed
+            } else {
+                ScenarioState::Completed
+            },
+            error,
+            action_results: actions.to_vec(),
+            at,
+        }
+    }
+
+    /// `success` or `failed`, the string `last_result` held before it was structured
+    pub fn summary(&self) -> &'static str {
+        match self.status {
+            ScenarioState::Failed => "failed",
+            _ => "success",
+        }
+    }
+}
+
+/// A started execution's actions, runnable without holding the engine
+///
+/// Obtained from [`ScenarioEngine::pending_actions`]; the outcome is recorded with
+/// [`ScenarioEngine::complete_actions`].
+pub struct PendingActions {
+    execution_id: String,
+    actions: Vec<ScenarioActionConfig>,
+    trigger_context: HashMap<String, serde_json::Value>,
+    notifiers: HashMap<String, Arc<dyn Notifier>>,
+}
+
+/// Actions run for an execution, not yet recorded on it
+pub struct CompletedActions {
+    execution_id: String,
+    executed: Vec<ActionExecution>,
+}
+
+impl PendingActions {
+    pub fn execution_id(&self) -> &str {
+        &self.execution_id
+    }
+
+    /// Run the actions in order
+    pub async fn run(self) -> CompletedActions {
+        let mut executed = Vec::with_capacity(self.actions.len());
+
+        for action_config in self.actions {
+            let now = Utc::now();
+
+            let (result, error) = match action_config.action {
+                ScenarioAction::SendNotification => {
+                    send_notification(
+                        &self.notifiers,
+                        &action_config.parameters,
+                        &self.trigger_context,
+                    )
+                    .await
+                }
+                _ => (None, None),
+            };
+
+            executed.push(ActionExecution {
+                action: action_config.action.clone(),
+                parameters: action_config.parameters.clone(),
+                result,
+                error,
+                started_at: now,
+                completed_at: Some(Utc::now()),
+            });
+        }
+
+        CompletedActions {
+            execution_id: self.execution_id,
+            executed,
+        }
+    }
+}
+
+impl CompletedActions {
+    pub fn execution_id(&self) -> &str {
+        &self.execution_id
+    }
+}
+
+/// Deliver a notification on the channel named by the action parameters
+///
+/// Unknown channels and failed deliveries fall back to the log channel.
+/// Returns the delivery result and any delivery error.
+async fn send_notification(
+    notifiers: &HashMap<String, Arc<dyn Notifier>>,
+    parameters: &HashMap<String, serde_json::Value>,
+    context: &HashMap<String, serde_json::Value>,
+) -> (Option<serde_json::Value>, Option<String>) {
+    let channel = parameters
+        .get("channel")
+        .and_then(|v| v.as_str())
+        .unwrap_or(DEFAULT_CHANNEL);
+    let notification = Notification::from_parameters(parameters, context);
+
+    let error = match notifiers.get(channel) {
+        Some(notifier) => match notifier.send(&notification).await {
+            Ok(details) => {
+                let result = serde_json::json!({
+                    "channel": channel,
+                    "delivered": true,
+                    "message": notification.message,
+                    "details": details,
+                });
+                return (Some(result), None);
+            }
+            Err(e) => e.to_string(),
+        },
+        None => format!("Notification channel not configured: {}", channel),
+    };
+
+    tracing::warn!(channel, error = %error, "Notification delivery failed, falling back to log");
+    let _ = LogNotifier.send(&notification).await;
+
+    let result = serde_json::json!({
+        "channel": channel,
+        "delivered": false,
+        "fallback": LogNotifier.channel(),
+        "message": notification.message,
+    });
+    (Some(result), Some(error))
+}
+
 /// Scenario engine for managing automation scenarios
 pub struct ScenarioEngine {
     /// Registered scenarios
@@ -211,6 +423,18 @@ pub struct ScenarioEngine {
 
     /// Execution history
     executions: HashMap<String, ScenarioExecution>,
+
+    /// Notification channels by name
+    notifiers: HashMap<String, Arc<dyn Notifier>>,
+
+    /// IDs of scenarios with a `DeviceEvent` trigger, by the event type it listens for
+    event_index: HashMap<String, BTreeSet<String>>,
+
+    /// Last-seen time each absence trigger fired for, by scenario and device
+    absences: HashMap<(String, String), DateTime<Utc>>,
+
+    /// Execution state transitions
+    events: ExecutionEvents,
 }
 
 impl ScenarioEngine {
@@ -219,7 +443,37 @@ impl ScenarioEngine {
         Self {
             scenarios: HashMap::new(),
             executions: HashMap::new(),
+            notifiers: HashMap::new(),
+            event_index: HashMap::new(),
+            absences: HashMap::new(),
+            events: ExecutionEvents::default(),
         }
+        .with_notifier(Arc::new(LogNotifier))
+    }
+
+    /// Send execution state events to a sink
+    pub fn with_event_sink(mut self, sink: Arc<dyn ExecutionEventSink>) -> Self {
+        self.set_event_sink(sink);
+        self
+    }
+
+    /// Send execution state events to a sink, including those emitted so far
+    pub fn set_event_sink(&mut self, sink: Arc<dyn ExecutionEventSink>) {
+        self.events.set_sink(sink);
+    }
+
+    /// Register a notification channel, replacing any channel with the same name
+    pub fn with_notifier(mut self, notifier: Arc<dyn Notifier>) -> Self {
+        self.notifiers
+            .insert(notifier.channel().to_string(), notifier);
+        self
+    }
+
+    /// Names of the registered notification channels, sorted
+    pub fn notification_channels(&self) -> Vec<&str> {
+        let mut channels: Vec<&str> = self.notifiers.keys().map(String::as_str).collect();
+        channels.sort_unstable();
+        channels
     }
 
     /// Register a scenario
@@ -236,6 +490,31 @@ impl ScenarioEngine {
             ));
         }
 
+        for trigger in &scenario.triggers {
+            if trigger.trigger_type == TriggerType::Absence {
+                Self::absence_timeout(trigger)?;
+                if !["device_id", "group_id"]
+                    .iter()
+                    .any(|key| trigger.config.get(*key).is_some_and(|v| v.is_string()))
+                {
+                    return Err(UaipError::InvalidConfiguration(
+                        "Absence trigger needs a device_id or group_id".to_string(),
+                    ));
+                }
+            }
+        }
+
+        self.unindex_events(&scenario.id);
+        self.absences
+            .retain(|(scenario_id, _), _| *scenario_id != scenario.id);
+        for trigger in &scenario.triggers {
+            if let Some(event_type) = Self::trigger_event_type(trigger) {
+                self.event_index
+                    .entry(event_type.to_string())
+                    .or_default()
+                    .insert(scenario.id.clone());
+            }
+        }
         self.scenarios.insert(scenario.id.clone(), scenario);
         Ok(())
     }
@@ -245,9 +524,30 @@ impl ScenarioEngine {
         self.scenarios
             .remove(scenario_id)
             .ok_or_else(|| UaipError::NotFound(format!("Scenario not found: {}", scenario_id)))?;
+        self.unindex_events(scenario_id);
+        self.absences.retain(|(id, _), _| id != scenario_id);
         Ok(())
     }
 
+    /// Event type a `DeviceEvent` trigger listens for; unset listens for every type
+    fn trigger_event_type(trigger: &ScenarioTrigger) -> Option<&str> {
+        (trigger.trigger_type == TriggerType::DeviceEvent).then(|| {
+            trigger
+                .config
+                .get("event_type")
+                .and_then(|v| v.as_str())
+                .unwrap_or(ANY_EVENT_TYPE)
+        })
+    }
+
+    /// Drop a scenario from the event index
+    fn unindex_events(&mut self, scenario_id: &str) {
+        self.event_index.retain(|_, scenario_ids| {
+            scenario_ids.remove(scenario_id);
+            !scenario_ids.is_empty()
+        });
+    }
+
     /// Get a scenario by ID
     pub fn get_scenario(&self, scenario_id: &str) -> Option<&Scenario> {
         self.scenarios.get(scenario_id)
@@ -304,6 +604,275 @@ impl ScenarioEngine {
         &mut self,
         scenario_id: &str,
         context: HashMap<String, serde_json::Value>,
+    ) -> Result<String> {
+        self.start_execution(scenario_id, TriggerType::Manual, context)
+    }
+
+    /// Fire every enabled scenario subscribed to a triggered rule
+    ///
+    /// A scenario subscribes with a `RuleTriggered` trigger whose config `rule_id` matches.
+    /// A config `group_id` scopes the trigger to contexts reporting state for a group member.
+    /// The rule's evaluation context is passed through as the trigger context.
+    /// Returns the IDs of the started executions.
+    pub fn handle_rule_triggered(
+        &mut self,
+        rule_id: &str,
+        context: &EvaluationContext,
+    ) -> Result<Vec<String>> {
+        let trigger_context = Self::rule_trigger_context(rule_id, context);
+
+        self.subscribed_scenarios(rule_id, context, &trigger_context)
+            .iter()
+            .map(|scenario_id| {
+                self.start_execution(
+                    scenario_id,
+                    TriggerType::RuleTriggered,
+                    trigger_context.clone(),
+                )
+            })
+            .collect()
+    }
+
+    /// IDs of the active scenarios a triggered rule would start, without starting them
+    pub fn scenarios_for_rule(&self, rule_id: &str, context: &EvaluationContext) -> Vec<String> {
+        let trigger_context = Self::rule_trigger_context(rule_id, context);
+        self.subscribed_scenarios(rule_id, context, &trigger_context)
+    }
+
+    /// Active scenarios with a `RuleTriggered` trigger for `rule_id` that holds, sorted
+    fn subscribed_scenarios(
+        &self,
+        rule_id: &str,
+        context: &EvaluationContext,
+        trigger_context: &HashMap<String, serde_json::Value>,
+    ) -> Vec<String> {
+        let mut subscribed: Vec<String> = self
+            .scenarios
+            .values()
+            .filter(|scenario| scenario.enabled)
+            .filter(|scenario| {
+                scenario.triggers.iter().any(|trigger| {
+                    trigger.trigger_type == TriggerType::RuleTriggered
+                        && trigger.config.get("rule_id").and_then(|v| v.as_str()) == Some(rule_id)
+                        && Self::in_group_scope(trigger, context)
+                        && self.check_trigger_condition(trigger, trigger_context)
+                })
+            })
+            .map(|scenario| scenario.id.clone())
+            .collect();
+        subscribed.sort();
+        subscribed
+    }
+
+    /// Fire every active scenario with a `DeviceEvent` trigger matching an event
+    ///
+    /// A trigger matches when its config `event_type` equals the event's (or is unset or
+    /// `*`), its config `device_id` (if any) equals the reporting device, and its
+    /// conditions hold for the event data. Only scenarios indexed under the event type are
+    /// checked. Returns the IDs of the started executions.
+    pub fn evaluate_event(&mut self, event: &DeviceEvent) -> Result<Vec<String>> {
+        let mut trigger_context = event.data.clone();
+        trigger_context.insert("device_id".to_string(), serde_json::json!(event.device_id));
+        trigger_context.insert(
+            "event_type".to_string(),
+            serde_json::json!(event.event_type),
+        );
+        trigger_context.insert(
+            "timestamp".to_string(),
+            serde_json::json!(event.timestamp.to_rfc3339()),
+        );
+
+        let candidates: BTreeSet<&String> = [event.event_type.as_str(), ANY_EVENT_TYPE]
+            .iter()
+            .filter_map(|event_type| self.event_index.get(*event_type))
+            .flatten()
+            .collect();
+
+        let matching: Vec<String> = candidates
+            .into_iter()
+            .filter_map(|scenario_id| self.scenarios.get(scenario_id))
+            .filter(|scenario| scenario.enabled && scenario.state == ScenarioState::Active)
+            .filter(|scenario| {
+                scenario.triggers.iter().any(|trigger| {
+                    Self::trigger_event_type(trigger)
+                        .is_some_and(|t| t == ANY_EVENT_TYPE || t == event.event_type)
+                        && trigger
+                            .config
+                            .get("device_id")
+                            .and_then(|v| v.as_str())
+                            .is_none_or(|device_id| device_id == event.device_id)
+                        && self.check_trigger_condition(trigger, &trigger_context)
+                })
+            })
+            .map(|scenario| scenario.id.clone())
+            .collect();
+
+        matching
+            .iter()
+            .map(|scenario_id| {
+                self.start_execution(
+                    scenario_id,
+                    TriggerType::DeviceEvent,
+                    trigger_context.clone(),
+                )
+            })
+            .collect()
+    }
+
+    /// Fire every active scenario with an `Absence` trigger whose devices have gone silent
+    ///
+    /// A trigger watches its config `device_id`, or every member of its config `group_id`,
+    /// and fires when a device was last seen more than `timeout_seconds` before `now`. It
+    /// fires once per silence and watches the device again once it is next seen. Devices
+    /// missing from `last_seen` are skipped. Returns the IDs of the started executions.
+    pub fn evaluate_absence(
+        &mut self,
+        last_seen: &HashMap<String, DateTime<Utc>>,
+        groups: &HashMap<String, Vec<String>>,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<String>> {
+        let mut silent = BTreeMap::new();
+        let mut seen_again = Vec::new();
+
+        let active = self
+            .scenarios
+            .values()
+            .filter(|scenario| scenario.enabled && scenario.state == ScenarioState::Active);
+        for scenario in active {
+            let triggers = scenario
+                .triggers
+                .iter()
+                .filter(|trigger| trigger.trigger_type == TriggerType::Absence);
+            for trigger in triggers {
+                let Ok(timeout) = Self::absence_timeout(trigger) else {
+                    continue;
+                };
+                for device_id in Self::absence_devices(trigger, groups) {
+                    let Some(&seen) = last_seen.get(device_id) else {
+                        continue;
+                    };
+                    let key = (scenario.id.clone(), device_id.to_string());
+                    if now - seen <= timeout {
+                        seen_again.push(key);
+                        continue;
+                    }
+                    if self.absences.get(&key) == Some(&seen) {
+                        // Already fired for this silence
+                        continue;
+                    }
+
+                    let context = HashMap::from([
+                        ("device_id".to_string(), serde_json::json!(device_id)),
+                        (
+                            "last_seen".to_string(),
+                            serde_json::json!(seen.to_rfc3339()),
+                        ),
+                        (
+                            "silent_seconds".to_string(),
+                            serde_json::json!((now - seen).num_seconds()),
+                        ),
+                        (
+                            "timeout_seconds".to_string(),
+                            serde_json::json!(timeout.num_seconds()),
+                        ),
+                        ("timestamp".to_string(), serde_json::json!(now.to_rfc3339())),
+                    ]);
+                    if self.check_trigger_condition(trigger, &context) {
+                        silent.entry(key).or_insert((seen, context));
+                    }
+                }
+            }
+        }
+
+        for key in seen_again {
+            self.absences.remove(&key);
+        }
+        silent
+            .into_iter()
+            .map(|((scenario_id, device_id), (seen, context))| {
+                let execution_id =
+                    self.start_execution(&scenario_id, TriggerType::Absence, context)?;
+                self.absences.insert((scenario_id, device_id), seen);
+                Ok(execution_id)
+            })
+            .collect()
+    }
+
+    /// Silence an `Absence` trigger tolerates, from its config `timeout_seconds`
+    fn absence_timeout(trigger: &ScenarioTrigger) -> Result<chrono::Duration> {
+        trigger
+            .config
+            .get("timeout_seconds")
+            .and_then(|v| v.as_i64())
+            .filter(|seconds| *seconds > 0)
+            .and_then(chrono::Duration::try_seconds)
+            .ok_or_else(|| {
+                UaipError::InvalidConfiguration(
+                    "Absence trigger needs a positive timeout_seconds".to_string(),
+                )
+            })
+    }
+
+    /// Devices an `Absence` trigger watches
+    fn absence_devices<'a>(
+        trigger: &'a ScenarioTrigger,
+        groups: &'a HashMap<String, Vec<String>>,
+    ) -> Vec<&'a str> {
+        let mut devices: Vec<&str> = trigger
+            .config
+            .get("device_id")
+            .and_then(|v| v.as_str())
+            .into_iter()
+            .collect();
+        if let Some(group_id) = trigger.config.get("group_id").and_then(|v| v.as_str()) {
+            devices.extend(
+                groups
+                    .get(group_id)
+                    .into_iter()
+                    .flatten()
+                    .map(String::as_str),
+            );
+        }
+        devices
+    }
+
+    /// Whether a trigger's `group_id` scope (if any) covers a device in the context
+    fn in_group_scope(trigger: &ScenarioTrigger, context: &EvaluationContext) -> bool {
+        match trigger.config.get("group_id").and_then(|v| v.as_str()) {
+            Some(group_id) => context
+                .group_members(group_id)
+                .iter()
+                .any(|device_id| context.device_states.contains_key(device_id)),
+            None => true,
+        }
+    }
+
+    /// Flatten a rule evaluation context into a scenario trigger context
+    fn rule_trigger_context(
+        rule_id: &str,
+        context: &EvaluationContext,
+    ) -> HashMap<String, serde_json::Value> {
+        let mut trigger_context = context.telemetry.clone();
+        trigger_context.insert("rule_id".to_string(), serde_json::json!(rule_id));
+        trigger_context.insert(
+            "timestamp".to_string(),
+            serde_json::json!(context.timestamp.to_rfc3339()),
+        );
+        if !context.device_states.is_empty() {
+            trigger_context.insert(
+                "device_states".to_string(),
+                serde_json::json!(context.device_states),
+            );
+        }
+        trigger_context
+    }
+
+    /// Record a new execution for a scenario
+    fn start_execution(
+        &mut self,
+        scenario_id: &str,
+        trigger: TriggerType,
+        context: HashMap<String, serde_json::Value>,
     ) -> Result<String> {
         let scenario = self
             .scenarios
@@ -323,7 +892,7 @@ impl ScenarioEngine {
         let execution = ScenarioExecution {
             id: execution_id.clone(),
             scenario_id: scenario_id.to_string(),
-            trigger: TriggerType::Manual,
+            trigger,
             state: ScenarioState::Executing,
             trigger_context: context,
             actions_executed: Vec::new(),
@@ -332,6 +901,14 @@ impl ScenarioEngine {
             completed_at: None,
         };
 
+        let mut started = ExecutionStateChanged::new(
+            ExecutionKind::Scenario,
+            &execution_id,
+            None,
+            &execution.state,
+        );
+        started.tenant_id = scenario.tenant_id.clone();
+        self.events.emit(started);
         self.executions.insert(execution_id.clone(), execution);
 
         // Update scenario state
@@ -346,45 +923,62 @@ impl ScenarioEngine {
     }
 
     /// Execute scenario actions
-    pub fn execute_actions(&mut self, execution_id: &str) -> Result<()> {
-        let scenario_id = {
-            let execution = self.executions.get(execution_id).ok_or_else(|| {
-                UaipError::NotFound(format!("Execution not found: {}", execution_id))
-            })?;
-            execution.scenario_id.clone()
-        };
-
-        let scenario = self
-            .scenarios
-            .get(&scenario_id)
-            .ok_or_else(|| UaipError::NotFound(format!("Scenario not found: {}", scenario_id)))?;
-
-        let actions = scenario.actions.clone();
-        let execution = self.executions.get_mut(execution_id).unwrap();
-
-        for action_config in actions {
-            let now = Utc::now();
+    pub async fn execute_actions(&mut self, execution_id: &str) -> Result<()> {
+        let completed = self.pending_actions(execution_id)?.run().await;
+        self.complete_actions(completed)
+    }
 
-            let action_exec = ActionExecution {
-                action: action_config.action.clone(),
-                parameters: action_config.parameters.clone(),
-                result: None,
-                error: None,
-                started_at: now,
-                completed_at: Some(Utc::now()),
-            };
+    /// Actions of a started execution, to run once the engine is no longer borrowed
+    pub fn pending_actions(&self, execution_id: &str) -> Result<PendingActions> {
+        let execution = self
+            .executions
+            .get(execution_id)
+            .ok_or_else(|| UaipError::NotFound(format!("Execution not found: {}", execution_id)))?;
+        let scenario = self.scenarios.get(&execution.scenario_id).ok_or_else(|| {
+            UaipError::NotFound(format!("Scenario not found: {}", execution.scenario_id))
+        })?;
+
+        Ok(PendingActions {
+            execution_id: execution_id.to_string(),
+            actions: scenario.actions.clone(),
+            trigger_context: execution.trigger_context.clone(),
+            notifiers: self.notifiers.clone(),
+        })
+    }
 
-            execution.actions_executed.push(action_exec);
-        }
+    /// Record the actions run for an execution and mark it completed
+    pub fn complete_actions(&mut self, completed: CompletedActions) -> Result<()> {
+        let execution_id = completed.execution_id.as_str();
+        let execution = self
+            .executions
+            .get_mut(execution_id)
+            .ok_or_else(|| UaipError::NotFound(format!("Execution not found: {}", execution_id)))?;
+        let scenario_id = execution.scenario_id.clone();
+        execution.actions_executed.extend(completed.executed);
 
         // Mark execution as completed
-        execution.state = ScenarioState::Completed;
-        execution.completed_at = Some(Utc::now());
+        let old = std::mem::replace(&mut execution.state, ScenarioState::Completed);
+        let completed_at = Utc::now();
+        execution.completed_at = Some(completed_at);
+        let result = ScenarioResult::from_actions(&execution.actions_executed, completed_at);
+        execution.error = result.error.clone();
+        let mut finished = ExecutionStateChanged::new(
+            ExecutionKind::Scenario,
+            execution_id,
+            Some(&old),
+            &execution.state,
+        );
+        finished.tenant_id = self
+            .scenarios
+            .get(&scenario_id)
+            .and_then(|scenario| scenario.tenant_id.clone());
+        self.events.emit(finished);
 
         // Update scenario state
         if let Some(scenario) = self.scenarios.get_mut(&scenario_id) {
             scenario.state = ScenarioState::Active;
-            scenario.last_result = Some("success".to_string());
+            scenario.last_result_summary = Some(result.summary().to_string());
+            scenario.last_result = Some(result);
             scenario.updated_at = Utc::now();
         }
 
@@ -457,6 +1051,31 @@ impl ScenarioEngine {
             .collect()
     }
 
+    /// Get executions that have not finished yet
+    pub fn get_active_executions(&self) -> Vec<&ScenarioExecution> {
+        self.executions
+            .values()
+            .filter(|e| e.completed_at.is_none())
+            .collect()
+    }
+
+    /// Get number of executions held in memory, finished or not
+    pub fn execution_count(&self) -> usize {
+        self.executions.len()
+    }
+
+    /// Remove a finished execution, handing it over (e.g. to a persistent history)
+    ///
+    /// Running executions are left in place.
+    pub fn take_execution(&mut self, execution_id: &str) -> Option<ScenarioExecution> {
+        match self.executions.get(execution_id) {
+            Some(execution) if execution.completed_at.is_some() => {
+                self.executions.remove(execution_id)
+            }
+            _ => None,
+        }
+    }
+
     /// Clean up old executions
     pub fn cleanup_executions(&mut self, older_than_seconds: i64) {
         let cutoff = Utc::now() - chrono::Duration::seconds(older_than_seconds);
@@ -514,6 +1133,8 @@ mod tests {
             execution_count: 0,
             last_triggered: None,
             last_result: None,
+            last_result_summary: None,
+            tenant_id: None,
             created_at: Utc::now(),
             updated_at: Utc::now(),
         }
@@ -594,8 +1215,8 @@ mod tests {
         assert!(scenario_ref.last_triggered.is_some());
     }
 
-    #[test]
-    fn test_execute_actions() {
+    #[tokio::test]
+    async fn test_execute_actions() {
         let mut engine = ScenarioEngine::new();
         let scenario = create_test_scenario();
 
@@ -603,9 +1224,12 @@ mod tests {
 
         let context = HashMap::new();
         let execution_id = engine.trigger_scenario(&scenario.id, context).unwrap();
+        assert_eq!(engine.get_active_executions().len(), 1);
 
         // Execute actions
-        assert!(engine.execute_actions(&execution_id).is_ok());
+        assert!(engine.execute_actions(&execution_id).await.is_ok());
+        assert!(engine.get_active_executions().is_empty());
+        assert_eq!(engine.execution_count(), 1);
 
         let execution = engine.get_execution(&execution_id).unwrap();
         assert_eq!(execution.state, ScenarioState::Completed);
@@ -614,7 +1238,11 @@ mod tests {
 
         let scenario_ref = engine.get_scenario(&scenario.id).unwrap();
         assert_eq!(scenario_ref.state, ScenarioState::Active);
-        assert_eq!(scenario_ref.last_result.as_deref(), Some("success"));
+        assert_eq!(scenario_ref.last_result_summary.as_deref(), Some("success"));
+        let result = scenario_ref.last_result.as_ref().unwrap();
+        assert_eq!(result.status, ScenarioState::Completed);
+        assert!(result.error.is_none());
+        assert_eq!(result.action_results.len(), 1);
     }
 
     #[test]
@@ -662,8 +1290,8 @@ mod tests {
         assert_eq!(active[0].id, "scenario_001");
     }
 
-    #[test]
-    fn test_cleanup_executions() {
+    #[tokio::test]
+    async fn test_cleanup_executions() {
         let mut engine = ScenarioEngine::new();
         let scenario = create_test_scenario();
 
@@ -671,7 +1299,7 @@ mod tests {
 
         let context = HashMap::new();
         let execution_id = engine.trigger_scenario(&scenario.id, context).unwrap();
-        engine.execute_actions(&execution_id).unwrap();
+        engine.execute_actions(&execution_id).await.unwrap();
 
         // Verify execution exists
         assert!(engine.get_execution(&execution_id).is_some());
@@ -684,4 +1312,418 @@ mod tests {
         engine.cleanup_executions(-1);
         assert!(engine.get_execution(&execution_id).is_none());
     }
+
+    #[tokio::test]
+    async fn test_take_finished_execution() {
+        let mut engine = ScenarioEngine::new();
+        let scenario = create_test_scenario();
+        engine.register_scenario(scenario.clone()).unwrap();
+
+        let execution_id = engine
+            .trigger_scenario(&scenario.id, HashMap::new())
+            .unwrap();
+        assert!(engine.take_execution(&execution_id).is_none());
+
+        engine.execute_actions(&execution_id).await.unwrap();
+        let execution = engine.take_execution(&execution_id).unwrap();
+        assert_eq!(execution.state, ScenarioState::Completed);
+        assert!(engine.get_execution(&execution_id).is_none());
+    }
+
+    fn rule_subscribed_scenario(rule_id: &str) -> Scenario {
+        let mut scenario = create_test_scenario();
+        scenario.id = format!("on_{}", rule_id);
+        scenario.triggers = vec![ScenarioTrigger {
+            trigger_type: TriggerType::RuleTriggered,
+            config: {
+                let mut config = HashMap::new();
+                config.insert("rule_id".to_string(), serde_json::json!(rule_id));
+                config
+            },
+            conditions: vec![],
+        }];
+        scenario
+    }
+
+    #[test]
+    fn test_handle_rule_triggered() {
+        let mut engine = ScenarioEngine::new();
+        engine
+            .register_scenario(rule_subscribed_scenario("overheat"))
+            .unwrap();
+        engine
+            .register_scenario(rule_subscribed_scenario("door_opened"))
+            .unwrap();
+
+        let context = EvaluationContext::new()
+            .with_telemetry("temperature".to_string(), serde_json::json!(42.0));
+
+        let executions = engine.handle_rule_triggered("overheat", &context).unwrap();
+        assert_eq!(executions.len(), 1);
+
+        let execution = engine.get_execution(&executions[0]).unwrap();
+        assert_eq!(execution.scenario_id, "on_overheat");
+        assert_eq!(execution.trigger, TriggerType::RuleTriggered);
+        assert_eq!(
+            execution.trigger_context.get("temperature"),
+            Some(&serde_json::json!(42.0))
+        );
+        assert_eq!(
+            execution.trigger_context.get("rule_id"),
+            Some(&serde_json::json!("overheat"))
+        );
+    }
+
+    fn event_scenario(id: &str, config: serde_json::Value) -> Scenario {
+        let mut scenario = create_test_scenario();
+        scenario.id = id.to_string();
+        scenario.triggers[0].config = serde_json::from_value(config).unwrap();
+        scenario
+    }
+
+    #[test]
+    fn test_evaluate_event_fires_matching_scenarios() {
+        let mut engine = ScenarioEngine::new();
+        engine
+            .register_scenario(event_scenario(
+                "door_alarm",
+                serde_json::json!({"event_type": "door_opened"}),
+            ))
+            .unwrap();
+        engine
+            .register_scenario(event_scenario(
+                "back_door_log",
+                serde_json::json!({"event_type": "door_opened", "device_id": "back-door"}),
+            ))
+            .unwrap();
+        engine
+            .register_scenario(event_scenario("audit_all", serde_json::json!({})))
+            .unwrap();
+        let mut guarded = event_scenario(
+            "forced_entry",
+            serde_json::json!({"event_type": "door_opened"}),
+        );
+        guarded.triggers[0].conditions = vec![TriggerCondition {
+            field: "forced".to_string(),
+            operator: "equals".to_string(),
+            value: serde_json::json!(true),
+        }];
+        engine.register_scenario(guarded).unwrap();
+
+        let event = DeviceEvent::new("front-door", "door_opened").with_data("forced", false.into());
+        let executions = engine.evaluate_event(&event).unwrap();
+        let mut fired: Vec<&str> = executions
+            .iter()
+            .map(|id| engine.get_execution(id).unwrap().scenario_id.as_str())
+            .collect();
+        fired.sort();
+        assert_eq!(fired, ["audit_all", "door_alarm"]);
+
+        let execution = engine.get_execution(&executions[0]).unwrap();
+        assert_eq!(execution.trigger, TriggerType::DeviceEvent);
+        assert_eq!(
+            execution.trigger_context.get("device_id"),
+            Some(&serde_json::json!("front-door"))
+        );
+
+        // Non-matching scenarios stay idle
+        for scenario_id in ["back_door_log", "forced_entry"] {
+            let scenario = engine.get_scenario(scenario_id).unwrap();
+            assert_eq!(scenario.state, ScenarioState::Active);
+            assert_eq!(scenario.execution_count, 0);
+        }
+    }
+
+    #[test]
+    fn test_event_index_follows_registration() {
+        let mut engine = ScenarioEngine::new();
+        engine
+            .register_scenario(event_scenario(
+                "on_motion",
+                serde_json::json!({"event_type": "motion"}),
+            ))
+            .unwrap();
+
+        // Re-registering under another event type replaces the index entry
+        engine
+            .register_scenario(event_scenario(
+                "on_motion",
+                serde_json::json!({"event_type": "smoke"}),
+            ))
+            .unwrap();
+        assert!(engine
+            .evaluate_event(&DeviceEvent::new("hall", "motion"))
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            engine
+                .evaluate_event(&DeviceEvent::new("hall", "smoke"))
+                .unwrap()
+                .len(),
+            1
+        );
+
+        engine.unregister_scenario("on_motion").unwrap();
+        assert!(engine.event_index.is_empty());
+    }
+
+    #[test]
+    fn test_handle_rule_triggered_skips_disabled() {
+        let mut engine = ScenarioEngine::new();
+        let mut scenario = rule_subscribed_scenario("overheat");
+        scenario.enabled = false;
+        engine.register_scenario(scenario).unwrap();
+
+        let executions = engine
+            .handle_rule_triggered("overheat", &EvaluationContext::new())
+            .unwrap();
+        assert!(executions.is_empty());
+    }
+
+    #[test]
+    fn test_handle_rule_triggered_group_scope() {
+        let mut engine = ScenarioEngine::new();
+        let mut scenario = rule_subscribed_scenario("overheat");
+        scenario.triggers[0]
+            .config
+            .insert("group_id".to_string(), serde_json::json!("kitchen"));
+        engine.register_scenario(scenario).unwrap();
+
+        let kitchen = vec!["sensor-1".to_string()];
+        let state = HashMap::from([("temperature".to_string(), serde_json::json!(42.0))]);
+
+        let outside = EvaluationContext::new()
+            .with_device_state("sensor-9".to_string(), state.clone())
+            .with_group("kitchen".to_string(), kitchen.clone());
+        assert!(engine
+            .handle_rule_triggered("overheat", &outside)
+            .unwrap()
+            .is_empty());
+
+        let inside = EvaluationContext::new()
+            .with_device_state("sensor-1".to_string(), state)
+            .with_group("kitchen".to_string(), kitchen);
+        assert_eq!(
+            engine
+                .handle_rule_triggered("overheat", &inside)
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_send_notification_templates_message() {
+        let mut engine = ScenarioEngine::new();
+        let mut scenario = create_test_scenario();
+        scenario.actions[0].parameters.insert(
+            "template".to_string(),
+            serde_json::json!("Temperature is {{temperature}}"),
+        );
+        engine.register_scenario(scenario.clone()).unwrap();
+
+        let mut context = HashMap::new();
+        context.insert("temperature".to_string(), serde_json::json!(31.5));
+        let execution_id = engine.trigger_scenario(&scenario.id, context).unwrap();
+        engine.execute_actions(&execution_id).await.unwrap();
+
+        let action = &engine
+            .get_execution(&execution_id)
+            .unwrap()
+            .actions_executed[0];
+        let result = action.result.as_ref().unwrap();
+        assert_eq!(result["channel"], "log");
+        assert_eq!(result["delivered"], true);
+        assert_eq!(result["message"], "Temperature is 31.5");
+        assert!(action.error.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_send_notification_falls_back_to_log() {
+        let mut engine = ScenarioEngine::new();
+        let mut scenario = create_test_scenario();
+        scenario.actions[0]
+            .parameters
+            .insert("channel".to_string(), serde_json::json!("webhook"));
+        engine.register_scenario(scenario.clone()).unwrap();
+
+        let execution_id = engine
+            .trigger_scenario(&scenario.id, HashMap::new())
+            .unwrap();
+        engine.execute_actions(&execution_id).await.unwrap();
+
+        let execution = engine.get_execution(&execution_id).unwrap();
+        assert_eq!(execution.state, ScenarioState::Completed);
+        let action = &execution.actions_executed[0];
+        let result = action.result.as_ref().unwrap();
+        assert_eq!(result["delivered"], false);
+        assert_eq!(result["fallback"], "log");
+        assert!(action.error.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_failed_action_recorded_in_last_result() {
+        let mut engine = ScenarioEngine::new();
+        let mut scenario = create_test_scenario();
+        let mut failing = scenario.actions[0].clone();
+        failing
+            .parameters
+            .insert("channel".to_string(), serde_json::json!("pager"));
+        scenario.actions.push(failing);
+        engine.register_scenario(scenario.clone()).unwrap();
+
+        let execution_id = engine
+            .trigger_scenario(&scenario.id, HashMap::new())
+            .unwrap();
+        engine.execute_actions(&execution_id).await.unwrap();
+
+        let scenario = engine.get_scenario(&scenario.id).unwrap();
+        assert_eq!(scenario.last_result_summary.as_deref(), Some("failed"));
+        let result = scenario.last_result.as_ref().unwrap();
+        assert_eq!(result.status, ScenarioState::Failed);
+        let error = result.error.as_deref().unwrap();
+        assert!(
+            error.starts_with("Action 2 (SendNotification) failed"),
+            "{}",
+            error
+        );
+        assert!(error.contains("pager"), "{}", error);
+        assert!(result.action_results[0].error.is_none());
+        assert!(result.action_results[1].error.is_some());
+
+        let execution = engine.get_execution(&execution_id).unwrap();
+        assert_eq!(execution.error.as_deref(), Some(error));
+        assert_eq!(Some(result.at), execution.completed_at);
+
+        let json = serde_json::to_value(scenario).unwrap();
+        assert_eq!(json["last_result"]["status"], "failed");
+        assert_eq!(json["last_result_summary"], "failed");
+    }
+
+    #[tokio::test]
+    async fn test_execution_lifecycle_emits_state_changes() {
+        let (sender, mut receiver) = tokio::sync::broadcast::channel(16);
+        let mut engine = ScenarioEngine::new();
+        let scenario = create_test_scenario();
+        engine.register_scenario(scenario.clone()).unwrap();
+
+        // Emitted before the sink is attached, so buffered
+        let execution_id = engine
+            .trigger_scenario(&scenario.id, HashMap::new())
+            .unwrap();
+        engine.set_event_sink(Arc::new(sender));
+        engine.execute_actions(&execution_id).await.unwrap();
+
+        let events: Vec<_> = std::iter::from_fn(|| receiver.try_recv().ok()).collect();
+        let transitions: Vec<_> = events
+            .iter()
+            .map(|e| (e.old.as_deref(), e.new.as_str()))
+            .collect();
+        assert_eq!(
+            transitions,
+            [(None, "executing"), (Some("executing"), "completed")]
+        );
+        assert!(events
+            .iter()
+            .all(|e| e.execution_id == execution_id && e.kind == ExecutionKind::Scenario));
+        assert!(events[0].at <= events[1].at);
+    }
+
+    fn watchdog_scenario(config: serde_json::Value) -> Scenario {
+        let mut scenario = create_test_scenario();
+        scenario.id = "watchdog".to_string();
+        scenario.triggers = vec![ScenarioTrigger {
+            trigger_type: TriggerType::Absence,
+            config: serde_json::from_value(config).unwrap(),
+            conditions: vec![],
+        }];
+        scenario
+    }
+
+    #[tokio::test]
+    async fn test_absence_trigger_fires_after_timeout_and_resets_on_activity() {
+        let mut engine = ScenarioEngine::new();
+        engine
+            .register_scenario(watchdog_scenario(
+                serde_json::json!({"device_id": "pump-1", "timeout_seconds": 300}),
+            ))
+            .unwrap();
+        let groups = HashMap::new();
+        let start = Utc::now();
+        let mut last_seen = HashMap::from([("pump-1".to_string(), start)]);
+        let at = |seconds| start + chrono::Duration::seconds(seconds);
+
+        // Within the timeout nothing fires
+        assert!(engine
+            .evaluate_absence(&last_seen, &groups, at(300))
+            .unwrap()
+            .is_empty());
+
+        let fired = engine
+            .evaluate_absence(&last_seen, &groups, at(301))
+            .unwrap();
+        assert_eq!(fired.len(), 1);
+        let execution = engine.get_execution(&fired[0]).unwrap();
+        assert_eq!(execution.trigger, TriggerType::Absence);
+        assert_eq!(execution.trigger_context["device_id"], "pump-1");
+        assert_eq!(execution.trigger_context["silent_seconds"], 301);
+        engine.execute_actions(&fired[0]).await.unwrap();
+
+        // One firing per silence
+        assert!(engine
+            .evaluate_absence(&last_seen, &groups, at(900))
+            .unwrap()
+            .is_empty());
+
+        // A heartbeat re-arms the trigger for the next silence
+        last_seen.insert("pump-1".to_string(), at(1000));
+        assert!(engine
+            .evaluate_absence(&last_seen, &groups, at(1200))
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            engine
+                .evaluate_absence(&last_seen, &groups, at(1301))
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_absence_trigger_watches_group_members() {
+        let mut engine = ScenarioEngine::new();
+        engine
+            .register_scenario(watchdog_scenario(
+                serde_json::json!({"group_id": "pumps", "timeout_seconds": 60}),
+            ))
+            .unwrap();
+        let now = Utc::now();
+        let groups = HashMap::from([(
+            "pumps".to_string(),
+            vec!["pump-1".to_string(), "pump-2".to_string()],
+        )]);
+        let last_seen = HashMap::from([
+            ("pump-1".to_string(), now - chrono::Duration::seconds(30)),
+            ("pump-2".to_string(), now - chrono::Duration::seconds(90)),
+            ("fan-1".to_string(), now - chrono::Duration::seconds(90)),
+        ]);
+
+        let fired = engine.evaluate_absence(&last_seen, &groups, now).unwrap();
+        assert_eq!(fired.len(), 1);
+        assert_eq!(
+            engine.get_execution(&fired[0]).unwrap().trigger_context["device_id"],
+            "pump-2"
+        );
+
+        // Absence triggers need a timeout and something to watch
+        for config in [
+            serde_json::json!({"device_id": "pump-1"}),
+            serde_json::json!({"device_id": "pump-1", "timeout_seconds": 0}),
+            serde_json::json!({"timeout_seconds": 60}),
+        ] {
+            let result = engine.register_scenario(watchdog_scenario(config));
+            assert!(matches!(result, Err(UaipError::InvalidConfiguration(_))));
+        }
+    }
 }
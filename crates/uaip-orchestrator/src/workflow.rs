@@ -4,11 +4,33 @@
 //! Supports sequential and parallel execution, conditional branching, and error handling.
 
 use chrono::{DateTime, Utc};
+use lazy_static::lazy_static;
+use prometheus::{register_histogram_vec, HistogramVec};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
 use uaip_core::error::{Result, UaipError};
+use uaip_core::version::Version;
 use uuid::Uuid;
 
+use crate::events::{ExecutionEventSink, ExecutionEvents, ExecutionKind, ExecutionStateChanged};
+
+lazy_static! {
+    /// Workflow step duration in seconds
+    pub static ref WORKFLOW_STEP_DURATION: HistogramVec = register_histogram_vec!(
+        "uaip_workflow_step_duration_seconds",
+        "Workflow step duration in seconds",
+        &["workflow", "step"],
+        vec![0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0, 10.0, 30.0, 60.0]
+    )
+    .unwrap();
+}
+
 /// Workflow execution state
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
@@ -41,6 +63,8 @@ pub enum StepState {
     Failed,
     /// Step was skipped
     Skipped,
+    /// Step was interrupted by cancellation
+    Cancelled,
 }
 
 /// Type of workflow step
@@ -59,6 +83,18 @@ pub enum StepType {
     Sequential,
     /// Loop over steps
     Loop,
+    /// Invoke another workflow and wait for it to finish
+    ///
+    /// Config: `workflow_id` (string), optional `input` mapping of child input key to
+    /// parent context key, and optional `output` mapping of parent context key to child
+    /// context key. Without an `output` mapping the child context is stored under the step ID.
+    SubWorkflow,
+    /// Run one of two lists of steps depending on a condition
+    ///
+    /// Config: `expression` (a condition, which may reference context values as `${key}`),
+    /// and `then` and `else` lists of steps, either of which may be omitted. The branch
+    /// taken is recorded in the step output and in the context as `<step_id>.branch`.
+    Branch,
 }
 
 /// A workflow step
@@ -99,6 +135,75 @@ fn default_on_error() -> String {
     "fail".to_string()
 }
 
+impl WorkflowStep {
+    /// The `then` and `else` steps of a branch step
+    pub fn branches(&self) -> Result<(Vec<WorkflowStep>, Vec<WorkflowStep>)> {
+        let parse = |key: &str| match self.config.get(key) {
+            None => Ok(Vec::new()),
+            Some(steps) => serde_json::from_value(steps.clone()).map_err(|e| {
+                UaipError::InvalidConfiguration(format!(
+                    "Branch step {} has invalid {} steps: {}",
+                    self.id, key, e
+                ))
+            }),
+        };
+        Ok((parse("then")?, parse("else")?))
+    }
+}
+
+/// Accepted `on_error` values
+pub const ON_ERROR_VALUES: &[&str] = &["fail", "skip", "retry"];
+
+/// A structural problem found by [`WorkflowEngine::validate_workflow`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WorkflowProblem {
+    /// More than one step (at any depth) uses this ID
+    DuplicateStepId(String),
+    /// A parallel, sequential or loop step without children
+    EmptyContainer(String),
+    /// A step whose `on_error` is not one of [`ON_ERROR_VALUES`]
+    InvalidOnError { step_id: String, on_error: String },
+    /// A sub-workflow step without a string `workflow_id`
+    MissingWorkflowId(String),
+    /// A branch step without a string `expression` or with unparseable `then`/`else` steps
+    InvalidBranch { step_id: String, reason: String },
+    /// A sub-workflow step referencing a workflow that is not registered
+    UnknownSubWorkflow {
+        step_id: String,
+        workflow_id: String,
+    },
+}
+
+impl std::fmt::Display for WorkflowProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::DuplicateStepId(step_id) => write!(f, "duplicate step ID {}", step_id),
+            Self::EmptyContainer(step_id) => write!(f, "step {} has no child steps", step_id),
+            Self::InvalidOnError { step_id, on_error } => write!(
+                f,
+                "step {} has invalid on_error '{}' (expected one of {})",
+                step_id,
+                on_error,
+                ON_ERROR_VALUES.join(", ")
+            ),
+            Self::MissingWorkflowId(step_id) => {
+                write!(f, "sub-workflow step {} is missing workflow_id", step_id)
+            }
+            Self::InvalidBranch { step_id, reason } => {
+                write!(f, "branch step {} is invalid: {}", step_id, reason)
+            }
+            Self::UnknownSubWorkflow {
+                step_id,
+                workflow_id,
+            } => write!(
+                f,
+                "sub-workflow step {} references unknown workflow {}",
+                step_id, workflow_id
+            ),
+        }
+    }
+}
+
 /// Workflow definition
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Workflow {
@@ -213,6 +318,46 @@ pub struct StepExecution {
     pub completed_at: Option<DateTime<Utc>>,
 }
 
+impl StepExecution {
+    /// Time from start to completion, if the step has completed
+    pub fn duration(&self) -> Option<chrono::Duration> {
+        self.completed_at
+            .map(|completed_at| completed_at - self.started_at)
+    }
+}
+
+/// Timing of one recorded step
+#[derive(Debug, Clone, PartialEq)]
+pub struct StepTiming {
+    pub step_id: String,
+    pub step_name: String,
+    pub state: StepState,
+    pub started_at: DateTime<Utc>,
+    /// `None` while the step has not completed
+    pub duration: Option<chrono::Duration>,
+}
+
+/// State threaded through step execution
+struct StepScope<'a> {
+    /// Registered workflows, for resolving sub-workflow steps
+    workflows: &'a HashMap<String, Workflow>,
+
+    /// Workflow IDs currently being executed (outermost first)
+    call_stack: Vec<String>,
+
+    /// Child executions started by sub-workflow steps
+    child_executions: Vec<WorkflowExecution>,
+
+    /// Cancelled when the execution is cancelled
+    cancel: CancellationToken,
+
+    /// Output recorded in the step history, by step ID
+    outputs: HashMap<String, HashMap<String, serde_json::Value>>,
+}
+
+/// Future returned by recursive step execution
+type StepFuture<'a> = Pin<Box<dyn Future<Output = Result<StepState>> + Send + 'a>>;
+
 /// Workflow engine for execution management
 pub struct WorkflowEngine {
     /// Registered workflows
@@ -220,6 +365,15 @@ pub struct WorkflowEngine {
 
     /// Active executions
     executions: HashMap<String, WorkflowExecution>,
+
+    /// Cancellation tokens of executions started by this engine
+    cancellations: HashMap<String, CancellationToken>,
+
+    /// Execution started for each (workflow ID, idempotency key)
+    idempotency_keys: HashMap<(String, String), String>,
+
+    /// Execution state transitions
+    events: ExecutionEvents,
 }
 
 impl WorkflowEngine {
@@ -228,9 +382,38 @@ impl WorkflowEngine {
         Self {
             workflows: HashMap::new(),
             executions: HashMap::new(),
+            cancellations: HashMap::new(),
+            idempotency_keys: HashMap::new(),
+            events: ExecutionEvents::default(),
         }
     }
 
+    /// Send execution state events to a sink
+    pub fn with_event_sink(mut self, sink: Arc<dyn ExecutionEventSink>) -> Self {
+        self.set_event_sink(sink);
+        self
+    }
+
+    /// Send execution state events to a sink, including those emitted so far
+    pub fn set_event_sink(&mut self, sink: Arc<dyn ExecutionEventSink>) {
+        self.events.set_sink(sink);
+    }
+
+    /// Move an execution to `state`, reporting the transition
+    fn transition(
+        events: &mut ExecutionEvents,
+        execution: &mut WorkflowExecution,
+        state: WorkflowState,
+    ) {
+        let old = std::mem::replace(&mut execution.state, state);
+        events.emit(ExecutionStateChanged::new(
+            ExecutionKind::Workflow,
+            &execution.id,
+            Some(&old),
+            &execution.state,
+        ));
+    }
+
     /// Register a workflow
     pub fn register_workflow(&mut self, workflow: Workflow) -> Result<()> {
         if !workflow.enabled {
@@ -240,10 +423,167 @@ impl WorkflowEngine {
             )));
         }
 
+        self.check_version(&workflow)?;
+
+        let problems = self.validate_workflow(&workflow);
+        if !problems.is_empty() {
+            let problems: Vec<String> = problems.iter().map(ToString::to_string).collect();
+            return Err(UaipError::InvalidConfiguration(format!(
+                "Workflow {} is invalid: {}",
+                workflow.id,
+                problems.join("; ")
+            )));
+        }
+
+        self.check_sub_workflow_cycle(&workflow)?;
+
         self.workflows.insert(workflow.id.clone(), workflow);
         Ok(())
     }
 
+    /// Check a workflow's structure against the registered workflows
+    ///
+    /// Reports every problem found rather than stopping at the first. Sub-workflow
+    /// steps may reference registered workflows or the workflow itself; the latter is
+    /// rejected separately as a cycle on registration.
+    pub fn validate_workflow(&self, workflow: &Workflow) -> Vec<WorkflowProblem> {
+        let mut problems = Vec::new();
+        let mut seen = HashSet::new();
+        let mut reported = HashSet::new();
+        let mut pending: Vec<Cow<WorkflowStep>> =
+            workflow.steps.iter().rev().map(Cow::Borrowed).collect();
+
+        while let Some(step) = pending.pop() {
+            if !seen.insert(step.id.clone()) && reported.insert(step.id.clone()) {
+                problems.push(WorkflowProblem::DuplicateStepId(step.id.clone()));
+            }
+
+            if !ON_ERROR_VALUES.contains(&step.on_error.as_str()) {
+                problems.push(WorkflowProblem::InvalidOnError {
+                    step_id: step.id.clone(),
+                    on_error: step.on_error.clone(),
+                });
+            }
+
+            match step.step_type {
+                StepType::Parallel | StepType::Sequential | StepType::Loop
+                    if step.children.is_empty() =>
+                {
+                    problems.push(WorkflowProblem::EmptyContainer(step.id.clone()));
+                }
+                StepType::SubWorkflow => {
+                    match step.config.get("workflow_id").and_then(|v| v.as_str()) {
+                        None => problems.push(WorkflowProblem::MissingWorkflowId(step.id.clone())),
+                        Some(workflow_id)
+                            if workflow_id != workflow.id
+                                && !self.workflows.contains_key(workflow_id) =>
+                        {
+                            problems.push(WorkflowProblem::UnknownSubWorkflow {
+                                step_id: step.id.clone(),
+                                workflow_id: workflow_id.to_string(),
+                            });
+                        }
+                        Some(_) => {}
+                    }
+                }
+                StepType::Branch => {
+                    let invalid = |reason: String| WorkflowProblem::InvalidBranch {
+                        step_id: step.id.clone(),
+                        reason,
+                    };
+                    if !step.config.get("expression").is_some_and(|v| v.is_string()) {
+                        problems.push(invalid("missing expression".to_string()));
+                    }
+                    match step.branches() {
+                        Ok((then_steps, else_steps)) => {
+                            if then_steps.is_empty() && else_steps.is_empty() {
+                                problems.push(WorkflowProblem::EmptyContainer(step.id.clone()));
+                            }
+                            let nested = then_steps.into_iter().chain(else_steps).rev();
+                            pending.extend(nested.map(Cow::Owned));
+                        }
+                        Err(e) => problems.push(invalid(e.to_string())),
+                    }
+                }
+                _ => {}
+            }
+
+            match step {
+                Cow::Borrowed(step) => {
+                    pending.extend(step.children.iter().rev().map(Cow::Borrowed))
+                }
+                Cow::Owned(step) => pending.extend(step.children.into_iter().rev().map(Cow::Owned)),
+            }
+        }
+
+        problems
+    }
+
+    /// Reject unparseable versions and downgrades of an already registered workflow
+    fn check_version(&self, workflow: &Workflow) -> Result<()> {
+        let version = Version::parse(&workflow.version).map_err(|e| {
+            UaipError::InvalidConfiguration(format!("Workflow {}: {}", workflow.id, e))
+        })?;
+
+        if let Some(existing) = self.workflows.get(&workflow.id) {
+            if let Ok(existing_version) = Version::parse(&existing.version) {
+                if version < existing_version {
+                    return Err(UaipError::InvalidConfiguration(format!(
+                        "Workflow {} version {} is older than registered version {}",
+                        workflow.id, version, existing_version
+                    )));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reject a workflow whose sub-workflow references lead back to itself
+    fn check_sub_workflow_cycle(&self, workflow: &Workflow) -> Result<()> {
+        let mut visited = HashSet::new();
+        let mut pending = Self::sub_workflow_refs(&workflow.steps);
+
+        while let Some(workflow_id) = pending.pop() {
+            if workflow_id == workflow.id {
+                return Err(UaipError::InvalidConfiguration(format!(
+                    "Workflow {} invokes itself through sub-workflow steps",
+                    workflow.id
+                )));
+            }
+
+            if !visited.insert(workflow_id.clone()) {
+                continue;
+            }
+
+            if let Some(child) = self.workflows.get(&workflow_id) {
+                pending.extend(Self::sub_workflow_refs(&child.steps));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Collect the workflow IDs referenced by sub-workflow steps (including nested steps)
+    fn sub_workflow_refs(steps: &[WorkflowStep]) -> Vec<String> {
+        let mut refs = Vec::new();
+        for step in steps {
+            if step.step_type == StepType::SubWorkflow {
+                if let Some(workflow_id) = step.config.get("workflow_id").and_then(|v| v.as_str()) {
+                    refs.push(workflow_id.to_string());
+                }
+            }
+            refs.extend(Self::sub_workflow_refs(&step.children));
+            if step.step_type == StepType::Branch {
+                if let Ok((then_steps, else_steps)) = step.branches() {
+                    refs.extend(Self::sub_workflow_refs(&then_steps));
+                    refs.extend(Self::sub_workflow_refs(&else_steps));
+                }
+            }
+        }
+        refs
+    }
+
     /// Unregister a workflow
     pub fn unregister_workflow(&mut self, workflow_id: &str) -> Result<()> {
         self.workflows
@@ -263,10 +603,14 @@ impl WorkflowEngine {
     }
 
     /// Start a workflow execution
+    ///
+    /// With an idempotency key, a running or paused execution already started for the same
+    /// workflow and key is returned instead of starting another.
     pub fn start_execution(
         &mut self,
         workflow_id: &str,
         input: HashMap<String, serde_json::Value>,
+        idempotency_key: Option<&str>,
     ) -> Result<String> {
         let workflow = self
             .workflows
@@ -280,6 +624,19 @@ impl WorkflowEngine {
             )));
         }
 
+        let key = idempotency_key.map(|key| (workflow_id.to_string(), key.to_string()));
+        if let Some(existing) = key
+            .as_ref()
+            .and_then(|key| self.idempotency_keys.get(key))
+            .filter(|id| {
+                self.executions.get(*id).is_some_and(|e| {
+                    e.state == WorkflowState::Running || e.state == WorkflowState::Paused
+                })
+            })
+        {
+            return Ok(existing.clone());
+        }
+
         let execution_id = Uuid::new_v4().to_string();
         let now = Utc::now();
 
@@ -298,10 +655,29 @@ impl WorkflowEngine {
             updated_at: now,
         };
 
+        self.events.emit(ExecutionStateChanged::new(
+            ExecutionKind::Workflow,
+            &execution_id,
+            None,
+            &execution.state,
+        ));
         self.executions.insert(execution_id.clone(), execution);
+        self.cancellations
+            .insert(execution_id.clone(), CancellationToken::new());
+        if let Some(key) = key {
+            self.idempotency_keys.insert(key, execution_id.clone());
+        }
         Ok(execution_id)
     }
 
+    /// Token that cancels an execution when triggered
+    ///
+    /// Cancelling the token aborts the running step even while the engine is busy
+    /// executing it; the execution is then marked cancelled.
+    pub fn cancellation_token(&self, execution_id: &str) -> Option<CancellationToken> {
+        self.cancellations.get(execution_id).cloned()
+    }
+
     /// Get execution by ID
     pub fn get_execution(&self, execution_id: &str) -> Option<&WorkflowExecution> {
         self.executions.get(execution_id)
@@ -326,10 +702,14 @@ impl WorkflowEngine {
             )));
         }
 
-        execution.state = WorkflowState::Cancelled;
+        Self::transition(&mut self.events, execution, WorkflowState::Cancelled);
         execution.completed_at = Some(Utc::now());
         execution.updated_at = Utc::now();
 
+        if let Some(token) = self.cancellations.get(execution_id) {
+            token.cancel();
+        }
+
         Ok(())
     }
 
@@ -347,7 +727,7 @@ impl WorkflowEngine {
             )));
         }
 
-        execution.state = WorkflowState::Paused;
+        Self::transition(&mut self.events, execution, WorkflowState::Paused);
         execution.updated_at = Utc::now();
 
         Ok(())
@@ -367,14 +747,18 @@ impl WorkflowEngine {
             )));
         }
 
-        execution.state = WorkflowState::Running;
+        Self::transition(&mut self.events, execution, WorkflowState::Running);
         execution.updated_at = Utc::now();
 
         Ok(())
     }
 
     /// Execute next step in a workflow
-    pub fn execute_next_step(&mut self, execution_id: &str) -> Result<StepState> {
+    ///
+    /// If the execution is cancelled while the step runs, the step is aborted and recorded
+    /// as cancelled. A step that returns an error fails the whole execution, which then
+    /// accepts no further steps.
+    pub async fn execute_next_step(&mut self, execution_id: &str) -> Result<StepState> {
         let workflow_id = {
             let execution = self.executions.get(execution_id).ok_or_else(|| {
                 UaipError::NotFound(format!("Execution not found: {}", execution_id))
@@ -399,14 +783,60 @@ impl WorkflowEngine {
 
         if execution.current_step_index >= workflow.steps.len() {
             // All steps completed
-            execution.state = WorkflowState::Completed;
+            Self::transition(&mut self.events, execution, WorkflowState::Completed);
             execution.completed_at = Some(Utc::now());
             execution.updated_at = Utc::now();
             return Ok(StepState::Completed);
         }
 
         let step = &workflow.steps[execution.current_step_index];
-        let step_result = Self::execute_step(step, execution)?;
+        let cancel = self
+            .cancellations
+            .get(execution_id)
+            .cloned()
+            .unwrap_or_default();
+        let started_at = Utc::now();
+        let mut scope = StepScope {
+            workflows: &self.workflows,
+            call_stack: vec![workflow_id.clone()],
+            child_executions: Vec::new(),
+            cancel: cancel.clone(),
+            outputs: HashMap::new(),
+        };
+        let step_result = if cancel.is_cancelled() {
+            StepState::Cancelled
+        } else {
+            match Self::execute_step(step, execution, &mut scope).await {
+                Ok(state) => state,
+                Err(e) => {
+                    execution.error = Some(e.to_string());
+                    execution.completed_at = Some(Utc::now());
+                    execution.updated_at = Utc::now();
+                    Self::transition(&mut self.events, execution, WorkflowState::Failed);
+                    return Err(e);
+                }
+            }
+        };
+
+        // Children run to completion inside the step; report their start and end
+        for child in &scope.child_executions {
+            let mut started = ExecutionStateChanged::new(
+                ExecutionKind::Workflow,
+                &child.id,
+                None,
+                &WorkflowState::Running,
+            );
+            started.at = child.started_at;
+            let mut finished = ExecutionStateChanged::new(
+                ExecutionKind::Workflow,
+                &child.id,
+                Some(&WorkflowState::Running),
+                &child.state,
+            );
+            finished.at = child.completed_at.unwrap_or(child.updated_at);
+            self.events.emit(started);
+            self.events.emit(finished);
+        }
 
         // Record step execution
         let step_exec = StepExecution {
@@ -415,60 +845,297 @@ impl WorkflowEngine {
             state: step_result.clone(),
             attempt: 1,
             input: execution.context.clone(),
-            output: None,
+            output: scope.outputs.remove(&step.id),
             error: None,
-            started_at: Utc::now(),
+            started_at,
             completed_at: Some(Utc::now()),
         };
 
+        if let Some(duration) = step_exec.duration().and_then(|d| d.to_std().ok()) {
+            WORKFLOW_STEP_DURATION
+                .with_label_values(&[workflow_id.as_str(), step.name.as_str()])
+                .observe(duration.as_secs_f64());
+        }
         execution.step_history.push(step_exec);
-        execution.current_step_index += 1;
         execution.updated_at = Utc::now();
 
+        if step_result == StepState::Cancelled {
+            Self::transition(&mut self.events, execution, WorkflowState::Cancelled);
+            execution.completed_at.get_or_insert_with(Utc::now);
+        } else {
+            execution.current_step_index += 1;
+        }
+
         // Check if all steps are completed
-        if execution.current_step_index >= workflow.steps.len() {
-            execution.state = WorkflowState::Completed;
+        if execution.state == WorkflowState::Running
+            && execution.current_step_index >= workflow.steps.len()
+        {
+            Self::transition(&mut self.events, execution, WorkflowState::Completed);
             execution.completed_at = Some(Utc::now());
         }
 
+        for child in scope.child_executions {
+            self.executions.insert(child.id.clone(), child);
+        }
+
         Ok(step_result)
     }
 
+    /// Duration of each recorded step of an execution, in execution order
+    pub fn step_timings(&self, execution_id: &str) -> Result<Vec<StepTiming>> {
+        let execution = self
+            .executions
+            .get(execution_id)
+            .ok_or_else(|| UaipError::NotFound(format!("Execution not found: {}", execution_id)))?;
+
+        Ok(execution
+            .step_history
+            .iter()
+            .map(|step| StepTiming {
+                step_id: step.step_id.clone(),
+                step_name: step.step_name.clone(),
+                state: step.state.clone(),
+                started_at: step.started_at,
+                duration: step.duration(),
+            })
+            .collect())
+    }
+
     /// Execute a single step
-    fn execute_step(step: &WorkflowStep, execution: &mut WorkflowExecution) -> Result<StepState> {
-        // Check condition if present
-        if let Some(condition) = &step.condition {
-            if !Self::evaluate_condition(condition, &execution.context)? {
-                return Ok(StepState::Skipped);
+    fn execute_step<'a>(
+        step: &'a WorkflowStep,
+        execution: &'a mut WorkflowExecution,
+        scope: &'a mut StepScope<'_>,
+    ) -> StepFuture<'a> {
+        Box::pin(async move {
+            if scope.cancel.is_cancelled() {
+                return Ok(StepState::Cancelled);
+            }
+
+            // Check condition if present
+            if let Some(condition) = &step.condition {
+                if !Self::evaluate_condition(condition, &execution.context)? {
+                    return Ok(StepState::Skipped);
+                }
+            }
+
+            match step.step_type {
+                StepType::Action => {
+                    // Execute action step
+                    Self::execute_action_step(step, execution)
+                }
+                StepType::Condition => {
+                    // Evaluate condition step
+                    Self::execute_condition_step(step, execution)
+                }
+                StepType::Delay => {
+                    // Wait, unless cancelled first
+                    Self::execute_delay_step(step, scope).await
+                }
+                StepType::Parallel => {
+                    // Execute child steps in parallel (simplified to run in order)
+                    Self::execute_parallel_step(step, execution, scope).await
+                }
+                StepType::Sequential => {
+                    // Execute child steps sequentially
+                    Self::execute_sequential_step(step, execution, scope).await
+                }
+                StepType::Loop => {
+                    // Execute child steps in a loop
+                    Self::execute_loop_step(step, execution, scope).await
+                }
+                StepType::SubWorkflow => {
+                    // Run another workflow to completion
+                    Self::execute_sub_workflow_step(step, execution, scope).await
+                }
+                StepType::Branch => {
+                    // Execute the steps of the branch the condition selects
+                    Self::execute_branch_step(step, execution, scope).await
+                }
             }
+        })
+    }
+
+    /// Execute a delay step
+    ///
+    /// Config: `duration_ms` (defaults to 0).
+    async fn execute_delay_step(step: &WorkflowStep, scope: &StepScope<'_>) -> Result<StepState> {
+        let duration = step
+            .config
+            .get("duration_ms")
+            .and_then(|v| v.as_u64())
+            .map(Duration::from_millis)
+            .unwrap_or_default();
+
+        tokio::select! {
+            biased;
+            _ = scope.cancel.cancelled() => Ok(StepState::Cancelled),
+            _ = tokio::time::sleep(duration) => Ok(StepState::Completed),
+        }
+    }
+
+    /// Execute a sub-workflow step
+    async fn execute_sub_workflow_step(
+        step: &WorkflowStep,
+        execution: &mut WorkflowExecution,
+        scope: &mut StepScope<'_>,
+    ) -> Result<StepState> {
+        let workflow_id = step
+            .config
+            .get("workflow_id")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| {
+                UaipError::InvalidConfiguration(format!(
+                    "Sub-workflow step {} is missing workflow_id",
+                    step.id
+                ))
+            })?;
+
+        if scope.call_stack.iter().any(|id| id == workflow_id) {
+            return Err(UaipError::InvalidState(format!(
+                "Recursive sub-workflow invocation: {} -> {}",
+                scope.call_stack.join(" -> "),
+                workflow_id
+            )));
         }
 
-        match step.step_type {
-            StepType::Action => {
-                // Execute action step
-                Self::execute_action_step(step, execution)
+        let workflows = scope.workflows;
+        let workflow = workflows
+            .get(workflow_id)
+            .ok_or_else(|| UaipError::NotFound(format!("Workflow not found: {}", workflow_id)))?;
+
+        // Map parent context into child input
+        let mut input = HashMap::new();
+        if let Some(mapping) = step.config.get("input").and_then(|v| v.as_object()) {
+            for (child_key, parent_key) in mapping {
+                let value = parent_key.as_str().and_then(|key| {
+                    execution
+                        .context
+                        .get(key)
+                        .or_else(|| execution.input.get(key))
+                });
+                if let Some(value) = value {
+                    input.insert(child_key.clone(), value.clone());
+                }
             }
-            StepType::Condition => {
-                // Evaluate condition step
-                Self::execute_condition_step(step, execution)
+        }
+
+        let now = Utc::now();
+        let mut child = WorkflowExecution {
+            id: Uuid::new_v4().to_string(),
+            workflow_id: workflow_id.to_string(),
+            state: WorkflowState::Running,
+            context: input.clone(),
+            input,
+            output: HashMap::new(),
+            step_history: Vec::new(),
+            current_step_index: 0,
+            error: None,
+            started_at: now,
+            completed_at: None,
+            updated_at: now,
+        };
+
+        let started = Instant::now();
+        let timeout = step.timeout_seconds.map(Duration::from_secs);
+
+        scope.call_stack.push(workflow_id.to_string());
+        let mut outcome = StepState::Completed;
+        let timed_out = || format!("Sub-workflow {} timed out", workflow_id);
+        for child_step in &workflow.steps {
+            if timeout.is_some_and(|t| started.elapsed() > t) {
+                child.error = Some(timed_out());
+                outcome = StepState::Failed;
+                break;
             }
-            StepType::Delay => {
-                // Delay step (would need async support in real implementation)
-                Ok(StepState::Completed)
+
+            // A child step that outlives the remaining budget is abandoned
+            let step_started = Utc::now();
+            let (result, expired) = match timeout {
+                Some(t) => match tokio::time::timeout(
+                    t.saturating_sub(started.elapsed()),
+                    Self::execute_step(child_step, &mut child, scope),
+                )
+                .await
+                {
+                    Ok(result) => (result, false),
+                    Err(_) => (Err(UaipError::Timeout(timed_out())), true),
+                },
+                None => (
+                    Self::execute_step(child_step, &mut child, scope).await,
+                    false,
+                ),
+            };
+            let (state, error) = match result {
+                Ok(state) => (state, None),
+                Err(e) => (StepState::Failed, Some(e.to_string())),
+            };
+
+            child.step_history.push(StepExecution {
+                step_id: child_step.id.clone(),
+                step_name: child_step.name.clone(),
+                state: state.clone(),
+                attempt: 1,
+                input: child.context.clone(),
+                output: scope.outputs.remove(&child_step.id),
+                error: error.clone(),
+                started_at: step_started,
+                completed_at: Some(Utc::now()),
+            });
+            if state == StepState::Cancelled {
+                outcome = StepState::Cancelled;
+                break;
             }
-            StepType::Parallel => {
-                // Execute child steps in parallel (simplified for sync implementation)
-                Self::execute_parallel_step(step, execution)
+            child.current_step_index += 1;
+
+            if expired {
+                child.error = Some(timed_out());
+                outcome = StepState::Failed;
+                break;
             }
-            StepType::Sequential => {
-                // Execute child steps sequentially
-                Self::execute_sequential_step(step, execution)
+            if state == StepState::Failed && child_step.on_error != "skip" {
+                child.error = error.or_else(|| Some(format!("Step {} failed", child_step.id)));
+                outcome = StepState::Failed;
+                break;
             }
-            StepType::Loop => {
-                // Execute child steps in a loop
-                Self::execute_loop_step(step, execution)
+        }
+        scope.call_stack.pop();
+
+        child.state = match outcome {
+            StepState::Failed => WorkflowState::Failed,
+            StepState::Cancelled => WorkflowState::Cancelled,
+            _ => WorkflowState::Completed,
+        };
+        child.completed_at = Some(Utc::now());
+        child.updated_at = Utc::now();
+
+        // Map child results back into the parent context
+        if outcome == StepState::Completed {
+            match step.config.get("output").and_then(|v| v.as_object()) {
+                Some(mapping) => {
+                    for (parent_key, child_key) in mapping {
+                        let value = child_key.as_str().and_then(|key| {
+                            child.output.get(key).or_else(|| child.context.get(key))
+                        });
+                        if let Some(value) = value {
+                            execution.context.insert(parent_key.clone(), value.clone());
+                        }
+                    }
+                }
+                None => {
+                    let child_context = serde_json::to_value(&child.context)?;
+                    execution.context.insert(step.id.clone(), child_context);
+                }
             }
         }
+
+        execution.context.insert(
+            format!("{}.execution_id", step.id),
+            serde_json::json!(child.id),
+        );
+        scope.child_executions.push(child);
+
+        Ok(outcome)
     }
 
     /// Execute an action step
@@ -506,14 +1173,18 @@ impl WorkflowEngine {
     }
 
     /// Execute a parallel step (simplified)
-    fn execute_parallel_step(
+    async fn execute_parallel_step(
         step: &WorkflowStep,
         execution: &mut WorkflowExecution,
+        scope: &mut StepScope<'_>,
     ) -> Result<StepState> {
         let mut all_completed = true;
 
         for child_step in &step.children {
-            let result = Self::execute_step(child_step, execution)?;
+            let result = Self::execute_step(child_step, execution, scope).await?;
+            if result == StepState::Cancelled {
+                return Ok(StepState::Cancelled);
+            }
             if result != StepState::Completed {
                 all_completed = false;
                 if step.on_error == "fail" {
@@ -530,13 +1201,62 @@ impl WorkflowEngine {
     }
 
     /// Execute a sequential step
-    fn execute_sequential_step(
+    async fn execute_sequential_step(
         step: &WorkflowStep,
         execution: &mut WorkflowExecution,
+        scope: &mut StepScope<'_>,
     ) -> Result<StepState> {
-        for child_step in &step.children {
-            let result = Self::execute_step(child_step, execution)?;
-            if result != StepState::Completed && step.on_error == "fail" {
+        Self::execute_in_order(&step.children, &step.on_error, execution, scope).await
+    }
+
+    /// Execute a branch step
+    async fn execute_branch_step(
+        step: &WorkflowStep,
+        execution: &mut WorkflowExecution,
+        scope: &mut StepScope<'_>,
+    ) -> Result<StepState> {
+        let expression = step
+            .config
+            .get("expression")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| {
+                UaipError::InvalidConfiguration(format!(
+                    "Branch step {} is missing expression",
+                    step.id
+                ))
+            })?;
+        let (then_steps, else_steps) = step.branches()?;
+
+        let (branch, steps) = if Self::evaluate_condition(expression, &execution.context)? {
+            ("then", then_steps)
+        } else {
+            ("else", else_steps)
+        };
+        execution
+            .context
+            .insert(format!("{}.branch", step.id), serde_json::json!(branch));
+        scope.outputs.insert(
+            step.id.clone(),
+            HashMap::from([("branch".to_string(), serde_json::json!(branch))]),
+        );
+
+        Self::execute_in_order(&steps, &step.on_error, execution, scope).await
+    }
+
+    /// Execute steps one after another, stopping at the first one that does not complete
+    /// if `on_error` is "fail"
+    async fn execute_in_order(
+        steps: &[WorkflowStep],
+        on_error: &str,
+        execution: &mut WorkflowExecution,
+        scope: &mut StepScope<'_>,
+    ) -> Result<StepState> {
+        for child_step in steps {
+            let result = Self::execute_step(child_step, execution, scope).await?;
+            if result == StepState::Cancelled {
+                return Ok(StepState::Cancelled);
+            }
+            if result != StepState::Completed && on_error == "fail" {
                 return Ok(StepState::Failed);
             }
         }
@@ -545,9 +1265,10 @@ impl WorkflowEngine {
     }
 
     /// Execute a loop step
-    fn execute_loop_step(
+    async fn execute_loop_step(
         step: &WorkflowStep,
         execution: &mut WorkflowExecution,
+        scope: &mut StepScope<'_>,
     ) -> Result<StepState> {
         let max_iterations = step
             .config
@@ -557,7 +1278,10 @@ impl WorkflowEngine {
 
         for _i in 0..max_iterations {
             for child_step in &step.children {
-                let result = Self::execute_step(child_step, execution)?;
+                let result = Self::execute_step(child_step, execution, scope).await?;
+                if result == StepState::Cancelled {
+                    return Ok(StepState::Cancelled);
+                }
                 if result != StepState::Completed && step.on_error == "fail" {
                     return Ok(StepState::Failed);
                 }
@@ -568,10 +1292,19 @@ impl WorkflowEngine {
     }
 
     /// Evaluate a condition expression (simplified)
+    ///
+    /// `${key}` placeholders are first replaced with context values (`null` if unset).
+    /// The result is either a comparison of two operands with `==`, `!=`, `>`, `>=`, `<`
+    /// or `<=`, `true`, `false`, or the name of a context variable holding a boolean.
+    /// Operands compare as numbers when both are numeric; otherwise only `==` and `!=`
+    /// apply, to the operands as text with surrounding quotes removed.
     fn evaluate_condition(
         condition: &str,
         context: &HashMap<String, serde_json::Value>,
     ) -> Result<bool> {
+        let condition = Self::interpolate(condition, context);
+        let condition = condition.trim();
+
         // Simple condition evaluation - in production would use a proper expression parser
         if condition == "true" {
             return Ok(true);
@@ -580,6 +1313,10 @@ impl WorkflowEngine {
             return Ok(false);
         }
 
+        if let Some((left, operator, right)) = Self::split_comparison(condition) {
+            return Ok(Self::compare(left, operator, right));
+        }
+
         // Check if variable exists and is truthy
         if let Some(value) = context.get(condition) {
             return Ok(value.as_bool().unwrap_or(false));
@@ -588,6 +1325,68 @@ impl WorkflowEngine {
         Ok(false)
     }
 
+    /// Replace `${key}` placeholders with context values; strings are inserted unquoted
+    fn interpolate(template: &str, context: &HashMap<String, serde_json::Value>) -> String {
+        let mut result = String::with_capacity(template.len());
+        let mut rest = template;
+
+        while let Some(start) = rest.find("${") {
+            let Some(len) = rest[start..].find('}') else {
+                break;
+            };
+            result.push_str(&rest[..start]);
+            let key = rest[start + 2..start + len].trim();
+            match context.get(key) {
+                Some(serde_json::Value::String(value)) => result.push_str(value),
+                Some(value) => result.push_str(&value.to_string()),
+                None => result.push_str("null"),
+            }
+            rest = &rest[start + len + 1..];
+        }
+
+        result.push_str(rest);
+        result
+    }
+
+    /// Split `left <operator> right` at the first comparison operator
+    fn split_comparison(expression: &str) -> Option<(&str, &str, &str)> {
+        const OPERATORS: [&str; 6] = ["==", "!=", ">=", "<=", ">", "<"];
+
+        let (index, operator) = OPERATORS
+            .iter()
+            .filter_map(|op| expression.find(op).map(|index| (index, *op)))
+            .min_by_key(|(index, op)| (*index, std::cmp::Reverse(op.len())))?;
+
+        Some((
+            expression[..index].trim(),
+            operator,
+            expression[index + operator.len()..].trim(),
+        ))
+    }
+
+    /// Compare two operands of a condition
+    fn compare(left: &str, operator: &str, right: &str) -> bool {
+        if let (Ok(left), Ok(right)) = (left.parse::<f64>(), right.parse::<f64>()) {
+            return match operator {
+                "==" => left == right,
+                "!=" => left != right,
+                ">" => left > right,
+                ">=" => left >= right,
+                "<" => left < right,
+                _ => left <= right,
+            };
+        }
+
+        fn unquote(operand: &str) -> &str {
+            operand.trim_matches(['"', '\''])
+        }
+        match operator {
+            "==" => unquote(left) == unquote(right),
+            "!=" => unquote(left) != unquote(right),
+            _ => false,
+        }
+    }
+
     /// Get all active executions
     pub fn get_active_executions(&self) -> Vec<&WorkflowExecution> {
         self.executions
@@ -607,6 +1406,10 @@ impl WorkflowEngine {
                 true // Keep running/paused executions
             }
         });
+        self.cancellations
+            .retain(|id, _| self.executions.contains_key(id));
+        self.idempotency_keys
+            .retain(|_, id| self.executions.contains_key(id));
     }
 }
 
@@ -683,6 +1486,27 @@ mod tests {
         assert!(engine.get_workflow(&workflow.id).is_none());
     }
 
+    #[test]
+    fn test_workflow_registration_version_check() {
+        let mut engine = WorkflowEngine::new();
+        let mut workflow = create_test_workflow();
+        workflow.version = "1.9.0".to_string();
+        engine.register_workflow(workflow.clone()).unwrap();
+
+        // 1.10.0 is newer than 1.9.0 even though it sorts lower as a string
+        workflow.version = "1.10.0".to_string();
+        assert!(engine.register_workflow(workflow.clone()).is_ok());
+
+        workflow.version = "1.9.5".to_string();
+        assert!(matches!(
+            engine.register_workflow(workflow.clone()),
+            Err(UaipError::InvalidConfiguration(_))
+        ));
+
+        workflow.version = "latest".to_string();
+        assert!(engine.register_workflow(workflow).is_err());
+    }
+
     #[test]
     fn test_start_execution() {
         let mut engine = WorkflowEngine::new();
@@ -691,7 +1515,7 @@ mod tests {
         engine.register_workflow(workflow.clone()).unwrap();
 
         let input = HashMap::new();
-        let execution_id = engine.start_execution(&workflow.id, input).unwrap();
+        let execution_id = engine.start_execution(&workflow.id, input, None).unwrap();
 
         let execution = engine.get_execution(&execution_id).unwrap();
         assert_eq!(execution.workflow_id, workflow.id);
@@ -699,18 +1523,18 @@ mod tests {
         assert_eq!(execution.current_step_index, 0);
     }
 
-    #[test]
-    fn test_execute_steps() {
+    #[tokio::test]
+    async fn test_execute_steps() {
         let mut engine = WorkflowEngine::new();
         let workflow = create_test_workflow();
 
         engine.register_workflow(workflow.clone()).unwrap();
 
         let input = HashMap::new();
-        let execution_id = engine.start_execution(&workflow.id, input).unwrap();
+        let execution_id = engine.start_execution(&workflow.id, input, None).unwrap();
 
         // Execute first step
-        let result = engine.execute_next_step(&execution_id).unwrap();
+        let result = engine.execute_next_step(&execution_id).await.unwrap();
         assert_eq!(result, StepState::Completed);
 
         let execution = engine.get_execution(&execution_id).unwrap();
@@ -718,7 +1542,7 @@ mod tests {
         assert_eq!(execution.step_history.len(), 1);
 
         // Execute second step
-        let result = engine.execute_next_step(&execution_id).unwrap();
+        let result = engine.execute_next_step(&execution_id).await.unwrap();
         assert_eq!(result, StepState::Completed);
 
         let execution = engine.get_execution(&execution_id).unwrap();
@@ -727,6 +1551,74 @@ mod tests {
         assert_eq!(execution.state, WorkflowState::Completed);
     }
 
+    #[test]
+    fn test_start_execution_idempotency_key() {
+        let mut engine = WorkflowEngine::new();
+        let workflow = create_test_workflow();
+        engine.register_workflow(workflow.clone()).unwrap();
+
+        let first = engine
+            .start_execution(&workflow.id, HashMap::new(), Some("job-1"))
+            .unwrap();
+        let repeated = engine
+            .start_execution(&workflow.id, HashMap::new(), Some("job-1"))
+            .unwrap();
+        assert_eq!(repeated, first);
+
+        let other = engine
+            .start_execution(&workflow.id, HashMap::new(), Some("job-2"))
+            .unwrap();
+        assert_ne!(other, first);
+        let unkeyed = engine
+            .start_execution(&workflow.id, HashMap::new(), None)
+            .unwrap();
+        assert_ne!(unkeyed, first);
+        assert_eq!(engine.get_active_executions().len(), 3);
+
+        // Once the original finishes, the key starts a new execution
+        engine.cancel_execution(&first).unwrap();
+        let restarted = engine
+            .start_execution(&workflow.id, HashMap::new(), Some("job-1"))
+            .unwrap();
+        assert_ne!(restarted, first);
+    }
+
+    #[tokio::test]
+    async fn test_step_timings_match_recorded_timestamps() {
+        let mut engine = WorkflowEngine::new();
+        let workflow = create_test_workflow();
+        engine.register_workflow(workflow.clone()).unwrap();
+
+        let execution_id = engine
+            .start_execution(&workflow.id, HashMap::new(), None)
+            .unwrap();
+        engine.execute_next_step(&execution_id).await.unwrap();
+        engine.execute_next_step(&execution_id).await.unwrap();
+
+        let start = Utc::now();
+        let execution = engine.get_execution_mut(&execution_id).unwrap();
+        execution.step_history[0].started_at = start;
+        execution.step_history[0].completed_at = Some(start + chrono::Duration::milliseconds(250));
+        execution.step_history[1].started_at = start + chrono::Duration::seconds(1);
+        execution.step_history[1].completed_at = None;
+
+        let timings = engine.step_timings(&execution_id).unwrap();
+        assert_eq!(timings.len(), 2);
+        assert_eq!(timings[0].step_id, workflow.steps[0].id);
+        assert_eq!(
+            timings[0].duration,
+            Some(chrono::Duration::milliseconds(250))
+        );
+        assert_eq!(timings[1].duration, None);
+
+        assert!(engine.step_timings("missing").is_err());
+
+        let recorded = WORKFLOW_STEP_DURATION
+            .with_label_values(&[workflow.id.as_str(), workflow.steps[0].name.as_str()])
+            .get_sample_count();
+        assert!(recorded >= 1);
+    }
+
     #[test]
     fn test_pause_resume_execution() {
         let mut engine = WorkflowEngine::new();
@@ -735,7 +1627,7 @@ mod tests {
         engine.register_workflow(workflow.clone()).unwrap();
 
         let input = HashMap::new();
-        let execution_id = engine.start_execution(&workflow.id, input).unwrap();
+        let execution_id = engine.start_execution(&workflow.id, input, None).unwrap();
 
         // Pause execution
         assert!(engine.pause_execution(&execution_id).is_ok());
@@ -756,7 +1648,7 @@ mod tests {
         engine.register_workflow(workflow.clone()).unwrap();
 
         let input = HashMap::new();
-        let execution_id = engine.start_execution(&workflow.id, input).unwrap();
+        let execution_id = engine.start_execution(&workflow.id, input, None).unwrap();
 
         // Cancel execution
         assert!(engine.cancel_execution(&execution_id).is_ok());
@@ -765,8 +1657,124 @@ mod tests {
         assert!(execution.completed_at.is_some());
     }
 
-    #[test]
-    fn test_conditional_step() {
+    /// Execution ID, old state and new state of a reported transition
+    type Transition = (String, Option<String>, String);
+
+    /// Engine reporting to a channel, and a way to drain what it reported
+    fn engine_with_events() -> (WorkflowEngine, impl FnMut() -> Vec<Transition>) {
+        let (sender, mut receiver) = tokio::sync::broadcast::channel::<ExecutionStateChanged>(64);
+        let drain = move || {
+            std::iter::from_fn(|| receiver.try_recv().ok())
+                .map(|e| (e.execution_id, e.old, e.new))
+                .collect()
+        };
+        (
+            WorkflowEngine::new().with_event_sink(Arc::new(sender)),
+            drain,
+        )
+    }
+
+    fn transition(id: &str, old: Option<&str>, new: &str) -> Transition {
+        (id.to_string(), old.map(str::to_string), new.to_string())
+    }
+
+    #[tokio::test]
+    async fn test_execution_lifecycle_emits_state_changes() {
+        let (mut engine, mut drain) = engine_with_events();
+        let workflow = create_test_workflow();
+        engine.register_workflow(workflow.clone()).unwrap();
+
+        let id = engine
+            .start_execution(&workflow.id, HashMap::new(), None)
+            .unwrap();
+        engine.pause_execution(&id).unwrap();
+        engine.resume_execution(&id).unwrap();
+        engine.execute_next_step(&id).await.unwrap();
+        engine.execute_next_step(&id).await.unwrap();
+        assert_eq!(
+            drain(),
+            [
+                transition(&id, None, "running"),
+                transition(&id, Some("running"), "paused"),
+                transition(&id, Some("paused"), "running"),
+                transition(&id, Some("running"), "completed"),
+            ]
+        );
+
+        let id = engine
+            .start_execution(&workflow.id, HashMap::new(), None)
+            .unwrap();
+        engine.cancel_execution(&id).unwrap();
+        assert_eq!(
+            drain(),
+            [
+                transition(&id, None, "running"),
+                transition(&id, Some("running"), "cancelled"),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_step_emits_failed_state() {
+        let (mut engine, mut drain) = engine_with_events();
+        let mut child = create_test_workflow();
+        child.id = "missing".to_string();
+        engine.register_workflow(child).unwrap();
+        let mut parent = create_test_workflow();
+        parent.steps = vec![sub_workflow_step("missing", serde_json::json!({}))];
+        engine.register_workflow(parent.clone()).unwrap();
+        engine.unregister_workflow("missing").unwrap();
+
+        let id = engine
+            .start_execution(&parent.id, HashMap::new(), None)
+            .unwrap();
+        assert!(engine.execute_next_step(&id).await.is_err());
+
+        let execution = engine.get_execution(&id).unwrap();
+        assert_eq!(execution.state, WorkflowState::Failed);
+        assert!(execution.error.is_some());
+        assert_eq!(
+            drain(),
+            [
+                transition(&id, None, "running"),
+                transition(&id, Some("running"), "failed"),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_step_error_fails_execution() {
+        let mut engine = WorkflowEngine::new();
+        let mut child = create_test_workflow();
+        child.id = "missing".to_string();
+        engine.register_workflow(child).unwrap();
+        let mut parent = create_test_workflow();
+        parent
+            .steps
+            .insert(0, sub_workflow_step("missing", serde_json::json!({})));
+        engine.register_workflow(parent.clone()).unwrap();
+        engine.unregister_workflow("missing").unwrap();
+
+        let id = engine
+            .start_execution(&parent.id, HashMap::new(), None)
+            .unwrap();
+        let error = engine.execute_next_step(&id).await.unwrap_err();
+
+        let execution = engine.get_execution(&id).unwrap();
+        assert_eq!(execution.state, WorkflowState::Failed);
+        assert_eq!(execution.error.as_deref(), Some(error.to_string().as_str()));
+        assert!(execution.completed_at.is_some());
+        assert_eq!(execution.current_step_index, 0);
+
+        // The remaining steps never run
+        assert!(matches!(
+            engine.execute_next_step(&id).await,
+            Err(UaipError::InvalidState(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_conditional_step() {
         let mut engine = WorkflowEngine::new();
 
         let mut workflow = create_test_workflow();
@@ -775,26 +1783,26 @@ mod tests {
         engine.register_workflow(workflow.clone()).unwrap();
 
         let input = HashMap::new();
-        let execution_id = engine.start_execution(&workflow.id, input).unwrap();
+        let execution_id = engine.start_execution(&workflow.id, input, None).unwrap();
 
         // Execute first step (should be skipped due to condition)
-        let result = engine.execute_next_step(&execution_id).unwrap();
+        let result = engine.execute_next_step(&execution_id).await.unwrap();
         assert_eq!(result, StepState::Skipped);
     }
 
-    #[test]
-    fn test_cleanup_executions() {
+    #[tokio::test]
+    async fn test_cleanup_executions() {
         let mut engine = WorkflowEngine::new();
         let workflow = create_test_workflow();
 
         engine.register_workflow(workflow.clone()).unwrap();
 
         let input = HashMap::new();
-        let execution_id = engine.start_execution(&workflow.id, input).unwrap();
+        let execution_id = engine.start_execution(&workflow.id, input, None).unwrap();
 
         // Complete the workflow
-        engine.execute_next_step(&execution_id).unwrap();
-        engine.execute_next_step(&execution_id).unwrap();
+        engine.execute_next_step(&execution_id).await.unwrap();
+        engine.execute_next_step(&execution_id).await.unwrap();
 
         // Verify execution exists
         assert!(engine.get_execution(&execution_id).is_some());
@@ -808,4 +1816,501 @@ mod tests {
         // Completed execution should be removed
         assert!(engine.get_execution(&execution_id).is_none());
     }
+
+    fn sub_workflow_step(workflow_id: &str, config: serde_json::Value) -> WorkflowStep {
+        let mut config: HashMap<String, serde_json::Value> =
+            serde_json::from_value(config).unwrap();
+        config.insert("workflow_id".to_string(), serde_json::json!(workflow_id));
+
+        WorkflowStep {
+            id: "invoke_child".to_string(),
+            name: "Invoke Child".to_string(),
+            step_type: StepType::SubWorkflow,
+            config,
+            children: vec![],
+            condition: None,
+            max_retries: 0,
+            timeout_seconds: Some(30),
+            on_error: "fail".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sub_workflow_step() {
+        let (mut engine, mut drain) = engine_with_events();
+
+        // Child copies its input into `last_action` via an action step
+        let mut child = create_test_workflow();
+        child.id = "child".to_string();
+        child.steps.truncate(1);
+        engine.register_workflow(child).unwrap();
+
+        let mut parent = create_test_workflow();
+        parent.id = "parent".to_string();
+        parent.steps = vec![sub_workflow_step(
+            "child",
+            serde_json::json!({
+                "input": {"target": "device_id"},
+                "output": {"child_action": "last_action", "child_target": "target"}
+            }),
+        )];
+        engine.register_workflow(parent).unwrap();
+
+        let mut input = HashMap::new();
+        input.insert("device_id".to_string(), serde_json::json!("sensor-7"));
+        let execution_id = engine.start_execution("parent", input, None).unwrap();
+
+        let result = engine.execute_next_step(&execution_id).await.unwrap();
+        assert_eq!(result, StepState::Completed);
+
+        let execution = engine.get_execution(&execution_id).unwrap();
+        assert_eq!(execution.state, WorkflowState::Completed);
+        assert_eq!(
+            execution.context.get("child_action"),
+            Some(&serde_json::json!("send_command"))
+        );
+        assert_eq!(
+            execution.context.get("child_target"),
+            Some(&serde_json::json!("sensor-7"))
+        );
+
+        // Child execution is tracked by the engine
+        let child_id = execution.context["invoke_child.execution_id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let child_execution = engine.get_execution(&child_id).unwrap();
+        assert_eq!(child_execution.workflow_id, "child");
+        assert_eq!(child_execution.state, WorkflowState::Completed);
+
+        // The child's lifecycle is reported before the parent completes
+        assert_eq!(
+            drain(),
+            [
+                transition(&execution_id, None, "running"),
+                transition(&child_id, None, "running"),
+                transition(&child_id, Some("running"), "completed"),
+                transition(&execution_id, Some("running"), "completed"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sub_workflow_self_reference_rejected() {
+        let mut engine = WorkflowEngine::new();
+
+        let mut workflow = create_test_workflow();
+        workflow.id = "recursive".to_string();
+        workflow.steps = vec![sub_workflow_step("recursive", serde_json::json!({}))];
+
+        let result = engine.register_workflow(workflow);
+        assert!(matches!(result, Err(UaipError::InvalidConfiguration(_))));
+        assert!(engine.get_workflow("recursive").is_none());
+    }
+
+    #[test]
+    fn test_sub_workflow_indirect_cycle_rejected() {
+        let mut engine = WorkflowEngine::new();
+
+        let mut second = create_test_workflow();
+        second.id = "second".to_string();
+        engine.register_workflow(second.clone()).unwrap();
+
+        let mut first = create_test_workflow();
+        first.id = "first".to_string();
+        first.steps = vec![sub_workflow_step("second", serde_json::json!({}))];
+        engine.register_workflow(first).unwrap();
+
+        // Updating the child to call back into its parent closes the cycle
+        second.steps = vec![sub_workflow_step("first", serde_json::json!({}))];
+        assert!(engine.register_workflow(second).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_sub_workflow_missing_child() {
+        let mut engine = WorkflowEngine::new();
+
+        let mut child = create_test_workflow();
+        child.id = "missing".to_string();
+        engine.register_workflow(child).unwrap();
+
+        let mut parent = create_test_workflow();
+        parent.steps = vec![sub_workflow_step("missing", serde_json::json!({}))];
+        engine.register_workflow(parent.clone()).unwrap();
+
+        // The child can be unregistered after its parent was validated
+        engine.unregister_workflow("missing").unwrap();
+
+        let execution_id = engine
+            .start_execution(&parent.id, HashMap::new(), None)
+            .unwrap();
+        assert!(engine.execute_next_step(&execution_id).await.is_err());
+    }
+
+    fn container_step(id: &str, step_type: StepType, children: Vec<WorkflowStep>) -> WorkflowStep {
+        WorkflowStep {
+            id: id.to_string(),
+            name: id.to_string(),
+            step_type,
+            config: HashMap::new(),
+            children,
+            condition: None,
+            max_retries: 0,
+            timeout_seconds: None,
+            on_error: "fail".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_validate_duplicate_step_ids() {
+        let engine = WorkflowEngine::new();
+        let mut workflow = create_test_workflow();
+        let nested = workflow.steps[0].clone();
+        workflow.steps[1].id = "step_1".to_string();
+        workflow
+            .steps
+            .push(container_step("group", StepType::Sequential, vec![nested]));
+
+        // Reported once however often the ID repeats, including in nested steps
+        assert_eq!(
+            engine.validate_workflow(&workflow),
+            [WorkflowProblem::DuplicateStepId("step_1".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_validate_empty_container_steps() {
+        let engine = WorkflowEngine::new();
+        let mut workflow = create_test_workflow();
+        workflow.steps = vec![
+            container_step("repeat", StepType::Loop, vec![]),
+            container_step("fan_out", StepType::Parallel, vec![]),
+            container_step(
+                "outer",
+                StepType::Sequential,
+                vec![container_step("inner", StepType::Sequential, vec![])],
+            ),
+        ];
+
+        assert_eq!(
+            engine.validate_workflow(&workflow),
+            [
+                WorkflowProblem::EmptyContainer("repeat".to_string()),
+                WorkflowProblem::EmptyContainer("fan_out".to_string()),
+                WorkflowProblem::EmptyContainer("inner".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_on_error_values() {
+        let engine = WorkflowEngine::new();
+        let mut workflow = create_test_workflow();
+        workflow.steps[0].on_error = "skip".to_string();
+        workflow.steps[1].on_error = "ignore".to_string();
+
+        assert_eq!(
+            engine.validate_workflow(&workflow),
+            [WorkflowProblem::InvalidOnError {
+                step_id: "step_2".to_string(),
+                on_error: "ignore".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_validate_sub_workflow_references() {
+        let mut engine = WorkflowEngine::new();
+        let mut child = create_test_workflow();
+        child.id = "child".to_string();
+        engine.register_workflow(child).unwrap();
+
+        let mut missing_id = sub_workflow_step("child", serde_json::json!({}));
+        missing_id.id = "no_target".to_string();
+        missing_id.config.remove("workflow_id");
+        let mut workflow = create_test_workflow();
+        workflow.steps = vec![
+            sub_workflow_step("child", serde_json::json!({})),
+            container_step(
+                "group",
+                StepType::Parallel,
+                vec![sub_workflow_step("ghost", serde_json::json!({}))],
+            ),
+            missing_id,
+        ];
+
+        assert_eq!(
+            engine.validate_workflow(&workflow),
+            [
+                WorkflowProblem::DuplicateStepId("invoke_child".to_string()),
+                WorkflowProblem::UnknownSubWorkflow {
+                    step_id: "invoke_child".to_string(),
+                    workflow_id: "ghost".to_string(),
+                },
+                WorkflowProblem::MissingWorkflowId("no_target".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_registration_reports_all_problems() {
+        let mut engine = WorkflowEngine::new();
+        let mut workflow = create_test_workflow();
+        workflow.steps[1].id = "step_1".to_string();
+        workflow.steps[1].on_error = "ignore".to_string();
+        workflow
+            .steps
+            .push(container_step("repeat", StepType::Loop, vec![]));
+
+        let Err(UaipError::InvalidConfiguration(message)) =
+            engine.register_workflow(workflow.clone())
+        else {
+            panic!("invalid workflow was registered");
+        };
+        assert!(message.contains("duplicate step ID step_1"), "{}", message);
+        assert!(message.contains("invalid on_error 'ignore'"), "{}", message);
+        assert!(
+            message.contains("step repeat has no child steps"),
+            "{}",
+            message
+        );
+        assert!(engine.get_workflow(&workflow.id).is_none());
+    }
+
+    fn long_delay_step() -> WorkflowStep {
+        WorkflowStep {
+            id: "wait".to_string(),
+            name: "Wait".to_string(),
+            step_type: StepType::Delay,
+            config: HashMap::from([("duration_ms".to_string(), serde_json::json!(60_000))]),
+            children: vec![],
+            condition: None,
+            max_retries: 0,
+            timeout_seconds: None,
+            on_error: "fail".to_string(),
+        }
+    }
+
+    /// Cancel `token` shortly after the current step starts
+    fn cancel_soon(token: CancellationToken) {
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            token.cancel();
+        });
+    }
+
+    #[tokio::test]
+    async fn test_cancel_interrupts_delay_step() {
+        let mut engine = WorkflowEngine::new();
+        let mut workflow = create_test_workflow();
+        workflow.steps.insert(0, long_delay_step());
+        engine.register_workflow(workflow.clone()).unwrap();
+
+        let execution_id = engine
+            .start_execution(&workflow.id, HashMap::new(), None)
+            .unwrap();
+        cancel_soon(engine.cancellation_token(&execution_id).unwrap());
+
+        let started = Instant::now();
+        let result = tokio::time::timeout(
+            Duration::from_secs(5),
+            engine.execute_next_step(&execution_id),
+        )
+        .await
+        .expect("cancellation should abort the delay")
+        .unwrap();
+        assert_eq!(result, StepState::Cancelled);
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        let execution = engine.get_execution(&execution_id).unwrap();
+        assert_eq!(execution.state, WorkflowState::Cancelled);
+        assert_eq!(execution.step_history.len(), 1);
+        assert_eq!(execution.step_history[0].state, StepState::Cancelled);
+
+        // Nothing runs after cancellation
+        assert!(engine.execute_next_step(&execution_id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_sub_workflow_timeout_interrupts_running_child_step() {
+        let mut engine = WorkflowEngine::new();
+
+        let mut child = create_test_workflow();
+        child.id = "child".to_string();
+        child.steps.insert(0, long_delay_step());
+        engine.register_workflow(child).unwrap();
+
+        let mut invoke = sub_workflow_step("child", serde_json::json!({}));
+        invoke.timeout_seconds = Some(1);
+        let mut parent = create_test_workflow();
+        parent.id = "parent".to_string();
+        parent.steps = vec![invoke];
+        engine.register_workflow(parent).unwrap();
+
+        let execution_id = engine
+            .start_execution("parent", HashMap::new(), None)
+            .unwrap();
+        let result = tokio::time::timeout(
+            Duration::from_secs(5),
+            engine.execute_next_step(&execution_id),
+        )
+        .await
+        .expect("the sub-workflow timeout should abort the delay")
+        .unwrap();
+        assert_eq!(result, StepState::Failed);
+
+        let execution = engine.get_execution(&execution_id).unwrap();
+        let child_id = execution.context["invoke_child.execution_id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let child = engine.get_execution(&child_id).unwrap();
+        assert_eq!(child.state, WorkflowState::Failed);
+        assert_eq!(child.error.as_deref(), Some("Sub-workflow child timed out"));
+        assert_eq!(child.step_history.len(), 1);
+        assert_eq!(child.step_history[0].state, StepState::Failed);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_propagates_to_sub_workflow() {
+        let mut engine = WorkflowEngine::new();
+
+        let mut child = create_test_workflow();
+        child.id = "child".to_string();
+        child.steps.insert(0, long_delay_step());
+        engine.register_workflow(child).unwrap();
+
+        let mut parent = create_test_workflow();
+        parent.id = "parent".to_string();
+        parent.steps = vec![sub_workflow_step("child", serde_json::json!({}))];
+        engine.register_workflow(parent).unwrap();
+
+        let execution_id = engine
+            .start_execution("parent", HashMap::new(), None)
+            .unwrap();
+        cancel_soon(engine.cancellation_token(&execution_id).unwrap());
+
+        let result = tokio::time::timeout(
+            Duration::from_secs(5),
+            engine.execute_next_step(&execution_id),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(result, StepState::Cancelled);
+
+        let execution = engine.get_execution(&execution_id).unwrap();
+        let child_id = execution.context["invoke_child.execution_id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let child_execution = engine.get_execution(&child_id).unwrap();
+        assert_eq!(child_execution.state, WorkflowState::Cancelled);
+        assert_eq!(child_execution.step_history.len(), 1);
+        assert_eq!(child_execution.step_history[0].state, StepState::Cancelled);
+    }
+
+    fn action_step(id: &str, action_type: &str) -> serde_json::Value {
+        serde_json::json!({
+            "id": id,
+            "name": id,
+            "step_type": "action",
+            "config": {"action_type": action_type},
+            "condition": null,
+            "timeout_seconds": null,
+        })
+    }
+
+    fn branch_workflow(expression: &str) -> Workflow {
+        let mut branch = container_step("check_temperature", StepType::Branch, vec![]);
+        branch.config = HashMap::from([
+            ("expression".to_string(), serde_json::json!(expression)),
+            (
+                "then".to_string(),
+                serde_json::json!([action_step("cool", "fan_on")]),
+            ),
+            (
+                "else".to_string(),
+                serde_json::json!([action_step("idle", "fan_off")]),
+            ),
+        ]);
+
+        let mut workflow = create_test_workflow();
+        workflow.steps = vec![branch];
+        workflow
+    }
+
+    /// Run the branch workflow with `temperature` in the context
+    async fn run_branch(expression: &str, temperature: f64) -> WorkflowExecution {
+        let mut engine = WorkflowEngine::new();
+        let workflow = branch_workflow(expression);
+        engine.register_workflow(workflow.clone()).unwrap();
+
+        let execution_id = engine
+            .start_execution(&workflow.id, HashMap::new(), None)
+            .unwrap();
+        engine
+            .get_execution_mut(&execution_id)
+            .unwrap()
+            .context
+            .insert("temperature".to_string(), serde_json::json!(temperature));
+
+        let result = engine.execute_next_step(&execution_id).await.unwrap();
+        assert_eq!(result, StepState::Completed);
+        engine.get_execution(&execution_id).unwrap().clone()
+    }
+
+    #[tokio::test]
+    async fn test_branch_step_runs_exactly_one_branch() {
+        for (temperature, branch, action) in [(35.0, "then", "fan_on"), (20.0, "else", "fan_off")] {
+            let execution = run_branch("${temperature} > 30", temperature).await;
+
+            assert_eq!(execution.state, WorkflowState::Completed);
+            assert_eq!(execution.context["last_action"], action);
+            assert_eq!(execution.context["check_temperature.branch"], branch);
+            let output = execution.step_history[0].output.as_ref().unwrap();
+            assert_eq!(output["branch"], branch);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_branch_condition_interpolates_context() {
+        // Unset variables interpolate as null, which never compares as a number
+        let execution = run_branch("${humidity} < 50", 20.0).await;
+        assert_eq!(execution.context["check_temperature.branch"], "else");
+
+        let execution = run_branch("'${temperature}' == '35.0'", 35.0).await;
+        assert_eq!(execution.context["check_temperature.branch"], "then");
+        let execution = run_branch("${ temperature } != 35", 35.0).await;
+        assert_eq!(execution.context["check_temperature.branch"], "else");
+    }
+
+    #[test]
+    fn test_validate_branch_steps() {
+        let engine = WorkflowEngine::new();
+        let mut workflow = branch_workflow("true");
+        let mut empty = container_step("empty", StepType::Branch, vec![]);
+        empty.config = HashMap::from([
+            ("then".to_string(), serde_json::json!([])),
+            ("else".to_string(), serde_json::json!("not steps")),
+        ]);
+        workflow.steps.push(empty);
+        // Steps nested in branches share the workflow's step IDs
+        workflow
+            .steps
+            .push(container_step("cool", StepType::Sequential, vec![]));
+
+        let problems = engine.validate_workflow(&workflow);
+        assert_eq!(problems.len(), 4, "{:?}", problems);
+        assert!(problems.contains(&WorkflowProblem::DuplicateStepId("cool".to_string())));
+        assert!(problems.contains(&WorkflowProblem::EmptyContainer("cool".to_string())));
+        let invalid: Vec<&str> = problems
+            .iter()
+            .filter_map(|p| match p {
+                WorkflowProblem::InvalidBranch { step_id, .. } => Some(step_id.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(invalid, ["empty", "empty"]);
+    }
 }
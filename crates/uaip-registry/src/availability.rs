@@ -0,0 +1,128 @@
+//! Device availability from status history
+//!
+//! Status changes are recorded in `device_status_history` by a database trigger.
+//! Availability is the share of a window a device spent online.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::FromRow;
+
+use crate::models::DeviceStatus;
+
+/// A recorded device status change
+#[derive(Debug, Clone, PartialEq, FromRow)]
+pub struct StatusTransition {
+    pub status: DeviceStatus,
+    pub changed_at: DateTime<Utc>,
+}
+
+impl StatusTransition {
+    pub fn new(status: DeviceStatus, changed_at: DateTime<Utc>) -> Self {
+        Self { status, changed_at }
+    }
+}
+
+/// Availability over a time window
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Availability {
+    /// Percentage of observed time spent online (0-100)
+    pub uptime_percent: f64,
+    /// Seconds spent online
+    pub online_seconds: i64,
+    /// Seconds counted towards availability
+    pub observed_seconds: i64,
+}
+
+/// Compute availability over `[start, end)` from transitions sorted by time
+///
+/// The latest transition at or before `start` gives the status at the start of
+/// the window; time before the first known transition is not counted. Planned
+/// downtime (maintenance or deactivation) is excluded from the observed time.
+/// Returns `None` if no time in the window could be observed.
+pub fn availability(
+    transitions: &[StatusTransition],
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Option<Availability> {
+    let mut online_ms = 0;
+    let mut observed_ms = 0;
+
+    for (i, transition) in transitions.iter().enumerate() {
+        let from = transition.changed_at.max(start);
+        let to = transitions
+            .get(i + 1)
+            .map_or(end, |next| next.changed_at.min(end));
+        if to <= from {
+            continue;
+        }
+
+        let span = (to - from).num_milliseconds();
+        match transition.status {
+            DeviceStatus::Online => {
+                online_ms += span;
+                observed_ms += span;
+            }
+            DeviceStatus::Offline | DeviceStatus::Error => observed_ms += span,
+            DeviceStatus::Maintenance | DeviceStatus::Deactivated => {}
+        }
+    }
+
+    if observed_ms == 0 {
+        return None;
+    }
+
+    Some(Availability {
+        uptime_percent: online_ms as f64 * 100.0 / observed_ms as f64,
+        online_seconds: online_ms / 1000,
+        observed_seconds: observed_ms / 1000,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    fn at(hour: i64) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap() + Duration::hours(hour)
+    }
+
+    #[test]
+    fn test_availability_from_transitions() {
+        let transitions = vec![
+            // Before the window: the device enters it online
+            StatusTransition::new(DeviceStatus::Online, at(-5)),
+            StatusTransition::new(DeviceStatus::Offline, at(6)),
+            StatusTransition::new(DeviceStatus::Online, at(9)),
+            StatusTransition::new(DeviceStatus::Error, at(21)),
+            StatusTransition::new(DeviceStatus::Online, at(22)),
+        ];
+
+        // 24h window: online for 6 + 12 + 2 hours
+        let result = availability(&transitions, at(0), at(24)).unwrap();
+        assert_eq!(result.online_seconds, 20 * 3600);
+        assert_eq!(result.observed_seconds, 24 * 3600);
+        assert!((result.uptime_percent - 83.333).abs() < 0.001);
+
+        // A window inside one outage
+        let result = availability(&transitions, at(7), at(8)).unwrap();
+        assert_eq!(result.uptime_percent, 0.0);
+    }
+
+    #[test]
+    fn test_availability_counts_only_known_and_unplanned_time() {
+        // Registered halfway through the window, then taken down for maintenance
+        let transitions = vec![
+            StatusTransition::new(DeviceStatus::Offline, at(12)),
+            StatusTransition::new(DeviceStatus::Online, at(14)),
+            StatusTransition::new(DeviceStatus::Maintenance, at(20)),
+        ];
+
+        let result = availability(&transitions, at(0), at(24)).unwrap();
+        assert_eq!(result.observed_seconds, 8 * 3600);
+        assert_eq!(result.uptime_percent, 75.0);
+
+        assert!(availability(&[], at(0), at(24)).is_none());
+        assert!(availability(&transitions, at(21), at(24)).is_none());
+    }
+}
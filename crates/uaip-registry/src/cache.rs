@@ -1,8 +1,13 @@
 //! Redis caching layer for device states
+//!
+//! The database remains the source of truth. By default the cache fails open: when Redis
+//! is unreachable, reads are logged and reported as misses and writes are skipped, so an
+//! outage costs performance but not correctness.
 
 use chrono::{DateTime, Utc};
-use redis::{aio::ConnectionManager, AsyncCommands};
+use redis::{aio::ConnectionManager, AsyncCommands, RedisError};
 use serde::{Deserialize, Serialize};
+use tracing::warn;
 
 use crate::models::{Device, DeviceStatus};
 use uaip_core::error::{UaipError, UaipResult};
@@ -16,6 +21,8 @@ pub struct CacheConfig {
     pub status_ttl: u64,
     /// Key prefix for cache entries
     pub key_prefix: String,
+    /// How Redis errors are surfaced to callers
+    pub failure_policy: CacheFailurePolicy,
 }
 
 impl Default for CacheConfig {
@@ -24,10 +31,49 @@ impl Default for CacheConfig {
             device_ttl: 300, // 5 minutes
             status_ttl: 60,  // 1 minute
             key_prefix: "uaip:".to_string(),
+            failure_policy: CacheFailurePolicy::default(),
         }
     }
 }
 
+/// How the cache behaves when Redis fails
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CacheFailurePolicy {
+    /// Log the error; reads become misses and writes are skipped
+    #[default]
+    FailOpen,
+    /// Return the error to the caller
+    FailClosed,
+}
+
+impl CacheFailurePolicy {
+    /// Outcome of a failed read of `key`
+    pub fn read_failed<T>(self, key: &str, error: RedisError) -> UaipResult<Option<T>> {
+        match self {
+            Self::FailOpen => {
+                warn!(key, "Cache read failed, treating as a miss: {}", error);
+                Ok(None)
+            }
+            Self::FailClosed => Err(redis_error(error)),
+        }
+    }
+
+    /// Outcome of a failed write of `key`
+    pub fn write_failed(self, key: &str, error: RedisError) -> UaipResult<()> {
+        match self {
+            Self::FailOpen => {
+                warn!(key, "Cache write failed, skipping: {}", error);
+                Ok(())
+            }
+            Self::FailClosed => Err(redis_error(error)),
+        }
+    }
+}
+
+fn redis_error(error: RedisError) -> UaipError {
+    UaipError::DatabaseError(format!("Redis error: {}", error))
+}
+
 /// Cached device state (lighter than full Device model)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CachedDeviceState {
@@ -64,12 +110,14 @@ impl CacheService {
         let key = format!("{}device:{}", self.config.key_prefix, device.device_id);
         let value = serde_json::to_string(device).map_err(UaipError::SerializationError)?;
 
-        self.connection
+        match self
+            .connection
             .set_ex::<_, _, ()>(&key, value, self.config.device_ttl)
             .await
-            .map_err(|e| UaipError::DatabaseError(format!("Redis error: {}", e)))?;
-
-        Ok(())
+        {
+            Ok(()) => Ok(()),
+            Err(e) => self.config.failure_policy.write_failed(&key, e),
+        }
     }
 
     /// Get cached device
@@ -82,11 +130,10 @@ impl CacheService {
     pub async fn get_device(&mut self, device_id: &str) -> UaipResult<Option<Device>> {
         let key = format!("{}device:{}", self.config.key_prefix, device_id);
 
-        let value: Option<String> = self
-            .connection
-            .get(&key)
-            .await
-            .map_err(|e| UaipError::DatabaseError(format!("Redis error: {}", e)))?;
+        let value: Option<String> = match self.connection.get(&key).await {
+            Ok(value) => value,
+            Err(e) => return self.config.failure_policy.read_failed(&key, e),
+        };
 
         match value {
             Some(json) => {
@@ -124,12 +171,45 @@ impl CacheService {
 
         let value = serde_json::to_string(&state).map_err(UaipError::SerializationError)?;
 
-        self.connection
+        match self
+            .connection
             .set_ex::<_, _, ()>(&key, value, self.config.status_ttl)
             .await
-            .map_err(|e| UaipError::DatabaseError(format!("Redis error: {}", e)))?;
+        {
+            Ok(()) => Ok(()),
+            Err(e) => self.config.failure_policy.write_failed(&key, e),
+        }
+    }
 
-        Ok(())
+    /// Cache several device statuses in one pipelined round-trip
+    ///
+    /// # Arguments
+    /// * `states` - Device statuses to cache
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub async fn cache_device_statuses_batch(
+        &mut self,
+        states: &[CachedDeviceState],
+    ) -> UaipResult<()> {
+        if states.is_empty() {
+            return Ok(());
+        }
+
+        let mut pipe = redis::pipe();
+        for state in states {
+            let key = format!("{}status:{}", self.config.key_prefix, state.device_id);
+            let value = serde_json::to_string(state).map_err(UaipError::SerializationError)?;
+            pipe.set_ex(key, value, self.config.status_ttl).ignore();
+        }
+
+        match pipe.query_async::<()>(&mut self.connection).await {
+            Ok(()) => Ok(()),
+            Err(e) => {
+                let key = format!("{}status:*", self.config.key_prefix);
+                self.config.failure_policy.write_failed(&key, e)
+            }
+        }
     }
 
     /// Get cached device status
@@ -145,11 +225,10 @@ impl CacheService {
     ) -> UaipResult<Option<CachedDeviceState>> {
         let key = format!("{}status:{}", self.config.key_prefix, device_id);
 
-        let value: Option<String> = self
-            .connection
-            .get(&key)
-            .await
-            .map_err(|e| UaipError::DatabaseError(format!("Redis error: {}", e)))?;
+        let value: Option<String> = match self.connection.get(&key).await {
+            Ok(value) => value,
+            Err(e) => return self.config.failure_policy.read_failed(&key, e),
+        };
 
         match value {
             Some(json) => {
@@ -172,12 +251,14 @@ impl CacheService {
         let device_key = format!("{}device:{}", self.config.key_prefix, device_id);
         let status_key = format!("{}status:{}", self.config.key_prefix, device_id);
 
-        self.connection
-            .del::<_, ()>(&[device_key, status_key])
+        match self
+            .connection
+            .del::<_, ()>(&[&device_key, &status_key])
             .await
-            .map_err(|e| UaipError::DatabaseError(format!("Redis error: {}", e)))?;
-
-        Ok(())
+        {
+            Ok(()) => Ok(()),
+            Err(e) => self.config.failure_policy.write_failed(&device_key, e),
+        }
     }
 
     /// Invalidate all device caches
@@ -189,33 +270,23 @@ impl CacheService {
         let device_pattern = format!("{}device:*", self.config.key_prefix);
         let status_pattern = format!("{}status:*", self.config.key_prefix);
 
-        // Delete all matching keys
-        let device_keys: Vec<String> = self
-            .connection
-            .keys(&device_pattern)
-            .await
-            .map_err(|e| UaipError::DatabaseError(format!("Redis error: {}", e)))?;
-
-        let status_keys: Vec<String> = self
-            .connection
-            .keys(&status_pattern)
+        match self
+            .delete_matching(&[&device_pattern, &status_pattern])
             .await
-            .map_err(|e| UaipError::DatabaseError(format!("Redis error: {}", e)))?;
-
-        if !device_keys.is_empty() {
-            self.connection
-                .del::<_, ()>(&device_keys)
-                .await
-                .map_err(|e| UaipError::DatabaseError(format!("Redis error: {}", e)))?;
+        {
+            Ok(()) => Ok(()),
+            Err(e) => self.config.failure_policy.write_failed(&device_pattern, e),
         }
+    }
 
-        if !status_keys.is_empty() {
-            self.connection
-                .del::<_, ()>(&status_keys)
-                .await
-                .map_err(|e| UaipError::DatabaseError(format!("Redis error: {}", e)))?;
+    /// Delete all keys matching each pattern
+    async fn delete_matching(&mut self, patterns: &[&str]) -> Result<(), RedisError> {
+        for pattern in patterns {
+            let keys: Vec<String> = self.connection.keys(*pattern).await?;
+            if !keys.is_empty() {
+                self.connection.del::<_, ()>(&keys).await?;
+            }
         }
-
         Ok(())
     }
 
@@ -229,17 +300,20 @@ impl CacheService {
     pub async fn is_device_cached(&mut self, device_id: &str) -> UaipResult<bool> {
         let key = format!("{}device:{}", self.config.key_prefix, device_id);
 
-        let exists: bool = self
-            .connection
-            .exists(&key)
-            .await
-            .map_err(|e| UaipError::DatabaseError(format!("Redis error: {}", e)))?;
-
-        Ok(exists)
+        match self.connection.exists(&key).await {
+            Ok(exists) => Ok(exists),
+            Err(e) => Ok(self
+                .config
+                .failure_policy
+                .read_failed::<()>(&key, e)?
+                .is_some()),
+        }
     }
 
     /// Get cache statistics
     ///
+    /// Redis errors are always returned, regardless of the failure policy.
+    ///
     /// # Returns
     /// * `Result<CacheStats>` - Cache statistics
     pub async fn get_stats(&mut self) -> UaipResult<CacheStats> {
@@ -250,14 +324,14 @@ impl CacheService {
             .connection
             .keys::<_, Vec<String>>(&device_pattern)
             .await
-            .map_err(|e| UaipError::DatabaseError(format!("Redis error: {}", e)))?
+            .map_err(redis_error)?
             .len();
 
         let status_count: usize = self
             .connection
             .keys::<_, Vec<String>>(&status_pattern)
             .await
-            .map_err(|e| UaipError::DatabaseError(format!("Redis error: {}", e)))?
+            .map_err(redis_error)?
             .len();
 
         Ok(CacheStats {
@@ -284,6 +358,7 @@ mod tests {
         assert_eq!(config.device_ttl, 300);
         assert_eq!(config.status_ttl, 60);
         assert_eq!(config.key_prefix, "uaip:");
+        assert_eq!(config.failure_policy, CacheFailurePolicy::FailOpen);
     }
 
     #[test]
@@ -292,11 +367,40 @@ mod tests {
             device_ttl: 600,
             status_ttl: 120,
             key_prefix: "test:".to_string(),
+            failure_policy: CacheFailurePolicy::FailClosed,
         };
 
         assert_eq!(config.device_ttl, 600);
         assert_eq!(config.status_ttl, 120);
         assert_eq!(config.key_prefix, "test:");
+        assert_eq!(config.failure_policy, CacheFailurePolicy::FailClosed);
+    }
+
+    fn connection_lost() -> RedisError {
+        std::io::Error::new(std::io::ErrorKind::ConnectionReset, "connection reset").into()
+    }
+
+    #[test]
+    fn test_redis_error_becomes_miss_when_failing_open() {
+        let policy = CacheFailurePolicy::FailOpen;
+
+        let read = policy.read_failed::<Device>("uaip:device:d1", connection_lost());
+        assert!(matches!(read, Ok(None)));
+        assert!(policy
+            .write_failed("uaip:device:d1", connection_lost())
+            .is_ok());
+    }
+
+    #[test]
+    fn test_redis_error_propagates_when_failing_closed() {
+        let policy = CacheFailurePolicy::FailClosed;
+
+        let read = policy.read_failed::<CachedDeviceState>("uaip:status:d1", connection_lost());
+        assert!(matches!(read, Err(UaipError::DatabaseError(ref m)) if m.contains("Redis error")));
+        assert!(matches!(
+            policy.write_failed("uaip:status:d1", connection_lost()),
+            Err(UaipError::DatabaseError(_))
+        ));
     }
 
     #[test]
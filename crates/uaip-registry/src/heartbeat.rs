@@ -2,11 +2,13 @@
 
 use chrono::{DateTime, Duration, Utc};
 use std::collections::HashMap;
+use std::sync::Arc;
 use tokio::sync::RwLock;
 use tokio::time::interval;
 
 use crate::models::DeviceStatus;
 use crate::repository::DeviceRepository;
+use crate::status_batch::StatusBatcher;
 use uaip_core::error::UaipResult;
 
 /// Heartbeat configuration
@@ -18,6 +20,11 @@ pub struct HeartbeatConfig {
     pub timeout_grace_period: i64,
     /// How often to check for stale devices (seconds)
     pub check_interval: i64,
+    /// Multiple of the heartbeat interval to wait, on top of the grace period,
+    /// before a heartbeat counts as missed
+    pub grace_multiplier: f64,
+    /// Consecutive missed heartbeats before a device is marked offline
+    pub misses_before_offline: u32,
 }
 
 impl Default for HeartbeatConfig {
@@ -26,16 +33,64 @@ impl Default for HeartbeatConfig {
             heartbeat_interval: 30,   // Devices send heartbeat every 30s
             timeout_grace_period: 60, // 60s grace period
             check_interval: 15,       // Check every 15s
+            grace_multiplier: 1.0,
+            misses_before_offline: 1,
         }
     }
 }
 
+impl HeartbeatConfig {
+    /// Silence after a heartbeat before the next one counts as missed
+    pub fn miss_deadline(&self) -> Duration {
+        let interval_ms = self.heartbeat_interval as f64 * 1000.0 * self.grace_multiplier;
+        Duration::milliseconds(interval_ms as i64) + Duration::seconds(self.timeout_grace_period)
+    }
+
+    /// Number of heartbeats missed after `silence` without one
+    ///
+    /// The first miss is counted once the deadline passes, then one more for
+    /// every further heartbeat interval.
+    pub fn missed_heartbeats(&self, silence: Duration) -> u32 {
+        let deadline = self.miss_deadline();
+        if silence <= deadline {
+            return 0;
+        }
+
+        let interval_ms = (self.heartbeat_interval * 1000).max(1);
+        let extra = (silence - deadline).num_milliseconds() / interval_ms;
+        u32::try_from(extra + 1).unwrap_or(u32::MAX)
+    }
+}
+
 /// Device heartbeat information
 #[derive(Debug, Clone)]
 struct HeartbeatInfo {
     last_heartbeat: DateTime<Utc>,
     status: DeviceStatus,
-    consecutive_failures: u32,
+    consecutive_misses: u32,
+}
+
+impl HeartbeatInfo {
+    fn new(last_heartbeat: DateTime<Utc>, status: DeviceStatus) -> Self {
+        Self {
+            last_heartbeat,
+            status,
+            consecutive_misses: 0,
+        }
+    }
+
+    /// Update the miss count at `now`, returning true if the device just went offline
+    fn observe(&mut self, config: &HeartbeatConfig, now: DateTime<Utc>) -> bool {
+        self.consecutive_misses = config.missed_heartbeats(now - self.last_heartbeat);
+
+        if self.consecutive_misses >= config.misses_before_offline.max(1)
+            && self.status != DeviceStatus::Offline
+        {
+            self.status = DeviceStatus::Offline;
+            return true;
+        }
+        false
+    }
 }
 
 /// Heartbeat service for tracking device status
@@ -43,6 +98,8 @@ pub struct HeartbeatService {
     repository: DeviceRepository,
     config: HeartbeatConfig,
     heartbeats: RwLock<HashMap<String, HeartbeatInfo>>,
+    /// Batched status cache kept in step with heartbeats
+    status_batcher: Option<Arc<StatusBatcher>>,
 }
 
 impl HeartbeatService {
@@ -56,6 +113,27 @@ impl HeartbeatService {
             repository,
             config,
             heartbeats: RwLock::new(HashMap::new()),
+            status_batcher: None,
+        }
+    }
+
+    /// Cache status changes through a batcher instead of per-heartbeat writes
+    pub fn with_status_batcher(mut self, status_batcher: Arc<StatusBatcher>) -> Self {
+        self.status_batcher = Some(status_batcher);
+        self
+    }
+
+    /// Queue a status for the cache; cache failures never fail the heartbeat path
+    async fn cache_status(
+        &self,
+        device_id: &str,
+        status: DeviceStatus,
+        last_seen: Option<DateTime<Utc>>,
+    ) {
+        if let Some(batcher) = &self.status_batcher {
+            if let Err(e) = batcher.record(device_id, status, last_seen).await {
+                tracing::warn!("Failed to cache device statuses: {}", e);
+            }
         }
     }
 
@@ -74,11 +152,7 @@ impl HeartbeatService {
             let mut heartbeats = self.heartbeats.write().await;
             heartbeats.insert(
                 device_id.to_string(),
-                HeartbeatInfo {
-                    last_heartbeat: now,
-                    status: DeviceStatus::Online,
-                    consecutive_failures: 0,
-                },
+                HeartbeatInfo::new(now, DeviceStatus::Online),
             );
         }
 
@@ -92,6 +166,8 @@ impl HeartbeatService {
                 .update_status(device_id, DeviceStatus::Online)
                 .await?;
         }
+        self.cache_status(device_id, DeviceStatus::Online, Some(now))
+            .await;
 
         Ok(())
     }
@@ -122,16 +198,29 @@ impl HeartbeatService {
             .map(|info| Utc::now() - info.last_heartbeat)
     }
 
+    /// Get the number of consecutive heartbeats a device has missed
+    ///
+    /// # Arguments
+    /// * `device_id` - Device identifier
+    ///
+    /// # Returns
+    /// * `Option<u32>` - Misses as of the last check, or None if not tracked
+    pub async fn consecutive_misses(&self, device_id: &str) -> Option<u32> {
+        let heartbeats = self.heartbeats.read().await;
+        heartbeats
+            .get(device_id)
+            .map(|info| info.consecutive_misses)
+    }
+
     /// Check for stale devices and update their status
     ///
-    /// This should be called periodically to detect offline devices
+    /// This should be called periodically to detect offline devices. A device is
+    /// marked offline once it has missed `misses_before_offline` heartbeats in a row.
     ///
     /// # Returns
     /// * `Result<usize>` - Number of devices marked as offline
     pub async fn check_stale_devices(&self) -> UaipResult<usize> {
         let now = Utc::now();
-        let timeout_threshold = now
-            - Duration::seconds(self.config.heartbeat_interval + self.config.timeout_grace_period);
 
         let mut offline_count = 0;
         let mut devices_to_update = Vec::new();
@@ -141,17 +230,15 @@ impl HeartbeatService {
             let mut heartbeats = self.heartbeats.write().await;
 
             for (device_id, info) in heartbeats.iter_mut() {
-                if info.last_heartbeat < timeout_threshold && info.status != DeviceStatus::Offline {
-                    info.status = DeviceStatus::Offline;
-                    info.consecutive_failures += 1;
-                    devices_to_update.push(device_id.clone());
+                if info.observe(&self.config, now) {
+                    devices_to_update.push((device_id.clone(), info.last_heartbeat));
                     offline_count += 1;
                 }
             }
         }
 
         // Update database for offline devices
-        for device_id in devices_to_update {
+        for (device_id, last_heartbeat) in devices_to_update {
             if let Err(e) = self
                 .repository
                 .update_status(&device_id, DeviceStatus::Offline)
@@ -159,6 +246,8 @@ impl HeartbeatService {
             {
                 tracing::warn!("Failed to update status for device {}: {}", device_id, e);
             }
+            self.cache_status(&device_id, DeviceStatus::Offline, Some(last_heartbeat))
+                .await;
         }
 
         Ok(offline_count)
@@ -215,11 +304,7 @@ impl HeartbeatService {
 
             heartbeats.insert(
                 device.device_id.clone(),
-                HeartbeatInfo {
-                    last_heartbeat,
-                    status: device.status.clone(),
-                    consecutive_failures: 0,
-                },
+                HeartbeatInfo::new(last_heartbeat, device.status.clone()),
             );
         }
 
@@ -316,9 +401,68 @@ mod tests {
             heartbeat_interval: 60,
             timeout_grace_period: 120,
             check_interval: 30,
+            ..HeartbeatConfig::default()
         };
 
         assert_eq!(config.heartbeat_interval, 60);
         assert_eq!(config.timeout_grace_period, 120);
     }
+
+    fn jittery_config() -> HeartbeatConfig {
+        HeartbeatConfig {
+            heartbeat_interval: 10,
+            timeout_grace_period: 0,
+            check_interval: 5,
+            grace_multiplier: 1.5,
+            misses_before_offline: 3,
+        }
+    }
+
+    #[test]
+    fn test_default_config_keeps_fixed_timeout() {
+        let config = HeartbeatConfig::default();
+        assert_eq!(config.missed_heartbeats(Duration::seconds(90)), 0);
+        assert_eq!(config.missed_heartbeats(Duration::seconds(91)), 1);
+        assert_eq!(config.missed_heartbeats(Duration::seconds(121)), 2);
+    }
+
+    #[test]
+    fn test_slightly_late_heartbeat_keeps_device_online() {
+        let config = jittery_config();
+        let start = Utc::now();
+        let mut info = HeartbeatInfo::new(start, DeviceStatus::Online);
+
+        // 14s of silence is late for a 10s interval but inside the 15s window
+        assert!(!info.observe(&config, start + Duration::seconds(14)));
+        assert_eq!(info.consecutive_misses, 0);
+
+        // One miss is tolerated while waiting for the next heartbeat
+        assert!(!info.observe(&config, start + Duration::seconds(16)));
+        assert_eq!(info.consecutive_misses, 1);
+        assert_eq!(info.status, DeviceStatus::Online);
+
+        // The heartbeat finally arrives and resets the count
+        let late = start + Duration::seconds(18);
+        info = HeartbeatInfo::new(late, DeviceStatus::Online);
+        assert!(!info.observe(&config, late + Duration::seconds(5)));
+        assert_eq!(info.consecutive_misses, 0);
+    }
+
+    #[test]
+    fn test_sustained_silence_marks_device_offline() {
+        let config = jittery_config();
+        let start = Utc::now();
+        let mut info = HeartbeatInfo::new(start, DeviceStatus::Online);
+
+        assert!(!info.observe(&config, start + Duration::seconds(26)));
+        assert_eq!(info.consecutive_misses, 2);
+        assert_eq!(info.status, DeviceStatus::Online);
+
+        assert!(info.observe(&config, start + Duration::seconds(36)));
+        assert_eq!(info.consecutive_misses, 3);
+        assert_eq!(info.status, DeviceStatus::Offline);
+
+        // Already offline, so later checks do not report a new transition
+        assert!(!info.observe(&config, start + Duration::seconds(60)));
+    }
 }
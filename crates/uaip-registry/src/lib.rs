@@ -2,6 +2,7 @@
 //!
 //! This crate manages device registration, discovery, and state tracking.
 
+pub mod availability;
 pub mod cache;
 pub mod capability;
 pub mod discovery;
@@ -9,3 +10,4 @@ pub mod heartbeat;
 pub mod models;
 pub mod registration;
 pub mod repository;
+pub mod status_batch;
@@ -4,6 +4,7 @@ use chrono::{DateTime, Utc};
 use sqlx::{PgPool, Row};
 use uuid::Uuid;
 
+use crate::availability::StatusTransition;
 use crate::models::{CreateDevice, Device, DeviceFilter, DeviceStatus, UpdateDevice};
 use uaip_core::error::{UaipError, UaipResult};
 
@@ -406,6 +407,41 @@ impl DeviceRepository {
         let exists: bool = row.get(0);
         Ok(exists)
     }
+
+    /// Get status changes relevant to a window starting at `since`
+    ///
+    /// Includes the latest change at or before `since`, which gives the status at
+    /// the start of the window, followed by every later change in time order.
+    ///
+    /// # Arguments
+    /// * `device_id` - Device identifier
+    /// * `since` - Window start
+    ///
+    /// # Returns
+    /// * `Result<Vec<StatusTransition>>` - Status changes, oldest first
+    pub async fn status_history(
+        &self,
+        device_id: &str,
+        since: DateTime<Utc>,
+    ) -> UaipResult<Vec<StatusTransition>> {
+        sqlx::query_as::<_, StatusTransition>(
+            r#"
+            SELECT status, changed_at
+            FROM device_status_history
+            WHERE device_id = $1
+              AND changed_at >= COALESCE(
+                  (SELECT MAX(changed_at) FROM device_status_history
+                   WHERE device_id = $1 AND changed_at <= $2),
+                  $2)
+            ORDER BY changed_at, id
+            "#,
+        )
+        .bind(device_id)
+        .bind(since)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| UaipError::DatabaseError(e.to_string()))
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,267 @@
+//! Coalesced device status caching
+//!
+//! When many devices heartbeat at once, caching each status separately costs one Redis
+//! round-trip per device. The batcher keeps the latest status per device and writes the
+//! pending statuses in one batch, either every `flush_interval` or once `max_batch`
+//! devices are waiting.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::interval;
+
+use crate::cache::{CacheService, CachedDeviceState};
+use crate::models::DeviceStatus;
+use uaip_core::error::UaipResult;
+
+/// Destination for batched status writes
+#[async_trait]
+pub trait StatusCache: Send + Sync {
+    /// Cache a batch of device statuses in one round-trip
+    async fn cache_statuses(&self, states: &[CachedDeviceState]) -> UaipResult<()>;
+}
+
+#[async_trait]
+impl StatusCache for Mutex<CacheService> {
+    async fn cache_statuses(&self, states: &[CachedDeviceState]) -> UaipResult<()> {
+        self.lock().await.cache_device_statuses_batch(states).await
+    }
+}
+
+/// Status batching configuration
+#[derive(Debug, Clone)]
+pub struct StatusBatchConfig {
+    /// How long updates may wait before being flushed
+    pub flush_interval: Duration,
+    /// Number of pending devices that triggers an immediate flush
+    pub max_batch: usize,
+}
+
+impl Default for StatusBatchConfig {
+    fn default() -> Self {
+        Self {
+            flush_interval: Duration::from_millis(250),
+            max_batch: 500,
+        }
+    }
+}
+
+/// Buffers device status updates and caches them in batches
+pub struct StatusBatcher {
+    cache: Arc<dyn StatusCache>,
+    config: StatusBatchConfig,
+    /// Latest pending status per device
+    pending: Mutex<HashMap<String, CachedDeviceState>>,
+}
+
+impl StatusBatcher {
+    /// Create a status batcher
+    ///
+    /// # Arguments
+    /// * `cache` - Where flushed statuses are written
+    /// * `config` - Batching configuration
+    pub fn new(cache: Arc<dyn StatusCache>, config: StatusBatchConfig) -> Self {
+        Self {
+            cache,
+            config,
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Queue a status update, replacing any pending update for the same device
+    ///
+    /// Flushes right away once `max_batch` devices are pending.
+    ///
+    /// # Returns
+    /// * `Result<()>` - Error if a triggered flush failed
+    pub async fn record(
+        &self,
+        device_id: &str,
+        status: DeviceStatus,
+        last_seen: Option<DateTime<Utc>>,
+    ) -> UaipResult<()> {
+        let pending = {
+            let mut pending = self.pending.lock().await;
+            pending.insert(
+                device_id.to_string(),
+                CachedDeviceState {
+                    device_id: device_id.to_string(),
+                    status,
+                    last_seen,
+                    cached_at: Utc::now(),
+                },
+            );
+            pending.len()
+        };
+
+        if pending >= self.config.max_batch {
+            self.flush().await?;
+        }
+        Ok(())
+    }
+
+    /// Write all pending statuses in one batch
+    ///
+    /// On failure the statuses are queued again, unless a newer update for the same
+    /// device arrived in the meantime.
+    ///
+    /// # Returns
+    /// * `Result<usize>` - Number of statuses written
+    pub async fn flush(&self) -> UaipResult<usize> {
+        let batch: Vec<CachedDeviceState> = std::mem::take(&mut *self.pending.lock().await)
+            .into_values()
+            .collect();
+        if batch.is_empty() {
+            return Ok(0);
+        }
+
+        if let Err(e) = self.cache.cache_statuses(&batch).await {
+            let mut pending = self.pending.lock().await;
+            for state in batch {
+                pending.entry(state.device_id.clone()).or_insert(state);
+            }
+            return Err(e);
+        }
+        Ok(batch.len())
+    }
+
+    /// Number of devices with a pending update
+    pub async fn pending_count(&self) -> usize {
+        self.pending.lock().await.len()
+    }
+
+    /// Start flushing pending updates every `flush_interval`
+    ///
+    /// # Returns
+    /// * `tokio::task::JoinHandle` - Handle to the background task
+    pub fn start_flushing(self: Arc<Self>) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut flush_interval = interval(self.config.flush_interval);
+
+            loop {
+                flush_interval.tick().await;
+
+                if let Err(e) = self.flush().await {
+                    tracing::warn!("Failed to flush cached device statuses: {}", e);
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uaip_core::error::UaipError;
+
+    /// Records each batch it is asked to write
+    #[derive(Default)]
+    struct RecordingCache {
+        batches: std::sync::Mutex<Vec<Vec<CachedDeviceState>>>,
+        fail: std::sync::atomic::AtomicBool,
+    }
+
+    #[async_trait]
+    impl StatusCache for RecordingCache {
+        async fn cache_statuses(&self, states: &[CachedDeviceState]) -> UaipResult<()> {
+            if self.fail.load(std::sync::atomic::Ordering::SeqCst) {
+                return Err(UaipError::DatabaseError("Redis error: down".to_string()));
+            }
+            self.batches.lock().unwrap().push(states.to_vec());
+            Ok(())
+        }
+    }
+
+    impl RecordingCache {
+        fn batches(&self) -> Vec<Vec<CachedDeviceState>> {
+            self.batches.lock().unwrap().clone()
+        }
+    }
+
+    fn batcher(cache: Arc<RecordingCache>, max_batch: usize) -> StatusBatcher {
+        StatusBatcher::new(
+            cache,
+            StatusBatchConfig {
+                flush_interval: Duration::from_millis(20),
+                max_batch,
+            },
+        )
+    }
+
+    #[tokio::test]
+    async fn test_rapid_updates_flush_as_one_batch() {
+        let cache = Arc::new(RecordingCache::default());
+        let batcher = Arc::new(batcher(cache.clone(), 500));
+
+        // 100 updates across 10 devices; each device ends up offline
+        for round in 0..10 {
+            for device in 0..10 {
+                let status = if round == 9 {
+                    DeviceStatus::Offline
+                } else {
+                    DeviceStatus::Online
+                };
+                batcher
+                    .record(&format!("device-{}", device), status, Some(Utc::now()))
+                    .await
+                    .unwrap();
+            }
+        }
+        assert!(cache.batches().is_empty());
+        assert_eq!(batcher.pending_count().await, 10);
+
+        let task = batcher.clone().start_flushing();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        task.abort();
+
+        let batches = cache.batches();
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].len(), 10);
+        assert!(batches[0]
+            .iter()
+            .all(|state| state.status == DeviceStatus::Offline));
+        assert_eq!(batcher.pending_count().await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_size_threshold_flushes_immediately() {
+        let cache = Arc::new(RecordingCache::default());
+        let batcher = batcher(cache.clone(), 3);
+
+        for device in ["a", "b", "a", "c"] {
+            batcher
+                .record(device, DeviceStatus::Online, None)
+                .await
+                .unwrap();
+        }
+        assert_eq!(cache.batches().len(), 1);
+        assert_eq!(cache.batches()[0].len(), 3);
+        assert_eq!(batcher.pending_count().await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_failed_flush_keeps_newer_updates() {
+        let cache = Arc::new(RecordingCache::default());
+        let batcher = batcher(cache.clone(), 500);
+        batcher
+            .record("a", DeviceStatus::Online, None)
+            .await
+            .unwrap();
+
+        cache.fail.store(true, std::sync::atomic::Ordering::SeqCst);
+        assert!(batcher.flush().await.is_err());
+        assert_eq!(batcher.pending_count().await, 1);
+
+        // A newer update wins over the requeued one
+        batcher
+            .record("a", DeviceStatus::Error, None)
+            .await
+            .unwrap();
+        cache.fail.store(false, std::sync::atomic::Ordering::SeqCst);
+        assert_eq!(batcher.flush().await.unwrap(), 1);
+        assert_eq!(cache.batches()[0][0].status, DeviceStatus::Error);
+    }
+}
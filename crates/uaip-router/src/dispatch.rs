@@ -0,0 +1,67 @@
+//! Recipient dispatch
+//!
+//! The router hands each message to the handler registered for its recipient's
+//! `EntityType`, so devices and AI agents can be reached over different transports.
+
+use async_trait::async_trait;
+
+use uaip_core::error::UaipResult;
+use uaip_core::message::UaipMessage;
+
+use crate::nats::NatsBroker;
+use crate::priority_queue::MessagePriorityQueue;
+
+/// Delivers routed messages to one kind of recipient
+#[async_trait]
+pub trait RecipientHandler: Send + Sync {
+    /// Deliver a message to its recipient
+    async fn deliver(&self, message: &UaipMessage) -> UaipResult<()>;
+}
+
+/// Publish on the recipient's NATS subject
+#[async_trait]
+impl RecipientHandler for NatsBroker {
+    async fn deliver(&self, message: &UaipMessage) -> UaipResult<()> {
+        self.publish(message).await
+    }
+}
+
+/// Hold the message in a queue the recipient drains (e.g. a device command queue)
+#[async_trait]
+impl RecipientHandler for MessagePriorityQueue {
+    async fn deliver(&self, message: &UaipMessage) -> UaipResult<()> {
+        self.push(message.clone()).await;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nats::NatsConfig;
+    use uaip_core::message::EntityType;
+
+    fn command() -> UaipMessage {
+        UaipMessage::new(
+            "hub".to_string(),
+            EntityType::System,
+            "valve-1".to_string(),
+            EntityType::Device,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_queue_handler_holds_message() {
+        let queue = MessagePriorityQueue::new();
+        let message = command();
+        queue.deliver(&message).await.unwrap();
+
+        assert_eq!(queue.pop().await.unwrap(), message);
+    }
+
+    #[tokio::test]
+    async fn test_nats_handler_requires_connection() {
+        let broker = NatsBroker::new(NatsConfig::default());
+        assert!(broker.deliver(&command()).await.is_err());
+    }
+}
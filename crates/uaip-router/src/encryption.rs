@@ -0,0 +1,237 @@
+//! Payload encryption at the router boundary
+//!
+//! Messages routed to a recipient named in the [`EncryptionPolicy`] have their payload
+//! encrypted with that recipient's key from the key ring. The encrypted payload keeps
+//! only its action in clear; everything else travels as ciphertext in `payload.data`,
+//! encoded per the message's content type, and `security.encryption` records the key ID
+//! and nonce needed to decrypt it.
+
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use uaip_core::error::{UaipError, UaipResult};
+use uaip_core::message::{
+    CompressionType, Data, DataEncoding, DataFormat, Encryption, Payload, UaipMessage,
+};
+use uaip_security::encryption::{EncryptedData, EncryptionEngine, ALGORITHM};
+use uaip_security::key_ring::KeyRing;
+
+/// Which recipients get encrypted payloads, and with which key
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct EncryptionPolicy {
+    /// Key ID to encrypt with, by recipient ID
+    #[serde(default)]
+    pub recipients: HashMap<String, String>,
+}
+
+impl EncryptionPolicy {
+    /// Encrypt payloads routed to a recipient with the given key
+    pub fn with_recipient(
+        mut self,
+        recipient_id: impl Into<String>,
+        key_id: impl Into<String>,
+    ) -> Self {
+        self.recipients.insert(recipient_id.into(), key_id.into());
+        self
+    }
+
+    /// Key ID payloads for a recipient are encrypted with, if any
+    pub fn key_for(&self, recipient_id: &str) -> Option<&str> {
+        self.recipients.get(recipient_id).map(String::as_str)
+    }
+}
+
+/// Encrypts and decrypts message payloads using a key ring and policy
+#[derive(Debug, Clone)]
+pub struct PayloadEncryption {
+    key_ring: KeyRing,
+    policy: EncryptionPolicy,
+}
+
+impl PayloadEncryption {
+    /// Create payload encryption, checking that every key the policy names is in the ring
+    pub fn new(key_ring: KeyRing, policy: EncryptionPolicy) -> UaipResult<Self> {
+        if let Some((recipient_id, key_id)) = policy
+            .recipients
+            .iter()
+            .find(|(_, key_id)| !key_ring.contains(key_id))
+        {
+            return Err(UaipError::InvalidConfiguration(format!(
+                "Encryption key '{}' for recipient '{}' is not in the key ring",
+                key_id, recipient_id
+            )));
+        }
+
+        Ok(Self { key_ring, policy })
+    }
+
+    /// Encrypt the payload if the policy covers the recipient
+    ///
+    /// Messages that are already encrypted, or whose recipient is not covered, are
+    /// returned unchanged.
+    pub fn encrypt_outbound(&self, mut message: UaipMessage) -> UaipResult<UaipMessage> {
+        if is_encrypted(&message) {
+            return Ok(message);
+        }
+        let Some(key_id) = self.policy.key_for(&message.header.recipient.id) else {
+            return Ok(message);
+        };
+
+        let plaintext = message.encode_payload()?;
+        let encrypted = self
+            .engine(key_id)?
+            .encrypt(&plaintext)
+            .map_err(|e| UaipError::EncryptionError(e.to_string()))?;
+
+        message.payload = Payload {
+            action: message.payload.action,
+            device_type: None,
+            capability: None,
+            data: Some(Data {
+                format: DataFormat::Binary,
+                encoding: DataEncoding::Base64,
+                compression: CompressionType::None,
+                content: serde_json::Value::String(encrypted.ciphertext),
+            }),
+            parameters: None,
+        };
+        message.security.encryption = Some(Encryption {
+            enabled: true,
+            algorithm: ALGORITHM.to_string(),
+            key_id: key_id.to_string(),
+            nonce: Some(encrypted.nonce),
+        });
+
+        Ok(message)
+    }
+
+    /// Decrypt the payload of an encrypted message; plaintext messages pass through
+    pub fn decrypt_inbound(&self, mut message: UaipMessage) -> UaipResult<UaipMessage> {
+        let Some(encryption) = message.security.encryption.take().filter(|e| e.enabled) else {
+            return Ok(message);
+        };
+
+        if encryption.algorithm != ALGORITHM {
+            return Err(UaipError::EncryptionError(format!(
+                "Unsupported algorithm: {}",
+                encryption.algorithm
+            )));
+        }
+        let nonce = encryption
+            .nonce
+            .ok_or_else(|| UaipError::EncryptionError("Missing nonce".to_string()))?;
+        let ciphertext = message
+            .payload
+            .data
+            .as_ref()
+            .and_then(|data| data.content.as_str())
+            .ok_or_else(|| UaipError::EncryptionError("Missing ciphertext".to_string()))?;
+
+        let plaintext = self
+            .engine(&encryption.key_id)?
+            .decrypt(&EncryptedData {
+                nonce,
+                ciphertext: ciphertext.to_string(),
+            })
+            .map_err(|e| UaipError::EncryptionError(e.to_string()))?;
+        message.decode_payload(&plaintext)?;
+
+        Ok(message)
+    }
+
+    fn engine(&self, key_id: &str) -> UaipResult<&EncryptionEngine> {
+        self.key_ring
+            .get(key_id)
+            .ok_or_else(|| UaipError::EncryptionError(format!("Unknown key: {}", key_id)))
+    }
+}
+
+/// Check if a message carries an encrypted payload
+pub fn is_encrypted(message: &UaipMessage) -> bool {
+    message
+        .security
+        .encryption
+        .as_ref()
+        .is_some_and(|e| e.enabled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uaip_core::message::{Action, EntityType};
+
+    fn message(recipient_id: &str) -> UaipMessage {
+        let mut message = UaipMessage::new(
+            "agent-1".to_string(),
+            EntityType::AiAgent,
+            recipient_id.to_string(),
+            EntityType::Device,
+        );
+        message.payload.action = Action::Write;
+        message.payload.capability = Some("valve".to_string());
+        message.payload.parameters = Some(HashMap::from([(
+            "position".to_string(),
+            serde_json::json!(40),
+        )]));
+        message
+    }
+
+    fn encryption(key: &[u8; 32]) -> PayloadEncryption {
+        PayloadEncryption::new(
+            KeyRing::new().with_key("plant-a", EncryptionEngine::from_key(key).unwrap()),
+            EncryptionPolicy::default().with_recipient("valve-7", "plant-a"),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_encrypt_on_send_decrypt_on_receive() {
+        let encryption = encryption(&EncryptionEngine::generate_key());
+        let original = message("valve-7");
+
+        let sent = encryption.encrypt_outbound(original.clone()).unwrap();
+        assert!(is_encrypted(&sent));
+        assert_eq!(sent.payload.action, Action::Write);
+        assert!(sent.payload.capability.is_none());
+        assert!(sent.payload.parameters.is_none());
+        let header = sent.security.encryption.as_ref().unwrap();
+        assert_eq!(header.key_id, "plant-a");
+        assert!(header.nonce.is_some());
+
+        // Routing again must not encrypt twice
+        let resent = encryption.encrypt_outbound(sent.clone()).unwrap();
+        assert_eq!(resent, sent);
+
+        let received = encryption.decrypt_inbound(sent).unwrap();
+        assert_eq!(received, original);
+    }
+
+    #[test]
+    fn test_recipients_outside_policy_stay_plaintext() {
+        let encryption = encryption(&EncryptionEngine::generate_key());
+        let original = message("thermostat-2");
+
+        let sent = encryption.encrypt_outbound(original.clone()).unwrap();
+        assert_eq!(sent, original);
+        assert_eq!(encryption.decrypt_inbound(sent).unwrap(), original);
+    }
+
+    #[test]
+    fn test_decryption_with_wrong_key_fails() {
+        let sender = encryption(&EncryptionEngine::generate_key());
+        let receiver = encryption(&EncryptionEngine::generate_key());
+
+        let sent = sender.encrypt_outbound(message("valve-7")).unwrap();
+        let err = receiver.decrypt_inbound(sent).unwrap_err();
+        assert!(matches!(err, UaipError::EncryptionError(_)));
+    }
+
+    #[test]
+    fn test_policy_keys_must_be_in_ring() {
+        let result = PayloadEncryption::new(
+            KeyRing::new(),
+            EncryptionPolicy::default().with_recipient("valve-7", "plant-a"),
+        );
+        assert!(matches!(result, Err(UaipError::InvalidConfiguration(_))));
+    }
+}
@@ -2,6 +2,8 @@
 //!
 //! This crate handles message routing, priority queues, and QoS levels.
 
+pub mod dispatch;
+pub mod encryption;
 pub mod nats;
 pub mod priority_queue;
 pub mod qos;
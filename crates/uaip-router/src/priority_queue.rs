@@ -1,8 +1,9 @@
 //! Priority queue for message processing
 
+use serde::Serialize;
 use std::cmp::Ordering;
 use std::collections::BinaryHeap;
-use tokio::sync::Mutex;
+use tokio::sync::{watch, Mutex};
 
 use uaip_core::message::{Priority, UaipMessage};
 
@@ -45,6 +46,8 @@ impl Ord for PriorityMessage {
 pub struct MessagePriorityQueue {
     heap: Mutex<BinaryHeap<PriorityMessage>>,
     sequence_counter: Mutex<u64>,
+    /// Number of messages pushed so far, watched by consumers waiting for new messages
+    pushed: watch::Sender<u64>,
 }
 
 impl MessagePriorityQueue {
@@ -53,6 +56,7 @@ impl MessagePriorityQueue {
         Self {
             heap: Mutex::new(BinaryHeap::new()),
             sequence_counter: Mutex::new(0),
+            pushed: watch::Sender::new(0),
         }
     }
 
@@ -77,6 +81,17 @@ impl MessagePriorityQueue {
 
         let mut heap = self.heap.lock().await;
         heap.push(priority_msg);
+        drop(heap);
+
+        self.pushed.send_modify(|pushed| *pushed += 1);
+    }
+
+    /// Watch for pushed messages
+    ///
+    /// Subscribe before looking for a message; the receiver then sees every push that
+    /// happens after the subscription, including those during the lookup.
+    pub fn subscribe(&self) -> watch::Receiver<u64> {
+        self.pushed.subscribe()
     }
 
     /// Pop the highest priority message
@@ -97,6 +112,49 @@ impl MessagePriorityQueue {
         heap.peek().map(|pm| pm.message.clone())
     }
 
+    /// Pop the highest priority message matching a predicate
+    ///
+    /// # Returns
+    /// * `Option<UaipMessage>` - Highest priority matching message or None if none match
+    pub async fn pop_where<F>(&self, mut predicate: F) -> Option<UaipMessage>
+    where
+        F: FnMut(&UaipMessage) -> bool,
+    {
+        let mut heap = self.heap.lock().await;
+        let best = heap
+            .iter()
+            .filter(|pm| predicate(&pm.message))
+            .max()
+            .map(|pm| pm.sequence)?;
+
+        let mut popped = None;
+        heap.retain(|pm| {
+            if pm.sequence == best {
+                popped = Some(pm.message.clone());
+                return false;
+            }
+            true
+        });
+        popped
+    }
+
+    /// Remove a queued message by ID
+    ///
+    /// # Returns
+    /// * `Option<UaipMessage>` - The removed message, or None if it was not queued
+    pub async fn remove(&self, message_id: &str) -> Option<UaipMessage> {
+        let mut heap = self.heap.lock().await;
+        let mut removed = None;
+        heap.retain(|pm| {
+            if removed.is_none() && pm.message.header.message_id == message_id {
+                removed = Some(pm.message.clone());
+                return false;
+            }
+            true
+        });
+        removed
+    }
+
     /// Get the number of messages in the queue
     pub async fn len(&self) -> usize {
         let heap = self.heap.lock().await;
@@ -150,7 +208,7 @@ impl Default for MessagePriorityQueue {
 }
 
 /// Priority queue statistics
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct PriorityStats {
     pub total: usize,
     pub critical: usize,
@@ -206,6 +264,7 @@ mod tests {
                 ack_timeout: None,
                 retry_policy: None,
                 qos: QosLevel::AtMostOnce,
+                ordered: false,
                 content_type: None,
                 user_data: None,
             },
@@ -290,4 +349,51 @@ mod tests {
         assert_eq!(popped.header.priority, Priority::Critical);
         assert_eq!(queue.len().await, 1);
     }
+
+    #[tokio::test]
+    async fn test_remove_by_message_id() {
+        let queue = MessagePriorityQueue::new();
+        let first = create_test_message(Priority::Normal);
+        let second = create_test_message(Priority::High);
+        queue.push(first.clone()).await;
+        queue.push(second.clone()).await;
+
+        let removed = queue.remove(&first.header.message_id).await.unwrap();
+        assert_eq!(removed.header.message_id, first.header.message_id);
+        assert!(queue.remove(&first.header.message_id).await.is_none());
+        assert_eq!(queue.len().await, 1);
+        assert_eq!(
+            queue.pop().await.unwrap().header.message_id,
+            second.header.message_id
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pop_where_keeps_priority_order_among_matches() {
+        let queue = MessagePriorityQueue::new();
+        let mut pushed = queue.subscribe();
+        let for_recipient = |priority, recipient: &str| {
+            let mut message = create_test_message(priority);
+            message.header.recipient.id = recipient.to_string();
+            message
+        };
+        let low = for_recipient(Priority::Low, "lamp-1");
+        let high = for_recipient(Priority::High, "lamp-1");
+        queue.push(low.clone()).await;
+        queue
+            .push(for_recipient(Priority::Critical, "lamp-2"))
+            .await;
+        queue.push(high.clone()).await;
+        assert!(pushed.has_changed().unwrap());
+        assert_eq!(*pushed.borrow_and_update(), 3);
+
+        let is_lamp_1 = |m: &UaipMessage| m.header.recipient.id == "lamp-1";
+        for expected in [&high, &low] {
+            let popped = queue.pop_where(is_lamp_1).await.unwrap();
+            assert_eq!(popped.header.message_id, expected.header.message_id);
+        }
+        assert!(queue.pop_where(is_lamp_1).await.is_none());
+        assert_eq!(queue.len().await, 1);
+        assert!(!pushed.has_changed().unwrap());
+    }
 }
@@ -1,14 +1,16 @@
 //! QoS (Quality of Service) levels implementation
 
+use chrono::{DateTime, Utc};
+use serde::Serialize;
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
 use uaip_core::error::{UaipError, UaipResult};
-use uaip_core::message::UaipMessage;
+use uaip_core::message::{RetryPolicy, UaipMessage};
 
 /// QoS levels for message delivery
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum QosLevel {
     /// QoS 0: At most once (fire-and-forget)
     AtMostOnce,
@@ -37,7 +39,32 @@ struct TrackedMessage {
     message: UaipMessage,
     state: DeliveryState,
     attempts: u32,
-    max_attempts: u32,
+    retry: RetryPolicy,
+    next_retry_at: DateTime<Utc>,
+}
+
+impl TrackedMessage {
+    fn new(message: UaipMessage, state: DeliveryState, retry: RetryPolicy) -> Self {
+        let next_retry_at = next_retry_time(&retry, 1);
+        Self {
+            message,
+            state,
+            attempts: 1,
+            retry,
+            next_retry_at,
+        }
+    }
+}
+
+/// Exhausted messages are due once the last backoff has passed, when they are declared failed
+fn next_retry_time(retry: &RetryPolicy, attempt: u32) -> DateTime<Utc> {
+    let delay = retry
+        .next_delay(attempt)
+        .unwrap_or_else(|| retry.backoff(attempt));
+    let delay = chrono::Duration::from_std(delay).unwrap_or(chrono::Duration::MAX);
+    Utc::now()
+        .checked_add_signed(delay)
+        .unwrap_or(DateTime::<Utc>::MAX_UTC)
 }
 
 /// QoS handler service
@@ -46,10 +73,12 @@ pub struct QosHandler {
     tracked: Arc<RwLock<HashMap<String, TrackedMessage>>>,
     /// Statistics
     stats: Arc<RwLock<QosStats>>,
+    /// Retry policy per QoS level
+    retry_policies: HashMap<QosLevel, RetryPolicy>,
 }
 
 /// QoS statistics
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct QosStats {
     pub qos0_sent: u64,
     pub qos1_sent: u64,
@@ -60,15 +89,29 @@ pub struct QosStats {
     pub failures: u64,
 }
 
+/// Messages awaiting acknowledgment, by QoS level
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct TrackedCounts {
+    pub at_least_once: usize,
+    pub exactly_once: usize,
+}
+
 impl QosHandler {
     /// Create a new QoS handler
     pub fn new() -> Self {
         Self {
             tracked: Arc::new(RwLock::new(HashMap::new())),
             stats: Arc::new(RwLock::new(QosStats::default())),
+            retry_policies: HashMap::new(),
         }
     }
 
+    /// Set the retry policy used for messages of a QoS level
+    pub fn with_retry_policy(mut self, qos_level: QosLevel, retry: RetryPolicy) -> Self {
+        self.retry_policies.insert(qos_level, retry);
+        self
+    }
+
     /// Handle message delivery with specified QoS level
     ///
     /// # Arguments
@@ -77,15 +120,37 @@ impl QosHandler {
     ///
     /// # Returns
     /// * `Result<()>` - Success or error
+    ///
+    /// Retries follow the message's own retry policy if it has one, otherwise the
+    /// policy for its QoS level.
     pub async fn handle_message(
         &self,
         message: UaipMessage,
         qos_level: QosLevel,
+    ) -> UaipResult<()> {
+        let retry = match &message.metadata.retry_policy {
+            Some(policy) => policy.clone(),
+            None => self
+                .retry_policies
+                .get(&qos_level)
+                .cloned()
+                .unwrap_or_default(),
+        };
+        self.handle_message_with_retry(message, qos_level, retry)
+            .await
+    }
+
+    /// Handle message delivery with an explicit retry policy
+    pub async fn handle_message_with_retry(
+        &self,
+        message: UaipMessage,
+        qos_level: QosLevel,
+        retry: RetryPolicy,
     ) -> UaipResult<()> {
         match qos_level {
             QosLevel::AtMostOnce => self.handle_qos0(message).await,
-            QosLevel::AtLeastOnce => self.handle_qos1(message).await,
-            QosLevel::ExactlyOnce => self.handle_qos2(message).await,
+            QosLevel::AtLeastOnce => self.handle_qos1(message, retry).await,
+            QosLevel::ExactlyOnce => self.handle_qos2(message, retry).await,
         }
     }
 
@@ -105,7 +170,7 @@ impl QosHandler {
     /// Handle QoS 1: At-least-once delivery
     ///
     /// Message is sent and tracked until acknowledgment is received
-    async fn handle_qos1(&self, message: UaipMessage) -> UaipResult<()> {
+    async fn handle_qos1(&self, message: UaipMessage, retry: RetryPolicy) -> UaipResult<()> {
         let message_id = message.header.message_id.clone();
 
         // Track message
@@ -113,12 +178,7 @@ impl QosHandler {
             let mut tracked = self.tracked.write().await;
             tracked.insert(
                 message_id.clone(),
-                TrackedMessage {
-                    message: message.clone(),
-                    state: DeliveryState::AwaitingAck,
-                    attempts: 1,
-                    max_attempts: 3,
-                },
+                TrackedMessage::new(message.clone(), DeliveryState::AwaitingAck, retry),
             );
         }
 
@@ -135,7 +195,7 @@ impl QosHandler {
     ///
     /// Message is delivered using a four-step handshake:
     /// 1. PUBLISH -> 2. PUBREC -> 3. PUBREL -> 4. PUBCOMP
-    async fn handle_qos2(&self, message: UaipMessage) -> UaipResult<()> {
+    async fn handle_qos2(&self, message: UaipMessage, retry: RetryPolicy) -> UaipResult<()> {
         let message_id = message.header.message_id.clone();
 
         // Track message (Phase 1: PUBLISH -> PUBREC)
@@ -143,12 +203,7 @@ impl QosHandler {
             let mut tracked = self.tracked.write().await;
             tracked.insert(
                 message_id.clone(),
-                TrackedMessage {
-                    message: message.clone(),
-                    state: DeliveryState::AwaitingPubRec,
-                    attempts: 1,
-                    max_attempts: 3,
-                },
+                TrackedMessage::new(message.clone(), DeliveryState::AwaitingPubRec, retry),
             );
         }
 
@@ -259,7 +314,7 @@ impl QosHandler {
         let mut tracked = self.tracked.write().await;
 
         if let Some(msg) = tracked.get_mut(message_id) {
-            if msg.attempts >= msg.max_attempts {
+            if msg.attempts >= msg.retry.max_attempts {
                 let mut stats = self.stats.write().await;
                 stats.failures += 1;
 
@@ -270,6 +325,7 @@ impl QosHandler {
             }
 
             msg.attempts += 1;
+            msg.next_retry_at = next_retry_time(&msg.retry, msg.attempts);
 
             // Simulate retry
             self.deliver_message(&msg.message).await?;
@@ -286,12 +342,44 @@ impl QosHandler {
         }
     }
 
+    /// When a tracked message is next due for a retry
+    pub async fn next_retry_at(&self, message_id: &str) -> Option<DateTime<Utc>> {
+        let tracked = self.tracked.read().await;
+        tracked.get(message_id).map(|msg| msg.next_retry_at)
+    }
+
+    /// Tracked messages whose next retry is due at `now`
+    pub async fn due_for_retry(&self, now: DateTime<Utc>) -> Vec<String> {
+        let tracked = self.tracked.read().await;
+        tracked
+            .iter()
+            .filter(|(_, msg)| msg.next_retry_at <= now)
+            .map(|(id, _)| id.clone())
+            .collect()
+    }
+
     /// Get number of tracked messages
     pub async fn tracked_count(&self) -> usize {
         let tracked = self.tracked.read().await;
         tracked.len()
     }
 
+    /// Get number of tracked messages per QoS level
+    pub async fn tracked_by_qos(&self) -> TrackedCounts {
+        let tracked = self.tracked.read().await;
+        let mut counts = TrackedCounts::default();
+        for message in tracked.values() {
+            match message.state {
+                DeliveryState::AwaitingAck => counts.at_least_once += 1,
+                DeliveryState::AwaitingPubRec | DeliveryState::AwaitingPubComp => {
+                    counts.exactly_once += 1
+                }
+                DeliveryState::Completed => {}
+            }
+        }
+        counts
+    }
+
     /// Get QoS statistics
     pub async fn get_stats(&self) -> QosStats {
         let stats = self.stats.read().await;
@@ -325,6 +413,7 @@ impl Default for QosHandler {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::time::Duration;
     use uaip_core::message::{
         Action, AuthMethod, Authentication, Entity, EntityType, Header, Metadata, Payload,
         Priority, Security,
@@ -369,6 +458,7 @@ mod tests {
                 ack_timeout: None,
                 retry_policy: None,
                 qos: uaip_core::message::QosLevel::AtMostOnce,
+                ordered: false,
                 content_type: None,
                 user_data: None,
             },
@@ -426,6 +516,13 @@ mod tests {
         // Phase 1: PUBREC
         handler.acknowledge_qos2_pubrec("msg-003").await.unwrap();
         assert_eq!(handler.tracked_count().await, 1);
+        assert_eq!(
+            handler.tracked_by_qos().await,
+            TrackedCounts {
+                at_least_once: 0,
+                exactly_once: 1,
+            }
+        );
 
         // Phase 2: PUBCOMP
         handler.acknowledge_qos2_pubcomp("msg-003").await.unwrap();
@@ -474,4 +571,77 @@ mod tests {
         let stats = handler.get_stats().await;
         assert_eq!(stats.failures, 1);
     }
+
+    #[tokio::test]
+    async fn test_custom_max_attempts() {
+        let handler = QosHandler::new().with_retry_policy(
+            QosLevel::ExactlyOnce,
+            RetryPolicy::default().with_max_attempts(1),
+        );
+
+        // Per-call configuration
+        handler
+            .handle_message_with_retry(
+                create_test_message("msg-006"),
+                QosLevel::AtLeastOnce,
+                RetryPolicy::default().with_max_attempts(5),
+            )
+            .await
+            .unwrap();
+        for _ in 0..4 {
+            handler.retry_message("msg-006").await.unwrap();
+        }
+        assert!(handler.retry_message("msg-006").await.is_err());
+
+        // Per-level configuration allows no retries at all
+        handler
+            .handle_message(create_test_message("msg-007"), QosLevel::ExactlyOnce)
+            .await
+            .unwrap();
+        assert!(handler.retry_message("msg-007").await.is_err());
+
+        // The message's own retry policy takes precedence
+        let mut message = create_test_message("msg-008");
+        message.metadata.retry_policy = Some(RetryPolicy::fixed(2, Duration::from_secs(1)));
+        handler
+            .handle_message(message, QosLevel::ExactlyOnce)
+            .await
+            .unwrap();
+        handler.retry_message("msg-008").await.unwrap();
+        assert!(handler.retry_message("msg-008").await.is_err());
+
+        let stats = handler.get_stats().await;
+        assert_eq!(stats.retries, 5);
+        assert_eq!(stats.failures, 3);
+    }
+
+    #[tokio::test]
+    async fn test_retry_delays_increase() {
+        let retry = RetryPolicy::exponential(6, Duration::from_secs(10), Duration::from_secs(60));
+        let delays: Vec<u64> = (1..=5).map(|n| retry.backoff(n).as_secs()).collect();
+        assert_eq!(delays, vec![10, 20, 40, 60, 60]);
+
+        // Each retry pushes the recorded next-retry time further out
+        let handler = QosHandler::new();
+        handler
+            .handle_message_with_retry(create_test_message("msg-009"), QosLevel::AtLeastOnce, retry)
+            .await
+            .unwrap();
+
+        let mut gaps = Vec::new();
+        for _ in 0..3 {
+            let before = Utc::now();
+            handler.retry_message("msg-009").await.unwrap();
+            let next = handler.next_retry_at("msg-009").await.unwrap();
+            gaps.push((next - before).num_seconds());
+        }
+        assert_eq!(gaps, vec![20, 40, 60]);
+
+        assert!(handler.due_for_retry(Utc::now()).await.is_empty());
+        let later = Utc::now() + chrono::Duration::seconds(61);
+        assert_eq!(
+            handler.due_for_retry(later).await,
+            vec!["msg-009".to_string()]
+        );
+    }
 }
@@ -1,12 +1,14 @@
 //! Message routing service
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{Mutex, RwLock};
 
-use uaip_core::error::UaipResult;
-use uaip_core::message::UaipMessage;
+use uaip_core::error::{UaipError, UaipResult};
+use uaip_core::message::{EntityType, UaipMessage};
 
+use crate::dispatch::RecipientHandler;
+use crate::encryption::PayloadEncryption;
 use crate::priority_queue::MessagePriorityQueue;
 use crate::qos::{QosHandler, QosLevel};
 
@@ -30,8 +32,21 @@ pub struct MessageRouter {
     routes: Arc<RwLock<HashMap<String, RouteEntry>>>,
     /// Message delivery statistics
     stats: Arc<RwLock<RouterStats>>,
+    /// Payload encryption for recipients covered by the encryption policy
+    encryption: Option<Arc<PayloadEncryption>>,
+    /// Delivery handlers by recipient entity type
+    handlers: HashMap<EntityType, Arc<dyn RecipientHandler>>,
+    /// Handler for entity types without a registered handler
+    default_handler: Option<Arc<dyn RecipientHandler>>,
+    /// Recipients whose messages are all delivered in submission order
+    ordered_recipients: Arc<RwLock<HashSet<String>>>,
+    /// Ordered messages awaiting delivery, in submission order per recipient
+    ordered_queues: Arc<RwLock<HashMap<String, OrderedQueue>>>,
 }
 
+/// In-order backlog of a single recipient
+type OrderedQueue = Arc<Mutex<VecDeque<UaipMessage>>>;
+
 /// Router statistics
 #[derive(Debug, Clone, Default)]
 pub struct RouterStats {
@@ -49,9 +64,43 @@ impl MessageRouter {
             qos_handler,
             routes: Arc::new(RwLock::new(HashMap::new())),
             stats: Arc::new(RwLock::new(RouterStats::default())),
+            encryption: None,
+            handlers: HashMap::new(),
+            default_handler: None,
+            ordered_recipients: Arc::new(RwLock::new(HashSet::new())),
+            ordered_queues: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 
+    /// Deliver messages for `entity_type` recipients through `handler`
+    pub fn with_handler(
+        mut self,
+        entity_type: EntityType,
+        handler: Arc<dyn RecipientHandler>,
+    ) -> Self {
+        self.handlers.insert(entity_type, handler);
+        self
+    }
+
+    /// Deliver messages for recipients of any other entity type through `handler`
+    pub fn with_default_handler(mut self, handler: Arc<dyn RecipientHandler>) -> Self {
+        self.default_handler = Some(handler);
+        self
+    }
+
+    /// Handler for a recipient entity type, falling back to the default handler
+    fn handler_for(&self, entity_type: &EntityType) -> Option<&Arc<dyn RecipientHandler>> {
+        self.handlers
+            .get(entity_type)
+            .or(self.default_handler.as_ref())
+    }
+
+    /// Encrypt payloads for the recipients covered by `encryption`'s policy
+    pub fn with_encryption(mut self, encryption: PayloadEncryption) -> Self {
+        self.encryption = Some(Arc::new(encryption));
+        self
+    }
+
     /// Register a recipient route
     ///
     /// # Arguments
@@ -97,20 +146,64 @@ impl MessageRouter {
         routes.contains_key(recipient_id)
     }
 
+    /// Deliver every message for a recipient in submission order, or stop doing so
+    ///
+    /// Individual messages can also opt in through `metadata.ordered`.
+    ///
+    /// # Arguments
+    /// * `recipient_id` - Recipient identifier
+    /// * `ordered` - Whether delivery to the recipient is ordered
+    pub async fn set_ordered_delivery(&self, recipient_id: &str, ordered: bool) {
+        let mut recipients = self.ordered_recipients.write().await;
+        if ordered {
+            recipients.insert(recipient_id.to_string());
+        } else {
+            recipients.remove(recipient_id);
+        }
+    }
+
+    /// Check if a message must be delivered in submission order
+    async fn is_ordered(&self, message: &UaipMessage) -> bool {
+        message.metadata.ordered
+            || self
+                .ordered_recipients
+                .read()
+                .await
+                .contains(&message.header.recipient.id)
+    }
+
     /// Route a message
     ///
+    /// The payload is encrypted first if the encryption policy covers the recipient,
+    /// so queued messages are held encrypted too. Messages for a connected recipient
+    /// are delivered by the handler for its entity type; without one, only QoS
+    /// tracking applies.
+    ///
+    /// Ordered messages join their recipient's in-order backlog instead of the
+    /// priority queue, and are never delivered ahead of an earlier ordered message
+    /// still awaiting delivery or retry.
+    ///
     /// # Arguments
     /// * `message` - Message to route
     ///
     /// # Returns
     /// * `Result<()>` - Success or error
     pub async fn route_message(&self, message: UaipMessage) -> UaipResult<()> {
+        let message = match &self.encryption {
+            Some(encryption) => encryption.encrypt_outbound(message)?,
+            None => message,
+        };
+
         // Update stats
         {
             let mut stats = self.stats.write().await;
             stats.messages_routed += 1;
         }
 
+        if self.is_ordered(&message).await {
+            return self.route_ordered(message).await;
+        }
+
         // Check if recipient route exists
         let recipient_id = &message.header.recipient.id;
         let route_exists = self.has_route(recipient_id).await;
@@ -125,18 +218,7 @@ impl MessageRouter {
             return Ok(());
         }
 
-        // Deliver message based on QoS level
-        let qos_level = match message.metadata.qos {
-            uaip_core::message::QosLevel::AtMostOnce => QosLevel::AtMostOnce,
-            uaip_core::message::QosLevel::AtLeastOnce => QosLevel::AtLeastOnce,
-            uaip_core::message::QosLevel::ExactlyOnce => QosLevel::ExactlyOnce,
-        };
-
-        match self
-            .qos_handler
-            .handle_message(message.clone(), qos_level)
-            .await
-        {
+        match self.deliver(&message).await {
             Ok(_) => {
                 let mut stats = self.stats.write().await;
                 stats.messages_delivered += 1;
@@ -155,9 +237,93 @@ impl MessageRouter {
         }
     }
 
+    /// Append an ordered message to its recipient's backlog and deliver what is due
+    async fn route_ordered(&self, message: UaipMessage) -> UaipResult<()> {
+        let queue = self
+            .ordered_queues
+            .write()
+            .await
+            .entry(message.header.recipient.id.clone())
+            .or_default()
+            .clone();
+
+        let mut pending = queue.lock().await;
+        pending.push_back(message);
+        let result = self.flush_ordered(&mut pending).await;
+
+        // The new message is last, so it is held if anything is
+        if !pending.is_empty() {
+            let mut stats = self.stats.write().await;
+            stats.messages_queued += 1;
+        }
+
+        result.map(|_| ())
+    }
+
+    /// Deliver a recipient's backlog front to back, stopping at the first failure
+    ///
+    /// # Returns
+    /// * `Result<usize>` - Number of messages delivered
+    async fn flush_ordered(&self, pending: &mut VecDeque<UaipMessage>) -> UaipResult<usize> {
+        let mut delivered = 0;
+
+        while let Some(message) = pending.front() {
+            if !self.has_route(&message.header.recipient.id).await {
+                break;
+            }
+
+            if let Err(e) = self.deliver(message).await {
+                let mut stats = self.stats.write().await;
+                stats.messages_failed += 1;
+                return Err(e);
+            }
+
+            pending.pop_front();
+            delivered += 1;
+            let mut stats = self.stats.write().await;
+            stats.messages_delivered += 1;
+        }
+
+        Ok(delivered)
+    }
+
+    /// Deliver a message through its recipient's handler, then apply QoS tracking
+    async fn deliver(&self, message: &UaipMessage) -> UaipResult<()> {
+        let qos_level = match message.metadata.qos {
+            uaip_core::message::QosLevel::AtMostOnce => QosLevel::AtMostOnce,
+            uaip_core::message::QosLevel::AtLeastOnce => QosLevel::AtLeastOnce,
+            uaip_core::message::QosLevel::ExactlyOnce => QosLevel::ExactlyOnce,
+        };
+
+        if let Some(handler) = self.handler_for(&message.header.recipient.entity_type) {
+            handler.deliver(message).await?;
+        }
+        self.qos_handler
+            .handle_message(message.clone(), qos_level)
+            .await
+    }
+
+    /// Accept a message arriving at the router, decrypting an encrypted payload
+    ///
+    /// # Arguments
+    /// * `message` - Received message
+    ///
+    /// # Returns
+    /// * `Result<UaipMessage>` - Message with a plaintext payload
+    pub fn receive_message(&self, message: UaipMessage) -> UaipResult<UaipMessage> {
+        match &self.encryption {
+            Some(encryption) => encryption.decrypt_inbound(message),
+            None if crate::encryption::is_encrypted(&message) => Err(UaipError::EncryptionError(
+                "Received an encrypted message but no keys are configured".to_string(),
+            )),
+            None => Ok(message),
+        }
+    }
+
     /// Process queued messages
     ///
-    /// Attempts to deliver messages from the priority queue
+    /// Attempts to deliver messages from the priority queue, then each recipient's
+    /// ordered backlog in submission order
     ///
     /// # Returns
     /// * `Result<usize>` - Number of messages processed
@@ -180,6 +346,15 @@ impl MessageRouter {
             }
         }
 
+        let queues: Vec<OrderedQueue> =
+            self.ordered_queues.read().await.values().cloned().collect();
+        for queue in queues {
+            // A failure holds back only that recipient's backlog
+            if let Ok(delivered) = self.flush_ordered(&mut *queue.lock().await).await {
+                processed += delivered;
+            }
+        }
+
         Ok(processed)
     }
 
@@ -197,7 +372,11 @@ impl MessageRouter {
     /// # Returns
     /// * `usize` - Number of queued messages
     pub async fn queue_size(&self) -> usize {
-        self.queue.len().await
+        let mut size = self.queue.len().await;
+        for queue in self.ordered_queues.read().await.values() {
+            size += queue.lock().await.len();
+        }
+        size
     }
 
     /// Get number of registered routes
@@ -212,6 +391,7 @@ impl MessageRouter {
     /// Clear all queued messages
     pub async fn clear_queue(&self) {
         self.queue.clear().await;
+        self.ordered_queues.write().await.clear();
     }
 }
 
@@ -262,6 +442,7 @@ mod tests {
                 ack_timeout: None,
                 retry_policy: None,
                 qos: uaip_core::message::QosLevel::AtMostOnce,
+                ordered: false,
                 content_type: None,
                 user_data: None,
             },
@@ -315,6 +496,231 @@ mod tests {
         assert_eq!(stats.messages_queued, 1);
     }
 
+    #[tokio::test]
+    async fn test_encrypted_routing_round_trip() {
+        use crate::encryption::EncryptionPolicy;
+        use uaip_security::encryption::EncryptionEngine;
+        use uaip_security::key_ring::KeyRing;
+
+        let key = EncryptionEngine::generate_key();
+        let encryption = || {
+            PayloadEncryption::new(
+                KeyRing::new().with_key("k1", EncryptionEngine::from_key(&key).unwrap()),
+                EncryptionPolicy::default().with_recipient("recipient-1", "k1"),
+            )
+            .unwrap()
+        };
+        let queue = Arc::new(MessagePriorityQueue::new());
+        let router = MessageRouter::new(queue.clone(), Arc::new(QosHandler::new()))
+            .with_encryption(encryption());
+
+        let mut message = create_test_message("sender-1", "recipient-1", Priority::Normal);
+        message.payload.capability = Some("relay".to_string());
+        router.route_message(message.clone()).await.unwrap();
+
+        // Held for the offline recipient with an encrypted payload
+        let queued = queue.pop().await.unwrap();
+        assert!(queued.security.encryption.is_some());
+        assert!(queued.payload.capability.is_none());
+
+        let receiver = MessageRouter::new(
+            Arc::new(MessagePriorityQueue::new()),
+            Arc::new(QosHandler::new()),
+        );
+        assert!(receiver.receive_message(queued.clone()).is_err());
+
+        let receiver = receiver.with_encryption(encryption());
+        assert_eq!(receiver.receive_message(queued).unwrap(), message);
+    }
+
+    /// Records the recipients of delivered messages
+    #[derive(Default)]
+    struct RecordingHandler {
+        delivered: std::sync::Mutex<Vec<String>>,
+        fail: bool,
+    }
+
+    #[async_trait::async_trait]
+    impl RecipientHandler for RecordingHandler {
+        async fn deliver(&self, message: &UaipMessage) -> UaipResult<()> {
+            if self.fail {
+                return Err(UaipError::ConnectionError("transport down".to_string()));
+            }
+            self.delivered
+                .lock()
+                .unwrap()
+                .push(message.header.recipient.id.clone());
+            Ok(())
+        }
+    }
+
+    impl RecordingHandler {
+        fn delivered(&self) -> Vec<String> {
+            self.delivered.lock().unwrap().clone()
+        }
+    }
+
+    async fn route_to(router: &MessageRouter, recipient_id: &str, entity_type: EntityType) {
+        router
+            .register_route(recipient_id.to_string())
+            .await
+            .unwrap();
+        let mut message = create_test_message("sender-1", recipient_id, Priority::Normal);
+        message.header.recipient.entity_type = entity_type;
+        router.route_message(message).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_by_recipient_entity_type() {
+        let devices = Arc::new(RecordingHandler::default());
+        let agents = Arc::new(RecordingHandler::default());
+        let fallback = Arc::new(RecordingHandler::default());
+        let router = MessageRouter::new(
+            Arc::new(MessagePriorityQueue::new()),
+            Arc::new(QosHandler::new()),
+        )
+        .with_handler(EntityType::Device, devices.clone())
+        .with_handler(EntityType::AiAgent, agents.clone())
+        .with_default_handler(fallback.clone());
+
+        route_to(&router, "thermostat-1", EntityType::Device).await;
+        route_to(&router, "agent-1", EntityType::AiAgent).await;
+        route_to(&router, "operator-1", EntityType::User).await;
+
+        assert_eq!(devices.delivered(), ["thermostat-1"]);
+        assert_eq!(agents.delivered(), ["agent-1"]);
+        assert_eq!(fallback.delivered(), ["operator-1"]);
+        assert_eq!(router.get_stats().await.messages_delivered, 3);
+    }
+
+    #[tokio::test]
+    async fn test_failed_dispatch_is_queued_for_retry() {
+        let router = MessageRouter::new(
+            Arc::new(MessagePriorityQueue::new()),
+            Arc::new(QosHandler::new()),
+        )
+        .with_handler(
+            EntityType::Device,
+            Arc::new(RecordingHandler {
+                fail: true,
+                ..Default::default()
+            }),
+        );
+        router
+            .register_route("thermostat-1".to_string())
+            .await
+            .unwrap();
+
+        let mut message = create_test_message("sender-1", "thermostat-1", Priority::Normal);
+        message.header.recipient.entity_type = EntityType::Device;
+        assert!(router.route_message(message).await.is_err());
+
+        // Agents have no handler and no default, so only QoS tracking applies
+        route_to(&router, "agent-1", EntityType::AiAgent).await;
+
+        let stats = router.get_stats().await;
+        assert_eq!(stats.messages_failed, 1);
+        assert_eq!(stats.messages_delivered, 1);
+        assert_eq!(router.queue_size().await, 1);
+    }
+
+    /// Records the IDs of delivered messages, failing the first `failures` deliveries
+    #[derive(Default)]
+    struct FlakyHandler {
+        failures: std::sync::atomic::AtomicUsize,
+        delivered: std::sync::Mutex<Vec<String>>,
+    }
+
+    #[async_trait::async_trait]
+    impl RecipientHandler for FlakyHandler {
+        async fn deliver(&self, message: &UaipMessage) -> UaipResult<()> {
+            use std::sync::atomic::Ordering;
+
+            let failing = self
+                .failures
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                .is_ok();
+            if failing {
+                return Err(UaipError::ConnectionError("transport down".to_string()));
+            }
+            self.delivered
+                .lock()
+                .unwrap()
+                .push(message.header.message_id.clone());
+            Ok(())
+        }
+    }
+
+    fn flaky_router(failures: usize) -> (MessageRouter, Arc<FlakyHandler>) {
+        let handler = Arc::new(FlakyHandler {
+            failures: failures.into(),
+            ..Default::default()
+        });
+        let router = MessageRouter::new(
+            Arc::new(MessagePriorityQueue::new()),
+            Arc::new(QosHandler::new()),
+        )
+        .with_handler(EntityType::Device, handler.clone());
+        (router, handler)
+    }
+
+    fn device_command(device_id: &str) -> UaipMessage {
+        let mut message = create_test_message("agent-1", device_id, Priority::Normal);
+        message.header.recipient.entity_type = EntityType::Device;
+        message
+    }
+
+    #[tokio::test]
+    async fn test_ordered_recipient_retry_is_not_overtaken() {
+        let (router, handler) = flaky_router(1);
+        router.register_route("valve-1".to_string()).await.unwrap();
+        router.set_ordered_delivery("valve-1", true).await;
+
+        let first = device_command("valve-1");
+        let second = device_command("valve-1");
+        assert!(router.route_message(first.clone()).await.is_err());
+        assert_eq!(router.queue_size().await, 1);
+
+        // The failed first command is retried ahead of the second
+        router.route_message(second.clone()).await.unwrap();
+        assert_eq!(
+            *handler.delivered.lock().unwrap(),
+            [first.header.message_id, second.header.message_id]
+        );
+        assert_eq!(router.queue_size().await, 0);
+
+        let stats = router.get_stats().await;
+        assert_eq!(stats.messages_failed, 1);
+        assert_eq!(stats.messages_delivered, 2);
+    }
+
+    #[tokio::test]
+    async fn test_ordered_messages_wait_behind_failed_retry() {
+        let (router, handler) = flaky_router(2);
+        router.register_route("valve-1".to_string()).await.unwrap();
+
+        let first = device_command("valve-1").with_ordered_delivery(true);
+        let second = device_command("valve-1").with_ordered_delivery(true);
+        assert!(router.route_message(first.clone()).await.is_err());
+        assert!(router.route_message(second.clone()).await.is_err());
+        assert!(handler.delivered.lock().unwrap().is_empty());
+
+        // An unordered message to the same device is not held back
+        let unordered = device_command("valve-1");
+        router.route_message(unordered.clone()).await.unwrap();
+
+        assert_eq!(router.process_queue().await.unwrap(), 2);
+        assert_eq!(
+            *handler.delivered.lock().unwrap(),
+            [
+                unordered.header.message_id,
+                first.header.message_id,
+                second.header.message_id
+            ]
+        );
+        assert_eq!(router.queue_size().await, 0);
+    }
+
     #[tokio::test]
     async fn test_router_stats() {
         let queue = Arc::new(MessagePriorityQueue::new());
@@ -18,6 +18,9 @@ pub const KEY_SIZE: usize = 32;
 /// Nonce size for AES-GCM (12 bytes)
 pub const NONCE_SIZE: usize = 12;
 
+/// Algorithm name recorded alongside encrypted data
+pub const ALGORITHM: &str = "AES-256-GCM";
+
 /// Encrypted data with nonce
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EncryptedData {
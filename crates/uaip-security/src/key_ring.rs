@@ -0,0 +1,91 @@
+//! Key Ring
+//!
+//! Named AES-256-GCM keys. Encrypted data records the ID of the key it was encrypted
+//! with, so keys can be rotated by adding a new key while the old one still decrypts.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::encryption::{EncryptionEngine, EncryptionError};
+
+/// Encryption keys by key ID
+#[derive(Debug, Clone, Default)]
+pub struct KeyRing {
+    keys: HashMap<String, Arc<EncryptionEngine>>,
+}
+
+impl KeyRing {
+    /// Create an empty key ring
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a key, replacing any key with the same ID
+    pub fn with_key(mut self, key_id: impl Into<String>, engine: EncryptionEngine) -> Self {
+        self.keys.insert(key_id.into(), Arc::new(engine));
+        self
+    }
+
+    /// Add a base64-encoded key, replacing any key with the same ID
+    pub fn with_base64_key(
+        self,
+        key_id: impl Into<String>,
+        key_b64: &str,
+    ) -> Result<Self, EncryptionError> {
+        Ok(self.with_key(key_id, EncryptionEngine::from_base64_key(key_b64)?))
+    }
+
+    /// Get a key by ID
+    pub fn get(&self, key_id: &str) -> Option<&EncryptionEngine> {
+        self.keys.get(key_id).map(Arc::as_ref)
+    }
+
+    /// Check if a key ID is known
+    pub fn contains(&self, key_id: &str) -> bool {
+        self.keys.contains_key(key_id)
+    }
+
+    /// Number of keys
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Check if the ring has no keys
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+
+    #[test]
+    fn test_keys_are_looked_up_by_id() {
+        let key = EncryptionEngine::generate_key();
+        let ring = KeyRing::new()
+            .with_base64_key("2026-10", &BASE64.encode(key))
+            .unwrap()
+            .with_key("2026-11", EncryptionEngine::new());
+
+        assert_eq!(ring.len(), 2);
+        assert!(ring.contains("2026-10"));
+        assert!(ring.get("2025-01").is_none());
+
+        let encrypted = EncryptionEngine::from_key(&key)
+            .unwrap()
+            .encrypt_string("reading")
+            .unwrap();
+        let decrypted = ring.get("2026-10").unwrap().decrypt_string(&encrypted);
+        assert_eq!(decrypted.unwrap(), "reading");
+        assert!(ring.get("2026-11").unwrap().decrypt(&encrypted).is_err());
+
+        assert_eq!(
+            KeyRing::new()
+                .with_base64_key("bad", "not-a-key")
+                .unwrap_err(),
+            EncryptionError::InvalidKey
+        );
+    }
+}
@@ -3,5 +3,6 @@
 //! This crate provides encryption, TLS configuration, and security utilities.
 
 pub mod encryption;
+pub mod key_ring;
 pub mod tls;
 pub mod validation;
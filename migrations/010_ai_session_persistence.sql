@@ -0,0 +1,5 @@
+-- Persist AI session context so sessions can be rehydrated after a hub restart
+
+ALTER TABLE ai_sessions
+ADD COLUMN IF NOT EXISTS context_order JSONB DEFAULT '[]',
+ADD COLUMN IF NOT EXISTS devices JSONB DEFAULT '[]';
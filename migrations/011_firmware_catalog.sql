@@ -0,0 +1,10 @@
+-- Firmware catalog: latest available firmware per device type
+
+CREATE TABLE IF NOT EXISTS firmware_catalog (
+    device_type VARCHAR(100) PRIMARY KEY,
+    latest_version VARCHAR(50) NOT NULL,
+    release_notes TEXT,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+COMMENT ON TABLE firmware_catalog IS 'Latest available firmware version for each device type';
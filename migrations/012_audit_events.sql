@@ -0,0 +1,21 @@
+-- Tamper-evident audit log
+-- Each entry stores the hash of the previous entry, forming a verifiable chain
+
+CREATE TABLE IF NOT EXISTS audit_events (
+    sequence BIGINT PRIMARY KEY,
+    timestamp TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    actor VARCHAR(255) NOT NULL,
+    action VARCHAR(100) NOT NULL,
+    target VARCHAR(255),
+    outcome VARCHAR(20) NOT NULL CHECK (outcome IN ('success', 'failure')),
+    details JSONB NOT NULL DEFAULT '{}',
+    prev_hash CHAR(64) NOT NULL,
+    hash CHAR(64) NOT NULL UNIQUE
+);
+
+CREATE INDEX IF NOT EXISTS idx_audit_events_timestamp ON audit_events(timestamp);
+CREATE INDEX IF NOT EXISTS idx_audit_events_actor ON audit_events(actor);
+CREATE INDEX IF NOT EXISTS idx_audit_events_action ON audit_events(action);
+CREATE INDEX IF NOT EXISTS idx_audit_events_target ON audit_events(target);
+
+COMMENT ON TABLE audit_events IS 'Hash-chained audit log of security-relevant actions';
@@ -0,0 +1,16 @@
+-- Device telemetry readings
+
+CREATE TABLE IF NOT EXISTS device_telemetry (
+    id BIGSERIAL PRIMARY KEY,
+    device_id VARCHAR(255) NOT NULL,
+    metric VARCHAR(100) NOT NULL,
+    value JSONB NOT NULL,
+    unit VARCHAR(50),
+    recorded_at TIMESTAMPTZ NOT NULL,
+    received_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_device_telemetry_device_metric_time
+ON device_telemetry(device_id, metric, recorded_at DESC);
+
+COMMENT ON TABLE device_telemetry IS 'Telemetry readings reported by devices';
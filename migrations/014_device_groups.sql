@@ -0,0 +1,13 @@
+-- Device groups (rooms, zones, sites) with nested membership
+
+CREATE TABLE IF NOT EXISTS device_groups (
+    id VARCHAR(255) PRIMARY KEY,
+    name VARCHAR(255) NOT NULL,
+    description TEXT,
+    device_ids TEXT[] NOT NULL DEFAULT '{}',
+    subgroup_ids TEXT[] NOT NULL DEFAULT '{}',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+COMMENT ON TABLE device_groups IS 'Named device groups; subgroup_ids nest other groups and must not form cycles';
@@ -0,0 +1,22 @@
+-- Downsampled device telemetry
+
+CREATE TABLE IF NOT EXISTS device_telemetry_rollups (
+    device_id VARCHAR(255) NOT NULL,
+    metric VARCHAR(100) NOT NULL,
+    resolution VARCHAR(10) NOT NULL CHECK (resolution IN ('hour', 'day')),
+    bucket_start TIMESTAMPTZ NOT NULL,
+    sample_count BIGINT NOT NULL,
+    avg_value DOUBLE PRECISION,
+    min_value DOUBLE PRECISION,
+    max_value DOUBLE PRECISION,
+    PRIMARY KEY (device_id, metric, resolution, bucket_start)
+);
+
+CREATE INDEX IF NOT EXISTS idx_device_telemetry_rollups_resolution_bucket
+ON device_telemetry_rollups(resolution, bucket_start);
+
+-- Retention prunes raw readings by age across all devices
+CREATE INDEX IF NOT EXISTS idx_device_telemetry_recorded_at
+ON device_telemetry(recorded_at);
+
+COMMENT ON TABLE device_telemetry_rollups IS 'Hourly and daily aggregates of expired telemetry readings';
@@ -0,0 +1,33 @@
+-- Device status transitions, used to compute availability
+
+CREATE TABLE IF NOT EXISTS device_status_history (
+    id BIGSERIAL PRIMARY KEY,
+    device_id VARCHAR(255) NOT NULL,
+    status TEXT NOT NULL,
+    changed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_device_status_history_device_time
+ON device_status_history(device_id, changed_at DESC);
+
+-- Function to record device status changes
+CREATE OR REPLACE FUNCTION record_device_status_change()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'INSERT' OR OLD.status IS DISTINCT FROM NEW.status THEN
+        INSERT INTO device_status_history (device_id, status)
+        VALUES (NEW.device_id, NEW.status);
+    END IF;
+
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Trigger to record every status a device enters
+DROP TRIGGER IF EXISTS trg_device_status_history ON devices;
+CREATE TRIGGER trg_device_status_history
+    AFTER INSERT OR UPDATE OF status ON devices
+    FOR EACH ROW
+    EXECUTE FUNCTION record_device_status_change();
+
+COMMENT ON TABLE device_status_history IS 'Status transitions recorded by trigger on devices; source for availability';
@@ -0,0 +1,21 @@
+-- Scenario execution history
+
+CREATE TABLE IF NOT EXISTS scenario_executions (
+    id VARCHAR(255) PRIMARY KEY,
+    scenario_id VARCHAR(255) NOT NULL,
+    trigger JSONB NOT NULL,
+    state VARCHAR(20) NOT NULL
+        CHECK (state IN ('active', 'inactive', 'executing', 'completed', 'failed')),
+    trigger_context JSONB NOT NULL DEFAULT '{}',
+    actions_executed JSONB NOT NULL DEFAULT '[]',
+    error TEXT,
+    started_at TIMESTAMPTZ NOT NULL,
+    completed_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_scenario_executions_scenario_started
+ON scenario_executions(scenario_id, started_at DESC);
+
+CREATE INDEX IF NOT EXISTS idx_scenario_executions_state ON scenario_executions(state);
+
+COMMENT ON TABLE scenario_executions IS 'Finished scenario executions, newest first by started_at';
@@ -0,0 +1,28 @@
+-- Persistent rule and scenario definitions
+-- Definitions are stored as JSON documents tagged with the schema version they were
+-- written with, so older documents can be upgraded when loaded
+
+CREATE TABLE IF NOT EXISTS automation_rules (
+    id VARCHAR(255) PRIMARY KEY,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    schema_version INTEGER NOT NULL,
+    definition JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_automation_rules_enabled ON automation_rules(enabled);
+
+CREATE TABLE IF NOT EXISTS automation_scenarios (
+    id VARCHAR(255) PRIMARY KEY,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    schema_version INTEGER NOT NULL,
+    definition JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_automation_scenarios_enabled ON automation_scenarios(enabled);
+
+COMMENT ON TABLE automation_rules IS 'Rule engine definitions, loaded into the hub at startup';
+COMMENT ON TABLE automation_scenarios IS 'Scenario engine definitions, loaded into the hub at startup';
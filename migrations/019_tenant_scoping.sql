@@ -0,0 +1,17 @@
+-- Tenant scoping
+-- Devices, media and accounts belong to a tenant. Tokens carry the tenant of the user or
+-- agent they were issued to, and the hub scopes every device and media query to it.
+-- Existing rows join the implicit 'default' tenant used when auth is absent.
+
+ALTER TABLE devices ADD COLUMN IF NOT EXISTS tenant_id VARCHAR(255) NOT NULL DEFAULT 'default';
+ALTER TABLE media_files ADD COLUMN IF NOT EXISTS tenant_id VARCHAR(255) NOT NULL DEFAULT 'default';
+ALTER TABLE users ADD COLUMN IF NOT EXISTS tenant_id VARCHAR(255) NOT NULL DEFAULT 'default';
+ALTER TABLE ai_agents ADD COLUMN IF NOT EXISTS tenant_id VARCHAR(255) NOT NULL DEFAULT 'default';
+
+CREATE INDEX IF NOT EXISTS idx_devices_tenant_device ON devices(tenant_id, device_id);
+CREATE INDEX IF NOT EXISTS idx_media_files_tenant ON media_files(tenant_id, uploaded_at DESC);
+
+COMMENT ON COLUMN devices.tenant_id IS 'Tenant that owns the device';
+COMMENT ON COLUMN media_files.tenant_id IS 'Tenant that owns the media file';
+COMMENT ON COLUMN users.tenant_id IS 'Tenant the user signs in to';
+COMMENT ON COLUMN ai_agents.tenant_id IS 'Tenant the agent signs in to';
@@ -0,0 +1,18 @@
+-- API keys for machine clients
+-- Only the SHA-256 hash of each key is stored; the key itself is shown once when issued
+
+CREATE TABLE IF NOT EXISTS api_keys (
+    id UUID PRIMARY KEY,
+    name VARCHAR(255) NOT NULL,
+    subject VARCHAR(255) NOT NULL,
+    scopes TEXT[] NOT NULL DEFAULT '{}',
+    tenant_id VARCHAR(255) NOT NULL DEFAULT 'default',
+    key_hash CHAR(64) NOT NULL UNIQUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ,
+    revoked_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_api_keys_tenant ON api_keys(tenant_id, created_at DESC);
+
+COMMENT ON TABLE api_keys IS 'Hashed API keys resolving to a subject, scopes and tenant';
@@ -0,0 +1,16 @@
+-- Device command queue visibility and cancellation
+-- Commands are scoped to the tenant of the device they target and can be cancelled
+-- until they are delivered
+
+ALTER TABLE message_log
+    ADD COLUMN IF NOT EXISTS tenant_id VARCHAR(255) NOT NULL DEFAULT 'default';
+
+ALTER TABLE message_log
+    ADD COLUMN IF NOT EXISTS cancelled_at TIMESTAMPTZ;
+
+-- Supports: SELECT * FROM message_log WHERE tenant_id = ? AND recipient_id = ? AND status = ?
+CREATE INDEX IF NOT EXISTS idx_message_log_tenant_recipient_status
+ON message_log(tenant_id, recipient_id, status, created_at DESC);
+
+COMMENT ON COLUMN message_log.tenant_id IS 'Tenant owning the recipient device';
+COMMENT ON COLUMN message_log.cancelled_at IS 'When an undelivered command was cancelled';
@@ -0,0 +1,20 @@
+-- Device provisioning tokens for zero-touch onboarding
+-- A token is issued into a tenant for one device type and is consumed by the first device
+-- that registers with it. Only the SHA-256 hash of each token is stored.
+
+CREATE TABLE IF NOT EXISTS provisioning_tokens (
+    id UUID PRIMARY KEY,
+    device_type VARCHAR(100) NOT NULL,
+    tenant_id VARCHAR(255) NOT NULL DEFAULT 'default',
+    token_hash CHAR(64) NOT NULL UNIQUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ NOT NULL,
+    consumed_at TIMESTAMPTZ,
+    device_id VARCHAR(255)
+);
+
+CREATE INDEX IF NOT EXISTS idx_provisioning_tokens_tenant
+ON provisioning_tokens(tenant_id, created_at DESC);
+
+COMMENT ON TABLE provisioning_tokens IS 'Single-use, expiring tokens devices present to self-register';
+COMMENT ON COLUMN provisioning_tokens.device_id IS 'Device registered with the token, once consumed';
@@ -0,0 +1,16 @@
+-- Device command delivery and results
+-- Devices fetch their pending commands, which marks them delivered, and report a result
+-- that completes or fails each one
+
+ALTER TABLE message_log
+    ADD COLUMN IF NOT EXISTS delivered_at TIMESTAMPTZ;
+
+ALTER TABLE message_log
+    ADD COLUMN IF NOT EXISTS completed_at TIMESTAMPTZ;
+
+ALTER TABLE message_log
+    ADD COLUMN IF NOT EXISTS result JSONB;
+
+COMMENT ON COLUMN message_log.delivered_at IS 'When the recipient device fetched the command';
+COMMENT ON COLUMN message_log.completed_at IS 'When the device reported the command result';
+COMMENT ON COLUMN message_log.result IS 'Result reported by the device (success, message, data)';
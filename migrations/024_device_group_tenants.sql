@@ -0,0 +1,10 @@
+-- Tenant scoping for device groups
+-- Groups belong to the tenant that created them; group handlers, group commands and
+-- group-scoped command grants only see the caller's tenant. Existing groups join the
+-- implicit 'default' tenant.
+
+ALTER TABLE device_groups ADD COLUMN IF NOT EXISTS tenant_id VARCHAR(255) NOT NULL DEFAULT 'default';
+
+CREATE INDEX IF NOT EXISTS idx_device_groups_tenant ON device_groups(tenant_id, id);
+
+COMMENT ON COLUMN device_groups.tenant_id IS 'Tenant that owns the group';
@@ -137,8 +137,11 @@ pub struct Device {
 
 #[derive(Debug, Deserialize)]
 pub struct DeviceListResponse {
-    pub devices: Vec<Device>,
+    pub items: Vec<Device>,
     pub total: usize,
+    pub page: i64,
+    pub per_page: i64,
+    pub has_more: bool,
 }
 
 #[derive(Debug, Serialize)]
@@ -3,7 +3,10 @@ use clap::Parser;
 use futures_util::{SinkExt, StreamExt};
 use rand::Rng;
 use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 use std::time::Duration;
+use tokio::sync::mpsc;
 use tokio::time;
 use tokio_tungstenite::{connect_async, tungstenite::Message};
 use tracing::{error, info, warn};
@@ -96,6 +99,15 @@ struct CommandResponse {
     message: String,
 }
 
+/// A command handed out by the hub's long-poll endpoint
+#[derive(Debug, Deserialize)]
+struct QueuedCommand {
+    message_id: String,
+    action: String,
+    #[serde(default)]
+    parameters: serde_json::Value,
+}
+
 #[derive(Parser, Debug)]
 #[command(name = "UAIP Device Simulator")]
 #[command(about = "Simulates IoT devices for UAIP Hub testing", long_about = None)]
@@ -119,11 +131,218 @@ struct Args {
     /// Enable verbose logging
     #[arg(short, long)]
     verbose: bool,
+
+    /// Fraction of commands answered with an injected failure (0.0-1.0)
+    #[arg(long, default_value = "0", value_parser = parse_rate)]
+    error_rate: f64,
+
+    /// Fraction of commands left unanswered (0.0-1.0)
+    #[arg(long, default_value = "0", value_parser = parse_rate)]
+    drop_rate: f64,
+
+    /// Fraction of commands answered with a malformed payload (0.0-1.0)
+    #[arg(long, default_value = "0", value_parser = parse_rate)]
+    malformed_rate: f64,
+
+    /// Delay added before answering each command, in milliseconds
+    #[arg(long, default_value = "0")]
+    latency_ms: u64,
+
+    /// HTTP URL of UAIP Hub to long-poll for queued commands (e.g. http://localhost:8443)
+    #[arg(long)]
+    command_url: Option<String>,
+
+    /// Bearer token sent with command polls and results
+    #[arg(long, env = "UAIP_TOKEN")]
+    token: Option<String>,
+}
+
+/// Seconds each command poll waits on the hub
+const COMMAND_WAIT_SECS: u64 = 30;
+
+/// Long-polls the hub for a device's queued commands and posts back their results
+#[derive(Clone)]
+struct CommandListener {
+    client: reqwest::Client,
+    base_url: String,
+    token: Option<String>,
+}
+
+impl CommandListener {
+    fn new(base_url: &str, token: Option<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token,
+        }
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        let request = self.client.request(method, format!("{}{}", self.base_url, path));
+        match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    /// Wait for the device's next command, `None` if none was queued in time
+    async fn next_command(&self, device_id: &str) -> Result<Option<QueuedCommand>> {
+        let response = self
+            .request(
+                reqwest::Method::GET,
+                &format!("/api/v1/devices/{}/commands/next", device_id),
+            )
+            .query(&[("wait_secs", COMMAND_WAIT_SECS)])
+            .timeout(Duration::from_secs(COMMAND_WAIT_SECS + 10))
+            .send()
+            .await?
+            .error_for_status()?;
+
+        if response.status() == reqwest::StatusCode::NO_CONTENT {
+            return Ok(None);
+        }
+        Ok(Some(response.json().await?))
+    }
+
+    /// Post the text a device answered a command with as the command's result
+    async fn report(&self, device_id: &str, message_id: &str, body: String) -> Result<()> {
+        let response = self
+            .request(
+                reqwest::Method::POST,
+                &format!("/api/v1/devices/{}/commands/{}/result", device_id, message_id),
+            )
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            warn!("Hub rejected result of command {}: {}", message_id, response.status());
+        }
+        Ok(())
+    }
+
+    /// Forward the device's commands to `commands` until the receiver is dropped
+    async fn listen(self, device_id: String, commands: mpsc::Sender<QueuedCommand>) {
+        while !commands.is_closed() {
+            match self.next_command(&device_id).await {
+                Ok(Some(command)) => {
+                    if commands.send(command).await.is_err() {
+                        break;
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    warn!("Failed to poll commands for device {}: {}", device_id, e);
+                    time::sleep(Duration::from_secs(5)).await;
+                }
+            }
+        }
+    }
+}
+
+fn parse_rate(value: &str) -> Result<f64, String> {
+    let rate: f64 = value.parse().map_err(|_| format!("'{}' is not a number", value))?;
+    if !(0.0..=1.0).contains(&rate) {
+        return Err(format!("{} is not between 0.0 and 1.0", rate));
+    }
+    Ok(rate)
+}
+
+/// Deliberate misbehaviour when answering commands, for resilience testing
+#[derive(Debug, Clone, Default)]
+struct FaultConfig {
+    error_rate: f64,
+    drop_rate: f64,
+    malformed_rate: f64,
+    latency: Duration,
+}
+
+impl FaultConfig {
+    fn from_args(args: &Args) -> Result<Self> {
+        let total = args.error_rate + args.drop_rate + args.malformed_rate;
+        if total > 1.0 {
+            anyhow::bail!(
+                "--error-rate, --drop-rate and --malformed-rate add up to {}, more than 1.0",
+                total
+            );
+        }
+
+        Ok(Self {
+            error_rate: args.error_rate,
+            drop_rate: args.drop_rate,
+            malformed_rate: args.malformed_rate,
+            latency: Duration::from_millis(args.latency_ms),
+        })
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.error_rate + self.drop_rate + self.malformed_rate > 0.0 || !self.latency.is_zero()
+    }
+
+    /// Pick the fault, if any, to inject into one command response
+    fn roll(&self, rng: &mut impl Rng) -> Option<Fault> {
+        let roll: f64 = rng.gen();
+        if roll < self.error_rate {
+            Some(Fault::Error)
+        } else if roll < self.error_rate + self.drop_rate {
+            Some(Fault::Drop)
+        } else if roll < self.error_rate + self.drop_rate + self.malformed_rate {
+            Some(Fault::Malformed)
+        } else {
+            None
+        }
+    }
+}
+
+/// A fault injected into a command response
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Fault {
+    /// Report failure without running the command
+    Error,
+    /// Send no response
+    Drop,
+    /// Send a payload that is not a valid response
+    Malformed,
+}
+
+/// Injected fault counts, shared by all simulated devices
+#[derive(Debug, Default)]
+struct FaultStats {
+    commands: AtomicU64,
+    errors: AtomicU64,
+    dropped: AtomicU64,
+    malformed: AtomicU64,
+}
+
+impl FaultStats {
+    fn record(&self, fault: Option<Fault>) {
+        self.commands.fetch_add(1, Ordering::Relaxed);
+        let counter = match fault {
+            Some(Fault::Error) => &self.errors,
+            Some(Fault::Drop) => &self.dropped,
+            Some(Fault::Malformed) => &self.malformed,
+            None => return,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn report(&self) {
+        info!(
+            "Injected faults: {} errors, {} dropped, {} malformed out of {} commands",
+            self.errors.load(Ordering::Relaxed),
+            self.dropped.load(Ordering::Relaxed),
+            self.malformed.load(Ordering::Relaxed),
+            self.commands.load(Ordering::Relaxed),
+        );
+    }
 }
 
 struct DeviceSimulator {
     config: DeviceConfig,
     state: DeviceState,
+    faults: FaultConfig,
+    fault_stats: Arc<FaultStats>,
 }
 
 #[derive(Debug, Clone)]
@@ -160,9 +379,70 @@ impl DeviceSimulator {
         Self {
             config,
             state: DeviceState::default(),
+            faults: FaultConfig::default(),
+            fault_stats: Arc::default(),
         }
     }
 
+    /// Inject faults into command responses, counting them in `stats`
+    fn with_faults(mut self, faults: FaultConfig, stats: Arc<FaultStats>) -> Self {
+        self.faults = faults;
+        self.fault_stats = stats;
+        self
+    }
+
+    /// Run a command and build the text to send back, `None` if no response is sent
+    fn respond(&mut self, cmd: CommandMessage, rng: &mut impl Rng) -> Result<Option<String>> {
+        let fault = self.faults.roll(rng);
+        self.fault_stats.record(fault);
+
+        let (success, message) = match fault {
+            Some(Fault::Drop) => return Ok(None),
+            Some(Fault::Malformed) => {
+                return Ok(Some(format!("{{\"device_id\": \"{}\", \"succ", self.config.id)))
+            }
+            Some(Fault::Error) => (false, "Injected failure".to_string()),
+            None => match self.handle_command(&cmd.command, &cmd.params) {
+                Ok(msg) => (true, msg),
+                Err(e) => (false, e.to_string()),
+            },
+        };
+
+        let response = CommandResponse {
+            device_id: self.config.id.clone(),
+            command: cmd.command,
+            success,
+            message,
+        };
+        Ok(Some(serde_json::to_string(&response)?))
+    }
+
+    /// Run a command fetched from the hub and report the result back
+    async fn answer_queued(
+        &mut self,
+        listener: &CommandListener,
+        command: QueuedCommand,
+    ) -> Result<()> {
+        info!("Received queued command '{}' for device {}", command.action, self.config.name);
+
+        if !self.faults.latency.is_zero() {
+            time::sleep(self.faults.latency).await;
+        }
+
+        let cmd = CommandMessage {
+            command: command.action,
+            device_id: self.config.id.clone(),
+            params: command.parameters,
+        };
+        let response = self.respond(cmd, &mut rand::thread_rng())?;
+        if let Some(body) = response {
+            listener
+                .report(&self.config.id, &command.message_id, body)
+                .await?;
+        }
+        Ok(())
+    }
+
     fn generate_data(&mut self) -> DeviceData {
         let mut rng = rand::thread_rng();
 
@@ -222,7 +502,7 @@ impl DeviceSimulator {
                 resolution: "1920x1080".to_string(),
             },
             DeviceType::SmartPlug => {
-                let power = if self.state.plug_on {
+                let power: f64 = if self.state.plug_on {
                     rng.gen_range(50.0..150.0)
                 } else {
                     0.0
@@ -290,7 +570,7 @@ impl DeviceSimulator {
         }
     }
 
-    async fn run(&mut self, url: String) -> Result<()> {
+    async fn run(&mut self, url: String, listener: Option<CommandListener>) -> Result<()> {
         info!(
             "Connecting device {} ({:?}) to {}",
             self.config.name, self.config.device_type, url
@@ -320,6 +600,13 @@ impl DeviceSimulator {
 
         let mut interval = time::interval(Duration::from_secs(self.config.update_interval_secs));
 
+        // Queued commands arrive over HTTP when a command URL is configured
+        let (command_tx, mut queued) = mpsc::channel(16);
+        let polling = listener.clone().map(|listener| {
+            info!("Polling queued commands for device {}", self.config.id);
+            tokio::spawn(listener.listen(self.config.id.clone(), command_tx))
+        });
+
         loop {
             tokio::select! {
                 _ = interval.tick() => {
@@ -337,6 +624,13 @@ impl DeviceSimulator {
                         break;
                     }
                 }
+                Some(command) = queued.recv() => {
+                    if let Some(listener) = &listener {
+                        if let Err(e) = self.answer_queued(listener, command).await {
+                            error!("Failed to answer queued command: {}", e);
+                        }
+                    }
+                }
                 msg = read.next() => {
                     match msg {
                         Some(Ok(Message::Text(text))) => {
@@ -345,21 +639,15 @@ impl DeviceSimulator {
                                 if cmd.device_id == self.config.id {
                                     info!("Received command '{}' for device {}", cmd.command, self.config.name);
 
-                                    let (success, message) = match self.handle_command(&cmd.command, &cmd.params) {
-                                        Ok(msg) => (true, msg),
-                                        Err(e) => (false, e.to_string()),
-                                    };
-
-                                    let response = CommandResponse {
-                                        device_id: self.config.id.clone(),
-                                        command: cmd.command,
-                                        success,
-                                        message,
-                                    };
-
-                                    let json = serde_json::to_string(&response)?;
-                                    if let Err(e) = write.send(Message::Text(json)).await {
-                                        error!("Failed to send response: {}", e);
+                                    if !self.faults.latency.is_zero() {
+                                        time::sleep(self.faults.latency).await;
+                                    }
+
+                                    let response = self.respond(cmd, &mut rand::thread_rng())?;
+                                    if let Some(json) = response {
+                                        if let Err(e) = write.send(Message::Text(json)).await {
+                                            error!("Failed to send response: {}", e);
+                                        }
                                     }
                                 }
                             }
@@ -382,6 +670,9 @@ impl DeviceSimulator {
             }
         }
 
+        if let Some(polling) = polling {
+            polling.abort();
+        }
         info!("Device {} disconnected", self.config.name);
         Ok(())
     }
@@ -454,16 +745,29 @@ async fn main() -> Result<()> {
 
     info!("Device types: {:?}", device_types);
 
+    let faults = FaultConfig::from_args(&args)?;
+    if faults.is_enabled() {
+        warn!("Fault injection enabled: {:?}", faults);
+    }
+    let fault_stats = Arc::new(FaultStats::default());
+
     let configs = generate_device_configs(args.count, device_types, args.interval);
+    let listener = args
+        .command_url
+        .as_deref()
+        .map(|url| CommandListener::new(url, args.token.clone()));
 
     // Spawn tasks for each device
     let mut handles = Vec::new();
 
     for config in configs {
         let url = args.url.clone();
+        let faults = faults.clone();
+        let fault_stats = fault_stats.clone();
+        let listener = listener.clone();
         let handle = tokio::spawn(async move {
-            let mut simulator = DeviceSimulator::new(config);
-            if let Err(e) = simulator.run(url).await {
+            let mut simulator = DeviceSimulator::new(config).with_faults(faults, fault_stats);
+            if let Err(e) = simulator.run(url, listener).await {
                 error!("Device simulator error: {}", e);
             }
         });
@@ -476,11 +780,160 @@ async fn main() -> Result<()> {
     info!("✅ All {} devices connected", args.count);
     info!("Press Ctrl+C to stop");
 
-    // Wait for all devices
-    for handle in handles {
-        let _ = handle.await;
+    // Wait for all devices, or for Ctrl+C
+    tokio::select! {
+        _ = async {
+            for handle in handles {
+                let _ = handle.await;
+            }
+        } => {}
+        _ = tokio::signal::ctrl_c() => {
+            info!("Received Ctrl+C, stopping devices");
+        }
     }
 
+    if faults.is_enabled() {
+        fault_stats.report();
+    }
     info!("Device simulator stopped");
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn light(faults: FaultConfig) -> (DeviceSimulator, Arc<FaultStats>) {
+        let config = generate_device_configs(1, vec![DeviceType::Light], 10).remove(0);
+        let stats = Arc::new(FaultStats::default());
+        (DeviceSimulator::new(config).with_faults(faults, stats.clone()), stats)
+    }
+
+    fn turn_on(simulator: &DeviceSimulator) -> CommandMessage {
+        CommandMessage {
+            command: "turn_on".to_string(),
+            device_id: simulator.config.id.clone(),
+            params: serde_json::json!({}),
+        }
+    }
+
+    #[test]
+    fn test_error_rate_fails_expected_fraction() {
+        let (mut simulator, stats) = light(FaultConfig {
+            error_rate: 0.3,
+            ..Default::default()
+        });
+        let mut rng = StdRng::seed_from_u64(7);
+
+        let mut failed = 0;
+        for _ in 0..2000 {
+            let json = simulator.respond(turn_on(&simulator), &mut rng).unwrap().unwrap();
+            let response: CommandResponse = serde_json::from_str(&json).unwrap();
+            if !response.success {
+                failed += 1;
+            }
+        }
+
+        let fraction = failed as f64 / 2000.0;
+        assert!((0.25..0.35).contains(&fraction), "failed fraction {}", fraction);
+        assert_eq!(stats.errors.load(Ordering::Relaxed), failed);
+        assert_eq!(stats.commands.load(Ordering::Relaxed), 2000);
+    }
+
+    #[test]
+    fn test_drop_and_malformed_responses() {
+        let (mut simulator, stats) = light(FaultConfig {
+            drop_rate: 0.5,
+            malformed_rate: 0.5,
+            ..Default::default()
+        });
+        let mut rng = StdRng::seed_from_u64(7);
+
+        for _ in 0..100 {
+            if let Some(text) = simulator.respond(turn_on(&simulator), &mut rng).unwrap() {
+                assert!(serde_json::from_str::<CommandResponse>(&text).is_err());
+            }
+        }
+
+        let dropped = stats.dropped.load(Ordering::Relaxed);
+        assert_eq!(dropped + stats.malformed.load(Ordering::Relaxed), 100);
+        assert!(dropped > 0 && dropped < 100);
+        // Faulted commands are not run
+        assert!(!simulator.state.light_on);
+    }
+
+    #[tokio::test]
+    async fn test_queued_command_is_applied_and_acked() {
+        use axum::extract::{Path, State};
+        use axum::routing::{get, post};
+        use std::sync::Mutex;
+
+        /// Hands out one brightness command, then records the posted result
+        #[derive(Clone, Default)]
+        struct MockHub {
+            handed_out: Arc<std::sync::atomic::AtomicBool>,
+            results: Arc<Mutex<Vec<(String, String, serde_json::Value)>>>,
+        }
+
+        async fn next(State(hub): State<MockHub>) -> axum::response::Response {
+            use axum::response::IntoResponse;
+            if hub.handed_out.swap(true, Ordering::SeqCst) {
+                return axum::http::StatusCode::NO_CONTENT.into_response();
+            }
+            axum::Json(serde_json::json!({
+                "message_id": "msg-1",
+                "action": "set_brightness",
+                "parameters": {"brightness": 40},
+                "status": "delivered",
+            }))
+            .into_response()
+        }
+
+        async fn result(
+            State(hub): State<MockHub>,
+            Path((device_id, message_id)): Path<(String, String)>,
+            axum::Json(body): axum::Json<serde_json::Value>,
+        ) {
+            hub.results.lock().unwrap().push((device_id, message_id, body));
+        }
+
+        let hub = MockHub::default();
+        let app = axum::Router::new()
+            .route("/api/v1/devices/:id/commands/next", get(next))
+            .route("/api/v1/devices/:id/commands/:message_id/result", post(result))
+            .with_state(hub.clone());
+        let server = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", server.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(server, app).await });
+
+        let (mut simulator, _) = light(FaultConfig::default());
+        let listener = CommandListener::new(&url, None);
+        let (tx, mut queued) = mpsc::channel(1);
+        let polling = tokio::spawn(listener.clone().listen(simulator.config.id.clone(), tx));
+
+        let command = time::timeout(Duration::from_secs(5), queued.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        simulator.answer_queued(&listener, command).await.unwrap();
+        polling.abort();
+
+        assert_eq!(simulator.state.light_brightness, 40);
+        let results = hub.results.lock().unwrap();
+        let (device_id, message_id, body) = &results[0];
+        assert_eq!(device_id, &simulator.config.id);
+        assert_eq!(message_id, "msg-1");
+        assert_eq!(body["success"], true);
+        assert_eq!(body["message"], "Brightness set to 40");
+    }
+
+    #[test]
+    fn test_parse_rate_bounds() {
+        assert_eq!(parse_rate("0.25"), Ok(0.25));
+        assert!(parse_rate("1.5").is_err());
+        assert!(parse_rate("-0.1").is_err());
+        assert!(parse_rate("often").is_err());
+    }
+}
@@ -16,8 +16,8 @@ export default function DevicesPage() {
         const fetchDevices = async () => {
             try {
                 const response = await api.get('/api/v1/devices')
-                // Backend returns { devices: [...], total: ... }
-                setData(response.data.devices || [])
+                // Backend returns { items: [...], total, page, per_page, has_more }
+                setData(response.data.items || [])
             } catch (error) {
                 console.error("Failed to fetch devices:", error)
                 toast.error("Failed to load devices", {